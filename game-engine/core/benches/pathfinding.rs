@@ -0,0 +1,122 @@
+//! A*, jump point search, hierarchical routing and waypoint simplification
+//! on grids much larger than the play field.
+//! Run with `cargo bench -p rust-rush-core --bench pathfinding`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rust_rush_core::hpa::find_route;
+use rust_rush_core::pathfinding::{find_path, find_path_with, find_waypoints, simplify_path, Octile, Search};
+use rust_rush_core::{Grid, Position};
+
+const SIZE: i32 = 200;
+const DENSITIES: [u32; 3] = [0, 10, 25]; // Percent of cells blocked
+
+/// Square grid with a fixed pseudo-random scatter of obstacles. The start and
+/// goal corners are always left open.
+fn obstacle_grid(size: i32, density: u32) -> Grid {
+    let mut grid = Grid::new(size, size);
+    let mut seed: u32 = 0x2545_f491;
+    for y in 0..size {
+        for x in 0..size {
+            // xorshift: deterministic, so every run measures the same map
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            if seed % 100 < density {
+                grid.set_walkable(&Position::new(x, y), false);
+            }
+        }
+    }
+    grid.set_walkable(&Position::new(0, 0), true);
+    grid.set_walkable(&Position::new(size - 1, size - 1), true);
+    grid
+}
+
+fn bench_find_path(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_path");
+    group.sample_size(20);
+    let start = Position::new(0, 0);
+    let goal = Position::new(SIZE - 1, SIZE - 1);
+
+    for density in DENSITIES {
+        let grid = obstacle_grid(SIZE, density);
+        group.bench_with_input(BenchmarkId::new(format!("{SIZE}x{SIZE}"), density), &grid, |b, grid| {
+            b.iter(|| find_path(black_box(grid), start, goal))
+        });
+    }
+    group.finish();
+}
+
+/// The same 8-connected searches with plain A* and with jump point search
+fn bench_jump_points(c: &mut Criterion) {
+    let mut group = c.benchmark_group("octile");
+    group.sample_size(20);
+    let start = Position::new(0, 0);
+    let goal = Position::new(SIZE - 1, SIZE - 1);
+
+    for density in DENSITIES {
+        let grid = obstacle_grid(SIZE, density);
+        let graph = Octile { grid: &grid };
+        for (name, search) in [("a_star", Search::AStar), ("jps", Search::Auto)] {
+            group.bench_with_input(BenchmarkId::new(name, density), &graph, |b, graph| {
+                b.iter(|| find_path_with(black_box(graph), start, goal, search))
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Hierarchical routes against plain A*, with the cluster cache warm and
+/// with a cell toggled before every search, as placing a tower does
+fn bench_hierarchical(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hierarchical");
+    group.sample_size(20);
+    let start = Position::new(0, 0);
+    let goal = Position::new(SIZE - 1, SIZE - 1);
+
+    for density in DENSITIES {
+        let mut grid = obstacle_grid(SIZE, density);
+        group.bench_with_input(BenchmarkId::new("a_star", density), &grid, |b, grid| {
+            b.iter(|| find_path(black_box(grid), start, goal))
+        });
+        find_route(&grid, start, goal); // Build every cluster up front
+        group.bench_with_input(BenchmarkId::new("hpa", density), &grid, |b, grid| {
+            b.iter(|| find_route(black_box(grid), start, goal))
+        });
+        let tower = Position::new(SIZE / 2, SIZE / 2 + 1);
+        let mut blocked = false;
+        group.bench_function(BenchmarkId::new("hpa_after_tower", density), |b| {
+            b.iter(|| {
+                blocked = !blocked;
+                grid.set_walkable(&tower, !blocked);
+                find_route(black_box(&grid), start, goal)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_waypoints(c: &mut Criterion) {
+    let mut group = c.benchmark_group("waypoints");
+    let start = Position::new(0, 0);
+    let goal = Position::new(SIZE - 1, SIZE - 1);
+
+    for density in DENSITIES {
+        let grid = obstacle_grid(SIZE, density);
+        let Some(path) = find_path(&grid, start, goal) else {
+            continue;
+        };
+        group.bench_with_input(BenchmarkId::new("simplify", density), &path, |b, path| {
+            b.iter(|| simplify_path(black_box(path)))
+        });
+    }
+
+    let grid = obstacle_grid(SIZE, 10);
+    group.sample_size(20);
+    group.bench_function("find_waypoints", |b| {
+        b.iter(|| find_waypoints(black_box(&grid), start, goal))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_find_path, bench_jump_points, bench_hierarchical, bench_waypoints);
+criterion_main!(benches);
@@ -0,0 +1,88 @@
+//! Whole-tick and splash-damage costs under a heavy late-game load.
+//! Run with `cargo bench -p rust-rush-core --bench simulation`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rust_rush_core::{Game, Position, TowerType, GRID_WIDTH};
+
+const ENEMIES: usize = 1000;
+const TOWERS: usize = 100;
+const TICK: f32 = 1.0 / 60.0;
+
+/// A board with `TOWERS` towers lining the route and `ENEMIES` enemies spread
+/// along it
+fn loaded_game(tower_type: TowerType) -> Game {
+    let mut game = Game::new();
+    game.state.gold = i32::MAX / 2;
+
+    // Fill rows above and below the spawn lane, leaving it open
+    let mut placed = 0;
+    'rows: for y in [6, 8, 5, 9, 4, 10, 3, 11] {
+        for x in 0..GRID_WIDTH {
+            if placed == TOWERS {
+                break 'rows;
+            }
+            if game.state.place_tower(tower_type, Position::new(x, y)).is_ok() {
+                placed += 1;
+            }
+        }
+    }
+    assert_eq!(placed, TOWERS, "bench board has no room for every tower");
+
+    for _ in 0..ENEMIES {
+        assert!(game.state.spawn_enemy().is_ok());
+    }
+    // Stagger each enemy along the path instead of stacking them on spawn
+    for (i, enemy) in game.state.enemies.values_mut().enumerate() {
+        enemy.health = i32::MAX / 2;
+        enemy.max_health = enemy.health;
+        enemy.update((i % 300) as f32 * 0.05);
+    }
+    game.enemy_index.rebuild(&game.state.enemies);
+    game
+}
+
+fn bench_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("game_update");
+    group.sample_size(20);
+
+    for (name, tower_type) in [("basic", TowerType::Basic), ("splash", TowerType::Splash)] {
+        group.bench_function(format!("{ENEMIES}_enemies_{TOWERS}_{name}_towers"), |b| {
+            b.iter_batched(
+                || loaded_game(tower_type),
+                |mut game| {
+                    game.update(black_box(TICK));
+                    game
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn bench_splash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("splash_damage");
+    let game = loaded_game(TowerType::Basic);
+    let target = game.state.enemies.keys().next().expect("enemies spawned");
+    let (hit_x, hit_y) = {
+        let enemy = &game.state.enemies[target];
+        (enemy.x, enemy.y)
+    };
+
+    for tower_type in [TowerType::Splash, TowerType::FrostBomb] {
+        group.bench_function(format!("{tower_type:?}"), |b| {
+            b.iter_batched(
+                || loaded_game(TowerType::Basic),
+                |mut game| {
+                    game.apply_damage(target, 1, tower_type, black_box(hit_x), black_box(hit_y));
+                    game
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_update, bench_splash);
+criterion_main!(benches);
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+/// Global player-cast abilities, usable at any point during a wave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AbilityType {
+    MeteorStrike,
+    GlobalFreeze,
+    EmergencyRepair,
+}
+
+impl AbilityType {
+    pub const ALL: [AbilityType; 3] = [
+        AbilityType::MeteorStrike,
+        AbilityType::GlobalFreeze,
+        AbilityType::EmergencyRepair,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AbilityType::MeteorStrike => "Meteor",
+            AbilityType::GlobalFreeze => "Freeze",
+            AbilityType::EmergencyRepair => "Repair",
+        }
+    }
+
+    pub fn cost(&self) -> i32 {
+        match self {
+            AbilityType::MeteorStrike => 40,
+            AbilityType::GlobalFreeze => 30,
+            AbilityType::EmergencyRepair => 100,
+        }
+    }
+
+    /// Cooldown in seconds after a successful cast
+    pub fn cooldown(&self) -> f32 {
+        match self {
+            AbilityType::MeteorStrike => 15.0,
+            AbilityType::GlobalFreeze => 30.0,
+            AbilityType::EmergencyRepair => 60.0,
+        }
+    }
+
+    /// Abilities that need a target point show a reticle before casting
+    pub fn needs_target(&self) -> bool {
+        matches!(self, AbilityType::MeteorStrike)
+    }
+
+    // Meteor strike parameters
+    pub fn radius(&self) -> f32 {
+        match self {
+            AbilityType::MeteorStrike => 2.0,
+            _ => 0.0,
+        }
+    }
+
+    pub fn damage(&self) -> i32 {
+        match self {
+            AbilityType::MeteorStrike => 80,
+            _ => 0,
+        }
+    }
+
+    /// Burn left on whatever the meteor hit, as (seconds, damage per second)
+    pub fn burn(&self) -> Option<(f32, f32)> {
+        match self {
+            AbilityType::MeteorStrike => Some((3.0, 5.0)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ability {
+    pub ability_type: AbilityType,
+    pub cooldown_remaining: f32,
+}
+
+impl Ability {
+    pub fn new(ability_type: AbilityType) -> Self {
+        Ability {
+            ability_type,
+            cooldown_remaining: 0.0,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown_remaining <= 0.0
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        if self.cooldown_remaining > 0.0 {
+            self.cooldown_remaining -= delta;
+        }
+    }
+
+    pub fn trigger(&mut self) {
+        self.cooldown_remaining = self.ability_type.cooldown();
+    }
+
+    /// Fraction of the cooldown still remaining (1.0 = just cast, 0.0 = ready)
+    pub fn cooldown_ratio(&self) -> f32 {
+        (self.cooldown_remaining / self.ability_type.cooldown()).clamp(0.0, 1.0)
+    }
+}
+
+/// The player's ability bar plus the ability currently waiting for a target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Abilities {
+    pub slots: Vec<Ability>,
+    pub armed: Option<AbilityType>,
+}
+
+impl Abilities {
+    pub fn new() -> Self {
+        Abilities {
+            slots: AbilityType::ALL.iter().map(|a| Ability::new(*a)).collect(),
+            armed: None,
+        }
+    }
+
+    pub fn get(&self, ability_type: AbilityType) -> Option<&Ability> {
+        self.slots.iter().find(|a| a.ability_type == ability_type)
+    }
+
+    pub fn get_mut(&mut self, ability_type: AbilityType) -> Option<&mut Ability> {
+        self.slots.iter_mut().find(|a| a.ability_type == ability_type)
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        for ability in self.slots.iter_mut() {
+            ability.update(delta);
+        }
+    }
+}
+
+impl Default for Abilities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
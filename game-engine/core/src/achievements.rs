@@ -0,0 +1,188 @@
+//! Achievements, defined as data and checked against the event bus. The
+//! definitions come from the frontend (the desktop game ships them as
+//! JSON); the tracker keeps this run's tallies plus the saved progress, and
+//! queues anything newly unlocked for the HUD to announce.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::codex::Codex;
+use crate::events::GameEvent;
+use crate::TowerType;
+
+/// What has to happen, as it appears in the definitions file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Criterion {
+    LifetimeKills(u64),
+    ReachWave(u32),
+    ReachWaveWithoutSelling(u32),
+    ReachWaveWithOnly { tower: TowerType, wave: u32 }, // Every tower placed this run was this type
+    FlawlessWaves(u32),                                 // Waves cleared in a row without a leak
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AchievementDef {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub criterion: Criterion,
+}
+
+/// What carries over between runs; saved with the player's profile
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AchievementProgress {
+    pub unlocked: BTreeSet<String>,
+    pub lifetime_kills: u64,
+    #[serde(default)]
+    pub codex: Codex, // Tower and enemy types seen so far
+}
+
+#[derive(Debug, Clone, Default)]
+struct RunTally {
+    wave: u32,
+    sold_any: bool,
+    tower_types: Vec<TowerType>, // Distinct types placed this run
+    leaks_this_wave: u32,
+    flawless_streak: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AchievementTracker {
+    defs: Vec<AchievementDef>,
+    pub progress: AchievementProgress,
+    run: RunTally,
+    newly_unlocked: Vec<AchievementDef>,
+}
+
+impl AchievementTracker {
+    pub fn new(defs: Vec<AchievementDef>, progress: AchievementProgress) -> Self {
+        AchievementTracker {
+            defs,
+            progress,
+            ..Self::default()
+        }
+    }
+
+    pub fn defs(&self) -> &[AchievementDef] {
+        &self.defs
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.progress.unlocked.contains(id)
+    }
+
+    /// Achievements unlocked since the last call, in unlock order
+    pub fn take_unlocked(&mut self) -> Vec<AchievementDef> {
+        std::mem::take(&mut self.newly_unlocked)
+    }
+
+    pub fn record(&mut self, event: &GameEvent) {
+        let run = &mut self.run;
+        match event {
+            GameEvent::EnemyKilled { .. } => self.progress.lifetime_kills += 1,
+            GameEvent::EnemyLeaked { .. } => run.leaks_this_wave += 1,
+            GameEvent::WaveStarted { wave } => {
+                run.wave = *wave;
+                run.leaks_this_wave = 0;
+            }
+            GameEvent::WaveCleared { .. } => {
+                run.flawless_streak = if run.leaks_this_wave == 0 { run.flawless_streak + 1 } else { 0 };
+            }
+            GameEvent::TowerPlaced { tower_type, .. } => {
+                if !run.tower_types.contains(tower_type) {
+                    run.tower_types.push(*tower_type);
+                }
+            }
+            GameEvent::TowerSold { .. } => run.sold_any = true,
+            GameEvent::TowerDestroyed { .. } => return,
+            GameEvent::DamageDealt { .. } => return, // Frequent and never unlocks anything
+        }
+        self.check();
+    }
+
+    fn check(&mut self) {
+        for def in &self.defs {
+            if self.progress.unlocked.contains(&def.id) || !self.met(&def.criterion) {
+                continue;
+            }
+            info!(id = %def.id, "achievement unlocked");
+            self.progress.unlocked.insert(def.id.clone());
+            self.newly_unlocked.push(def.clone());
+        }
+    }
+
+    fn met(&self, criterion: &Criterion) -> bool {
+        let run = &self.run;
+        match *criterion {
+            Criterion::LifetimeKills(kills) => self.progress.lifetime_kills >= kills,
+            Criterion::ReachWave(wave) => run.wave >= wave,
+            Criterion::ReachWaveWithoutSelling(wave) => run.wave >= wave && !run.sold_any,
+            Criterion::ReachWaveWithOnly { tower, wave } => run.wave >= wave && run.tower_types == [tower],
+            Criterion::FlawlessWaves(count) => run.flawless_streak >= count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EnemyId, Position, TowerId};
+
+    fn def(id: &str, criterion: Criterion) -> AchievementDef {
+        AchievementDef {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            criterion,
+        }
+    }
+
+    fn placed(tower_type: TowerType) -> GameEvent {
+        GameEvent::TowerPlaced { tower_id: TowerId::default(), tower_type, position: Position::new(0, 0) }
+    }
+
+    #[test]
+    fn test_only_slow_towers() {
+        let only_slow = Criterion::ReachWaveWithOnly { tower: TowerType::Slow, wave: 2 };
+        let mut tracker = AchievementTracker::new(vec![def("frost", only_slow)], AchievementProgress::default());
+
+        tracker.record(&placed(TowerType::Slow));
+        tracker.record(&GameEvent::WaveStarted { wave: 2 });
+        assert_eq!(tracker.take_unlocked()[0].id, "frost");
+        assert!(tracker.take_unlocked().is_empty());
+
+        let mut tracker = AchievementTracker::new(tracker.defs().to_vec(), AchievementProgress::default());
+        tracker.record(&placed(TowerType::Slow));
+        tracker.record(&placed(TowerType::Basic));
+        tracker.record(&GameEvent::WaveStarted { wave: 5 });
+        assert!(!tracker.is_unlocked("frost"));
+    }
+
+    #[test]
+    fn test_lifetime_kills_carry_over() {
+        let saved = AchievementProgress { unlocked: BTreeSet::new(), lifetime_kills: 999, ..Default::default() };
+        let mut tracker = AchievementTracker::new(vec![def("thousand", Criterion::LifetimeKills(1000))], saved);
+        tracker.record(&GameEvent::EnemyKilled { enemy_id: EnemyId::default(), x: 0.0, y: 0.0, gold: 5, max_health: 100 });
+        assert!(tracker.is_unlocked("thousand"));
+        assert_eq!(tracker.progress.lifetime_kills, 1000);
+    }
+
+    #[test]
+    fn test_flawless_streak_resets_on_leak() {
+        let mut tracker = AchievementTracker::new(vec![def("clean", Criterion::FlawlessWaves(2))], AchievementProgress::default());
+        let leak = GameEvent::EnemyLeaked { enemy_id: EnemyId::default(), x: 0.0, y: 0.0 };
+        for (wave, leaked) in [(1, false), (2, true), (3, false)] {
+            tracker.record(&GameEvent::WaveStarted { wave });
+            if leaked {
+                tracker.record(&leak);
+            }
+            tracker.record(&GameEvent::WaveCleared { wave });
+        }
+        assert!(!tracker.is_unlocked("clean"));
+        tracker.record(&GameEvent::WaveStarted { wave: 4 });
+        tracker.record(&GameEvent::WaveCleared { wave: 4 });
+        assert!(tracker.is_unlocked("clean"));
+    }
+}
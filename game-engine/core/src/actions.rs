@@ -0,0 +1,125 @@
+//! Why a player action was refused. Building, selling, merging, clearing,
+//! repairing, casting and the debug and scenario actions return a
+//! `Result<(), ActionError>` rather than a bare yes or no, and `Game::apply_command` does the same for every `Command`,
+//! so the HUD, the server's rejections and the bot API can all say what
+//! went wrong.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{GameState, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "error")]
+pub enum ActionError {
+    InsufficientGold { price: i32, gold: i32 },
+    CellBlocked,    // A tower, obstacle, trap, generator or the hero is in the way
+    WouldBlockPath, // It would cut a spawn or portal off from the goal
+    OutOfBounds,
+    NotBuildable, // Wall, gate, portal or goal: nothing goes there
+    NotAllowed,   // The run's rules or mode forbid it
+    NoSuchTower,
+    NoSuchEnemy,
+    NoObstacle,    // Nothing to clear at that cell
+    NothingToUndo, // No clear left to take back this build phase
+    NotAdjacent,   // Merging needs two touching towers
+    NoRecipe,      // Those two tower types don't merge
+    NotDamaged,    // Nothing to repair
+    NotReady,      // On cooldown, or not in this phase
+    NoTarget,      // A targeted ability needs a point to land on
+    NoRoute,       // Nothing walking could get from there to where it's going
+    NoCheckpoint,  // No checkpoint kept for that wave
+    TooMany { max: u32 }, // More of one kind than a wave may hold
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::InsufficientGold { price, gold } => write!(f, "not enough gold (${} with ${})", price, gold),
+            ActionError::CellBlocked => write!(f, "something's in the way"),
+            ActionError::WouldBlockPath => write!(f, "it would block the path"),
+            ActionError::OutOfBounds => write!(f, "off the board"),
+            ActionError::NotBuildable => write!(f, "nothing can be built there"),
+            ActionError::NotAllowed => write!(f, "not allowed in this run"),
+            ActionError::NoSuchTower => write!(f, "no such tower"),
+            ActionError::NoSuchEnemy => write!(f, "no such enemy"),
+            ActionError::NoObstacle => write!(f, "nothing to clear there"),
+            ActionError::NothingToUndo => write!(f, "nothing to undo"),
+            ActionError::NotAdjacent => write!(f, "the towers aren't touching"),
+            ActionError::NoRecipe => write!(f, "those towers don't merge"),
+            ActionError::NotDamaged => write!(f, "nothing to repair"),
+            ActionError::NotReady => write!(f, "not now"),
+            ActionError::NoTarget => write!(f, "pick a target first"),
+            ActionError::NoRoute => write!(f, "there's no way through"),
+            ActionError::NoCheckpoint => write!(f, "no checkpoint for that wave"),
+            ActionError::TooMany { max } => write!(f, "at most {} of a kind", max),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// For an action that only answers yes or no: `err` is what its no means
+pub(crate) fn unless(done: bool, err: ActionError) -> Result<(), ActionError> {
+    if done {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
+
+impl GameState {
+    /// Why nothing new can go on `cell`, if that's so. The hero doesn't
+    /// count; traps may sit under it.
+    pub(crate) fn cell_refusal(&self, cell: &Position) -> Option<ActionError> {
+        if !self.grid.in_bounds(cell) {
+            Some(ActionError::OutOfBounds)
+        } else if self.tower_at(cell).is_some()
+            || self.obstacle_at(cell).is_some()
+            || self.trap_at(cell).is_some()
+            || self.generator_at(cell)
+        {
+            Some(ActionError::CellBlocked)
+        } else if !self.grid.is_walkable(cell) || self.is_gated(cell) || self.is_portal(cell) {
+            Some(ActionError::NotBuildable)
+        } else {
+            None
+        }
+    }
+
+    /// `cell_refusal`, plus the hero standing there
+    pub(crate) fn solid_cell_refusal(&self, cell: &Position) -> Option<ActionError> {
+        self.cell_refusal(cell).or_else(|| {
+            let hero_there = self.hero.is_alive() && self.hero.cell() == *cell;
+            hero_there.then_some(ActionError::CellBlocked)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType, GRID_HEIGHT};
+
+    #[test]
+    fn test_refusals_say_why() {
+        let mut game = Game::new();
+        let basic = BuildTool::Tower(TowerType::Basic);
+        assert_eq!(game.try_build(basic, Position::new(-1, 3)), Err(ActionError::OutOfBounds));
+        assert_eq!(game.try_build(basic, Position::new(5, 3)), Ok(()));
+        assert_eq!(game.try_build(basic, Position::new(5, 3)), Err(ActionError::CellBlocked));
+        game.state.grid.set_walkable(&Position::new(7, 3), false); // A wall
+        assert_eq!(game.try_build(basic, Position::new(7, 3)), Err(ActionError::NotBuildable));
+
+        game.state.gold = 100_000;
+        for y in (0..GRID_HEIGHT).filter(|&y| y != 7) {
+            assert_eq!(game.try_build(basic, Position::new(10, y)), Ok(()));
+        }
+        assert_eq!(game.try_build(basic, Position::new(10, 7)), Err(ActionError::WouldBlockPath));
+
+        game.state.gold = 0;
+        let refused = game.try_build(basic, Position::new(3, 12));
+        assert!(matches!(refused, Err(ActionError::InsufficientGold { gold: 0, .. })));
+    }
+}
@@ -0,0 +1,202 @@
+//! Computer player. Once per build phase it spends up to a gold budget on
+//! towers, choosing each spot by two heuristics: how much of the enemy
+//! route the tower would cover where coverage is still thin (see
+//! `coverage`), and how far blocking that spot would lengthen the route
+//! (choke points). It also merges adjacent towers whenever a recipe
+//! allows, which is the game's only upgrade.
+//!
+//! The AI plays through the same `Command`s a client sends, so anything it
+//! does a player could do too. `play` runs it headless for balance checks.
+
+use tracing::{debug, info};
+
+use crate::coverage::Coverage;
+use crate::merging;
+use crate::pathfinding::find_path;
+use crate::protocol::Command;
+use crate::stat_sheet::{Stat, StatSheet};
+use crate::{BuildTool, Game, GameState, Position, TowerType, CELL_SIZE};
+
+/// Build order, repeated; falls back to the cheapest affordable tower
+const PREFERENCE: [TowerType; 6] = [
+    TowerType::Basic,
+    TowerType::Basic,
+    TowerType::Slow,
+    TowerType::Splash,
+    TowerType::Sniper,
+    TowerType::Cannon,
+];
+const DETOUR_WEIGHT: f32 = 2.0; // Score per cell a placement adds to the route
+const MAX_TICKS_PER_WAVE: u32 = 20_000; // Bails out of `play` if a wave never ends
+
+#[derive(Debug, Clone)]
+pub struct AiPlayer {
+    pub budget_per_wave: i32,
+    pub call_waves: bool, // Call each wave as soon as it has built
+    planned_wave: Option<u32>,
+}
+
+impl AiPlayer {
+    pub fn new(budget_per_wave: i32) -> Self {
+        AiPlayer { budget_per_wave, call_waves: false, planned_wave: None }
+    }
+
+    /// Build for the upcoming wave if it hasn't yet. Returns the commands
+    /// the game accepted.
+    pub fn act(&mut self, game: &mut Game) -> Vec<Command> {
+        let wave = game.state.waves.wave;
+        if !game.state.waves.is_build_phase() || self.planned_wave == Some(wave) {
+            return Vec::new();
+        }
+        self.planned_wave = Some(wave);
+
+        let mut issued = Vec::new();
+        if let Some(command) = merge_command(&game.state) {
+            if game.apply_command(&command).is_ok() {
+                issued.push(command);
+            }
+        }
+        let mut budget = self.budget_per_wave.min(game.state.gold);
+        while let Some(command) = self.next_build(&game.state, budget) {
+            if game.apply_command(&command).is_err() {
+                break;
+            }
+            if let Command::Build { tool, .. } = command {
+                budget -= tool.cost();
+            }
+            issued.push(command);
+        }
+        if self.call_waves && game.apply_command(&Command::CallNextWave).is_ok() {
+            issued.push(Command::CallNextWave);
+        }
+        info!(wave, commands = issued.len(), gold = game.state.gold, "ai built");
+        issued
+    }
+
+    /// The best tower to place with `budget` gold left, if any
+    fn next_build(&self, state: &GameState, budget: i32) -> Option<Command> {
+        let preferred = PREFERENCE[state.towers.len() % PREFERENCE.len()];
+        let usable = |tower_type: &TowerType| tower_type.cost() <= budget && tower_type.fits_on(state.grid.shape());
+        let tower_type = if usable(&preferred) {
+            preferred
+        } else {
+            PREFERENCE.into_iter().filter(usable).min_by_key(|t| t.cost())?
+        };
+        let position = best_spot(state, tower_type)?;
+        Some(Command::Build { tool: BuildTool::Tower(tower_type), position })
+    }
+}
+
+/// Highest-scoring anchor cell for a tower, scanning in row order so ties
+/// go to the first
+fn best_spot(state: &GameState, tower_type: TowerType) -> Option<Position> {
+    let route = find_path(&state.grid, state.spawn_point, state.goal_point)?;
+    let coverage = Coverage::compute(state);
+    let stats = StatSheet::for_tower(tower_type);
+    let (near, far) = (tower_type.min_range() * CELL_SIZE, stats.get(Stat::Range) * CELL_SIZE);
+    let half = tower_type.footprint() as f32 * CELL_SIZE / 2.0;
+
+    let mut best: Option<(f32, Position)> = None;
+    for y in 0..state.grid.height() {
+        for x in 0..state.grid.width() {
+            let anchor = Position::new(x, y);
+            let cells = tower_type.cells_at(anchor);
+            if !cells.iter().all(|cell| state.cell_is_free(*cell)) {
+                continue;
+            }
+
+            // Detour first: spots that would seal the route are out
+            let detour = if cells.iter().any(|cell| route.contains(cell)) {
+                let mut grid = state.grid.clone();
+                for cell in &cells {
+                    grid.set_walkable(cell, false);
+                }
+                match find_path(&grid, state.spawn_point, state.goal_point) {
+                    Some(longer) => (longer.len() - route.len()) as f32,
+                    None => continue,
+                }
+            } else {
+                0.0
+            };
+
+            let (wx, wy) = anchor.to_world();
+            let (center_x, center_y) = (wx + half, wy + half);
+            let thin_cover: f32 = route
+                .iter()
+                .filter(|cell| !cells.contains(cell))
+                .filter(|cell| {
+                    let (cx, cy) = cell.to_world();
+                    let (dx, dy) = (cx + CELL_SIZE / 2.0 - center_x, cy + CELL_SIZE / 2.0 - center_y);
+                    let distance = (dx * dx + dy * dy).sqrt();
+                    distance >= near && distance <= far
+                })
+                .map(|cell| 1.0 / (1.0 + coverage.dps_at(*cell)))
+                .sum();
+
+            let score = thin_cover + DETOUR_WEIGHT * detour;
+            if score > 0.0 && best.is_none_or(|(top, _)| score > top) {
+                best = Some((score, anchor));
+            }
+        }
+    }
+    best.map(|(_, anchor)| anchor)
+}
+
+/// The first adjacent pair of towers a recipe can merge
+fn merge_command(state: &GameState) -> Option<Command> {
+    let mut towers: Vec<_> = state.towers.iter().collect();
+    towers.sort_by_key(|(_, tower)| (tower.position.y, tower.position.x));
+    for (source_id, source) in &towers {
+        for (target_id, target) in &towers {
+            let adjacent =
+                source.cells().iter().any(|cell| state.grid.neighbors(*cell).iter().any(|n| target.occupies(n)));
+            if source_id != target_id && adjacent && merging::find_recipe(source.tower_type, target.tower_type).is_some() {
+                return Some(Command::MergeTowers { source_id: *source_id, target_id: *target_id });
+            }
+        }
+    }
+    None
+}
+
+/// Let the AI play until the run is decided or `waves` waves are cleared, in
+/// steps of `delta` seconds. Returns the number of waves cleared.
+pub fn play(game: &mut Game, ai: &mut AiPlayer, waves: u32, delta: f32) -> u32 {
+    let cleared = |game: &Game| game.state.waves.wave - 1; // The wave number moves on when one is cleared
+    let mut ticks = 0;
+    while game.state.health > 0 && !game.state.objective.is_decided() && cleared(game) < waves {
+        ai.act(game);
+        game.update(delta);
+        ticks += 1;
+        if ticks > MAX_TICKS_PER_WAVE * waves {
+            debug!(ticks, "ai run timed out");
+            break;
+        }
+    }
+    cleared(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ai_builds_within_budget_and_keeps_the_route_open() {
+        let mut game = Game::new();
+        let mut ai = AiPlayer::new(150);
+        let gold = game.state.gold;
+        let commands = ai.act(&mut game);
+        assert!(!commands.is_empty());
+        assert!(gold - game.state.gold <= 150);
+        assert!(find_path(&game.state.grid, game.state.spawn_point, game.state.goal_point).is_some());
+        assert!(ai.act(&mut game).is_empty()); // Once per build phase
+    }
+
+    #[test]
+    fn test_headless_run_holds_early_waves() {
+        let mut game = Game::new();
+        let mut ai = AiPlayer::new(200);
+        ai.call_waves = true;
+        assert_eq!(play(&mut game, &mut ai, 3, 1.0 / 30.0), 3);
+        assert!(game.state.health > 0);
+    }
+}
@@ -0,0 +1,62 @@
+//! The optional ammo rule (`RuleSet::ammo`). Towers fire from magazines:
+//! after `TowerType::magazine` shots they stop for
+//! `TowerType::magazine_reload` seconds before firing again, so damage
+//! comes in bursts. Without the rule magazines are never counted.
+
+use crate::Tower;
+
+impl Tower {
+    /// Count a shot against the magazine, starting a reload once it's empty
+    pub(crate) fn spend_round(&mut self) {
+        self.rounds_fired += 1;
+        if self.rounds_fired >= self.tower_type.magazine() {
+            self.rounds_fired = 0;
+            self.reloading = self.tower_type.magazine_reload();
+        }
+    }
+
+    pub fn rounds_left(&self) -> u32 {
+        self.tower_type.magazine().saturating_sub(self.rounds_fired)
+    }
+
+    /// How far along a reload is (0 to 1), if one is under way
+    pub fn reload_progress(&self) -> Option<f32> {
+        (self.reloading > 0.0).then(|| 1.0 - self.reloading / self.tower_type.magazine_reload())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mutators::RuleSet;
+    use crate::{BuildTool, Game, Position, TowerType, CELL_SIZE};
+
+    fn run_until_reload(mut game: Game) -> Option<u32> {
+        game.build(BuildTool::Tower(TowerType::Gatling), Position::new(6, 6));
+        let tower_id = game.state.tower_at(&Position::new(6, 6)).unwrap().id;
+        let enemy_id = game.state.spawn_enemy_with(|enemy| enemy.max_health = 1_000_000).unwrap();
+        let mut shots = 0;
+        for _ in 0..600 {
+            let (x, y) = Position::new(7, 7).to_world();
+            let enemy = &mut game.state.enemies[enemy_id];
+            (enemy.x, enemy.y, enemy.health) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0, 1_000_000);
+            let ready = game.state.towers[tower_id].can_shoot();
+            game.update(1.0 / 30.0);
+            let tower = &game.state.towers[tower_id];
+            if ready && !tower.can_shoot() {
+                shots += 1;
+            }
+            if tower.reload_progress().is_some() {
+                return Some(shots);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_towers_reload_after_a_magazine() {
+        let mut rules = RuleSet::default();
+        rules.ammo = true;
+        assert_eq!(run_until_reload(Game::with_rules(rules)), Some(TowerType::Gatling.magazine()));
+        assert_eq!(run_until_reload(Game::new()), None);
+    }
+}
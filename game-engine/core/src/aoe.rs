@@ -0,0 +1,125 @@
+//! Area-of-effect queries shared by splash shots, abilities, traps and the
+//! hero. Candidates come from the spatial hash; the exact shape test happens
+//! here. Boundaries are inclusive: an enemy whose center sits exactly on the
+//! edge is affected.
+
+use slotmap::SlotMap;
+
+use crate::spatial::SpatialHash;
+use crate::{Enemy, EnemyId};
+
+const ANGLE_TOLERANCE: f32 = 1e-5; // Keeps enemies exactly on a cone edge inside
+
+/// Every enemy whose center is within `radius` of `center`, dead or alive
+pub fn enemies_in_radius(
+    index: &SpatialHash,
+    enemies: &SlotMap<EnemyId, Enemy>,
+    center: (f32, f32),
+    radius: f32,
+) -> Vec<EnemyId> {
+    if radius < 0.0 {
+        return Vec::new();
+    }
+    index
+        .query_radius(center, radius)
+        .into_iter()
+        .filter(|id| {
+            enemies.get(*id).is_some_and(|enemy| {
+                let (dx, dy) = (enemy.x - center.0, enemy.y - center.1);
+                dx * dx + dy * dy <= radius * radius
+            })
+        })
+        .collect()
+}
+
+/// Enemies within `range` of `apex` and no more than `half_angle` radians
+/// either side of `direction` (also radians, screen-space atan2)
+pub fn enemies_in_cone(
+    index: &SpatialHash,
+    enemies: &SlotMap<EnemyId, Enemy>,
+    apex: (f32, f32),
+    direction: f32,
+    half_angle: f32,
+    range: f32,
+) -> Vec<EnemyId> {
+    let (dir_x, dir_y) = (direction.cos(), direction.sin());
+    let min_cos = half_angle.clamp(0.0, std::f32::consts::PI).cos() - ANGLE_TOLERANCE;
+
+    enemies_in_radius(index, enemies, apex, range)
+        .into_iter()
+        .filter(|id| {
+            let enemy = &enemies[*id];
+            let (dx, dy) = (enemy.x - apex.0, enemy.y - apex.1);
+            let distance = (dx * dx + dy * dy).sqrt();
+            // An enemy standing on the apex is caught by any cone
+            distance < f32::EPSILON || (dx * dir_x + dy * dir_y) / distance >= min_cos
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Grid, Position};
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    /// Enemies parked at the given points, with the spatial hash built over them
+    fn field(points: &[(f32, f32)]) -> (SpatialHash, SlotMap<EnemyId, Enemy>, Vec<EnemyId>) {
+        let grid = Grid::new(20, 15);
+        let mut enemies: SlotMap<EnemyId, Enemy> = SlotMap::with_key();
+        let ids: Vec<EnemyId> = points
+            .iter()
+            .map(|&(x, y)| {
+                let id = enemies
+                    .try_insert_with_key(|id| Enemy::new(id, Position::new(0, 0), Position::new(5, 0), &grid).ok_or(()))
+                    .unwrap();
+                enemies[id].x = x;
+                enemies[id].y = y;
+                id
+            })
+            .collect();
+        let mut index = SpatialHash::default();
+        index.rebuild(&enemies);
+        (index, enemies, ids)
+    }
+
+    #[test]
+    fn test_radius_edge_is_inclusive() {
+        let (index, enemies, ids) = field(&[(130.0, 100.0), (130.01, 100.0), (100.0, 70.0)]);
+        let found = enemies_in_radius(&index, &enemies, (100.0, 100.0), 30.0);
+        assert!(found.contains(&ids[0])); // Exactly on the edge
+        assert!(!found.contains(&ids[1])); // Just past it
+        assert!(found.contains(&ids[2])); // On the edge, in another bucket
+    }
+
+    #[test]
+    fn test_zero_and_negative_radius() {
+        let (index, enemies, ids) = field(&[(100.0, 100.0), (101.0, 100.0)]);
+        assert_eq!(enemies_in_radius(&index, &enemies, (100.0, 100.0), 0.0), vec![ids[0]]);
+        assert!(enemies_in_radius(&index, &enemies, (100.0, 100.0), -1.0).is_empty());
+    }
+
+    #[test]
+    fn test_cone_edges() {
+        // Apex at the origin facing +x with a 90 degree spread
+        let (index, enemies, ids) = field(&[
+            (50.0, 0.0),   // Dead ahead
+            (50.0, 50.0),  // Exactly on the 45 degree edge
+            (50.0, 51.0),  // Just outside it
+            (-50.0, 0.0),  // Behind
+            (100.0, 0.0),  // Ahead but exactly at range
+            (100.5, 0.0),  // Ahead, out of range
+        ]);
+        let found = enemies_in_cone(&index, &enemies, (0.0, 0.0), 0.0, FRAC_PI_4, 100.0);
+        assert!(found.contains(&ids[0]));
+        assert!(found.contains(&ids[1]));
+        assert!(!found.contains(&ids[2]));
+        assert!(!found.contains(&ids[3]));
+        assert!(found.contains(&ids[4]));
+        assert!(!found.contains(&ids[5]));
+
+        // Facing down the screen picks up a different set
+        let down = enemies_in_cone(&index, &enemies, (0.0, 0.0), FRAC_PI_2, FRAC_PI_4, 100.0);
+        assert_eq!(down, vec![ids[1], ids[2]]);
+    }
+}
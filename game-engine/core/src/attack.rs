@@ -0,0 +1,175 @@
+//! The attack mode (`RuleSet::attack`), with the roles swapped: the AI
+//! builds the defense (see `ai`) and the player sends the waves. Each wave
+//! is composed from a `Roster` and a `Formation` and paid for out of a
+//! budget for the whole run, then goes out through the wave script like any
+//! map's scripted wave. Nothing comes until the player sends it.
+//!
+//! The run is scored on leaks. Bringing the base down wins outright; the
+//! defense holds once `ATTACK_WAVES` waves are through or the budget can't
+//! buy another grunt.
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::actions::ActionError;
+use crate::checksum::StateHasher;
+use crate::formations::{Formation, Roster, WavePlan};
+use crate::gunners::EnemyKind;
+use crate::victory::{Defeat, Outcome};
+use crate::GameState;
+
+pub const ATTACK_BUDGET: i32 = 1000;
+pub const ATTACK_WAVES: u32 = 5;
+pub const MAX_ROSTER: u32 = 40; // Per kind, per wave
+
+/// What sending one enemy of `kind` costs
+pub fn send_cost(kind: EnemyKind) -> i32 {
+    match kind {
+        EnemyKind::Grunt => 20,
+        EnemyKind::Gunner => 35,
+    }
+}
+
+pub fn roster_cost(roster: &Roster) -> i32 {
+    roster.grunts as i32 * send_cost(EnemyKind::Grunt) + roster.gunners as i32 * send_cost(EnemyKind::Gunner)
+}
+
+/// Score before mutators: 100 per leak and 1 per 10 gold of budget unspent
+pub fn attack_score(leaks: u32, budget_left: i32) -> u64 {
+    100 * leaks as u64 + budget_left.max(0) as u64 / 10
+}
+
+/// The attacker's purse and the wave it's putting together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttackState {
+    pub budget: i32,
+    pub draft: Roster,
+    pub formation: Formation,
+}
+
+impl Default for AttackState {
+    fn default() -> Self {
+        AttackState { budget: ATTACK_BUDGET, draft: Roster::default(), formation: Formation::Column }
+    }
+}
+
+impl AttackState {
+    pub fn can_send(&self) -> bool {
+        self.draft.size() > 0 && roster_cost(&self.draft) <= self.budget
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.i32(self.budget);
+        hasher.u32(self.draft.grunts);
+        hasher.u32(self.draft.gunners);
+        hasher.variant(&self.formation);
+    }
+}
+
+impl GameState {
+    /// Set the wave to send next. Refused outside the attack mode, or for
+    /// more than `MAX_ROSTER` of a kind.
+    pub fn compose_wave(&mut self, roster: Roster, formation: Formation) -> Result<(), ActionError> {
+        if !self.rules.attack {
+            return Err(ActionError::NotAllowed);
+        }
+        if roster.grunts > MAX_ROSTER || roster.gunners > MAX_ROSTER {
+            return Err(ActionError::TooMany { max: MAX_ROSTER });
+        }
+        self.attack.draft = roster;
+        self.attack.formation = formation;
+        Ok(())
+    }
+
+    /// Pay for the drafted wave and send it, during a build phase with
+    /// waves left to send
+    pub fn send_attack(&mut self) -> Result<(), ActionError> {
+        let wave = self.waves.wave;
+        let (roster, formation) = (self.attack.draft, self.attack.formation);
+        let price = roster_cost(&roster);
+        let refusal = if !self.waves.is_build_phase() || wave > ATTACK_WAVES || roster.size() == 0 {
+            Some(ActionError::NotReady)
+        } else if price > self.attack.budget {
+            Some(ActionError::InsufficientGold { price, gold: self.attack.budget })
+        } else {
+            None
+        };
+        if let Some(err) = refusal {
+            debug!(wave, budget = self.attack.budget, %err, "attack not sent");
+            return Err(err);
+        }
+        self.attack.budget -= price;
+        self.waves.plans.push(WavePlan { from_wave: wave, formation, speed_jitter: 0.0, roster: Some(roster) });
+        self.waves.call_early(); // No early-call bonus: the gold isn't the attacker's
+        info!(wave, ?roster, budget = self.attack.budget, "attack sent");
+        Ok(())
+    }
+
+    /// Whether the attack has won or the defense has held
+    pub(crate) fn attack_outcome(&self) -> Option<Outcome> {
+        if self.health <= 0 {
+            return Some(Outcome::Won);
+        }
+        let out_of_waves = self.waves.wave > ATTACK_WAVES;
+        let out_of_gold = self.attack.budget < send_cost(EnemyKind::Grunt);
+        (self.waves.is_build_phase() && (out_of_waves || out_of_gold)).then_some(Outcome::Lost(Defeat::DefenseHeld))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutators::RuleSet;
+    use crate::Game;
+
+    fn attack_game() -> Game {
+        let mut rules = RuleSet::default();
+        rules.attack = true;
+        Game::with_rules(rules)
+    }
+
+    #[test]
+    fn test_composed_waves_cost_budget_and_spawn_their_roster() {
+        let mut game = attack_game();
+        for _ in 0..(60 * 30) {
+            game.update(1.0 / 30.0);
+        }
+        assert!(game.state.waves.is_build_phase()); // Waits to be sent
+        assert_eq!(game.state.call_next_wave(), Err(ActionError::NotReady)); // Nothing drafted
+
+        let roster = Roster { grunts: 4, gunners: 2 };
+        let crowd = Roster { grunts: MAX_ROSTER + 1, gunners: 0 };
+        assert_eq!(game.state.compose_wave(crowd, Formation::Column), Err(ActionError::TooMany { max: MAX_ROSTER }));
+        assert!(game.state.compose_wave(roster, Formation::StaggeredPairs).is_ok());
+        assert!(game.state.call_next_wave().is_ok());
+        assert_eq!(game.state.attack.budget, ATTACK_BUDGET - 4 * 20 - 2 * 35);
+        assert_eq!(game.state.send_attack(), Err(ActionError::NotReady)); // Already under way
+
+        for _ in 0..(8 * 30) {
+            game.update(1.0 / 30.0);
+        }
+        let gunners = game.state.enemies.values().filter(|enemy| enemy.kind == EnemyKind::Gunner).count();
+        assert_eq!(game.state.enemies.len(), 6);
+        assert_eq!(gunners, 2);
+        assert_eq!(Game::new().state.compose_wave(roster, Formation::Column), Err(ActionError::NotAllowed));
+    }
+
+    #[test]
+    fn test_breaking_the_base_wins_and_running_dry_loses() {
+        let mut game = attack_game();
+        game.state.health = 0;
+        game.update(1.0 / 30.0);
+        assert_eq!(game.state.objective.outcome, Some(Outcome::Won));
+
+        let mut game = attack_game();
+        assert!(game.state.compose_wave(Roster { grunts: 40, gunners: 5 }, Formation::Column).is_ok());
+        assert!(game.state.call_next_wave().is_ok());
+        game.state.attack.budget = 10;
+        for _ in 0..(60 * 30) {
+            game.update(1.0 / 30.0);
+            game.state.enemies.clear(); // An airtight defense
+        }
+        assert_eq!(game.state.objective.outcome, Some(Outcome::Lost(Defeat::DefenseHeld)));
+        assert_eq!(attack_score(3, 155), 315);
+    }
+}
@@ -0,0 +1,169 @@
+//! Interface for programs that play the game: bots, automated playtests,
+//! learning agents. Actions are the protocol's `Command`s, the same ones a
+//! networked client sends. An `Observation` is a `Snapshot` plus what a
+//! player reads off the screen: the phase, the board's blocked cells and
+//! the route enemies will take. `BotSession` advances the game in fixed
+//! ticks between actions, so runs are reproducible.
+//!
+//! Requests and replies serialize to JSON, one per line, for the headless
+//! server's `--stdio` mode.
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::ActionError;
+use crate::pathfinding::find_path;
+use crate::portals::SpawnPortal;
+use crate::victory::Objective;
+use crate::protocol::{Command, Snapshot};
+use crate::{Game, Position};
+
+pub const TICK: f32 = 1.0 / 30.0; // Seconds per step tick, the server's rate
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum BotRequest {
+    Reset,
+    Observe,
+    /// Apply `commands` in order, then run `ticks` ticks
+    Step {
+        #[serde(default)]
+        commands: Vec<Command>,
+        #[serde(default = "one_tick")]
+        ticks: u32,
+    },
+}
+
+fn one_tick() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    #[serde(flatten)]
+    pub snapshot: Snapshot,
+    pub build_phase: bool,
+    pub countdown: f32, // Seconds until the wave starts; 0 in combat
+    pub early_call_bonus: i32,
+    pub width: i32,
+    pub height: i32,
+    pub spawn: Position,
+    #[serde(default)]
+    pub portals: Vec<SpawnPortal>, // More spawns, opening on later waves
+    pub goal: Position,
+    pub blocked: Vec<Position>, // Unwalkable cells, row by row
+    pub route: Vec<Position>,   // Cell by cell from spawn to goal
+    pub game_over: bool, // Won or lost
+    #[serde(default)]
+    pub objective: Objective, // The map's win condition and how it's going
+}
+
+impl Observation {
+    pub fn capture(game: &Game) -> Self {
+        let state = &game.state;
+        let (width, height) = (state.grid.width(), state.grid.height());
+        let blocked = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Position::new(x, y)))
+            .filter(|cell| !state.grid.is_walkable(cell))
+            .collect();
+        let countdown = match state.waves.phase {
+            crate::waves::WavePhase::Build { countdown } => countdown,
+            crate::waves::WavePhase::Combat => 0.0,
+        };
+        Observation {
+            snapshot: Snapshot::capture(game),
+            build_phase: state.waves.is_build_phase(),
+            countdown,
+            early_call_bonus: state.waves.early_call_bonus(),
+            width,
+            height,
+            spawn: state.spawn_point,
+            portals: state.portals.clone(),
+            goal: state.goal_point,
+            blocked,
+            route: find_path(&state.grid, state.spawn_point, state.goal_point).unwrap_or_default(),
+            game_over: state.health <= 0 || state.objective.is_decided(),
+            objective: state.objective.clone(),
+        }
+    }
+}
+
+/// Reply to every request; `accepted` and `errors` have one entry per
+/// command stepped, the error saying why a refused one was refused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepResult {
+    pub accepted: Vec<bool>,
+    #[serde(default)]
+    pub errors: Vec<Option<ActionError>>,
+    pub observation: Observation,
+}
+
+pub struct BotSession {
+    pub game: Game,
+}
+
+impl BotSession {
+    pub fn new() -> Self {
+        BotSession { game: Game::new() }
+    }
+
+    pub fn handle(&mut self, request: &BotRequest) -> StepResult {
+        match request {
+            BotRequest::Reset => {
+                self.game = Game::new();
+                self.reply(Vec::new())
+            }
+            BotRequest::Observe => self.reply(Vec::new()),
+            BotRequest::Step { commands, ticks } => self.step(commands, *ticks),
+        }
+    }
+
+    /// Apply commands, then run up to `ticks` ticks (fewer if the game ends)
+    pub fn step(&mut self, commands: &[Command], ticks: u32) -> StepResult {
+        let results: Vec<_> = commands.iter().map(|command| self.game.apply_command(command)).collect();
+        for _ in 0..ticks {
+            if self.game.state.health <= 0 || self.game.state.objective.is_decided() {
+                break;
+            }
+            self.game.update(TICK);
+        }
+        self.reply(results)
+    }
+
+    fn reply(&self, results: Vec<Result<(), ActionError>>) -> StepResult {
+        StepResult {
+            accepted: results.iter().map(Result::is_ok).collect(),
+            errors: results.iter().map(|result| result.err()).collect(),
+            observation: Observation::capture(&self.game),
+        }
+    }
+}
+
+impl Default for BotSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, TowerType};
+
+    #[test]
+    fn test_step_applies_commands_and_observes_the_result() {
+        let mut session = BotSession::new();
+        let build = Command::Build { tool: BuildTool::Tower(TowerType::Basic), position: Position::new(4, 6) };
+        let request = BotRequest::Step { commands: vec![build.clone(), build], ticks: 30 };
+
+        let result = session.handle(&request);
+        assert_eq!(result.accepted, vec![true, false]);
+        assert_eq!(result.errors, vec![None, Some(ActionError::CellBlocked)]);
+        assert_eq!(result.observation.snapshot.tick, 30);
+        assert!(result.observation.blocked.contains(&Position::new(4, 6)));
+        assert_eq!(result.observation.route.first(), Some(&result.observation.spawn));
+        assert!(result.observation.build_phase && !result.observation.game_over);
+
+        let reset = session.handle(&BotRequest::Reset);
+        assert_eq!(reset.observation.snapshot.tick, 0);
+    }
+}
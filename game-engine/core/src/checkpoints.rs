@@ -0,0 +1,98 @@
+//! Wave checkpoints: a `GameSnapshot` taken as each wave starts, so a lost
+//! run can be restarted from any wave it reached instead of from scratch.
+//! Restarting throws away the checkpoints after the one restored, since
+//! that future no longer happens, and marks the run unranked
+//! (`Taint::CheckpointRestart`). Checkpoints live in memory only, for the
+//! one session.
+
+use tracing::info;
+
+use crate::actions::ActionError;
+use crate::integrity::Taint;
+use crate::rollback::GameSnapshot;
+use crate::Game;
+
+pub const MAX_CHECKPOINTS: usize = 50; // The oldest go first past this
+
+#[derive(Debug, Clone, Default)]
+pub struct Checkpoints {
+    saves: Vec<(u32, GameSnapshot)>, // Wave and the state as it started, oldest first
+}
+
+impl Checkpoints {
+    pub fn new() -> Self {
+        Checkpoints::default()
+    }
+
+    /// Take a checkpoint if a wave has started since the last one. Called
+    /// after every update, so a wave called early is caught on its first
+    /// tick too.
+    pub fn record(&mut self, game: &Game) {
+        let wave = game.state.waves.wave;
+        if game.state.waves.is_build_phase() || self.saves.last().is_some_and(|(saved, _)| *saved >= wave) {
+            return;
+        }
+        if self.saves.len() == MAX_CHECKPOINTS {
+            self.saves.remove(0);
+        }
+        self.saves.push((wave, game.snapshot()));
+    }
+
+    /// The waves there's a checkpoint for, oldest first
+    pub fn waves(&self) -> Vec<u32> {
+        self.saves.iter().map(|(wave, _)| *wave).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.saves.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.saves.clear();
+    }
+
+    /// Put the game back to the start of `wave`, if there's a checkpoint
+    /// for it
+    pub fn restart(&mut self, wave: u32, game: &mut Game) -> Result<(), ActionError> {
+        let index = self.saves.iter().position(|(saved, _)| *saved == wave).ok_or(ActionError::NoCheckpoint)?;
+        self.saves.truncate(index + 1);
+        game.restore(&self.saves[index].1);
+        game.state.taint(Taint::CheckpointRestart);
+        info!(wave, "restarted from checkpoint");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::victory::Outcome;
+
+    #[test]
+    fn test_restart_from_an_earlier_wave_after_defeat() {
+        let mut game = Game::new();
+        let mut checkpoints = Checkpoints::new();
+        for _ in 0..3 {
+            assert!(game.state.call_next_wave().is_ok());
+            game.update(1.0 / 30.0);
+            checkpoints.record(&game);
+            while !game.state.waves.is_build_phase() {
+                game.state.enemies.clear();
+                game.update(1.0);
+            }
+        }
+        assert_eq!(checkpoints.waves(), vec![1, 2, 3]);
+        let gold_at_two = checkpoints.saves[1].1.state.gold;
+
+        game.state.health = 0;
+        game.update(1.0 / 30.0);
+        assert!(matches!(game.state.objective.outcome, Some(Outcome::Lost(_))));
+        assert_eq!(checkpoints.restart(7, &mut game), Err(ActionError::NoCheckpoint));
+
+        assert!(checkpoints.restart(2, &mut game).is_ok());
+        assert_eq!((game.state.waves.wave, game.state.gold), (2, gold_at_two));
+        assert!(game.state.objective.outcome.is_none() && game.state.health > 0);
+        assert_eq!(checkpoints.waves(), vec![1, 2]);
+        assert_eq!(game.state.run_integrity.taints(), &[Taint::CheckpointRestart]);
+    }
+}
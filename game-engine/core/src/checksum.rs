@@ -0,0 +1,302 @@
+//! Deterministic state hashes for desync detection, replay verification and
+//! run-vs-run regression tests. The hash covers everything that can change
+//! how later ticks play out and nothing that is only drawn (effects,
+//! floating text), so two machines running the same inputs agree
+//! even if their renderers don't.
+//!
+//! FNV-1a rather than std's `DefaultHasher`, whose output may change between
+//! Rust releases. Floats are hashed by bit pattern, maps in key order.
+
+use slotmap::Key;
+
+use crate::stat_sheet::{ModifierOp, Stat, StatSheet};
+use crate::{Game, GameState, Position};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Clone)]
+pub struct StateHasher(u64);
+
+impl StateHasher {
+    pub fn new() -> Self {
+        StateHasher(FNV_OFFSET)
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn i32(&mut self, value: i32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    pub fn f32(&mut self, value: f32) {
+        self.u32(value.to_bits());
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.bytes(&[value as u8]);
+    }
+
+    pub fn position(&mut self, position: Position) {
+        self.i32(position.x);
+        self.i32(position.y);
+    }
+
+    pub fn key(&mut self, key: impl Key) {
+        self.u64(key.data().as_ffi());
+    }
+
+    /// Fieldless enums, by their `Debug` name so reordering variants is harmless
+    pub fn variant(&mut self, value: &impl std::fmt::Debug) {
+        self.bytes(format!("{:?}", value).as_bytes());
+    }
+
+    fn stats(&mut self, stats: &StatSheet) {
+        for stat in Stat::ALL {
+            self.f32(stats.base(stat));
+        }
+        self.usize(stats.modifiers().len());
+        for modifier in stats.modifiers() {
+            self.bytes(modifier.source.as_bytes());
+            self.variant(&modifier.stat);
+            match modifier.op {
+                ModifierOp::Add(amount) => {
+                    self.u32(0);
+                    self.f32(amount);
+                }
+                ModifierOp::Multiply(factor) => {
+                    self.u32(1);
+                    self.f32(factor);
+                }
+            }
+            self.f32(modifier.remaining.unwrap_or(-1.0));
+        }
+    }
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameState {
+    /// Stable hash of every simulation-relevant field
+    pub fn hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        self.hash_into(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn hash_into(&self, hasher: &mut StateHasher) {
+        for y in 0..self.grid.height() {
+            for x in 0..self.grid.width() {
+                let cell = Position::new(x, y);
+                hasher.bool(self.grid.is_walkable(&cell));
+                hasher.variant(&self.grid.one_way(&cell));
+                if let Some(exit) = self.grid.teleporter(&cell) {
+                    hasher.position(exit);
+                }
+            }
+        }
+
+        hasher.usize(self.towers.len());
+        for (id, tower) in &self.towers {
+            hasher.key(id);
+            hasher.variant(&tower.tower_type);
+            hasher.position(tower.position);
+            hasher.stats(&tower.stats);
+            hasher.f32(tower.cooldown_remaining);
+            hasher.f32(tower.rotation); // Turrets only fire once turned
+            hasher.u64(tower.target_id.map_or(0, |target| target.data().as_ffi()));
+            hasher.u64(tower.locked_target.map_or(0, |target| target.data().as_ffi()));
+            hasher.bool(tower.disabled);
+            hasher.u32(tower.rounds_fired);
+            hasher.f32(tower.reloading);
+            hasher.i32(tower.damage_taken);
+            hasher.u32(tower.kills);
+            hasher.usize(tower.ignore_zones.len());
+            for cell in &tower.ignore_zones {
+                hasher.position(*cell);
+            }
+        }
+
+        hasher.usize(self.enemies.len());
+        for (id, enemy) in &self.enemies {
+            hasher.key(id);
+            hasher.f32(enemy.x);
+            hasher.f32(enemy.y);
+            hasher.usize(enemy.path.len());
+            for waypoint in &enemy.path {
+                hasher.position(*waypoint);
+            }
+            hasher.usize(enemy.current_waypoint);
+            hasher.f32(enemy.speed);
+            hasher.i32(enemy.health);
+            hasher.i32(enemy.max_health);
+            hasher.f32(enemy.armor);
+            hasher.f32(enemy.lane);
+            enemy.effects.hash_into(hasher);
+            hasher.f32(enemy.slow_multiplier);
+            hasher.variant(&enemy.siege);
+            hasher.variant(&enemy.kind);
+            hasher.f32(enemy.fire_cooldown);
+            hasher.u64(enemy.chasing.map_or(0, |convoy| convoy.data().as_ffi()));
+            hasher.usize(enemy.lane_id);
+        }
+
+        let mut trap_ids: Vec<&u32> = self.traps.keys().collect();
+        trap_ids.sort();
+        hasher.usize(trap_ids.len());
+        for id in trap_ids {
+            let trap = &self.traps[id];
+            hasher.u32(trap.id);
+            hasher.variant(&trap.trap_type);
+            hasher.position(trap.position);
+        }
+
+        self.hero.hash_into(hasher);
+        self.waves.hash_into(hasher);
+        self.rng.hash_into(hasher);
+        hasher.position(self.spawn_point);
+        hasher.position(self.goal_point);
+        hasher.usize(self.portals.len());
+        for portal in &self.portals {
+            portal.hash_into(hasher);
+        }
+        hasher.usize(self.lanes.len());
+        for lane in &self.lanes {
+            lane.hash_into(hasher);
+        }
+        hasher.u32(self.next_trap_id);
+        hasher.i32(self.gold);
+        hasher.i32(self.health);
+        hasher.bool(self.paused);
+        hasher.bool(self.clock.enabled);
+        hasher.f32(self.clock.time);
+        hasher.f32(self.clock.day_length);
+        hasher.bool(self.weather.enabled);
+        hasher.variant(&self.weather.current);
+        for obstacle in self.obstacles.iter().chain(&self.undoable_clears) {
+            obstacle.hash_into(hasher);
+        }
+        hasher.usize(self.undoable_clears.len());
+        for gate in &self.gates {
+            gate.hash_into(hasher);
+        }
+        for patrol in &self.patrols {
+            patrol.hash_into(hasher);
+        }
+        self.rules.hash_into(hasher);
+        self.power.hash_into(hasher);
+        self.objective.hash_into(hasher);
+        self.convoys.hash_into(hasher);
+        self.attack.hash_into(hasher);
+        self.run_integrity.hash_into(hasher);
+        self.threat.hash_into(hasher);
+    }
+}
+
+impl Game {
+    /// `GameState::hash` plus the simulation state kept outside it: shots in
+    /// flight both ways, fog of war, ability cooldowns and the tick counter
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.u64(self.tick);
+        self.state.hash_into(&mut hasher);
+
+        hasher.usize(self.projectiles.len());
+        for (id, projectile) in &self.projectiles {
+            hasher.key(id);
+            hasher.variant(&projectile.tower_type);
+            hasher.f32(projectile.x);
+            hasher.f32(projectile.y);
+            hasher.key(projectile.target_id);
+            hasher.f32(projectile.target_x);
+            hasher.f32(projectile.target_y);
+            hasher.i32(projectile.damage);
+            hasher.f32(projectile.origin.0);
+            hasher.f32(projectile.origin.1);
+            hasher.f32(projectile.reach);
+            hasher.f32(projectile.speed);
+            hasher.f32(projectile.lifetime);
+            hasher.usize(projectile.hit_enemies.len());
+            for enemy_id in &projectile.hit_enemies {
+                hasher.key(*enemy_id);
+            }
+            hasher.bool(projectile.target_lost);
+        }
+
+        hasher.usize(self.enemy_shots.len());
+        for shot in &self.enemy_shots {
+            shot.hash_into(&mut hasher);
+        }
+
+        hasher.bool(self.fog.enabled); // Visibility itself follows from the towers
+
+        for ability in &self.abilities.slots {
+            hasher.variant(&ability.ability_type);
+            hasher.f32(ability.cooldown_remaining);
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, TowerType};
+
+    fn run(ticks: usize) -> Game {
+        let mut game = Game::new();
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6));
+        game.build(BuildTool::Tower(TowerType::Slow), Position::new(9, 8));
+        assert!(game.state.call_next_wave().is_ok());
+        for _ in 0..ticks {
+            game.update(1.0 / 30.0);
+        }
+        game
+    }
+
+    #[test]
+    fn test_same_inputs_same_hash() {
+        let (a, b) = (run(200), run(200));
+        assert_eq!(a.state.hash(), b.state.hash());
+        assert_eq!(a.checksum(), b.checksum());
+        assert_ne!(a.checksum(), run(201).checksum());
+    }
+
+    #[test]
+    fn test_cosmetic_fields_are_ignored() {
+        let mut game = run(100);
+        let before = game.checksum();
+        game.muzzle_flashes.clear();
+        game.floating_texts.clear();
+        game.leak_flash = 0.5;
+        assert_eq!(game.checksum(), before);
+
+        game.state.gold += 1;
+        assert_ne!(game.checksum(), before);
+    }
+}
@@ -0,0 +1,142 @@
+//! The codex: every tower and enemy type with its stats, for planning
+//! counters. Entries are built from the same data the game runs on, so
+//! modded stats (see `mods`) show up without any codex-specific content.
+//! A type's entry unlocks the first time one is on the board, in any run;
+//! what has been seen is kept in the profile alongside achievement progress.
+
+use serde::{Deserialize, Serialize};
+
+use crate::gunners::{EnemyKind, GUNNER_FIRST_WAVE};
+use crate::merging::MERGE_RECIPES;
+use crate::stat_sheet::{Stat, StatSheet};
+use crate::{Enemy, EnemyId, GameState, Grid, Position, TowerType, CELL_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Subject {
+    Tower(TowerType),
+    Enemy(EnemyKind),
+}
+
+/// Which entries are unlocked, in the order they were first seen
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Codex {
+    pub seen: Vec<Subject>,
+}
+
+impl Codex {
+    pub fn has_seen(&self, subject: Subject) -> bool {
+        self.seen.contains(&subject)
+    }
+
+    /// Unlock whatever is on the board now. True if anything was new.
+    pub fn observe(&mut self, state: &GameState) -> bool {
+        let before = self.seen.len();
+        let towers = state.towers.values().map(|tower| Subject::Tower(tower.tower_type));
+        let enemies = state.enemies.values().map(|enemy| Subject::Enemy(enemy.kind));
+        for subject in towers.chain(enemies) {
+            if !self.has_seen(subject) {
+                self.seen.push(subject);
+            }
+        }
+        self.seen.len() > before
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TowerEntry {
+    pub tower_type: TowerType,
+    pub cost: i32, // Under this run's rules
+    pub damage: f32,
+    pub range: f32,     // Cells
+    pub fire_rate: f32, // Shots a second
+    pub splash: f32,    // Cells; zero for single-target
+    pub slow: Option<(f32, f32)>, // Seconds, and the speed multiplier
+    pub footprint: i32,
+    pub merged_from: Option<(TowerType, TowerType)>, // Only built by merging these
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnemyEntry {
+    pub kind: EnemyKind,
+    pub health: i32,
+    pub speed: f32,     // Cells a second
+    pub range: f32,     // Cells it shoots at towers from; zero if it doesn't
+    pub first_wave: u32,
+}
+
+/// Every tower type, with its stats after mods
+pub fn tower_entries(state: &GameState) -> Vec<TowerEntry> {
+    TowerType::ALL
+        .iter()
+        .map(|&tower_type| {
+            let mut stats = StatSheet::for_tower(tower_type);
+            state.content.apply_to_tower(tower_type, &mut stats);
+            let recipe = MERGE_RECIPES.iter().find(|recipe| recipe.result == tower_type);
+            TowerEntry {
+                tower_type,
+                cost: state.rules.price(tower_type.cost()),
+                damage: stats.get(Stat::Damage),
+                range: stats.get(Stat::Range),
+                fire_rate: stats.get(Stat::FireRate),
+                splash: tower_type.splash_radius(),
+                slow: tower_type.slow_effect(),
+                footprint: tower_type.footprint(),
+                merged_from: recipe.map(|recipe| recipe.inputs),
+            }
+        })
+        .collect()
+}
+
+/// Every enemy kind as it spawns, after mods
+pub fn enemy_entries(state: &GameState) -> Vec<EnemyEntry> {
+    EnemyKind::ALL
+        .iter()
+        .map(|&kind| {
+            let mut enemy = Enemy::on_path(EnemyId::default(), Position::new(0, 0), Vec::new(), &Grid::new(1, 1));
+            state.content.apply_to_enemy(&mut enemy);
+            enemy.set_kind(kind);
+            EnemyEntry {
+                kind,
+                health: enemy.max_health,
+                speed: enemy.speed / CELL_SIZE,
+                range: kind.range(),
+                first_wave: if kind == EnemyKind::Gunner { GUNNER_FIRST_WAVE } else { 1 },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mods::{EnemyOverride, ModManifest, ModRegistry};
+    use crate::{BuildTool, Game};
+
+    #[test]
+    fn test_codex_unlocks_on_sight_with_modded_stats() {
+        let mut game = Game::new();
+        let mut codex = Codex::default();
+        assert!(!codex.observe(&game.state));
+        assert!(game.build(BuildTool::Tower(TowerType::Sniper), Position::new(4, 3)));
+        assert!(game.state.spawn_enemy().is_ok());
+        assert!(codex.observe(&game.state));
+        assert_eq!(codex.seen, vec![Subject::Tower(TowerType::Sniper), Subject::Enemy(EnemyKind::Grunt)]);
+        assert!(!codex.observe(&game.state));
+
+        let mut content = ModRegistry::new();
+        let enemy = EnemyOverride { health: Some(400), speed: None };
+        content.merge(&ModManifest {
+            name: "Tough".to_string(),
+            version: String::new(),
+            towers: Vec::new(),
+            enemy,
+            effects: Vec::new(),
+            script: None,
+        });
+        game.state.reload_content(content);
+        let grunt = &enemy_entries(&game.state)[0];
+        assert_eq!((grunt.kind, grunt.health), (EnemyKind::Grunt, 400));
+        let gatling = tower_entries(&game.state).into_iter().find(|entry| entry.tower_type == TowerType::Gatling);
+        assert_eq!(gatling.unwrap().merged_from, Some((TowerType::Basic, TowerType::Basic)));
+    }
+}
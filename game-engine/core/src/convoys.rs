@@ -0,0 +1,302 @@
+//! The convoy mode (`RuleSet::convoys`). While a wave is on, a friendly
+//! convoy leaves the goal every `DEPARTURE_INTERVAL` seconds and drives the
+//! route backwards to the spawn, paying `DELIVERY_GOLD` when it gets there.
+//! Enemies would rather wreck a convoy than reach the goal: one that comes
+//! within `AGGRO_RANGE` cells leaves its route to chase it
+//! (`Enemy::chasing`), wears it down on contact and heads back to the goal
+//! once the convoy is gone or out of reach. Losing `MAX_LOST` convoys loses
+//! the run (see `victory`), and every delivery adds to the score.
+
+use serde::{Deserialize, Serialize};
+use slotmap::SlotMap;
+use tracing::{debug, info};
+
+use crate::checksum::StateHasher;
+use crate::pathfinding::find_waypoints;
+use crate::game::teleport_exit;
+use crate::{aoe, ConvoyId, EffectStyle, FloatingText, Game, Grid, Position, CELL_SIZE};
+
+pub const DEPARTURE_INTERVAL: f32 = 15.0;
+pub const DELIVERY_GOLD: i32 = 25;
+pub const MAX_LOST: u32 = 3;
+const FIRST_DEPARTURE: f32 = 3.0; // Seconds into a wave, once enemies are on the board
+const CONVOY_SPEED: f32 = 40.0; // A little slower than a grunt
+const CONVOY_HEALTH: i32 = 150;
+const CONTACT_DPS: f32 = 10.0; // Damage per second from each enemy touching a convoy
+const CONTACT_RANGE: f32 = 0.5; // Cells
+const AGGRO_RANGE: f32 = 2.5; // Cells at which an enemy turns on a convoy
+const LEASH_RANGE: f32 = 4.0; // Cells at which a chasing enemy gives up
+
+/// A friendly unit driving from the goal to the spawn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvoyUnit {
+    pub id: ConvoyId,
+    pub x: f32,
+    pub y: f32,
+    pub path: Vec<Position>,
+    pub current_waypoint: usize,
+    pub health: i32,
+    pub max_health: i32,
+    #[serde(default)]
+    contact_damage: f32, // Fractional contact damage not yet applied
+}
+
+impl ConvoyUnit {
+    fn new(id: ConvoyId, start: Position, path: Vec<Position>) -> Self {
+        let (x, y) = start.to_world();
+        ConvoyUnit {
+            id,
+            x: x + CELL_SIZE / 2.0,
+            y: y + CELL_SIZE / 2.0,
+            path,
+            current_waypoint: 0,
+            health: CONVOY_HEALTH,
+            max_health: CONVOY_HEALTH,
+            contact_damage: 0.0,
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    /// Drive along the path. Returns true once at its end.
+    fn advance(&mut self, delta: f32) -> bool {
+        let Some(waypoint) = self.path.get(self.current_waypoint) else {
+            return !self.path.is_empty();
+        };
+        let (target_x, target_y) = waypoint.to_world();
+        let dx = target_x + CELL_SIZE / 2.0 - self.x;
+        let dy = target_y + CELL_SIZE / 2.0 - self.y;
+        let distance = dx.hypot(dy);
+        let move_distance = CONVOY_SPEED * delta;
+        if distance <= move_distance {
+            self.x += dx;
+            self.y += dy;
+            self.current_waypoint += 1;
+        } else {
+            self.x += dx / distance * move_distance;
+            self.y += dy / distance * move_distance;
+        }
+        false
+    }
+
+    fn take_contact_damage(&mut self, delta: f32, attackers: usize) {
+        self.contact_damage += CONTACT_DPS * attackers as f32 * delta;
+        let whole = self.contact_damage.floor();
+        self.contact_damage -= whole;
+        self.health = (self.health - whole as i32).max(0);
+    }
+}
+
+/// The convoys on the road and how the run's deliveries have gone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvoyRun {
+    pub units: SlotMap<ConvoyId, ConvoyUnit>,
+    pub departure_timer: f32, // Until the next convoy leaves, counted during waves only
+    pub delivered: u32,
+    pub lost: u32,
+}
+
+impl Default for ConvoyRun {
+    fn default() -> Self {
+        ConvoyRun { units: SlotMap::with_key(), departure_timer: FIRST_DEPARTURE, delivered: 0, lost: 0 }
+    }
+}
+
+impl ConvoyRun {
+    /// Re-route every convoy to the spawn after the grid changed
+    pub(crate) fn recalculate_paths(&mut self, grid: &Grid, spawn: Position) {
+        for unit in self.units.values_mut() {
+            if let Some(path) = find_waypoints(grid, grid.cell_at(unit.x, unit.y), spawn) {
+                unit.path = path;
+                unit.current_waypoint = 0;
+            }
+        }
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.usize(self.units.len());
+        for (id, unit) in &self.units {
+            hasher.key(id);
+            hasher.f32(unit.x);
+            hasher.f32(unit.y);
+            hasher.usize(unit.path.len());
+            for waypoint in &unit.path {
+                hasher.position(*waypoint);
+            }
+            hasher.usize(unit.current_waypoint);
+            hasher.i32(unit.health);
+            hasher.f32(unit.contact_damage);
+        }
+        hasher.f32(self.departure_timer);
+        hasher.u32(self.delivered);
+        hasher.u32(self.lost);
+    }
+}
+
+impl Game {
+    pub(crate) fn update_convoys(&mut self, delta: f32) {
+        if !self.state.rules.convoys {
+            return;
+        }
+        self.dispatch_convoys(delta);
+        self.drive_convoys(delta);
+        self.pick_enemy_targets();
+        self.wreck_convoys(delta);
+    }
+
+    fn dispatch_convoys(&mut self, delta: f32) {
+        let state = &mut self.state;
+        let convoys = &mut state.convoys;
+        if state.waves.is_build_phase() {
+            convoys.departure_timer = FIRST_DEPARTURE;
+            return;
+        }
+        convoys.departure_timer -= delta;
+        if convoys.departure_timer > 0.0 {
+            return;
+        }
+        convoys.departure_timer += DEPARTURE_INTERVAL;
+        let (start, end) = (state.goal_point, state.spawn_point);
+        let Some(path) = find_waypoints(&state.grid, start, end) else {
+            debug!("no road back to the spawn; convoy held");
+            return;
+        };
+        let convoy_id = convoys.units.insert_with_key(|id| ConvoyUnit::new(id, start, path));
+        info!(?convoy_id, "convoy departed");
+    }
+
+    fn drive_convoys(&mut self, delta: f32) {
+        let state = &mut self.state;
+        let mut arrived = Vec::new();
+        for unit in state.convoys.units.values_mut() {
+            if unit.advance(delta) {
+                arrived.push(unit.id);
+            } else if let Some((x, y)) = teleport_exit(&state.grid, &unit.path, unit.current_waypoint) {
+                (unit.x, unit.y) = (x, y);
+            }
+        }
+
+        for convoy_id in arrived {
+            let Some(unit) = state.convoys.units.remove(convoy_id) else {
+                continue;
+            };
+            let gold = state.rules.gold(DELIVERY_GOLD);
+            state.gold += gold;
+            state.convoys.delivered += 1;
+            info!(?convoy_id, gold = state.gold, "convoy delivered");
+            let (x, y) = (unit.x - 12.0, unit.y - CELL_SIZE / 2.0);
+            self.floating_texts.push(FloatingText::new(x, y, &format!("+${}", gold), EffectStyle::Delivery));
+        }
+    }
+
+    /// Enemies turn on the nearest convoy within `AGGRO_RANGE`, follow it
+    /// while it stays within `LEASH_RANGE` and head back to the goal after
+    fn pick_enemy_targets(&mut self) {
+        let state = &mut self.state;
+        let (grid, units, goals) = (&state.grid, &state.convoys.units, state.lane_goals());
+        for enemy in state.enemies.values_mut() {
+            if enemy.is_blocked() {
+                continue; // Busy with a wall
+            }
+            let (x, y) = (enemy.x, enemy.y);
+            let cells_to = |unit: &ConvoyUnit| (unit.x - x).hypot(unit.y - y) / CELL_SIZE;
+            let target = match enemy.chasing.and_then(|id| units.get(id)) {
+                Some(unit) if cells_to(unit) <= LEASH_RANGE => Some(unit),
+                _ => units
+                    .values()
+                    .filter(|unit| cells_to(unit) <= AGGRO_RANGE)
+                    .min_by(|a, b| cells_to(a).total_cmp(&cells_to(b))),
+            };
+
+            let Some(unit) = target else {
+                if enemy.chasing.take().is_some() {
+                    enemy.recalculate_path(grid, goals.get(enemy.lane_id).copied().unwrap_or(state.goal_point));
+                }
+                continue;
+            };
+            let cell = grid.cell_at(unit.x, unit.y);
+            if enemy.chasing == Some(unit.id) && enemy.path.last() == Some(&cell) {
+                continue; // Already on its way
+            }
+            if let Some(path) = find_waypoints(grid, grid.cell_at(x, y), cell) {
+                enemy.path = path;
+                enemy.current_waypoint = 0;
+                enemy.chasing = Some(unit.id);
+            }
+        }
+    }
+
+    /// Enemies touching a convoy wear it down; a wrecked one counts as lost
+    fn wreck_convoys(&mut self, delta: f32) {
+        let (index, enemies) = (&self.enemy_index, &self.state.enemies);
+        let mut wrecked = Vec::new();
+        for unit in self.state.convoys.units.values_mut() {
+            let attackers = aoe::enemies_in_radius(index, enemies, (unit.x, unit.y), CONTACT_RANGE * CELL_SIZE).len();
+            unit.take_contact_damage(delta, attackers);
+            if !unit.is_alive() {
+                wrecked.push(unit.id);
+            }
+        }
+
+        for convoy_id in wrecked {
+            let Some(unit) = self.state.convoys.units.remove(convoy_id) else {
+                continue;
+            };
+            self.state.convoys.lost += 1;
+            info!(?convoy_id, lost = self.state.convoys.lost, "convoy lost");
+            self.floating_texts.push(FloatingText::new(unit.x - 20.0, unit.y, "Convoy lost", EffectStyle::Leak));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutators::RuleSet;
+    use crate::victory::{Defeat, Outcome};
+
+    fn convoy_game() -> Game {
+        let mut rules = RuleSet::default();
+        rules.convoys = true;
+        let mut game = Game::with_rules(rules);
+        game.state.waves.call_early();
+        game
+    }
+
+    #[test]
+    fn test_convoys_drive_to_the_spawn_and_pay_out() {
+        let mut game = convoy_game();
+        let gold = game.state.gold;
+        game.update(FIRST_DEPARTURE + 0.01);
+        assert_eq!(game.state.convoys.units.len(), 1);
+        for _ in 0..(25 * 30) {
+            game.state.enemies.clear();
+            game.update(1.0 / 30.0);
+        }
+        assert_eq!(game.state.convoys.delivered, 1);
+        assert_eq!(game.state.gold, gold + DELIVERY_GOLD);
+        assert!(game.state.convoys.units.is_empty());
+    }
+
+    #[test]
+    fn test_enemies_chase_convoys_and_losses_end_the_run() {
+        let mut game = convoy_game();
+        game.update(FIRST_DEPARTURE + 0.01);
+        let convoy_id = game.state.convoys.units.keys().next().unwrap();
+        let enemy_id = game.state.spawn_enemy_with(|enemy| (enemy.x, enemy.y) = (600.0, 300.0)).unwrap();
+        game.state.convoys.units[convoy_id].x = 660.0;
+        game.update(1.0 / 30.0);
+        let enemy = &game.state.enemies[enemy_id];
+        assert_eq!(enemy.chasing, Some(convoy_id));
+        assert_eq!(enemy.path.last(), Some(&game.state.grid.cell_at(660.0, 300.0))); // Not the goal
+
+        game.state.convoys.lost = MAX_LOST - 1;
+        let unit = &mut game.state.convoys.units[convoy_id];
+        (unit.x, unit.y, unit.health) = (600.0, 300.0, 1);
+        game.update(0.2);
+        assert_eq!(game.state.convoys.lost, MAX_LOST);
+        assert_eq!(game.state.objective.outcome, Some(Outcome::Lost(Defeat::ConvoysLost)));
+    }
+}
@@ -0,0 +1,103 @@
+//! Maze analysis: the tower damage per second that covers each open cell,
+//! i.e. the summed DPS of every tower whose firing band (minimum to
+//! maximum range) reaches the cell's center. Low values along the route
+//! are the maze's weak spots.
+//!
+//! Computing it touches every cell for every tower, so frontends keep the
+//! result and recompute only when `fingerprint` changes.
+
+use crate::checksum::StateHasher;
+use crate::stat_sheet::Stat;
+use crate::{GameState, Position, CELL_SIZE};
+
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    width: i32,
+    height: i32,
+    dps: Vec<f32>, // Row-major; zero for blocked cells
+    max: f32,
+}
+
+impl Coverage {
+    pub fn compute(state: &GameState) -> Self {
+        let (width, height) = (state.grid.width(), state.grid.height());
+        let mut dps = vec![0.0; (width * height).max(0) as usize];
+        for tower in state.towers.values().filter(|tower| !tower.disabled) {
+            let (tower_x, tower_y) = tower.world_position();
+            let near = tower.tower_type.min_range() * CELL_SIZE;
+            let far = tower.stats.get(Stat::Range) * CELL_SIZE;
+            let tower_dps = tower.stats.get(Stat::Damage) * tower.stats.get(Stat::FireRate);
+            for y in 0..height {
+                for x in 0..width {
+                    let cell = Position::new(x, y);
+                    if !state.grid.is_walkable(&cell) {
+                        continue;
+                    }
+                    let (cx, cy) = cell.to_world();
+                    let (dx, dy) = (cx + CELL_SIZE / 2.0 - tower_x, cy + CELL_SIZE / 2.0 - tower_y);
+                    let distance = (dx * dx + dy * dy).sqrt();
+                    if distance >= near && distance <= far {
+                        dps[(y * width + x) as usize] += tower_dps;
+                    }
+                }
+            }
+        }
+        let max = dps.iter().copied().fold(0.0, f32::max);
+        Coverage { width, height, dps, max }
+    }
+
+    /// Summed tower DPS reaching a cell; zero off the grid
+    pub fn dps_at(&self, cell: Position) -> f32 {
+        let inside = cell.x >= 0 && cell.y >= 0 && cell.x < self.width && cell.y < self.height;
+        if inside {
+            self.dps[(cell.y * self.width + cell.x) as usize]
+        } else {
+            0.0
+        }
+    }
+
+    /// The best-covered cell's DPS, for scaling colors
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    /// Changes whenever the coverage would: the grid, or any tower's place,
+    /// range or damage output (upgrades, merges, night, weather...)
+    pub fn fingerprint(state: &GameState) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.u64(state.grid.revision());
+        for tower in state.towers.values() {
+            hasher.position(tower.position);
+            hasher.variant(&tower.tower_type);
+            hasher.bool(tower.disabled);
+            for stat in [Stat::Damage, Stat::Range, Stat::FireRate] {
+                hasher.f32(tower.stats.get(stat));
+            }
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType};
+
+    #[test]
+    fn test_coverage_sums_towers_in_range() {
+        let mut game = Game::new();
+        assert_eq!(Coverage::compute(&game.state).max(), 0.0);
+        let before = Coverage::fingerprint(&game.state);
+
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6));
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(6, 6));
+        assert_ne!(Coverage::fingerprint(&game.state), before);
+
+        let coverage = Coverage::compute(&game.state);
+        let single = TowerType::Basic.damage() as f32 * TowerType::Basic.fire_rate();
+        assert_eq!(coverage.dps_at(Position::new(5, 6)), single * 2.0);
+        assert_eq!(coverage.dps_at(Position::new(4, 6)), 0.0); // The tower's own cell
+        assert_eq!(coverage.dps_at(Position::new(19, 0)), 0.0);
+        assert_eq!(coverage.max(), single * 2.0);
+    }
+}
@@ -0,0 +1,117 @@
+//! Day/night cycle. `WorldClock` lives in `GameState` and runs in game
+//! time; the frontend tints the scene by `light_level`, and while it is
+//! night every tower carries a range penalty through the stat-modifier
+//! system (source `"night"`), added and removed as the clock crosses dusk
+//! and dawn. Off unless a frontend enables it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::stat_sheet::{Modifier, ModifierOp, Stat, StatSheet};
+
+pub const NIGHT_SOURCE: &str = "night";
+const NIGHT_RANGE_MULTIPLIER: f32 = 0.8;
+const DEFAULT_DAY_LENGTH: f32 = 180.0; // Seconds from one noon to the next
+const NIGHT_BELOW: f32 = 0.3; // Light level under which it counts as night
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeOfDay {
+    Day,
+    Night,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldClock {
+    pub enabled: bool,
+    pub time: f32, // Seconds since noon of the first day
+    pub day_length: f32,
+}
+
+impl WorldClock {
+    pub fn new(enabled: bool) -> Self {
+        WorldClock { enabled, time: 0.0, day_length: DEFAULT_DAY_LENGTH }
+    }
+
+    /// 1 at noon, 0 at midnight; always 1 with the cycle off
+    pub fn light_level(&self) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+        let turn = self.time / self.day_length.max(f32::EPSILON) * std::f32::consts::TAU;
+        0.5 + 0.5 * turn.cos()
+    }
+
+    pub fn time_of_day(&self) -> TimeOfDay {
+        if self.light_level() < NIGHT_BELOW {
+            TimeOfDay::Night
+        } else {
+            TimeOfDay::Day
+        }
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.time_of_day() == TimeOfDay::Night
+    }
+
+    /// Run the clock; returns the new time of day if it just changed
+    pub fn advance(&mut self, delta: f32) -> Option<TimeOfDay> {
+        if !self.enabled {
+            return None;
+        }
+        let before = self.time_of_day();
+        self.time = (self.time + delta) % self.day_length.max(f32::EPSILON);
+        let after = self.time_of_day();
+        (after != before).then_some(after)
+    }
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+/// Put a tower's stats in line with the time of day
+pub fn apply_time_of_day(stats: &mut StatSheet, time_of_day: TimeOfDay) {
+    stats.remove_source(NIGHT_SOURCE);
+    if time_of_day == TimeOfDay::Night {
+        stats.add_modifier(Modifier::new(NIGHT_SOURCE, Stat::Range, ModifierOp::Multiply(NIGHT_RANGE_MULTIPLIER)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, Position, TowerType};
+
+    #[test]
+    fn test_clock_reports_dusk_and_dawn() {
+        let mut clock = WorldClock::new(true);
+        assert_eq!(clock.light_level(), 1.0);
+        let mut changes = Vec::new();
+        for _ in 0..180 {
+            changes.extend(clock.advance(1.0));
+        }
+        assert_eq!(changes, vec![TimeOfDay::Night, TimeOfDay::Day]);
+        assert_eq!(WorldClock::default().advance(100.0), None);
+    }
+
+    #[test]
+    fn test_towers_lose_range_at_night() {
+        let mut game = Game::new();
+        game.state.clock = WorldClock::new(true);
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6));
+        let tower = game.state.towers.keys().next().unwrap();
+        let day_range = game.state.towers[tower].stats.get(Stat::Range);
+
+        game.state.clock.time = game.state.clock.day_length * 0.3; // Just before dusk
+        for _ in 0..100 {
+            game.update(0.1);
+        }
+        assert!(game.state.clock.is_night());
+        assert_eq!(game.state.towers[tower].stats.get(Stat::Range), day_range * NIGHT_RANGE_MULTIPLIER);
+
+        // Built after dusk: penalised straight away
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(8, 2));
+        assert!(game.state.towers.values().all(|tower| tower.stats.get(Stat::Range) < day_range));
+    }
+}
@@ -0,0 +1,113 @@
+//! Armor and the support towers that wear it down. From `ARMOR_FIRST_WAVE`
+//! on, enemies spawn with armor that blocks a share of every hit. Shredder
+//! hits strip some of it and Marker hits make the enemy take extra damage;
+//! both build up with each hit, up to a limit per tower type, and fade a few
+//! seconds after the last one. The damage formula (`damage_after_defenses`)
+//! reads them for every source of damage, burns included.
+
+use crate::status_effects::{EffectKind, EffectRules, EffectSource, StatusEffect};
+use crate::{Enemy, EnemyId, Game, TowerType};
+
+pub const ARMOR_FIRST_WAVE: u32 = 5;
+const ARMOR_PER_WAVE: f32 = 0.04;
+const MAX_ARMOR: f32 = 0.5;
+
+/// Share of each hit blocked by the armor of a wave's enemies
+pub fn armor_for_wave(wave: u32) -> f32 {
+    if wave < ARMOR_FIRST_WAVE {
+        return 0.0;
+    }
+    ((wave - ARMOR_FIRST_WAVE + 1) as f32 * ARMOR_PER_WAVE).min(MAX_ARMOR)
+}
+
+/// An effect a tower's hits build up on enemies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Debuff {
+    pub kind: EffectKind,
+    pub per_hit: f32,
+    pub max: f32,      // Most one tower type can build up
+    pub duration: f32, // Seconds after the last hit
+}
+
+impl TowerType {
+    pub fn debuff(&self) -> Option<Debuff> {
+        match self {
+            TowerType::Shredder => Some(Debuff { kind: EffectKind::Shred, per_hit: 0.1, max: 0.5, duration: 4.0 }),
+            TowerType::Marker => Some(Debuff { kind: EffectKind::Amplify, per_hit: 0.15, max: 0.45, duration: 3.0 }),
+            _ => None,
+        }
+    }
+}
+
+impl Enemy {
+    /// Share of each hit its armor still blocks after shredding
+    pub fn armor_left(&self, rules: &EffectRules) -> f32 {
+        (self.armor - self.effects.total(EffectKind::Shred, rules)).max(0.0)
+    }
+
+    /// What a hit of `damage` actually takes off, after armor and amplify.
+    /// A hit that does anything does at least one point.
+    pub fn damage_after_defenses(&self, damage: i32, rules: &EffectRules) -> i32 {
+        if damage <= 0 {
+            return damage;
+        }
+        let amplify = self.effects.total(EffectKind::Amplify, rules);
+        let scaled = damage as f32 * (1.0 - self.armor_left(rules)) * (1.0 + amplify);
+        (scaled.round() as i32).max(1)
+    }
+}
+
+impl Game {
+    /// Build up a tower's debuff on an enemy it hit
+    pub(crate) fn apply_debuff(&mut self, enemy_id: EnemyId, tower_type: TowerType) {
+        let Some(debuff) = tower_type.debuff() else {
+            return;
+        };
+        let effect = StatusEffect::new(debuff.kind, EffectSource::Tower(tower_type), debuff.per_hit, debuff.duration);
+        self.stack_effect(enemy_id, effect, debuff.max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_effects::EffectStack;
+
+    #[test]
+    fn test_armor_grows_with_waves_up_to_a_cap() {
+        assert_eq!(armor_for_wave(ARMOR_FIRST_WAVE - 1), 0.0);
+        assert!(armor_for_wave(ARMOR_FIRST_WAVE) > 0.0);
+        assert!(armor_for_wave(ARMOR_FIRST_WAVE + 1) > armor_for_wave(ARMOR_FIRST_WAVE));
+        assert_eq!(armor_for_wave(1000), MAX_ARMOR);
+    }
+
+    #[test]
+    fn test_shred_and_amplify_feed_the_damage_formula() {
+        let mut game = Game::new();
+        let enemy_id = game.state.spawn_enemy_with(|enemy| enemy.armor = 0.4).unwrap();
+        let rules = EffectRules::default();
+        let enemy = &game.state.enemies[enemy_id];
+        assert_eq!(enemy.damage_after_defenses(100, &rules), 60);
+        assert_eq!(enemy.damage_after_defenses(1, &rules), 1); // Never shrugged off entirely
+
+        // Shred builds up hit by hit, to the tower type's limit
+        for _ in 0..3 {
+            game.apply_debuff(enemy_id, TowerType::Shredder);
+        }
+        assert_eq!(game.state.enemies[enemy_id].damage_after_defenses(100, &rules), 90);
+        for _ in 0..10 {
+            game.apply_debuff(enemy_id, TowerType::Shredder);
+        }
+        let enemy = &game.state.enemies[enemy_id];
+        assert_eq!(enemy.armor_left(&rules), 0.0);
+        assert_eq!(enemy.damage_after_defenses(100, &rules), 100);
+
+        game.apply_debuff(enemy_id, TowerType::Marker);
+        assert_eq!(game.state.enemies[enemy_id].damage_after_defenses(100, &rules), 115);
+
+        // Both fade once the hits stop
+        game.update_status_effects(5.0);
+        assert_eq!(game.state.enemies[enemy_id].effects, EffectStack::default());
+        assert_eq!(game.state.enemies[enemy_id].damage_after_defenses(100, &rules), 60);
+    }
+}
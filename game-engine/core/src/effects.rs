@@ -0,0 +1,119 @@
+//! Short-lived visual effects the simulation emits for the renderer:
+//! muzzle flashes, explosions and floating text.
+
+use crate::abilities::AbilityType;
+use crate::traps::TrapType;
+use crate::{TowerType, CELL_SIZE};
+
+/// What produced a visual effect; the renderer maps this to a color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EffectStyle {
+    Projectile(TowerType), // Muzzle flash of a tower shot
+    Splash,
+    Frost,
+    Ability(AbilityType),
+    Trap(TrapType),
+    HeroStrike,
+    Leak,
+    Delivery, // A convoy's payout
+}
+
+#[derive(Debug, Clone)]
+pub struct MuzzleFlash {
+    pub x: f32,
+    pub y: f32,
+    pub style: EffectStyle,
+    pub lifetime: f32,
+    pub max_lifetime: f32,
+}
+
+impl MuzzleFlash {
+    pub fn new(x: f32, y: f32, style: EffectStyle) -> Self {
+        MuzzleFlash {
+            x,
+            y,
+            style,
+            lifetime: 0.1,
+            max_lifetime: 0.1,
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) -> bool {
+        self.lifetime -= delta;
+        self.lifetime > 0.0
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.lifetime / self.max_lifetime
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExplosionEffect {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    pub max_radius: f32,
+    pub style: EffectStyle,
+    pub lifetime: f32,
+    pub max_lifetime: f32,
+}
+
+impl ExplosionEffect {
+    pub fn new(x: f32, y: f32, radius: f32, style: EffectStyle) -> Self {
+        ExplosionEffect {
+            x,
+            y,
+            radius: 0.0,
+            max_radius: radius * CELL_SIZE,
+            style,
+            lifetime: 0.3,
+            max_lifetime: 0.3,
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) -> bool {
+        self.lifetime -= delta;
+        let progress = 1.0 - (self.lifetime / self.max_lifetime);
+        self.radius = self.max_radius * progress;
+        self.lifetime > 0.0
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.lifetime / self.max_lifetime
+    }
+}
+
+/// Text that drifts upward and fades, e.g. "-1" over the goal on a leak
+#[derive(Debug, Clone)]
+pub struct FloatingText {
+    pub x: f32,
+    pub y: f32,
+    pub text: String,
+    pub style: EffectStyle,
+    pub lifetime: f32,
+    pub max_lifetime: f32,
+}
+
+impl FloatingText {
+    pub fn new(x: f32, y: f32, text: &str, style: EffectStyle) -> Self {
+        FloatingText {
+            x,
+            y,
+            text: text.to_string(),
+            style,
+            lifetime: 1.0,
+            max_lifetime: 1.0,
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) -> bool {
+        self.lifetime -= delta;
+        self.y -= 30.0 * delta;
+        self.lifetime > 0.0
+    }
+
+    pub fn alpha(&self) -> f32 {
+        self.lifetime / self.max_lifetime
+    }
+}
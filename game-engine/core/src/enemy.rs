@@ -0,0 +1,177 @@
+//! Enemies walking the route, their movement and health.
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::gunners::EnemyKind;
+use crate::lanes::{LaneId, MAIN_LANE};
+use crate::pathfinding::{find_waypoints, find_waypoints_or_nearest, PathResult};
+use crate::siege::Siege;
+use crate::status_effects::EffectStack;
+use crate::{ConvoyId, EnemyId, Grid, Position, CELL_SIZE};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Enemy {
+    pub id: EnemyId,
+    pub x: f32,
+    pub y: f32,
+    pub path: Vec<Position>,
+    pub current_waypoint: usize,
+    pub speed: f32,
+    pub health: i32,
+    pub max_health: i32,
+    #[serde(default)]
+    pub armor: f32, // Share of each hit blocked; see `debuffs`
+    #[serde(default)]
+    pub lane: f32, // Cells to the right of its route, for formations
+    #[serde(default)]
+    pub effects: EffectStack, // Slows and burns on it; see `status_effects`
+    #[serde(default)]
+    pub slow_multiplier: f32, // Speed multiplier from `effects`
+    #[serde(default)]
+    pub distance_to_goal: f32, // Pixels left along the path; see `progress`
+    #[serde(default)]
+    pub siege: Siege, // Whether the goal is walled off, and what it's doing about it
+    #[serde(default)]
+    pub kind: EnemyKind,
+    #[serde(default)]
+    pub fire_cooldown: f32, // Until a gunner can shoot at a tower again; see `gunners`
+    #[serde(default)]
+    pub chasing: Option<ConvoyId>, // A convoy it left its route for; see `convoys`
+    #[serde(default)]
+    pub lane_id: LaneId, // The lane it walks, to that lane's goal; see `lanes`
+}
+
+impl Enemy {
+    pub fn new(id: EnemyId, start: Position, goal: Position, grid: &Grid) -> Option<Self> {
+        let path = find_waypoints(grid, start, goal)?;
+        Some(Enemy::on_path(id, start, path, grid))
+    }
+
+    pub(crate) fn on_path(id: EnemyId, start: Position, path: Vec<Position>, grid: &Grid) -> Self {
+        let (x, y) = start.to_world();
+        
+        let mut enemy = Enemy {
+            id,
+            x: x + CELL_SIZE / 2.0,
+            y: y + CELL_SIZE / 2.0,
+            path,
+            current_waypoint: 0,
+            speed: 50.0,
+            health: 100,
+            max_health: 100,
+            armor: 0.0,
+            lane: 0.0,
+            effects: EffectStack::default(),
+            slow_multiplier: 1.0,
+            distance_to_goal: 0.0,
+            siege: Siege::Marching,
+            kind: EnemyKind::Grunt,
+            fire_cooldown: 0.0,
+            chasing: None,
+            lane_id: MAIN_LANE,
+        };
+        enemy.distance_to_goal = enemy.measure_remaining(grid);
+        enemy
+    }
+
+    pub fn update(&mut self, delta: f32) -> bool {
+        self.advance(delta, 1.0)
+    }
+
+    /// Like `update`, with movement scaled by `pace` (weather and the like)
+    pub fn advance(&mut self, delta: f32, pace: f32) -> bool {
+        if self.current_waypoint >= self.path.len() {
+            return false; // Reached goal
+        }
+
+        let Some((target_x, target_y)) = self.heading() else {
+            return false;
+        };
+
+        let dx = target_x - self.x;
+        let dy = target_y - self.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+
+        if distance < 2.0 {
+            self.current_waypoint += 1;
+            return self.current_waypoint < self.path.len();
+        }
+
+        let effective_speed = self.speed * self.slow_multiplier * pace;
+        let move_distance = effective_speed * delta;
+        let direction_x = dx / distance;
+        let direction_y = dy / distance;
+
+        self.x += direction_x * move_distance;
+        self.y += direction_y * move_distance;
+
+        true
+    }
+
+    /// Whether the rest of the route (from the leg the enemy is on) passes
+    /// through a cell
+    pub fn route_crosses(&self, cell: Position) -> bool {
+        let Some(rest) = self.path.get(self.current_waypoint.saturating_sub(1)..) else {
+            return false;
+        };
+        if let [only] = rest {
+            return *only == cell;
+        }
+        rest.windows(2).any(|leg| {
+            let (from, to) = (leg[0], leg[1]);
+            let between = |value: i32, a: i32, b: i32| a.min(b) <= value && value <= a.max(b);
+            if from.x == to.x || from.y == to.y {
+                between(cell.x, from.x, to.x) && between(cell.y, from.y, to.y)
+            } else {
+                cell == from || cell == to // Teleporter jump
+            }
+        })
+    }
+
+    /// Re-route toward `goal`. If it's walled off, head as close as possible
+    /// and break through there (see `siege`).
+    pub fn recalculate_path(&mut self, grid: &Grid, goal: Position) {
+        let current_pos = grid.cell_at(self.x, self.y);
+        match find_waypoints_or_nearest(grid, current_pos, goal) {
+            PathResult::Complete(new_path) => {
+                self.path = new_path;
+                self.current_waypoint = 0;
+                self.siege = Siege::Marching;
+            }
+            PathResult::Partial(new_path) => {
+                debug!(enemy_id = ?self.id, ?current_pos, "goal walled off; heading as close as possible");
+                self.path = new_path;
+                self.current_waypoint = 0;
+                self.siege = Siege::Breaching;
+            }
+            PathResult::Unreachable => {
+                warn!(enemy_id = ?self.id, ?current_pos, "standing on a closed cell; keeping old route");
+            }
+        }
+    }
+
+    pub fn take_damage(&mut self, damage: i32) {
+        self.health = (self.health - damage).max(0);
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    /// Current velocity in pixels per second, heading for the next waypoint
+    /// at `pace` (see `advance`)
+    pub fn velocity(&self, pace: f32) -> (f32, f32) {
+        let Some((target_x, target_y)) = self.heading() else {
+            return (0.0, 0.0);
+        };
+        let dx = target_x - self.x;
+        let dy = target_y - self.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance < f32::EPSILON {
+            return (0.0, 0.0);
+        }
+        let speed = self.speed * self.slow_multiplier * pace;
+        (dx / distance * speed, dy / distance * speed)
+    }
+}
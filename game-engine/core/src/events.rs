@@ -0,0 +1,39 @@
+use crate::{EnemyId, Position, TowerId, TowerType};
+
+/// Things that happened during a simulation tick. Gameplay code emits these;
+/// stats, effects and audio consume them instead of being called directly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    EnemyKilled { enemy_id: EnemyId, x: f32, y: f32, gold: i32, max_health: i32 },
+    EnemyLeaked { enemy_id: EnemyId, x: f32, y: f32 },
+    WaveStarted { wave: u32 },
+    WaveCleared { wave: u32 },
+    TowerPlaced { tower_id: TowerId, tower_type: TowerType, position: Position },
+    TowerSold { tower_id: TowerId, tower_type: TowerType, position: Position },
+    TowerDestroyed { tower_id: TowerId, tower_type: TowerType, position: Position }, // Broken by enemies; see `siege`
+    DamageDealt { tower_type: TowerType, amount: i32 }, // Health actually removed by one shot
+}
+
+/// Queue of events emitted since the last time the game handled them
+#[derive(Debug, Clone, Default)]
+pub struct EventBus {
+    queue: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus { queue: Vec::new() }
+    }
+
+    pub fn emit(&mut self, event: GameEvent) {
+        self.queue.push(event);
+    }
+
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.queue)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
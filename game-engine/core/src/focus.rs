@@ -0,0 +1,115 @@
+//! Player control over what a tower shoots. A tower can be hard-locked
+//! onto one enemy: it then fires only at that enemy, holding fire while it
+//! is out of range, until it dies or leaks. Separately, a tower can have
+//! ignore zones: cells it won't shoot into, e.g. to save a Splash tower for
+//! the packs in front of it. A lock overrides the zones.
+
+use tracing::debug;
+
+use crate::actions::ActionError;
+use crate::{EnemyId, GameState, Position, Tower, TowerId};
+
+impl Tower {
+    /// Whether a world point lies in one of this tower's ignore zones
+    pub fn ignores(&self, x: f32, y: f32) -> bool {
+        self.ignore_zones.binary_search_by_key(&row_order(&Position::from_world(x, y)), row_order).is_ok()
+    }
+}
+
+fn row_order(position: &Position) -> (i32, i32) {
+    (position.y, position.x)
+}
+
+impl GameState {
+    /// Lock a tower onto an enemy, or release its lock with `None`
+    pub fn lock_target(&mut self, tower_id: TowerId, enemy_id: Option<EnemyId>) -> Result<(), ActionError> {
+        if enemy_id.is_some_and(|enemy_id| !self.enemies.contains_key(enemy_id)) {
+            return Err(ActionError::NoSuchEnemy);
+        }
+        let tower = self.towers.get_mut(tower_id).ok_or(ActionError::NoSuchTower)?;
+        tower.locked_target = enemy_id;
+        debug!(?tower_id, ?enemy_id, "target lock set");
+        Ok(())
+    }
+
+    /// Add a cell to a tower's ignore zones, or take it out
+    pub fn set_ignored(&mut self, tower_id: TowerId, cell: Position, ignored: bool) -> Result<(), ActionError> {
+        if !self.grid.in_bounds(&cell) {
+            return Err(ActionError::OutOfBounds);
+        }
+        let tower = self.towers.get_mut(tower_id).ok_or(ActionError::NoSuchTower)?;
+        let zones = &mut tower.ignore_zones;
+        match (zones.binary_search_by_key(&row_order(&cell), row_order), ignored) {
+            (Err(index), true) => zones.insert(index, cell),
+            (Ok(index), false) => {
+                zones.remove(index);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Drop a tower's lock and all its ignore zones
+    pub fn reset_targeting(&mut self, tower_id: TowerId) -> Result<(), ActionError> {
+        let tower = self.towers.get_mut(tower_id).ok_or(ActionError::NoSuchTower)?;
+        tower.locked_target = None;
+        tower.ignore_zones.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType, CELL_SIZE};
+
+    fn game_with_tower() -> (Game, TowerId) {
+        let mut game = Game::new();
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(6, 6));
+        let tower_id = game.state.tower_at(&Position::new(6, 6)).unwrap().id;
+        (game, tower_id)
+    }
+
+    fn place_enemy(game: &mut Game, cell: Position) -> EnemyId {
+        let enemy_id = game.state.spawn_enemy_with(|_| {}).unwrap();
+        let (x, y) = cell.to_world();
+        let enemy = &mut game.state.enemies[enemy_id];
+        (enemy.x, enemy.y) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+        enemy_id
+    }
+
+    #[test]
+    fn test_lock_overrides_the_usual_pick() {
+        let (mut game, tower_id) = game_with_tower();
+        let behind = place_enemy(&mut game, Position::new(5, 7));
+        let ahead = place_enemy(&mut game, Position::new(7, 7));
+        game.state.enemies[ahead].current_waypoint = 3; // Closer to the goal, the default pick
+        assert!(game.state.lock_target(tower_id, Some(behind)).is_ok());
+
+        game.update(1.0 / 30.0);
+        assert_eq!(game.state.towers[tower_id].target_id, Some(behind));
+
+        game.state.enemies.remove(behind);
+        game.update(1.0 / 30.0);
+        assert_eq!(game.state.towers[tower_id].locked_target, None);
+    }
+
+    #[test]
+    fn test_ignore_zones_are_skipped() {
+        let (mut game, tower_id) = game_with_tower();
+        let enemy_id = place_enemy(&mut game, Position::new(7, 7));
+        assert!(game.state.set_ignored(tower_id, Position::new(7, 7), true).is_ok());
+        assert!(game.state.set_ignored(tower_id, Position::new(3, 5), true).is_ok());
+        assert_eq!(game.state.set_ignored(tower_id, Position::new(-1, 5), true), Err(ActionError::OutOfBounds));
+        assert_eq!(game.state.towers[tower_id].ignore_zones, vec![Position::new(3, 5), Position::new(7, 7)]);
+
+        game.update(1.0 / 30.0);
+        assert_eq!(game.state.towers[tower_id].target_id, None);
+
+        assert!(game.state.set_ignored(tower_id, Position::new(7, 7), false).is_ok());
+        game.update(1.0 / 30.0);
+        assert_eq!(game.state.towers[tower_id].target_id, Some(enemy_id));
+        assert!(game.state.reset_targeting(tower_id).is_ok());
+        assert!(game.state.towers[tower_id].ignore_zones.is_empty());
+    }
+}
@@ -0,0 +1,139 @@
+//! Optional fog of war. Every tower lights up the cells within its vision
+//! radius (the base sees a little around the goal too); anything outside
+//! that is hidden from the player and can't be targeted by towers, so
+//! long-range towers need spotters nearby. Cells that were ever lit stay
+//! "explored" and are drawn dimmer than unexplored ones.
+//!
+//! Visibility only depends on where towers stand, so it is recomputed when
+//! the grid's revision changes rather than every tick.
+
+use crate::{GameState, Position, CELL_SIZE};
+
+const GOAL_VISION: f32 = 2.5; // Cells around the goal that are always visible
+
+#[derive(Debug, Clone, Default)]
+pub struct FogOfWar {
+    pub enabled: bool,
+    width: i32,
+    height: i32,
+    visible: Vec<bool>,
+    explored: Vec<bool>,
+    built_for: Option<(u64, usize)>, // Grid revision and tower count of `visible`
+}
+
+impl FogOfWar {
+    pub fn new(enabled: bool) -> Self {
+        FogOfWar { enabled, ..Self::default() }
+    }
+
+    /// Recompute visibility if towers may have moved since the last call
+    pub fn refresh(&mut self, state: &GameState) {
+        if !self.enabled {
+            return;
+        }
+        let key = (state.grid.revision(), state.towers.len());
+        let resized = (self.width, self.height) != (state.grid.width(), state.grid.height());
+        if self.built_for == Some(key) && !resized {
+            return;
+        }
+        self.built_for = Some(key);
+        if resized {
+            self.width = state.grid.width();
+            self.height = state.grid.height();
+            let cells = (self.width * self.height).max(0) as usize;
+            self.visible = vec![false; cells];
+            self.explored = vec![false; cells];
+        }
+
+        self.visible.fill(false);
+        let mut sources: Vec<((f32, f32), f32)> = state
+            .towers
+            .values()
+            .map(|tower| (tower.world_position(), tower.tower_type.vision()))
+            .collect();
+        for goal in state.lane_goals() {
+            let (goal_x, goal_y) = goal.to_world();
+            sources.push(((goal_x + CELL_SIZE / 2.0, goal_y + CELL_SIZE / 2.0), GOAL_VISION));
+        }
+
+        for ((x, y), radius) in sources {
+            let reach = radius * CELL_SIZE;
+            let (min, max) = (cell_at(x - reach, y - reach), cell_at(x + reach, y + reach));
+            for cy in min.y.max(0)..=max.y.min(self.height - 1) {
+                for cx in min.x.max(0)..=max.x.min(self.width - 1) {
+                    let (center_x, center_y) = (cx as f32 * CELL_SIZE + CELL_SIZE / 2.0, cy as f32 * CELL_SIZE + CELL_SIZE / 2.0);
+                    let (dx, dy) = (center_x - x, center_y - y);
+                    if dx * dx + dy * dy <= reach * reach {
+                        let index = (cy * self.width + cx) as usize;
+                        self.visible[index] = true;
+                        self.explored[index] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn index(&self, cell: Position) -> Option<usize> {
+        let inside = cell.x >= 0 && cell.y >= 0 && cell.x < self.width && cell.y < self.height;
+        inside.then(|| (cell.y * self.width + cell.x) as usize)
+    }
+
+    /// Whether a cell is currently in sight. Everything is when fog is off.
+    pub fn is_visible(&self, cell: Position) -> bool {
+        !self.enabled || self.index(cell).is_some_and(|index| self.visible[index])
+    }
+
+    pub fn is_explored(&self, cell: Position) -> bool {
+        !self.enabled || self.index(cell).is_some_and(|index| self.explored[index])
+    }
+
+    /// Whether a world-space point is in sight
+    pub fn can_see(&self, x: f32, y: f32) -> bool {
+        self.is_visible(cell_at(x, y))
+    }
+}
+
+fn cell_at(x: f32, y: f32) -> Position {
+    Position::new((x / CELL_SIZE).floor() as i32, (y / CELL_SIZE).floor() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType};
+
+    #[test]
+    fn test_towers_reveal_and_explored_cells_persist() {
+        let mut game = Game::new();
+        game.fog = FogOfWar::new(true);
+        game.fog.refresh(&game.state);
+        assert!(!game.fog.is_visible(Position::new(4, 6)));
+        assert!(game.fog.is_visible(game.state.goal_point));
+
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6));
+        game.fog.refresh(&game.state);
+        assert!(game.fog.is_visible(Position::new(6, 6)));
+        assert!(!game.fog.is_visible(Position::new(12, 6)));
+
+        let tower = game.state.towers.keys().next().unwrap();
+        assert!(game.sell_tower(tower).is_ok());
+        game.fog.refresh(&game.state);
+        assert!(!game.fog.is_visible(Position::new(6, 6)));
+        assert!(game.fog.is_explored(Position::new(6, 6)));
+    }
+
+    #[test]
+    fn test_towers_ignore_enemies_in_fog() {
+        let mut game = Game::new();
+        game.fog = FogOfWar::new(true);
+        assert!(game.state.spawn_enemy().is_ok());
+        game.fog.refresh(&game.state);
+        let enemy = game.state.enemies.keys().next().unwrap();
+        game.state.enemies[enemy].x = 100.0;
+        game.state.enemies[enemy].y = 100.0;
+        assert!(game.find_target_where(100.0, 100.0, (0.0, 3.0), |_| true).is_none());
+
+        game.fog.enabled = false;
+        assert!(game.find_target_where(100.0, 100.0, (0.0, 3.0), |_| true).is_some());
+    }
+}
@@ -0,0 +1,249 @@
+//! How a wave's enemies arrive. A map's `waves` list is its wave script:
+//! each plan applies from its `from_wave` until a later plan takes over, and
+//! picks a formation plus how much each enemy's speed may vary. Formations
+//! spawn enemies in small groups, each member walking its own lane to the
+//! side of the route; groups come as often on average as a single file
+//! would, so a wave takes as long either way. Speed jitter is drawn from
+//! the seeded `GameState::rng`, so a replay with the same seed matches. A
+//! plan may also fix the wave's `roster` instead of the usual mix.
+//!
+//! ```text
+//! "waves": [
+//!   { "from_wave": 3, "formation": "StaggeredPairs", "speed_jitter": 0.1 },
+//!   { "from_wave": 6, "formation": "Wedge", "roster": { "grunts": 12, "gunners": 4 } }
+//! ]
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::StateHasher;
+use crate::gunners::EnemyKind;
+use crate::waves::SPAWN_INTERVAL;
+use crate::{Enemy, CELL_SIZE};
+
+const PAIR_STAGGER: f32 = 0.3; // Seconds between a pair's two members
+const PAIR_LANE: f32 = 0.25; // Cells either side of the route
+const WEDGE_LAG: f32 = 0.35; // Seconds between the point and its flanks
+const WEDGE_LANE: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Formation {
+    /// Single file, evenly spaced
+    #[default]
+    Column,
+    /// One in front, two side by side just behind
+    Wedge,
+    /// Two abreast, one a step behind the other
+    StaggeredPairs,
+}
+
+impl Formation {
+    /// Enemies per group
+    pub fn size(&self) -> u32 {
+        match self {
+            Formation::Column => 1,
+            Formation::Wedge => 3,
+            Formation::StaggeredPairs => 2,
+        }
+    }
+
+    /// Cells to the right of the route (left if negative) for the enemy in
+    /// `slot` of the wave, counting from zero
+    pub fn lane(&self, slot: u32) -> f32 {
+        match (self, slot % self.size()) {
+            (Formation::Wedge, 1) => -WEDGE_LANE,
+            (Formation::Wedge, 2) => WEDGE_LANE,
+            (Formation::StaggeredPairs, 0) => -PAIR_LANE,
+            (Formation::StaggeredPairs, _) => PAIR_LANE,
+            _ => 0.0,
+        }
+    }
+
+    /// Seconds from spawning `slot` to the next enemy
+    pub fn gap_after(&self, slot: u32) -> f32 {
+        let group = SPAWN_INTERVAL * self.size() as f32;
+        match (self, slot % self.size()) {
+            (Formation::Column, _) => SPAWN_INTERVAL,
+            (Formation::Wedge, 0) => WEDGE_LAG,
+            (Formation::Wedge, 1) => 0.0, // Flanks side by side
+            (Formation::Wedge, _) => group - WEDGE_LAG,
+            (Formation::StaggeredPairs, 0) => PAIR_STAGGER,
+            (Formation::StaggeredPairs, _) => group - PAIR_STAGGER,
+        }
+    }
+}
+
+/// One entry of a map's wave script
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WavePlan {
+    #[serde(default = "first_wave")]
+    pub from_wave: u32,
+    #[serde(default)]
+    pub formation: Formation,
+    #[serde(default)]
+    pub speed_jitter: f32, // Each enemy's speed is scaled by 1 ± up to this
+    #[serde(default)]
+    pub roster: Option<Roster>, // Exactly who comes; the usual mix if unset
+}
+
+/// How many of each enemy kind a wave sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Roster {
+    #[serde(default)]
+    pub grunts: u32,
+    #[serde(default)]
+    pub gunners: u32,
+}
+
+impl Roster {
+    pub fn size(&self) -> u32 {
+        self.grunts + self.gunners
+    }
+
+    /// The kind of the enemy in `slot`, with gunners spread evenly through
+    /// the wave
+    pub fn kind(&self, slot: u32) -> EnemyKind {
+        let size = self.size().max(1);
+        if (slot + 1) * self.gunners / size > slot * self.gunners / size {
+            EnemyKind::Gunner
+        } else {
+            EnemyKind::Grunt
+        }
+    }
+}
+
+fn first_wave() -> u32 {
+    1
+}
+
+impl Default for WavePlan {
+    fn default() -> Self {
+        WavePlan { from_wave: first_wave(), formation: Formation::Column, speed_jitter: 0.0, roster: None }
+    }
+}
+
+impl WavePlan {
+    /// The plan in force for `wave`: the latest-starting one that has
+    /// started, or a plain column
+    pub fn for_wave(plans: &[WavePlan], wave: u32) -> WavePlan {
+        plans.iter().filter(|plan| plan.from_wave <= wave).max_by_key(|plan| plan.from_wave).copied().unwrap_or_default()
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.u32(self.from_wave);
+        hasher.variant(&self.formation);
+        hasher.f32(self.speed_jitter);
+        if let Some(roster) = &self.roster {
+            hasher.u32(roster.grunts);
+            hasher.u32(roster.gunners);
+        }
+    }
+}
+
+impl Enemy {
+    /// Where it's walking to: the next waypoint's center, moved `lane`
+    /// cells to the right of the leg that leads there
+    pub(crate) fn heading(&self) -> Option<(f32, f32)> {
+        let waypoint = self.path.get(self.current_waypoint)?;
+        let (x, y) = waypoint.to_world();
+        let center = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+        let Some(from) = self.current_waypoint.checked_sub(1).and_then(|index| self.path.get(index)) else {
+            return Some(center);
+        };
+        let (dx, dy) = ((waypoint.x - from.x) as f32, (waypoint.y - from.y) as f32);
+        let length = dx.hypot(dy);
+        if self.lane == 0.0 || length == 0.0 {
+            return Some(center);
+        }
+        // Right of travel, with y pointing down the screen
+        let offset = self.lane * CELL_SIZE / length;
+        Some((center.0 - dy * offset, center.1 + dx * offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maps::MapFile;
+    use crate::{Game, Position};
+
+    fn scripted_game(plans: Vec<WavePlan>, seed: u64) -> Game {
+        let mut game = Game::new();
+        let map = MapFile {
+            name: "Scripted".to_string(),
+            shape: Default::default(),
+            spawn: Position::new(0, 7),
+            goal: Position::new(19, 7),
+            walls: Vec::new(),
+            obstacles: Vec::new(),
+            teleporters: Vec::new(),
+            portals: Vec::new(),
+            lanes: Vec::new(),
+            decorations: Vec::new(),
+            waves: plans,
+            win_condition: None,
+        };
+        game.state.load_map(&map).unwrap();
+        game.state.rng = crate::rng::SimRng::new(seed);
+        game.state.waves.call_early();
+        game
+    }
+
+    #[test]
+    fn test_groups_keep_the_column_pace() {
+        for formation in [Formation::Column, Formation::Wedge, Formation::StaggeredPairs] {
+            let size = formation.size();
+            let total: f32 = (0..size * 4).map(|slot| formation.gap_after(slot)).sum();
+            assert!((total - SPAWN_INTERVAL * (size * 4) as f32).abs() < 1e-4, "{:?}", formation);
+        }
+        let plans = [
+            WavePlan { from_wave: 3, formation: Formation::Wedge, ..WavePlan::default() },
+            WavePlan { from_wave: 5, formation: Formation::StaggeredPairs, ..WavePlan::default() },
+        ];
+        assert_eq!(WavePlan::for_wave(&plans, 2), WavePlan::default());
+        assert_eq!(WavePlan::for_wave(&plans, 4).formation, Formation::Wedge);
+        assert_eq!(WavePlan::for_wave(&plans, 9).formation, Formation::StaggeredPairs);
+
+        let roster = Roster { grunts: 6, gunners: 2 };
+        let kinds: Vec<EnemyKind> = (0..roster.size()).map(|slot| roster.kind(slot)).collect();
+        assert_eq!(kinds.iter().filter(|kind| **kind == EnemyKind::Gunner).count(), 2);
+        assert_eq!(kinds[3], EnemyKind::Gunner); // Spread out, not bunched at one end
+    }
+
+    #[test]
+    fn test_wedge_flanks_walk_beside_the_point() {
+        let mut game = scripted_game(vec![WavePlan { formation: Formation::Wedge, ..WavePlan::default() }], 1);
+        for _ in 0..30 {
+            game.update(1.0 / 30.0);
+        }
+        let mut enemies: Vec<&Enemy> = game.state.enemies.values().collect();
+        assert_eq!(enemies.len(), 3);
+        enemies.sort_by(|a, b| a.lane.total_cmp(&b.lane));
+        let lanes: Vec<f32> = enemies.iter().map(|enemy| enemy.lane).collect();
+        assert_eq!(lanes, vec![-WEDGE_LANE, 0.0, WEDGE_LANE]);
+
+        // The flanks came out together, behind the point, on either side
+        let (left, point, right) = (enemies[0], enemies[1], enemies[2]);
+        assert_eq!(left.x, right.x);
+        assert!(point.x > left.x);
+        assert!(left.y < point.y && point.y < right.y);
+    }
+
+    #[test]
+    fn test_speed_jitter_follows_the_seed() {
+        let jittery = vec![WavePlan { speed_jitter: 0.2, ..WavePlan::default() }];
+        let speeds = |seed| {
+            let mut game = scripted_game(jittery.clone(), seed);
+            for _ in 0..200 {
+                game.update(1.0 / 30.0);
+            }
+            game.state.enemies.values().map(|enemy| enemy.speed).collect::<Vec<_>>()
+        };
+        let first = speeds(3);
+        assert!(first.len() > 3);
+        assert!(first.iter().all(|speed| (40.0..60.0).contains(speed)));
+        assert!(first.windows(2).any(|pair| pair[0] != pair[1]));
+        assert_eq!(first, speeds(3));
+        assert_ne!(first, speeds(4));
+    }
+}
@@ -0,0 +1,1266 @@
+//! `Game`: the state plus the update loop that runs it, projectiles,
+//! effects and the event bus.
+
+use serde::{Deserialize, Serialize};
+use slotmap::SlotMap;
+use tracing::{debug, info, trace};
+
+use crate::abilities::{Abilities, AbilityType};
+use crate::achievements::AchievementTracker;
+use crate::actions::ActionError;
+use crate::events::{EventBus, GameEvent};
+use crate::fog::FogOfWar;
+use crate::gunners::EnemyShot;
+use crate::mutators::{Mutator, RuleSet};
+use crate::profiling::{Profiler, TargetDecision, TargetingLog};
+use crate::spatial::SpatialHash;
+use crate::stat_sheet::Stat;
+use crate::stats::RunStats;
+use crate::status_effects::{EffectKind, EffectSource, StatusEffect};
+use crate::traps::TrapType;
+use crate::weather::Weather;
+use crate::{
+    lead_target, segment_distance, EffectStyle, Enemy, EnemyId, ExplosionEffect, FloatingText, GameState, Grid,
+    GridShape, Guidance, MuzzleFlash, Position, Projectile, ProjectileId, Tower, TowerId, TowerType, CELL_SIZE,
+    ENEMY_RADIUS, LOW_HEALTH_THRESHOLD, PROJECTILE_RADIUS, STARTING_HEALTH,
+};
+
+const PARALLEL_ENEMY_CHUNK: usize = 256; // Fewer enemies than this tick on one thread
+
+/// What a left click on the grid builds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BuildTool {
+    Tower(TowerType),
+    Trap(TrapType),
+    Generator, // Only with the power grid on
+}
+
+impl BuildTool {
+    /// Tools in build-slot order, matching `Action::BUILD_SLOTS`
+    pub const SLOTS: [BuildTool; 10] = [
+        BuildTool::Tower(TowerType::Basic),
+        BuildTool::Tower(TowerType::Sniper),
+        BuildTool::Tower(TowerType::Splash),
+        BuildTool::Tower(TowerType::Slow),
+        BuildTool::Trap(TrapType::Mine),
+        BuildTool::Trap(TrapType::Glue),
+        BuildTool::Trap(TrapType::Spikes),
+        BuildTool::Tower(TowerType::Cannon),
+        BuildTool::Tower(TowerType::Shredder),
+        BuildTool::Tower(TowerType::Marker),
+    ];
+
+    /// The slots a board of this shape offers, in order
+    pub fn slots_for(shape: GridShape) -> Vec<BuildTool> {
+        BuildTool::SLOTS.into_iter().filter(|tool| tool.fits_on(shape)).collect()
+    }
+
+    /// Whether the tool can build on a board of this shape
+    pub fn fits_on(&self, shape: GridShape) -> bool {
+        match self {
+            BuildTool::Tower(tower_type) => tower_type.fits_on(shape),
+            BuildTool::Trap(_) | BuildTool::Generator => true,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            BuildTool::Tower(tower_type) => format!("{:?} Tower", tower_type),
+            BuildTool::Trap(trap_type) => format!("{} Trap", trap_type.name()),
+            BuildTool::Generator => "Generator".to_string(),
+        }
+    }
+
+    pub fn cost(&self) -> i32 {
+        match self {
+            BuildTool::Tower(tower_type) => tower_type.cost(),
+            BuildTool::Trap(trap_type) => trap_type.cost(),
+            BuildTool::Generator => crate::power::GENERATOR_COST,
+        }
+    }
+}
+
+pub struct Game {
+    pub state: GameState,
+    pub projectiles: SlotMap<ProjectileId, Projectile>,
+    pub enemy_shots: Vec<EnemyShot>, // Gunners firing back at towers
+    pub muzzle_flashes: Vec<MuzzleFlash>,
+    pub explosions: Vec<ExplosionEffect>,
+    pub abilities: Abilities,
+    pub build_tool: BuildTool,
+    pub dragging_tower: Option<TowerId>, // Tower being dragged onto another to merge
+    pub aiming_tower: Option<TowerId>,   // Tower being dragged onto a target to lock (see `focus`)
+    pub stroke: Option<crate::painting::Stroke>, // Line or rectangle of towers being dragged out
+    pub events: EventBus,
+    pub frame_events: Vec<GameEvent>, // Events handled during the last update, for audio
+    pub stats: RunStats,
+    pub achievements: AchievementTracker, // Empty until the frontend loads definitions
+    pub fog: FogOfWar, // Off unless the frontend turns it on
+    pub floating_texts: Vec<FloatingText>,
+    pub leak_flash: f32, // Remaining time of the red screen-edge flash
+    pub profiler: Profiler, // Per-system update timings for the debug overlay
+    pub targeting: TargetingLog,
+    pub enemy_index: SpatialHash, // Enemy positions bucketed for collision queries
+    pub tick: u64, // Simulation ticks run so far; tags every trace event
+    pub strict_invariants: bool, // Panic on a broken invariant instead of logging it
+    #[cfg(feature = "scripting")]
+    pub scripts: Option<crate::scripting::ScriptHost>, // Custom rule hooks, run after each tick's events
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self::with_rules(RuleSet::default())
+    }
+
+    /// A new game under the given mutators
+    pub fn with_rules(rules: RuleSet) -> Self {
+        let state = GameState::with_rules(rules);
+        let mut stats = RunStats::new();
+        stats.track_gold(state.gold); // So gold spent before the first tick counts
+        Game {
+            state,
+            projectiles: SlotMap::with_key(),
+            enemy_shots: Vec::new(),
+            muzzle_flashes: Vec::new(),
+            explosions: Vec::new(),
+            abilities: Abilities::new(),
+            build_tool: BuildTool::Tower(TowerType::Basic),
+            dragging_tower: None,
+            aiming_tower: None,
+            stroke: None,
+            events: EventBus::new(),
+            frame_events: Vec::new(),
+            stats,
+            achievements: AchievementTracker::default(),
+            fog: FogOfWar::default(),
+            floating_texts: Vec::new(),
+            leak_flash: 0.0,
+            profiler: Profiler::new(),
+            targeting: TargetingLog::new(),
+            enemy_index: SpatialHash::default(),
+            tick: 0,
+            strict_invariants: cfg!(debug_assertions),
+            #[cfg(feature = "scripting")]
+            scripts: None,
+        }
+    }
+
+    /// Build whatever the current build tool is at a grid cell
+    pub fn build_at(&mut self, position: Position) -> Result<(), ActionError> {
+        self.try_build(self.build_tool, position)
+    }
+
+    /// Whether a build went through, for tests that only care about that
+    #[cfg(test)]
+    pub fn build(&mut self, tool: BuildTool, position: Position) -> bool {
+        self.try_build(tool, position).is_ok()
+    }
+
+    /// Build, or say why not
+    pub fn try_build(&mut self, tool: BuildTool, position: Position) -> Result<(), ActionError> {
+        match tool {
+            BuildTool::Tower(tower_type) => {
+                self.state.place_tower(tower_type, position)?;
+                if let Some(tower) = self.state.tower_at(&position) {
+                    self.events.emit(GameEvent::TowerPlaced { tower_id: tower.id, tower_type, position });
+                }
+                Ok(())
+            }
+            BuildTool::Trap(trap_type) => self.state.place_trap(trap_type, position),
+            BuildTool::Generator => self.state.place_generator(position),
+        }
+    }
+
+    pub fn sell_tower(&mut self, tower_id: TowerId) -> Result<(), ActionError> {
+        let Some(&Tower { tower_type, position, .. }) = self.state.towers.get(tower_id) else {
+            return Err(ActionError::NoSuchTower);
+        };
+        self.state.sell_tower(tower_id)?;
+        self.events.emit(GameEvent::TowerSold { tower_id, tower_type, position });
+        Ok(())
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.fog.refresh(&self.state); // Before the pause check, so towers built while paused reveal
+        if self.state.paused || self.state.objective.is_decided() {
+            return;
+        }
+
+        self.tick += 1;
+        let _span = tracing::trace_span!("tick", tick = self.tick, delta).entered();
+
+        let mut mark = self.profiler.now();
+        self.targeting.begin_tick(delta);
+
+        // Advance the build/combat cycle and spawn the current wave
+        self.update_waves(delta);
+        self.update_world_clock(delta);
+        self.update_weather();
+        self.state.update_gates(delta);
+        self.state.update_threat();
+        mark = self.profiler.lap("waves", mark);
+
+        // Update towers
+        self.update_towers(delta);
+        mark = self.profiler.lap("towers", mark);
+
+        // Update projectiles
+        self.update_projectiles(delta);
+        mark = self.profiler.lap("projectiles", mark);
+
+        // Update enemies
+        self.update_status_effects(delta);
+        self.update_enemies(delta);
+        self.update_sieges(delta);
+        self.update_enemy_fire(delta);
+        self.update_convoys(delta);
+        mark = self.profiler.lap("enemies", mark);
+
+        // Update hero
+        self.update_hero(delta);
+        mark = self.profiler.lap("hero", mark);
+
+        // Update effects
+        self.update_effects(delta);
+
+        // Tick ability cooldowns
+        self.abilities.update(delta);
+        mark = self.profiler.lap("effects", mark);
+
+        // Check the map's win condition
+        self.state.update_objective(delta);
+
+        // React to everything that happened this tick
+        self.handle_events();
+        self.stats.track_gold(self.state.gold);
+        self.stats.advance(delta);
+        #[cfg(feature = "scripting")]
+        self.run_script_hooks(delta);
+        self.profiler.lap("events", mark);
+
+        self.check_invariants();
+    }
+
+    pub fn is_low_health(&self) -> bool {
+        self.state.health <= LOW_HEALTH_THRESHOLD
+    }
+
+    fn handle_events(&mut self) {
+        self.frame_events = self.events.drain();
+
+        for event in &self.frame_events {
+            self.stats.record(event);
+            if self.state.run_integrity.is_clean() {
+                self.achievements.record(event);
+            }
+            match event {
+                GameEvent::WaveStarted { wave } => info!(wave, "wave started"),
+                GameEvent::WaveCleared { wave } => info!(wave, gold = self.state.gold, "wave cleared"),
+                GameEvent::EnemyLeaked { enemy_id, .. } => {
+                    info!(?enemy_id, health = self.state.health, "enemy leaked")
+                }
+                GameEvent::EnemyKilled { enemy_id, gold, .. } => debug!(?enemy_id, gold, "enemy killed"),
+                GameEvent::TowerDestroyed { tower_id, .. } => info!(?tower_id, "tower destroyed"),
+                GameEvent::TowerPlaced { .. } | GameEvent::TowerSold { .. } | GameEvent::DamageDealt { .. } => {}
+            }
+
+            if let GameEvent::EnemyLeaked { x, y, .. } = event {
+                self.leak_flash = 0.4;
+                let goal = self.state.grid.cell_at(*x, *y); // Whichever lane's goal it got to
+                let (gx, gy) = goal.to_world();
+                self.floating_texts.push(FloatingText::new(
+                    gx + CELL_SIZE / 2.0 - 8.0,
+                    gy,
+                    "-1",
+                    EffectStyle::Leak,
+                ));
+            }
+        }
+        self.achievements.progress.codex.observe(&self.state);
+    }
+
+    fn update_waves(&mut self, delta: f32) {
+        let was_building = self.state.waves.is_build_phase();
+        let wave = self.state.waves.wave;
+        if self.state.rules.sandbox || self.state.rules.attack {
+            self.state.waves.hold(); // Waves only come when called
+        }
+
+        let jitter = self.state.waves.plan().speed_jitter;
+        for spawn in self.state.waves.update(delta, self.state.enemies.len()) {
+            let wave = self.state.waves.wave;
+            let portal = self.state.spawn_cell(wave, spawn.group);
+            let pace = if jitter > 0.0 { 1.0 + self.state.rng.range(-jitter, jitter) } else { 1.0 };
+            self.state.spawn_enemy_from(portal, |enemy| {
+                enemy.set_kind(spawn.kind);
+                enemy.armor = crate::debuffs::armor_for_wave(wave);
+                enemy.lane = spawn.lane;
+                enemy.speed *= pace;
+            });
+        }
+
+        if !self.state.waves.is_build_phase() {
+            self.state.undoable_clears.clear(); // Clears are final once combat starts
+        }
+        if was_building && !self.state.waves.is_build_phase() {
+            self.events.emit(GameEvent::WaveStarted { wave });
+            self.state.charge_upkeep();
+        } else if !was_building && self.state.waves.is_build_phase() {
+            self.events.emit(GameEvent::WaveCleared { wave });
+            if self.state.rules.has(Mutator::TowerDecay) {
+                for tower in self.state.towers.values_mut() {
+                    crate::mutators::decay(&mut tower.stats);
+                }
+            }
+        }
+    }
+
+    /// Run the day/night clock, re-applying the night modifier to every
+    /// tower at dusk and dawn
+    fn update_world_clock(&mut self, delta: f32) {
+        if let Some(time_of_day) = self.state.clock.advance(delta) {
+            info!(?time_of_day, "time of day changed");
+            for tower in self.state.towers.values_mut() {
+                crate::daynight::apply_time_of_day(&mut tower.stats, time_of_day);
+            }
+        }
+    }
+
+    /// Bring in the forecast weather when a wave's combat starts and clear
+    /// it once the wave is over
+    fn update_weather(&mut self) {
+        let waves = &self.state.waves;
+        let wanted = match self.state.weather.forecast(waves.wave) {
+            Some(weather) if !waves.is_build_phase() => weather,
+            _ => Weather::Clear,
+        };
+        if wanted != self.state.weather.current {
+            info!(weather = wanted.name(), "weather changed");
+            self.state.weather.current = wanted;
+            for tower in self.state.towers.values_mut() {
+                crate::weather::apply_weather(&mut tower.stats, wanted);
+            }
+        }
+    }
+
+    /// Towers track their target every tick, but only fire once reloaded
+    /// and with the barrel on target
+    fn update_towers(&mut self, delta: f32) {
+        let mut new_flashes = Vec::new();
+        self.state.refresh_progress();
+
+        // Collect tower IDs and positions first to avoid borrow issues
+        let tower_data: Vec<(TowerId, TowerType, f32, f32, bool)> = self.state.towers
+            .values()
+            .map(|tower| {
+                let (x, y) = tower.world_position();
+                (tower.id, tower.tower_type, x, y, tower.can_shoot())
+            })
+            .collect();
+
+        // Update tower cooldowns, dropping locks on enemies that are gone
+        for tower in self.state.towers.values_mut() {
+            tower.update(delta);
+            if tower.locked_target.is_some_and(|locked| !self.state.enemies.contains_key(locked)) {
+                tower.locked_target = None;
+            }
+        }
+
+        // Find targets, turn toward them and shoot; switched-off and
+        // unpowered towers sit idle
+        let powered = self.state.powered_towers();
+        let ammo = self.state.rules.ammo;
+        for (tower_id, tower_type, tower_x, tower_y, can_shoot) in tower_data {
+            if !powered.contains(&tower_id) {
+                self.state.towers[tower_id].target_id = None;
+                continue;
+            }
+            let tower = &self.state.towers[tower_id];
+            let range = (tower_type.min_range(), tower.stats.get(Stat::Range));
+            let found = match tower.locked_target {
+                Some(locked) => self.find_target_where(tower_x, tower_y, range, |enemy| enemy.id == locked),
+                None => self.find_target_where(tower_x, tower_y, range, |enemy| !tower.ignores(enemy.x, enemy.y)),
+            };
+            if let Some((target, candidates)) = found {
+                let tower = &mut self.state.towers[tower_id];
+                tower.target_id = Some(target.id);
+                let on_target = tower.turn_toward((target.y - tower_y).atan2(target.x - tower_x), delta);
+                if !can_shoot || !on_target {
+                    continue;
+                }
+                tower.shoot();
+                if ammo {
+                    tower.spend_round();
+                }
+
+                self.targeting.record_decision(TargetDecision {
+                    tower_id,
+                    from: (tower_x, tower_y),
+                    enemy_id: target.id,
+                    to: (target.x, target.y),
+                    enemy_waypoint: target.current_waypoint,
+                    candidates,
+                });
+
+                // Create projectile, aimed where the enemy will be
+                let stats = &self.state.towers[tower_id].stats;
+                let aim = lead_target(
+                    (tower_x, tower_y),
+                    (target.x, target.y),
+                    target.velocity(self.state.enemy_pace()),
+                    stats.get(Stat::ProjectileSpeed),
+                );
+                self.projectiles.insert_with_key(|id| {
+                    let projectile = Projectile::new(id, tower_type, stats, (tower_x, tower_y), target.id, aim);
+                    Projectile { tower_id: Some(tower_id), ..projectile }
+                });
+
+                // Create muzzle flash
+                new_flashes.push(MuzzleFlash::new(
+                    tower_x,
+                    tower_y,
+                    EffectStyle::Projectile(tower_type),
+                ));
+            } else {
+                // Clear target if none found
+                if let Some(tower) = self.state.towers.get_mut(tower_id) {
+                    tower.target_id = None;
+                }
+            }
+        }
+
+        // Add new flashes
+        self.muzzle_flashes.extend(new_flashes);
+    }
+
+    /// Pick a target among the enemies `allowed` accepts for a tower with a
+    /// (minimum, maximum) range in cells, along with how many were in range
+    pub(crate) fn find_target_where(
+        &self,
+        tower_x: f32,
+        tower_y: f32,
+        range: (f32, f32),
+        allowed: impl Fn(&Enemy) -> bool,
+    ) -> Option<(Enemy, usize)> {
+        let (min_range, range) = (range.0 * CELL_SIZE, range.1 * CELL_SIZE);
+
+        let in_range: Vec<&Enemy> = self.state
+            .enemies
+            .values()
+            .filter(|enemy| {
+                let dx = enemy.x - tower_x;
+                let dy = enemy.y - tower_y;
+                let distance = (dx * dx + dy * dy).sqrt();
+                distance >= min_range
+                    && distance <= range
+                    && enemy.is_alive()
+                    && self.fog.can_see(enemy.x, enemy.y)
+                    && allowed(enemy)
+            })
+            .collect();
+
+        in_range
+            .iter()
+            // Target the enemy with the least path left (closest to goal)
+            .min_by(|a, b| a.distance_to_goal.total_cmp(&b.distance_to_goal))
+            .map(|enemy| ((*enemy).clone(), in_range.len()))
+    }
+
+    fn update_projectiles(&mut self, delta: f32) {
+        let mut projectiles_to_remove = Vec::new();
+        let mut hits = Vec::new();
+        self.enemy_index.rebuild(&self.state.enemies);
+
+        let pace = self.state.enemy_pace();
+        for (id, projectile) in self.projectiles.iter_mut() {
+            let origin = (projectile.x, projectile.y);
+            let enemy_pos = self.state.enemies
+                .get(projectile.target_id)
+                .map(|e| lead_target(origin, (e.x, e.y), e.velocity(pace), projectile.speed));
+
+            let still_active = projectile.update(delta, enemy_pos);
+
+            let flown = (origin, (projectile.x, projectile.y));
+
+            if projectile.hits_first {
+                // Strike whichever enemy the shot touched first this frame
+                let reach = ENEMY_RADIUS + PROJECTILE_RADIUS;
+                if let Some((_, enemy_id, x, y)) = swept_enemies(&self.enemy_index, &self.state.enemies, flown, reach)
+                    .into_iter()
+                    .next()
+                {
+                    hits.push((enemy_id, projectile.damage_at(x, y), projectile.tower_type, x, y, projectile.tower_id));
+                    projectiles_to_remove.push(id);
+                    continue;
+                }
+            }
+
+            if projectile.is_piercing() {
+                // Sweep the path flown this frame, nearest victims first
+                let struck: Vec<(f32, EnemyId, f32, f32)> =
+                    swept_enemies(&self.enemy_index, &self.state.enemies, flown, ENEMY_RADIUS)
+                        .into_iter()
+                        .filter(|(_, enemy_id, _, _)| !projectile.hit_enemies.contains(enemy_id))
+                        .collect();
+
+                for (_, enemy_id, x, y) in struck {
+                    if projectile.hit_enemies.len() >= projectile.max_hits {
+                        break;
+                    }
+                    projectile.hit_enemies.push(enemy_id);
+                    hits.push((enemy_id, projectile.damage_at(x, y), projectile.tower_type, x, y, projectile.tower_id));
+                }
+
+                if !still_active || projectile.hit_enemies.len() >= projectile.max_hits {
+                    projectiles_to_remove.push(id);
+                }
+                continue;
+            }
+
+            if !still_active || projectile.has_hit() {
+                // Record hit before removing projectile
+                let target = self.state.enemies.get(projectile.target_id);
+                match projectile.guidance {
+                    Guidance::Homing => {
+                        if let Some(enemy) = target {
+                            hits.push((
+                                projectile.target_id,
+                                projectile.damage_at(enemy.x, enemy.y),
+                                projectile.tower_type,
+                                enemy.x,
+                                enemy.y,
+                                projectile.tower_id,
+                            ));
+                        }
+                    }
+                    Guidance::Dumbfire if projectile.has_hit() => {
+                        // Lands at the aim point: splash goes off regardless,
+                        // a direct hit needs the target to actually be there
+                        let (impact_x, impact_y) = (projectile.target_x, projectile.target_y);
+                        let struck = target.is_some_and(|enemy| {
+                            let dx = enemy.x - impact_x;
+                            let dy = enemy.y - impact_y;
+                            (dx * dx + dy * dy).sqrt() <= ENEMY_RADIUS
+                        });
+                        if struck || projectile.tower_type.splash_radius() > 0.0 {
+                            hits.push((
+                                projectile.target_id,
+                                projectile.damage_at(impact_x, impact_y),
+                                projectile.tower_type,
+                                impact_x,
+                                impact_y,
+                                projectile.tower_id,
+                            ));
+                        }
+                    }
+                    Guidance::Dumbfire => {} // Ran out of time in flight
+                }
+                projectiles_to_remove.push(id);
+            }
+        }
+
+        // Remove finished projectiles
+        for id in projectiles_to_remove {
+            self.projectiles.remove(id);
+        }
+
+        // Apply damage, crediting kills to the tower that fired
+        for (enemy_id, damage, tower_type, hit_x, hit_y, tower_id) in hits {
+            let before = self.state.enemies.len();
+            self.apply_damage(enemy_id, damage, tower_type, hit_x, hit_y);
+            let killed = before.saturating_sub(self.state.enemies.len());
+            if let Some(tower) = tower_id.and_then(|id| self.state.towers.get_mut(id)) {
+                tower.kills += killed as u32;
+            }
+        }
+    }
+
+    /// Resolve a hit at (hit_x, hit_y): the target alone, or every enemy in
+    /// the tower's splash radius. Splash is looked up in `enemy_index`, so
+    /// rebuild it first if enemies moved since the last tick.
+    pub fn apply_damage(&mut self, enemy_id: EnemyId, damage: i32, tower_type: TowerType, hit_x: f32, hit_y: f32) {
+        let splash_radius = tower_type.splash_radius();
+        let enemies_to_damage: Vec<EnemyId> = if splash_radius > 0.0 {
+            // Splash damage to nearby enemies
+            crate::aoe::enemies_in_radius(&self.enemy_index, &self.state.enemies, (hit_x, hit_y), splash_radius * CELL_SIZE)
+        } else {
+            // Regular single-target damage
+            vec![enemy_id]
+        };
+
+        let dealt: i32 = enemies_to_damage.iter().map(|id| self.damage_enemy(*id, damage)).sum();
+        if dealt > 0 {
+            self.events.emit(GameEvent::DamageDealt { tower_type, amount: dealt });
+        }
+
+        // Apply slow effect
+        if let Some((duration, multiplier)) = tower_type.slow_effect() {
+            for id in &enemies_to_damage {
+                self.apply_effect(*id, StatusEffect::slow(EffectSource::Tower(tower_type), duration, multiplier));
+            }
+        }
+        for id in &enemies_to_damage {
+            self.apply_debuff(*id, tower_type);
+        }
+
+        if splash_radius > 0.0 {
+            // Create explosion effect
+            let style = if tower_type.slow_effect().is_some() { EffectStyle::Frost } else { EffectStyle::Splash };
+            self.explosions.push(ExplosionEffect::new(hit_x, hit_y, tower_type.splash_radius(), style));
+            self.targeting
+                .record_splash(hit_x, hit_y, tower_type.splash_radius() * CELL_SIZE, enemies_to_damage.len());
+        }
+
+        self.remove_dead_enemies();
+    }
+
+    /// Deal damage to a single enemy, through its armor and debuffs, and
+    /// return how much health it lost. Dead enemies are collected by `remove_dead_enemies`.
+    pub(crate) fn damage_enemy(&mut self, enemy_id: EnemyId, damage: i32) -> i32 {
+        let rules = self.state.content.effect_rules();
+        let Some(enemy) = self.state.enemies.get_mut(enemy_id) else {
+            return 0;
+        };
+        let damage = enemy.damage_after_defenses(damage, &rules);
+        let before = enemy.health.max(0);
+        enemy.take_damage(damage);
+        trace!(?enemy_id, damage, health = enemy.health, "enemy damaged");
+        before - enemy.health.max(0)
+    }
+
+    pub(crate) fn remove_dead_enemies(&mut self) {
+        let mut enemies_to_remove = Vec::new();
+        for (id, enemy) in self.state.enemies.iter() {
+            if !enemy.is_alive() {
+                enemies_to_remove.push(id);
+            }
+        }
+
+        for id in enemies_to_remove {
+            if let Some(enemy) = self.state.enemies.remove(id) {
+                self.release_target(id);
+                let gold = self.state.rules.gold(10); // Reward for killing enemy
+                self.state.gold += gold;
+                self.events.emit(GameEvent::EnemyKilled {
+                    enemy_id: id,
+                    x: enemy.x,
+                    y: enemy.y,
+                    gold,
+                    max_health: enemy.max_health,
+                });
+            }
+        }
+    }
+
+    /// Mark projectiles chasing a removed enemy so they fly out harmlessly
+    pub(crate) fn release_target(&mut self, enemy_id: EnemyId) {
+        for projectile in self.projectiles.values_mut() {
+            if projectile.target_id == enemy_id {
+                projectile.target_lost = true;
+            }
+        }
+    }
+
+    /// Cast an ability. Targeted abilities need a world-space target point.
+    /// Abilities are for waves: refused between waves and while paused, as
+    /// well as if the ability isn't in this run, is on cooldown, has nothing
+    /// to do, or is unaffordable; nothing is spent then.
+    pub fn cast_ability(&mut self, ability_type: AbilityType, target: Option<(f32, f32)>) -> Result<(), ActionError> {
+        let ability = self.abilities.get(ability_type).ok_or(ActionError::NotAllowed)?;
+        if !ability.is_ready() || self.state.paused || self.state.waves.is_build_phase() {
+            return Err(ActionError::NotReady);
+        }
+        match ability_type {
+            AbilityType::MeteorStrike if target.is_none() => return Err(ActionError::NoTarget),
+            AbilityType::EmergencyRepair if self.state.health >= STARTING_HEALTH => return Err(ActionError::NotDamaged),
+            _ => {}
+        }
+        let price = self.state.rules.price(ability_type.cost());
+        if self.state.gold < price {
+            return Err(ActionError::InsufficientGold { price, gold: self.state.gold });
+        }
+
+        match ability_type {
+            AbilityType::MeteorStrike => {
+                let Some((target_x, target_y)) = target else {
+                    return Err(ActionError::NoTarget);
+                };
+                let radius = ability_type.radius() * CELL_SIZE;
+                self.enemy_index.rebuild(&self.state.enemies); // Cast between ticks
+                let enemies_hit =
+                    crate::aoe::enemies_in_radius(&self.enemy_index, &self.state.enemies, (target_x, target_y), radius);
+
+                for id in enemies_hit {
+                    self.damage_enemy(id, ability_type.damage());
+                    if let Some((duration, per_second)) = ability_type.burn() {
+                        let burn = StatusEffect::new(EffectKind::Burn, EffectSource::Ability(ability_type), per_second, duration);
+                        self.apply_effect(id, burn);
+                    }
+                }
+
+                self.explosions.push(ExplosionEffect::new(
+                    target_x,
+                    target_y,
+                    ability_type.radius(),
+                    EffectStyle::Ability(ability_type),
+                ));
+            }
+            AbilityType::GlobalFreeze => {
+                let frozen = StatusEffect::slow(EffectSource::Ability(ability_type), 3.0, 0.0); // Frozen solid for 3 seconds
+                let ids: Vec<EnemyId> = self.state.enemies.keys().collect();
+                for id in ids {
+                    self.apply_effect(id, frozen);
+                }
+            }
+            AbilityType::EmergencyRepair => {
+                self.state.health = (self.state.health + 5).min(STARTING_HEALTH);
+            }
+        }
+
+        self.state.gold -= price;
+        info!(?ability_type, ?target, gold = self.state.gold, "ability cast");
+        if let Some(ability) = self.abilities.get_mut(ability_type) {
+            ability.trigger();
+        }
+        self.remove_dead_enemies();
+
+        Ok(())
+    }
+
+    fn update_enemies(&mut self, delta: f32) {
+        let mut enemies_to_remove = Vec::new();
+        let mut triggered_traps = Vec::new();
+
+        // Movement and status timers touch only the enemy itself, so they run
+        // in parallel; leaks and traps below are applied serially in slot order
+        // to keep the outcome deterministic
+        let pace = self.state.enemy_pace();
+        let moved = tick_enemies(&mut self.state.enemies, delta, pace);
+
+        for (id, still_moving) in moved {
+            let enemy = &mut self.state.enemies[id];
+            if let Some((x, y)) = teleport_exit(&self.state.grid, &enemy.path, enemy.current_waypoint) {
+                (enemy.x, enemy.y) = (x, y);
+            }
+            let enemy = &self.state.enemies[id];
+            if !still_moving && !enemy.is_blocked() && enemy.chasing.is_none() {
+                // Enemy reached goal
+                enemies_to_remove.push(id);
+                self.state.health -= 1;
+                self.events.emit(GameEvent::EnemyLeaked {
+                    enemy_id: id,
+                    x: enemy.x,
+                    y: enemy.y,
+                });
+                continue;
+            }
+
+            // Check for traps under the enemy
+            let cell = self.state.grid.cell_at(enemy.x, enemy.y);
+            if let Some(trap) = self.state.traps.values().find(|trap| trap.position == cell) {
+                if !triggered_traps.iter().any(|(trap_id, _)| *trap_id == trap.id) {
+                    triggered_traps.push((trap.id, id));
+                }
+            }
+        }
+
+        for id in enemies_to_remove {
+            self.state.enemies.remove(id);
+            self.release_target(id);
+        }
+
+        // Everyone moved; traps and the hero query the new positions
+        self.enemy_index.rebuild(&self.state.enemies);
+
+        for (trap_id, enemy_id) in triggered_traps {
+            self.trigger_trap(trap_id, enemy_id);
+        }
+    }
+
+    fn update_hero(&mut self, delta: f32) {
+        self.state.hero.update(delta);
+        if !self.state.hero.is_alive() {
+            return;
+        }
+        let hero = &mut self.state.hero;
+        if let Some((x, y)) = teleport_exit(&self.state.grid, &hero.path, hero.current_waypoint) {
+            (hero.x, hero.y) = (x, y);
+        }
+
+        let (hero_x, hero_y) = (self.state.hero.x, self.state.hero.y);
+
+        // Enemies in melee contact wear the hero down
+        let contact_range = CELL_SIZE * 0.5;
+        let attackers =
+            crate::aoe::enemies_in_radius(&self.enemy_index, &self.state.enemies, (hero_x, hero_y), contact_range).len();
+        self.state.hero.take_contact_damage(delta, attackers);
+
+        if !self.state.hero.can_attack() {
+            return;
+        }
+
+        // Auto-attack the closest enemy in range
+        let range = self.state.hero.range() * CELL_SIZE;
+        let target = self.state.enemies
+            .values()
+            .map(|enemy| {
+                let dx = enemy.x - hero_x;
+                let dy = enemy.y - hero_y;
+                (enemy.id, enemy.x, enemy.y, (dx * dx + dy * dy).sqrt())
+            })
+            .filter(|(_, _, _, distance)| *distance <= range)
+            .min_by(|a, b| a.3.total_cmp(&b.3));
+
+        if let Some((enemy_id, enemy_x, enemy_y, _)) = target {
+            self.state.hero.attack(enemy_x, enemy_y);
+            self.damage_enemy(enemy_id, self.state.hero.damage());
+            self.muzzle_flashes.push(MuzzleFlash::new(enemy_x, enemy_y, EffectStyle::HeroStrike));
+
+            let killed = self.state.enemies.get(enemy_id).is_some_and(|e| !e.is_alive());
+            if killed {
+                self.state.hero.gain_xp(10);
+            }
+            self.remove_dead_enemies();
+        }
+    }
+
+    fn trigger_trap(&mut self, trap_id: u32, enemy_id: EnemyId) {
+        let Some(trap) = self.state.traps.remove(&trap_id) else {
+            return;
+        };
+        let trap_type = trap.trap_type;
+        let (x, y) = trap.position.to_world();
+        let (center_x, center_y) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+
+        if trap_type.radius() > 0.0 {
+            let radius = trap_type.radius() * CELL_SIZE;
+            let enemies_hit =
+                crate::aoe::enemies_in_radius(&self.enemy_index, &self.state.enemies, (center_x, center_y), radius);
+
+            for id in enemies_hit {
+                self.damage_enemy(id, trap_type.damage());
+            }
+
+            self.explosions.push(ExplosionEffect::new(
+                center_x,
+                center_y,
+                trap_type.radius(),
+                EffectStyle::Trap(trap_type),
+            ));
+        } else if trap_type.damage() > 0 {
+            self.damage_enemy(enemy_id, trap_type.damage());
+        }
+
+        if let Some((duration, multiplier)) = trap_type.slow() {
+            self.apply_effect(enemy_id, StatusEffect::slow(EffectSource::Trap(trap_id), duration, multiplier));
+        }
+
+        self.remove_dead_enemies();
+    }
+
+    fn update_effects(&mut self, delta: f32) {
+        // Update muzzle flashes
+        self.muzzle_flashes.retain_mut(|flash| flash.update(delta));
+
+        // Update explosions
+        self.explosions.retain_mut(|explosion| explosion.update(delta));
+
+        self.floating_texts.retain_mut(|text| text.update(delta));
+
+        if self.leak_flash > 0.0 {
+            self.leak_flash -= delta;
+        }
+    }
+}
+
+/// Enemies within `reach` of the segment a projectile flew this frame, as
+/// (fraction along the segment, id, x, y), nearest the start first
+fn swept_enemies(
+    index: &SpatialHash,
+    enemies: &SlotMap<EnemyId, Enemy>,
+    (from, to): ((f32, f32), (f32, f32)),
+    reach: f32,
+) -> Vec<(f32, EnemyId, f32, f32)> {
+    let mut found: Vec<(f32, EnemyId, f32, f32)> = index
+        .query_segment(from, to, reach)
+        .into_iter()
+        .filter_map(|enemy_id| {
+            let enemy = enemies.get(enemy_id)?;
+            let (t, distance) = segment_distance(from, to, (enemy.x, enemy.y));
+            (distance <= reach).then_some((t, enemy_id, enemy.x, enemy.y))
+        })
+        .collect();
+    found.sort_by(|a, b| a.0.total_cmp(&b.0));
+    found
+}
+
+/// Where a walker heading for `path[next]` should be if the leg there is a
+/// teleporter jump: the exit's center. Walkers step onto the entrance
+/// first, then land on the exit without crossing the cells between.
+pub(crate) fn teleport_exit(grid: &Grid, path: &[Position], next: usize) -> Option<(f32, f32)> {
+    let from = *path.get(next.checked_sub(1)?)?;
+    let to = *path.get(next)?;
+    let (x, y) = to.to_world();
+    (grid.teleporter(&from) == Some(to)).then_some((x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0))
+}
+
+/// Advance every enemy by `delta` at `pace`, returning whether each is still en route,
+/// in slot order
+#[cfg(not(target_arch = "wasm32"))]
+fn tick_enemies(enemies: &mut SlotMap<EnemyId, Enemy>, delta: f32, pace: f32) -> Vec<(EnemyId, bool)> {
+    use rayon::prelude::*;
+
+    let mut refs: Vec<(EnemyId, &mut Enemy)> = enemies.iter_mut().collect();
+    refs.par_iter_mut()
+        .with_min_len(PARALLEL_ENEMY_CHUNK)
+        .map(|(id, enemy)| (*id, enemy.advance(delta, pace)))
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn tick_enemies(enemies: &mut SlotMap<EnemyId, Enemy>, delta: f32, pace: f32) -> Vec<(EnemyId, bool)> {
+    enemies.iter_mut().map(|(id, enemy)| (id, enemy.advance(delta, pace))).collect()
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stat_sheet::StatSheet;
+    use crate::{wrap_angle, FIRING_ARC, GRID_HEIGHT};
+
+    #[test]
+    fn test_large_tower_reserves_and_frees_footprint() {
+        let mut state = GameState::new();
+        state.gold = 1000;
+        let anchor = Position::new(5, 2);
+
+        assert!(state.place_tower(TowerType::Cannon, anchor).is_ok());
+        for cell in TowerType::Cannon.cells_at(anchor) {
+            assert!(!state.grid.is_walkable(&cell));
+        }
+
+        // Overlapping placement is rejected
+        assert_eq!(state.place_tower(TowerType::Basic, Position::new(6, 3)), Err(ActionError::CellBlocked));
+
+        let tower_id = state.tower_at(&Position::new(6, 3)).unwrap().id;
+        assert!(state.sell_tower(tower_id).is_ok());
+        for cell in TowerType::Cannon.cells_at(anchor) {
+            assert!(state.grid.is_walkable(&cell));
+        }
+    }
+
+    #[test]
+    fn test_placement_cannot_block_path() {
+        let mut state = GameState::new();
+        state.gold = 100_000;
+
+        // Wall off column 10 except for rows 6 and 7
+        for y in 0..GRID_HEIGHT {
+            if y != 6 && y != 7 {
+                assert!(state.place_tower(TowerType::Basic, Position::new(10, y)).is_ok());
+            }
+        }
+
+        // A 2x2 tower over the gap would cut spawn from goal
+        let revision = state.grid.revision();
+        assert_eq!(state.place_tower(TowerType::Cannon, Position::new(10, 6)), Err(ActionError::WouldBlockPath));
+        assert!(state.grid.is_walkable(&Position::new(10, 6)));
+        assert!(state.grid.is_walkable(&Position::new(10, 7)));
+        assert_eq!(state.grid.revision(), revision); // Checked without touching the grid
+
+        // The cell's own problems come before the price
+        state.gold = 0;
+        assert_eq!(state.place_tower(TowerType::Basic, Position::new(-1, 3)), Err(ActionError::OutOfBounds));
+        assert_eq!(state.place_tower(TowerType::Basic, Position::new(10, 3)), Err(ActionError::CellBlocked));
+    }
+
+    #[test]
+    fn test_multi_cell_towers_are_square_boards_only() {
+        let mut state = GameState::new();
+        state.grid.set_shape(GridShape::Hex);
+        (state.spawn_point, state.goal_point) = (Position::new(0, 8), Position::new(18, 8));
+
+        assert_eq!(state.place_tower(TowerType::Cannon, Position::new(4, 4)), Err(ActionError::NotAllowed));
+        assert!(state.place_tower(TowerType::Basic, Position::new(4, 4)).is_ok());
+
+        let slots = BuildTool::slots_for(GridShape::Hex);
+        assert!(!slots.contains(&BuildTool::Tower(TowerType::Cannon)));
+        assert_eq!(slots.len(), BuildTool::SLOTS.len() - 1);
+        assert_eq!(BuildTool::slots_for(GridShape::Square), BuildTool::SLOTS);
+    }
+
+    #[test]
+    fn test_walled_off_enemy_heads_as_close_as_it_can() {
+        let mut grid = Grid::new(10, 10);
+        let goal = Position::new(9, 5);
+        let mut enemy = Enemy::new(EnemyId::default(), Position::new(0, 5), goal, &grid).unwrap();
+        for y in 0..10 {
+            grid.set_walkable(&Position::new(5, y), false);
+        }
+        enemy.recalculate_path(&grid, goal);
+        assert!(enemy.is_blocked());
+        assert_eq!(enemy.path.last(), Some(&Position::new(4, 5)));
+
+        grid.set_walkable(&Position::new(5, 0), true);
+        enemy.recalculate_path(&grid, goal);
+        assert!(!enemy.is_blocked());
+        assert_eq!(enemy.path.last(), Some(&goal));
+    }
+
+    #[test]
+    fn test_merge_adjacent_towers() {
+        let mut state = GameState::new();
+        state.gold = 1000;
+        assert!(state.place_tower(TowerType::Slow, Position::new(4, 2)).is_ok());
+        assert!(state.place_tower(TowerType::Splash, Position::new(5, 2)).is_ok());
+        assert!(state.place_tower(TowerType::Basic, Position::new(8, 2)).is_ok());
+        let slow_id = state.tower_at(&Position::new(4, 2)).unwrap().id;
+        let splash_id = state.tower_at(&Position::new(5, 2)).unwrap().id;
+        let basic_id = state.tower_at(&Position::new(8, 2)).unwrap().id;
+
+        // Not adjacent (and no recipe either)
+        assert_eq!(state.merge_towers(basic_id, splash_id), Err(ActionError::NotAdjacent));
+
+        assert!(state.merge_towers(slow_id, splash_id).is_ok());
+        assert_eq!(state.towers[splash_id].tower_type, TowerType::FrostBomb);
+        assert!(!state.towers.contains_key(slow_id));
+        assert!(state.grid.is_walkable(&Position::new(4, 2)));
+    }
+
+    #[test]
+    fn test_abilities_only_during_a_running_wave() {
+        let mut game = Game::new();
+        game.state.gold = 1000;
+        let center = Some((5.0 * CELL_SIZE, 5.0 * CELL_SIZE));
+        assert_eq!(game.cast_ability(AbilityType::MeteorStrike, center), Err(ActionError::NotReady));
+        assert!(game.state.call_next_wave().is_ok());
+        game.state.gold = 1000; // Without the early-call bonus
+        game.state.paused = true;
+        assert_eq!(game.cast_ability(AbilityType::MeteorStrike, center), Err(ActionError::NotReady));
+        assert_eq!(game.state.gold, 1000);
+        game.state.paused = false;
+        assert!(game.cast_ability(AbilityType::MeteorStrike, center).is_ok());
+    }
+
+    #[test]
+    fn test_refused_casts_say_why_and_cost_nothing() {
+        let mut game = Game::new();
+        assert!(game.state.call_next_wave().is_ok());
+        game.state.gold = 1000;
+        assert_eq!(game.cast_ability(AbilityType::MeteorStrike, None), Err(ActionError::NoTarget));
+        assert_eq!(game.cast_ability(AbilityType::EmergencyRepair, None), Err(ActionError::NotDamaged));
+        assert_eq!(game.state.gold, 1000);
+
+        game.state.health -= 3;
+        game.state.gold = 50;
+        let price = game.state.rules.price(AbilityType::EmergencyRepair.cost());
+        let refused = game.cast_ability(AbilityType::EmergencyRepair, None);
+        assert_eq!(refused, Err(ActionError::InsufficientGold { price, gold: 50 }));
+
+        game.state.gold = 1000;
+        assert!(game.cast_ability(AbilityType::EmergencyRepair, None).is_ok());
+        assert_eq!(game.cast_ability(AbilityType::EmergencyRepair, None), Err(ActionError::NotReady));
+    }
+
+    #[test]
+    fn test_lead_target_intercepts_moving_enemy() {
+        // Stationary target: aim straight at it
+        assert_eq!(lead_target((0.0, 0.0), (100.0, 0.0), (0.0, 0.0), 200.0), (100.0, 0.0));
+
+        // Crossing target: shell and enemy arrive at the same time
+        let (ax, ay) = lead_target((0.0, 0.0), (100.0, 0.0), (0.0, 50.0), 200.0);
+        let shot_time = (ax * ax + ay * ay).sqrt() / 200.0;
+        assert!((ay - 50.0 * shot_time).abs() < 0.01);
+        assert_eq!(ax, 100.0);
+
+        // Outrunning target can't be caught; fall back to its position
+        assert_eq!(lead_target((0.0, 0.0), (100.0, 0.0), (300.0, 0.0), 200.0), (100.0, 0.0));
+    }
+
+    #[test]
+    fn test_dumbfire_keeps_its_aim_point() {
+        let cannon = StatSheet::for_tower(TowerType::Cannon);
+        let mut shell =
+            Projectile::new(ProjectileId::default(), TowerType::Cannon, &cannon, (0.0, 0.0), EnemyId::default(), (100.0, 0.0));
+        assert_eq!(shell.guidance, Guidance::Dumbfire);
+        shell.update(0.01, Some((100.0, 80.0)));
+        assert_eq!((shell.target_x, shell.target_y), (100.0, 0.0));
+
+        let basic = StatSheet::for_tower(TowerType::Basic);
+        let mut bolt =
+            Projectile::new(ProjectileId::default(), TowerType::Basic, &basic, (0.0, 0.0), EnemyId::default(), (100.0, 0.0));
+        bolt.update(0.01, Some((100.0, 80.0)));
+        assert_eq!((bolt.target_x, bolt.target_y), (100.0, 80.0));
+    }
+
+    #[test]
+    fn test_piercing_shot_hits_up_to_max_along_its_line() {
+        let mut game = Game::new();
+        for _ in 0..5 {
+            assert!(game.state.spawn_enemy().is_ok());
+        }
+        for (i, enemy) in game.state.enemies.values_mut().enumerate() {
+            enemy.x = 100.0 + i as f32 * 40.0;
+            enemy.y = 300.0;
+        }
+        let first = game.state.enemies.keys().next().unwrap();
+        game.projectiles.insert_with_key(|id| {
+            let stats = StatSheet::for_tower(TowerType::Railgun);
+            Projectile::new(id, TowerType::Railgun, &stats, (0.0, 300.0), first, (100.0, 300.0))
+        });
+
+        // One long step flies the whole line; only the nearest three are hit
+        game.update_projectiles(0.5);
+        assert!(game.projectiles.is_empty());
+        let survivors: Vec<f32> = game.state.enemies.values().map(|enemy| enemy.x).collect();
+        assert_eq!(survivors, vec![220.0, 260.0]);
+    }
+
+    #[test]
+    fn test_shot_strikes_enemy_blocking_its_target() {
+        let mut game = Game::new();
+        assert!(game.state.spawn_enemy().is_ok());
+        assert!(game.state.spawn_enemy().is_ok());
+        let ids: Vec<EnemyId> = game.state.enemies.keys().collect();
+        game.state.enemies[ids[0]].x = 200.0; // Intended target, far away
+        game.state.enemies[ids[1]].x = 100.0; // Standing in the line of fire
+        for id in &ids {
+            game.state.enemies[*id].y = 300.0;
+        }
+        game.projectiles.insert_with_key(|id| {
+            let stats = StatSheet::for_tower(TowerType::Basic);
+            Projectile::new(id, TowerType::Basic, &stats, (0.0, 300.0), ids[0], (200.0, 300.0))
+        });
+
+        game.update_projectiles(0.4);
+        assert!(game.projectiles.is_empty());
+        assert_eq!(game.state.enemies[ids[0]].health, 100);
+        assert!(game.state.enemies[ids[1]].health < 100);
+    }
+
+    #[test]
+    fn test_kills_are_credited_to_the_tower_that_fired() {
+        let mut game = Game::new();
+        assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6)));
+        let tower_id = game.state.towers.keys().next().unwrap();
+        assert!(game.state.spawn_enemy().is_ok());
+        let enemy_id = game.state.enemies.keys().next().unwrap();
+        let enemy = &mut game.state.enemies[enemy_id];
+        (enemy.x, enemy.y, enemy.health) = (100.0, 300.0, 1);
+        game.projectiles.insert_with_key(|id| {
+            let stats = StatSheet::for_tower(TowerType::Basic);
+            let projectile = Projectile::new(id, TowerType::Basic, &stats, (0.0, 300.0), enemy_id, (100.0, 300.0));
+            Projectile { tower_id: Some(tower_id), ..projectile }
+        });
+
+        game.update_projectiles(0.4);
+        assert!(game.state.enemies.is_empty());
+        assert_eq!(game.state.towers[tower_id].kills, 1);
+    }
+
+    #[test]
+    fn test_slow_turret_waits_for_barrel_before_firing() {
+        let mut game = Game::new();
+        game.build(BuildTool::Tower(TowerType::Cannon), Position::new(4, 6));
+        assert!(game.state.spawn_enemy().is_ok());
+        let tower_id = game.state.towers.keys().next().unwrap();
+        let (tower_x, tower_y) = game.state.towers[tower_id].world_position();
+        for enemy in game.state.enemies.values_mut() {
+            enemy.x = tower_x - CELL_SIZE * 2.0; // Directly behind the barrel
+            enemy.y = tower_y;
+            enemy.speed = 0.0;
+        }
+
+        // Half a turn at 1.5 rad/s, less the firing arc, takes about two seconds
+        let mut ticks_to_fire = 0;
+        while game.projectiles.is_empty() {
+            game.update_towers(1.0 / 30.0);
+            ticks_to_fire += 1;
+            assert!(ticks_to_fire < 120);
+        }
+        assert!(ticks_to_fire > 50);
+        assert!(wrap_angle(game.state.towers[tower_id].rotation - std::f32::consts::PI).abs() <= FIRING_ARC);
+    }
+
+    #[test]
+    fn test_enemies_jump_through_teleporters() {
+        let mut game = Game::new();
+        game.state.grid.link_teleporters(Position::new(2, 7), Position::new(17, 7));
+        assert!(game.state.spawn_enemy().is_ok());
+        let id = game.state.enemies.keys().next().unwrap();
+        let mut farthest: f32 = 0.0;
+        for _ in 0..30 {
+            game.update(0.1);
+            farthest = farthest.max(game.state.enemies.get(id).map_or(f32::MAX, |enemy| enemy.x));
+        }
+        assert!(farthest > 17.0 * CELL_SIZE); // 3 seconds at 50 px/s walks about two cells
+    }
+
+    #[test]
+    fn test_sniper_ignores_enemies_inside_min_range() {
+        let mut game = Game::new();
+        assert!(game.state.spawn_enemy().is_ok());
+        let id = game.state.enemies.keys().next().unwrap();
+        let range = (TowerType::Sniper.min_range(), TowerType::Sniper.range());
+        game.state.enemies[id].x = 100.0 + CELL_SIZE; // One cell away
+        game.state.enemies[id].y = 100.0;
+        assert!(game.find_target_where(100.0, 100.0, range, |_| true).is_none());
+
+        game.state.enemies[id].x = 100.0 + CELL_SIZE * 3.0;
+        assert!(game.find_target_where(100.0, 100.0, range, |_| true).is_some());
+    }
+
+    #[test]
+    fn test_damage_falls_off_with_distance() {
+        let stats = StatSheet::for_tower(TowerType::Basic);
+        let bolt = Projectile::new(ProjectileId::default(), TowerType::Basic, &stats, (0.0, 0.0), EnemyId::default(), (1.0, 0.0));
+        let edge = TowerType::Basic.range() * CELL_SIZE;
+        assert_eq!(bolt.damage_at(0.0, 0.0), 10);
+        assert_eq!(bolt.damage_at(edge / 2.0, 0.0), 9);
+        assert_eq!(bolt.damage_at(edge, 0.0), 8);
+
+        let stats = StatSheet::for_tower(TowerType::Sniper);
+        let round = Projectile::new(ProjectileId::default(), TowerType::Sniper, &stats, (0.0, 0.0), EnemyId::default(), (1.0, 0.0));
+        assert_eq!(round.damage_at(edge * 2.0, 0.0), 50);
+    }
+
+    #[test]
+    fn test_parallel_enemy_tick_matches_serial() {
+        let mut game = Game::new();
+        for _ in 0..PARALLEL_ENEMY_CHUNK * 4 {
+            assert!(game.state.spawn_enemy().is_ok());
+        }
+        for (i, enemy) in game.state.enemies.values_mut().enumerate() {
+            enemy.update(i as f32 * 0.01);
+        }
+        let mut expected = game.state.enemies.clone();
+
+        let moved = tick_enemies(&mut game.state.enemies, 0.1, 1.0);
+        for (id, still_moving) in &moved {
+            assert_eq!(expected[*id].update(0.1), *still_moving);
+        }
+        assert!(moved.iter().map(|(id, _)| *id).eq(expected.keys()));
+        for (id, enemy) in &expected {
+            assert_eq!((game.state.enemies[id].x, game.state.enemies[id].y), (enemy.x, enemy.y));
+        }
+    }
+
+    #[test]
+    fn test_stale_enemy_handle_misses_reused_slot() {
+        let mut state = GameState::new();
+        assert!(state.spawn_enemy().is_ok());
+        let old_id = state.enemies.keys().next().unwrap();
+        state.enemies.remove(old_id);
+
+        // The replacement reuses the slot but not the generation
+        assert!(state.spawn_enemy().is_ok());
+        assert_eq!(state.enemies.len(), 1);
+        assert!(state.enemies.get(old_id).is_none());
+    }
+}
@@ -0,0 +1,227 @@
+//! Obstacles that move on a schedule: gates that open and close on a timer,
+//! and blockers that patrol back and forth along a route. Both toggle grid
+//! walkability as they go. Neither will close a cell while something stands
+//! in it or when doing so would cut the spawn off from the goal; they wait
+//! and try again a moment later instead.
+//!
+//! Closing a cell repaths only the enemies whose route runs through it.
+//! Opening one repaths everyone, since any route might now be shorter.
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::actions::ActionError;
+use crate::checksum::StateHasher;
+use crate::{GameState, Position};
+
+const RETRY_DELAY: f32 = 0.5; // Seconds a blocked gate or patrol waits before trying again
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gate {
+    pub position: Position,
+    pub open_time: f32,
+    pub closed_time: f32,
+    pub open: bool,
+    timer: f32, // Until the next toggle
+}
+
+impl Gate {
+    /// A gate that starts open
+    pub fn new(position: Position, open_time: f32, closed_time: f32) -> Self {
+        Gate { position, open_time, closed_time, open: true, timer: open_time }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Patrol {
+    pub route: Vec<Position>,
+    pub step_time: f32,
+    index: usize,
+    forward: bool,
+    timer: f32, // Until the next step
+}
+
+impl Patrol {
+    /// A blocker that starts on the first cell of `route` and walks it
+    /// end to end and back, one cell every `step_time` seconds
+    pub fn new(route: Vec<Position>, step_time: f32) -> Self {
+        Patrol { route, step_time, index: 0, forward: true, timer: step_time }
+    }
+
+    /// The cell the blocker occupies
+    pub fn cell(&self) -> Position {
+        self.route[self.index]
+    }
+
+    fn next_index(&self) -> (usize, bool) {
+        let last = self.route.len().saturating_sub(1);
+        match (self.forward, self.index) {
+            (true, index) if index >= last => (index.saturating_sub(1), false),
+            (true, index) => (index + 1, true),
+            (false, 0) => (1.min(last), true),
+            (false, index) => (index - 1, false),
+        }
+    }
+}
+
+impl GameState {
+    /// Whether a gate or patrol route covers a cell; nothing may be built there
+    pub fn is_gated(&self, cell: &Position) -> bool {
+        self.gates.iter().any(|gate| gate.position == *cell)
+            || self.patrols.iter().any(|patrol| patrol.route.contains(cell))
+    }
+
+    pub fn add_gate(&mut self, gate: Gate) -> Result<(), ActionError> {
+        if !self.grid.in_bounds(&gate.position) {
+            return Err(ActionError::OutOfBounds);
+        }
+        if !self.cell_is_free(gate.position) || self.is_gated(&gate.position) {
+            return Err(ActionError::CellBlocked);
+        }
+        self.gates.push(gate);
+        Ok(())
+    }
+
+    /// Add a patrol, blocking its starting cell straight away
+    pub fn add_patrol(&mut self, patrol: Patrol) -> Result<(), ActionError> {
+        let &start = patrol.route.first().ok_or(ActionError::NoRoute)?;
+        if patrol.route.iter().any(|cell| !self.grid.in_bounds(cell)) {
+            return Err(ActionError::OutOfBounds);
+        }
+        if patrol.route.iter().any(|cell| !self.cell_is_free(*cell) || self.is_gated(cell)) {
+            return Err(ActionError::CellBlocked);
+        }
+        if !self.can_close(start) {
+            let cut_off = self.would_block_path(&[start]);
+            return Err(if cut_off { ActionError::WouldBlockPath } else { ActionError::CellBlocked });
+        }
+        self.grid.set_walkable(&start, false);
+        self.patrols.push(patrol);
+        self.repath_through(&[start]);
+        Ok(())
+    }
+
+    /// Run gate timers and patrols, then repath whoever was affected
+    pub fn update_gates(&mut self, delta: f32) {
+        let (mut closed, mut opened) = (Vec::new(), false);
+
+        let mut gates = std::mem::take(&mut self.gates);
+        for gate in &mut gates {
+            gate.timer -= delta;
+            if gate.timer > 0.0 {
+                continue;
+            }
+            if !gate.open {
+                self.grid.set_walkable(&gate.position, true);
+                (gate.open, gate.timer, opened) = (true, gate.open_time, true);
+            } else if self.can_close(gate.position) {
+                self.grid.set_walkable(&gate.position, false);
+                (gate.open, gate.timer) = (false, gate.closed_time);
+                closed.push(gate.position);
+            } else {
+                debug!(position = ?gate.position, "gate held open");
+                gate.timer = RETRY_DELAY;
+            }
+        }
+        self.gates = gates;
+
+        let mut patrols = std::mem::take(&mut self.patrols);
+        for patrol in &mut patrols {
+            patrol.timer -= delta;
+            if patrol.timer > 0.0 {
+                continue;
+            }
+            let (next, forward) = patrol.next_index();
+            let to = patrol.route[next];
+            if to == patrol.cell() {
+                patrol.timer = patrol.step_time; // Single-cell route: nowhere to go
+            } else if self.can_close(to) {
+                self.grid.set_walkable(&patrol.cell(), true);
+                self.grid.set_walkable(&to, false);
+                (patrol.index, patrol.forward, patrol.timer, opened) = (next, forward, patrol.step_time, true);
+                closed.push(to);
+            } else {
+                patrol.timer = RETRY_DELAY;
+            }
+        }
+        self.patrols = patrols;
+
+        if opened {
+            self.recalculate_paths();
+        } else if !closed.is_empty() {
+            self.repath_through(&closed);
+        }
+    }
+
+    /// A walkable cell can be blocked if nobody stands in it and the spawn
+    /// can still reach the goal afterwards
+    fn can_close(&mut self, cell: Position) -> bool {
+        let occupied = self.enemies.values().any(|enemy| self.grid.cell_at(enemy.x, enemy.y) == cell)
+            || (self.hero.is_alive() && self.hero.cell() == cell);
+        self.grid.is_walkable(&cell) && !occupied && !self.would_block_path(&[cell])
+    }
+}
+
+impl Gate {
+    pub fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.position(self.position);
+        hasher.bool(self.open);
+        hasher.f32(self.timer);
+    }
+}
+
+impl Patrol {
+    pub fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.position(self.cell());
+        hasher.bool(self.forward);
+        hasher.f32(self.timer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType};
+
+    #[test]
+    fn test_gate_cycles_and_only_repaths_enemies_it_blocks() {
+        let mut game = Game::new();
+        let gate = Position::new(5, 7);
+        assert!(game.state.add_gate(Gate::new(gate, 1.0, 1.0)).is_ok());
+        assert!(!game.build(BuildTool::Tower(TowerType::Basic), gate));
+
+        assert!(game.state.spawn_enemy().is_ok());
+        let id = game.state.enemies.keys().next().unwrap();
+        assert!(game.state.enemies[id].route_crosses(gate));
+
+        for _ in 0..11 {
+            game.state.update_gates(0.1);
+        }
+        assert!(!game.state.gates[0].open);
+        assert!(!game.state.grid.is_walkable(&gate));
+        assert!(!game.state.enemies[id].route_crosses(gate));
+
+        for _ in 0..10 {
+            game.state.update_gates(0.1);
+        }
+        assert!(game.state.gates[0].open);
+        assert!(game.state.grid.is_walkable(&gate));
+    }
+
+    #[test]
+    fn test_patrol_walks_its_route_but_never_seals_the_path() {
+        let mut state = GameState::new();
+        for y in 1..state.grid.height() {
+            state.grid.set_walkable(&Position::new(10, y), false);
+        }
+        // The only way through is (9, 0) -> (10, 0); the patrol may not step into it
+        let route = (0..4).rev().map(|y| Position::new(9, y)).collect();
+        assert!(state.add_patrol(Patrol::new(route, 1.0)).is_ok());
+        for _ in 0..50 {
+            state.update_gates(0.1);
+        }
+        assert_eq!(state.patrols[0].cell(), Position::new(9, 1));
+        assert!(state.grid.is_walkable(&Position::new(9, 0)));
+        assert!(state.grid.is_walkable(&Position::new(9, 3)));
+    }
+}
@@ -0,0 +1,425 @@
+//! The board: cell positions, directions, and the grid of walkable cells
+//! with its neighbors, teleporters and one-way tiles.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::CELL_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub fn new(x: i32, y: i32) -> Self {
+        Position { x, y }
+    }
+
+    pub fn to_world(&self) -> (f32, f32) {
+        (self.x as f32 * CELL_SIZE, self.y as f32 * CELL_SIZE)
+    }
+
+    pub fn from_world(x: f32, y: f32) -> Self {
+        Position {
+            x: (x / CELL_SIZE).floor() as i32,
+            y: (y / CELL_SIZE).floor() as i32,
+        }
+    }
+
+    pub fn manhattan_distance(&self, other: &Position) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    pub fn distance(&self, other: &Position) -> f32 {
+        let dx = (self.x - other.x) as f32;
+        let dy = (self.y - other.y) as f32;
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// Axial hex coordinates `(q, r)` of a cell on a hex board, which
+    /// stores positions in doubled-width form (see `GridShape::Hex`)
+    pub fn to_axial(&self) -> (i32, i32) {
+        ((self.x - self.y).div_euclid(2), self.y)
+    }
+
+    pub fn from_axial(q: i32, r: i32) -> Self {
+        Position::new(2 * q + r, r)
+    }
+
+    /// Steps between two hex cells
+    pub fn hex_distance(&self, other: &Position) -> i32 {
+        let ((q1, r1), (q2, r2)) = (self.to_axial(), other.to_axial());
+        let (dq, dr) = (q1 - q2, r1 - r2);
+        (dq.abs() + dr.abs() + (dq + dr).abs()) / 2
+    }
+
+    pub fn neighbors(&self) -> Vec<Position> {
+        vec![
+            Position::new(self.x + 1, self.y),
+            Position::new(self.x - 1, self.y),
+            Position::new(self.x, self.y + 1),
+            Position::new(self.x, self.y - 1),
+        ]
+    }
+
+    /// The adjacent cell in a direction
+    pub fn step(&self, direction: Direction) -> Position {
+        let (dx, dy) = direction.offset();
+        Position::new(self.x + dx, self.y + dy)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    /// Grid offset of one step, with y growing downward
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+}
+
+/// How cells tile the board, chosen per map.
+///
+/// Hex boards keep the square grid's world coordinates: each hex is two
+/// cells wide and one row tall, and positions use doubled-width coordinates,
+/// so only cells whose `x + y` is even exist and a hex's center is the
+/// center of its square cell. Hexes come out flattened, as if the board were
+/// seen at an angle, but everything that converts cells to world space works
+/// unchanged. `Position::to_axial` gives the usual axial coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GridShape {
+    #[default]
+    Square,
+    Hex,
+}
+
+/// Doubled-width offsets of the six hex neighbors, clockwise from the right
+const HEX_NEIGHBORS: [(i32, i32); 6] = [(2, 0), (1, 1), (-1, 1), (-2, 0), (-1, -1), (1, -1)];
+
+/// Serde for maps keyed by cell, as a list of pairs: JSON only allows
+/// string keys, and the sealed save text is JSON
+mod cell_map {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<(K, V)>::deserialize(deserializer).map(|pairs| pairs.into_iter().collect())
+    }
+}
+
+/// Walls are kept one bit per cell, row by row (`y * width + x`), so the
+/// pathfinders' walkability checks are a shift and a mask. Saves write them
+/// as rows of hex digits (see `SavedGrid`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "SavedGrid", into = "SavedGrid")]
+pub struct Grid {
+    width: i32,
+    height: i32,
+    shape: GridShape,
+    walls: Vec<u64>, // Set bits are blocked cells
+    teleporters: HashMap<Position, Position>, // Entrance -> exit, stored both ways for a pair
+    one_way: HashMap<Position, Direction>, // Tiles that may only be crossed in one direction
+    pub(crate) revision: u64, // Bumped on every change, so caches know when to refresh
+    pub(crate) hierarchy: crate::hpa::HierarchyCache, // Routing clusters for big boards
+}
+
+const GRID_VERSION: u32 = 1; // Layout of the walls in a `SavedGrid`
+
+/// `Grid` as saved. Since version 1 the walls are `walls`: a string per
+/// row, a hex digit per four cells with the leftmost in the lowest bit, and
+/// trailing zeros dropped. Version 0 saves, from before there was a
+/// version, list the cells in `walkable` instead. New fields go at the end,
+/// as binary saves store them by position.
+#[derive(Serialize, Deserialize)]
+struct SavedGrid {
+    width: i32,
+    height: i32,
+    #[serde(default)]
+    shape: GridShape,
+    #[serde(default, with = "cell_map")]
+    walkable: HashMap<Position, bool>, // Version 0 only
+    #[serde(default, with = "cell_map")]
+    teleporters: HashMap<Position, Position>,
+    #[serde(default, with = "cell_map")]
+    one_way: HashMap<Position, Direction>,
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    walls: Vec<String>,
+}
+
+impl TryFrom<SavedGrid> for Grid {
+    type Error = String;
+
+    fn try_from(saved: SavedGrid) -> Result<Self, String> {
+        let mut grid = Grid::new(saved.width, saved.height);
+        grid.shape = saved.shape;
+        match saved.version {
+            0 => {
+                for (pos, walkable) in &saved.walkable {
+                    grid.set_walkable(pos, *walkable);
+                }
+            }
+            GRID_VERSION => {
+                if saved.walls.len() != grid.height.max(0) as usize {
+                    return Err(format!("{} wall rows on a board {} tall", saved.walls.len(), grid.height));
+                }
+                for (y, row) in saved.walls.iter().enumerate() {
+                    for (i, digit) in row.chars().enumerate() {
+                        let bits = digit.to_digit(16).ok_or_else(|| format!("{:?} in a wall row", digit))?;
+                        for bit in (0..4).filter(|bit| bits & (1 << bit) != 0) {
+                            grid.set_walkable(&Position::new(4 * i as i32 + bit, y as i32), false);
+                        }
+                    }
+                }
+            }
+            version => return Err(format!("grid version {} is newer than this build", version)),
+        }
+        grid.teleporters = saved.teleporters;
+        grid.one_way = saved.one_way;
+        grid.revision = 0;
+        Ok(grid)
+    }
+}
+
+impl From<Grid> for SavedGrid {
+    fn from(grid: Grid) -> Self {
+        let row = |y: i32| -> String {
+            let digits = (0..grid.width).step_by(4).map(|x| {
+                let walls = (0..4).filter(|bit| grid.is_wall(&Position::new(x + bit, y)));
+                char::from_digit(walls.fold(0, |bits, bit| bits | 1 << bit), 16).unwrap_or('0')
+            });
+            digits.collect::<String>().trim_end_matches('0').to_string()
+        };
+        let walls = (0..grid.height).map(row).collect();
+        SavedGrid {
+            width: grid.width,
+            height: grid.height,
+            shape: grid.shape,
+            walkable: HashMap::new(),
+            teleporters: grid.teleporters,
+            one_way: grid.one_way,
+            version: GRID_VERSION,
+            walls,
+        }
+    }
+}
+
+impl Grid {
+    pub fn new(width: i32, height: i32) -> Self {
+        Grid {
+            width,
+            height,
+            shape: GridShape::Square,
+            walls: vec![0; (width.max(0) as usize * height.max(0) as usize).div_ceil(64)],
+            teleporters: HashMap::new(),
+            one_way: HashMap::new(),
+            revision: 0,
+            hierarchy: crate::hpa::HierarchyCache::default(),
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn shape(&self) -> GridShape {
+        self.shape
+    }
+
+    pub fn set_shape(&mut self, shape: GridShape) {
+        if self.shape != shape {
+            self.shape = shape;
+            self.revision += 1;
+            self.hierarchy.clear();
+        }
+    }
+
+    /// Whether the cell is on the board; on hex boards, also whether it's a
+    /// hex at all
+    pub fn in_bounds(&self, pos: &Position) -> bool {
+        let on_board = pos.x >= 0 && pos.x < self.width && pos.y >= 0 && pos.y < self.height;
+        on_board && (self.shape == GridShape::Square || (pos.x + pos.y) % 2 == 0)
+    }
+
+    /// Cells sharing an edge with `pos`: four on square boards, six on hex
+    pub fn neighbors(&self, pos: Position) -> Vec<Position> {
+        match self.shape {
+            GridShape::Square => pos.neighbors(),
+            GridShape::Hex => HEX_NEIGHBORS.iter().map(|(dx, dy)| Position::new(pos.x + dx, pos.y + dy)).collect(),
+        }
+    }
+
+    /// Fewest steps between two cells, ignoring walls
+    pub fn distance(&self, a: &Position, b: &Position) -> i32 {
+        match self.shape {
+            GridShape::Square => a.manhattan_distance(b),
+            GridShape::Hex => a.hex_distance(b),
+        }
+    }
+
+    /// The cell under a world point. On hex boards that's the nearest hex
+    /// center, measured with rows stretched back to a regular hex's height.
+    pub fn cell_at(&self, x: f32, y: f32) -> Position {
+        let square = Position::from_world(x, y);
+        if self.shape == GridShape::Square {
+            return square;
+        }
+        let stretch = 3f32.sqrt();
+        let distance = |cell: &Position| {
+            let (cx, cy) = cell.to_world();
+            let (dx, dy) = (cx + CELL_SIZE / 2.0 - x, (cy + CELL_SIZE / 2.0 - y) * stretch);
+            dx * dx + dy * dy
+        };
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| Position::new(square.x + dx, square.y + dy)))
+            .filter(|cell| (cell.x + cell.y).rem_euclid(2) == 0)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .unwrap_or(square)
+    }
+
+    /// Bit index of a cell on the board's rectangle
+    fn index(&self, pos: &Position) -> Option<usize> {
+        let on_board = pos.x >= 0 && pos.x < self.width && pos.y >= 0 && pos.y < self.height;
+        on_board.then(|| (pos.y * self.width + pos.x) as usize)
+    }
+
+    fn is_wall(&self, pos: &Position) -> bool {
+        self.index(pos).is_some_and(|i| self.walls[i / 64] & (1 << (i % 64)) != 0)
+    }
+
+    pub fn is_walkable(&self, pos: &Position) -> bool {
+        self.in_bounds(pos) && !self.is_wall(pos)
+    }
+
+    /// Off-board cells are always blocked; setting them does nothing
+    pub fn set_walkable(&mut self, pos: &Position, walkable: bool) {
+        let Some(i) = self.index(pos) else {
+            return;
+        };
+        if self.is_wall(pos) == walkable {
+            self.walls[i / 64] ^= 1 << (i % 64);
+            self.revision += 1;
+            self.hierarchy.invalidate_around(pos);
+        }
+    }
+
+    /// Link two cells so walkers entering either come out of the other
+    pub fn link_teleporters(&mut self, a: Position, b: Position) {
+        self.teleporters.insert(a, b);
+        self.teleporters.insert(b, a);
+        self.revision += 1;
+        self.hierarchy.clear();
+    }
+
+    pub fn teleporter(&self, pos: &Position) -> Option<Position> {
+        self.teleporters.get(pos).copied()
+    }
+
+    /// Every teleporter entrance with its exit
+    pub fn teleporters(&self) -> impl Iterator<Item = (Position, Position)> + '_ {
+        self.teleporters.iter().map(|(from, to)| (*from, *to))
+    }
+
+    /// Make a tile one-way, or two-way again with `None`
+    pub fn set_one_way(&mut self, pos: &Position, direction: Option<Direction>) {
+        let before = match direction {
+            Some(direction) => self.one_way.insert(*pos, direction),
+            None => self.one_way.remove(pos),
+        };
+        if before != direction {
+            self.revision += 1;
+            self.hierarchy.invalidate_around(pos);
+        }
+    }
+
+    pub fn one_way(&self, pos: &Position) -> Option<Direction> {
+        self.one_way.get(pos).copied()
+    }
+
+    /// Whether a walker may step between two adjacent cells. A one-way tile
+    /// can only be left in its direction, and never entered from the cell it
+    /// points at.
+    pub fn can_step(&self, from: Position, to: Position) -> bool {
+        self.is_walkable(&to)
+            && self.one_way(&from).is_none_or(|direction| from.step(direction) == to)
+            && self.one_way(&to).is_none_or(|direction| to.step(direction) != from)
+    }
+
+    /// Cells reachable in one move: walkable neighbors the one-way rules
+    /// allow, plus the far end of a teleporter
+    pub fn exits(&self, from: Position) -> Vec<Position> {
+        let mut exits: Vec<Position> = self.neighbors(from).into_iter().filter(|to| self.can_step(from, *to)).collect();
+        exits.extend(self.teleporter(&from).filter(|to| self.is_walkable(to)));
+        exits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walls_survive_a_save_in_either_layout() {
+        let mut grid = Grid::new(70, 3); // Rows straddle the 64-cell words
+        grid.set_walkable(&Position::new(69, 0), false);
+        grid.set_walkable(&Position::new(0, 1), false);
+        grid.set_walkable(&Position::new(70, 1), false); // Off the board
+        assert!(!grid.is_walkable(&Position::new(69, 0)) && !grid.is_walkable(&Position::new(0, 1)));
+        assert!(grid.is_walkable(&Position::new(1, 1)));
+
+        let saved = SavedGrid::from(grid.clone());
+        assert_eq!(saved.walls, vec![format!("{}2", "0".repeat(17)), "1".to_string(), String::new()]);
+        let loaded: Grid = rmp_serde::from_slice(&rmp_serde::to_vec(&grid).unwrap()).unwrap();
+        assert_eq!(loaded.walls, grid.walls);
+
+        // Version 0 listed cells, open ones too, and had no version or walls fields
+        let walkable = vec![(Position::new(2, 2), true), (Position::new(3, 2), false)];
+        let none: Vec<(Position, Position)> = Vec::new();
+        let before = (5, 5, GridShape::Square, walkable, none, Vec::<(Position, Direction)>::new());
+        let old: Grid = rmp_serde::from_slice(&rmp_serde::to_vec(&before).unwrap()).unwrap();
+        assert!(old.is_walkable(&Position::new(2, 2)) && !old.is_walkable(&Position::new(3, 2)));
+
+        let garbled = SavedGrid { walls: vec!["0g".to_string()], ..SavedGrid::from(Grid::new(5, 1)) };
+        assert!(rmp_serde::from_slice::<Grid>(&rmp_serde::to_vec(&garbled).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Tower groups: the towers inside a box dragged on the grid, for copying
+//! elsewhere and for selling or merging all at once. A copy keeps each
+//! tower's type and corner cell counted from the group's top-left, and can
+//! be pasted mirrored left to right and/or top to bottom. Unlike a preset
+//! (see `presets`), a paste isn't all or nothing: every tower goes through
+//! `Game::try_build` on its own, paying for itself, and the ones that don't fit
+//! are left out.
+
+use crate::actions::ActionError;
+use crate::presets::PresetTower;
+use crate::{BuildTool, Game, GameState, Position, TowerId};
+
+/// Towers with any cell in the box between two corner cells
+pub fn towers_in(state: &GameState, a: Position, b: Position) -> Vec<TowerId> {
+    let (min, max) = (Position::new(a.x.min(b.x), a.y.min(b.y)), Position::new(a.x.max(b.x), a.y.max(b.y)));
+    let inside = |cell: &Position| (min.x..=max.x).contains(&cell.x) && (min.y..=max.y).contains(&cell.y);
+    state.towers.values().filter(|tower| tower.cells().iter().any(inside)).map(|tower| tower.id).collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clipboard {
+    pub towers: Vec<PresetTower>, // Counted from the group's top-left
+}
+
+/// How a paste went
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Pasted {
+    pub placed: usize,
+    pub refused: Vec<(Position, ActionError)>, // Corner cell of each tower left out, and why
+}
+
+impl Clipboard {
+    /// The towers in `ids` that still stand, or None if none do
+    pub fn copy(state: &GameState, ids: &[TowerId]) -> Option<Self> {
+        let towers: Vec<_> = ids.iter().filter_map(|id| state.towers.get(*id)).collect();
+        let left = towers.iter().map(|tower| tower.position.x).min()?;
+        let top = towers.iter().map(|tower| tower.position.y).min()?;
+        let mut towers: Vec<PresetTower> = towers
+            .iter()
+            .map(|tower| PresetTower {
+                tower_type: tower.tower_type,
+                dx: tower.position.x - left,
+                dy: tower.position.y - top,
+            })
+            .collect();
+        towers.sort_by_key(|tower| (tower.dy, tower.dx));
+        Some(Clipboard { towers })
+    }
+
+    /// Cells across and down, counting footprints
+    pub fn size(&self) -> (i32, i32) {
+        let extent = |offset: fn(&PresetTower) -> i32| {
+            self.towers.iter().map(|tower| offset(tower) + tower.tower_type.footprint()).max().unwrap_or(0)
+        };
+        (extent(|tower| tower.dx), extent(|tower| tower.dy))
+    }
+
+    /// Flipped within its own box, so it still starts at the top-left
+    pub fn mirrored(&self, left_right: bool, up_down: bool) -> Self {
+        let (width, height) = self.size();
+        let mut towers: Vec<PresetTower> = self
+            .towers
+            .iter()
+            .map(|tower| {
+                let size = tower.tower_type.footprint();
+                PresetTower {
+                    dx: if left_right { width - size - tower.dx } else { tower.dx },
+                    dy: if up_down { height - size - tower.dy } else { tower.dy },
+                    ..*tower
+                }
+            })
+            .collect();
+        towers.sort_by_key(|tower| (tower.dy, tower.dx));
+        Clipboard { towers }
+    }
+
+    /// Where each tower would go with the top-left at `origin`
+    pub fn placements(&self, origin: Position) -> impl Iterator<Item = (PresetTower, Position)> + '_ {
+        self.towers.iter().map(move |tower| (*tower, Position::new(origin.x + tower.dx, origin.y + tower.dy)))
+    }
+}
+
+impl Game {
+    /// Build a copy with its top-left at `origin`, each tower checked and
+    /// paid for as it goes down
+    pub fn paste(&mut self, clipboard: &Clipboard, origin: Position) -> Pasted {
+        let mut pasted = Pasted::default();
+        for (tower, position) in clipboard.placements(origin) {
+            match self.try_build(BuildTool::Tower(tower.tower_type), position) {
+                Ok(()) => pasted.placed += 1,
+                Err(err) => pasted.refused.push((position, err)),
+            }
+        }
+        pasted
+    }
+
+    /// Sell every tower in the group. Returns how many sold.
+    pub fn sell_group(&mut self, ids: &[TowerId]) -> usize {
+        ids.iter().filter(|id| self.sell_tower(**id).is_ok()).count()
+    }
+
+    /// Merging is the only upgrade, so upgrading a group merges every
+    /// adjacent pair in it that has a recipe, until none is left. Returns
+    /// how many merges were made.
+    pub fn merge_group(&mut self, ids: &[TowerId]) -> usize {
+        let mut group = ids.to_vec();
+        let mut merges = 0;
+        while let Some(index) = group.iter().enumerate().find_map(|(index, source)| {
+            group.iter().any(|target| self.state.merge_towers(*source, *target).is_ok()).then_some(index)
+        }) {
+            group.remove(index); // The source is gone; the target keeps its id as the hybrid
+            merges += 1;
+        }
+        merges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TowerType;
+
+    #[test]
+    fn test_group_copies_mirror_and_paste_per_tower() {
+        let mut game = Game::new();
+        game.state.gold = 10_000;
+        assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 2)));
+        assert!(game.build(BuildTool::Tower(TowerType::Cannon), Position::new(5, 2)));
+        let ids = towers_in(&game.state, Position::new(6, 3), Position::new(4, 1));
+        assert_eq!(ids.len(), 2);
+
+        let clipboard = Clipboard::copy(&game.state, &ids).unwrap();
+        assert_eq!(clipboard.size(), (3, 2));
+        let mirrored = clipboard.mirrored(true, false);
+        let cannon = mirrored.towers.iter().find(|tower| tower.tower_type == TowerType::Cannon).unwrap();
+        assert_eq!((cannon.dx, cannon.dy), (0, 0));
+        assert_eq!(mirrored.mirrored(true, false), clipboard);
+
+        // Each tower pays for itself, and one that doesn't fit is left out
+        let gold = game.state.gold;
+        assert_eq!(game.paste(&mirrored, Position::new(4, 6)), Pasted { placed: 2, refused: Vec::new() });
+        assert_eq!(gold - game.state.gold, cost_of(&game, &mirrored));
+        assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(6, 9)));
+        let refused = vec![(Position::new(5, 8), ActionError::CellBlocked)];
+        assert_eq!(game.paste(&clipboard, Position::new(4, 8)), Pasted { placed: 1, refused });
+    }
+
+    fn cost_of(game: &Game, clipboard: &Clipboard) -> i32 {
+        clipboard.towers.iter().map(|tower| game.state.rules.price(tower.tower_type.cost())).sum()
+    }
+
+    #[test]
+    fn test_group_sell_and_merge() {
+        let mut game = Game::new();
+        game.state.gold = 10_000;
+        for x in 4..8 {
+            assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(x, 2)));
+        }
+        let ids = towers_in(&game.state, Position::new(4, 2), Position::new(7, 2));
+        assert_eq!(game.merge_group(&ids), 2);
+        assert!(game.state.towers.values().all(|tower| tower.tower_type == TowerType::Gatling));
+
+        let ids = towers_in(&game.state, Position::new(0, 0), Position::new(20, 20));
+        assert_eq!(game.sell_group(&ids), 2);
+        assert!(game.state.towers.is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+//! Enemies that shoot back. From `GUNNER_FIRST_WAVE` on, every
+//! `GUNNER_EVERY`th enemy of a wave is a gunner: frailer than a grunt, but
+//! it fires at the nearest tower in range as it walks. Its shots fly like
+//! tower projectiles in reverse and wear down the tower's health (see
+//! `siege`); a tower that runs out falls and frees its cells. The player can
+//! pay to patch a damaged tower back to full.
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::checksum::StateHasher;
+use crate::actions::ActionError;
+use crate::{Enemy, Game, GameState, Tower, TowerId, CELL_SIZE};
+
+pub const GUNNER_FIRST_WAVE: u32 = 3;
+pub const GUNNER_EVERY: u32 = 4;
+const GUNNER_HEALTH_PERCENT: i32 = 70;
+const GUNNER_RANGE: f32 = 2.5; // Cells
+const GUNNER_FIRE_INTERVAL: f32 = 1.5;
+const GUNNER_DAMAGE: i32 = 8;
+const SHOT_SPEED: f32 = 220.0;
+const REPAIR_COST_PERCENT: i32 = 50; // Of the tower's price, for a tower at zero health
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum EnemyKind {
+    #[default]
+    Grunt,
+    Gunner,
+}
+
+impl EnemyKind {
+    pub const ALL: [EnemyKind; 2] = [EnemyKind::Grunt, EnemyKind::Gunner];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyKind::Grunt => "Grunt",
+            EnemyKind::Gunner => "Gunner",
+        }
+    }
+
+    /// The kind of a wave's enemy, given how many are left to spawn after it
+    pub fn in_wave(wave: u32, remaining_to_spawn: u32) -> EnemyKind {
+        if wave >= GUNNER_FIRST_WAVE && remaining_to_spawn.is_multiple_of(GUNNER_EVERY) {
+            EnemyKind::Gunner
+        } else {
+            EnemyKind::Grunt
+        }
+    }
+
+    /// How far it shoots at towers, in cells; zero if it doesn't
+    pub fn range(&self) -> f32 {
+        match self {
+            EnemyKind::Grunt => 0.0,
+            EnemyKind::Gunner => GUNNER_RANGE,
+        }
+    }
+}
+
+impl Enemy {
+    /// Make this enemy a `kind`, scaling its health to match
+    pub fn set_kind(&mut self, kind: EnemyKind) {
+        self.kind = kind;
+        if kind == EnemyKind::Gunner {
+            self.max_health = (self.max_health * GUNNER_HEALTH_PERCENT / 100).max(1);
+            self.health = self.max_health;
+        }
+    }
+}
+
+/// A gunner's shot in flight, homing on a tower
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnemyShot {
+    pub x: f32,
+    pub y: f32,
+    pub target: TowerId,
+    pub damage: i32,
+}
+
+impl EnemyShot {
+    /// Fly toward (x, y). True once there.
+    fn advance(&mut self, delta: f32, (to_x, to_y): (f32, f32)) -> bool {
+        let (dx, dy) = (to_x - self.x, to_y - self.y);
+        let distance = (dx * dx + dy * dy).sqrt();
+        let step = SHOT_SPEED * delta;
+        if step >= distance {
+            (self.x, self.y) = (to_x, to_y);
+            return true;
+        }
+        self.x += dx / distance * step;
+        self.y += dy / distance * step;
+        false
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.f32(self.x);
+        hasher.f32(self.y);
+        hasher.key(self.target);
+        hasher.i32(self.damage);
+    }
+}
+
+impl Tower {
+    pub fn is_damaged(&self) -> bool {
+        self.damage_taken > 0
+    }
+
+    /// Gold to bring it back to full health, in proportion to the damage
+    pub fn repair_cost(&self) -> i32 {
+        let full = self.tower_type.cost() * REPAIR_COST_PERCENT;
+        (full * self.damage_taken + 100 * self.max_health() - 1) / (100 * self.max_health())
+    }
+}
+
+impl GameState {
+    /// Pay to restore a damaged tower to full health
+    pub fn repair_tower(&mut self, tower_id: TowerId) -> Result<(), ActionError> {
+        let tower = self.towers.get(tower_id).ok_or(ActionError::NoSuchTower)?;
+        if !tower.is_damaged() {
+            return Err(ActionError::NotDamaged);
+        }
+        let price = self.rules.price(tower.repair_cost());
+        if self.gold < price {
+            debug!(?tower_id, gold = self.gold, price, "repair rejected: not enough gold");
+            return Err(ActionError::InsufficientGold { price, gold: self.gold });
+        }
+        self.gold -= price;
+        self.towers[tower_id].damage_taken = 0;
+        info!(?tower_id, price, gold = self.gold, "tower repaired");
+        Ok(())
+    }
+}
+
+impl Game {
+    /// Gunners fire at the nearest tower in range; shots in flight home in
+    /// and damage their tower on arrival
+    pub(crate) fn update_enemy_fire(&mut self, delta: f32) {
+        let towers = &self.state.towers;
+        for enemy in self.state.enemies.values_mut().filter(|enemy| enemy.kind.range() > 0.0) {
+            enemy.fire_cooldown = (enemy.fire_cooldown - delta).max(0.0);
+            if enemy.fire_cooldown > 0.0 || !enemy.is_alive() {
+                continue;
+            }
+            let reach = enemy.kind.range() * CELL_SIZE;
+            let distance = |tower: &Tower| {
+                let (x, y) = tower.world_position();
+                ((x - enemy.x).powi(2) + (y - enemy.y).powi(2)).sqrt()
+            };
+            let nearest = towers
+                .values()
+                .map(|tower| (tower.id, distance(tower)))
+                .filter(|(_, distance)| *distance <= reach)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((target, _)) = nearest {
+                self.enemy_shots.push(EnemyShot { x: enemy.x, y: enemy.y, target, damage: GUNNER_DAMAGE });
+                enemy.fire_cooldown = GUNNER_FIRE_INTERVAL;
+            }
+        }
+
+        let mut hits = Vec::new();
+        let towers = &self.state.towers;
+        self.enemy_shots.retain_mut(|shot| {
+            let Some(tower) = towers.get(shot.target) else {
+                return false; // Sold or already fallen
+            };
+            let arrived = shot.advance(delta, tower.world_position());
+            if arrived {
+                hits.push((shot.target, shot.damage));
+            }
+            !arrived
+        });
+        for (tower_id, damage) in hits {
+            self.damage_tower(tower_id, damage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Position, TowerType};
+
+    #[test]
+    fn test_wave_mix() {
+        let size = crate::waves::WaveManager::wave_size(GUNNER_FIRST_WAVE);
+        let gunners = (0..size).filter(|left| EnemyKind::in_wave(GUNNER_FIRST_WAVE, *left) == EnemyKind::Gunner).count();
+        assert_eq!(gunners as u32, size.div_ceil(GUNNER_EVERY));
+        assert!((0..20).all(|left| EnemyKind::in_wave(GUNNER_FIRST_WAVE - 1, left) == EnemyKind::Grunt));
+    }
+
+    #[test]
+    fn test_gunner_wears_down_a_tower_until_repaired() {
+        let mut game = Game::new();
+        game.state.gold = 1000;
+        assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6)));
+        let tower_id = game.state.tower_at(&Position::new(4, 6)).unwrap().id;
+        game.state.towers[tower_id].disabled = true;
+        let gunner = game.state.spawn_enemy_with(|enemy| enemy.set_kind(EnemyKind::Gunner)).unwrap();
+        assert_eq!(game.state.enemies[gunner].max_health, 70);
+
+        for _ in 0..150 {
+            game.update(1.0 / 30.0);
+        }
+        let tower = &game.state.towers[tower_id];
+        assert!(tower.is_damaged() && tower.health() < tower.max_health());
+
+        let (gold, cost) = (game.state.gold, tower.repair_cost());
+        assert!(cost > 0 && cost <= TowerType::Basic.cost() / 2);
+        assert!(game.state.repair_tower(tower_id).is_ok());
+        assert_eq!(game.state.gold, gold - cost);
+        assert!(!game.state.towers[tower_id].is_damaged());
+        assert_eq!(game.state.repair_tower(tower_id), Err(ActionError::NotDamaged));
+    }
+
+    #[test]
+    fn test_a_tower_out_of_health_falls() {
+        let mut game = Game::new();
+        game.state.gold = 1000;
+        assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6)));
+        let tower_id = game.state.tower_at(&Position::new(4, 6)).unwrap().id;
+        let max_health = game.state.towers[tower_id].max_health();
+        game.damage_tower(tower_id, max_health - 1);
+        assert!(game.state.towers.contains_key(tower_id));
+        game.damage_tower(tower_id, 1);
+        assert!(!game.state.towers.contains_key(tower_id));
+        assert!(game.state.grid.is_walkable(&Position::new(4, 6)));
+    }
+}
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+
+use crate::actions::ActionError;
+use crate::checksum::StateHasher;
+use crate::pathfinding::find_waypoints;
+use crate::{Grid, Position, CELL_SIZE};
+
+const HERO_SPEED: f32 = 80.0;
+const HERO_BASE_HEALTH: i32 = 200;
+const HERO_RESPAWN_TIME: f32 = 8.0;
+const CONTACT_DPS: f32 = 10.0; // Damage per second from each enemy touching the hero
+
+/// Player-controlled hero. Walks to clicked cells using the same A* as enemies,
+/// auto-attacks enemies in range, and respawns at home after dying.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hero {
+    pub x: f32,
+    pub y: f32,
+    pub home: Position,
+    pub path: Vec<Position>,
+    pub current_waypoint: usize,
+    pub level: u32,
+    pub xp: u32,
+    pub health: i32,
+    pub max_health: i32,
+    #[serde(default)]
+    pub attack_cooldown: f32,
+    #[serde(default)]
+    pub respawn_timer: f32,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    contact_damage: f32, // Fractional contact damage not yet applied
+}
+
+impl Hero {
+    pub fn new(home: Position) -> Self {
+        let (x, y) = home.to_world();
+        Hero {
+            x: x + CELL_SIZE / 2.0,
+            y: y + CELL_SIZE / 2.0,
+            home,
+            path: Vec::new(),
+            current_waypoint: 0,
+            level: 1,
+            xp: 0,
+            health: HERO_BASE_HEALTH,
+            max_health: HERO_BASE_HEALTH,
+            attack_cooldown: 0.0,
+            respawn_timer: 0.0,
+            rotation: 0.0,
+            contact_damage: 0.0,
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    pub fn cell(&self) -> Position {
+        Position::from_world(self.x, self.y)
+    }
+
+    /// Attack range in cells
+    pub fn range(&self) -> f32 {
+        2.0
+    }
+
+    pub fn damage(&self) -> i32 {
+        12 + 4 * self.level as i32
+    }
+
+    pub fn attack_rate(&self) -> f32 {
+        1.5
+    }
+
+    pub fn xp_to_next_level(&self) -> u32 {
+        50 * self.level
+    }
+
+    /// Order the hero to walk to a cell. Refused while the hero is down,
+    /// or if there is no route.
+    pub fn move_to(&mut self, grid: &Grid, goal: Position) -> Result<(), ActionError> {
+        if !self.is_alive() {
+            return Err(ActionError::NotReady);
+        }
+        if !grid.in_bounds(&goal) {
+            return Err(ActionError::OutOfBounds);
+        }
+        self.path = find_waypoints(grid, self.cell(), goal).ok_or(ActionError::NoRoute)?;
+        self.current_waypoint = 0;
+        Ok(())
+    }
+
+    /// Re-route toward the current destination after the grid changed
+    pub fn recalculate_path(&mut self, grid: &Grid) {
+        if let Some(&destination) = self.path.last() {
+            if self.current_waypoint < self.path.len() && self.move_to(grid, destination).is_err() {
+                self.path.clear();
+            }
+        }
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        if !self.is_alive() {
+            self.respawn_timer -= delta;
+            if self.respawn_timer <= 0.0 {
+                self.respawn();
+            }
+            return;
+        }
+
+        if self.attack_cooldown > 0.0 {
+            self.attack_cooldown -= delta;
+        }
+
+        if self.current_waypoint >= self.path.len() {
+            return;
+        }
+
+        let (target_x, target_y) = self.path[self.current_waypoint].to_world();
+        let dx = target_x + CELL_SIZE / 2.0 - self.x;
+        let dy = target_y + CELL_SIZE / 2.0 - self.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+
+        let move_distance = HERO_SPEED * delta;
+        if distance <= move_distance {
+            self.x += dx;
+            self.y += dy;
+            self.current_waypoint += 1;
+            return;
+        }
+
+        self.x += dx / distance * move_distance;
+        self.y += dy / distance * move_distance;
+    }
+
+    pub fn can_attack(&self) -> bool {
+        self.is_alive() && self.attack_cooldown <= 0.0
+    }
+
+    pub fn attack(&mut self, target_x: f32, target_y: f32) {
+        self.attack_cooldown = 1.0 / self.attack_rate();
+        self.rotation = (target_y - self.y).atan2(target_x - self.x);
+    }
+
+    /// Grant experience, leveling up (and fully healing) when the bar fills
+    pub fn gain_xp(&mut self, amount: u32) {
+        self.xp += amount;
+        while self.xp >= self.xp_to_next_level() {
+            self.xp -= self.xp_to_next_level();
+            self.level += 1;
+            self.max_health += 25;
+            self.health = self.max_health;
+        }
+    }
+
+    pub fn take_damage(&mut self, damage: i32) {
+        if !self.is_alive() {
+            return;
+        }
+        self.health = (self.health - damage).max(0);
+        if self.health == 0 {
+            self.respawn_timer = HERO_RESPAWN_TIME;
+            self.path.clear();
+            self.current_waypoint = 0;
+        }
+    }
+
+    /// Apply melee damage from enemies standing on top of the hero
+    pub fn take_contact_damage(&mut self, delta: f32, attackers: usize) {
+        if attackers == 0 {
+            return;
+        }
+        self.contact_damage += CONTACT_DPS * attackers as f32 * delta;
+        let whole = self.contact_damage.floor();
+        if whole >= 1.0 {
+            self.contact_damage -= whole;
+            self.take_damage(whole as i32);
+        }
+    }
+
+    fn respawn(&mut self) {
+        let (x, y) = self.home.to_world();
+        self.x = x + CELL_SIZE / 2.0;
+        self.y = y + CELL_SIZE / 2.0;
+        self.health = self.max_health;
+        self.respawn_timer = 0.0;
+        self.attack_cooldown = 0.0;
+    }
+}
+
+impl Hero {
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.f32(self.x);
+        hasher.f32(self.y);
+        hasher.position(self.home);
+        hasher.usize(self.path.len());
+        for waypoint in &self.path {
+            hasher.position(*waypoint);
+        }
+        hasher.usize(self.current_waypoint);
+        hasher.u32(self.level);
+        hasher.u32(self.xp);
+        hasher.i32(self.health);
+        hasher.i32(self.max_health);
+        hasher.f32(self.attack_cooldown);
+        hasher.f32(self.respawn_timer);
+        hasher.f32(self.contact_damage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hero_levels_up_and_heals() {
+        let mut hero = Hero::new(Position::new(0, 0));
+        hero.take_damage(50);
+        hero.gain_xp(60);
+
+        assert_eq!(hero.level, 2);
+        assert_eq!(hero.xp, 10);
+        assert_eq!(hero.health, hero.max_health);
+    }
+
+    #[test]
+    fn test_hero_respawns_after_delay() {
+        let mut hero = Hero::new(Position::new(2, 2));
+        hero.take_damage(hero.max_health);
+        assert!(!hero.is_alive());
+
+        hero.update(HERO_RESPAWN_TIME / 2.0);
+        assert!(!hero.is_alive());
+
+        hero.update(HERO_RESPAWN_TIME);
+        assert!(hero.is_alive());
+        assert_eq!(hero.cell(), Position::new(2, 2));
+    }
+
+    #[test]
+    fn test_respawn_countdown_survives_a_save() {
+        let mut hero = Hero::new(Position::new(2, 2));
+        hero.take_damage(hero.max_health);
+        hero.update(HERO_RESPAWN_TIME / 2.0);
+
+        let mut loaded: Hero = rmp_serde::from_slice(&rmp_serde::to_vec(&hero).unwrap()).unwrap();
+        assert_eq!(loaded.respawn_timer, hero.respawn_timer);
+        loaded.update(0.1);
+        assert!(!loaded.is_alive());
+    }
+
+    #[test]
+    fn test_hero_walks_to_target() {
+        let grid = Grid::new(10, 10);
+        let mut hero = Hero::new(Position::new(0, 0));
+        assert!(hero.move_to(&grid, Position::new(3, 0)).is_ok());
+        assert_eq!(hero.move_to(&grid, Position::new(3, 10)), Err(ActionError::OutOfBounds));
+
+        for _ in 0..200 {
+            hero.update(0.05);
+        }
+        assert_eq!(hero.cell(), Position::new(3, 0));
+    }
+}
@@ -0,0 +1,394 @@
+//! Hierarchical pathfinding (HPA*) for big boards. The board is cut into
+//! `CLUSTER_SIZE` square clusters. Where two clusters touch, each open run
+//! of cells along the border gets one portal pair; teleporter ends count as
+//! portals too. Each cluster caches the steps from every portal to every
+//! one of its cells, so a route is an A* over portals followed by short
+//! searches inside single clusters. Routes are near-shortest, not always
+//! shortest.
+//!
+//! The grid owns the cache and rebuilds it lazily: changing a cell only
+//! marks the clusters within reach of it, so placing a tower costs a few
+//! clusters, not the whole board. Boards under `MIN_CELLS` use plain A*.
+
+use std::sync::Mutex;
+
+use crate::pathfinding::{find_path, find_path_with, PathGraph, Search};
+use crate::{Grid, Position};
+
+pub const CLUSTER_SIZE: i32 = 10;
+pub const MIN_CELLS: i32 = 64 * 64;
+
+const UNREACHABLE: u32 = u32::MAX;
+
+/// Shortest route, hierarchically on big boards
+pub fn find_route(grid: &Grid, start: Position, goal: Position) -> Option<Vec<Position>> {
+    if !routes_hierarchically(grid) {
+        return find_path(grid, start, goal);
+    }
+    let mut cache = grid.hierarchy.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let hierarchy = cache.get_or_insert_with(|| Hierarchy::new(grid));
+    hierarchy.find_path(grid, start, goal)
+}
+
+/// Whether `find_route` goes through the hierarchy on this board rather
+/// than plain A*
+pub fn routes_hierarchically(grid: &Grid) -> bool {
+    grid.width() * grid.height() >= MIN_CELLS
+}
+
+/// The grid's cached hierarchy; cloning a grid starts the clone's cache
+/// empty, and it isn't saved
+#[derive(Debug, Default)]
+pub struct HierarchyCache(Mutex<Option<Hierarchy>>);
+
+impl Clone for HierarchyCache {
+    fn clone(&self) -> Self {
+        HierarchyCache::default()
+    }
+}
+
+impl HierarchyCache {
+    /// `pos` changed: rebuild whatever clusters it could affect
+    pub(crate) fn invalidate_around(&mut self, pos: &Position) {
+        if let Some(hierarchy) = self.0.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+            hierarchy.invalidate_around(pos);
+        }
+    }
+
+    /// Links or the layout changed: rebuild everything
+    pub(crate) fn clear(&mut self) {
+        *self.0.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+}
+
+#[derive(Debug)]
+struct Cluster {
+    nodes: Vec<Position>,          // Portals and teleporter ends, sorted
+    distances: Vec<Vec<u32>>,      // Per node, steps to each cell of the cluster
+}
+
+#[derive(Debug)]
+pub struct Hierarchy {
+    width: i32,
+    height: i32,
+    columns: i32,
+    clusters: Vec<Option<Cluster>>, // None until (re)built
+    rebuilt: u64,                   // Clusters built so far
+}
+
+impl Hierarchy {
+    fn new(grid: &Grid) -> Self {
+        let columns = (grid.width() + CLUSTER_SIZE - 1) / CLUSTER_SIZE;
+        let rows = (grid.height() + CLUSTER_SIZE - 1) / CLUSTER_SIZE;
+        Hierarchy {
+            width: grid.width(),
+            height: grid.height(),
+            columns,
+            clusters: (0..columns * rows).map(|_| None).collect(),
+            rebuilt: 0,
+        }
+    }
+
+    fn cluster_of(&self, pos: &Position) -> usize {
+        ((pos.y / CLUSTER_SIZE) * self.columns + pos.x / CLUSTER_SIZE) as usize
+    }
+
+    fn origin(&self, index: usize) -> Position {
+        let index = index as i32;
+        Position::new(index % self.columns * CLUSTER_SIZE, index / self.columns * CLUSTER_SIZE)
+    }
+
+    fn contains(&self, index: usize, pos: &Position) -> bool {
+        let origin = self.origin(index);
+        (0..self.width).contains(&pos.x)
+            && (0..self.height).contains(&pos.y)
+            && (origin.x..origin.x + CLUSTER_SIZE).contains(&pos.x)
+            && (origin.y..origin.y + CLUSTER_SIZE).contains(&pos.y)
+    }
+
+    fn local(&self, index: usize, pos: &Position) -> usize {
+        let origin = self.origin(index);
+        ((pos.y - origin.y) * CLUSTER_SIZE + pos.x - origin.x) as usize
+    }
+
+    fn cells(&self, index: usize) -> impl Iterator<Item = Position> {
+        let origin = self.origin(index);
+        let (right, bottom) = ((origin.x + CLUSTER_SIZE).min(self.width), (origin.y + CLUSTER_SIZE).min(self.height));
+        (origin.y..bottom).flat_map(move |y| (origin.x..right).map(move |x| Position::new(x, y)))
+    }
+
+    fn invalidate_around(&mut self, pos: &Position) {
+        // Hex neighbors are up to two columns away; a border cell changes
+        // the portals of the cluster across the border too
+        for dy in -2..=2 {
+            for dx in -2..=2 {
+                let near = Position::new(pos.x + dx, pos.y + dy);
+                if (0..self.width).contains(&near.x) && (0..self.height).contains(&near.y) {
+                    let index = self.cluster_of(&near);
+                    self.clusters[index] = None;
+                }
+            }
+        }
+    }
+
+    /// Open cell pairs across the border of clusters `low` < `high`, one
+    /// per run of touching pairs: the middle one
+    fn border_portals(&self, grid: &Grid, low: usize, high: usize) -> Vec<(Position, Position)> {
+        let mut pairs = Vec::new();
+        for a in self.cells(low).filter(|cell| grid.is_walkable(cell)) {
+            for b in grid.neighbors(a) {
+                if grid.is_walkable(&b) && self.contains(high, &b) {
+                    pairs.push((a, b));
+                }
+            }
+        }
+        let mut portals = Vec::new();
+        let mut run_start = 0;
+        for end in 1..=pairs.len() {
+            let joined = end < pairs.len() && {
+                let (previous, next) = (pairs[end - 1].0, pairs[end].0);
+                previous == next || grid.neighbors(previous).contains(&next)
+            };
+            if !joined {
+                portals.push(pairs[(run_start + end - 1) / 2]);
+                run_start = end;
+            }
+        }
+        portals
+    }
+
+    fn build(&mut self, grid: &Grid, index: usize) {
+        let mut others: Vec<usize> = self
+            .cells(index)
+            .flat_map(|cell| grid.neighbors(cell))
+            .filter(|cell| grid.in_bounds(cell) && !self.contains(index, cell))
+            .map(|cell| self.cluster_of(&cell))
+            .collect();
+        others.sort_unstable();
+        others.dedup();
+
+        let mut nodes = Vec::new();
+        for other in others {
+            let portals = self.border_portals(grid, index.min(other), index.max(other));
+            nodes.extend(portals.into_iter().map(|(a, b)| if index < other { a } else { b }));
+        }
+        for (entrance, exit) in grid.teleporters() {
+            nodes.extend([entrance, exit].into_iter().filter(|end| self.contains(index, end) && grid.is_walkable(end)));
+        }
+        nodes.sort_by_key(|node| (node.y, node.x));
+        nodes.dedup();
+
+        let distances = nodes.iter().map(|node| self.steps_from(grid, index, *node)).collect();
+        self.clusters[index] = Some(Cluster { nodes, distances });
+        self.rebuilt += 1;
+    }
+
+    /// Breadth-first steps from `from` to every cell of its cluster, walking
+    /// only inside it and not through teleporters
+    fn steps_from(&self, grid: &Grid, index: usize, from: Position) -> Vec<u32> {
+        let mut steps = vec![UNREACHABLE; (CLUSTER_SIZE * CLUSTER_SIZE) as usize];
+        let mut queue = std::collections::VecDeque::from([from]);
+        steps[self.local(index, &from)] = 0;
+        while let Some(at) = queue.pop_front() {
+            let next = steps[self.local(index, &at)] + 1;
+            for to in grid.exits(at) {
+                if !self.contains(index, &to) || !grid.neighbors(at).contains(&to) {
+                    continue;
+                }
+                let slot = &mut steps[self.local(index, &to)];
+                if *slot == UNREACHABLE {
+                    *slot = next;
+                    queue.push_back(to);
+                }
+            }
+        }
+        steps
+    }
+
+    fn cluster(&self, index: usize) -> &Cluster {
+        self.clusters[index].as_ref().expect("clusters are built before searching")
+    }
+
+    fn find_path(&mut self, grid: &Grid, start: Position, goal: Position) -> Option<Vec<Position>> {
+        if !grid.is_walkable(&start) || !grid.is_walkable(&goal) {
+            return None;
+        }
+        for index in 0..self.clusters.len() {
+            if self.clusters[index].is_none() {
+                self.build(grid, index);
+            }
+        }
+
+        let start_cluster = self.cluster_of(&start);
+        let from_start = self.steps_from(grid, start_cluster, start);
+        let mut start_moves: Vec<(Position, i32)> = self
+            .cluster(start_cluster)
+            .nodes
+            .iter()
+            .map(|node| (*node, from_start[self.local(start_cluster, node)]))
+            .chain(self.contains(start_cluster, &goal).then(|| (goal, from_start[self.local(start_cluster, &goal)])))
+            .filter(|(_, steps)| *steps != UNREACHABLE)
+            .map(|(node, steps)| (node, steps as i32))
+            .collect();
+        start_moves.extend(grid.teleporter(&start).filter(|exit| grid.is_walkable(exit)).map(|exit| (exit, 1)));
+
+        let portals = Portals { hierarchy: self, grid, start, start_moves, goal };
+        let route = find_path_with(&portals, start, goal, Search::AStar)?;
+        Some(self.refine(grid, &route))
+    }
+
+    /// Fill in the cells between consecutive portals
+    fn refine(&self, grid: &Grid, route: &[Position]) -> Vec<Position> {
+        let mut path = vec![route[0]];
+        for hop in route.windows(2) {
+            let (from, to) = (hop[0], hop[1]);
+            let index = self.cluster_of(&from);
+            let jumped = grid.teleporter(&from) == Some(to) && !grid.neighbors(from).contains(&to);
+            if jumped || !self.contains(index, &to) {
+                path.push(to);
+                continue;
+            }
+            let inside = Inside { hierarchy: self, grid, index };
+            let leg = find_path_with(&inside, from, to, Search::AStar).expect("portal steps come from a walk inside the cluster");
+            path.extend_from_slice(&leg[1..]);
+        }
+        path
+    }
+}
+
+/// The portal graph for one search, with the start and goal joined in
+struct Portals<'a> {
+    hierarchy: &'a Hierarchy,
+    grid: &'a Grid,
+    start: Position,
+    start_moves: Vec<(Position, i32)>,
+    goal: Position,
+}
+
+impl PathGraph for Portals<'_> {
+    fn is_open(&self, pos: &Position) -> bool {
+        self.grid.is_walkable(pos)
+    }
+
+    fn moves(&self, from: Position) -> Vec<(Position, i32)> {
+        let mut moves = if from == self.start { self.start_moves.clone() } else { Vec::new() };
+        let hierarchy = self.hierarchy;
+        let index = hierarchy.cluster_of(&from);
+        let cluster = hierarchy.cluster(index);
+        let Some(node) = cluster.nodes.iter().position(|node| *node == from) else {
+            return moves;
+        };
+
+        // Across the cluster, to its other portals and perhaps the goal
+        let steps = &cluster.distances[node];
+        let goal_here = hierarchy.contains(index, &self.goal).then_some(self.goal);
+        for to in cluster.nodes.iter().copied().chain(goal_here).filter(|to| *to != from) {
+            let count = steps[hierarchy.local(index, &to)];
+            if count != UNREACHABLE {
+                moves.push((to, count as i32));
+            }
+        }
+        // Over a border into the next cluster's portal
+        for to in self.grid.neighbors(from) {
+            if self.grid.in_bounds(&to) && !hierarchy.contains(index, &to) && self.grid.can_step(from, to) {
+                let other = hierarchy.cluster(hierarchy.cluster_of(&to));
+                if other.nodes.contains(&to) || to == self.goal {
+                    moves.push((to, 1));
+                }
+            }
+        }
+        // Through a teleporter
+        moves.extend(self.grid.teleporter(&from).filter(|exit| self.grid.is_walkable(exit)).map(|exit| (exit, 1)));
+        moves
+    }
+
+    fn heuristic(&self, from: &Position, goal: &Position) -> i32 {
+        self.grid.heuristic(from, goal)
+    }
+}
+
+/// One cluster's cells, for refining a hop across it
+struct Inside<'a> {
+    hierarchy: &'a Hierarchy,
+    grid: &'a Grid,
+    index: usize,
+}
+
+impl PathGraph for Inside<'_> {
+    fn is_open(&self, pos: &Position) -> bool {
+        self.hierarchy.contains(self.index, pos) && self.grid.is_walkable(pos)
+    }
+
+    fn moves(&self, from: Position) -> Vec<(Position, i32)> {
+        let neighbors = self.grid.neighbors(from);
+        let exits = self.grid.exits(from).into_iter();
+        exits.filter(|to| self.is_open(to) && neighbors.contains(to)).map(|to| (to, 1)).collect()
+    }
+
+    fn heuristic(&self, from: &Position, goal: &Position) -> i32 {
+        self.grid.distance(from, goal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 100x100 with a seeded scatter of walls
+    fn big_grid(density: u32) -> Grid {
+        let mut grid = Grid::new(100, 100);
+        let mut seed: u32 = 0x1234_5678;
+        for y in 0..100 {
+            for x in 0..100 {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                if seed % 100 < density {
+                    grid.set_walkable(&Position::new(x, y), false);
+                }
+            }
+        }
+        grid
+    }
+
+    fn assert_walkable_route(grid: &Grid, path: &[Position]) {
+        assert!(path.windows(2).all(|step| grid.exits(step[0]).contains(&step[1])));
+    }
+
+    #[test]
+    fn test_routes_are_walkable_and_near_shortest() {
+        for density in [0, 15, 25] {
+            let grid = big_grid(density);
+            for (start, goal) in [((0, 0), (99, 99)), ((5, 90), (93, 3)), ((41, 42), (47, 45))] {
+                let (start, goal) = (Position::new(start.0, start.1), Position::new(goal.0, goal.1));
+                let exact = find_path(&grid, start, goal);
+                let route = find_route(&grid, start, goal);
+                assert_eq!(exact.is_some(), route.is_some(), "density {}", density);
+                let (Some(exact), Some(route)) = (exact, route) else {
+                    continue;
+                };
+                assert_eq!((route[0], *route.last().unwrap()), (start, goal));
+                assert_walkable_route(&grid, &route);
+                assert!(route.len() as f32 <= exact.len() as f32 * 1.25 + 4.0, "{} vs {}", route.len(), exact.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_a_wall_only_rebuilds_nearby_clusters() {
+        let mut grid = big_grid(0);
+        let (start, goal) = (Position::new(0, 50), Position::new(99, 50));
+        let before = find_route(&grid, start, goal).unwrap();
+        let built = |grid: &Grid| grid.hierarchy.0.lock().unwrap().as_ref().unwrap().rebuilt;
+        assert_eq!(built(&grid), 100);
+
+        // Block a cell the route uses: at most the four clusters around it
+        // are rebuilt
+        let blocked = before[before.len() / 2];
+        grid.set_walkable(&blocked, false);
+        let after = find_route(&grid, start, goal).unwrap();
+        assert!(!after.contains(&blocked));
+        assert_walkable_route(&grid, &after);
+        assert!(built(&grid) - 100 <= 4);
+    }
+}
@@ -0,0 +1,123 @@
+//! Run integrity: whether a run still counts. Anything that steps outside
+//! the rules marks the run with a `Taint`, and a mark is never lifted. A
+//! marked run plays on as normal, but achievements stop counting it and
+//! its run report says it's unranked, so nothing that ranks runs has to
+//! know which tools were used.
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::actions::ActionError;
+use crate::checksum::StateHasher;
+use crate::GameState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Taint {
+    Sandbox,           // Free building, or the sandbox's spawn and clear controls
+    DebugSpawn,        // An enemy spawned off the wave script
+    ContentReload,     // Balance or the wave script changed mid-run
+    CheckpointRestart, // Played on from a wave checkpoint after losing
+    Script,            // A rule script set gold or health itself
+}
+
+impl Taint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Taint::Sandbox => "sandbox",
+            Taint::DebugSpawn => "debug spawn",
+            Taint::ContentReload => "reloaded content",
+            Taint::CheckpointRestart => "checkpoint restart",
+            Taint::Script => "scripted gold or health",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunIntegrity {
+    taints: Vec<Taint>, // Sorted, each at most once
+}
+
+impl RunIntegrity {
+    pub fn is_clean(&self) -> bool {
+        self.taints.is_empty()
+    }
+
+    pub fn taints(&self) -> &[Taint] {
+        &self.taints
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.usize(self.taints.len());
+        for taint in &self.taints {
+            hasher.variant(taint);
+        }
+    }
+}
+
+impl GameState {
+    /// Mark the run as unranked for good
+    pub fn taint(&mut self, taint: Taint) {
+        let taints = &mut self.run_integrity.taints;
+        if let Err(at) = taints.binary_search(&taint) {
+            taints.insert(at, taint);
+            info!(?taint, "run marked unranked");
+        }
+    }
+
+    /// Spawn an enemy outside the wave script, as the debug spawn key does
+    pub fn debug_spawn(&mut self) -> Result<(), ActionError> {
+        self.taint(Taint::DebugSpawn);
+        self.spawn_enemy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::achievements::{AchievementDef, AchievementTracker, Criterion};
+    use crate::mutators::RuleSet;
+    use crate::report::RunReport;
+    use crate::Game;
+
+    #[test]
+    fn test_cheats_mark_the_run_and_stop_achievements() {
+        let mut game = Game::new();
+        let reach = AchievementDef {
+            id: "first".to_string(),
+            name: "First".to_string(),
+            description: String::new(),
+            criterion: Criterion::ReachWave(1),
+        };
+        game.achievements = AchievementTracker::new(vec![reach], Default::default());
+        assert!(game.state.run_integrity.is_clean());
+        assert!(game.state.debug_spawn().is_ok());
+        game.state.taint(Taint::DebugSpawn);
+        assert_eq!(game.state.run_integrity.taints(), &[Taint::DebugSpawn]);
+
+        game.state.enemies.clear();
+        game.update(1.0);
+        assert!(game.state.call_next_wave().is_ok());
+        game.update(1.0);
+        assert!(!game.achievements.is_unlocked("first"));
+        assert!(!RunReport::from_game(&game).summary.ranked);
+
+        let sandbox = Game::with_rules(RuleSet::sandbox());
+        assert_eq!(sandbox.state.run_integrity.taints(), &[Taint::Sandbox]);
+    }
+
+    #[cfg(feature = "scripting")]
+    #[test]
+    fn test_scripted_gold_marks_the_run() {
+        use crate::scripting::ScriptHost;
+
+        let mut game = Game::new();
+        game.scripts = Some(ScriptHost::from_source("fn on_tick(delta) { print(this.gold); }").unwrap());
+        game.update(1.0 / 30.0);
+        assert!(game.state.run_integrity.is_clean()); // Only looking
+
+        game.scripts = Some(ScriptHost::from_source("fn on_tick(delta) { this.gold += 1000; }").unwrap());
+        game.update(1.0 / 30.0);
+        assert_eq!(game.state.run_integrity.taints(), &[Taint::Script]);
+        assert!(!RunReport::from_game(&game).summary.ranked);
+    }
+}
@@ -0,0 +1,141 @@
+//! Consistency checks run after every tick. A violation means some system left
+//! the state corrupt; catching it on the tick it happens beats chasing the
+//! symptoms frames later.
+
+use std::fmt;
+
+use tracing::error;
+
+use crate::{EnemyId, Game, Position, ProjectileId, TowerId, CELL_SIZE};
+
+/// One broken invariant, with enough context to find the culprit
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    NegativeGold(i32),
+    TowerOnWalkableCell { tower_id: TowerId, cell: Position },
+    EnemyOutOfBounds { enemy_id: EnemyId, x: f32, y: f32 },
+    DanglingProjectile { projectile_id: ProjectileId, target_id: EnemyId },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::NegativeGold(gold) => write!(f, "gold is negative ({})", gold),
+            Violation::TowerOnWalkableCell { tower_id, cell } => {
+                write!(f, "tower {:?} occupies walkable cell ({}, {})", tower_id, cell.x, cell.y)
+            }
+            Violation::EnemyOutOfBounds { enemy_id, x, y } => {
+                write!(f, "enemy {:?} is off the grid at ({:.1}, {:.1})", enemy_id, x, y)
+            }
+            Violation::DanglingProjectile { projectile_id, target_id } => write!(
+                f,
+                "projectile {:?} targets missing enemy {:?} without being marked lost",
+                projectile_id, target_id
+            ),
+        }
+    }
+}
+
+impl Game {
+    /// Every invariant the current state breaks; empty when consistent
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let state = &self.state;
+
+        if state.gold < 0 {
+            violations.push(Violation::NegativeGold(state.gold));
+        }
+
+        for tower in state.towers.values() {
+            for cell in tower.cells() {
+                if state.grid.is_walkable(&cell) {
+                    violations.push(Violation::TowerOnWalkableCell { tower_id: tower.id, cell });
+                }
+            }
+        }
+
+        let max_x = state.grid.width() as f32 * CELL_SIZE;
+        let max_y = state.grid.height() as f32 * CELL_SIZE;
+        for enemy in state.enemies.values() {
+            let inside = (0.0..=max_x).contains(&enemy.x) && (0.0..=max_y).contains(&enemy.y);
+            if !inside {
+                violations.push(Violation::EnemyOutOfBounds {
+                    enemy_id: enemy.id,
+                    x: enemy.x,
+                    y: enemy.y,
+                });
+            }
+        }
+
+        for projectile in self.projectiles.values() {
+            if !projectile.target_lost && !state.enemies.contains_key(projectile.target_id) {
+                violations.push(Violation::DanglingProjectile {
+                    projectile_id: projectile.id,
+                    target_id: projectile.target_id,
+                });
+            }
+        }
+
+        violations
+    }
+
+    /// Run `validate` and react according to `strict_invariants`: panic
+    /// (debug builds and tests) or log and keep going (release)
+    pub(crate) fn check_invariants(&self) {
+        let violations = self.validate();
+        if violations.is_empty() {
+            return;
+        }
+
+        if self.strict_invariants {
+            let report: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+            panic!("tick {}: invariants violated: {}", self.tick, report.join("; "));
+        }
+        for violation in &violations {
+            error!(tick = self.tick, %violation, "invariant violated");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TowerType;
+
+    #[test]
+    fn test_running_game_stays_valid() {
+        let mut game = Game::new();
+        game.state.gold = 1000;
+        assert!(game.state.place_tower(TowerType::Splash, Position::new(5, 6)).is_ok());
+        for _ in 0..5 {
+            assert!(game.state.spawn_enemy().is_ok());
+        }
+        for _ in 0..600 {
+            game.update(1.0 / 60.0); // Panics on any violation in test builds
+        }
+        assert!(game.validate().is_empty());
+    }
+
+    #[test]
+    fn test_detects_corruption() {
+        let mut game = Game::new();
+        game.state.gold = 1000;
+        assert!(game.state.place_tower(TowerType::Basic, Position::new(3, 3)).is_ok());
+        game.state.grid.set_walkable(&Position::new(3, 3), true);
+        game.state.gold = -5;
+
+        let violations = game.validate();
+        assert!(violations.contains(&Violation::NegativeGold(-5)));
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, Violation::TowerOnWalkableCell { cell, .. } if *cell == Position::new(3, 3))));
+    }
+
+    #[test]
+    #[should_panic(expected = "invariants violated")]
+    fn test_strict_mode_panics() {
+        let mut game = Game::new();
+        game.state.gold = -1;
+        game.update(1.0 / 60.0);
+    }
+}
@@ -0,0 +1,266 @@
+//! Jump point search: A* for uniform 8-connected grids that skips the
+//! symmetric runs of open cells plain A* would push one by one. From each
+//! node it only follows the directions that could lead somewhere a straight
+//! run couldn't, and jumps along them until it meets the goal or a "forced
+//! neighbor" beside a wall. Paths cost the same as A*'s; this is the
+//! variant that never cuts a blocked corner, matching `Octile` moves.
+//!
+//! `pathfinding::find_path` picks it for graphs that report an
+//! `octile_size`. Runs read many more cells than A* does, so the graph's
+//! `is_open` is copied into a flat table first.
+
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::pathfinding::{reconstruct_path, Node, PathGraph};
+use crate::Position;
+
+/// Which cells are open, row by row
+struct OpenCells {
+    width: i32,
+    height: i32,
+    open: Vec<bool>,
+}
+
+impl OpenCells {
+    fn new<G: PathGraph + ?Sized>(graph: &G, (width, height): (i32, i32)) -> Self {
+        let open = (0..height).flat_map(|y| (0..width).map(move |x| Position::new(x, y))).map(|pos| graph.is_open(&pos));
+        OpenCells { width, height, open: open.collect() }
+    }
+
+    fn at(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height && self.open[(y * self.width + x) as usize]
+    }
+
+    fn contains(&self, pos: &Position) -> bool {
+        self.at(pos.x, pos.y)
+    }
+}
+
+pub(crate) fn find_path<G: PathGraph + ?Sized>(
+    graph: &G,
+    size: (i32, i32),
+    start: Position,
+    goal: Position,
+) -> Option<Vec<Position>> {
+    let cells = OpenCells::new(graph, size);
+    if !cells.contains(&start) || !cells.contains(&goal) {
+        return None;
+    }
+
+    let mut open_set = BinaryHeap::new();
+    let mut closed_set = HashSet::new();
+    let mut came_from: HashMap<Position, Position> = HashMap::new();
+    let mut g_scores: HashMap<Position, i32> = HashMap::new();
+    open_set.push(Node::new(start, 0, graph.heuristic(&start, &goal), None));
+    g_scores.insert(start, 0);
+
+    while let Some(current) = open_set.pop() {
+        let at = current.position;
+        if at == goal {
+            return Some(fill_in(&reconstruct_path(&came_from, at)));
+        }
+        if !closed_set.insert(at) {
+            continue;
+        }
+
+        let parent = came_from.get(&at).copied();
+        for next in pruned_neighbors(&cells, at, parent) {
+            let Some(jump_point) = jump(&cells, next, at, goal) else {
+                continue;
+            };
+            if closed_set.contains(&jump_point) {
+                continue;
+            }
+            let tentative_g = current.g_cost + graph.heuristic(&at, &jump_point); // A straight or diagonal run
+            if g_scores.get(&jump_point).is_none_or(|&existing| tentative_g < existing) {
+                came_from.insert(jump_point, at);
+                g_scores.insert(jump_point, tentative_g);
+                let h = graph.heuristic(&jump_point, &goal);
+                open_set.push(Node::new(jump_point, tentative_g, h, Some(at)));
+            }
+        }
+    }
+    None
+}
+
+/// Neighbors worth following when arriving from `parent`: straight on, plus
+/// anything a wall beside the run makes reachable only through this cell
+fn pruned_neighbors(cells: &OpenCells, at: Position, parent: Option<Position>) -> Vec<Position> {
+    let open = |x, y| cells.at(x, y);
+    let (x, y) = (at.x, at.y);
+    let mut neighbors = Vec::with_capacity(8);
+    let mut add = |dx: i32, dy: i32| neighbors.push(Position::new(x + dx, y + dy));
+    let Some(parent) = parent else {
+        // The start: every legal move
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let diagonal_clear = dx == 0 || dy == 0 || (open(x + dx, y) && open(x, y + dy));
+                if (dx, dy) != (0, 0) && open(x + dx, y + dy) && diagonal_clear {
+                    add(dx, dy);
+                }
+            }
+        }
+        return neighbors;
+    };
+
+    let (dx, dy) = ((x - parent.x).signum(), (y - parent.y).signum());
+    if dx != 0 && dy != 0 {
+        let (side_x, side_y) = (open(x + dx, y), open(x, y + dy));
+        if side_y {
+            add(0, dy);
+        }
+        if side_x {
+            add(dx, 0);
+        }
+        if side_x && side_y && open(x + dx, y + dy) {
+            add(dx, dy);
+        }
+    } else if dx != 0 {
+        let (ahead, below, above) = (open(x + dx, y), open(x, y + 1), open(x, y - 1));
+        if ahead {
+            add(dx, 0);
+            if below && open(x + dx, y + 1) {
+                add(dx, 1);
+            }
+            if above && open(x + dx, y - 1) {
+                add(dx, -1);
+            }
+        }
+        if below {
+            add(0, 1);
+        }
+        if above {
+            add(0, -1);
+        }
+    } else {
+        let (ahead, right, left) = (open(x, y + dy), open(x + 1, y), open(x - 1, y));
+        if ahead {
+            add(0, dy);
+            if right && open(x + 1, y + dy) {
+                add(1, dy);
+            }
+            if left && open(x - 1, y + dy) {
+                add(-1, dy);
+            }
+        }
+        if right {
+            add(1, 0);
+        }
+        if left {
+            add(-1, 0);
+        }
+    }
+    neighbors
+}
+
+/// Run from `from` through `at` until reaching the goal or a cell with a
+/// forced neighbor (returned), or hitting a wall (None)
+fn jump(cells: &OpenCells, mut at: Position, mut from: Position, goal: Position) -> Option<Position> {
+    let open = |x, y| cells.at(x, y);
+    loop {
+        let (x, y) = (at.x, at.y);
+        let (dx, dy) = (x - from.x, y - from.y);
+        if !cells.contains(&at) {
+            return None;
+        }
+        if at == goal {
+            return Some(at);
+        }
+
+        if dx != 0 && dy != 0 {
+            // Diagonal: stop wherever a straight run from here finds something
+            if jump(cells, Position::new(x + dx, y), at, goal).is_some()
+                || jump(cells, Position::new(x, y + dy), at, goal).is_some()
+            {
+                return Some(at);
+            }
+        } else if dx != 0 {
+            if (open(x, y - 1) && !open(x - dx, y - 1)) || (open(x, y + 1) && !open(x - dx, y + 1)) {
+                return Some(at);
+            }
+        } else if (open(x - 1, y) && !open(x - 1, y - dy)) || (open(x + 1, y) && !open(x + 1, y - dy)) {
+            return Some(at);
+        }
+
+        // Carry on; a diagonal needs both cells beside it open
+        if !(open(x + dx, y) && open(x, y + dy)) {
+            return None;
+        }
+        from = at;
+        at = Position::new(x + dx, y + dy);
+    }
+}
+
+/// Expand jump points into every cell between them
+fn fill_in(jump_points: &[Position]) -> Vec<Position> {
+    let mut path = vec![jump_points[0]];
+    for pair in jump_points.windows(2) {
+        let (mut at, to) = (pair[0], pair[1]);
+        let (dx, dy) = ((to.x - at.x).signum(), (to.y - at.y).signum());
+        while at != to {
+            at = Position::new(at.x + dx, at.y + dy);
+            path.push(at);
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::{find_path_with, octile_distance, Octile, Search};
+    use crate::Grid;
+
+    /// What a path costs in octile moves
+    fn cost(path: &[Position]) -> i32 {
+        path.windows(2).map(|step| octile_distance(&step[0], &step[1])).sum()
+    }
+
+    #[test]
+    fn test_jump_points_match_a_star_costs() {
+        let mut seed: u32 = 0x9e37_79b9;
+        for density in [0, 15, 30] {
+            for _ in 0..8 {
+                let mut grid = Grid::new(40, 40);
+                for y in 0..40 {
+                    for x in 0..40 {
+                        seed ^= seed << 13;
+                        seed ^= seed >> 17;
+                        seed ^= seed << 5;
+                        if seed % 100 < density {
+                            grid.set_walkable(&Position::new(x, y), false);
+                        }
+                    }
+                }
+                let (start, goal) = (Position::new(0, 0), Position::new(39, 27));
+                grid.set_walkable(&start, true);
+                grid.set_walkable(&goal, true);
+
+                let graph = Octile { grid: &grid };
+                let a_star = find_path_with(&graph, start, goal, Search::AStar);
+                let jps = find_path_with(&graph, start, goal, Search::Auto);
+                assert_eq!(a_star.as_deref().map(cost), jps.as_deref().map(cost), "density {}", density);
+                if let Some(path) = jps {
+                    // Every step is a legal move
+                    assert!(path.windows(2).all(|step| graph.moves(step[0]).iter().any(|(to, _)| *to == step[1])));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_never_cuts_corners() {
+        let mut grid = Grid::new(5, 5);
+        grid.set_walkable(&Position::new(1, 0), false);
+        grid.set_walkable(&Position::new(0, 1), false);
+        // The only way out of the corner is squeezing diagonally between two walls
+        assert_eq!(jps_path(&grid, Position::new(0, 0), Position::new(4, 4)), None);
+        grid.set_walkable(&Position::new(0, 1), true);
+        let path = jps_path(&grid, Position::new(0, 0), Position::new(4, 4)).unwrap();
+        assert_eq!(&path[..3], &[Position::new(0, 0), Position::new(0, 1), Position::new(1, 2)]);
+    }
+
+    fn jps_path(grid: &Grid, start: Position, goal: Position) -> Option<Vec<Position>> {
+        crate::pathfinding::find_path(&Octile { grid }, start, goal)
+    }
+}
@@ -0,0 +1,186 @@
+//! Extra lanes: separate spawn-to-goal mazes on one board, for maps that
+//! make the player split their attention and gold (`MapFile::lanes`). The
+//! lanes share the board, the gold and the base's health, but each has its
+//! own spawn and goal, and its enemies only ever walk to that goal.
+//!
+//! A lane's `LaneSchedule` picks the waves it takes part in. A wave's
+//! formation groups take turns between the lanes it uses, the main lane
+//! (the map's spawn, goal and portals) first, so a wave is as big as it
+//! would be on one lane, just split. No placement may cut any lane's spawn
+//! off from its goal.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::StateHasher;
+use crate::pathfinding::{find_path, Walled};
+use crate::{GameState, Position};
+
+/// 0 is the main lane; lane `n` is `GameState::lanes[n - 1]`
+pub type LaneId = usize;
+
+pub const MAIN_LANE: LaneId = 0;
+
+/// Which waves come down a lane
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LaneSchedule {
+    #[serde(default = "first_wave")]
+    pub from_wave: u32,
+    #[serde(default = "every_wave")]
+    pub every: u32, // 2 for every other wave from `from_wave`, and so on
+}
+
+fn first_wave() -> u32 {
+    1
+}
+
+fn every_wave() -> u32 {
+    1
+}
+
+impl Default for LaneSchedule {
+    fn default() -> Self {
+        LaneSchedule { from_wave: first_wave(), every: every_wave() }
+    }
+}
+
+impl LaneSchedule {
+    pub fn includes(&self, wave: u32) -> bool {
+        wave >= self.from_wave && (wave - self.from_wave).is_multiple_of(self.every.max(1))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Lane {
+    pub spawn: Position,
+    pub goal: Position,
+    #[serde(default)]
+    pub schedule: LaneSchedule,
+}
+
+impl Lane {
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.position(self.spawn);
+        hasher.position(self.goal);
+        hasher.u32(self.schedule.from_wave);
+        hasher.u32(self.schedule.every);
+    }
+}
+
+impl GameState {
+    /// Where a lane's enemies are headed
+    pub fn lane_goal(&self, lane: LaneId) -> Position {
+        lane.checked_sub(1).and_then(|index| self.lanes.get(index)).map_or(self.goal_point, |lane| lane.goal)
+    }
+
+    /// Every lane's goal, by `LaneId`
+    pub fn lane_goals(&self) -> Vec<Position> {
+        std::iter::once(self.goal_point).chain(self.lanes.iter().map(|lane| lane.goal)).collect()
+    }
+
+    /// The lanes `wave` comes down, main lane first
+    pub fn lanes_for(&self, wave: u32) -> Vec<LaneId> {
+        let extra = self.lanes.iter().enumerate().filter(|(_, lane)| lane.schedule.includes(wave));
+        std::iter::once(MAIN_LANE).chain(extra.map(|(index, _)| index + 1)).collect()
+    }
+
+    /// The lane whose spawn is `cell`; the main lane for its own spawn and
+    /// portals, and anywhere else
+    pub fn lane_spawning_at(&self, cell: Position) -> LaneId {
+        self.lanes.iter().position(|lane| lane.spawn == cell).map_or(MAIN_LANE, |index| index + 1)
+    }
+
+    /// Where formation group `group` of `wave` comes out: groups take turns
+    /// between the wave's lanes, and those on the main lane between its
+    /// open portals
+    pub(crate) fn spawn_cell(&self, wave: u32, group: u32) -> Position {
+        let lanes = self.lanes_for(wave);
+        let (lane, turn) = (lanes[group as usize % lanes.len()], group as usize / lanes.len());
+        if lane == MAIN_LANE {
+            let portals = self.spawn_points(wave);
+            portals[turn % portals.len()]
+        } else {
+            self.lanes[lane - 1].spawn
+        }
+    }
+
+    /// Each lane's spawn and goal, main lane first
+    pub fn lane_ends(&self) -> Vec<(Position, Position)> {
+        let lanes = self.lanes.iter().map(|lane| (lane.spawn, lane.goal));
+        std::iter::once((self.spawn_point, self.goal_point)).chain(lanes).collect()
+    }
+
+    /// The shortest route down each lane that isn't cut off
+    pub fn lane_routes(&self) -> Vec<Vec<Position>> {
+        self.lane_ends().into_iter().filter_map(|(spawn, goal)| find_path(&self.grid, spawn, goal)).collect()
+    }
+
+    /// Whether `cell` is a spawn, the main one or a lane's
+    pub fn is_spawn(&self, cell: &Position) -> bool {
+        *cell == self.spawn_point || self.lanes.iter().any(|lane| lane.spawn == *cell)
+    }
+
+    /// Whether `cell` is a goal, the main one or a lane's
+    pub fn is_goal(&self, cell: &Position) -> bool {
+        *cell == self.goal_point || self.lanes.iter().any(|lane| lane.goal == *cell)
+    }
+
+    /// Whether any portal is cut off from the main goal, or any lane's
+    /// spawn from its own
+    pub fn any_route_cut(&self) -> bool {
+        self.any_route_cut_by(&HashSet::new())
+    }
+
+    /// `any_route_cut` as it would be with `walls` blocked as well
+    pub fn any_route_cut_by(&self, walls: &HashSet<Position>) -> bool {
+        let graph = Walled { grid: &self.grid, walls };
+        let main = self.all_portals().map(|portal| (portal.position, self.goal_point));
+        let lanes = self.lanes.iter().map(|lane| (lane.spawn, lane.goal));
+        main.chain(lanes).any(|(spawn, goal)| find_path(&graph, spawn, goal).is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType};
+
+    /// The default board split in two by a wall along row 8, with a second
+    /// lane along the bottom part that joins every other wave
+    fn two_lanes() -> Game {
+        let mut game = Game::new();
+        for x in 0..game.state.grid.width() {
+            game.state.grid.set_walkable(&Position::new(x, 8), false);
+        }
+        let schedule = LaneSchedule { from_wave: 2, every: 2 };
+        game.state.lanes.push(Lane { spawn: Position::new(0, 11), goal: Position::new(19, 11), schedule });
+        game
+    }
+
+    #[test]
+    fn test_waves_split_between_lanes_on_schedule() {
+        let mut game = two_lanes();
+        assert_eq!(game.state.lanes_for(1), vec![MAIN_LANE]);
+        assert_eq!(game.state.lanes_for(2), vec![MAIN_LANE, 1]);
+        assert_eq!(game.state.lanes_for(3), vec![MAIN_LANE]);
+        assert_eq!(game.state.spawn_cell(2, 1), Position::new(0, 11));
+        assert_eq!(game.state.spawn_cell(2, 2), game.state.spawn_point);
+
+        let lower = game.state.spawn_enemy_from(Position::new(0, 11), |_| {}).unwrap();
+        let enemy = &game.state.enemies[lower];
+        assert_eq!((enemy.lane_id, enemy.path.last()), (1, Some(&Position::new(19, 11))));
+
+        // Walling the lower lane in is refused even though the main lane is open
+        game.state.gold = 10_000;
+        for y in 9..game.state.grid.height() - 1 {
+            assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(10, y)));
+        }
+        assert!(!game.build(BuildTool::Tower(TowerType::Basic), Position::new(10, 14)));
+        assert!(!game.state.any_route_cut());
+
+        // A repath keeps each enemy on its own lane
+        assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(5, 11)));
+        assert_eq!(game.state.enemies[lower].path.last(), Some(&Position::new(19, 11)));
+    }
+}
@@ -0,0 +1,116 @@
+//! Leak prediction: which enemies will probably reach the goal alive. An
+//! enemy's expected damage is the average tower DPS over the cells left on
+//! its route times the seconds it needs to walk the remaining distance
+//! (see `progress`).
+//! Towers split their fire across a pack, so an enemy counts as at risk
+//! unless that estimate beats its health by `SAFETY_MARGIN`.
+//!
+//! The pass is cheap enough to run every tick; the coverage map behind it
+//! is only rebuilt when its fingerprint changes.
+
+use std::collections::HashSet;
+
+use crate::coverage::Coverage;
+use crate::{Enemy, EnemyId, GameState, Position};
+
+pub const SAFETY_MARGIN: f32 = 1.5;
+
+#[derive(Debug, Clone, Default)]
+pub struct LeakForecast {
+    coverage: Coverage,
+    built_for: Option<u64>, // Fingerprint of `coverage`
+    at_risk: HashSet<EnemyId>,
+}
+
+impl LeakForecast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Re-run the prediction for the enemies on the board now
+    pub fn update(&mut self, state: &GameState) {
+        let fingerprint = Coverage::fingerprint(state);
+        if self.built_for != Some(fingerprint) {
+            self.coverage = Coverage::compute(state);
+            self.built_for = Some(fingerprint);
+        }
+        let pace = state.enemy_pace();
+        self.at_risk = state
+            .enemies
+            .values()
+            .filter(|enemy| expected_damage(&self.coverage, enemy, pace) < enemy.health as f32 * SAFETY_MARGIN)
+            .map(|enemy| enemy.id)
+            .collect();
+    }
+
+    pub fn is_at_risk(&self, enemy_id: EnemyId) -> bool {
+        self.at_risk.contains(&enemy_id)
+    }
+
+    pub fn at_risk_count(&self) -> usize {
+        self.at_risk.len()
+    }
+}
+
+/// Damage the towers could deal to an enemy before it reaches the goal,
+/// ignoring slows (which only help)
+pub fn expected_damage(coverage: &Coverage, enemy: &Enemy, pace: f32) -> f32 {
+    let cells = route_cells(enemy);
+    if cells.is_empty() {
+        return 0.0;
+    }
+    let average = cells.iter().map(|cell| coverage.dps_at(*cell)).sum::<f32>() / cells.len() as f32;
+    let speed = enemy.speed * pace;
+    if speed <= f32::EPSILON {
+        return f32::INFINITY; // Stuck enemies never arrive
+    }
+    average * enemy.distance_to_goal / speed
+}
+
+/// Cells from the enemy's own to the goal along its waypoints. Legs that
+/// aren't straight are teleporter jumps and contribute just their ends.
+fn route_cells(enemy: &Enemy) -> Vec<Position> {
+    let mut cells = vec![Position::from_world(enemy.x, enemy.y)];
+    for next in enemy.path.iter().skip(enemy.current_waypoint) {
+        let from = *cells.last().unwrap();
+        if from.x != next.x && from.y != next.y {
+            cells.push(*next);
+            continue;
+        }
+        let (step_x, step_y) = ((next.x - from.x).signum(), (next.y - from.y).signum());
+        let mut cell = from;
+        while cell != *next {
+            cell = Position::new(cell.x + step_x, cell.y + step_y);
+            cells.push(cell);
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType};
+
+    #[test]
+    fn test_uncovered_routes_leak_and_covered_ones_do_not() {
+        let mut game = Game::new();
+        let enemy_id = game.state.spawn_enemy_with(|_| {}).unwrap();
+        game.update(1.0 / 30.0);
+        let mut forecast = LeakForecast::new();
+        forecast.update(&game.state);
+        assert!(forecast.is_at_risk(enemy_id));
+
+        game.state.gold = 10_000;
+        let route = route_cells(&game.state.enemies[enemy_id]);
+        for cell in route.iter().step_by(3) {
+            for offset in [-1, 1] {
+                game.build(BuildTool::Tower(TowerType::Gatling), Position::new(cell.x, cell.y + offset));
+            }
+        }
+        game.update(1.0 / 30.0);
+        forecast.update(&game.state);
+        assert!(!forecast.is_at_risk(enemy_id));
+        assert_eq!(forecast.at_risk_count(), 0);
+    }
+}
@@ -0,0 +1,102 @@
+//! Simulation core for Rust Rush: grid, pathfinding, towers, enemies, waves
+//! and the `Game` update loop. Nothing here depends on rendering or windowing,
+//! so it can be tested and benchmarked headless.
+
+use slotmap::new_key_type;
+
+pub mod abilities;
+pub mod actions;
+pub mod ai;
+pub mod bot;
+pub mod achievements;
+pub mod ammo;
+pub mod attack;
+pub mod aoe;
+pub mod checkpoints;
+pub mod checksum;
+pub mod codex;
+pub mod convoys;
+pub mod coverage;
+pub mod daynight;
+pub mod debuffs;
+mod effects;
+mod enemy;
+pub mod events;
+pub mod focus;
+pub mod fog;
+pub mod formations;
+mod game;
+pub mod gates;
+mod grid;
+pub mod groups;
+pub mod gunners;
+pub mod hero;
+pub mod hpa;
+pub mod integrity;
+pub mod invariants;
+pub mod lanes;
+mod jps;
+pub mod maps;
+pub mod leaks;
+pub mod merging;
+pub mod mods;
+pub mod mutators;
+pub mod obstacles;
+pub mod painting;
+pub mod pathfinding;
+pub mod portals;
+pub mod presets;
+pub mod power;
+pub mod profiling;
+pub mod progress;
+mod projectile;
+pub mod protocol;
+pub mod report;
+pub mod report_card;
+pub mod rng;
+pub mod rollback;
+pub mod savegame;
+pub mod sandbox;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod siege;
+pub mod spatial;
+pub mod stat_sheet;
+mod state;
+pub mod stats;
+pub mod status_effects;
+pub mod traps;
+pub mod threat;
+mod tower;
+pub mod tutorial;
+pub mod versus;
+pub mod victory;
+pub mod waves;
+pub mod weather;
+
+pub use effects::{EffectStyle, ExplosionEffect, FloatingText, MuzzleFlash};
+pub use enemy::Enemy;
+pub use game::{BuildTool, Game};
+pub use grid::{Direction, Grid, GridShape, Position};
+pub use projectile::{lead_target, segment_distance, wrap_angle, Guidance, Projectile};
+pub use state::GameState;
+pub use tower::{Tower, TowerType};
+
+pub const CELL_SIZE: f32 = 40.0;
+pub const GRID_WIDTH: i32 = 20;
+pub const GRID_HEIGHT: i32 = 15;
+pub const STARTING_HEALTH: i32 = 20;
+pub const LOW_HEALTH_THRESHOLD: i32 = 5;
+pub const ENEMY_RADIUS: f32 = CELL_SIZE * 0.3; // Body radius for drawing and projectile hits
+pub const PROJECTILE_RADIUS: f32 = 5.0;
+pub const FIRING_ARC: f32 = 0.15; // Radians a barrel may still be off target when it fires
+
+// Generational handles: a removed entity's slot can be reused, but old
+// handles to it stop resolving instead of finding the newcomer
+new_key_type! {
+    pub struct TowerId;
+    pub struct EnemyId;
+    pub struct ProjectileId;
+    pub struct ConvoyId;
+}
+
@@ -0,0 +1,245 @@
+//! Map files: the spawn and goal, permanent walls, obstacles and teleporter
+//! pairs for a board, extra spawn portals (see `portals`), extra lanes
+//! with their own spawn and goal (see `lanes`), purely
+//! decorative props, an optional wave script (see `formations`) and an
+//! optional win condition (see `victory`), so testers can set up a layout
+//! without editing code. `"shape": "Hex"` makes a hex board; its
+//! cells use doubled-width coordinates (see `GridShape`), and multi-cell
+//! towers like the Cannon can't be built on it.
+//! Maps use the standard `GRID_WIDTH` x `GRID_HEIGHT` board. The engine
+//! reads them as JSON:
+//!
+//! ```text
+//! {
+//!   "name": "Switchback",
+//!   "spawn": { "x": 0, "y": 2 },
+//!   "goal": { "x": 19, "y": 12 },
+//!   "walls": [{ "x": 5, "y": 0 }, { "x": 5, "y": 1 }],
+//!   "obstacles": [{ "kind": "Rock", "position": { "x": 9, "y": 9 } }],
+//!   "teleporters": [[{ "x": 2, "y": 13 }, { "x": 17, "y": 1 }]],
+//!   "portals": [{ "position": { "x": 10, "y": 0 }, "opens_on": 6 }],
+//!   "lanes": [{ "spawn": { "x": 0, "y": 14 }, "goal": { "x": 19, "y": 14 }, "schedule": { "from_wave": 3, "every": 2 } }],
+//!   "decorations": [{ "kind": "Sign", "x": 30.0, "y": 140.0, "label": "Keep out" }],
+//!   "waves": [{ "from_wave": 4, "formation": "Wedge", "speed_jitter": 0.15 }],
+//!   "win_condition": { "goal": "SurviveWaves", "waves": 12 }
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::formations::WavePlan;
+use crate::hero::Hero;
+use crate::obstacles::Obstacle;
+use crate::lanes::Lane;
+use crate::portals::SpawnPortal;
+use crate::victory::{Objective, WinCondition};
+use crate::{GameState, GridShape, Position};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapFile {
+    pub name: String,
+    #[serde(default)]
+    pub shape: GridShape,
+    pub spawn: Position,
+    pub goal: Position,
+    #[serde(default)]
+    pub walls: Vec<Position>, // Never walkable or buildable
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    #[serde(default)]
+    pub teleporters: Vec<(Position, Position)>,
+    #[serde(default)]
+    pub portals: Vec<SpawnPortal>, // Besides the spawn
+    #[serde(default)]
+    pub lanes: Vec<Lane>, // Separate mazes besides the main one
+    #[serde(default)]
+    pub decorations: Vec<Prop>,
+    #[serde(default)]
+    pub waves: Vec<WavePlan>,
+    #[serde(default)]
+    pub win_condition: Option<WinCondition>, // None: endless
+}
+
+/// Scenery with no effect on play; it may sit anywhere, even on walls or
+/// the route. `x`/`y` is where the prop meets the ground, in world units:
+/// a prop further down the board than an enemy draws in front of it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prop {
+    pub kind: PropKind,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label: String, // Written on signs
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PropKind {
+    Tree,
+    Rock,
+    Sign,
+    Flowers,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapError {
+    OutOfBounds(Position),
+    Overlap(Position), // A wall, obstacle, teleporter, portal or core on the spawn, goal or another feature
+    NoRoute,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::OutOfBounds(cell) => write!(f, "cell ({}, {}) is off the board", cell.x, cell.y),
+            MapError::Overlap(cell) => write!(f, "cell ({}, {}) is used twice", cell.x, cell.y),
+            MapError::NoRoute => write!(f, "no route from a spawn portal to the goal"),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl MapFile {
+    /// Every cell the map claims, spawn and goal first
+    fn cells(&self) -> impl Iterator<Item = Position> + '_ {
+        let obstacles = self.obstacles.iter().map(|obstacle| obstacle.position);
+        let teleporters = self.teleporters.iter().flat_map(|(a, b)| [*a, *b]);
+        let portals = self.portals.iter().map(|portal| portal.position);
+        let lanes = self.lanes.iter().flat_map(|lane| [lane.spawn, lane.goal]);
+        let core = match self.win_condition {
+            Some(WinCondition::DefendCore { position, .. }) => Some(position),
+            _ => None,
+        };
+        [self.spawn, self.goal]
+            .into_iter()
+            .chain(self.walls.iter().copied())
+            .chain(obstacles)
+            .chain(teleporters)
+            .chain(portals)
+            .chain(lanes)
+            .chain(core)
+    }
+}
+
+impl GameState {
+    /// Lay a map onto a fresh board. The hero starts on the goal.
+    pub fn load_map(&mut self, map: &MapFile) -> Result<(), MapError> {
+        self.grid.set_shape(map.shape);
+        let mut seen = Vec::new();
+        for cell in map.cells() {
+            if !self.grid.in_bounds(&cell) {
+                return Err(MapError::OutOfBounds(cell));
+            }
+            if seen.contains(&cell) {
+                return Err(MapError::Overlap(cell));
+            }
+            seen.push(cell);
+        }
+        for prop in &map.decorations {
+            let cell = Position::from_world(prop.x, prop.y);
+            if !self.grid.in_bounds(&cell) {
+                return Err(MapError::OutOfBounds(cell));
+            }
+        }
+
+        self.spawn_point = map.spawn;
+        self.goal_point = map.goal;
+        self.hero = Hero::new(map.goal);
+        for wall in &map.walls {
+            self.grid.set_walkable(wall, false);
+        }
+        for (a, b) in &map.teleporters {
+            self.grid.link_teleporters(*a, *b);
+        }
+        self.portals = map.portals.clone();
+        self.lanes = map.lanes.clone();
+        self.map_name = map.name.clone();
+        let condition = map.win_condition.clone();
+        self.objective = condition.map(|condition| Objective::new(condition, map.spawn)).unwrap_or_default();
+        if let Some(core) = self.objective.core() {
+            self.grid.set_walkable(&core, false);
+        }
+        if self.any_route_cut() {
+            return Err(MapError::NoRoute);
+        }
+        for obstacle in &map.obstacles {
+            if !self.add_obstacle(obstacle.kind, obstacle.position) {
+                return Err(MapError::NoRoute);
+            }
+        }
+        self.decorations = map.decorations.clone();
+        self.decorations.sort_by(|a, b| a.y.total_cmp(&b.y)); // Back to front
+        self.waves.plans = map.waves.clone();
+        info!(name = %map.name, walls = map.walls.len(), "map loaded");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::find_path;
+    use crate::obstacles::ObstacleKind;
+    use crate::Game;
+
+    fn map() -> MapFile {
+        MapFile {
+            name: "Test".to_string(),
+            shape: GridShape::Square,
+            spawn: Position::new(0, 2),
+            goal: Position::new(19, 12),
+            walls: (0..14).map(|y| Position::new(5, y)).collect(),
+            obstacles: vec![Obstacle { kind: ObstacleKind::Rock, position: Position::new(9, 9) }],
+            teleporters: Vec::new(),
+            portals: Vec::new(),
+            lanes: Vec::new(),
+            decorations: vec![
+                Prop { kind: PropKind::Tree, x: 300.0, y: 100.0, label: String::new() },
+                Prop { kind: PropKind::Sign, x: 20.0, y: 60.0, label: "Start".to_string() },
+            ],
+            waves: Vec::new(),
+            win_condition: None,
+        }
+    }
+
+    #[test]
+    fn test_map_sets_up_the_board() {
+        let mut game = Game::new();
+        game.state.load_map(&map()).unwrap();
+        assert_eq!(game.state.spawn_point, Position::new(0, 2));
+        assert!(!game.state.grid.is_walkable(&Position::new(5, 3)));
+        assert!(game.state.obstacle_at(&Position::new(9, 9)).is_some());
+        let route = find_path(&game.state.grid, game.state.spawn_point, game.state.goal_point).unwrap();
+        assert!(route.contains(&Position::new(5, 14))); // The only gap
+        let labels: Vec<&str> = game.state.decorations.iter().map(|prop| prop.label.as_str()).collect();
+        assert_eq!(labels, ["Start", ""]); // Sorted back to front
+    }
+
+    #[test]
+    fn test_bad_maps_are_rejected() {
+        let mut sealed = map();
+        sealed.walls.push(Position::new(5, 14));
+        assert_eq!(GameState::new().load_map(&sealed), Err(MapError::NoRoute));
+
+        let mut core_in_the_gap = map();
+        let position = Position::new(5, 14);
+        let core = WinCondition::DefendCore { position, health: 100.0, hold_above: 0.0, waves: 5 };
+        core_in_the_gap.win_condition = Some(core);
+        assert_eq!(GameState::new().load_map(&core_in_the_gap), Err(MapError::NoRoute));
+
+        let mut off_board = map();
+        off_board.walls.push(Position::new(20, 0));
+        assert_eq!(GameState::new().load_map(&off_board), Err(MapError::OutOfBounds(Position::new(20, 0))));
+
+        let mut odd_hex = map();
+        odd_hex.shape = GridShape::Hex; // (0, 2) is a hex, the goal (19, 12) isn't
+        assert_eq!(GameState::new().load_map(&odd_hex), Err(MapError::OutOfBounds(Position::new(19, 12))));
+
+        let mut stray_prop = map();
+        stray_prop.decorations[0].y = -50.0;
+        assert!(matches!(GameState::new().load_map(&stray_prop), Err(MapError::OutOfBounds(_))));
+    }
+}
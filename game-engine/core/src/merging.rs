@@ -0,0 +1,49 @@
+use crate::TowerType;
+
+/// A merge recipe: dragging one input tower onto an adjacent tower of the other
+/// input type combines them into `result`. Inputs are order-insensitive.
+#[derive(Debug, Clone, Copy)]
+pub struct MergeRecipe {
+    pub inputs: (TowerType, TowerType),
+    pub result: TowerType,
+}
+
+pub const MERGE_RECIPES: &[MergeRecipe] = &[
+    MergeRecipe {
+        inputs: (TowerType::Slow, TowerType::Splash),
+        result: TowerType::FrostBomb,
+    },
+    MergeRecipe {
+        inputs: (TowerType::Sniper, TowerType::Sniper),
+        result: TowerType::Railgun,
+    },
+    MergeRecipe {
+        inputs: (TowerType::Basic, TowerType::Basic),
+        result: TowerType::Gatling,
+    },
+];
+
+/// Look up the hybrid produced by merging two tower types, if any
+pub fn find_recipe(a: TowerType, b: TowerType) -> Option<TowerType> {
+    MERGE_RECIPES
+        .iter()
+        .find(|recipe| recipe.inputs == (a, b) || recipe.inputs == (b, a))
+        .map(|recipe| recipe.result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recipe_is_order_insensitive() {
+        assert_eq!(find_recipe(TowerType::Slow, TowerType::Splash), Some(TowerType::FrostBomb));
+        assert_eq!(find_recipe(TowerType::Splash, TowerType::Slow), Some(TowerType::FrostBomb));
+    }
+
+    #[test]
+    fn test_unknown_combination() {
+        assert_eq!(find_recipe(TowerType::Basic, TowerType::Sniper), None);
+        assert_eq!(find_recipe(TowerType::Railgun, TowerType::Railgun), None);
+    }
+}
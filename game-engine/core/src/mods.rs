@@ -0,0 +1,295 @@
+//! Mod content merged into the game's data. Each mod ships a manifest of
+//! tower stat, enemy and effect stacking overrides plus an optional rule
+//! script; the frontend
+//! finds and parses manifests, and this registry merges them in load order.
+//! A later mod that overrides something an earlier one already did wins, and
+//! the clash is recorded so it can be reported. Content can also be swapped
+//! mid-run (`GameState::reload_content`), for iterating on balance.
+
+use serde::{Deserialize, Serialize};
+
+use crate::stat_sheet::{Stat, StatSheet};
+use crate::status_effects::{EffectKind, EffectRules, Stacking};
+use crate::{Enemy, GameState, TowerType};
+
+/// One mod's `mod.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModManifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub towers: Vec<TowerOverride>,
+    #[serde(default)]
+    pub enemy: EnemyOverride,
+    #[serde(default)]
+    pub effects: Vec<EffectRule>,
+    #[serde(default)]
+    pub script: Option<String>, // Path relative to the mod's folder
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TowerOverride {
+    pub tower: TowerType,
+    pub damage: Option<f32>,
+    pub range: Option<f32>,
+    pub fire_rate: Option<f32>,
+    pub projectile_speed: Option<f32>,
+}
+
+impl TowerOverride {
+    fn stats(&self) -> impl Iterator<Item = (Stat, f32)> {
+        [
+            (Stat::Damage, self.damage),
+            (Stat::Range, self.range),
+            (Stat::FireRate, self.fire_rate),
+            (Stat::ProjectileSpeed, self.projectile_speed),
+        ]
+        .into_iter()
+        .filter_map(|(stat, value)| value.map(|value| (stat, value)))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnemyOverride {
+    pub health: Option<i32>,
+    pub speed: Option<f32>,
+}
+
+/// How one effect kind stacks, e.g.
+/// `{ "effect": "Slow", "policy": "diminishing_returns", "falloff": 0.5 }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EffectRule {
+    pub effect: EffectKind,
+    #[serde(flatten)]
+    pub stacking: Stacking,
+}
+
+/// What two mods both tried to set; `winner` is the one that took effect
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModConflict {
+    pub field: String,
+    pub loser: String,
+    pub winner: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Sourced<T> {
+    value: T,
+    source: String, // Name of the mod that set it
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModRegistry {
+    pub active: Vec<String>, // Mod names in load order
+    pub conflicts: Vec<ModConflict>,
+    tower_stats: Vec<(TowerType, Stat, Sourced<f32>)>,
+    enemy_health: Option<Sourced<i32>>,
+    enemy_speed: Option<Sourced<f32>>,
+    effect_rules: Vec<(EffectKind, Sourced<Stacking>)>,
+    script: Option<Sourced<String>>,
+}
+
+impl ModRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Merge a mod on top of everything loaded before it
+    pub fn merge(&mut self, manifest: &ModManifest) {
+        let name = &manifest.name;
+        for tower in &manifest.towers {
+            for (stat, value) in tower.stats() {
+                let slot = self.tower_stats.iter_mut().find(|(t, s, _)| *t == tower.tower && *s == stat);
+                match slot {
+                    Some((_, _, current)) => {
+                        let field = format!("{:?}.{:?}", tower.tower, stat);
+                        record(&mut self.conflicts, current, value, name, field);
+                    }
+                    None => self.tower_stats.push((tower.tower, stat, sourced(value, name))),
+                }
+            }
+        }
+
+        if let Some(health) = manifest.enemy.health {
+            set(&mut self.enemy_health, &mut self.conflicts, health, name, "enemy.health");
+        }
+        if let Some(speed) = manifest.enemy.speed {
+            set(&mut self.enemy_speed, &mut self.conflicts, speed, name, "enemy.speed");
+        }
+        for rule in &manifest.effects {
+            match self.effect_rules.iter_mut().find(|(kind, _)| *kind == rule.effect) {
+                Some((_, current)) => {
+                    let field = format!("effects.{:?}", rule.effect);
+                    record(&mut self.conflicts, current, rule.stacking, name, field);
+                }
+                None => self.effect_rules.push((rule.effect, sourced(rule.stacking, name))),
+            }
+        }
+        if let Some(script) = &manifest.script {
+            set(&mut self.script, &mut self.conflicts, script.clone(), name, "script");
+        }
+
+        self.active.push(name.clone());
+    }
+
+    /// The winning script as (mod name, path inside that mod)
+    pub fn script(&self) -> Option<(&str, &str)> {
+        self.script.as_ref().map(|script| (script.source.as_str(), script.value.as_str()))
+    }
+
+    /// Replace a fresh sheet's base values with any modded ones
+    pub fn apply_to_tower(&self, tower_type: TowerType, stats: &mut StatSheet) {
+        for (_, stat, value) in self.tower_stats.iter().filter(|(t, _, _)| *t == tower_type) {
+            stats.set_base(*stat, value.value);
+        }
+    }
+
+    /// The stacking policy for each effect kind, modded or default
+    pub fn effect_rules(&self) -> EffectRules {
+        let mut rules = EffectRules::default();
+        for (kind, stacking) in &self.effect_rules {
+            rules.set(*kind, stacking.value);
+        }
+        rules
+    }
+
+    pub fn apply_to_enemy(&self, enemy: &mut Enemy) {
+        if let Some(health) = &self.enemy_health {
+            enemy.health = health.value.max(1);
+            enemy.max_health = enemy.health;
+        }
+        if let Some(speed) = &self.enemy_speed {
+            enemy.speed = speed.value.max(0.0);
+        }
+    }
+}
+
+impl GameState {
+    /// Replace the mod content mid-run. Standing towers take the new base
+    /// stats and keep their modifiers; enemies already out keep theirs, and
+    /// later spawns get the new overrides.
+    pub fn reload_content(&mut self, content: ModRegistry) {
+        for tower in self.towers.values_mut() {
+            tower.stats.rebase(tower.tower_type);
+            content.apply_to_tower(tower.tower_type, &mut tower.stats);
+        }
+        self.content = content;
+    }
+}
+
+fn sourced<T>(value: T, source: &str) -> Sourced<T> {
+    Sourced { value, source: source.to_string() }
+}
+
+fn set<T>(slot: &mut Option<Sourced<T>>, conflicts: &mut Vec<ModConflict>, value: T, name: &str, field: &str) {
+    match slot {
+        Some(current) => record(conflicts, current, value, name, field.to_string()),
+        None => *slot = Some(sourced(value, name)),
+    }
+}
+
+fn record<T>(conflicts: &mut Vec<ModConflict>, current: &mut Sourced<T>, value: T, name: &str, field: String) {
+    conflicts.push(ModConflict {
+        field,
+        loser: std::mem::replace(&mut current.source, name.to_string()),
+        winner: name.to_string(),
+    });
+    current.value = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(name: &str, damage: f32) -> ModManifest {
+        ModManifest {
+            name: name.to_string(),
+            version: String::new(),
+            towers: vec![TowerOverride {
+                tower: TowerType::Basic,
+                damage: Some(damage),
+                range: None,
+                fire_rate: None,
+                projectile_speed: None,
+            }],
+            enemy: EnemyOverride::default(),
+            effects: Vec::new(),
+            script: None,
+        }
+    }
+
+    #[test]
+    fn test_later_mod_wins_and_conflict_is_reported() {
+        let mut registry = ModRegistry::new();
+        registry.merge(&manifest("glass-cannons", 50.0));
+        registry.merge(&manifest("balance-patch", 15.0));
+
+        let mut stats = StatSheet::for_tower(TowerType::Basic);
+        registry.apply_to_tower(TowerType::Basic, &mut stats);
+        assert_eq!(stats.get(Stat::Damage), 15.0);
+        assert_eq!(stats.get(Stat::Range), TowerType::Basic.range());
+
+        assert_eq!(registry.active, vec!["glass-cannons", "balance-patch"]);
+        assert_eq!(
+            registry.conflicts,
+            vec![ModConflict {
+                field: "Basic.Damage".to_string(),
+                loser: "glass-cannons".to_string(),
+                winner: "balance-patch".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_effect_stacking_comes_from_the_manifest() {
+        let mut sticky = manifest("sticky", 10.0);
+        let falloff = Stacking::DiminishingReturns { falloff: 0.5 };
+        sticky.effects.push(EffectRule { effect: EffectKind::Slow, stacking: falloff });
+        let mut registry = ModRegistry::new();
+        registry.merge(&sticky);
+        let rules = registry.effect_rules();
+        assert_eq!(rules.slow, Stacking::DiminishingReturns { falloff: 0.5 });
+        assert_eq!(rules.burn, EffectRules::default().burn);
+
+        let mut later = manifest("tidy", 10.0);
+        later.effects.push(EffectRule { effect: EffectKind::Slow, stacking: Stacking::StrongestWins });
+        registry.merge(&later);
+        assert_eq!(registry.effect_rules().slow, Stacking::StrongestWins);
+        let fields: Vec<&str> = registry.conflicts.iter().map(|conflict| conflict.field.as_str()).collect();
+        assert_eq!(fields, vec!["Basic.Damage", "effects.Slow"]);
+    }
+
+    #[test]
+    fn test_overrides_only_touch_their_tower() {
+        let mut registry = ModRegistry::new();
+        registry.merge(&manifest("glass-cannons", 50.0));
+
+        let mut stats = StatSheet::for_tower(TowerType::Sniper);
+        registry.apply_to_tower(TowerType::Sniper, &mut stats);
+        assert_eq!(stats.get(Stat::Damage), TowerType::Sniper.damage() as f32);
+        assert!(registry.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_reloading_rebases_standing_towers() {
+        let mut state = GameState::new();
+        let mut registry = ModRegistry::new();
+        registry.merge(&manifest("glass-cannons", 50.0));
+        state.reload_content(registry);
+        assert!(state.place_tower(TowerType::Basic, crate::Position::new(4, 4)).is_ok());
+        let tower_id = state.towers.keys().next().unwrap();
+        assert_eq!(state.towers[tower_id].stats.get(Stat::Damage), 50.0);
+
+        let mut registry = ModRegistry::new();
+        registry.merge(&manifest("balance-patch", 15.0));
+        state.reload_content(registry);
+        assert_eq!(state.towers[tower_id].stats.get(Stat::Damage), 15.0);
+        state.reload_content(ModRegistry::new());
+        assert_eq!(state.towers[tower_id].stats.get(Stat::Damage), TowerType::Basic.damage() as f32);
+    }
+}
@@ -0,0 +1,233 @@
+//! Challenge mutators: optional rules picked before a run starts. A
+//! `RuleSet` holds the chosen ones; `GameState::with_rules` applies the
+//! starting changes, and the action layer asks the rule set before selling
+//! or building. Each mutator raises the score multiplier, and combining
+//! them multiplies their bonuses.
+//!
+//! The same rule set carries the sandbox flag (see `sandbox`), which makes
+//! everything free and the run unscored.
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::checksum::StateHasher;
+use crate::integrity::Taint;
+use crate::stat_sheet::{Modifier, ModifierOp, Stat, StatSheet};
+use crate::{GameState, TowerType};
+
+pub const DECAY_SOURCE: &str = "decay";
+const DECAY_PER_WAVE: f32 = 0.9; // Damage kept per wave a tower stands through
+const MAX_DECAY_STEPS: usize = 6; // Decay stops at about half damage
+const TWO_TOWER_TYPES: [TowerType; 2] = [TowerType::Basic, TowerType::Splash];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Mutator {
+    NoSelling,
+    DoubleSpeed,
+    HalfGold,
+    TwoTowerTypes,
+    TowerDecay,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 5] =
+        [Mutator::NoSelling, Mutator::DoubleSpeed, Mutator::HalfGold, Mutator::TwoTowerTypes, Mutator::TowerDecay];
+
+    /// Short name, as used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mutator::NoSelling => "no-selling",
+            Mutator::DoubleSpeed => "double-speed",
+            Mutator::HalfGold => "half-gold",
+            Mutator::TwoTowerTypes => "two-towers",
+            Mutator::TowerDecay => "decay",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Mutator::NoSelling => "Towers can't be sold",
+            Mutator::DoubleSpeed => "Enemies move twice as fast",
+            Mutator::HalfGold => "Half the starting gold, kill rewards and early-call bonuses",
+            Mutator::TwoTowerTypes => "Only Basic and Splash towers can be built",
+            Mutator::TowerDecay => "Towers lose 10% damage for every wave they stand through",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Mutator> {
+        Mutator::ALL.into_iter().find(|mutator| mutator.name() == name)
+    }
+
+    pub fn score_multiplier(&self) -> f32 {
+        match self {
+            Mutator::NoSelling => 1.2,
+            Mutator::DoubleSpeed => 1.5,
+            Mutator::HalfGold => 1.4,
+            Mutator::TwoTowerTypes => 1.3,
+            Mutator::TowerDecay => 1.3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    mutators: Vec<Mutator>, // Sorted, no duplicates
+    #[serde(default)]
+    pub sandbox: bool,
+    #[serde(default)]
+    pub ammo: bool, // Towers fire in magazines; see `ammo`
+    #[serde(default)]
+    pub siege: bool, // Towers may wall off the goal and enemies break through; see `siege`
+    #[serde(default)]
+    pub convoys: bool, // Friendly convoys to protect; see `convoys`
+    #[serde(default)]
+    pub attack: bool, // The player sends the waves against the AI; see `attack`
+    #[serde(default)]
+    pub smart_enemies: bool, // Enemies route around tower coverage; see `threat`
+    #[serde(default)]
+    pub hardcore: bool, // One autosaved life, no checkpoints, its own leaderboard; see `savegame`
+}
+
+impl RuleSet {
+    pub fn new(mutators: impl IntoIterator<Item = Mutator>) -> Self {
+        let mut mutators: Vec<Mutator> = mutators.into_iter().collect();
+        mutators.sort();
+        mutators.dedup();
+        RuleSet { mutators, ..RuleSet::default() }
+    }
+
+    /// Free building and spawn controls, for trying out mazes and mods
+    pub fn sandbox() -> Self {
+        RuleSet { sandbox: true, ..RuleSet::default() }
+    }
+
+    /// Parse a comma-separated list of mutator names
+    pub fn parse(list: &str) -> Result<Self, String> {
+        let names = list.split(',').map(str::trim).filter(|name| !name.is_empty());
+        let mutators = names
+            .map(|name| Mutator::from_name(name).ok_or_else(|| format!("unknown mutator '{}'", name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(RuleSet::new(mutators))
+    }
+
+    pub fn mutators(&self) -> &[Mutator] {
+        &self.mutators
+    }
+
+    pub fn has(&self, mutator: Mutator) -> bool {
+        self.mutators.contains(&mutator)
+    }
+
+    /// Zero in the sandbox, where nothing counts
+    pub fn score_multiplier(&self) -> f32 {
+        if self.sandbox {
+            return 0.0;
+        }
+        self.mutators.iter().map(Mutator::score_multiplier).product()
+    }
+
+    /// What something costing `cost` actually costs; nothing in the sandbox
+    pub fn price(&self, cost: i32) -> i32 {
+        if self.sandbox {
+            0
+        } else {
+            cost
+        }
+    }
+
+    pub fn allows_selling(&self) -> bool {
+        !self.has(Mutator::NoSelling)
+    }
+
+    pub fn allows_tower(&self, tower_type: TowerType) -> bool {
+        !self.has(Mutator::TwoTowerTypes) || TWO_TOWER_TYPES.contains(&tower_type)
+    }
+
+    /// Enemy movement multiplier
+    pub fn enemy_pace(&self) -> f32 {
+        if self.has(Mutator::DoubleSpeed) {
+            2.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Gold actually paid out for a reward of `amount`
+    pub fn gold(&self, amount: i32) -> i32 {
+        if self.has(Mutator::HalfGold) {
+            amount / 2
+        } else {
+            amount
+        }
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.usize(self.mutators.len());
+        for mutator in &self.mutators {
+            hasher.variant(mutator);
+        }
+        hasher.bool(self.sandbox);
+        hasher.bool(self.ammo);
+        hasher.bool(self.siege);
+        hasher.bool(self.convoys);
+        hasher.bool(self.attack);
+        hasher.bool(self.smart_enemies);
+        hasher.bool(self.hardcore);
+    }
+}
+
+/// One more step of decay for a tower that stood through a wave
+pub fn decay(stats: &mut StatSheet) {
+    let steps = stats.modifiers().iter().filter(|modifier| modifier.source == DECAY_SOURCE).count();
+    if steps < MAX_DECAY_STEPS {
+        stats.add_modifier(Modifier::new(DECAY_SOURCE, Stat::Damage, ModifierOp::Multiply(DECAY_PER_WAVE)));
+    }
+}
+
+impl GameState {
+    pub fn with_rules(rules: RuleSet) -> Self {
+        let mut state = GameState::new();
+        state.gold = rules.gold(state.gold);
+        debug!(mutators = ?rules.mutators(), gold = state.gold, "rules applied");
+        if rules.sandbox {
+            state.taint(Taint::Sandbox);
+        }
+        state.rules = rules;
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::ActionError;
+    use crate::{BuildTool, Game, Position};
+
+    #[test]
+    fn test_parse_and_score_combos() {
+        let rules = RuleSet::parse("half-gold, no-selling,half-gold").unwrap();
+        assert_eq!(rules.mutators(), &[Mutator::NoSelling, Mutator::HalfGold]);
+        assert!((rules.score_multiplier() - 1.2 * 1.4).abs() < 1e-6);
+        assert_eq!(RuleSet::default().score_multiplier(), 1.0);
+        assert!(RuleSet::parse("easy-mode").is_err());
+    }
+
+    #[test]
+    fn test_rules_gate_actions() {
+        let rules = RuleSet::new([Mutator::NoSelling, Mutator::HalfGold, Mutator::TwoTowerTypes, Mutator::TowerDecay]);
+        let mut game = Game::with_rules(rules);
+        assert_eq!(game.state.gold, GameState::new().gold / 2);
+
+        assert!(!game.build(BuildTool::Tower(TowerType::Sniper), Position::new(4, 6)));
+        assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6)));
+        let tower_id = game.state.tower_at(&Position::new(4, 6)).unwrap().id;
+        assert_eq!(game.sell_tower(tower_id), Err(ActionError::NotAllowed));
+
+        let damage = game.state.towers[tower_id].stats.get(Stat::Damage);
+        assert!(game.state.call_next_wave().is_ok());
+        while !game.state.waves.is_build_phase() {
+            game.update(1.0 / 30.0);
+        }
+        assert!(game.state.towers[tower_id].stats.get(Stat::Damage) < damage);
+    }
+}
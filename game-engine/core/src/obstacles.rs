@@ -0,0 +1,181 @@
+//! Destructible terrain. Rocks and trees block their cell like a tower
+//! does, but the player can pay to clear one, opening a build spot or a
+//! shortcut for enemies. Clears made during the build phase can be undone
+//! (for a full refund) until the next wave starts.
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::checksum::StateHasher;
+use crate::actions::ActionError;
+use crate::{GameState, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObstacleKind {
+    Rock,
+    Tree,
+}
+
+impl ObstacleKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ObstacleKind::Rock => "Rock",
+            ObstacleKind::Tree => "Tree",
+        }
+    }
+
+    /// Gold it takes to clear
+    pub fn clear_cost(self) -> i32 {
+        match self {
+            ObstacleKind::Rock => 60,
+            ObstacleKind::Tree => 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub kind: ObstacleKind,
+    pub position: Position,
+}
+
+impl Obstacle {
+    pub fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.variant(&self.kind);
+        hasher.position(self.position);
+    }
+}
+
+/// Scatter for the built-in map, clear of the spawn row so there is always
+/// a way through
+pub fn default_layout() -> Vec<Obstacle> {
+    let rocks = [(3, 3), (3, 4), (7, 10), (8, 10), (12, 4), (15, 11)];
+    let trees = [(5, 12), (6, 12), (10, 2), (11, 2), (14, 5), (16, 2), (2, 10)];
+    let place = |kind| move |&(x, y): &(i32, i32)| Obstacle { kind, position: Position::new(x, y) };
+    rocks.iter().map(place(ObstacleKind::Rock)).chain(trees.iter().map(place(ObstacleKind::Tree))).collect()
+}
+
+impl GameState {
+    pub fn obstacle_at(&self, position: &Position) -> Option<&Obstacle> {
+        self.obstacles.iter().find(|obstacle| obstacle.position == *position)
+    }
+
+    /// Put an obstacle on a free cell. Refused if it would cut the spawn
+    /// off from the goal.
+    pub fn add_obstacle(&mut self, kind: ObstacleKind, position: Position) -> bool {
+        if !self.cell_is_free(position) || self.would_block_path(&[position]) {
+            debug!(?kind, ?position, "obstacle rejected");
+            return false;
+        }
+        self.obstacles.push(Obstacle { kind, position });
+        self.grid.set_walkable(&position, false);
+        self.repath_through(&[position]);
+        true
+    }
+
+    /// Pay to remove the obstacle on a cell and reroute everyone through it
+    pub fn clear_obstacle(&mut self, position: Position) -> Result<(), ActionError> {
+        let Some(index) = self.obstacles.iter().position(|obstacle| obstacle.position == position) else {
+            return Err(ActionError::NoObstacle);
+        };
+        let obstacle = self.obstacles[index];
+        let price = self.rules.price(obstacle.kind.clear_cost());
+        if self.gold < price {
+            debug!(?position, gold = self.gold, "clear rejected: not enough gold");
+            return Err(ActionError::InsufficientGold { price, gold: self.gold });
+        }
+
+        self.obstacles.remove(index);
+        self.gold -= price;
+        self.grid.set_walkable(&position, true);
+        if self.waves.is_build_phase() {
+            self.undoable_clears.push(obstacle);
+        }
+        info!(kind = obstacle.kind.name(), ?position, gold = self.gold, "obstacle cleared");
+
+        self.recalculate_paths();
+        Ok(())
+    }
+
+    /// Put back the most recent obstacle cleared this build phase and
+    /// refund it. Fails if its cell has been built on or the path now
+    /// depends on it.
+    pub fn undo_clear(&mut self) -> Result<(), ActionError> {
+        if !self.waves.is_build_phase() {
+            return Err(ActionError::NotReady);
+        }
+        let &obstacle = self.undoable_clears.last().ok_or(ActionError::NothingToUndo)?;
+        let refusal = if !self.cell_is_free(obstacle.position) {
+            Some(ActionError::CellBlocked)
+        } else if self.would_block_path(&[obstacle.position]) {
+            Some(ActionError::WouldBlockPath)
+        } else {
+            None
+        };
+        if let Some(err) = refusal {
+            debug!(position = ?obstacle.position, %err, "undo rejected");
+            return Err(err);
+        }
+
+        self.undoable_clears.pop();
+        self.obstacles.push(obstacle);
+        self.gold += self.rules.price(obstacle.kind.clear_cost());
+        self.grid.set_walkable(&obstacle.position, false);
+        info!(kind = obstacle.kind.name(), position = ?obstacle.position, gold = self.gold, "clear undone");
+
+        self.repath_through(&[obstacle.position]);
+        Ok(())
+    }
+
+    pub(crate) fn cell_is_free(&self, position: Position) -> bool {
+        if self.is_portal(&position) || self.is_goal(&position) {
+            return false;
+        }
+        self.grid.is_walkable(&position)
+            && self.trap_at(&position).is_none()
+            && !self.is_gated(&position)
+            && !(self.hero.is_alive() && self.hero.cell() == position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType};
+
+    #[test]
+    fn test_clearing_opens_the_cell_and_can_be_undone() {
+        let mut game = Game::new();
+        let cell = Position::new(4, 6);
+        assert!(game.state.add_obstacle(ObstacleKind::Rock, cell));
+        assert!(!game.build(BuildTool::Tower(TowerType::Basic), cell));
+
+        let gold = game.state.gold;
+        assert!(game.state.clear_obstacle(cell).is_ok());
+        assert_eq!(game.state.gold, gold - ObstacleKind::Rock.clear_cost());
+        assert!(game.state.grid.is_walkable(&cell));
+
+        assert!(game.state.undo_clear().is_ok());
+        assert_eq!(game.state.gold, gold);
+        assert!(game.state.obstacle_at(&cell).is_some());
+        assert_eq!(game.state.undo_clear(), Err(ActionError::NothingToUndo));
+
+        // Once built on, or after the wave starts, the clear sticks
+        assert!(game.state.clear_obstacle(cell).is_ok());
+        assert!(game.build(BuildTool::Tower(TowerType::Basic), cell));
+        assert_eq!(game.state.undo_clear(), Err(ActionError::CellBlocked));
+        assert!(game.state.call_next_wave().is_ok());
+        game.update(0.1);
+        assert!(game.state.undoable_clears.is_empty());
+    }
+
+    #[test]
+    fn test_obstacles_never_seal_the_path() {
+        let mut game = Game::new();
+        for y in 0..game.state.grid.height() - 1 {
+            assert!(game.state.add_obstacle(ObstacleKind::Tree, Position::new(10, y)));
+        }
+        assert!(!game.state.add_obstacle(ObstacleKind::Tree, Position::new(10, game.state.grid.height() - 1)));
+        assert!(default_layout().iter().all(|obstacle| Game::new().state.add_obstacle(obstacle.kind, obstacle.position)));
+    }
+}
@@ -0,0 +1,184 @@
+//! Tower strokes: dragging out a line or a filled rectangle of one tower
+//! type in a single gesture. While the drag is live, `Game::stroke` keeps a
+//! dry run (see `plan_stroke`) that marks each tower legal or not and totals
+//! the price, for the frontend to show. Releasing builds the stroke through
+//! `Game::try_build`, and it's all or nothing: if any tower is refused (not
+//! enough gold, no room, or the path would be cut), none of them stay.
+
+use std::collections::HashSet;
+
+use crate::actions::ActionError;
+use crate::{BuildTool, Game, Position, TowerType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrokeShape {
+    Line,
+    Rectangle,
+}
+
+/// Corner cells of the towers a drag from `from` to `to` covers, stepping
+/// by the tower's footprint so that big towers sit side by side
+pub fn stroke_cells(tower_type: TowerType, from: Position, to: Position, shape: StrokeShape) -> Vec<Position> {
+    let size = tower_type.footprint();
+    // In footprints from the start; a partial footprint doesn't get a tower
+    let (ux, uy) = ((to.x - from.x) / size, (to.y - from.y) / size);
+    let at = |x: i32, y: i32| Position::new(from.x + x * size, from.y + y * size);
+    match shape {
+        StrokeShape::Rectangle => {
+            let (xs, ys) = (ux.min(0)..=ux.max(0), uy.min(0)..=uy.max(0));
+            ys.flat_map(|y| xs.clone().map(move |x| at(x, y))).collect()
+        }
+        StrokeShape::Line => {
+            // One tower per step along the longer axis
+            let steps = ux.abs().max(uy.abs());
+            let lerp = |end: i32, step: i32| (end as f32 * step as f32 / steps.max(1) as f32).round() as i32;
+            (0..=steps).map(|step| at(lerp(ux, step), lerp(uy, step))).collect()
+        }
+    }
+}
+
+/// A stroke's dry run
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokePlan {
+    pub towers: Vec<(Position, bool)>, // Corner cell, and whether it would go down after the ones before it
+    pub cost: i32,                     // Price of the whole stroke
+}
+
+impl StrokePlan {
+    pub fn buildable(&self) -> bool {
+        self.towers.iter().all(|(_, legal)| *legal)
+    }
+}
+
+/// A stroke being dragged out
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    pub tower_type: TowerType,
+    pub from: Position,
+    pub to: Position,
+    pub shape: StrokeShape,
+    pub cells: Vec<Position>,
+    pub plan: StrokePlan,
+    planned_for: (u64, i32), // Grid revision and gold the plan was made with
+}
+
+impl Game {
+    pub fn start_stroke(&mut self, tower_type: TowerType, at: Position) {
+        let plan = self.plan_stroke(tower_type, &[at]);
+        let planned_for = (self.state.grid.revision(), self.state.gold);
+        let stroke = Stroke { tower_type, from: at, to: at, shape: StrokeShape::Line, cells: vec![at], plan, planned_for };
+        self.stroke = Some(stroke);
+    }
+
+    /// Move the live end of the stroke, planning it again if anything it
+    /// depends on changed
+    pub fn drag_stroke(&mut self, to: Position, shape: StrokeShape) {
+        let Some(stroke) = &self.stroke else {
+            return;
+        };
+        let planned_for = (self.state.grid.revision(), self.state.gold);
+        if (stroke.to, stroke.shape, stroke.planned_for) == (to, shape, planned_for) {
+            return;
+        }
+        let (tower_type, from) = (stroke.tower_type, stroke.from);
+        let cells = stroke_cells(tower_type, from, to, shape);
+        let plan = self.plan_stroke(tower_type, &cells);
+        self.stroke = Some(Stroke { tower_type, from, to, shape, cells, plan, planned_for });
+    }
+
+    /// Build the stroke being dragged, if there is one
+    pub fn finish_stroke(&mut self) -> Option<Result<usize, (Position, ActionError)>> {
+        let stroke = self.stroke.take()?;
+        Some(self.build_stroke(stroke.tower_type, &stroke.cells))
+    }
+
+    /// Check a stroke tower by tower, each against the cells and gold the
+    /// legal ones before it would take, without building anything
+    pub fn plan_stroke(&self, tower_type: TowerType, cells: &[Position]) -> StrokePlan {
+        let price = self.state.rules.price(tower_type.cost());
+        let (mut planned, mut gold) = (HashSet::new(), self.state.gold);
+        let towers = cells
+            .iter()
+            .map(|&cell| {
+                let legal = self.state.check_planned_placement(tower_type, cell, &planned, gold).is_ok();
+                if legal {
+                    planned.extend(tower_type.cells_at(cell));
+                    gold -= price;
+                }
+                (cell, legal)
+            })
+            .collect();
+        StrokePlan { towers, cost: price * cells.len() as i32 }
+    }
+
+    /// Build every tower in the stroke, or none. Returns how many went
+    /// down, or the first cell that was refused and why.
+    pub fn build_stroke(&mut self, tower_type: TowerType, cells: &[Position]) -> Result<usize, (Position, ActionError)> {
+        let before = self.snapshot();
+        for &cell in cells {
+            if let Err(err) = self.try_build(BuildTool::Tower(tower_type), cell) {
+                self.restore(&before);
+                return Err((cell, err));
+            }
+        }
+        Ok(cells.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strokes_step_by_footprint() {
+        let line = stroke_cells(TowerType::Basic, Position::new(2, 2), Position::new(6, 4), StrokeShape::Line);
+        assert_eq!(line.len(), 5);
+        assert_eq!((line[0], line[4]), (Position::new(2, 2), Position::new(6, 4)));
+        let cannons = stroke_cells(TowerType::Cannon, Position::new(2, 2), Position::new(7, 3), StrokeShape::Rectangle);
+        assert_eq!(cannons, vec![Position::new(2, 2), Position::new(4, 2), Position::new(6, 2)]);
+        let back = stroke_cells(TowerType::Basic, Position::new(4, 4), Position::new(3, 3), StrokeShape::Rectangle);
+        assert_eq!(back.len(), 4);
+    }
+
+    #[test]
+    fn test_plan_counts_the_towers_before_each_one() {
+        let mut game = Game::new();
+        game.state.gold = 10_000;
+        let bottom = Position::new(10, game.state.grid.height() - 1);
+        let wall = stroke_cells(TowerType::Basic, Position::new(10, 0), bottom, StrokeShape::Line);
+        let revision = game.state.grid.revision();
+        let plan = game.plan_stroke(TowerType::Basic, &wall);
+        assert!(plan.towers[..wall.len() - 1].iter().all(|(_, legal)| *legal));
+        assert!(!plan.towers[wall.len() - 1].1); // The last one would seal the column the others left open
+        assert_eq!(game.state.grid.revision(), revision);
+
+        game.state.gold = 3 * game.state.rules.price(TowerType::Basic.cost());
+        let plan = game.plan_stroke(TowerType::Basic, &wall[..4]);
+        assert_eq!(plan.towers.iter().map(|(_, legal)| *legal).collect::<Vec<_>>(), vec![true, true, true, false]);
+    }
+
+    #[test]
+    fn test_stroke_builds_whole_or_not_at_all() {
+        let mut game = Game::new();
+        game.state.gold = 10_000;
+        let cells = stroke_cells(TowerType::Basic, Position::new(3, 2), Position::new(8, 2), StrokeShape::Line);
+        let plan = game.plan_stroke(TowerType::Basic, &cells);
+        assert!(plan.buildable() && plan.cost == 6 * game.state.rules.price(TowerType::Basic.cost()));
+        assert!(game.state.towers.is_empty());
+        assert_eq!(game.build_stroke(TowerType::Basic, &cells), Ok(6));
+
+        // Crossing the finished line: the first cell is taken, so nothing goes down
+        let gold = game.state.gold;
+        let cross = stroke_cells(TowerType::Basic, Position::new(5, 2), Position::new(5, 6), StrokeShape::Line);
+        assert!(!game.plan_stroke(TowerType::Basic, &cross).towers[0].1);
+        assert_eq!(game.build_stroke(TowerType::Basic, &cross), Err((Position::new(5, 2), ActionError::CellBlocked)));
+        assert_eq!((game.state.towers.len(), game.state.gold), (6, gold));
+
+        // Dragged out live, then released
+        game.start_stroke(TowerType::Basic, Position::new(3, 5));
+        game.drag_stroke(Position::new(4, 6), StrokeShape::Rectangle);
+        assert_eq!(game.stroke.as_ref().map(|stroke| stroke.plan.towers.len()), Some(4));
+        assert_eq!(game.finish_stroke(), Some(Ok(4)));
+        assert!(game.stroke.is_none());
+    }
+}
@@ -0,0 +1,710 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+use crate::jps;
+use crate::{Grid, GridShape, Position};
+
+// Instrumentation for the debug overlay
+static SEARCHES: AtomicU64 = AtomicU64::new(0);
+static PATH_NODES: AtomicU64 = AtomicU64::new(0);
+
+/// Pathfinding work done since the counters were last taken
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PathCounters {
+    pub searches: u64,
+    pub path_nodes: u64, // Positions allocated in returned paths
+}
+
+/// Read and reset the global pathfinding counters
+pub fn take_counters() -> PathCounters {
+    PathCounters {
+        searches: SEARCHES.swap(0, AtomicOrdering::Relaxed),
+        path_nodes: PATH_NODES.swap(0, AtomicOrdering::Relaxed),
+    }
+}
+
+// ============================================================================
+// GRAPHS
+// ============================================================================
+
+/// Anything A* can search: which cells can be stood on, the moves out of a
+/// cell with what each costs, and an estimate of the cost left that never
+/// overshoots (or the path found may not be the cheapest)
+pub trait PathGraph {
+    fn is_open(&self, pos: &Position) -> bool;
+    fn moves(&self, from: Position) -> Vec<(Position, i32)>;
+    fn heuristic(&self, from: &Position, goal: &Position) -> i32;
+
+    /// `Some((width, height))` for plain 8-connected grids: straight moves
+    /// cost `STRAIGHT`, diagonals `DIAGONAL`, a diagonal needs both cells
+    /// beside it open, and nothing outside the size is open. `find_path`
+    /// uses jump point search on those, which only needs `is_open`.
+    fn octile_size(&self) -> Option<(i32, i32)> {
+        None
+    }
+}
+
+/// Move costs on 8-connected graphs, so a diagonal is about √2 straights
+pub const STRAIGHT: i32 = 10;
+pub const DIAGONAL: i32 = 14;
+
+/// Cheapest 8-connected cost between two cells on an open board
+pub fn octile_distance(a: &Position, b: &Position) -> i32 {
+    let (dx, dy) = ((a.x - b.x).abs(), (a.y - b.y).abs());
+    STRAIGHT * dx.max(dy) + (DIAGONAL - STRAIGHT) * dx.min(dy)
+}
+
+/// Diagonal steps from `from` that don't squeeze between two closed cells
+fn octile_moves(from: Position, is_open: impl Fn(&Position) -> bool) -> Vec<(Position, i32)> {
+    let mut moves = Vec::with_capacity(8);
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            let to = Position::new(from.x + dx, from.y + dy);
+            if (dx, dy) == (0, 0) || !is_open(&to) {
+                continue;
+            }
+            if dx == 0 || dy == 0 {
+                moves.push((to, STRAIGHT));
+            } else if is_open(&Position::new(from.x + dx, from.y)) && is_open(&Position::new(from.x, from.y + dy)) {
+                moves.push((to, DIAGONAL));
+            }
+        }
+    }
+    moves
+}
+
+/// Walkers on the board: one step to each neighbor the one-way rules allow
+/// (four, or six on hex boards) and through teleporters
+impl PathGraph for Grid {
+    fn is_open(&self, pos: &Position) -> bool {
+        self.is_walkable(pos)
+    }
+
+    fn moves(&self, from: Position) -> Vec<(Position, i32)> {
+        self.exits(from).into_iter().map(|to| (to, 1)).collect()
+    }
+
+    /// Steps on an open board, or less if a teleporter could shortcut it
+    fn heuristic(&self, from: &Position, goal: &Position) -> i32 {
+        self.teleporters()
+            .map(|(entrance, exit)| self.distance(from, &entrance) + 1 + self.distance(&exit, goal))
+            .fold(self.distance(from, goal), i32::min)
+    }
+}
+
+/// A grid where some cells cost more to cross, e.g. mud or shallow water.
+/// Entering a cell costs its weight; unlisted cells cost 1.
+pub struct Weighted<'a> {
+    pub grid: &'a Grid,
+    pub weights: &'a HashMap<Position, i32>,
+}
+
+impl PathGraph for Weighted<'_> {
+    fn is_open(&self, pos: &Position) -> bool {
+        self.grid.is_open(pos)
+    }
+
+    fn moves(&self, from: Position) -> Vec<(Position, i32)> {
+        let weight = |to: &Position| self.weights.get(to).copied().unwrap_or(1).max(1);
+        self.grid.exits(from).into_iter().map(|to| (to, weight(&to))).collect()
+    }
+
+    fn heuristic(&self, from: &Position, goal: &Position) -> i32 {
+        self.grid.heuristic(from, goal) // Weights are at least 1, so plain steps never overshoot
+    }
+}
+
+/// Walkers that may also step diagonally, without cutting past a blocked
+/// corner. Teleporters and one-way tiles don't apply.
+pub struct Octile<'a> {
+    pub grid: &'a Grid,
+}
+
+impl PathGraph for Octile<'_> {
+    fn is_open(&self, pos: &Position) -> bool {
+        self.grid.is_walkable(pos)
+    }
+
+    fn moves(&self, from: Position) -> Vec<(Position, i32)> {
+        octile_moves(from, |pos| self.grid.is_walkable(pos))
+    }
+
+    fn heuristic(&self, from: &Position, goal: &Position) -> i32 {
+        octile_distance(from, goal)
+    }
+
+    fn octile_size(&self) -> Option<(i32, i32)> {
+        (self.grid.shape() == GridShape::Square).then(|| (self.grid.width(), self.grid.height()))
+    }
+}
+
+/// Flyers ignore walls, towers and one-way tiles: eight directions on square
+/// boards, six on hex ones, anywhere on the board
+pub struct Airspace<'a> {
+    pub grid: &'a Grid,
+}
+
+impl PathGraph for Airspace<'_> {
+    fn is_open(&self, pos: &Position) -> bool {
+        self.grid.in_bounds(pos)
+    }
+
+    fn moves(&self, from: Position) -> Vec<(Position, i32)> {
+        match self.grid.shape() {
+            GridShape::Square => octile_moves(from, |pos| self.grid.in_bounds(pos)),
+            GridShape::Hex => {
+                let neighbors = self.grid.neighbors(from).into_iter().filter(|to| self.grid.in_bounds(to));
+                neighbors.map(|to| (to, STRAIGHT)).collect()
+            }
+        }
+    }
+
+    fn heuristic(&self, from: &Position, goal: &Position) -> i32 {
+        match self.grid.shape() {
+            GridShape::Square => octile_distance(from, goal),
+            GridShape::Hex => STRAIGHT * from.hex_distance(goal),
+        }
+    }
+
+    fn octile_size(&self) -> Option<(i32, i32)> {
+        (self.grid.shape() == GridShape::Square).then(|| (self.grid.width(), self.grid.height()))
+    }
+}
+
+/// The grid as walkers see it with some more cells walled off, for asking
+/// "what if something were built here" without touching the grid itself
+pub struct Walled<'a> {
+    pub grid: &'a Grid,
+    pub walls: &'a HashSet<Position>,
+}
+
+impl PathGraph for Walled<'_> {
+    fn is_open(&self, pos: &Position) -> bool {
+        self.grid.is_open(pos) && !self.walls.contains(pos)
+    }
+
+    fn moves(&self, from: Position) -> Vec<(Position, i32)> {
+        let mut moves = self.grid.moves(from);
+        moves.retain(|(to, _)| !self.walls.contains(to));
+        moves
+    }
+
+    fn heuristic(&self, from: &Position, goal: &Position) -> i32 {
+        self.grid.heuristic(from, goal) // Walls only make routes longer
+    }
+}
+
+// ============================================================================
+// SEARCH
+// ============================================================================
+
+/// Which search `find_path_with` runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Search {
+    /// Jump point search on uniform 8-connected grids, A* otherwise
+    #[default]
+    Auto,
+    AStar,
+}
+
+/// What a best-effort search found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathResult {
+    /// Every step from the start to the goal
+    Complete(Vec<Position>),
+    /// The goal can't be reached: every step to the reachable cell closest
+    /// to it, which may be the start itself
+    Partial(Vec<Position>),
+    /// The start isn't open
+    Unreachable,
+}
+
+impl PathResult {
+    /// The steps to follow, whether or not they reach the goal
+    pub fn path(&self) -> Option<&[Position]> {
+        match self {
+            PathResult::Complete(path) | PathResult::Partial(path) => Some(path),
+            PathResult::Unreachable => None,
+        }
+    }
+
+    pub fn into_path(self) -> Option<Vec<Position>> {
+        match self {
+            PathResult::Complete(path) | PathResult::Partial(path) => Some(path),
+            PathResult::Unreachable => None,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        matches!(self, PathResult::Complete(_))
+    }
+
+    /// Transform the steps, keeping whether they reach the goal
+    pub fn map(self, f: impl FnOnce(Vec<Position>) -> Vec<Position>) -> Self {
+        match self {
+            PathResult::Complete(path) => PathResult::Complete(f(path)),
+            PathResult::Partial(path) => PathResult::Partial(f(path)),
+            PathResult::Unreachable => PathResult::Unreachable,
+        }
+    }
+}
+
+/// Node used in A* pathfinding
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Node {
+    pub(crate) position: Position,
+    pub(crate) g_cost: i32, // Cost from start to this node
+    h_cost: i32,            // Heuristic cost from this node to goal
+    parent: Option<Position>,
+}
+
+impl Node {
+    pub(crate) fn new(position: Position, g_cost: i32, h_cost: i32, parent: Option<Position>) -> Self {
+        Node {
+            position,
+            g_cost,
+            h_cost,
+            parent,
+        }
+    }
+
+    /// Total cost (f_cost = g_cost + h_cost)
+    fn f_cost(&self) -> i32 {
+        self.g_cost + self.h_cost
+    }
+}
+
+// Implement Ord for BinaryHeap (min-heap based on f_cost)
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reverse ordering for min-heap
+        other.f_cost().cmp(&self.f_cost())
+            .then_with(|| other.h_cost.cmp(&self.h_cost))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Find the cheapest path from start to goal, every step of it
+pub fn find_path<G: PathGraph + ?Sized>(graph: &G, start: Position, goal: Position) -> Option<Vec<Position>> {
+    find_path_with(graph, start, goal, Search::Auto)
+}
+
+pub fn find_path_with<G: PathGraph + ?Sized>(
+    graph: &G,
+    start: Position,
+    goal: Position,
+    search: Search,
+) -> Option<Vec<Position>> {
+    SEARCHES.fetch_add(1, AtomicOrdering::Relaxed);
+    let path = match graph.octile_size() {
+        Some(size) if search == Search::Auto => jps::find_path(graph, size, start, goal),
+        _ => a_star(graph, start, goal),
+    };
+    if let Some(path) = &path {
+        PATH_NODES.fetch_add(path.len() as u64, AtomicOrdering::Relaxed);
+    }
+    path
+}
+
+/// Like `find_path`, but when the goal can't be reached, the path to the
+/// reachable cell closest to it by the graph's heuristic (the cheapest one
+/// to get to among equally close cells)
+pub fn find_path_or_nearest<G: PathGraph + ?Sized>(graph: &G, start: Position, goal: Position) -> PathResult {
+    SEARCHES.fetch_add(1, AtomicOrdering::Relaxed);
+    // One A* flood answers both ways; jump point search skips most cells,
+    // so it can't say which reachable one is nearest
+    let Some(path) = a_star_search(graph, start, goal, true) else {
+        return PathResult::Unreachable;
+    };
+    PATH_NODES.fetch_add(path.len() as u64, AtomicOrdering::Relaxed);
+    if path.last() == Some(&goal) {
+        PathResult::Complete(path)
+    } else {
+        PathResult::Partial(path)
+    }
+}
+
+fn a_star<G: PathGraph + ?Sized>(graph: &G, start: Position, goal: Position) -> Option<Vec<Position>> {
+    a_star_search(graph, start, goal, false)
+}
+
+/// A* from `start`; with `nearest`, when the goal is never reached, the
+/// path to the closest cell reached instead
+fn a_star_search<G: PathGraph + ?Sized>(graph: &G, start: Position, goal: Position, nearest: bool) -> Option<Vec<Position>> {
+    // Check if start and goal are valid
+    if !graph.is_open(&start) || (!nearest && !graph.is_open(&goal)) {
+        return None;
+    }
+
+    let mut open_set = BinaryHeap::new();
+    let mut closed_set = HashSet::new();
+    let mut came_from: HashMap<Position, Position> = HashMap::new();
+    let mut g_scores: HashMap<Position, i32> = HashMap::new();
+
+    // Initialize start node
+    let start_h = graph.heuristic(&start, &goal);
+    open_set.push(Node::new(start, 0, start_h, None));
+    g_scores.insert(start, 0);
+    let mut closest = (start_h, 0, start);
+
+    while let Some(current) = open_set.pop() {
+        let current_pos = current.position;
+
+        // Goal reached!
+        if current_pos == goal {
+            return Some(reconstruct_path(&came_from, current_pos));
+        }
+
+        // Skip if already evaluated
+        if closed_set.contains(&current_pos) {
+            continue;
+        }
+
+        closed_set.insert(current_pos);
+        if (current.h_cost, current.g_cost) < (closest.0, closest.1) {
+            closest = (current.h_cost, current.g_cost, current_pos);
+        }
+
+        // Check every cell one move away
+        for (neighbor_pos, cost) in graph.moves(current_pos) {
+            // Skip if already evaluated
+            if closed_set.contains(&neighbor_pos) {
+                continue;
+            }
+
+            // Calculate tentative g_score
+            let tentative_g = current.g_cost + cost;
+
+            // Check if this path to neighbor is better
+            let is_better = match g_scores.get(&neighbor_pos) {
+                Some(&existing_g) => tentative_g < existing_g,
+                None => true,
+            };
+
+            if is_better {
+                // This path is the best so far
+                came_from.insert(neighbor_pos, current_pos);
+                g_scores.insert(neighbor_pos, tentative_g);
+
+                let h = graph.heuristic(&neighbor_pos, &goal);
+                open_set.push(Node::new(neighbor_pos, tentative_g, h, Some(current_pos)));
+            }
+        }
+    }
+
+    // No path found
+    nearest.then(|| reconstruct_path(&came_from, closest.2))
+}
+
+/// Reconstruct the path from start to goal
+pub(crate) fn reconstruct_path(came_from: &HashMap<Position, Position>, mut current: Position) -> Vec<Position> {
+    let mut path = vec![current];
+
+    while let Some(&parent) = came_from.get(&current) {
+        path.push(parent);
+        current = parent;
+    }
+
+    path.reverse();
+    path
+}
+
+/// Find path and return as list of waypoints (simplified version)
+/// This version returns key waypoints, not every single step. Big boards
+/// route hierarchically (see `hpa`).
+pub fn find_waypoints(grid: &Grid, start: Position, goal: Position) -> Option<Vec<Position>> {
+    let full_path = crate::hpa::find_route(grid, start, goal)?;
+    Some(simplify_path(&full_path))
+}
+
+/// `find_waypoints`, or the waypoints to the reachable cell closest to the
+/// goal when it can't be reached
+pub fn find_waypoints_or_nearest(grid: &Grid, start: Position, goal: Position) -> PathResult {
+    // Big boards try the portal route first, which fails fast when the goal
+    // is walled off; small ones go straight to the single search
+    let routed = if crate::hpa::routes_hierarchically(grid) { crate::hpa::find_route(grid, start, goal) } else { None };
+    let result = match routed {
+        Some(path) => PathResult::Complete(path),
+        None => find_path_or_nearest(grid, start, goal),
+    };
+    result.map(|path| simplify_path(&path))
+}
+
+/// Reduce a step-by-step path to its corners: keep start, end, and points
+/// where direction changes
+pub fn simplify_path(full_path: &[Position]) -> Vec<Position> {
+    if full_path.len() <= 2 {
+        return full_path.to_vec();
+    }
+
+    let mut waypoints = vec![full_path[0]];
+    
+    for i in 1..full_path.len() - 1 {
+        let prev = full_path[i - 1];
+        let current = full_path[i];
+        let next = full_path[i + 1];
+        
+        // Calculate direction vectors
+        let dir1 = (current.x - prev.x, current.y - prev.y);
+        let dir2 = (next.x - current.x, next.y - current.y);
+        
+        // If direction changes, this is a waypoint
+        if dir1 != dir2 {
+            waypoints.push(current);
+        }
+    }
+    
+    waypoints.push(*full_path.last().unwrap());
+    waypoints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, CELL_SIZE};
+
+    #[test]
+    fn test_straight_path() {
+        let grid = Grid::new(10, 10);
+        let start = Position::new(0, 0);
+        let goal = Position::new(5, 0);
+
+        let path = find_path(&grid, start, goal).unwrap();
+        assert_eq!(path.len(), 6); // 0,0 -> 1,0 -> 2,0 -> 3,0 -> 4,0 -> 5,0
+        assert_eq!(path[0], start);
+        assert_eq!(path[5], goal);
+    }
+
+    #[test]
+    fn test_diagonal_path() {
+        let grid = Grid::new(10, 10);
+        let start = Position::new(0, 0);
+        let goal = Position::new(3, 3);
+
+        let path = find_path(&grid, start, goal).unwrap();
+        assert!(path.len() >= 7); // Manhattan path length
+        assert_eq!(path[0], start);
+        assert_eq!(*path.last().unwrap(), goal);
+    }
+
+    #[test]
+    fn test_hex_path_steps_diagonally() {
+        let mut grid = Grid::new(10, 10);
+        grid.set_shape(crate::GridShape::Hex);
+        let start = Position::new(0, 0);
+        let goal = Position::new(4, 4); // Four steps down-right; axial (0, 4)
+        assert_eq!(goal.to_axial(), (0, 4));
+        assert!(!grid.in_bounds(&Position::new(1, 0)));
+
+        let path = find_path(&grid, start, goal).unwrap();
+        assert_eq!(path.len(), 5);
+        assert_eq!(grid.distance(&start, &goal), 4);
+        assert!(path.windows(2).all(|step| grid.neighbors(step[0]).contains(&step[1])));
+
+        // Picking: this point is in square cell (2, 1), which isn't a hex,
+        // just inside the upper-left edge of hex (3, 1)
+        assert_eq!(grid.cell_at(2.9 * CELL_SIZE, 1.1 * CELL_SIZE), Position::new(3, 1));
+        assert_eq!(grid.cell_at(2.2 * CELL_SIZE, 0.5 * CELL_SIZE), Position::new(2, 0));
+    }
+
+    #[test]
+    fn test_weighted_path_avoids_costly_cells() {
+        let grid = Grid::new(10, 10);
+        // A band of mud across the straight route, with a dry gap at y = 4
+        let weights: HashMap<Position, i32> = (0..4).map(|y| (Position::new(3, y), 20)).collect();
+        let graph = Weighted { grid: &grid, weights: &weights };
+        let path = find_path(&graph, Position::new(0, 0), Position::new(6, 0)).unwrap();
+        assert!(path.contains(&Position::new(3, 4)));
+        assert_eq!(path.len(), 15); // Eight extra steps beat wading through mud
+    }
+
+    #[test]
+    fn test_airspace_flies_over_walls_diagonally() {
+        let mut grid = Grid::new(10, 10);
+        for y in 0..10 {
+            grid.set_walkable(&Position::new(5, y), false); // Sealed for walkers
+        }
+        assert!(find_path(&grid, Position::new(0, 0), Position::new(9, 9)).is_none());
+        let path = find_path(&Airspace { grid: &grid }, Position::new(0, 0), Position::new(9, 9)).unwrap();
+        assert_eq!(path.len(), 10); // Straight down the diagonal
+    }
+
+    #[test]
+    fn test_walled_path_goes_around_extra_walls() {
+        let grid = Grid::new(10, 10);
+        let walls: HashSet<Position> = (0..9).map(|y| Position::new(5, y)).collect();
+        let path = find_path(&Walled { grid: &grid, walls: &walls }, Position::new(0, 0), Position::new(9, 0)).unwrap();
+        assert!(path.contains(&Position::new(5, 9)));
+
+        let sealed: HashSet<Position> = (0..10).map(|y| Position::new(5, y)).collect();
+        assert!(find_path(&Walled { grid: &grid, walls: &sealed }, Position::new(0, 0), Position::new(9, 0)).is_none());
+        assert!(grid.is_walkable(&Position::new(5, 0)));
+    }
+
+    #[test]
+    fn test_path_around_obstacle() {
+        let mut grid = Grid::new(10, 10);
+        
+        // Create a wall
+        grid.set_walkable(&Position::new(2, 0), false);
+        grid.set_walkable(&Position::new(2, 1), false);
+        grid.set_walkable(&Position::new(2, 2), false);
+
+        let start = Position::new(0, 1);
+        let goal = Position::new(4, 1);
+
+        let path = find_path(&grid, start, goal).unwrap();
+        
+        // Path should go around the wall
+        assert!(path.len() > 5); // Longer than straight path
+        assert_eq!(path[0], start);
+        assert_eq!(*path.last().unwrap(), goal);
+        
+        // Verify path doesn't go through blocked cells
+        for pos in &path {
+            assert!(grid.is_walkable(pos), "Path goes through blocked cell: {:?}", pos);
+        }
+    }
+
+    #[test]
+    fn test_no_path_completely_blocked() {
+        let mut grid = Grid::new(10, 10);
+        
+        // Create a complete wall blocking the path
+        for y in 0..10 {
+            grid.set_walkable(&Position::new(5, y), false);
+        }
+
+        let start = Position::new(0, 5);
+        let goal = Position::new(9, 5);
+
+        let path = find_path(&grid, start, goal);
+        assert!(path.is_none());
+    }
+
+    #[test]
+    fn test_start_blocked() {
+        let mut grid = Grid::new(10, 10);
+        grid.set_walkable(&Position::new(0, 0), false);
+
+        let start = Position::new(0, 0);
+        let goal = Position::new(5, 5);
+
+        let path = find_path(&grid, start, goal);
+        assert!(path.is_none());
+    }
+
+    #[test]
+    fn test_goal_blocked() {
+        let mut grid = Grid::new(10, 10);
+        grid.set_walkable(&Position::new(5, 5), false);
+
+        let start = Position::new(0, 0);
+        let goal = Position::new(5, 5);
+
+        let path = find_path(&grid, start, goal);
+        assert!(path.is_none());
+    }
+
+    #[test]
+    fn test_nearest_stops_short_of_a_sealed_goal() {
+        let mut grid = Grid::new(10, 10);
+        for y in 0..10 {
+            grid.set_walkable(&Position::new(5, y), false);
+        }
+        let (start, goal) = (Position::new(0, 2), Position::new(9, 5));
+        let result = find_path_or_nearest(&grid, start, goal);
+        let PathResult::Partial(path) = &result else {
+            panic!("expected a partial path, got {:?}", result);
+        };
+        assert_eq!((path[0], *path.last().unwrap()), (start, Position::new(4, 5)));
+        assert_eq!(path.len(), 8); // The cheapest way to the wall's nearest face
+
+        // A blocked goal cell is no different; a blocked start is hopeless
+        grid.set_walkable(&Position::new(5, 5), true);
+        grid.set_walkable(&goal, false);
+        let path = find_path_or_nearest(&grid, start, goal).into_path().unwrap();
+        assert_eq!(grid.distance(path.last().unwrap(), &goal), 1);
+        assert_eq!(find_path_or_nearest(&grid, goal, start), PathResult::Unreachable);
+        assert!(find_path_or_nearest(&grid, start, Position::new(4, 2)).is_complete());
+    }
+
+    #[test]
+    fn test_nearest_waypoints_on_a_hierarchical_board() {
+        let mut grid = Grid::new(64, 64);
+        assert!(crate::hpa::routes_hierarchically(&grid));
+        let (start, goal) = (Position::new(0, 0), Position::new(63, 63));
+        assert!(find_waypoints_or_nearest(&grid, start, goal).is_complete());
+
+        for x in 0..64 {
+            grid.set_walkable(&Position::new(x, 40), false);
+        }
+        let result = find_waypoints_or_nearest(&grid, start, goal);
+        assert!(!result.is_complete());
+        assert_eq!(result.into_path().unwrap().last().unwrap().y, 39);
+    }
+
+    #[test]
+    fn test_waypoints_straight_line() {
+        let grid = Grid::new(10, 10);
+        let start = Position::new(0, 0);
+        let goal = Position::new(5, 0);
+
+        let waypoints = find_waypoints(&grid, start, goal).unwrap();
+        
+        // Straight line should only have start and end
+        assert_eq!(waypoints.len(), 2);
+        assert_eq!(waypoints[0], start);
+        assert_eq!(waypoints[1], goal);
+    }
+
+    #[test]
+    fn test_waypoints_around_corner() {
+        let mut grid = Grid::new(10, 10);
+        
+        // Create an obstacle forcing a corner
+        grid.set_walkable(&Position::new(2, 1), false);
+        grid.set_walkable(&Position::new(2, 2), false);
+
+        let start = Position::new(0, 2);
+        let goal = Position::new(4, 2);
+
+        let waypoints = find_waypoints(&grid, start, goal).unwrap();
+        
+        // Should have waypoints at direction changes
+        assert!(waypoints.len() >= 2);
+        assert_eq!(waypoints[0], start);
+        assert_eq!(*waypoints.last().unwrap(), goal);
+    }
+
+    #[test]
+    fn test_heuristic_manhattan() {
+        let pos1 = Position::new(0, 0);
+        let pos2 = Position::new(3, 4);
+        
+        assert_eq!(Grid::new(10, 10).heuristic(&pos1, &pos2), 7);
+    }
+
+    #[test]
+    fn test_teleporter_shortcut() {
+        let mut grid = Grid::new(20, 3);
+        grid.link_teleporters(Position::new(1, 0), Position::new(18, 0));
+        let path = find_path(&grid, Position::new(0, 0), Position::new(19, 0)).unwrap();
+        assert_eq!(path, vec![Position::new(0, 0), Position::new(1, 0), Position::new(18, 0), Position::new(19, 0)]);
+        assert!(grid.heuristic(&Position::new(0, 0), &Position::new(19, 0)) <= 3);
+    }
+
+    #[test]
+    fn test_one_way_tile_blocks_going_back() {
+        let mut grid = Grid::new(5, 1);
+        grid.set_one_way(&Position::new(2, 0), Some(Direction::Right));
+        assert!(find_path(&grid, Position::new(0, 0), Position::new(4, 0)).is_some());
+        assert!(find_path(&grid, Position::new(4, 0), Position::new(0, 0)).is_none());
+    }
+}
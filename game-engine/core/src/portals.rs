@@ -0,0 +1,144 @@
+//! Spawn portals. Enemies come out of a portal on the spawn cell rather than
+//! appearing from nothing, and a map can add more portals on the board that
+//! open from a later wave (`MapFile::portals`). Over the last
+//! `TELEGRAPH_TIME` seconds of each build phase, every portal the coming
+//! wave uses charges open, so the player can see where it will come from.
+//! A portal that opens for the first time is announced in the wave preview
+//! for that whole build phase.
+//!
+//! A wave's formation groups take turns between its open portals; no
+//! placement may cut any portal, open yet or not, off from the goal.
+
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::StateHasher;
+use crate::waves::{WaveManager, WavePhase};
+use crate::{GameState, Position};
+
+pub const TELEGRAPH_TIME: f32 = 3.0; // Seconds before a wave that its portals start opening
+
+/// A portal on the board besides the main spawn
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpawnPortal {
+    pub position: Position,
+    #[serde(default = "first_wave")]
+    pub opens_on: u32, // First wave that comes through it
+}
+
+fn first_wave() -> u32 {
+    1
+}
+
+/// What a portal is doing, for drawing it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PortalStage {
+    /// Shut: between waves, or not in use yet
+    Dormant,
+    /// Opening for the coming wave; `progress` runs 0 to 1
+    Telegraphing { progress: f32 },
+    Open,
+}
+
+impl SpawnPortal {
+    pub fn new(position: Position, opens_on: u32) -> Self {
+        SpawnPortal { position, opens_on }
+    }
+
+    /// Whether `wave` comes through this portal
+    pub fn serves(&self, wave: u32) -> bool {
+        wave >= self.opens_on
+    }
+
+    pub fn stage(&self, waves: &WaveManager) -> PortalStage {
+        if !self.serves(waves.wave) {
+            return PortalStage::Dormant;
+        }
+        match waves.phase {
+            WavePhase::Combat => PortalStage::Open,
+            WavePhase::Build { countdown } if countdown <= TELEGRAPH_TIME => {
+                PortalStage::Telegraphing { progress: 1.0 - countdown.max(0.0) / TELEGRAPH_TIME }
+            }
+            WavePhase::Build { .. } => PortalStage::Dormant,
+        }
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.position(self.position);
+        hasher.u32(self.opens_on);
+    }
+}
+
+impl GameState {
+    /// The main spawn's portal, which every wave uses, then the map's
+    pub fn all_portals(&self) -> impl Iterator<Item = SpawnPortal> + '_ {
+        std::iter::once(SpawnPortal::new(self.spawn_point, first_wave())).chain(self.portals.iter().copied())
+    }
+
+    /// Where `wave`'s enemies come from, main spawn first
+    pub fn spawn_points(&self, wave: u32) -> Vec<Position> {
+        self.all_portals().filter(|portal| portal.serves(wave)).map(|portal| portal.position).collect()
+    }
+
+    /// Portals that open for the first time on `wave`, for the preview
+    pub fn portals_opening_on(&self, wave: u32) -> Vec<Position> {
+        self.portals.iter().filter(|portal| portal.opens_on == wave).map(|portal| portal.position).collect()
+    }
+
+    pub fn is_portal(&self, cell: &Position) -> bool {
+        self.all_portals().any(|portal| portal.position == *cell) || self.lanes.iter().any(|lane| lane.spawn == *cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType};
+
+    fn game_with_portal(opens_on: u32) -> Game {
+        let mut game = Game::new();
+        game.state.portals.push(SpawnPortal::new(Position::new(6, 1), opens_on));
+        game
+    }
+
+    #[test]
+    fn test_portals_telegraph_then_open_for_their_waves() {
+        let mut game = game_with_portal(2);
+        let portal = game.state.portals[0];
+        let main = game.state.all_portals().next().unwrap();
+        assert_eq!(portal.stage(&game.state.waves), PortalStage::Dormant);
+
+        game.state.waves.phase = WavePhase::Build { countdown: TELEGRAPH_TIME / 2.0 };
+        assert_eq!(main.stage(&game.state.waves), PortalStage::Telegraphing { progress: 0.5 });
+        assert_eq!(portal.stage(&game.state.waves), PortalStage::Dormant); // Not its wave yet
+
+        game.state.waves.call_early();
+        assert_eq!(main.stage(&game.state.waves), PortalStage::Open);
+        assert_eq!(game.state.spawn_points(1), vec![game.state.spawn_point]);
+        assert_eq!(game.state.spawn_points(2), vec![game.state.spawn_point, Position::new(6, 1)]);
+        assert_eq!(game.state.portals_opening_on(2), vec![Position::new(6, 1)]);
+    }
+
+    #[test]
+    fn test_groups_take_turns_between_open_portals() {
+        let mut game = game_with_portal(1);
+        game.state.waves.call_early();
+        for _ in 0..30 {
+            game.update(1.0 / 30.0); // Long enough for two groups of a column
+        }
+        let mut starts: Vec<Position> = game.state.enemies.values().map(|enemy| enemy.path[0]).collect();
+        starts.sort_by_key(|cell| (cell.x, cell.y));
+        assert_eq!(starts, vec![game.state.spawn_point, Position::new(6, 1)]);
+    }
+
+    #[test]
+    fn test_cannot_build_on_or_cut_off_a_closed_portal() {
+        let mut game = game_with_portal(5);
+        game.state.gold = 10_000;
+        let tower = BuildTool::Tower(TowerType::Basic);
+        assert!(!game.build(tower, Position::new(6, 1)));
+        assert!(game.build(tower, Position::new(5, 1)));
+        assert!(game.build(tower, Position::new(7, 1)));
+        assert!(game.build(tower, Position::new(6, 0)));
+        assert!(!game.build(tower, Position::new(6, 2))); // Last way out
+    }
+}
@@ -0,0 +1,164 @@
+//! Switching towers off, and the optional power grid. Any tower can be
+//! switched off: it stops tracking and firing until switched back on.
+//!
+//! With the power grid enabled, towers also need power. Each generator
+//! powers the `GENERATOR_CAPACITY` nearest switched-on towers within
+//! `POWER_RADIUS` cells, and unpowered towers hold fire. Every switched-on
+//! tower costs `UPKEEP` gold when a wave starts, so switching spare towers
+//! off saves their upkeep.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::checksum::StateHasher;
+use crate::actions::ActionError;
+use crate::{GameState, Position, TowerId, CELL_SIZE};
+
+pub const GENERATOR_COST: i32 = 80;
+pub const POWER_RADIUS: f32 = 3.5; // Cells from the generator to a tower's center
+pub const GENERATOR_CAPACITY: usize = 4;
+pub const UPKEEP: i32 = 5; // Gold per switched-on tower per wave
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PowerGrid {
+    pub enabled: bool,
+    pub generators: Vec<Position>,
+}
+
+impl PowerGrid {
+    pub fn new(enabled: bool) -> Self {
+        PowerGrid { enabled, generators: Vec::new() }
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.bool(self.enabled);
+        hasher.usize(self.generators.len());
+        for generator in &self.generators {
+            hasher.position(*generator);
+        }
+    }
+}
+
+impl GameState {
+    pub fn set_tower_enabled(&mut self, tower_id: TowerId, enabled: bool) -> Result<(), ActionError> {
+        let tower = self.towers.get_mut(tower_id).ok_or(ActionError::NoSuchTower)?;
+        tower.disabled = !enabled;
+        if !enabled {
+            tower.target_id = None;
+        }
+        debug!(?tower_id, enabled, "tower switched");
+        Ok(())
+    }
+
+    pub fn generator_at(&self, position: &Position) -> bool {
+        self.power.generators.contains(position)
+    }
+
+    /// Build a generator; only while the power grid is enabled
+    pub fn place_generator(&mut self, position: Position) -> Result<(), ActionError> {
+        let price = self.rules.price(GENERATOR_COST);
+        if !self.power.enabled {
+            return Err(ActionError::NotAllowed);
+        }
+        let refusal = self.solid_cell_refusal(&position);
+        if let Some(err) = refusal.or(self.is_goal(&position).then_some(ActionError::NotBuildable)) {
+            debug!(?position, ?err, "generator rejected");
+            return Err(err);
+        }
+        if self.gold < price {
+            return Err(ActionError::InsufficientGold { price, gold: self.gold });
+        }
+        if self.would_block_path(&[position]) {
+            debug!(?position, "generator rejected: would block the path");
+            return Err(ActionError::WouldBlockPath);
+        }
+        self.gold -= price;
+        self.power.generators.push(position);
+        self.grid.set_walkable(&position, false);
+        info!(?position, gold = self.gold, "generator placed");
+        self.repath_through(&[position]);
+        Ok(())
+    }
+
+    /// Towers that may fire: every switched-on tower without the power
+    /// grid, otherwise only those a generator has capacity for
+    pub fn powered_towers(&self) -> HashSet<TowerId> {
+        let enabled = self.towers.values().filter(|tower| !tower.disabled);
+        if !self.power.enabled {
+            return enabled.map(|tower| tower.id).collect();
+        }
+        let enabled: Vec<_> = enabled.collect();
+        let mut powered = HashSet::new();
+        for generator in &self.power.generators {
+            let (gx, gy) = generator.to_world();
+            let (gx, gy) = (gx + CELL_SIZE / 2.0, gy + CELL_SIZE / 2.0);
+            let mut in_reach: Vec<_> = enabled
+                .iter()
+                .filter(|tower| !powered.contains(&tower.id))
+                .map(|tower| {
+                    let (tx, ty) = tower.world_position();
+                    ((tx - gx).hypot(ty - gy), (tower.position.y, tower.position.x), tower.id)
+                })
+                .filter(|(distance, _, _)| *distance <= POWER_RADIUS * CELL_SIZE)
+                .collect();
+            in_reach.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+            powered.extend(in_reach.into_iter().take(GENERATOR_CAPACITY).map(|(_, _, id)| id));
+        }
+        powered
+    }
+
+    /// Charge wave upkeep for switched-on towers, as far as gold allows.
+    /// Returns what was paid.
+    pub(crate) fn charge_upkeep(&mut self) -> i32 {
+        if !self.power.enabled {
+            return 0;
+        }
+        let active = self.towers.values().filter(|tower| !tower.disabled).count() as i32;
+        let paid = self.rules.price(active * UPKEEP).min(self.gold);
+        self.gold -= paid;
+        info!(towers = active, paid, gold = self.gold, "upkeep charged");
+        paid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, TowerType};
+
+    #[test]
+    fn test_switched_off_towers_hold_fire() {
+        let mut game = Game::new();
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(6, 6));
+        let tower_id = game.state.tower_at(&Position::new(6, 6)).unwrap().id;
+        assert!(game.state.powered_towers().contains(&tower_id));
+        assert!(game.state.set_tower_enabled(tower_id, false).is_ok());
+        assert!(game.state.powered_towers().is_empty());
+    }
+
+    #[test]
+    fn test_generators_power_nearby_towers_and_upkeep_is_charged() {
+        let mut game = Game::new();
+        game.state.power = PowerGrid::new(true);
+        game.state.gold = 1000;
+        for x in [2, 4, 6, 8, 10, 12] {
+            game.build(BuildTool::Tower(TowerType::Basic), Position::new(x, 5));
+        }
+        assert!(game.state.powered_towers().is_empty());
+
+        assert!(game.build(BuildTool::Generator, Position::new(7, 4)));
+        let powered = game.state.powered_towers();
+        assert_eq!(powered.len(), GENERATOR_CAPACITY);
+        let far = game.state.tower_at(&Position::new(2, 5)).unwrap().id;
+        assert!(!powered.contains(&far));
+
+        assert!(game.state.set_tower_enabled(far, false).is_ok());
+        let gold = game.state.gold;
+        while game.state.waves.is_build_phase() {
+            game.update(0.5);
+        }
+        assert_eq!(game.state.gold, gold - 5 * UPKEEP);
+    }
+}
@@ -0,0 +1,138 @@
+//! Starting loadouts: a named list of towers, each placed relative to the
+//! spawn, that a player saves from a layout they like and puts down in one
+//! go before the first wave of another run. Every tower goes through
+//! `Game::try_build` like any other placement, so the rules, the price and the
+//! path check all apply. A preset is all or nothing: if it costs more gold
+//! than there is, or any tower can't be placed, nothing is.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::ActionError;
+use crate::{BuildTool, Game, GameState, Position, TowerType};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub towers: Vec<PresetTower>,
+}
+
+/// A tower and its corner cell, counted from the spawn (or, in a copied
+/// group, from the group's top-left; see `groups`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PresetTower {
+    pub tower_type: TowerType,
+    pub dx: i32,
+    pub dy: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PresetError {
+    Started, // The first wave has begun
+    TooExpensive { cost: i32, gold: i32 },
+    Blocked { tower_type: TowerType, position: Position, reason: ActionError }, // The first tower that couldn't go down
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Started => write!(f, "presets only go down before the first wave"),
+            PresetError::TooExpensive { cost, gold } => write!(f, "costs ${} with ${} to spend", cost, gold),
+            PresetError::Blocked { tower_type, position, reason } => {
+                write!(f, "{:?} tower can't go at ({}, {}): {}", tower_type, position.x, position.y, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+impl Preset {
+    /// The towers standing now, as a preset
+    pub fn capture(name: &str, state: &GameState) -> Self {
+        let spawn = state.spawn_point;
+        let mut towers: Vec<PresetTower> = state
+            .towers
+            .values()
+            .map(|tower| PresetTower {
+                tower_type: tower.tower_type,
+                dx: tower.position.x - spawn.x,
+                dy: tower.position.y - spawn.y,
+            })
+            .collect();
+        // Placement order decides which tower is refused first; keep it stable
+        towers.sort_by_key(|tower| (tower.dy, tower.dx));
+        Preset { name: name.to_string(), towers }
+    }
+
+    /// Gold it takes under this run's rules
+    pub fn cost(&self, state: &GameState) -> i32 {
+        self.towers.iter().map(|tower| state.rules.price(tower.tower_type.cost())).sum()
+    }
+}
+
+impl Game {
+    /// Whether a preset can still go down: the first wave hasn't started
+    pub fn before_first_wave(&self) -> bool {
+        self.state.waves.wave == 1 && self.state.waves.is_build_phase()
+    }
+
+    /// Place every tower in `preset`, or none of them. Returns how many
+    /// went down.
+    pub fn apply_preset(&mut self, preset: &Preset) -> Result<usize, PresetError> {
+        if !self.before_first_wave() {
+            return Err(PresetError::Started);
+        }
+        let cost = preset.cost(&self.state);
+        if cost > self.state.gold {
+            return Err(PresetError::TooExpensive { cost, gold: self.state.gold });
+        }
+        let before = self.snapshot();
+        let spawn = self.state.spawn_point;
+        for tower in &preset.towers {
+            let position = Position::new(spawn.x + tower.dx, spawn.y + tower.dy);
+            if let Err(reason) = self.try_build(BuildTool::Tower(tower.tower_type), position) {
+                self.restore(&before);
+                return Err(PresetError::Blocked { tower_type: tower.tower_type, position, reason });
+            }
+        }
+        Ok(preset.towers.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_go_down_whole_or_not_at_all() {
+        let mut game = Game::new();
+        assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(5, 3)));
+        assert!(game.build(BuildTool::Tower(TowerType::Slow), Position::new(7, 6)));
+        let preset = Preset::capture("Opening", &game.state);
+        assert_eq!(preset.cost(&game.state), TowerType::Basic.cost() + TowerType::Slow.cost());
+
+        let mut fresh = Game::new();
+        assert_eq!(fresh.apply_preset(&preset), Ok(2));
+        assert_eq!(Preset::capture("Opening", &fresh.state), preset);
+
+        // One tower off the board: nothing is placed and no gold is spent
+        let mut wild = preset.clone();
+        wild.towers.push(PresetTower { tower_type: TowerType::Basic, dx: -50, dy: 0 });
+        let mut blocked = Game::new();
+        let gold = blocked.state.gold;
+        let spawn = blocked.state.spawn_point;
+        let position = Position::new(spawn.x - 50, spawn.y);
+        let reason = ActionError::OutOfBounds;
+        let off_board = PresetError::Blocked { tower_type: TowerType::Basic, position, reason };
+        assert_eq!(blocked.apply_preset(&wild), Err(off_board));
+        assert!(blocked.state.towers.is_empty() && blocked.state.gold == gold);
+
+        blocked.state.gold = 10;
+        assert!(matches!(blocked.apply_preset(&preset), Err(PresetError::TooExpensive { .. })));
+        assert!(blocked.state.call_next_wave().is_ok());
+        blocked.update(1.0 / 30.0);
+        assert_eq!(blocked.apply_preset(&preset), Err(PresetError::Started));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{EnemyId, TowerId};
+
+const SMOOTHING: f64 = 0.1; // Weight of the newest sample in the moving average
+const SPLASH_MARKER_TIME: f32 = 0.6;
+
+/// Seconds since the first call. `std::time::Instant` isn't available in the
+/// browser, so web builds should install their own clock with `set_clock`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn std_clock() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn std_clock() -> f64 {
+    0.0
+}
+
+/// Per-system update timings, smoothed so the overlay is readable
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    timings: Vec<(&'static str, f64)>, // (system, milliseconds), in update order
+    clock: fn() -> f64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            timings: Vec::new(),
+            clock: std_clock,
+        }
+    }
+
+    /// Use a different time source (seconds, any epoch)
+    pub fn set_clock(&mut self, clock: fn() -> f64) {
+        self.clock = clock;
+    }
+
+    pub fn now(&self) -> f64 {
+        (self.clock)()
+    }
+
+    /// Record the time since `since` against `system` and return the current
+    /// time, so consecutive systems can be chained:
+    /// `mark = profiler.lap("towers", mark);`
+    pub fn lap(&mut self, system: &'static str, since: f64) -> f64 {
+        let now = self.now();
+        self.record(system, (now - since) * 1000.0);
+        now
+    }
+
+    pub fn record(&mut self, system: &'static str, millis: f64) {
+        match self.timings.iter_mut().find(|(name, _)| *name == system) {
+            Some((_, average)) => *average += (millis - *average) * SMOOTHING,
+            None => self.timings.push((system, millis)),
+        }
+    }
+
+    pub fn timings(&self) -> &[(&'static str, f64)] {
+        &self.timings
+    }
+
+    pub fn total_millis(&self) -> f64 {
+        self.timings.iter().map(|(_, millis)| millis).sum()
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Why a tower picked the enemy it fired at this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetDecision {
+    pub tower_id: TowerId,
+    pub from: (f32, f32),
+    pub enemy_id: EnemyId,
+    pub to: (f32, f32),
+    pub enemy_waypoint: usize, // Path progress used to rank candidates
+    pub candidates: usize,     // Enemies that were in range
+}
+
+/// Splash damage area at the moment of impact
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplashMarker {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32, // World units
+    pub enemies_hit: usize,
+    pub lifetime: f32,
+}
+
+/// Targeting decisions exposed by the simulation while the targeting view is
+/// on. Nothing is recorded while `enabled` is false.
+#[derive(Debug, Clone, Default)]
+pub struct TargetingLog {
+    pub enabled: bool,
+    pub decisions: Vec<TargetDecision>, // Towers that fired during the last update
+    pub splashes: Vec<SplashMarker>,
+}
+
+impl TargetingLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new tick: forget last tick's shots and age splash markers
+    pub fn begin_tick(&mut self, delta: f32) {
+        self.decisions.clear();
+        self.splashes.retain_mut(|splash| {
+            splash.lifetime -= delta;
+            splash.lifetime > 0.0
+        });
+    }
+
+    pub fn record_decision(&mut self, decision: TargetDecision) {
+        if self.enabled {
+            self.decisions.push(decision);
+        }
+    }
+
+    pub fn record_splash(&mut self, x: f32, y: f32, radius: f32, enemies_hit: usize) {
+        if self.enabled {
+            self.splashes.push(SplashMarker {
+                x,
+                y,
+                radius,
+                enemies_hit,
+                lifetime: SPLASH_MARKER_TIME,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiler_keeps_update_order_and_smooths() {
+        let mut profiler = Profiler::new();
+        profiler.record("waves", 1.0);
+        profiler.record("towers", 2.0);
+        profiler.record("waves", 11.0);
+
+        let timings = profiler.timings();
+        assert_eq!(timings[0].0, "waves");
+        assert_eq!(timings[1].0, "towers");
+        assert!((timings[0].1 - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_targeting_log_only_records_when_enabled() {
+        let mut log = TargetingLog::new();
+        log.record_splash(0.0, 0.0, 10.0, 2);
+        assert!(log.splashes.is_empty());
+
+        log.enabled = true;
+        log.record_splash(0.0, 0.0, 10.0, 2);
+        log.begin_tick(SPLASH_MARKER_TIME / 2.0);
+        assert_eq!(log.splashes.len(), 1);
+        log.begin_tick(SPLASH_MARKER_TIME);
+        assert!(log.splashes.is_empty());
+    }
+}
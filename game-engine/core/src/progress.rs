@@ -0,0 +1,75 @@
+//! How far each enemy still has to walk. Paths are simplified into
+//! waypoints with legs of any length, so the waypoint index alone doesn't
+//! say who is in front; this sums the real remaining legs instead. Towers
+//! target the enemy with the least path left, and `leaks` uses the same
+//! distance for leak prediction.
+
+use crate::{Enemy, GameState, Grid, CELL_SIZE};
+
+impl Enemy {
+    /// Pixels left to walk: to the next waypoint, then along every later
+    /// leg. Teleporter jumps count for nothing.
+    pub fn measure_remaining(&self, grid: &Grid) -> f32 {
+        let Some(next) = self.path.get(self.current_waypoint) else {
+            return 0.0;
+        };
+        let (x, y) = next.to_world();
+        let mut remaining = (x + CELL_SIZE / 2.0 - self.x).hypot(y + CELL_SIZE / 2.0 - self.y);
+        for leg in self.path[self.current_waypoint..].windows(2) {
+            if grid.teleporter(&leg[0]) != Some(leg[1]) {
+                remaining += ((leg[1].x - leg[0].x) as f32).hypot((leg[1].y - leg[0].y) as f32) * CELL_SIZE;
+            }
+        }
+        remaining
+    }
+
+    /// Seconds until it reaches the goal at its current speed, as of the
+    /// last progress refresh
+    pub fn time_to_goal(&self, pace: f32) -> f32 {
+        let speed = self.speed * self.slow_multiplier * pace;
+        if speed <= f32::EPSILON {
+            return f32::INFINITY;
+        }
+        self.distance_to_goal / speed
+    }
+}
+
+impl GameState {
+    /// Re-measure every enemy's remaining path
+    pub(crate) fn refresh_progress(&mut self) {
+        for enemy in self.enemies.values_mut() {
+            enemy.distance_to_goal = enemy.measure_remaining(&self.grid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BuildTool, EnemyId, Game, Position, TowerType, CELL_SIZE};
+
+    fn place_enemy(game: &mut Game, path: &[(i32, i32)], next: usize) -> EnemyId {
+        let enemy_id = game.state.spawn_enemy_with(|_| {}).unwrap();
+        let enemy = &mut game.state.enemies[enemy_id];
+        enemy.path = path.iter().map(|&(x, y)| Position::new(x, y)).collect();
+        enemy.current_waypoint = next;
+        let (x, y) = enemy.path[next - 1].to_world();
+        (enemy.x, enemy.y) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+        enemy_id
+    }
+
+    #[test]
+    fn test_first_means_least_path_left() {
+        let mut game = Game::new();
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(6, 6));
+        let tower_id = game.state.tower_at(&Position::new(6, 6)).unwrap().id;
+        // Later waypoint, but a long leg still ahead
+        let many_legs = place_enemy(&mut game, &[(7, 4), (7, 5), (7, 6), (7, 7), (19, 7)], 4);
+        let short_way = place_enemy(&mut game, &[(5, 7), (5, 5)], 1);
+
+        game.update(1.0 / 30.0);
+        let enemies = &game.state.enemies;
+        assert!((enemies[many_legs].distance_to_goal - 12.0 * CELL_SIZE).abs() < 2.0);
+        assert!((enemies[short_way].distance_to_goal - 2.0 * CELL_SIZE).abs() < 2.0);
+        assert_eq!(game.state.towers[tower_id].target_id, Some(short_way));
+    }
+}
@@ -0,0 +1,190 @@
+//! Shots in flight: how they aim, steer and hit.
+
+use crate::stat_sheet::{Stat, StatSheet};
+use crate::{EnemyId, ProjectileId, TowerId, TowerType, CELL_SIZE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guidance {
+    Homing,   // Re-aims at the target's lead point every frame
+    Dumbfire, // Flies to the intercept point picked at fire time and can miss
+}
+
+/// Closest approach of point `p` to the segment `a`-`b`, as (fraction along
+/// the segment, distance)
+pub fn segment_distance(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> (f32, f32) {
+    let (sx, sy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = sx * sx + sy * sy;
+    let t = if length_sq < f32::EPSILON {
+        0.0
+    } else {
+        (((p.0 - a.0) * sx + (p.1 - a.1) * sy) / length_sq).clamp(0.0, 1.0)
+    };
+    let (dx, dy) = (a.0 + sx * t - p.0, a.1 + sy * t - p.1);
+    (t, (dx * dx + dy * dy).sqrt())
+}
+
+/// `angle` brought into (-PI, PI]
+pub fn wrap_angle(angle: f32) -> f32 {
+    let wrapped = angle.rem_euclid(std::f32::consts::TAU);
+    if wrapped > std::f32::consts::PI { wrapped - std::f32::consts::TAU } else { wrapped }
+}
+
+/// Point where a projectile fired from `from` at `speed` meets a target at
+/// `target` moving with constant `velocity`. Falls back to the target's
+/// current position when it can't be caught.
+pub fn lead_target(from: (f32, f32), target: (f32, f32), velocity: (f32, f32), speed: f32) -> (f32, f32) {
+    let (rx, ry) = (target.0 - from.0, target.1 - from.1);
+    let (vx, vy) = velocity;
+
+    // Solve |r + v*t| = speed*t for the smallest positive t
+    let a = vx * vx + vy * vy - speed * speed;
+    let b = 2.0 * (rx * vx + ry * vy);
+    let c = rx * rx + ry * ry;
+
+    let time = if a.abs() < f32::EPSILON {
+        if b.abs() < f32::EPSILON { None } else { Some(-c / b) }
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            None
+        } else {
+            let root = discriminant.sqrt();
+            let t1 = (-b - root) / (2.0 * a);
+            let t2 = (-b + root) / (2.0 * a);
+            [t1, t2].into_iter().filter(|t| *t > 0.0).reduce(f32::min)
+        }
+    };
+
+    match time {
+        Some(t) if t > 0.0 => (target.0 + vx * t, target.1 + vy * t),
+        _ => target,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Projectile {
+    pub id: ProjectileId,
+    pub tower_type: TowerType,
+    pub x: f32,
+    pub y: f32,
+    pub target_id: EnemyId,
+    pub target_x: f32,
+    pub target_y: f32,
+    pub damage: i32,
+    pub origin: (f32, f32), // Where the tower fired from, for range falloff
+    pub reach: f32,         // The tower's range in world units when it fired
+    pub speed: f32,
+    pub lifetime: f32, // For safety, remove after X seconds
+    pub guidance: Guidance,
+    pub max_hits: usize,
+    pub hits_first: bool, // Collides with any enemy in its path, not just the target
+    pub hit_enemies: Vec<EnemyId>, // Already damaged by this shot; piercing never hits twice
+    pub target_lost: bool, // Target is gone; flies on to its last position
+    pub tower_id: Option<TowerId>, // The tower that fired it, credited with its kills
+}
+
+impl Projectile {
+    /// A shot from a tower with the given stats, heading for `aim`
+    pub fn new(
+        id: ProjectileId,
+        tower_type: TowerType,
+        stats: &StatSheet,
+        (start_x, start_y): (f32, f32),
+        target_id: EnemyId,
+        (target_x, target_y): (f32, f32),
+    ) -> Self {
+        let max_hits = tower_type.max_hits();
+        let reach = stats.get(Stat::Range) * CELL_SIZE;
+        let (target_x, target_y) = if max_hits > 1 {
+            // Piercing shots keep going through the aim point to full range
+            let (dx, dy) = (target_x - start_x, target_y - start_y);
+            let distance = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+            (start_x + dx / distance * reach, start_y + dy / distance * reach)
+        } else {
+            (target_x, target_y)
+        };
+
+        Projectile {
+            id,
+            tower_type,
+            x: start_x,
+            y: start_y,
+            target_id,
+            target_x,
+            target_y,
+            damage: stats.get(Stat::Damage).round() as i32,
+            origin: (start_x, start_y),
+            reach,
+            speed: stats.get(Stat::ProjectileSpeed),
+            lifetime: 5.0, // 5 seconds max
+            guidance: tower_type.guidance(),
+            max_hits,
+            hits_first: tower_type.hits_first_in_path(),
+            hit_enemies: Vec::new(),
+            target_lost: false,
+            tower_id: None,
+        }
+    }
+
+    pub fn update(&mut self, delta: f32, enemy_pos: Option<(f32, f32)>) -> bool {
+        self.lifetime -= delta;
+        
+        // Homing shots follow the enemy; dumbfire ones keep their aim point
+        match enemy_pos {
+            Some((new_x, new_y)) if self.guidance == Guidance::Homing => {
+                self.target_x = new_x;
+                self.target_y = new_y;
+            }
+            Some(_) => {}
+            None => self.target_lost = true,
+        }
+
+        // Calculate direction to target
+        let dx = self.target_x - self.x;
+        let dy = self.target_y - self.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+
+        // Check if reached target
+        if distance < 5.0 || self.lifetime <= 0.0 {
+            return false; // Projectile should be removed
+        }
+
+        // Move toward target
+        let move_distance = self.speed * delta;
+        if move_distance >= distance {
+            self.x = self.target_x;
+            self.y = self.target_y;
+            return false; // Reached target
+        }
+
+        let direction_x = dx / distance;
+        let direction_y = dy / distance;
+        
+        self.x += direction_x * move_distance;
+        self.y += direction_y * move_distance;
+
+        true // Continue moving
+    }
+
+    /// Damage for a hit at (x, y), after the tower type's range falloff
+    pub fn damage_at(&self, x: f32, y: f32) -> i32 {
+        let falloff = self.tower_type.range_falloff();
+        if falloff <= 0.0 {
+            return self.damage;
+        }
+        let (dx, dy) = (x - self.origin.0, y - self.origin.1);
+        let share = ((dx * dx + dy * dy).sqrt() / self.reach.max(f32::EPSILON)).min(1.0);
+        ((self.damage as f32 * (1.0 - falloff * share)).round() as i32).max(1)
+    }
+
+    pub fn is_piercing(&self) -> bool {
+        self.max_hits > 1
+    }
+
+    pub fn has_hit(&self) -> bool {
+        let dx = self.target_x - self.x;
+        let dy = self.target_y - self.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        distance < 10.0
+    }
+}
@@ -0,0 +1,428 @@
+//! Messages between a headless server and its clients. Clients send
+//! `Command`s; the server runs the only simulation and answers with a full
+//! `Snapshot` when a client joins (and every so often as a keyframe), and
+//! with `Delta`s in between. A delta carries only the entities whose
+//! visible state changed plus the ids that disappeared.
+//!
+//! Each client `Ack`s the ticks it has, and its deltas are made against the
+//! newest tick it acked, or its last keyframe until it acks one. So a
+//! client that falls behind still gets deltas it can apply, only bigger
+//! ones. `Baselines` keeps the snapshots a client may get deltas against.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{unless, ActionError};
+use crate::abilities::AbilityType;
+use crate::formations::{Formation, Roster};
+use crate::gunners::EnemyKind;
+use crate::status_effects::EffectKind;
+use crate::{BuildTool, EnemyId, Game, Position, ProjectileId, TowerId, TowerType};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Command {
+    Build { tool: BuildTool, position: Position },
+    SellTower { tower_id: TowerId },
+    MergeTowers { source_id: TowerId, target_id: TowerId },
+    CallNextWave,
+    CastAbility { ability: AbilityType, target: Option<(f32, f32)> },
+    SetPaused { paused: bool },
+    ClearObstacle { position: Position },
+    UndoClear,
+    SpawnEnemy { health_scale: f32, speed_scale: f32 }, // Sandbox only
+    ClearWave,                                          // Sandbox only
+    LockTarget { tower_id: TowerId, enemy_id: Option<EnemyId> },
+    IgnoreCell { tower_id: TowerId, position: Position, ignored: bool },
+    ResetTargeting { tower_id: TowerId },
+    SetTowerEnabled { tower_id: TowerId, enabled: bool },
+    RepairTower { tower_id: TowerId },
+    ComposeWave { roster: Roster, formation: Formation }, // Attack mode only
+}
+
+/// "I have the snapshot for `ack`": sent by clients as `{"ack": 120}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ack {
+    pub ack: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    Snapshot(Snapshot),
+    Delta(Delta),
+    Rejected {
+        command: Command,
+        #[serde(default)]
+        reason: Option<ActionError>, // Why the game refused it; spectators are NotAllowed
+    },
+}
+
+// ============================================================================
+// SNAPSHOTS
+// ============================================================================
+
+/// Entities a client sees, keyed by their id
+pub trait View: Clone + PartialEq {
+    type Id: Copy + Eq + Hash + Ord;
+    fn id(&self) -> Self::Id;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TowerView {
+    pub id: TowerId,
+    pub tower_type: TowerType,
+    pub position: Position,
+    pub rotation: f32,
+    pub health: i32,
+    pub max_health: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnemyView {
+    pub id: EnemyId,
+    pub kind: EnemyKind,
+    pub x: f32,
+    pub y: f32,
+    pub health: i32,
+    pub max_health: i32,
+    pub armor: f32,               // Left after shredding
+    pub debuffs: Vec<EffectKind>, // Shred and amplify, for icons
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectileView {
+    pub id: ProjectileId,
+    pub tower_type: TowerType,
+    pub x: f32,
+    pub y: f32,
+}
+
+impl View for TowerView {
+    type Id = TowerId;
+    fn id(&self) -> TowerId {
+        self.id
+    }
+}
+
+impl View for EnemyView {
+    type Id = EnemyId;
+    fn id(&self) -> EnemyId {
+        self.id
+    }
+}
+
+impl View for ProjectileView {
+    type Id = ProjectileId;
+    fn id(&self) -> ProjectileId {
+        self.id
+    }
+}
+
+/// Everything a client needs to draw one tick. Entity lists are sorted by id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub checksum: u64, // Game::checksum on the server, for clients that simulate too
+    pub gold: i32,
+    pub health: i32,
+    pub wave: u32,
+    pub paused: bool,
+    pub towers: Vec<TowerView>,
+    pub enemies: Vec<EnemyView>,
+    pub projectiles: Vec<ProjectileView>,
+}
+
+impl Snapshot {
+    pub fn capture(game: &Game) -> Self {
+        let state = &game.state;
+        let effect_rules = state.content.effect_rules();
+        let mut snapshot = Snapshot {
+            tick: game.tick,
+            checksum: game.checksum(),
+            gold: state.gold,
+            health: state.health,
+            wave: state.waves.wave,
+            paused: state.paused,
+            towers: state
+                .towers
+                .values()
+                .map(|tower| TowerView {
+                    id: tower.id,
+                    tower_type: tower.tower_type,
+                    position: tower.position,
+                    rotation: tower.rotation,
+                    health: tower.health(),
+                    max_health: tower.max_health(),
+                })
+                .collect(),
+            enemies: state
+                .enemies
+                .values()
+                .map(|enemy| EnemyView {
+                    id: enemy.id,
+                    kind: enemy.kind,
+                    x: enemy.x,
+                    y: enemy.y,
+                    health: enemy.health,
+                    max_health: enemy.max_health,
+                    armor: enemy.armor_left(&effect_rules),
+                    debuffs: [EffectKind::Shred, EffectKind::Amplify]
+                        .into_iter()
+                        .filter(|kind| enemy.effects.has(*kind))
+                        .collect(),
+                })
+                .collect(),
+            projectiles: game
+                .projectiles
+                .values()
+                .map(|projectile| ProjectileView {
+                    id: projectile.id,
+                    tower_type: projectile.tower_type,
+                    x: projectile.x,
+                    y: projectile.y,
+                })
+                .collect(),
+        };
+        // Slot order is usually id order already, but not after a slot is reused
+        snapshot.towers.sort_by_key(View::id);
+        snapshot.enemies.sort_by_key(View::id);
+        snapshot.projectiles.sort_by_key(View::id);
+        snapshot
+    }
+
+    /// What changed since `base`
+    pub fn delta_from(&self, base: &Snapshot) -> Delta {
+        Delta {
+            base_tick: base.tick,
+            tick: self.tick,
+            checksum: self.checksum,
+            gold: self.gold,
+            health: self.health,
+            wave: self.wave,
+            paused: self.paused,
+            towers: EntityDelta::between(&base.towers, &self.towers),
+            enemies: EntityDelta::between(&base.enemies, &self.enemies),
+            projectiles: EntityDelta::between(&base.projectiles, &self.projectiles),
+        }
+    }
+
+    /// Bring this snapshot up to date. Fails if the delta was made against
+    /// a different tick; the client should wait for the next keyframe.
+    pub fn apply(&mut self, delta: &Delta) -> Result<(), u64> {
+        if delta.base_tick != self.tick {
+            return Err(self.tick);
+        }
+        self.tick = delta.tick;
+        self.checksum = delta.checksum;
+        self.gold = delta.gold;
+        self.health = delta.health;
+        self.wave = delta.wave;
+        self.paused = delta.paused;
+        delta.towers.apply(&mut self.towers);
+        delta.enemies.apply(&mut self.enemies);
+        delta.projectiles.apply(&mut self.projectiles);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delta {
+    pub base_tick: u64, // Tick of the snapshot this applies to
+    pub tick: u64,
+    pub checksum: u64,
+    pub gold: i32,
+    pub health: i32,
+    pub wave: u32,
+    pub paused: bool,
+    pub towers: EntityDelta<TowerView>,
+    pub enemies: EntityDelta<EnemyView>,
+    pub projectiles: EntityDelta<ProjectileView>,
+}
+
+impl Delta {
+    pub fn is_empty(&self) -> bool {
+        self.towers.is_empty() && self.enemies.is_empty() && self.projectiles.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(bound(serialize = "V: Serialize, V::Id: Serialize", deserialize = "V: Deserialize<'de>, V::Id: Deserialize<'de>"))]
+pub struct EntityDelta<V: View> {
+    pub changed: Vec<V>, // New or different since the base
+    pub removed: Vec<V::Id>,
+}
+
+impl<V: View> EntityDelta<V> {
+    fn between(base: &[V], current: &[V]) -> Self {
+        let old: HashMap<V::Id, &V> = base.iter().map(|view| (view.id(), view)).collect();
+        let changed = current
+            .iter()
+            .filter(|view| old.get(&view.id()) != Some(view))
+            .cloned()
+            .collect();
+
+        let alive: HashSet<V::Id> = current.iter().map(View::id).collect();
+        let removed = base.iter().map(View::id).filter(|id| !alive.contains(id)).collect();
+        EntityDelta { changed, removed }
+    }
+
+    fn apply(&self, views: &mut Vec<V>) {
+        views.retain(|view| !self.removed.contains(&view.id()));
+        for view in &self.changed {
+            match views.binary_search_by_key(&view.id(), View::id) {
+                Ok(index) => views[index] = view.clone(),
+                Err(index) => views.insert(index, view.clone()),
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A client's recent snapshots: the newest, and the older ones the server
+/// may still send deltas against
+#[derive(Debug, Clone, Default)]
+pub struct Baselines {
+    snapshots: VecDeque<Snapshot>, // Oldest first
+}
+
+impl Baselines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take in a server message. Returns the up-to-date snapshot if it
+    /// brought one, whose tick is the one to ack. Fails with the base tick
+    /// of a delta made against a snapshot this client doesn't have; the
+    /// next keyframe fixes that.
+    pub fn receive(&mut self, message: &ServerMessage) -> Result<Option<&Snapshot>, u64> {
+        match message {
+            ServerMessage::Snapshot(snapshot) => {
+                self.snapshots.clear();
+                self.snapshots.push_back(snapshot.clone());
+            }
+            ServerMessage::Delta(delta) => {
+                // The server's baseline only moves forward, so older
+                // snapshots won't be needed again
+                while self.snapshots.front().is_some_and(|snapshot| snapshot.tick < delta.base_tick) {
+                    self.snapshots.pop_front();
+                }
+                let mut snapshot = self.snapshots.front().ok_or(delta.base_tick)?.clone();
+                snapshot.apply(delta).map_err(|_| delta.base_tick)?;
+                self.snapshots.push_back(snapshot);
+            }
+            ServerMessage::Rejected { .. } => return Ok(None),
+        }
+        Ok(self.snapshots.back())
+    }
+
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.snapshots.back()
+    }
+}
+
+// ============================================================================
+// COMMANDS
+// ============================================================================
+
+impl Game {
+    /// Run a client command, or say why the game refused it
+    pub fn apply_command(&mut self, command: &Command) -> Result<(), ActionError> {
+        match *command {
+            Command::Build { tool, position } => self.try_build(tool, position),
+            Command::SellTower { tower_id } => self.sell_tower(tower_id),
+            Command::MergeTowers { source_id, target_id } => self.state.merge_towers(source_id, target_id),
+            Command::CallNextWave => self.state.call_next_wave(),
+            Command::CastAbility { ability, target } => self.cast_ability(ability, target),
+            Command::ClearObstacle { position } => self.state.clear_obstacle(position),
+            Command::UndoClear => self.state.undo_clear(),
+            Command::SpawnEnemy { health_scale, speed_scale } => {
+                unless(self.spawn_scaled(health_scale, speed_scale), ActionError::NotAllowed)
+            }
+            Command::ClearWave => unless(self.clear_wave(), ActionError::NotAllowed),
+            Command::LockTarget { tower_id, enemy_id } => self.state.lock_target(tower_id, enemy_id),
+            Command::IgnoreCell { tower_id, position, ignored } => self.state.set_ignored(tower_id, position, ignored),
+            Command::ResetTargeting { tower_id } => self.state.reset_targeting(tower_id),
+            Command::SetTowerEnabled { tower_id, enabled } => self.state.set_tower_enabled(tower_id, enabled),
+            Command::RepairTower { tower_id } => self.state.repair_tower(tower_id),
+            Command::ComposeWave { roster, formation } => self.state.compose_wave(roster, formation),
+            Command::SetPaused { paused } => {
+                self.state.paused = paused;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_round_trip() {
+        let mut game = Game::new();
+        assert!(game.apply_command(&Command::Build {
+            tool: BuildTool::Tower(TowerType::Basic),
+            position: Position::new(5, 3),
+        })
+        .is_ok());
+        for _ in 0..3 {
+            assert!(game.state.spawn_enemy().is_ok());
+        }
+        let base = Snapshot::capture(&game);
+
+        for _ in 0..30 {
+            game.update(1.0 / 30.0);
+        }
+        let tower_id = game.state.towers.keys().next().unwrap();
+        assert!(game.apply_command(&Command::SellTower { tower_id }).is_ok());
+        let current = Snapshot::capture(&game);
+
+        let delta = current.delta_from(&base);
+        assert_eq!(delta.towers.removed, vec![tower_id]);
+        assert!(delta.towers.changed.is_empty());
+
+        let mut client = base.clone();
+        client.apply(&delta).unwrap();
+        assert_eq!(client, current);
+        assert_eq!(client.apply(&delta), Err(current.tick));
+    }
+
+    #[test]
+    fn test_deltas_against_an_older_acked_tick() {
+        let mut game = Game::new();
+        assert!(game.state.spawn_enemy().is_ok());
+        let mut snapshots = vec![Snapshot::capture(&game)];
+        for _ in 0..3 {
+            game.update(1.0 / 30.0);
+            snapshots.push(Snapshot::capture(&game));
+        }
+        let mut client = Baselines::new();
+        client.receive(&ServerMessage::Snapshot(snapshots[0].clone())).unwrap();
+        client.receive(&ServerMessage::Delta(snapshots[1].delta_from(&snapshots[0]))).unwrap();
+        // The ack for tick 1 hasn't reached the server yet
+        let latest = client.receive(&ServerMessage::Delta(snapshots[2].delta_from(&snapshots[0]))).unwrap();
+        assert_eq!(latest, Some(&snapshots[2]));
+
+        // Once a delta builds on tick 2, tick 0 is gone
+        client.receive(&ServerMessage::Delta(snapshots[3].delta_from(&snapshots[2]))).unwrap();
+        assert_eq!(client.latest(), Some(&snapshots[3]));
+        let stale = snapshots[3].delta_from(&snapshots[0]);
+        assert_eq!(client.receive(&ServerMessage::Delta(stale)), Err(snapshots[0].tick));
+    }
+
+    #[test]
+    fn test_unchanged_entities_are_left_out() {
+        let mut game = Game::new();
+        game.build(BuildTool::Tower(TowerType::Sniper), Position::new(2, 2));
+        let base = Snapshot::capture(&game);
+        game.tick += 1;
+        let delta = Snapshot::capture(&game).delta_from(&base);
+        assert!(delta.is_empty());
+    }
+}
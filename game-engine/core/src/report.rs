@@ -0,0 +1,138 @@
+//! Run exports for analysis in external tools. A `RunReport` bundles the
+//! final summary, the per-wave records and the per-second samples; it
+//! serializes to JSON as-is, and `waves_csv`/`samples_csv` give the two
+//! time series as CSV with a header row.
+
+use serde::Serialize;
+
+use crate::attack::attack_score;
+use crate::integrity::Taint;
+use crate::mutators::Mutator;
+use crate::stats::{StatSample, WaveStats};
+use crate::{Game, TowerType};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunSummary {
+    pub seconds: f32,
+    pub ticks: u64,
+    pub wave_reached: u32,
+    pub health: i32,
+    pub gold: i32,
+    pub kills: u32,
+    pub leaks: u32,
+    pub total_damage: u64,
+    pub gold_earned: i64,
+    pub gold_spent: i64,
+    pub damage_by_tower: Vec<(TowerType, u64)>,
+    pub convoys_delivered: u32,
+    pub convoys_lost: u32,
+    pub mutators: Vec<Mutator>,
+    pub score: u64, // `base_score` (`attack_score` in the attack mode) times the mutators' multiplier
+    pub ranked: bool, // False once anything marked the run (see `integrity`); rankings should skip it
+    pub taints: Vec<Taint>,
+    pub hardcore: bool, // Ranked hardcore runs go on their own leaderboard (see `savegame`)
+}
+
+/// Score before mutators: 100 per wave cleared, 10 per kill, 5 per life left
+/// and 50 per convoy delivered
+pub fn base_score(waves_cleared: u32, kills: u32, health: i32, convoys_delivered: u32) -> u64 {
+    100 * waves_cleared as u64 + 10 * kills as u64 + 5 * health.max(0) as u64 + 50 * convoys_delivered as u64
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunReport {
+    pub summary: RunSummary,
+    pub waves: Vec<WaveStats>,
+    pub samples: Vec<StatSample>,
+}
+
+impl RunReport {
+    pub fn from_game(game: &Game) -> Self {
+        let stats = &game.stats;
+        let rules = &game.state.rules;
+        let convoys = &game.state.convoys;
+        let base = if rules.attack {
+            attack_score(stats.total_leaks, game.state.attack.budget)
+        } else {
+            base_score(game.state.waves.wave - 1, stats.kills, game.state.health, convoys.delivered)
+        };
+        RunReport {
+            summary: RunSummary {
+                seconds: stats.elapsed,
+                ticks: game.tick,
+                wave_reached: game.state.waves.wave,
+                health: game.state.health,
+                gold: game.state.gold,
+                kills: stats.kills,
+                leaks: stats.total_leaks,
+                total_damage: stats.total_damage,
+                gold_earned: stats.gold_earned,
+                gold_spent: stats.gold_spent,
+                damage_by_tower: stats.damage_by_tower.clone(),
+                convoys_delivered: convoys.delivered,
+                convoys_lost: convoys.lost,
+                mutators: rules.mutators().to_vec(),
+                score: (base as f64 * rules.score_multiplier() as f64).round() as u64,
+                ranked: game.state.run_integrity.is_clean(),
+                taints: game.state.run_integrity.taints().to_vec(),
+                hardcore: rules.hardcore,
+            },
+            waves: stats.waves.clone(),
+            samples: stats.samples.clone(),
+        }
+    }
+
+    /// One row per wave; `cleared_at` is empty for a wave still in progress
+    pub fn waves_csv(&self) -> String {
+        let mut csv = String::from("wave,started_at,cleared_at,kills,leaks,damage,gold_earned,gold_spent\n");
+        for wave in &self.waves {
+            let cleared_at = wave.cleared_at.map(|time| format!("{:.2}", time)).unwrap_or_default();
+            csv.push_str(&format!(
+                "{},{:.2},{},{},{},{},{},{}\n",
+                wave.wave, wave.started_at, cleared_at, wave.kills, wave.leaks, wave.damage, wave.gold_earned, wave.gold_spent
+            ));
+        }
+        csv
+    }
+
+    /// Cumulative totals, one row per sample
+    pub fn samples_csv(&self) -> String {
+        let mut csv = String::from("time,gold_earned,gold_spent,damage\n");
+        for sample in &self.samples {
+            csv.push_str(&format!(
+                "{:.2},{},{},{}\n",
+                sample.time, sample.gold_earned, sample.gold_spent, sample.damage
+            ));
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Position};
+
+    #[test]
+    fn test_report_covers_each_wave() {
+        let mut game = Game::new();
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6));
+        for _ in 0..900 {
+            game.update(1.0 / 30.0);
+        }
+
+        let report = RunReport::from_game(&game);
+        assert_eq!(report.waves.len(), 1);
+        assert_eq!(report.waves[0].wave, 1);
+        assert_eq!(report.waves[0].gold_spent, 0); // Bought before the wave started
+        assert_eq!(report.summary.gold_spent, TowerType::Basic.cost() as i64);
+        let summary = &report.summary;
+        assert_eq!(summary.score, base_score(game.state.waves.wave - 1, summary.kills, summary.health, 0));
+
+        let csv = report.waves_csv();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("wave,started_at"));
+        assert!(lines.next().unwrap().starts_with("1,"));
+        assert_eq!(report.samples_csv().lines().count(), report.samples.len() + 1);
+    }
+}
@@ -0,0 +1,155 @@
+//! The report card shown after each wave: the wave's economy, damage by
+//! tower type and leaks from its `WaveStats`, a letter grade for how much of
+//! the wave was stopped, and a few hints drawn from the same numbers. A
+//! damage tower whose share of the wave's damage falls well below its share
+//! of the towers is flagged; support towers are left out of that, since
+//! their worth is in what they set up.
+
+use serde::Serialize;
+
+use crate::stats::WaveStats;
+use crate::{GameState, TowerType};
+
+const WEAK_SHARE: f32 = 0.25; // Of a type's fair share of the damage, below which it's flagged
+const IDLE_GOLD: i32 = 300; // Gold banked after a wave that's worth spending
+const MAX_HINTS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Grade {
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+
+impl Grade {
+    /// From the fraction of the wave's enemies that were stopped
+    pub fn for_efficiency(efficiency: f32) -> Grade {
+        match efficiency {
+            e if e >= 1.0 => Grade::A,
+            e if e >= 0.9 => Grade::B,
+            e if e >= 0.75 => Grade::C,
+            e if e >= 0.5 => Grade::D,
+            _ => Grade::F,
+        }
+    }
+
+    pub fn letter(&self) -> &'static str {
+        match self {
+            Grade::A => "A",
+            Grade::B => "B",
+            Grade::C => "C",
+            Grade::D => "D",
+            Grade::F => "F",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportCard {
+    pub wave: u32,
+    pub gold_earned: i64,
+    pub gold_spent: i64,
+    pub damage_by_tower: Vec<(TowerType, u64)>, // Highest first
+    pub kills: u32,
+    pub leaks: u32,
+    pub efficiency: f32, // Kills over kills plus leaks; 1 for an empty wave
+    pub grade: Grade,
+    pub hints: Vec<String>,
+}
+
+impl ReportCard {
+    /// The card for a finished wave, with hints judged against the towers
+    /// standing now
+    pub fn new(stats: &WaveStats, state: &GameState) -> Self {
+        let mut damage_by_tower = stats.damage_by_tower.clone();
+        damage_by_tower.sort_by_key(|(_, damage)| std::cmp::Reverse(*damage));
+        let enemies = stats.kills + stats.leaks;
+        let efficiency = if enemies == 0 { 1.0 } else { stats.kills as f32 / enemies as f32 };
+        let mut card = ReportCard {
+            wave: stats.wave,
+            gold_earned: stats.gold_earned,
+            gold_spent: stats.gold_spent,
+            damage_by_tower,
+            kills: stats.kills,
+            leaks: stats.leaks,
+            efficiency,
+            grade: Grade::for_efficiency(efficiency),
+            hints: Vec::new(),
+        };
+        card.hints = card.hints_for(state);
+        card
+    }
+
+    fn hints_for(&self, state: &GameState) -> Vec<String> {
+        let mut hints = Vec::new();
+        if self.leaks > 0 {
+            hints.push(format!("{} got through: cover the last stretch before the goal", self.leaks));
+        }
+
+        let total: u64 = self.damage_by_tower.iter().map(|(_, damage)| damage).sum();
+        let towers: Vec<TowerType> =
+            state.towers.values().map(|tower| tower.tower_type).filter(|tower_type| !is_support(*tower_type)).collect();
+        let mut types: Vec<TowerType> = Vec::new();
+        for tower_type in &towers {
+            if !types.contains(tower_type) {
+                types.push(*tower_type);
+            }
+        }
+        for tower_type in types.into_iter().filter(|_| total > 0) {
+            let damage = self.damage_by_tower.iter().find(|(t, _)| *t == tower_type).map_or(0, |(_, damage)| *damage);
+            let share = damage as f32 / total as f32;
+            let fair = towers.iter().filter(|t| **t == tower_type).count() as f32 / towers.len() as f32;
+            if share < fair * WEAK_SHARE {
+                hints.push(format!("{:?} dealt {:.0}% of damage; consider selling", tower_type, share * 100.0));
+            }
+        }
+
+        if state.gold >= IDLE_GOLD {
+            hints.push(format!("${} in the bank: more towers would earn their keep", state.gold));
+        }
+        hints.truncate(MAX_HINTS);
+        hints
+    }
+}
+
+/// Towers that weaken or slow rather than kill
+fn is_support(tower_type: TowerType) -> bool {
+    matches!(tower_type, TowerType::Slow | TowerType::Shredder | TowerType::Marker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    fn wave(kills: u32, leaks: u32, damage_by_tower: Vec<(TowerType, u64)>) -> WaveStats {
+        WaveStats { wave: 3, kills, leaks, damage_by_tower, ..WaveStats::default() }
+    }
+
+    #[test]
+    fn test_grade_follows_the_enemies_stopped() {
+        let state = GameState::new();
+        assert_eq!(ReportCard::new(&wave(10, 0, Vec::new()), &state).grade, Grade::A);
+        assert_eq!(ReportCard::new(&wave(0, 0, Vec::new()), &state).grade, Grade::A);
+        assert_eq!(ReportCard::new(&wave(9, 1, Vec::new()), &state).grade, Grade::B);
+        let card = ReportCard::new(&wave(1, 3, Vec::new()), &state);
+        assert_eq!(card.grade, Grade::F);
+        assert!(card.hints[0].starts_with("3 got through"));
+    }
+
+    #[test]
+    fn test_idle_damage_towers_are_flagged() {
+        let mut state = GameState::new();
+        state.gold = 10_000;
+        for (x, tower_type) in [(3, TowerType::Basic), (5, TowerType::Sniper), (7, TowerType::Slow)] {
+            assert!(state.place_tower(tower_type, Position::new(x, 3)).is_ok());
+        }
+        state.gold = 0;
+        let damage = vec![(TowerType::Sniper, 3), (TowerType::Basic, 97)];
+        let card = ReportCard::new(&wave(20, 0, damage), &state);
+        assert_eq!(card.damage_by_tower[0].0, TowerType::Basic);
+        assert_eq!(card.hints, vec!["Sniper dealt 3% of damage; consider selling".to_string()]); // Not the Slow
+    }
+}
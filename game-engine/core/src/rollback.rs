@@ -0,0 +1,190 @@
+//! Whole-game save states. `Game::snapshot` copies everything the simulation
+//! reads (plus the short-lived effects, so a replayed frame looks right);
+//! `Game::restore` puts it back. The simulation's only randomness is the
+//! seeded generator in `GameState`, which is copied with it, so restoring
+//! tick N and feeding the same commands and deltas reproduces every later
+//! tick. That is what rollback netcode and replays build on.
+//!
+//! Not captured: debug tooling (profiler, targeting log), the build tool and
+//! drag state, which belong to the local player, and a rule script's memory.
+
+use std::collections::VecDeque;
+
+use slotmap::SlotMap;
+
+use crate::abilities::Abilities;
+use crate::events::{EventBus, GameEvent};
+use crate::fog::FogOfWar;
+use crate::stats::RunStats;
+use crate::gunners::EnemyShot;
+use crate::{ExplosionEffect, FloatingText, Game, GameState, MuzzleFlash, Projectile, ProjectileId};
+
+#[derive(Debug, Clone)]
+pub struct GameSnapshot {
+    pub tick: u64,
+    pub state: GameState,
+    projectiles: SlotMap<ProjectileId, Projectile>,
+    enemy_shots: Vec<EnemyShot>,
+    abilities: Abilities,
+    events: EventBus,
+    frame_events: Vec<GameEvent>,
+    stats: RunStats,
+    fog: FogOfWar,
+    muzzle_flashes: Vec<MuzzleFlash>,
+    explosions: Vec<ExplosionEffect>,
+    floating_texts: Vec<FloatingText>,
+    leak_flash: f32,
+}
+
+impl Game {
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            tick: self.tick,
+            state: self.state.clone(),
+            projectiles: self.projectiles.clone(),
+            enemy_shots: self.enemy_shots.clone(),
+            abilities: self.abilities.clone(),
+            events: self.events.clone(),
+            frame_events: self.frame_events.clone(),
+            stats: self.stats.clone(),
+            fog: self.fog.clone(),
+            muzzle_flashes: self.muzzle_flashes.clone(),
+            explosions: self.explosions.clone(),
+            floating_texts: self.floating_texts.clone(),
+            leak_flash: self.leak_flash,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &GameSnapshot) {
+        self.tick = snapshot.tick;
+        self.state.clone_from(&snapshot.state);
+        self.projectiles.clone_from(&snapshot.projectiles);
+        self.enemy_shots.clone_from(&snapshot.enemy_shots);
+        self.abilities.clone_from(&snapshot.abilities);
+        self.events.clone_from(&snapshot.events);
+        self.frame_events.clone_from(&snapshot.frame_events);
+        self.stats.clone_from(&snapshot.stats);
+        self.fog.clone_from(&snapshot.fog);
+        self.muzzle_flashes.clone_from(&snapshot.muzzle_flashes);
+        self.explosions.clone_from(&snapshot.explosions);
+        self.floating_texts.clone_from(&snapshot.floating_texts);
+        self.leak_flash = snapshot.leak_flash;
+
+        // Handles held by the local player may point at things that no longer exist
+        self.dragging_tower = self.dragging_tower.filter(|id| self.state.towers.contains_key(*id));
+        self.enemy_index.rebuild(&self.state.enemies);
+    }
+}
+
+/// Ring buffer of the most recent snapshots, oldest first
+#[derive(Debug, Clone)]
+pub struct History {
+    capacity: usize,
+    frames: VecDeque<GameSnapshot>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            capacity: capacity.max(1),
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn record(&mut self, game: &Game) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(game.snapshot());
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Frame `index`, counting from the oldest kept
+    pub fn get(&self, index: usize) -> Option<&GameSnapshot> {
+        self.frames.get(index)
+    }
+
+    pub fn latest(&self) -> Option<&GameSnapshot> {
+        self.frames.back()
+    }
+
+    pub fn at_tick(&self, tick: u64) -> Option<&GameSnapshot> {
+        self.frames.iter().rev().find(|frame| frame.tick == tick)
+    }
+
+    /// Restore the game to `tick` and forget every later frame, ready to
+    /// resimulate. False if that tick has already left the buffer.
+    pub fn rewind(&mut self, game: &mut Game, tick: u64) -> bool {
+        let Some(index) = self.frames.iter().rposition(|frame| frame.tick == tick) else {
+            return false;
+        };
+        self.frames.truncate(index + 1);
+        game.restore(&self.frames[index]);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Position, TowerType};
+
+    const DELTA: f32 = 1.0 / 30.0;
+
+    fn busy_game() -> Game {
+        let mut game = Game::new();
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6));
+        game.build(BuildTool::Tower(TowerType::Splash), Position::new(8, 8));
+        assert!(game.state.call_next_wave().is_ok());
+        game
+    }
+
+    #[test]
+    fn test_restore_then_resimulate_matches() {
+        let mut game = busy_game();
+        for _ in 0..60 {
+            game.update(DELTA);
+        }
+        let saved = game.snapshot();
+        for _ in 0..90 {
+            game.update(DELTA);
+        }
+        let expected = game.checksum();
+
+        game.restore(&saved);
+        assert_eq!(game.tick, saved.tick);
+        for _ in 0..90 {
+            game.update(DELTA);
+        }
+        assert_eq!(game.checksum(), expected);
+    }
+
+    #[test]
+    fn test_history_keeps_last_frames_and_rewinds() {
+        let mut game = busy_game();
+        let mut history = History::new(10);
+        for _ in 0..25 {
+            game.update(DELTA);
+            history.record(&game);
+        }
+        assert_eq!(history.len(), 10);
+        assert_eq!(history.get(0).unwrap().tick, 16);
+        assert!(history.at_tick(15).is_none());
+
+        assert!(history.rewind(&mut game, 20));
+        assert_eq!(game.tick, 20);
+        assert_eq!(history.latest().unwrap().tick, 20);
+        assert!(!history.rewind(&mut game, 3));
+    }
+}
@@ -0,0 +1,87 @@
+//! Sandbox controls, for testing builds and mods. A game whose rules have
+//! `sandbox` set charges nothing (see `RuleSet::price`), holds the build
+//! countdown until a wave is called, and accepts the commands here: spawn
+//! an enemy with scaled health and speed, or wipe out the current wave.
+//! Outside the sandbox both are refused. Sandbox runs are unranked (see
+//! `integrity`).
+
+use tracing::info;
+
+use crate::integrity::Taint;
+use crate::Game;
+
+pub const MIN_SCALE: f32 = 0.25;
+pub const MAX_SCALE: f32 = 4.0;
+
+impl Game {
+    /// Spawn one enemy with health and speed scaled (clamped to
+    /// `MIN_SCALE..=MAX_SCALE`)
+    pub fn spawn_scaled(&mut self, health_scale: f32, speed_scale: f32) -> bool {
+        if !self.state.rules.sandbox {
+            return false;
+        }
+        let (health_scale, speed_scale) =
+            (health_scale.clamp(MIN_SCALE, MAX_SCALE), speed_scale.clamp(MIN_SCALE, MAX_SCALE));
+        self.state.taint(Taint::Sandbox);
+        self.state
+            .spawn_enemy_with(|enemy| {
+                enemy.max_health = ((enemy.max_health as f32 * health_scale).round() as i32).max(1);
+                enemy.health = enemy.max_health;
+                enemy.speed *= speed_scale;
+            })
+            .is_some()
+    }
+
+    /// Remove every enemy without reward and stop the wave's spawning, so
+    /// the next tick returns to the build phase
+    pub fn clear_wave(&mut self) -> bool {
+        if !self.state.rules.sandbox {
+            return false;
+        }
+        self.state.taint(Taint::Sandbox);
+        let enemy_ids: Vec<_> = self.state.enemies.keys().collect();
+        for enemy_id in &enemy_ids {
+            self.release_target(*enemy_id);
+        }
+        self.state.enemies.clear();
+        for tower in self.state.towers.values_mut() {
+            tower.target_id = None;
+        }
+        self.state.waves.remaining_to_spawn = 0;
+        info!(removed = enemy_ids.len(), "wave cleared by sandbox");
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutators::RuleSet;
+    use crate::{BuildTool, Position, TowerType};
+
+    #[test]
+    fn test_sandbox_is_free_and_controls_spawns() {
+        assert!(!Game::new().spawn_scaled(1.0, 1.0));
+
+        let mut game = Game::with_rules(RuleSet::sandbox());
+        let gold = game.state.gold;
+        assert!(game.build(BuildTool::Tower(TowerType::Cannon), Position::new(4, 4)));
+        assert_eq!(game.state.gold, gold);
+
+        let normal = game.state.spawn_enemy_with(|_| {}).unwrap();
+        assert!(game.spawn_scaled(2.0, 10.0));
+        let scaled = game.state.enemies.values().find(|enemy| enemy.id != normal).unwrap();
+        assert_eq!(scaled.max_health, game.state.enemies[normal].max_health * 2);
+        assert_eq!(scaled.speed, game.state.enemies[normal].speed * MAX_SCALE);
+
+        assert!(game.state.call_next_wave().is_ok());
+        game.update(1.0);
+        assert!(game.clear_wave());
+        game.update(1.0);
+        assert!(game.state.enemies.is_empty() && game.state.waves.is_build_phase());
+        for _ in 0..60 {
+            game.update(1.0); // The countdown is held
+        }
+        assert!(game.state.waves.is_build_phase());
+    }
+}
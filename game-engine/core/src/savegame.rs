@@ -0,0 +1,210 @@
+//! The hardcore mode's save slot (`RuleSet::hardcore`). A hardcore run
+//! gets one life: no checkpoints, a single slot the frontend autosaves to
+//! as it plays and deletes once the run is decided, and its own
+//! leaderboard (`RunSummary::hardcore`).
+//!
+//! A `SavedRun` is what the slot holds, as do the frontend's other save
+//! slots. It's enough to play on: the state, the tick, ability cooldowns
+//! and the stats behind the run report.
+//! Shots in flight and effects are dropped.
+//!
+//! Saves are binary by default (`to_binary`): `BINARY_MAGIC`, the save
+//! version as one byte, the seal and a MessagePack body. A late-game state
+//! with thousands of enemies and their paths comes out several times
+//! smaller than JSON and loads faster. MessagePack rather than a bare
+//! format like bincode because it describes itself, which the state's
+//! internally tagged enums (`WinCondition`, `Stacking`) need to decode. The
+//! frontend can still write JSON for inspection; it `seal`s that text. The
+//! seal is a keyed FNV-1a hash of the body either way, and a body that
+//! doesn't match it is refused. That stops a hand-edited save (more gold,
+//! full health) from loading. It isn't cryptography; someone who digs the
+//! key out of the binary can still forge one.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::abilities::Abilities;
+use crate::checksum::StateHasher;
+use crate::stats::RunStats;
+use crate::{Game, GameState};
+
+pub const SAVE_VERSION: u32 = 1; // Bumped whenever an old save can't be played on
+
+/// The first bytes of a binary save
+pub const BINARY_MAGIC: &[u8; 4] = b"RRSV";
+const BINARY_HEADER: usize = BINARY_MAGIC.len() + 1 + 8; // Magic, version byte and seal
+
+const SEAL_KEY: &[u8] = b"rust-rush hardcore slot";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedRun {
+    pub version: u32,
+    pub tick: u64,
+    pub state: GameState,
+    pub abilities: Abilities,
+    pub stats: RunStats,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveError {
+    Malformed, // No seal line or header
+    Tampered,  // The body doesn't match its seal
+    OldVersion(u32),
+    Encoding(String), // The body isn't a run
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Malformed => write!(f, "not a save file"),
+            SaveError::Tampered => write!(f, "the save was changed outside the game"),
+            SaveError::OldVersion(version) => write!(f, "save version {} can't be played on", version),
+            SaveError::Encoding(err) => write!(f, "the save couldn't be read: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl SavedRun {
+    pub fn capture(game: &Game) -> Self {
+        SavedRun {
+            version: SAVE_VERSION,
+            tick: game.tick,
+            state: game.state.clone(),
+            abilities: game.abilities.clone(),
+            stats: game.stats.clone(),
+        }
+    }
+
+    pub fn check_version(&self) -> Result<(), SaveError> {
+        match self.version {
+            SAVE_VERSION => Ok(()),
+            version => Err(SaveError::OldVersion(version)),
+        }
+    }
+
+    /// The run as a sealed binary save
+    pub fn to_binary(&self) -> Result<Vec<u8>, SaveError> {
+        let body = rmp_serde::to_vec(self).map_err(|err| SaveError::Encoding(err.to_string()))?;
+        let mut bytes = Vec::with_capacity(BINARY_HEADER + body.len());
+        bytes.extend_from_slice(BINARY_MAGIC);
+        bytes.push(SAVE_VERSION as u8);
+        bytes.extend_from_slice(&seal_of(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+
+    /// The run in a binary save, if its seal holds and it's this version
+    pub fn from_binary(bytes: &[u8]) -> Result<SavedRun, SaveError> {
+        if !is_binary(bytes) || bytes.len() < BINARY_HEADER {
+            return Err(SaveError::Malformed);
+        }
+        let version = bytes[BINARY_MAGIC.len()];
+        if u32::from(version) != SAVE_VERSION {
+            return Err(SaveError::OldVersion(version.into()));
+        }
+        let seal = u64::from_le_bytes(bytes[BINARY_MAGIC.len() + 1..BINARY_HEADER].try_into().expect("8 bytes"));
+        let body = &bytes[BINARY_HEADER..];
+        if seal != seal_of(body) {
+            return Err(SaveError::Tampered);
+        }
+        let saved: SavedRun = rmp_serde::from_slice(body).map_err(|err| SaveError::Encoding(err.to_string()))?;
+        saved.check_version()?;
+        Ok(saved)
+    }
+}
+
+/// Whether `bytes` are a binary save rather than sealed text
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(BINARY_MAGIC)
+}
+
+impl Game {
+    /// Play on from a saved run. The local player's tools and anything in
+    /// flight start fresh.
+    pub fn resume(&mut self, saved: SavedRun) {
+        // The grid's revision isn't saved; carry it on so caches drawn from
+        // the old board don't look current
+        let revision = self.state.grid.revision + 1;
+        self.tick = saved.tick;
+        self.state = saved.state;
+        self.state.grid.revision = revision;
+        self.abilities = saved.abilities;
+        self.stats = saved.stats;
+        self.projectiles.clear();
+        self.enemy_shots.clear();
+        self.frame_events.clear();
+        self.dragging_tower = None;
+        self.aiming_tower = None;
+        self.stroke = None;
+        self.enemy_index.rebuild(&self.state.enemies);
+    }
+}
+
+fn seal_of(body: &[u8]) -> u64 {
+    let mut hasher = StateHasher::new();
+    hasher.bytes(SEAL_KEY);
+    hasher.bytes(body);
+    hasher.bytes(SEAL_KEY);
+    hasher.finish()
+}
+
+/// The text to write to the slot: the seal on the first line, then `body`
+pub fn seal(body: &str) -> String {
+    format!("{:016x}\n{}", seal_of(body.as_bytes()), body)
+}
+
+/// The body of sealed text, if the seal matches it
+pub fn unseal(text: &str) -> Result<&str, SaveError> {
+    let (line, body) = text.split_once('\n').ok_or(SaveError::Malformed)?;
+    let seal = u64::from_str_radix(line.trim(), 16).map_err(|_| SaveError::Malformed)?;
+    if seal == seal_of(body.as_bytes()) {
+        Ok(body)
+    } else {
+        Err(SaveError::Tampered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutators::RuleSet;
+    use crate::victory::{Objective, WinCondition};
+
+    #[test]
+    fn test_edited_saves_are_refused() {
+        let body = r#"{"gold":100}"#;
+        let sealed = seal(body);
+        assert_eq!(unseal(&sealed), Ok(body));
+        assert_eq!(unseal(&sealed.replace("100", "9999")), Err(SaveError::Tampered));
+        assert_eq!(unseal(body), Err(SaveError::Malformed));
+    }
+
+    #[test]
+    fn test_resume_plays_on_where_the_save_left_off() {
+        let mut rules = RuleSet::default();
+        rules.hardcore = true;
+        let mut game = Game::with_rules(rules);
+        let escort = WinCondition::Escort { health: 50.0, speed: 1.0 };
+        game.state.objective = Objective::new(escort, game.state.spawn_point);
+        assert!(game.state.spawn_enemy().is_ok());
+        for _ in 0..30 {
+            game.update(1.0 / 30.0);
+        }
+        let bytes = SavedRun::capture(&game).to_binary().unwrap();
+        assert!(is_binary(&bytes));
+        let mut edited = bytes.clone();
+        *edited.last_mut().unwrap() ^= 1;
+        assert_eq!(SavedRun::from_binary(&edited).unwrap_err(), SaveError::Tampered);
+
+        let mut resumed = Game::new();
+        resumed.resume(SavedRun::from_binary(&bytes).unwrap());
+        assert_eq!((resumed.tick, resumed.state.hash()), (game.tick, game.state.hash()));
+        assert!(resumed.state.rules.hardcore);
+        game.update(1.0 / 30.0);
+        resumed.update(1.0 / 30.0);
+        assert_eq!(resumed.state.hash(), game.state.hash());
+    }
+}
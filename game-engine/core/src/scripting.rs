@@ -0,0 +1,392 @@
+//! Rhai rule scripts. A script defines any of these hooks; each runs with
+//! `this` bound to a small view of the game:
+//!
+//! ```text
+//! fn on_tick(delta) { ... }
+//! fn on_wave_start(wave) { this.gold += 10 * wave; }
+//! fn on_enemy_killed(x, y, gold) { ... }
+//! fn on_tower_placed(kind, x, y) { ... }
+//! ```
+//!
+//! `this` exposes `gold` and `health` (read/write), `wave`, `enemy_count`,
+//! `tower_count` and `tick` (read-only), a `memory` map that persists between
+//! calls, and `spawn_enemies(n)`. Scripts can't reach files or modules, and
+//! every hook in a tick shares one operation budget. A failing hook is
+//! logged once, not every tick, and switched off after
+//! `MAX_STRAIGHT_FAILURES` failed calls in a row.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST, FLOAT, INT};
+use tracing::{info, warn};
+
+use crate::events::GameEvent;
+use crate::integrity::Taint;
+use crate::{Game, STARTING_HEALTH};
+
+pub const TICK_OPERATION_BUDGET: u64 = 50_000; // Shared by every hook run in one tick
+const MAX_SPAWNS_PER_CALL: INT = 50;
+pub const MAX_STRAIGHT_FAILURES: u32 = 30; // About a second of a failing `on_tick`
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Io(io::Error),
+    Compile(String),
+    Runtime(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Io(err) => write!(f, "could not read script: {}", err),
+            ScriptError::Compile(err) => write!(f, "script does not compile: {}", err),
+            ScriptError::Runtime(err) => write!(f, "script failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// The slice of game state a hook sees as `this`
+#[derive(Debug, Clone, Default)]
+pub struct ScriptContext {
+    pub gold: INT,
+    pub health: INT,
+    pub wave: INT,
+    pub enemy_count: INT,
+    pub tower_count: INT,
+    pub tick: INT,
+    pub memory: Map,
+    pub spawn_requests: INT,
+}
+
+/// One hook call, with its arguments
+#[derive(Debug, Clone, PartialEq)]
+pub enum Hook {
+    Tick(f32),
+    WaveStart(u32),
+    EnemyKilled { x: f32, y: f32, gold: i32 },
+    TowerPlaced { kind: String, x: i32, y: i32 },
+}
+
+impl Hook {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hook::Tick(_) => "on_tick",
+            Hook::WaveStart(_) => "on_wave_start",
+            Hook::EnemyKilled { .. } => "on_enemy_killed",
+            Hook::TowerPlaced { .. } => "on_tower_placed",
+        }
+    }
+
+    fn args(&self) -> Vec<Dynamic> {
+        match self {
+            Hook::Tick(delta) => vec![(*delta as FLOAT).into()],
+            Hook::WaveStart(wave) => vec![(*wave as INT).into()],
+            Hook::EnemyKilled { x, y, gold } => {
+                vec![(*x as FLOAT).into(), (*y as FLOAT).into(), (*gold as INT).into()]
+            }
+            Hook::TowerPlaced { kind, x, y } => {
+                vec![kind.clone().into(), (*x as INT).into(), (*y as INT).into()]
+            }
+        }
+    }
+
+    /// The hook, if any, a simulation event triggers
+    pub fn for_event(event: &GameEvent) -> Option<Hook> {
+        match event {
+            GameEvent::WaveStarted { wave } => Some(Hook::WaveStart(*wave)),
+            GameEvent::EnemyKilled { x, y, gold, .. } => Some(Hook::EnemyKilled { x: *x, y: *y, gold: *gold }),
+            GameEvent::TowerPlaced { tower_type, position, .. } => Some(Hook::TowerPlaced {
+                kind: format!("{:?}", tower_type),
+                x: position.x,
+                y: position.y,
+            }),
+            GameEvent::EnemyLeaked { .. }
+            | GameEvent::WaveCleared { .. }
+            | GameEvent::TowerSold { .. }
+            | GameEvent::TowerDestroyed { .. }
+            | GameEvent::DamageDealt { .. } => None,
+        }
+    }
+}
+
+/// A compiled rule script plus its sandboxed engine
+pub struct ScriptHost {
+    engine: Engine,
+    ast: AST,
+    memory: Map,
+    ops_used: u64,            // This tick, across every hook
+    ops_limit: Arc<AtomicU64>, // Operations the running call may still use
+    ops_last: Arc<AtomicU64>,  // Operations the last call actually used
+    failures: HashMap<&'static str, u32>, // Failed calls in a row, by hook
+    disabled: HashSet<&'static str>,      // Hooks that failed too often to keep calling
+}
+
+impl ScriptHost {
+    pub fn from_source(source: &str) -> Result<Self, ScriptError> {
+        let ops_limit = Arc::new(AtomicU64::new(TICK_OPERATION_BUDGET));
+        let ops_last = Arc::new(AtomicU64::new(0));
+        let engine = sandboxed_engine(ops_limit.clone(), ops_last.clone());
+        let ast = engine.compile(source).map_err(|err| ScriptError::Compile(err.to_string()))?;
+
+        Ok(ScriptHost {
+            engine,
+            ast,
+            memory: Map::new(),
+            ops_used: 0,
+            ops_limit,
+            ops_last,
+            failures: HashMap::new(),
+            disabled: HashSet::new(),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ScriptError> {
+        let source = std::fs::read_to_string(path).map_err(ScriptError::Io)?;
+        Self::from_source(&source)
+    }
+
+    pub fn has_hook(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|function| function.name == name)
+    }
+
+    /// Whether a hook was switched off for failing too many calls in a row
+    pub fn is_disabled(&self, name: &str) -> bool {
+        self.disabled.contains(name)
+    }
+
+    /// Start a new tick's operation budget
+    pub fn begin_tick(&mut self) {
+        self.ops_used = 0;
+    }
+
+    /// Run one hook against `context`. Missing and disabled hooks are a
+    /// no-op; a hook that errors or runs over the tick budget leaves
+    /// `context` untouched.
+    pub fn call(&mut self, hook: &Hook, context: &mut ScriptContext) -> Result<(), ScriptError> {
+        if !self.has_hook(hook.name()) || self.is_disabled(hook.name()) {
+            return Ok(());
+        }
+        let remaining = TICK_OPERATION_BUDGET.saturating_sub(self.ops_used);
+        if remaining == 0 {
+            return Err(ScriptError::Runtime(format!("{} skipped: tick budget used up", hook.name())));
+        }
+        self.ops_limit.store(remaining, Ordering::Relaxed);
+        self.ops_last.store(0, Ordering::Relaxed);
+
+        let mut view = context.clone();
+        view.memory = std::mem::take(&mut self.memory);
+        let mut this = Dynamic::from(view);
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut this);
+        let result = self
+            .engine
+            .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, hook.name(), hook.args());
+        self.ops_used += self.ops_last.load(Ordering::Relaxed);
+
+        let view = this.try_cast::<ScriptContext>().unwrap_or_default();
+        match result {
+            Ok(_) => {
+                self.memory = view.memory.clone();
+                *context = view;
+                Ok(())
+            }
+            Err(err) => {
+                self.memory = view.memory;
+                Err(match *err {
+                    EvalAltResult::ErrorTerminated(..) => {
+                        ScriptError::Runtime(format!("{} ran over the per-tick budget", hook.name()))
+                    }
+                    other => ScriptError::Runtime(other.to_string()),
+                })
+            }
+        }
+    }
+
+    /// Log the first of a run of failures, and switch the hook off once the
+    /// run reaches `MAX_STRAIGHT_FAILURES`
+    fn note_failure(&mut self, hook: &Hook, err: &ScriptError) {
+        let straight = self.failures.entry(hook.name()).or_insert(0);
+        *straight += 1;
+        if *straight == 1 {
+            warn!(hook = hook.name(), %err, "script hook failed; failures in a row after this aren't logged");
+        }
+        if *straight >= MAX_STRAIGHT_FAILURES {
+            self.disabled.insert(hook.name());
+            warn!(hook = hook.name(), %err, failures = MAX_STRAIGHT_FAILURES, "script hook disabled");
+        }
+    }
+
+    fn note_success(&mut self, hook: &Hook) {
+        if let Some(failures) = self.failures.remove(hook.name()) {
+            info!(hook = hook.name(), failures, "script hook working again");
+        }
+    }
+}
+
+impl fmt::Debug for ScriptHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptHost").field("ops_used", &self.ops_used).finish_non_exhaustive()
+    }
+}
+
+fn sandboxed_engine(ops_limit: Arc<AtomicU64>, ops_last: Arc<AtomicU64>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(10_000)
+        .set_max_array_size(10_000)
+        .set_max_map_size(1_000)
+        .on_print(|text| info!(target: "script", "{}", text))
+        .on_debug(|text, _, _| info!(target: "script", "{}", text))
+        .on_progress(move |ops| {
+            ops_last.store(ops, Ordering::Relaxed);
+            (ops > ops_limit.load(Ordering::Relaxed)).then_some(Dynamic::UNIT)
+        });
+    engine.disable_symbol("eval");
+
+    engine
+        .register_type_with_name::<ScriptContext>("Game")
+        .register_get_set("gold", |c: &mut ScriptContext| c.gold, |c: &mut ScriptContext, v: INT| c.gold = v)
+        .register_get_set("health", |c: &mut ScriptContext| c.health, |c: &mut ScriptContext, v: INT| c.health = v)
+        .register_get_set(
+            "memory",
+            |c: &mut ScriptContext| c.memory.clone(),
+            |c: &mut ScriptContext, v: Map| c.memory = v,
+        )
+        .register_get("wave", |c: &mut ScriptContext| c.wave)
+        .register_get("enemy_count", |c: &mut ScriptContext| c.enemy_count)
+        .register_get("tower_count", |c: &mut ScriptContext| c.tower_count)
+        .register_get("tick", |c: &mut ScriptContext| c.tick)
+        .register_fn("spawn_enemies", |c: &mut ScriptContext, count: INT| {
+            c.spawn_requests = (c.spawn_requests + count.max(0)).min(MAX_SPAWNS_PER_CALL);
+        });
+    engine
+}
+
+impl Game {
+    fn script_context(&self) -> ScriptContext {
+        ScriptContext {
+            gold: self.state.gold as INT,
+            health: self.state.health as INT,
+            wave: self.state.waves.wave as INT,
+            enemy_count: self.state.enemies.len() as INT,
+            tower_count: self.state.towers.len() as INT,
+            tick: self.tick as INT,
+            memory: Map::new(),
+            spawn_requests: 0,
+        }
+    }
+
+    /// Run the event hooks for this tick's events, then `on_tick`
+    pub(crate) fn run_script_hooks(&mut self, delta: f32) {
+        let Some(mut scripts) = self.scripts.take() else {
+            return;
+        };
+        scripts.begin_tick();
+
+        let hooks = self.frame_events.iter().filter_map(Hook::for_event).chain(std::iter::once(Hook::Tick(delta)));
+        for hook in hooks.collect::<Vec<_>>() {
+            let mut context = self.script_context();
+            match scripts.call(&hook, &mut context) {
+                Ok(()) => {
+                    scripts.note_success(&hook);
+                    self.apply_script_context(&context);
+                }
+                Err(err) => scripts.note_failure(&hook, &err),
+            }
+        }
+
+        self.scripts = Some(scripts);
+    }
+
+    /// Write back what a hook changed. Gold and health are the game's to
+    /// grant, so a hook that sets either takes the run off the rankings.
+    fn apply_script_context(&mut self, context: &ScriptContext) {
+        let gold = context.gold.clamp(0, i32::MAX as INT) as i32;
+        let health = context.health.clamp(0, STARTING_HEALTH as INT * 10) as i32;
+        if (gold, health) != (self.state.gold, self.state.health) {
+            self.state.taint(Taint::Script);
+        }
+        (self.state.gold, self.state.health) = (gold, health);
+        for _ in 0..context.spawn_requests {
+            if let Err(err) = self.state.spawn_enemy() {
+                warn!(%err, "script spawn refused");
+                break; // The rest would be refused the same way
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_modify_game_through_this() {
+        let mut game = Game::new();
+        game.scripts = Some(
+            ScriptHost::from_source(
+                r#"
+                fn on_wave_start(wave) { this.gold += 100 * wave; }
+                fn on_tick(delta) {
+                    let m = this.memory;
+                    m.ticks = (m.ticks ?? 0) + 1;
+                    this.memory = m;
+                    if m.ticks == 2 { this.spawn_enemies(3); }
+                }
+                "#,
+            )
+            .unwrap(),
+        );
+        let gold = game.state.gold;
+
+        game.frame_events = vec![GameEvent::WaveStarted { wave: 2 }];
+        game.run_script_hooks(0.1);
+        assert_eq!(game.state.gold, gold + 200);
+
+        game.frame_events.clear();
+        game.run_script_hooks(0.1);
+        assert_eq!(game.state.enemies.len(), 3);
+    }
+
+    #[test]
+    fn test_runaway_script_is_stopped() {
+        let mut host = ScriptHost::from_source("fn on_tick(delta) { this.gold = 1; loop { } }").unwrap();
+        let mut context = ScriptContext { gold: 50, ..ScriptContext::default() };
+        let err = host.call(&Hook::Tick(0.1), &mut context).unwrap_err();
+        assert!(err.to_string().contains("budget"));
+        assert_eq!(context.gold, 50);
+
+        // The budget is spent for the rest of this tick, and refills on the next
+        assert!(host.call(&Hook::Tick(0.1), &mut context).is_err());
+        host.begin_tick();
+        assert_eq!(host.ops_used, 0);
+    }
+
+    #[test]
+    fn test_failing_hook_is_switched_off() {
+        let mut game = Game::new();
+        game.scripts = Some(ScriptHost::from_source("fn on_tick(delta) { this.gold = 1; throw \"broken\"; }").unwrap());
+        let gold = game.state.gold;
+        for _ in 0..MAX_STRAIGHT_FAILURES {
+            game.run_script_hooks(0.1);
+        }
+        let scripts = game.scripts.as_ref().unwrap();
+        assert!(scripts.is_disabled("on_tick") && game.state.gold == gold);
+    }
+
+    #[test]
+    fn test_sandbox_blocks_imports() {
+        let mut host = ScriptHost::from_source(r#"fn on_tick(delta) { import "fs" as fs; }"#).unwrap();
+        assert!(host.call(&Hook::Tick(0.1), &mut ScriptContext::default()).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! The optional siege rule (`RuleSet::siege`). Towers may wall the goal off
+//! completely; enemies then head for the reachable cell closest to the goal
+//! (see `pathfinding::find_path_or_nearest`) and hit a tower next to it
+//! until it falls, which re-opens its cells and sends everyone back on
+//! their way. A wall with no tower beside that cell just holds them.
+
+use serde::{Deserialize, Serialize};
+use slotmap::SlotMap;
+use tracing::{debug, info};
+
+use crate::events::GameEvent;
+use crate::pathfinding::{find_waypoints_or_nearest, PathResult};
+use crate::{Enemy, EnemyId, Game, GameState, Grid, Position, Tower, TowerId};
+
+pub const TOWER_HEALTH: i32 = 200; // Per cell of footprint
+pub const ATTACK_DAMAGE: i32 = 10;
+pub const ATTACK_INTERVAL: f32 = 1.0; // Seconds between an enemy's blows
+
+/// What an enemy does about its route
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Siege {
+    /// On a route that reaches the goal
+    #[default]
+    Marching,
+    /// Walled off, heading as close to the goal as it can get
+    Breaching,
+    /// At the wall, hitting a tower in it
+    Attacking { tower: TowerId, cooldown: f32 },
+}
+
+impl Enemy {
+    /// Like `new`, but an enemy walled off from the goal still spawns and
+    /// starts breaching
+    pub fn new_or_breaching(id: EnemyId, start: Position, goal: Position, grid: &Grid) -> Option<Self> {
+        match find_waypoints_or_nearest(grid, start, goal) {
+            PathResult::Complete(path) => Some(Enemy::on_path(id, start, path, grid)),
+            PathResult::Partial(path) => {
+                let mut enemy = Enemy::on_path(id, start, path, grid);
+                enemy.siege = Siege::Breaching;
+                Some(enemy)
+            }
+            PathResult::Unreachable => None,
+        }
+    }
+
+    /// Whether the goal is walled off from this enemy
+    pub fn is_blocked(&self) -> bool {
+        self.siege != Siege::Marching
+    }
+}
+
+impl Tower {
+    pub fn max_health(&self) -> i32 {
+        TOWER_HEALTH * self.tower_type.footprint() * self.tower_type.footprint()
+    }
+
+    pub fn health(&self) -> i32 {
+        (self.max_health() - self.damage_taken).max(0)
+    }
+}
+
+impl GameState {
+    /// Remove a tower broken by enemies, freeing its cells. No refund.
+    pub fn destroy_tower(&mut self, tower_id: TowerId) -> Option<Tower> {
+        let tower = self.towers.remove(tower_id)?;
+        for cell in tower.cells() {
+            self.grid.set_walkable(&cell, true);
+        }
+        info!(?tower_id, tower_type = ?tower.tower_type, "tower destroyed");
+        self.recalculate_paths();
+        Some(tower)
+    }
+}
+
+/// The tower next to `cell` closest to the goal, which is the one most
+/// likely to be in the way
+fn blocking_tower(towers: &SlotMap<TowerId, Tower>, grid: &Grid, cell: Position, goal: Position) -> Option<TowerId> {
+    grid.neighbors(cell)
+        .into_iter()
+        .filter_map(|near| towers.values().find(|tower| tower.occupies(&near)).map(|tower| (tower.id, near)))
+        .min_by_key(|(_, near)| grid.distance(near, &goal))
+        .map(|(tower_id, _)| tower_id)
+}
+
+impl Game {
+    /// Take `amount` off a tower's health, destroying it at zero
+    pub fn damage_tower(&mut self, tower_id: TowerId, amount: i32) {
+        let Some(tower) = self.state.towers.get_mut(tower_id) else {
+            return;
+        };
+        tower.damage_taken += amount;
+        if tower.health() == 0 {
+            let (tower_type, position) = (tower.tower_type, tower.position);
+            self.state.destroy_tower(tower_id);
+            self.events.emit(GameEvent::TowerDestroyed { tower_id, tower_type, position });
+        }
+    }
+
+    /// Walled-off enemies at the end of their route pick a tower to break
+    /// and hit it every `ATTACK_INTERVAL`
+    pub(crate) fn update_sieges(&mut self, delta: f32) {
+        let state = &mut self.state;
+        let (towers, grid, goals) = (&state.towers, &state.grid, state.lane_goals());
+        let mut blows = Vec::new();
+        for enemy in state.enemies.values_mut() {
+            if enemy.current_waypoint < enemy.path.len() {
+                continue; // Still walking
+            }
+            match &mut enemy.siege {
+                Siege::Marching => {}
+                Siege::Breaching => {
+                    let goal = goals.get(enemy.lane_id).copied().unwrap_or(state.goal_point);
+                    if let Some(tower) = blocking_tower(towers, grid, grid.cell_at(enemy.x, enemy.y), goal) {
+                        debug!(enemy_id = ?enemy.id, ?tower, "enemy attacking a tower in the way");
+                        enemy.siege = Siege::Attacking { tower, cooldown: 0.0 };
+                    }
+                }
+                Siege::Attacking { tower, cooldown } => {
+                    *cooldown -= delta;
+                    if *cooldown <= 0.0 {
+                        *cooldown += ATTACK_INTERVAL;
+                        blows.push(*tower);
+                    }
+                }
+            }
+        }
+
+        for tower_id in blows {
+            self.damage_tower(tower_id, ATTACK_DAMAGE); // Does nothing if an earlier blow felled it
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutators::RuleSet;
+    use crate::{BuildTool, TowerType};
+
+    fn siege_game() -> Game {
+        let mut rules = RuleSet::default();
+        rules.siege = true;
+        let mut game = Game::with_rules(rules);
+        game.state.gold = 100_000;
+        game
+    }
+
+    #[test]
+    fn test_walled_off_enemies_break_through_a_tower() {
+        let mut game = siege_game();
+        let wall_x = 10;
+        for y in 0..game.state.grid.height() {
+            assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(wall_x, y)));
+        }
+        for tower in game.state.towers.values_mut() {
+            tower.disabled = true; // Let the enemy live long enough
+        }
+        let enemy_id = game.state.spawn_enemy_with(|enemy| enemy.max_health = 1_000_000).unwrap();
+        assert_eq!(game.state.enemies[enemy_id].siege, Siege::Breaching);
+        assert_eq!(game.state.enemies[enemy_id].path.last(), Some(&Position::new(wall_x - 1, 7)));
+
+        let health = game.state.health;
+        let mut broken = None;
+        for _ in 0..3000 {
+            game.update(0.05);
+            broken = game.frame_events.iter().find_map(|event| match event {
+                GameEvent::TowerDestroyed { position, .. } => Some(*position),
+                _ => None,
+            });
+            if broken.is_some() {
+                break;
+            }
+        }
+        assert_eq!(broken, Some(Position::new(wall_x, 7)));
+        assert!(game.state.grid.is_walkable(&Position::new(wall_x, 7)));
+        assert_eq!(game.state.enemies[enemy_id].siege, Siege::Marching);
+        assert_eq!(game.state.health, health); // Waiting at the wall isn't leaking
+    }
+
+    #[test]
+    fn test_sealing_the_goal_needs_the_rule() {
+        for (mut game, allowed) in [(Game::new(), false), (siege_game(), true)] {
+            game.state.gold = 100_000;
+            let height = game.state.grid.height();
+            for y in 0..height - 1 {
+                assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(10, y)));
+            }
+            assert_eq!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(10, height - 1)), allowed);
+        }
+    }
+}
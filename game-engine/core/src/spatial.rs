@@ -0,0 +1,132 @@
+//! Uniform-grid bucketing of enemy positions for proximity queries. Queries
+//! return candidates from every bucket the query shape touches; callers do
+//! the exact distance test.
+
+use std::collections::HashMap;
+
+use slotmap::SlotMap;
+
+use crate::{Enemy, EnemyId, CELL_SIZE};
+
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    bucket_size: f32,
+    buckets: HashMap<(i32, i32), Vec<EnemyId>>,
+}
+
+impl SpatialHash {
+    pub fn new(bucket_size: f32) -> Self {
+        SpatialHash {
+            bucket_size,
+            buckets: HashMap::new(),
+        }
+    }
+
+    pub fn bucket_size(&self) -> f32 {
+        self.bucket_size
+    }
+
+    fn bucket_of(&self, x: f32, y: f32) -> (i32, i32) {
+        ((x / self.bucket_size).floor() as i32, (y / self.bucket_size).floor() as i32)
+    }
+
+    pub fn clear(&mut self) {
+        // Keep the bucket allocations; the same cells tend to fill every frame
+        for bucket in self.buckets.values_mut() {
+            bucket.clear();
+        }
+    }
+
+    pub fn insert(&mut self, id: EnemyId, x: f32, y: f32) {
+        let key = self.bucket_of(x, y);
+        self.buckets.entry(key).or_default().push(id);
+    }
+
+    /// Re-bucket every enemy at its current position
+    pub fn rebuild(&mut self, enemies: &SlotMap<EnemyId, Enemy>) {
+        self.clear();
+        for (id, enemy) in enemies {
+            self.insert(id, enemy.x, enemy.y);
+        }
+    }
+
+    /// Enemies bucketed anywhere inside the rectangle, row by row
+    pub fn query_rect(&self, min: (f32, f32), max: (f32, f32)) -> Vec<EnemyId> {
+        let (min_x, min_y) = self.bucket_of(min.0, min.1);
+        let (max_x, max_y) = self.bucket_of(max.0, max.1);
+        let mut found = Vec::new();
+        for by in min_y..=max_y {
+            for bx in min_x..=max_x {
+                if let Some(bucket) = self.buckets.get(&(bx, by)) {
+                    found.extend_from_slice(bucket);
+                }
+            }
+        }
+        found
+    }
+
+    /// Candidates whose centers may lie within `radius` of `center`
+    pub fn query_radius(&self, center: (f32, f32), radius: f32) -> Vec<EnemyId> {
+        self.query_rect((center.0 - radius, center.1 - radius), (center.0 + radius, center.1 + radius))
+    }
+
+    /// Candidates whose centers may lie within `radius` of the segment `a`-`b`
+    pub fn query_segment(&self, a: (f32, f32), b: (f32, f32), radius: f32) -> Vec<EnemyId> {
+        self.query_rect(
+            (a.0.min(b.0) - radius, a.1.min(b.1) - radius),
+            (a.0.max(b.0) + radius, a.1.max(b.1) + radius),
+        )
+    }
+
+    /// Non-empty buckets and how many enemies each holds
+    pub fn occupancy(&self) -> impl Iterator<Item = ((i32, i32), usize)> + '_ {
+        self.buckets
+            .iter()
+            .filter(|(_, bucket)| !bucket.is_empty())
+            .map(|(key, bucket)| (*key, bucket.len()))
+    }
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self::new(CELL_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: usize) -> Vec<EnemyId> {
+        let mut keys: SlotMap<EnemyId, ()> = SlotMap::with_key();
+        (0..count).map(|_| keys.insert(())).collect()
+    }
+
+    #[test]
+    fn test_radius_query_spans_bucket_edges() {
+        let ids = ids(3);
+        let mut hash = SpatialHash::new(40.0);
+        hash.insert(ids[0], 39.0, 39.0); // Bucket (0, 0), right at its corner
+        hash.insert(ids[1], 41.0, 41.0); // Bucket (1, 1)
+        hash.insert(ids[2], 200.0, 200.0);
+
+        let found = hash.query_radius((40.0, 40.0), 5.0);
+        assert!(found.contains(&ids[0]) && found.contains(&ids[1]));
+        assert!(!found.contains(&ids[2]));
+    }
+
+    #[test]
+    fn test_segment_query_and_clear() {
+        let ids = ids(2);
+        let mut hash = SpatialHash::new(40.0);
+        hash.insert(ids[0], 300.0, 20.0);
+        hash.insert(ids[1], 300.0, 300.0);
+
+        let found = hash.query_segment((0.0, 20.0), (400.0, 20.0), 12.0);
+        assert_eq!(found, vec![ids[0]]);
+
+        hash.clear();
+        assert!(hash.query_segment((0.0, 20.0), (400.0, 20.0), 12.0).is_empty());
+        assert_eq!(hash.occupancy().count(), 0);
+    }
+}
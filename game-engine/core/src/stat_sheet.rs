@@ -0,0 +1,196 @@
+//! Tower stats as base values plus modifier layers. Upgrades, auras,
+//! veterancy and debuffs all push modifiers here instead of touching the
+//! per-type tables, and the firing code reads the resolved values.
+
+use serde::{Deserialize, Serialize};
+
+use crate::TowerType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Stat {
+    Damage,
+    Range,
+    FireRate,
+    ProjectileSpeed,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 4] = [Stat::Damage, Stat::Range, Stat::FireRate, Stat::ProjectileSpeed];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ModifierOp {
+    Add(f32),      // Summed onto the base first
+    Multiply(f32), // Then every multiplier scales the total
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Modifier {
+    pub source: String, // Lets e.g. an aura remove exactly what it added
+    pub stat: Stat,
+    pub op: ModifierOp,
+    pub remaining: Option<f32>, // Seconds left for timed effects; None lasts forever
+}
+
+impl Modifier {
+    pub fn new(source: &str, stat: Stat, op: ModifierOp) -> Self {
+        Modifier {
+            source: source.to_string(),
+            stat,
+            op,
+            remaining: None,
+        }
+    }
+
+    pub fn timed(mut self, seconds: f32) -> Self {
+        self.remaining = Some(seconds);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatSheet {
+    base: [f32; 4],
+    modifiers: Vec<Modifier>,
+    #[serde(skip)]
+    resolved: [f32; 4],
+    #[serde(skip)]
+    resolved_valid: bool, // False after any change until the next `resolve`
+}
+
+impl StatSheet {
+    pub fn for_tower(tower_type: TowerType) -> Self {
+        let mut sheet = StatSheet {
+            base: [0.0; 4],
+            modifiers: Vec::new(),
+            resolved: [0.0; 4],
+            resolved_valid: false,
+        };
+        sheet.rebase(tower_type);
+        sheet
+    }
+
+    /// Swap in another type's base values, keeping every modifier (merges)
+    pub fn rebase(&mut self, tower_type: TowerType) {
+        self.base[Stat::Damage.index()] = tower_type.damage() as f32;
+        self.base[Stat::Range.index()] = tower_type.range();
+        self.base[Stat::FireRate.index()] = tower_type.fire_rate();
+        self.base[Stat::ProjectileSpeed.index()] = tower_type.projectile_speed();
+        self.resolve();
+    }
+
+    /// Override one base value (mod content); modifiers still apply on top
+    pub fn set_base(&mut self, stat: Stat, value: f32) {
+        self.base[stat.index()] = value;
+        self.resolve();
+    }
+
+    pub fn base(&self, stat: Stat) -> f32 {
+        self.base[stat.index()]
+    }
+
+    pub fn modifiers(&self) -> &[Modifier] {
+        &self.modifiers
+    }
+
+    pub fn add_modifier(&mut self, modifier: Modifier) {
+        self.modifiers.push(modifier);
+        self.resolved_valid = false;
+    }
+
+    /// Drop every modifier from `source`; returns how many were removed
+    pub fn remove_source(&mut self, source: &str) -> usize {
+        let before = self.modifiers.len();
+        self.modifiers.retain(|modifier| modifier.source != source);
+        let removed = before - self.modifiers.len();
+        if removed > 0 {
+            self.resolved_valid = false;
+        }
+        removed
+    }
+
+    /// Expire timed modifiers, then refresh the cached values. Run once per
+    /// tick so reads during the tick are plain array lookups.
+    pub fn tick(&mut self, delta: f32) {
+        let before = self.modifiers.len();
+        for modifier in &mut self.modifiers {
+            if let Some(remaining) = &mut modifier.remaining {
+                *remaining -= delta;
+            }
+        }
+        self.modifiers.retain(|modifier| modifier.remaining.is_none_or(|left| left > 0.0));
+        if self.modifiers.len() != before || !self.resolved_valid {
+            self.resolve();
+        }
+    }
+
+    pub fn resolve(&mut self) {
+        for stat in Stat::ALL {
+            self.resolved[stat.index()] = self.compute(stat);
+        }
+        self.resolved_valid = true;
+    }
+
+    /// Resolved value: cached when the sheet is up to date, computed otherwise
+    pub fn get(&self, stat: Stat) -> f32 {
+        if self.resolved_valid {
+            self.resolved[stat.index()]
+        } else {
+            self.compute(stat)
+        }
+    }
+
+    fn compute(&self, stat: Stat) -> f32 {
+        let mut added = self.base[stat.index()];
+        let mut multiplier = 1.0;
+        for modifier in self.modifiers.iter().filter(|modifier| modifier.stat == stat) {
+            match modifier.op {
+                ModifierOp::Add(amount) => added += amount,
+                ModifierOp::Multiply(factor) => multiplier *= factor,
+            }
+        }
+        (added * multiplier).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adds_apply_before_multipliers() {
+        let mut sheet = StatSheet::for_tower(TowerType::Basic);
+        assert_eq!(sheet.get(Stat::Damage), 10.0);
+
+        sheet.add_modifier(Modifier::new("upgrade", Stat::Damage, ModifierOp::Multiply(2.0)));
+        sheet.add_modifier(Modifier::new("veterancy", Stat::Damage, ModifierOp::Add(5.0)));
+        assert_eq!(sheet.get(Stat::Damage), 30.0);
+        assert_eq!(sheet.get(Stat::Range), TowerType::Basic.range());
+
+        assert_eq!(sheet.remove_source("upgrade"), 1);
+        assert_eq!(sheet.get(Stat::Damage), 15.0);
+    }
+
+    #[test]
+    fn test_timed_modifiers_expire_on_tick() {
+        let mut sheet = StatSheet::for_tower(TowerType::Basic);
+        sheet.add_modifier(Modifier::new("debuff", Stat::FireRate, ModifierOp::Multiply(0.5)).timed(1.0));
+        sheet.tick(0.5);
+        assert_eq!(sheet.get(Stat::FireRate), 0.5);
+        sheet.tick(0.5);
+        assert_eq!(sheet.get(Stat::FireRate), 1.0);
+        assert!(sheet.modifiers().is_empty());
+    }
+
+    #[test]
+    fn test_rebase_keeps_modifiers() {
+        let mut sheet = StatSheet::for_tower(TowerType::Sniper);
+        sheet.add_modifier(Modifier::new("upgrade", Stat::Range, ModifierOp::Add(1.0)));
+        sheet.rebase(TowerType::Railgun);
+        assert_eq!(sheet.get(Stat::Range), TowerType::Railgun.range() + 1.0);
+    }
+}
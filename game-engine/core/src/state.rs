@@ -0,0 +1,395 @@
+//! `GameState`: everything a run saves, and the rules for changing it
+//! directly (placing, selling, merging, spawning).
+
+use serde::{Deserialize, Serialize};
+use slotmap::SlotMap;
+use std::collections::{HashMap, HashSet};
+use tracing::{debug, info, trace, warn};
+
+use crate::actions::ActionError;
+use crate::attack::AttackState;
+use crate::convoys::ConvoyRun;
+use crate::daynight::WorldClock;
+use crate::gates::{Gate, Patrol};
+use crate::hero::Hero;
+use crate::integrity::RunIntegrity;
+use crate::lanes::Lane;
+use crate::maps::Prop;
+use crate::mods::ModRegistry;
+use crate::mutators::RuleSet;
+use crate::obstacles::Obstacle;
+use crate::portals::SpawnPortal;
+use crate::power::PowerGrid;
+use crate::rng::SimRng;
+use crate::threat::ThreatMap;
+use crate::traps::{Trap, TrapType};
+use crate::victory::Objective;
+use crate::waves::WaveManager;
+use crate::weather::WeatherState;
+use crate::{Enemy, EnemyId, Grid, Position, Tower, TowerId, TowerType, GRID_HEIGHT, GRID_WIDTH, STARTING_HEALTH};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameState {
+    pub grid: Grid,
+    pub towers: SlotMap<TowerId, Tower>,
+    pub enemies: SlotMap<EnemyId, Enemy>,
+    pub traps: HashMap<u32, Trap>,
+    pub hero: Hero,
+    pub waves: WaveManager,
+    pub spawn_point: Position,
+    pub goal_point: Position,
+    #[serde(default)]
+    pub portals: Vec<SpawnPortal>, // Besides the main spawn; see `portals`
+    #[serde(default)]
+    pub lanes: Vec<Lane>, // Separate mazes besides the main one; see `lanes`
+    #[serde(default)]
+    pub map_name: String, // Empty on the built-in board
+    pub next_trap_id: u32,
+    pub gold: i32,
+    pub health: i32,
+    pub paused: bool,
+    #[serde(default)]
+    pub content: ModRegistry, // Stat overrides from loaded mods
+    #[serde(default)]
+    pub clock: WorldClock,
+    #[serde(default)]
+    pub weather: WeatherState,
+    #[serde(default)]
+    pub obstacles: Vec<Obstacle>,
+    #[serde(default)]
+    pub undoable_clears: Vec<Obstacle>, // Cleared this build phase, most recent last
+    #[serde(default)]
+    pub gates: Vec<Gate>,
+    #[serde(default)]
+    pub patrols: Vec<Patrol>,
+    #[serde(default)]
+    pub rules: RuleSet, // Mutators chosen for this run
+    #[serde(default)]
+    pub power: PowerGrid,
+    #[serde(default)]
+    pub decorations: Vec<Prop>, // From the map; sorted by `y`, never read by the simulation
+    #[serde(default)]
+    pub rng: SimRng, // The simulation's only randomness
+    #[serde(default)]
+    pub objective: Objective, // How the run is won; see `victory`
+    #[serde(default)]
+    pub convoys: ConvoyRun, // Friendly convoys, in the convoy mode
+    #[serde(default)]
+    pub attack: AttackState, // The attacker's budget and draft, in the attack mode
+    #[serde(default)]
+    pub run_integrity: RunIntegrity, // Whether the run still counts; see `integrity`
+    #[serde(default)]
+    pub threat: ThreatMap, // Path costs from tower coverage, in the smart enemies mode
+}
+
+impl GameState {
+    pub fn new() -> Self {
+        GameState {
+            grid: Grid::new(GRID_WIDTH, GRID_HEIGHT),
+            towers: SlotMap::with_key(),
+            enemies: SlotMap::with_key(),
+            traps: HashMap::new(),
+            hero: Hero::new(Position::new(17, 7)),
+            waves: WaveManager::new(),
+            spawn_point: Position::new(0, 7),
+            goal_point: Position::new(19, 7),
+            portals: Vec::new(),
+            lanes: Vec::new(),
+            map_name: String::new(),
+            next_trap_id: 0,
+            gold: 200,
+            health: STARTING_HEALTH,
+            paused: false,
+            content: ModRegistry::new(),
+            clock: WorldClock::default(),
+            weather: WeatherState::default(),
+            obstacles: Vec::new(),
+            undoable_clears: Vec::new(),
+            gates: Vec::new(),
+            patrols: Vec::new(),
+            rules: RuleSet::default(),
+            power: PowerGrid::default(),
+            decorations: Vec::new(),
+            rng: SimRng::default(),
+            objective: Objective::default(),
+            convoys: ConvoyRun::default(),
+            attack: AttackState::default(),
+            run_integrity: RunIntegrity::default(),
+            threat: ThreatMap::default(),
+        }
+    }
+
+    pub fn place_tower(&mut self, tower_type: TowerType, position: Position) -> Result<(), ActionError> {
+        self.check_placement(tower_type, position)?;
+        let price = self.rules.price(tower_type.cost());
+        let cells = tower_type.cells_at(position);
+
+        let (content, time_of_day, weather) = (&self.content, self.clock.time_of_day(), self.weather.current);
+        let tower_id = self.towers.insert_with_key(|id| {
+            let mut tower = Tower::new(id, tower_type, position);
+            content.apply_to_tower(tower_type, &mut tower.stats);
+            crate::daynight::apply_time_of_day(&mut tower.stats, time_of_day);
+            crate::weather::apply_weather(&mut tower.stats, weather);
+            tower
+        });
+        self.gold -= price;
+        for cell in &cells {
+            self.grid.set_walkable(cell, false);
+        }
+        info!(?tower_id, ?tower_type, ?position, gold = self.gold, "tower placed");
+
+        self.repath_through(&cells);
+
+        Ok(())
+    }
+
+    /// Whether a tower could go at `position` now: allowed by the rules,
+    /// affordable, on open cells clear of the hero, and not cutting a route
+    pub fn can_place_tower(&self, tower_type: TowerType, position: Position) -> bool {
+        self.check_placement(tower_type, position).is_ok()
+    }
+
+    /// Why a tower can't go at `position` now, if it can't
+    pub fn check_placement(&self, tower_type: TowerType, position: Position) -> Result<(), ActionError> {
+        self.check_planned_placement(tower_type, position, &HashSet::new(), self.gold)
+    }
+
+    /// `check_placement` as if the `planned` cells were already built on
+    /// and only `gold` were left, for towers that go down together
+    pub fn check_planned_placement(
+        &self,
+        tower_type: TowerType,
+        position: Position,
+        planned: &HashSet<Position>,
+        gold: i32,
+    ) -> Result<(), ActionError> {
+        if !self.rules.allows_tower(tower_type) {
+            debug!(?tower_type, ?position, "placement rejected: tower type not allowed");
+            return Err(ActionError::NotAllowed);
+        }
+        if !tower_type.fits_on(self.grid.shape()) {
+            debug!(?tower_type, ?position, "placement rejected: no footprint on this board");
+            return Err(ActionError::NotAllowed);
+        }
+        let cells = tower_type.cells_at(position);
+        for cell in &cells {
+            let refusal = self.solid_cell_refusal(cell).or(planned.contains(cell).then_some(ActionError::CellBlocked));
+            if let Some(err) = refusal {
+                debug!(?tower_type, ?position, ?cell, ?err, "placement rejected");
+                return Err(err);
+            }
+        }
+
+        let price = self.rules.price(tower_type.cost());
+        if gold < price {
+            debug!(?tower_type, ?position, gold, "placement rejected: not enough gold");
+            return Err(ActionError::InsufficientGold { price, gold });
+        }
+
+        let walls = planned.iter().chain(&cells).copied().collect();
+        if !self.rules.siege && self.any_route_cut_by(&walls) {
+            debug!(?tower_type, ?position, "placement rejected: would block the path");
+            return Err(ActionError::WouldBlockPath);
+        }
+
+        Ok(())
+    }
+
+    /// Check whether blocking `cells` would cut any portal off from the goal,
+    /// or any lane's spawn from its goal
+    pub fn would_block_path(&self, cells: &[Position]) -> bool {
+        self.any_route_cut_by(&cells.iter().copied().collect())
+    }
+
+    /// Sell a tower for part of its cost, freeing every cell it covered
+    pub fn sell_tower(&mut self, tower_id: TowerId) -> Result<(), ActionError> {
+        if !self.rules.allows_selling() {
+            debug!(?tower_id, "sale rejected: selling not allowed");
+            return Err(ActionError::NotAllowed);
+        }
+        let tower = self.towers.remove(tower_id).ok_or(ActionError::NoSuchTower)?;
+
+        for cell in tower.cells() {
+            self.grid.set_walkable(&cell, true);
+        }
+        self.gold += self.rules.price(tower.tower_type.cost()) * 7 / 10;
+        info!(?tower_id, tower_type = ?tower.tower_type, gold = self.gold, "tower sold");
+
+        self.recalculate_paths();
+
+        Ok(())
+    }
+
+    /// Merge `source` into an adjacent `target` tower according to the recipe table.
+    /// The source tower is removed and the target becomes the hybrid.
+    pub fn merge_towers(&mut self, source_id: TowerId, target_id: TowerId) -> Result<(), ActionError> {
+        if source_id == target_id {
+            return Err(ActionError::NotAdjacent);
+        }
+        let (Some(source), Some(target)) = (self.towers.get(source_id), self.towers.get(target_id)) else {
+            return Err(ActionError::NoSuchTower);
+        };
+
+        let adjacent = source.cells().iter().any(|cell| {
+            self.grid.neighbors(*cell).iter().any(|neighbor| target.occupies(neighbor))
+        });
+        if !adjacent {
+            return Err(ActionError::NotAdjacent);
+        }
+
+        let result = crate::merging::find_recipe(source.tower_type, target.tower_type).ok_or(ActionError::NoRecipe)?;
+        if !self.rules.allows_tower(result) {
+            debug!(?result, "merge rejected: tower type not allowed");
+            return Err(ActionError::NotAllowed);
+        }
+
+        let source = self.towers.remove(source_id).unwrap();
+        for cell in source.cells() {
+            self.grid.set_walkable(&cell, true);
+        }
+        if let Some(target) = self.towers.get_mut(target_id) {
+            target.tower_type = result;
+            target.stats.rebase(result);
+            self.content.apply_to_tower(result, &mut target.stats);
+            target.cooldown_remaining = 0.0;
+        }
+        info!(?source_id, ?target_id, ?result, "towers merged");
+
+        self.recalculate_paths();
+
+        Ok(())
+    }
+
+    pub fn tower_at(&self, cell: &Position) -> Option<&Tower> {
+        self.towers.values().find(|tower| tower.occupies(cell))
+    }
+
+    /// Re-route every moving unit after the grid changed
+    pub(crate) fn recalculate_paths(&mut self) {
+        debug!(enemies = self.enemies.len(), "recomputing paths");
+        let goals = self.lane_goals();
+        for enemy in self.enemies.values_mut() {
+            enemy.recalculate_path(&self.grid, goals.get(enemy.lane_id).copied().unwrap_or(self.goal_point));
+        }
+        self.hero.recalculate_path(&self.grid);
+        self.convoys.recalculate_paths(&self.grid, self.spawn_point);
+    }
+
+    /// Repath after `closed` became unwalkable. Blocking cells can't make
+    /// another route shorter, so only enemies routed through them need a
+    /// new one.
+    pub(crate) fn repath_through(&mut self, closed: &[Position]) {
+        let mut repathed = 0;
+        let goals = self.lane_goals();
+        for enemy in self.enemies.values_mut() {
+            if closed.iter().any(|cell| enemy.route_crosses(*cell)) {
+                enemy.recalculate_path(&self.grid, goals.get(enemy.lane_id).copied().unwrap_or(self.goal_point));
+                repathed += 1;
+            }
+        }
+        debug!(repathed, enemies = self.enemies.len(), "recomputing blocked paths");
+        self.hero.recalculate_path(&self.grid);
+        self.convoys.recalculate_paths(&self.grid, self.spawn_point);
+    }
+
+    pub fn place_trap(&mut self, trap_type: TrapType, position: Position) -> Result<(), ActionError> {
+        // Traps go on open path cells, one per cell, never on a portal or the goal
+        if let Some(err) = self.cell_refusal(&position) {
+            return Err(err);
+        }
+        if self.is_goal(&position) {
+            return Err(ActionError::NotBuildable);
+        }
+        let price = self.rules.price(trap_type.cost());
+        if self.gold < price {
+            return Err(ActionError::InsufficientGold { price, gold: self.gold });
+        }
+
+        let trap = Trap::new(self.next_trap_id, trap_type, position);
+        self.traps.insert(self.next_trap_id, trap);
+        self.next_trap_id += 1;
+        self.gold -= price;
+        info!(?trap_type, ?position, gold = self.gold, "trap placed");
+
+        Ok(())
+    }
+
+    pub fn trap_at(&self, position: &Position) -> Option<&Trap> {
+        self.traps.values().find(|trap| trap.position == *position)
+    }
+
+    /// Spawn an enemy at the main spawn. Refused if it has no way to the goal.
+    pub fn spawn_enemy(&mut self) -> Result<(), ActionError> {
+        self.spawn_enemy_with(|_| {}).map(|_| ()).ok_or(ActionError::NoRoute)
+    }
+
+    /// Spawn an enemy, letting `adjust` change it after mods have applied
+    pub fn spawn_enemy_with(&mut self, adjust: impl FnOnce(&mut Enemy)) -> Option<EnemyId> {
+        self.spawn_enemy_from(self.spawn_point, adjust)
+    }
+
+    /// Like `spawn_enemy_with`, out of the portal or lane spawn at `spawn`,
+    /// headed for that lane's goal
+    pub fn spawn_enemy_from(&mut self, spawn: Position, adjust: impl FnOnce(&mut Enemy)) -> Option<EnemyId> {
+        let lane_id = self.lane_spawning_at(spawn);
+        let goal = self.lane_goal(lane_id);
+        let (grid, content, siege) = (&self.grid, &self.content, self.rules.siege);
+        let spawned = self.enemies.try_insert_with_key(|id| {
+            let enemy = if siege { Enemy::new_or_breaching(id, spawn, goal, grid) } else { Enemy::new(id, spawn, goal, grid) };
+            let mut enemy = enemy.ok_or(())?;
+            enemy.lane_id = lane_id;
+            content.apply_to_enemy(&mut enemy);
+            adjust(&mut enemy);
+            Ok::<_, ()>(enemy)
+        });
+        match spawned {
+            Ok(enemy_id) => {
+                trace!(?enemy_id, "enemy spawned");
+                if self.rules.smart_enemies {
+                    self.route_around_threat(enemy_id);
+                }
+                Some(enemy_id)
+            }
+            Err(()) => {
+                warn!("enemy spawn failed: no path from spawn to goal");
+                None
+            }
+        }
+    }
+
+    /// Skip the build countdown and start the next wave, earning bonus gold
+    /// for the time skipped
+    pub fn call_next_wave(&mut self) -> Result<(), ActionError> {
+        if self.rules.attack {
+            return self.send_attack();
+        }
+        let bonus = self.waves.call_early().ok_or(ActionError::NotReady)?;
+        let bonus = self.rules.gold(bonus);
+        self.gold += bonus;
+        self.charge_upkeep();
+        info!(wave = self.waves.wave, bonus, "wave called early");
+        Ok(())
+    }
+
+    /// Enemy movement multiplier from the weather and the run's mutators
+    pub fn enemy_pace(&self) -> f32 {
+        self.weather.current.enemy_pace() * self.rules.enemy_pace()
+    }
+
+    pub fn clear_all(&mut self) {
+        for tower in self.towers.values() {
+            for cell in tower.cells() {
+                self.grid.set_walkable(&cell, true);
+            }
+        }
+        self.towers.clear();
+        self.enemies.clear();
+        self.traps.clear();
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,230 @@
+//! Per-run statistics: running tallies fed from the event bus, plus a time
+//! series sampled once a second and one record per wave, for the
+//! end-of-game dashboard and run exports (see `report.rs`). The hits of
+//! the last `DPS_WINDOW` seconds are also kept, for a live DPS meter.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::GameEvent;
+use crate::TowerType;
+
+/// Seconds of game time between samples
+pub const SAMPLE_INTERVAL: f32 = 1.0;
+
+/// Seconds of hits behind `rolling_dps`
+pub const DPS_WINDOW: f32 = 10.0;
+
+/// Cumulative totals at one point in the run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatSample {
+    pub time: f32,
+    pub gold_earned: i64,
+    pub gold_spent: i64,
+    pub damage: u64,
+}
+
+/// What happened between one wave starting and the next
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaveStats {
+    pub wave: u32,
+    pub started_at: f32,
+    pub cleared_at: Option<f32>, // None if the run ended first
+    pub kills: u32,
+    pub leaks: u32,
+    pub damage: u64,
+    pub gold_earned: i64,
+    pub gold_spent: i64,
+    pub damage_by_tower: Vec<(TowerType, u64)>, // In the order each type first hit something
+}
+
+/// Running tallies for the current game, fed from the event bus
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub current_wave: u32,
+    pub kills: u32,
+    pub total_leaks: u32,
+    pub leaks_by_wave: HashMap<u32, u32>,
+    pub damage_by_tower: Vec<(TowerType, u64)>, // In the order each type first hit something
+    pub total_damage: u64,
+    pub gold_earned: i64,
+    pub gold_spent: i64,
+    pub elapsed: f32,
+    pub samples: Vec<StatSample>,
+    pub waves: Vec<WaveStats>,
+    last_gold: Option<i32>,
+    since_sample: f32,
+    recent_hits: VecDeque<(f32, TowerType, u64)>, // (time, by, damage), oldest first
+}
+
+impl RunStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::WaveStarted { wave } => {
+                self.current_wave = *wave;
+                self.waves.push(WaveStats { wave: *wave, started_at: self.elapsed, ..WaveStats::default() });
+            }
+            GameEvent::WaveCleared { wave } => {
+                if let Some(record) = self.waves.iter_mut().rev().find(|record| record.wave == *wave) {
+                    record.cleared_at = Some(self.elapsed);
+                }
+            }
+            GameEvent::EnemyKilled { .. } => {
+                self.kills += 1;
+                if let Some(record) = self.waves.last_mut() {
+                    record.kills += 1;
+                }
+            }
+            GameEvent::EnemyLeaked { .. } => {
+                self.total_leaks += 1;
+                *self.leaks_by_wave.entry(self.current_wave).or_insert(0) += 1;
+                if let Some(record) = self.waves.last_mut() {
+                    record.leaks += 1;
+                }
+            }
+            GameEvent::DamageDealt { tower_type, amount } => {
+                let amount = (*amount).max(0) as u64;
+                self.total_damage += amount;
+                if let Some(record) = self.waves.last_mut() {
+                    record.damage += amount;
+                    add_damage(&mut record.damage_by_tower, *tower_type, amount);
+                }
+                add_damage(&mut self.damage_by_tower, *tower_type, amount);
+                self.recent_hits.push_back((self.elapsed, *tower_type, amount));
+            }
+            GameEvent::TowerPlaced { .. } | GameEvent::TowerSold { .. } | GameEvent::TowerDestroyed { .. } => {}
+        }
+    }
+
+    /// Compare against last frame's gold: a rise was earned, a drop was spent
+    pub fn track_gold(&mut self, gold: i32) {
+        if let Some(last) = self.last_gold {
+            let change = (gold - last) as i64;
+            let record = self.waves.last_mut();
+            if change > 0 {
+                self.gold_earned += change;
+                if let Some(record) = record {
+                    record.gold_earned += change;
+                }
+            } else {
+                self.gold_spent -= change;
+                if let Some(record) = record {
+                    record.gold_spent -= change;
+                }
+            }
+        }
+        self.last_gold = Some(gold);
+    }
+
+    /// Advance the clock, taking a sample every `SAMPLE_INTERVAL`
+    pub fn advance(&mut self, delta: f32) {
+        if self.samples.is_empty() {
+            self.samples.push(self.sample());
+        }
+        self.elapsed += delta;
+        self.since_sample += delta;
+        while self.since_sample >= SAMPLE_INTERVAL {
+            self.since_sample -= SAMPLE_INTERVAL;
+            self.samples.push(self.sample());
+        }
+        while self.recent_hits.front().is_some_and(|(time, _, _)| *time < self.elapsed - DPS_WINDOW) {
+            self.recent_hits.pop_front();
+        }
+    }
+
+    fn sample(&self) -> StatSample {
+        StatSample {
+            time: self.elapsed,
+            gold_earned: self.gold_earned,
+            gold_spent: self.gold_spent,
+            damage: self.total_damage,
+        }
+    }
+
+    /// Damage per second over each sample interval, as (time, dps) points
+    pub fn dps_series(&self) -> Vec<(f32, f32)> {
+        self.samples
+            .windows(2)
+            .map(|pair| {
+                let span = (pair[1].time - pair[0].time).max(f32::EPSILON);
+                (pair[1].time, (pair[1].damage - pair[0].damage) as f32 / span)
+            })
+            .collect()
+    }
+
+    /// Damage per second by tower type over the last `DPS_WINDOW` seconds
+    /// (or the whole run, if shorter), highest first
+    pub fn rolling_dps(&self) -> Vec<(TowerType, f32)> {
+        let mut totals: Vec<(TowerType, u64)> = Vec::new();
+        for (_, tower_type, amount) in &self.recent_hits {
+            add_damage(&mut totals, *tower_type, *amount);
+        }
+        let span = self.elapsed.clamp(SAMPLE_INTERVAL, DPS_WINDOW);
+        let mut dps: Vec<(TowerType, f32)> =
+            totals.into_iter().map(|(tower_type, total)| (tower_type, total as f32 / span)).collect();
+        dps.sort_by(|a, b| b.1.total_cmp(&a.1));
+        dps
+    }
+
+    pub fn leaks_in_wave(&self, wave: u32) -> u32 {
+        self.leaks_by_wave.get(&wave).copied().unwrap_or(0)
+    }
+}
+
+fn add_damage(totals: &mut Vec<(TowerType, u64)>, tower_type: TowerType, amount: u64) {
+    match totals.iter_mut().find(|(t, _)| *t == tower_type) {
+        Some((_, total)) => *total += amount,
+        None => totals.push((tower_type, amount)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EnemyId;
+
+    #[test]
+    fn test_leaks_are_counted_per_wave() {
+        let mut stats = RunStats::new();
+        let leak = GameEvent::EnemyLeaked { enemy_id: EnemyId::default(), x: 0.0, y: 0.0 };
+
+        stats.record(&GameEvent::WaveStarted { wave: 1 });
+        stats.record(&leak);
+        stats.record(&GameEvent::WaveStarted { wave: 2 });
+        stats.record(&leak);
+        stats.record(&leak);
+
+        assert_eq!(stats.leaks_in_wave(1), 1);
+        assert_eq!(stats.leaks_in_wave(2), 2);
+        assert_eq!(stats.leaks_in_wave(3), 0);
+        assert_eq!(stats.total_leaks, 3);
+    }
+
+    #[test]
+    fn test_gold_and_damage_series() {
+        let mut stats = RunStats::new();
+        stats.track_gold(100);
+        stats.advance(0.5);
+        stats.track_gold(50); // Bought a tower
+        stats.record(&GameEvent::DamageDealt { tower_type: TowerType::Basic, amount: 20 });
+        stats.record(&GameEvent::DamageDealt { tower_type: TowerType::Slow, amount: 5 });
+        stats.record(&GameEvent::DamageDealt { tower_type: TowerType::Basic, amount: 10 });
+        stats.advance(0.5);
+        stats.track_gold(60);
+        stats.advance(1.0);
+
+        assert_eq!((stats.gold_earned, stats.gold_spent), (10, 50));
+        assert_eq!(stats.damage_by_tower, vec![(TowerType::Basic, 30), (TowerType::Slow, 5)]);
+        assert_eq!(stats.samples.len(), 3);
+        assert_eq!(stats.dps_series(), vec![(1.0, 35.0), (2.0, 0.0)]);
+        assert_eq!(stats.rolling_dps(), vec![(TowerType::Basic, 15.0), (TowerType::Slow, 2.5)]);
+
+        stats.advance(DPS_WINDOW);
+        assert!(stats.rolling_dps().is_empty());
+    }
+}
@@ -0,0 +1,381 @@
+//! Status effects on enemies and how several of one kind combine. Each
+//! source (a tower, a trap, a spell) keeps its own entry on the enemy, which
+//! a fresh hit from the same source replaces; the enemy feels all entries of
+//! a kind combined by that kind's `Stacking` policy. Policies come from the
+//! mod registry (`ModRegistry::effect_rules`) and default to
+//! `EffectRules::default`.
+//!
+//! Slow magnitudes are the fraction of speed taken away (1 is frozen solid)
+//! and combine to at most 1. Burn magnitudes are damage per second. Shred
+//! and amplify are the debuffs the damage formula reads (see `debuffs`): the
+//! fraction of armor stripped, and the extra fraction of damage taken.
+
+use serde::{Deserialize, Serialize};
+
+use crate::abilities::AbilityType;
+use crate::checksum::StateHasher;
+use crate::{Enemy, EnemyId, Game, TowerType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EffectKind {
+    Slow,
+    Burn,
+    Shred,
+    Amplify,
+}
+
+impl EffectKind {
+    pub const ALL: [EffectKind; 4] = [EffectKind::Slow, EffectKind::Burn, EffectKind::Shred, EffectKind::Amplify];
+
+    /// Highest combined magnitude that means anything
+    fn ceiling(&self) -> f32 {
+        match self {
+            EffectKind::Slow | EffectKind::Shred => 1.0,
+            EffectKind::Burn | EffectKind::Amplify => f32::INFINITY,
+        }
+    }
+}
+
+/// Who put an effect on an enemy. Towers of one type count as one source,
+/// so a row of them keeps one slow going rather than stacking it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EffectSource {
+    Tower(TowerType),
+    Trap(u32),
+    Ability(AbilityType),
+}
+
+/// How entries of one kind from different sources combine
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum Stacking {
+    /// Only the strongest entry counts
+    StrongestWins,
+    /// Strongest first; each weaker entry counts `falloff` times as much as
+    /// the one before it
+    DiminishingReturns { falloff: f32 },
+    /// Entries add up, to at most `cap`
+    Additive { cap: f32 },
+}
+
+impl Stacking {
+    /// Combine magnitudes; the order they were applied in doesn't matter
+    pub fn combine(&self, magnitudes: impl IntoIterator<Item = f32>) -> f32 {
+        let mut magnitudes: Vec<f32> = magnitudes.into_iter().collect();
+        magnitudes.sort_by(|a, b| b.total_cmp(a));
+        match *self {
+            Stacking::StrongestWins => magnitudes.first().copied().unwrap_or(0.0),
+            Stacking::DiminishingReturns { falloff } => {
+                let weights = std::iter::successors(Some(1.0), |weight| Some(weight * falloff.clamp(0.0, 1.0)));
+                magnitudes.iter().zip(weights).map(|(magnitude, weight)| magnitude * weight).sum()
+            }
+            Stacking::Additive { cap } => magnitudes.iter().sum::<f32>().min(cap),
+        }
+    }
+}
+
+/// The stacking policy for every effect kind
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EffectRules {
+    pub slow: Stacking,
+    pub burn: Stacking,
+    pub shred: Stacking,
+    pub amplify: Stacking,
+}
+
+impl Default for EffectRules {
+    fn default() -> Self {
+        EffectRules {
+            slow: Stacking::StrongestWins,
+            burn: Stacking::Additive { cap: 20.0 },
+            shred: Stacking::Additive { cap: 1.0 },
+            amplify: Stacking::DiminishingReturns { falloff: 0.5 },
+        }
+    }
+}
+
+impl EffectRules {
+    pub fn stacking(&self, kind: EffectKind) -> Stacking {
+        match kind {
+            EffectKind::Slow => self.slow,
+            EffectKind::Burn => self.burn,
+            EffectKind::Shred => self.shred,
+            EffectKind::Amplify => self.amplify,
+        }
+    }
+
+    pub fn set(&mut self, kind: EffectKind, stacking: Stacking) {
+        match kind {
+            EffectKind::Slow => self.slow = stacking,
+            EffectKind::Burn => self.burn = stacking,
+            EffectKind::Shred => self.shred = stacking,
+            EffectKind::Amplify => self.amplify = stacking,
+        }
+    }
+}
+
+/// One source's effect on an enemy
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: EffectKind,
+    pub source: EffectSource,
+    pub magnitude: f32,
+    pub remaining: f32, // Seconds
+}
+
+/// Everything affecting one enemy
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EffectStack {
+    entries: Vec<StatusEffect>, // In the order they were first applied
+    burn_owed: f32,             // Burn damage not yet dealt, below one point
+}
+
+impl EffectStack {
+    /// Add an effect, replacing the same source's earlier one of that kind
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let same = |entry: &&mut StatusEffect| entry.kind == effect.kind && entry.source == effect.source;
+        match self.entries.iter_mut().find(same) {
+            Some(entry) => *entry = effect,
+            None => self.entries.push(effect),
+        }
+    }
+
+    /// Like `apply`, but a fresh hit from the same source adds its magnitude
+    /// to what's there, up to `max`, and restarts the timer
+    pub fn stack(&mut self, effect: StatusEffect, max: f32) {
+        let same = |entry: &&mut StatusEffect| entry.kind == effect.kind && entry.source == effect.source;
+        match self.entries.iter_mut().find(same) {
+            Some(entry) => {
+                entry.magnitude = (entry.magnitude + effect.magnitude).min(max);
+                entry.remaining = effect.remaining;
+            }
+            None => self.entries.push(StatusEffect { magnitude: effect.magnitude.min(max), ..effect }),
+        }
+    }
+
+    /// Count down, dropping what ran out. Returns the whole points of burn
+    /// damage dealt over `delta`, under `rules`.
+    pub fn tick(&mut self, delta: f32, rules: &EffectRules) -> i32 {
+        // Burn over the part of `delta` each entry was still running
+        let mut burned = 0.0;
+        let mut active: Vec<&StatusEffect> =
+            self.entries.iter().filter(|entry| entry.kind == EffectKind::Burn).collect();
+        let mut elapsed = 0.0;
+        active.sort_by(|a, b| a.remaining.total_cmp(&b.remaining));
+        while elapsed < delta && !active.is_empty() {
+            let until = active[0].remaining.min(delta);
+            let rate = rules.burn.combine(active.iter().map(|entry| entry.magnitude));
+            burned += rate * (until - elapsed).max(0.0);
+            elapsed = elapsed.max(until);
+            active.retain(|entry| entry.remaining > elapsed);
+        }
+
+        for entry in &mut self.entries {
+            entry.remaining -= delta;
+        }
+        self.entries.retain(|entry| entry.remaining > 0.0);
+
+        self.burn_owed += burned;
+        let dealt = self.burn_owed.floor();
+        self.burn_owed -= dealt;
+        dealt as i32
+    }
+
+    /// The combined magnitude of a kind under `rules`
+    pub fn total(&self, kind: EffectKind, rules: &EffectRules) -> f32 {
+        let magnitudes = self.entries.iter().filter(|entry| entry.kind == kind).map(|entry| entry.magnitude);
+        rules.stacking(kind).combine(magnitudes).clamp(0.0, kind.ceiling())
+    }
+
+    pub fn has(&self, kind: EffectKind) -> bool {
+        self.entries.iter().any(|entry| entry.kind == kind)
+    }
+
+    pub fn entries(&self) -> &[StatusEffect] {
+        &self.entries
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.usize(self.entries.len());
+        for entry in &self.entries {
+            hasher.variant(&entry.kind);
+            hasher.variant(&entry.source);
+            hasher.f32(entry.magnitude);
+            hasher.f32(entry.remaining);
+        }
+        hasher.f32(self.burn_owed);
+    }
+}
+
+impl StatusEffect {
+    pub fn new(kind: EffectKind, source: EffectSource, magnitude: f32, duration: f32) -> Self {
+        StatusEffect { kind, source, magnitude, remaining: duration }
+    }
+
+    /// A slow given the old way, as a speed multiplier (0.5 is half speed)
+    pub fn slow(source: EffectSource, duration: f32, multiplier: f32) -> Self {
+        StatusEffect::new(EffectKind::Slow, source, 1.0 - multiplier.clamp(0.0, 1.0), duration)
+    }
+}
+
+impl Enemy {
+    pub fn apply_effect(&mut self, effect: StatusEffect, rules: &EffectRules) {
+        self.effects.apply(effect);
+        self.slow_multiplier = 1.0 - self.effects.total(EffectKind::Slow, rules);
+    }
+
+    /// Add a hit's worth of an effect that builds up; see `EffectStack::stack`
+    pub fn stack_effect(&mut self, effect: StatusEffect, max: f32, rules: &EffectRules) {
+        self.effects.stack(effect, max);
+        self.slow_multiplier = 1.0 - self.effects.total(EffectKind::Slow, rules);
+    }
+
+    pub fn is_slowed(&self) -> bool {
+        self.effects.has(EffectKind::Slow)
+    }
+
+    /// Count its effects down. Returns the burn damage it takes over `delta`.
+    pub fn tick_effects(&mut self, delta: f32, rules: &EffectRules) -> i32 {
+        let burned = self.effects.tick(delta, rules);
+        self.slow_multiplier = 1.0 - self.effects.total(EffectKind::Slow, rules);
+        burned
+    }
+}
+
+impl Game {
+    /// Put an effect on an enemy, stacked under the loaded mods' rules
+    pub(crate) fn apply_effect(&mut self, enemy_id: EnemyId, effect: StatusEffect) {
+        let rules = self.state.content.effect_rules();
+        if let Some(enemy) = self.state.enemies.get_mut(enemy_id) {
+            enemy.apply_effect(effect, &rules);
+        }
+    }
+
+    pub(crate) fn stack_effect(&mut self, enemy_id: EnemyId, effect: StatusEffect, max: f32) {
+        let rules = self.state.content.effect_rules();
+        if let Some(enemy) = self.state.enemies.get_mut(enemy_id) {
+            enemy.stack_effect(effect, max, &rules);
+        }
+    }
+
+    /// Wear effects down and deal burn damage, before anyone moves
+    pub(crate) fn update_status_effects(&mut self, delta: f32) {
+        let rules = self.state.content.effect_rules();
+        let burns: Vec<(EnemyId, i32)> = self
+            .state
+            .enemies
+            .iter_mut()
+            .map(|(id, enemy)| (id, enemy.tick_effects(delta, &rules)))
+            .filter(|(_, damage)| *damage > 0)
+            .collect();
+        if burns.is_empty() {
+            return;
+        }
+        for (enemy_id, damage) in burns {
+            self.damage_enemy(enemy_id, damage);
+        }
+        self.remove_dead_enemies();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn effect(kind: EffectKind, trap: u32, magnitude: f32, remaining: f32) -> StatusEffect {
+        StatusEffect { kind, source: EffectSource::Trap(trap), magnitude, remaining }
+    }
+
+    #[test]
+    fn test_policies_ignore_application_order() {
+        let policies = [
+            (Stacking::StrongestWins, 0.5),
+            (Stacking::DiminishingReturns { falloff: 0.5 }, 0.5 + 0.3 * 0.5 + 0.2 * 0.25),
+            (Stacking::Additive { cap: 0.8 }, 0.8),
+        ];
+        for (stacking, expected) in policies {
+            for order in [[0.2, 0.5, 0.3], [0.5, 0.3, 0.2], [0.3, 0.2, 0.5]] {
+                assert!((stacking.combine(order) - expected).abs() < 1e-6, "{:?} {:?}", stacking, order);
+            }
+            assert_eq!(stacking.combine([]), 0.0);
+        }
+    }
+
+    #[test]
+    fn test_same_source_refreshes_instead_of_stacking() {
+        let rules = EffectRules { slow: Stacking::Additive { cap: 1.0 }, ..EffectRules::default() };
+        let mut stack = EffectStack::default();
+        stack.apply(effect(EffectKind::Slow, 1, 0.3, 1.0));
+        stack.apply(effect(EffectKind::Slow, 1, 0.3, 2.0));
+        assert_eq!(stack.entries().len(), 1);
+        assert!((stack.total(EffectKind::Slow, &rules) - 0.3).abs() < 1e-6);
+
+        stack.apply(effect(EffectKind::Slow, 2, 0.9, 1.0));
+        assert_eq!(stack.total(EffectKind::Slow, &rules), 1.0); // Never faster than frozen
+    }
+
+    #[test]
+    fn test_expiry_hands_over_to_the_next_strongest() {
+        let rules = EffectRules::default();
+        let mut stack = EffectStack::default();
+        stack.apply(effect(EffectKind::Slow, 1, 0.6, 1.0));
+        stack.apply(effect(EffectKind::Slow, 2, 0.25, 3.0));
+        assert_eq!(stack.total(EffectKind::Slow, &rules), 0.6);
+
+        stack.tick(1.5, &rules);
+        assert_eq!(stack.total(EffectKind::Slow, &rules), 0.25);
+        stack.tick(1.5, &rules);
+        assert!(!stack.has(EffectKind::Slow));
+        assert_eq!(stack.total(EffectKind::Slow, &rules), 0.0);
+    }
+
+    #[test]
+    fn test_burn_stops_when_each_entry_expires_mid_tick() {
+        let rules = EffectRules { burn: Stacking::Additive { cap: 100.0 }, ..EffectRules::default() };
+        let mut stack = EffectStack::default();
+        stack.apply(effect(EffectKind::Burn, 1, 10.0, 0.5));
+        stack.apply(effect(EffectKind::Burn, 2, 4.0, 2.0));
+
+        // 14/s for half a second, then 4/s for the other half
+        assert_eq!(stack.tick(1.0, &rules), 9);
+        // 4/s for the last second, then nothing
+        assert_eq!(stack.tick(5.0, &rules), 4);
+        assert!(stack.entries().is_empty());
+
+        // Fractions carry over between ticks
+        stack.apply(effect(EffectKind::Burn, 3, 1.0, 10.0));
+        let dealt: i32 = (0..10).map(|_| stack.tick(0.25, &rules)).sum();
+        assert_eq!(dealt, 2);
+    }
+
+    #[test]
+    fn test_game_stacks_slows_under_mod_rules() {
+        let mut game = Game::new();
+        let enemy_id = game.state.spawn_enemy_with(|_| {}).unwrap();
+        game.apply_effect(enemy_id, StatusEffect::slow(EffectSource::Tower(TowerType::Slow), 2.0, 0.5));
+        game.apply_effect(enemy_id, StatusEffect::slow(EffectSource::Trap(1), 4.0, 0.7));
+        assert_eq!(game.state.enemies[enemy_id].slow_multiplier, 0.5); // Strongest wins
+
+        let manifest = crate::mods::ModManifest {
+            name: "sticky".to_string(),
+            version: String::new(),
+            towers: Vec::new(),
+            enemy: Default::default(),
+            effects: vec![crate::mods::EffectRule {
+                effect: EffectKind::Slow,
+                stacking: Stacking::Additive { cap: 0.9 },
+            }],
+            script: None,
+        };
+        game.state.content.merge(&manifest);
+        game.update_status_effects(0.0);
+        assert!((game.state.enemies[enemy_id].slow_multiplier - 0.2).abs() < 1e-6);
+
+        // The tower's slow wears off; the trap's is still on
+        game.update_status_effects(2.5);
+        assert!((game.state.enemies[enemy_id].slow_multiplier - 0.7).abs() < 1e-6);
+        game.update_status_effects(2.0);
+        assert!(!game.state.enemies[enemy_id].is_slowed());
+        assert_eq!(game.state.enemies[enemy_id].slow_multiplier, 1.0);
+    }
+}
@@ -0,0 +1,169 @@
+//! Threat-aware routing for the smart enemies mode
+//! (`RuleSet::smart_enemies`). Crossing a cell costs 1 plus a threat term
+//! from the tower DPS covering it (see `coverage`), so where the maze
+//! leaves more than one way through, enemies take the less defended one
+//! even if it's longer.
+//!
+//! The threat map is rebuilt whenever `Coverage::fingerprint` changes (a
+//! tower built, sold or upgraded, or the grid changed), and every marching
+//! enemy reroutes through it then. It's kept in the state, so a restored
+//! snapshot doesn't reroute anyone it wouldn't have.
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::checksum::StateHasher;
+use crate::coverage::Coverage;
+use crate::pathfinding::{find_path, simplify_path, PathGraph};
+use crate::siege::Siege;
+use crate::{EnemyId, GameState, Grid, Position};
+
+pub const DPS_PER_STEP: f32 = 10.0; // Covering DPS that weighs as much as one more cell of walking
+pub const MAX_THREAT: i32 = 10; // So no single cell is worth any detour
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThreatMap {
+    fingerprint: u64, // `Coverage::fingerprint` it was built for; 0 before the first build
+    width: i32,
+    costs: Vec<i32>, // Row-major: 1 plus the cell's threat
+}
+
+impl ThreatMap {
+    pub fn build(state: &GameState, fingerprint: u64) -> Self {
+        let coverage = Coverage::compute(state);
+        let (width, height) = (state.grid.width(), state.grid.height());
+        let mut costs = Vec::with_capacity((width * height).max(0) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let threat = (coverage.dps_at(Position::new(x, y)) / DPS_PER_STEP).round() as i32;
+                costs.push(1 + threat.min(MAX_THREAT));
+            }
+        }
+        ThreatMap { fingerprint, width, costs }
+    }
+
+    /// Changes whenever the costs do
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// What entering a cell costs; 1 off the map
+    pub fn cost_at(&self, cell: Position) -> i32 {
+        if cell.x < 0 || cell.y < 0 || cell.x >= self.width {
+            return 1;
+        }
+        self.costs.get((cell.y * self.width + cell.x) as usize).copied().unwrap_or(1)
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.u64(self.fingerprint); // The costs follow from it
+    }
+}
+
+/// Walkers on the board, with each step weighed by the threat map
+pub struct Threatened<'a> {
+    pub grid: &'a Grid,
+    pub threat: &'a ThreatMap,
+}
+
+impl PathGraph for Threatened<'_> {
+    fn is_open(&self, pos: &Position) -> bool {
+        self.grid.is_open(pos)
+    }
+
+    fn moves(&self, from: Position) -> Vec<(Position, i32)> {
+        self.grid.exits(from).into_iter().map(|to| (to, self.threat.cost_at(to))).collect()
+    }
+
+    fn heuristic(&self, from: &Position, goal: &Position) -> i32 {
+        self.grid.heuristic(from, goal) // Every step costs at least 1
+    }
+}
+
+impl GameState {
+    /// Rebuild the threat map if the towers or the grid changed, and reroute
+    /// every marching enemy through it
+    pub(crate) fn update_threat(&mut self) {
+        if !self.rules.smart_enemies {
+            return;
+        }
+        let fingerprint = Coverage::fingerprint(self);
+        if fingerprint == self.threat.fingerprint {
+            return;
+        }
+        self.threat = ThreatMap::build(self, fingerprint);
+        let enemy_ids: Vec<EnemyId> = self.enemies.keys().collect();
+        for enemy_id in &enemy_ids {
+            self.route_around_threat(*enemy_id);
+        }
+        debug!(enemies = enemy_ids.len(), "threat map rebuilt");
+    }
+
+    /// Send an enemy the cheapest way to the goal by the threat map. Enemies
+    /// breaking through to a walled-off goal or chasing a convoy keep their
+    /// course.
+    pub(crate) fn route_around_threat(&mut self, enemy_id: EnemyId) {
+        let graph = Threatened { grid: &self.grid, threat: &self.threat };
+        let goals = self.lane_goals();
+        let Some(enemy) = self.enemies.get_mut(enemy_id) else {
+            return;
+        };
+        if enemy.siege != Siege::Marching || enemy.chasing.is_some() {
+            return;
+        }
+        let goal = goals.get(enemy.lane_id).copied().unwrap_or(self.goal_point);
+        if let Some(path) = find_path(&graph, self.grid.cell_at(enemy.x, enemy.y), goal) {
+            enemy.path = simplify_path(&path);
+            enemy.current_waypoint = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutators::RuleSet;
+    use crate::{BuildTool, Game, TowerType};
+
+    /// A wall down the middle of the board with a gap near the top and one
+    /// near the bottom; the default spawn and goal sit level with neither
+    fn two_ways(rules: RuleSet) -> Game {
+        let mut game = Game::with_rules(rules);
+        for y in 0..game.state.grid.height() {
+            if y != 5 && y != 10 {
+                game.state.grid.set_walkable(&Position::new(10, y), false);
+            }
+        }
+        game.state.gold = 10_000;
+        game
+    }
+
+    fn crosses_at(game: &Game, enemy_id: EnemyId) -> i32 {
+        let enemy = &game.state.enemies[enemy_id];
+        [5, 10].into_iter().find(|y| enemy.route_crosses(Position::new(10, *y))).unwrap_or(-1)
+    }
+
+    #[test]
+    fn test_smart_enemies_take_the_less_defended_gap() {
+        let mut rules = RuleSet::default();
+        rules.smart_enemies = true;
+        for (rules, gap) in [(RuleSet::default(), 5), (rules.clone(), 10)] {
+            let mut game = two_ways(rules);
+            for x in [9, 11] {
+                assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(x, 4)));
+            }
+            let enemy_id = game.state.spawn_enemy_with(|_| {}).unwrap();
+            game.update(1.0 / 30.0);
+            assert_eq!(crosses_at(&game, enemy_id), gap); // Smart ones go the longer way, away from the towers
+        }
+
+        // Guarding the bottom gap harder sends them back over the top
+        let mut game = two_ways(rules);
+        let enemy_id = game.state.spawn_enemy_with(|_| {}).unwrap();
+        for (x, y) in [(9, 4), (11, 4), (9, 11), (11, 11), (8, 11), (12, 11)] {
+            assert!(game.build(BuildTool::Tower(TowerType::Basic), Position::new(x, y)));
+        }
+        game.update(1.0 / 30.0);
+        assert_eq!(crosses_at(&game, enemy_id), 5);
+    }
+}
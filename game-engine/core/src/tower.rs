@@ -0,0 +1,356 @@
+//! Tower types and their fixed stats, and the towers standing on the board.
+
+use serde::{Deserialize, Serialize};
+
+use crate::stat_sheet::{Stat, StatSheet};
+use crate::{wrap_angle, EnemyId, GridShape, Guidance, Position, TowerId, CELL_SIZE, FIRING_ARC};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TowerType {
+    Basic,
+    Sniper,
+    Splash,
+    Slow,
+    Cannon,
+    // Support towers whose hits weaken enemies (see debuffs.rs)
+    Shredder,
+    Marker,
+    // Hybrids, only obtainable by merging (see merging.rs)
+    FrostBomb,
+    Railgun,
+    Gatling,
+}
+
+impl TowerType {
+    pub const ALL: [TowerType; 10] = [
+        TowerType::Basic,
+        TowerType::Sniper,
+        TowerType::Splash,
+        TowerType::Slow,
+        TowerType::Cannon,
+        TowerType::Shredder,
+        TowerType::Marker,
+        TowerType::FrostBomb,
+        TowerType::Railgun,
+        TowerType::Gatling,
+    ];
+
+    pub fn cost(&self) -> i32 {
+        match self {
+            TowerType::Basic => 50,
+            TowerType::Sniper => 100,
+            TowerType::Splash => 75,
+            TowerType::Slow => 60,
+            TowerType::Cannon => 200,
+            TowerType::Shredder => 80,
+            TowerType::Marker => 90,
+            TowerType::FrostBomb => TowerType::Slow.cost() + TowerType::Splash.cost(),
+            TowerType::Railgun => TowerType::Sniper.cost() * 2,
+            TowerType::Gatling => TowerType::Basic.cost() * 2,
+        }
+    }
+
+    pub fn range(&self) -> f32 {
+        match self {
+            TowerType::Basic => 3.0,
+            TowerType::Sniper => 6.0,
+            TowerType::Splash => 2.5,
+            TowerType::Slow => 3.5,
+            TowerType::Cannon => 4.0,
+            TowerType::Shredder => 3.0,
+            TowerType::Marker => 4.0,
+            TowerType::FrostBomb => 3.0,
+            TowerType::Railgun => 8.0,
+            TowerType::Gatling => 3.0,
+        }
+    }
+
+    pub fn damage(&self) -> i32 {
+        match self {
+            TowerType::Basic => 10,
+            TowerType::Sniper => 50,
+            TowerType::Splash => 15,
+            TowerType::Slow => 5,
+            TowerType::Cannon => 80,
+            TowerType::Shredder => 6,
+            TowerType::Marker => 2,
+            TowerType::FrostBomb => 20,
+            TowerType::Railgun => 150,
+            TowerType::Gatling => 8,
+        }
+    }
+
+    pub fn fire_rate(&self) -> f32 {
+        match self {
+            TowerType::Basic => 1.0,
+            TowerType::Sniper => 0.3,
+            TowerType::Splash => 0.8,
+            TowerType::Slow => 2.0,
+            TowerType::Cannon => 0.4,
+            TowerType::Shredder => 1.5,
+            TowerType::Marker => 1.0,
+            TowerType::FrostBomb => 0.8,
+            TowerType::Railgun => 0.25,
+            TowerType::Gatling => 4.0,
+        }
+    }
+
+    /// Shots per magazine when the ammo rule is on
+    pub fn magazine(&self) -> u32 {
+        match self {
+            TowerType::Basic => 6,
+            TowerType::Sniper => 3,
+            TowerType::Splash => 4,
+            TowerType::Slow => 8,
+            TowerType::Cannon => 2,
+            TowerType::Shredder => 8,
+            TowerType::Marker => 6,
+            TowerType::FrostBomb => 4,
+            TowerType::Railgun => 2,
+            TowerType::Gatling => 30,
+        }
+    }
+
+    /// Seconds to reload an empty magazine
+    pub fn magazine_reload(&self) -> f32 {
+        match self {
+            TowerType::Basic => 2.0,
+            TowerType::Sniper => 3.0,
+            TowerType::Splash => 2.5,
+            TowerType::Slow => 1.5,
+            TowerType::Cannon => 3.0,
+            TowerType::Shredder => 2.0,
+            TowerType::Marker => 2.0,
+            TowerType::FrostBomb => 2.5,
+            TowerType::Railgun => 4.0,
+            TowerType::Gatling => 3.0,
+        }
+    }
+
+    pub fn projectile_speed(&self) -> f32 {
+        match self {
+            TowerType::Basic => 300.0,
+            TowerType::Sniper => 600.0,
+            TowerType::Splash => 200.0,
+            TowerType::Slow => 250.0,
+            TowerType::Cannon => 250.0,
+            TowerType::Shredder => 350.0,
+            TowerType::Marker => 400.0,
+            TowerType::FrostBomb => 220.0,
+            TowerType::Railgun => 900.0,
+            TowerType::Gatling => 450.0,
+        }
+    }
+
+    /// Dead zone in cells: enemies closer than this can't be targeted
+    pub fn min_range(&self) -> f32 {
+        match self {
+            TowerType::Sniper => 1.5,
+            TowerType::Railgun => 2.0,
+            _ => 0.0,
+        }
+    }
+
+    /// Share of damage lost by a hit at the edge of range, scaling linearly
+    /// from nothing at the tower. 0 means full damage at any distance.
+    pub fn range_falloff(&self) -> f32 {
+        match self {
+            TowerType::Basic => 0.25,
+            TowerType::Gatling => 0.4,
+            _ => 0.0,
+        }
+    }
+
+    /// Cells around the tower it reveals when fog of war is on. Shorter than
+    /// range for the long-range towers, which need spotters.
+    pub fn vision(&self) -> f32 {
+        match self {
+            TowerType::Sniper => 3.5,
+            TowerType::Railgun => 4.0,
+            TowerType::Cannon => 4.0,
+            _ => self.range() + 1.0,
+        }
+    }
+
+    /// How fast the barrel swings toward a target, in radians per second
+    pub fn turn_rate(&self) -> f32 {
+        match self {
+            TowerType::Basic => 6.0,
+            TowerType::Sniper => 2.0,
+            TowerType::Splash => 4.0,
+            TowerType::Slow => 5.0,
+            TowerType::Cannon => 1.5,
+            TowerType::Shredder => 6.0,
+            TowerType::Marker => 5.0,
+            TowerType::FrostBomb => 4.0,
+            TowerType::Railgun => 1.2,
+            TowerType::Gatling => 8.0,
+        }
+    }
+
+    pub fn splash_radius(&self) -> f32 {
+        match self {
+            TowerType::Splash => 1.5,
+            TowerType::Cannon => 1.0,
+            TowerType::FrostBomb => 1.5,
+            _ => 0.0,
+        }
+    }
+
+    /// Whether shots track their target or fly straight at the aim point.
+    /// Shells are dumbfire: well-led they land, but a target that changes
+    /// course can dodge them. Piercing rail shots fly straight too.
+    pub fn guidance(&self) -> Guidance {
+        match self {
+            TowerType::Splash | TowerType::Cannon | TowerType::Railgun => Guidance::Dumbfire,
+            _ => Guidance::Homing,
+        }
+    }
+
+    /// Whether shots strike the first enemy they touch rather than flying
+    /// through everything on the way to their target
+    pub fn hits_first_in_path(&self) -> bool {
+        matches!(self, TowerType::Basic | TowerType::Splash)
+    }
+
+    /// How many enemies one shot can damage. Above 1 the shot pierces,
+    /// carrying on through victims to the edge of the tower's range.
+    pub fn max_hits(&self) -> usize {
+        match self {
+            TowerType::Railgun => 3,
+            _ => 1,
+        }
+    }
+
+    /// (duration, speed multiplier) applied to enemies hit by this tower
+    pub fn slow_effect(&self) -> Option<(f32, f32)> {
+        match self {
+            TowerType::Slow => Some((2.0, 0.5)), // Slow for 2 seconds at 50% speed
+            TowerType::FrostBomb => Some((2.5, 0.4)),
+            _ => None,
+        }
+    }
+
+    /// Width and height of the square area the tower occupies, in cells
+    pub fn footprint(&self) -> i32 {
+        match self {
+            TowerType::Cannon => 2,
+            _ => 1,
+        }
+    }
+
+    /// Whether the tower can stand on a board of this shape. Hexes don't
+    /// tile into squares, so multi-cell towers are square boards only.
+    pub fn fits_on(&self, shape: GridShape) -> bool {
+        shape == GridShape::Square || self.footprint() == 1
+    }
+
+    /// Every cell covered by a tower of this type anchored (top-left) at `position`
+    pub fn cells_at(&self, position: Position) -> Vec<Position> {
+        let size = self.footprint();
+        let mut cells = Vec::with_capacity((size * size) as usize);
+        for dy in 0..size {
+            for dx in 0..size {
+                cells.push(Position::new(position.x + dx, position.y + dy));
+            }
+        }
+        cells
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tower {
+    pub id: TowerId,
+    pub tower_type: TowerType,
+    pub position: Position,
+    pub stats: StatSheet,
+    #[serde(default)]
+    pub cooldown_remaining: f32,
+    #[serde(default)]
+    pub target_id: Option<EnemyId>,
+    #[serde(default)]
+    pub rotation: f32, // Barrel angle in radians, in (-PI, PI]
+    #[serde(default)]
+    pub locked_target: Option<EnemyId>, // Player's hard lock; see `focus`
+    #[serde(default)]
+    pub ignore_zones: Vec<Position>, // Cells it won't shoot into, sorted
+    #[serde(default)]
+    pub disabled: bool, // Switched off by the player; see `power`
+    #[serde(default)]
+    pub rounds_fired: u32, // From the current magazine; see `ammo`
+    #[serde(default)]
+    pub reloading: f32, // Seconds left on a magazine reload
+    #[serde(default)]
+    pub damage_taken: i32, // From enemies breaking through; see `siege`
+    #[serde(default)]
+    pub kills: u32, // Enemies its shots finished off
+}
+
+impl Tower {
+    pub fn new(id: TowerId, tower_type: TowerType, position: Position) -> Self {
+        Tower {
+            id,
+            tower_type,
+            position,
+            stats: StatSheet::for_tower(tower_type),
+            cooldown_remaining: 0.0,
+            target_id: None,
+            rotation: 0.0,
+            locked_target: None,
+            ignore_zones: Vec::new(),
+            disabled: false,
+            rounds_fired: 0,
+            reloading: 0.0,
+            damage_taken: 0,
+            kills: 0,
+        }
+    }
+
+    pub fn can_shoot(&self) -> bool {
+        self.cooldown_remaining <= 0.0 && self.reloading <= 0.0
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.stats.tick(delta);
+        if self.cooldown_remaining > 0.0 {
+            self.cooldown_remaining -= delta;
+        }
+        self.reloading = (self.reloading - delta).max(0.0);
+    }
+
+    /// Seconds between shots at the current fire rate
+    pub fn reload_time(&self) -> f32 {
+        1.0 / self.stats.get(Stat::FireRate).max(f32::EPSILON)
+    }
+
+    pub fn shoot(&mut self) {
+        self.cooldown_remaining = self.reload_time();
+    }
+
+    /// Swing the barrel toward `angle` at the type's turn rate. True once
+    /// it points within `FIRING_ARC` of it.
+    pub fn turn_toward(&mut self, angle: f32, delta: f32) -> bool {
+        let step = self.tower_type.turn_rate() * delta;
+        let offset = wrap_angle(angle - self.rotation);
+        self.rotation = if offset.abs() <= step { angle } else { wrap_angle(self.rotation + step * offset.signum()) };
+        wrap_angle(angle - self.rotation).abs() <= FIRING_ARC
+    }
+
+    pub fn world_position(&self) -> (f32, f32) {
+        let (x, y) = self.position.to_world();
+        let half_size = self.tower_type.footprint() as f32 * CELL_SIZE / 2.0;
+        (x + half_size, y + half_size)
+    }
+
+    pub fn cells(&self) -> Vec<Position> {
+        self.tower_type.cells_at(self.position)
+    }
+
+    pub fn occupies(&self, cell: &Position) -> bool {
+        let size = self.tower_type.footprint();
+        cell.x >= self.position.x
+            && cell.x < self.position.x + size
+            && cell.y >= self.position.y
+            && cell.y < self.position.y + size
+    }
+}
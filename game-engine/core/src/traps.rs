@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Position;
+
+/// One-shot traps placed on walkable path cells. Traps never block movement;
+/// they trigger when an enemy crosses their cell and are then consumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrapType {
+    Mine,
+    Glue,
+    Spikes,
+}
+
+impl TrapType {
+    pub fn cost(&self) -> i32 {
+        match self {
+            TrapType::Mine => 30,
+            TrapType::Glue => 20,
+            TrapType::Spikes => 25,
+        }
+    }
+
+    pub fn damage(&self) -> i32 {
+        match self {
+            TrapType::Mine => 60,
+            TrapType::Glue => 0,
+            TrapType::Spikes => 40,
+        }
+    }
+
+    /// Radius in cells for traps that hit everything nearby (0 = triggering enemy only)
+    pub fn radius(&self) -> f32 {
+        match self {
+            TrapType::Mine => 1.2,
+            _ => 0.0,
+        }
+    }
+
+    /// (duration, speed multiplier) applied to the triggering enemy
+    pub fn slow(&self) -> Option<(f32, f32)> {
+        match self {
+            TrapType::Glue => Some((4.0, 0.3)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrapType::Mine => "Mine",
+            TrapType::Glue => "Glue",
+            TrapType::Spikes => "Spikes",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trap {
+    pub id: u32,
+    pub trap_type: TrapType,
+    pub position: Position,
+}
+
+impl Trap {
+    pub fn new(id: u32, trap_type: TrapType, position: Position) -> Self {
+        Trap {
+            id,
+            trap_type,
+            position,
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Guided first game: a script of steps, each with a line of text, an
+//! optional cell to highlight and a goal that moves the script on. While a
+//! tutorial runs the build countdown is held, so the player sets the pace,
+//! and `allows` gates commands so only the step's action gets through.
+
+use tracing::info;
+
+use crate::events::GameEvent;
+use crate::protocol::Command;
+use crate::{BuildTool, Game, Position, TowerType};
+
+const MINI_WAVE_INTERVAL: f32 = 1.5; // Seconds between tutorial enemies
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepGoal {
+    Acknowledge, // The player clicks to continue
+    PlaceTower { tower_type: TowerType, position: Position },
+    MiniWave { enemies: u32 }, // Spawns them, then waits until none are left
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TutorialStep {
+    pub text: String,
+    pub highlight: Option<Position>,
+    pub goal: StepGoal,
+}
+
+impl TutorialStep {
+    pub fn new(text: &str, highlight: Option<Position>, goal: StepGoal) -> Self {
+        TutorialStep { text: text.to_string(), highlight, goal }
+    }
+}
+
+/// The script for a new player on the default board
+pub fn first_game() -> Vec<TutorialStep> {
+    let spot = Position::new(6, 6);
+    vec![
+        TutorialStep::new(
+            "Enemies walk from the spawn to your base. Each one that gets there costs a life.",
+            None,
+            StepGoal::Acknowledge,
+        ),
+        TutorialStep::new(
+            "Place a Basic tower on the highlighted cell.",
+            Some(spot),
+            StepGoal::PlaceTower { tower_type: TowerType::Basic, position: spot },
+        ),
+        TutorialStep::new(
+            "Towers aim at the enemy in range that is furthest along the route, the one closest to your base.",
+            Some(spot),
+            StepGoal::Acknowledge,
+        ),
+        TutorialStep::new("Here come a few enemies. Watch your tower.", None, StepGoal::MiniWave { enemies: 3 }),
+        TutorialStep::new(
+            "Towers also block the path, so enemies walk around them. Build a maze to make the route longer.",
+            None,
+            StepGoal::Acknowledge,
+        ),
+    ]
+}
+
+#[derive(Debug, Clone)]
+pub struct Tutorial {
+    steps: Vec<TutorialStep>,
+    current: usize,
+    spawned: u32,
+    spawn_timer: f32,
+}
+
+impl Tutorial {
+    pub fn new(steps: Vec<TutorialStep>) -> Self {
+        Tutorial { steps, current: 0, spawned: 0, spawn_timer: 0.0 }
+    }
+
+    pub fn step(&self) -> Option<&TutorialStep> {
+        self.steps.get(self.current)
+    }
+
+    /// (step number from 1, step count)
+    pub fn progress(&self) -> (usize, usize) {
+        ((self.current + 1).min(self.steps.len()), self.steps.len())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.steps.len()
+    }
+
+    /// Whether a command may go through during the current step. Once the
+    /// tutorial is over everything is allowed.
+    pub fn allows(&self, command: &Command) -> bool {
+        match self.step().map(|step| &step.goal) {
+            None => true,
+            Some(StepGoal::PlaceTower { tower_type, position }) => {
+                *command == Command::Build { tool: BuildTool::Tower(*tower_type), position: *position }
+            }
+            Some(_) => matches!(command, Command::SetPaused { .. }),
+        }
+    }
+
+    /// The player clicked to continue; only moves on from `Acknowledge` steps
+    pub fn acknowledge(&mut self) -> bool {
+        let waiting = self.step().is_some_and(|step| step.goal == StepGoal::Acknowledge);
+        if waiting {
+            self.next();
+        }
+        waiting
+    }
+
+    /// Run after each game update: holds the wave countdown, spawns the
+    /// mini-wave and checks the current goal
+    pub fn update(&mut self, game: &mut Game, delta: f32) {
+        let Some(step) = self.step() else {
+            return;
+        };
+        game.state.waves.hold();
+        let done = match step.goal {
+            StepGoal::Acknowledge => false,
+            StepGoal::PlaceTower { tower_type, position } => game.frame_events.iter().any(|event| {
+                matches!(event, GameEvent::TowerPlaced { tower_type: placed, position: at, .. }
+                    if *placed == tower_type && *at == position)
+            }),
+            StepGoal::MiniWave { enemies } => {
+                self.spawn_timer -= delta;
+                if self.spawned < enemies && self.spawn_timer <= 0.0 && game.state.spawn_enemy().is_ok() {
+                    self.spawned += 1;
+                    self.spawn_timer = MINI_WAVE_INTERVAL;
+                }
+                self.spawned == enemies && game.state.enemies.is_empty()
+            }
+        };
+        if done {
+            self.next();
+        }
+    }
+
+    fn next(&mut self) {
+        self.current += 1;
+        self.spawned = 0;
+        self.spawn_timer = 0.0;
+        info!(step = self.current, of = self.steps.len(), "tutorial step");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_gates_input_and_advances_on_goals() {
+        let mut game = Game::new();
+        let mut tutorial = Tutorial::new(first_game());
+        assert!(!tutorial.allows(&Command::CallNextWave));
+        assert!(tutorial.acknowledge());
+
+        // Only the highlighted Basic tower gets through
+        let wrong = Command::Build { tool: BuildTool::Tower(TowerType::Sniper), position: Position::new(6, 6) };
+        let right = Command::Build { tool: BuildTool::Tower(TowerType::Basic), position: Position::new(6, 6) };
+        assert!(!tutorial.allows(&wrong) && tutorial.allows(&right));
+        assert!(!tutorial.acknowledge());
+        assert!(game.apply_command(&right).is_ok());
+        game.update(1.0 / 30.0);
+        tutorial.update(&mut game, 1.0 / 30.0);
+        assert_eq!(tutorial.progress(), (3, 5));
+        assert!(tutorial.acknowledge());
+
+        // The mini-wave plays out with the build countdown held
+        for _ in 0..30 * 120 {
+            game.update(1.0 / 30.0);
+            tutorial.update(&mut game, 1.0 / 30.0);
+            if tutorial.progress().0 == 5 {
+                break;
+            }
+        }
+        assert_eq!(tutorial.progress(), (5, 5));
+        assert!(game.state.waves.is_build_phase() && game.state.waves.wave == 1);
+        assert!(tutorial.acknowledge() && tutorial.is_finished());
+        assert!(tutorial.allows(&Command::CallNextWave));
+    }
+}
@@ -0,0 +1,169 @@
+//! Two-player versus. Each player defends their own lane (a full `Game`) and
+//! spends gold to send extra enemies into the other lane. Every unit sent
+//! also raises the sender's income, paid out on a fixed interval, so sending
+//! early trades defence now for gold later.
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::Game;
+
+pub const SEND_COST: i32 = 15;
+pub const INCOME_PER_SEND: i32 = 2; // Added to the sender's income for each unit sent
+pub const BASE_INCOME: i32 = 10;
+pub const INCOME_INTERVAL: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchResult {
+    Won(Side),
+    Draw, // Both lanes fell on the same tick
+}
+
+pub struct VersusMatch {
+    lanes: [Game; 2],
+    income: [i32; 2],
+    income_timer: f32,
+    pub result: Option<MatchResult>,
+}
+
+impl VersusMatch {
+    pub fn new() -> Self {
+        VersusMatch {
+            lanes: [Game::new(), Game::new()],
+            income: [BASE_INCOME; 2],
+            income_timer: INCOME_INTERVAL,
+            result: None,
+        }
+    }
+
+    pub fn lane(&self, side: Side) -> &Game {
+        &self.lanes[side.index()]
+    }
+
+    pub fn lane_mut(&mut self, side: Side) -> &mut Game {
+        &mut self.lanes[side.index()]
+    }
+
+    pub fn income(&self, side: Side) -> i32 {
+        self.income[side.index()]
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Buy up to `count` enemies for the opponent's lane. Returns how many
+    /// were actually sent; only those are paid for.
+    pub fn send(&mut self, side: Side, count: u32) -> u32 {
+        if self.is_over() {
+            return 0;
+        }
+
+        let mut sent = 0;
+        for _ in 0..count {
+            if self.lane(side).state.gold < SEND_COST || self.lane_mut(side.opponent()).state.spawn_enemy().is_err() {
+                break;
+            }
+            self.lane_mut(side).state.gold -= SEND_COST;
+            self.income[side.index()] += INCOME_PER_SEND;
+            sent += 1;
+        }
+        if sent > 0 {
+            info!(?side, sent, income = self.income(side), "enemies sent");
+        }
+        sent
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        if self.is_over() {
+            return;
+        }
+
+        for lane in &mut self.lanes {
+            lane.update(delta);
+        }
+
+        self.income_timer -= delta;
+        if self.income_timer <= 0.0 {
+            self.income_timer += INCOME_INTERVAL;
+            for (lane, income) in self.lanes.iter_mut().zip(self.income) {
+                lane.state.gold += income;
+            }
+        }
+
+        let fallen = [Side::Left, Side::Right].map(|side| self.lane(side).state.health <= 0);
+        self.result = match fallen {
+            [true, true] => Some(MatchResult::Draw),
+            [true, false] => Some(MatchResult::Won(Side::Right)),
+            [false, true] => Some(MatchResult::Won(Side::Left)),
+            [false, false] => None,
+        };
+        if let Some(result) = self.result {
+            info!(?result, "versus match over");
+        }
+    }
+}
+
+impl Default for VersusMatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_spawns_in_opponent_lane_and_raises_income() {
+        let mut versus = VersusMatch::new();
+        let gold = versus.lane(Side::Left).state.gold;
+
+        assert_eq!(versus.send(Side::Left, 3), 3);
+        assert_eq!(versus.lane(Side::Left).state.gold, gold - 3 * SEND_COST);
+        assert_eq!(versus.lane(Side::Right).state.enemies.len(), 3);
+        assert!(versus.lane(Side::Left).state.enemies.is_empty());
+        assert_eq!(versus.income(Side::Left), BASE_INCOME + 3 * INCOME_PER_SEND);
+
+        // Income pays out on the interval
+        let right_gold = versus.lane(Side::Right).state.gold;
+        versus.update(INCOME_INTERVAL);
+        assert_eq!(versus.lane(Side::Right).state.gold, right_gold + BASE_INCOME);
+    }
+
+    #[test]
+    fn test_send_stops_when_gold_runs_out() {
+        let mut versus = VersusMatch::new();
+        versus.lane_mut(Side::Right).state.gold = SEND_COST * 2 + 1;
+        assert_eq!(versus.send(Side::Right, 5), 2);
+        assert_eq!(versus.lane(Side::Right).state.gold, 1);
+    }
+
+    #[test]
+    fn test_lane_at_zero_health_loses() {
+        let mut versus = VersusMatch::new();
+        versus.lane_mut(Side::Left).state.health = 0;
+        versus.update(0.1);
+        assert_eq!(versus.result, Some(MatchResult::Won(Side::Right)));
+        assert_eq!(versus.send(Side::Right, 1), 0);
+    }
+}
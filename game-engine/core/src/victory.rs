@@ -0,0 +1,301 @@
+//! How a run ends. With no win condition the game goes on until the base
+//! falls; a map can set one (`MapFile::win_condition`) to make it winnable:
+//!
+//! ```text
+//! "win_condition": { "goal": "SurviveWaves", "waves": 12 }
+//! "win_condition": { "goal": "Escort", "health": 200.0, "speed": 0.5 }
+//! "win_condition": { "goal": "DefendCore", "position": { "x": 10, "y": 7 },
+//!                    "health": 300.0, "hold_above": 100.0, "waves": 8 }
+//! "win_condition": { "goal": "HoldOut", "seconds": 240.0 }
+//! ```
+//!
+//! The convoy and the core are hurt by every enemy close enough to touch
+//! them. `GameState::update_objective` checks the condition each tick; once
+//! the run is decided the simulation stops. The base falling always loses,
+//! and so does losing too many convoys in the convoy mode. The attack mode
+//! turns this around: see `attack`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::StateHasher;
+use crate::convoys;
+use crate::pathfinding::find_path;
+use crate::{Enemy, GameState, Position, CELL_SIZE};
+
+const CONTACT_DPS: f32 = 10.0; // Damage per second from each enemy touching the convoy or core
+const CONVOY_REACH: f32 = 0.6; // Cells from the convoy's center
+const CORE_REACH: f32 = 1.2; // Cells from the core's center; enough for enemies passing next to it
+const ASSAULT_BREATHER: f32 = 3.0; // Longest build phase once an assault is under way
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "goal")]
+pub enum WinCondition {
+    /// Clear `waves` waves
+    SurviveWaves { waves: u32 },
+    /// Get a convoy from the spawn to the goal. It rolls along the route at
+    /// `speed` cells per second, but only while a wave is on.
+    Escort { health: f32, speed: f32 },
+    /// Clear `waves` waves without the core at `position`, which blocks its
+    /// cell like a wall, dropping to `hold_above` health
+    DefendCore { position: Position, health: f32, hold_above: f32, waves: u32 },
+    /// Last `seconds` from the first wave on, with only a few seconds'
+    /// break between waves
+    HoldOut { seconds: f32 },
+}
+
+impl WinCondition {
+    pub fn describe(&self) -> String {
+        match self {
+            WinCondition::SurviveWaves { waves } => format!("Survive {} waves", waves),
+            WinCondition::Escort { .. } => "Escort the convoy to the goal".to_string(),
+            WinCondition::DefendCore { hold_above, waves, .. } => {
+                format!("Keep the core above {} health for {} waves", hold_above.round(), waves)
+            }
+            WinCondition::HoldOut { seconds } => {
+                format!("Hold out for {}:{:02}", *seconds as u32 / 60, *seconds as u32 % 60)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Won,
+    Lost(Defeat),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Defeat {
+    BaseFell,
+    ConvoyDestroyed,
+    CoreBreached,
+    ConvoysLost, // `convoys::MAX_LOST` of them, in the convoy mode
+    DefenseHeld, // The attack mode's waves or budget ran out first
+}
+
+impl Defeat {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Defeat::BaseFell => "The base fell",
+            Defeat::ConvoyDestroyed => "The convoy was destroyed",
+            Defeat::CoreBreached => "The core was breached",
+            Defeat::ConvoysLost => "Too many convoys were lost",
+            Defeat::DefenseHeld => "The defense held",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Convoy {
+    pub x: f32,
+    pub y: f32,
+    pub health: f32,
+}
+
+/// The run's win condition and how it's going
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Objective {
+    pub condition: Option<WinCondition>,
+    pub convoy: Option<Convoy>, // Escort only
+    pub core_health: f32,       // DefendCore only
+    pub elapsed: f32,           // Seconds since the first wave started
+    pub outcome: Option<Outcome>,
+}
+
+impl Objective {
+    /// A fresh objective, with the convoy (if any) waiting on `spawn`
+    pub fn new(condition: WinCondition, spawn: Position) -> Self {
+        let (x, y) = spawn.to_world();
+        let (x, y) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+        let convoy = match condition {
+            WinCondition::Escort { health, .. } => Some(Convoy { x, y, health }),
+            _ => None,
+        };
+        let core_health = match condition {
+            WinCondition::DefendCore { health, .. } => health,
+            _ => 0.0,
+        };
+        Objective { condition: Some(condition), convoy, core_health, elapsed: 0.0, outcome: None }
+    }
+
+    pub fn is_decided(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// The core's cell, if there is one
+    pub fn core(&self) -> Option<Position> {
+        match self.condition {
+            Some(WinCondition::DefendCore { position, .. }) => Some(position),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.variant(&self.condition);
+        if let Some(convoy) = &self.convoy {
+            hasher.f32(convoy.x);
+            hasher.f32(convoy.y);
+            hasher.f32(convoy.health);
+        }
+        hasher.f32(self.core_health);
+        hasher.f32(self.elapsed);
+        hasher.variant(&self.outcome);
+    }
+}
+
+impl GameState {
+    /// Advance the convoy, let enemies hurt what they touch and decide the
+    /// run if the condition says so
+    pub(crate) fn update_objective(&mut self, delta: f32) {
+        if self.objective.is_decided() {
+            return;
+        }
+        let started = self.waves.wave > 1 || !self.waves.is_build_phase();
+        if started {
+            self.objective.elapsed += delta;
+        }
+        let cleared = self.waves.wave - 1;
+        let outcome = match self.objective.condition.clone() {
+            _ if self.rules.attack => self.attack_outcome(),
+            _ if self.health <= 0 => Some(Outcome::Lost(Defeat::BaseFell)),
+            _ if self.convoys.lost >= convoys::MAX_LOST => Some(Outcome::Lost(Defeat::ConvoysLost)),
+            None => None,
+            Some(WinCondition::SurviveWaves { waves }) => (cleared >= waves).then_some(Outcome::Won),
+            Some(WinCondition::Escort { speed, .. }) => self.update_convoy(speed, delta),
+            Some(WinCondition::DefendCore { position, hold_above, waves, .. }) => {
+                let (x, y) = position.to_world();
+                let center = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+                self.objective.core_health -= self.contact_damage(center, CORE_REACH, delta);
+                if self.objective.core_health <= hold_above {
+                    Some(Outcome::Lost(Defeat::CoreBreached))
+                } else {
+                    (cleared >= waves).then_some(Outcome::Won)
+                }
+            }
+            Some(WinCondition::HoldOut { seconds }) => {
+                if started {
+                    self.waves.rush(ASSAULT_BREATHER);
+                }
+                (self.objective.elapsed >= seconds).then_some(Outcome::Won)
+            }
+        };
+        if let Some(outcome) = outcome {
+            tracing::info!(?outcome, "run decided");
+            self.objective.outcome = Some(outcome);
+        }
+    }
+
+    fn update_convoy(&mut self, speed: f32, delta: f32) -> Option<Outcome> {
+        let convoy = self.objective.convoy?;
+        let damage = self.contact_damage((convoy.x, convoy.y), CONVOY_REACH, delta);
+        let cell = self.grid.cell_at(convoy.x, convoy.y);
+        let moving = !self.waves.is_build_phase() && cell != self.goal_point;
+        let step = moving.then(|| find_path(&self.grid, cell, self.goal_point)).flatten();
+        let convoy = self.objective.convoy.as_mut()?;
+        convoy.health -= damage;
+        if convoy.health <= 0.0 {
+            return Some(Outcome::Lost(Defeat::ConvoyDestroyed));
+        }
+        if let Some(next) = step.as_ref().and_then(|route| route.get(1)) {
+            let (x, y) = next.to_world();
+            let (target_x, target_y) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+            let (dx, dy) = (target_x - convoy.x, target_y - convoy.y);
+            let distance = dx.hypot(dy);
+            let travel = speed * CELL_SIZE * delta;
+            if self.grid.teleporter(&cell) == Some(*next) || distance <= travel {
+                (convoy.x, convoy.y) = (target_x, target_y);
+            } else {
+                convoy.x += dx / distance * travel;
+                convoy.y += dy / distance * travel;
+            }
+        }
+        (self.grid.cell_at(convoy.x, convoy.y) == self.goal_point).then_some(Outcome::Won)
+    }
+
+    /// Damage this tick from the enemies within `reach` cells of a point
+    fn contact_damage(&self, (x, y): (f32, f32), reach: f32, delta: f32) -> f32 {
+        let touching = |enemy: &&Enemy| (enemy.x - x).hypot(enemy.y - y) <= reach * CELL_SIZE;
+        let touching = self.enemies.values().filter(touching).count();
+        touching as f32 * CONTACT_DPS * delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    fn game_with(condition: WinCondition) -> Game {
+        let mut game = Game::new();
+        game.state.objective = Objective::new(condition, game.state.spawn_point);
+        game
+    }
+
+    #[test]
+    fn test_surviving_the_waves_wins_and_stops_the_run() {
+        let mut game = game_with(WinCondition::SurviveWaves { waves: 1 });
+        game.state.waves.call_early();
+        for _ in 0..3000 {
+            game.update(1.0 / 30.0);
+            game.state.enemies.clear(); // Perfect defense
+        }
+        assert_eq!(game.state.objective.outcome, Some(Outcome::Won));
+        let tick = game.tick;
+        game.update(1.0 / 30.0);
+        assert_eq!(game.tick, tick);
+
+        let mut game = game_with(WinCondition::SurviveWaves { waves: 1 });
+        game.state.health = 0;
+        game.update(1.0 / 30.0);
+        assert_eq!(game.state.objective.outcome, Some(Outcome::Lost(Defeat::BaseFell)));
+    }
+
+    #[test]
+    fn test_convoy_rolls_to_the_goal_during_waves() {
+        let mut game = game_with(WinCondition::Escort { health: 100.0, speed: 4.0 });
+        let start = game.state.objective.convoy.unwrap();
+        game.update(1.0);
+        assert_eq!(game.state.objective.convoy.unwrap().x, start.x); // Waits for a wave
+
+        game.state.waves.call_early();
+        for _ in 0..600 {
+            game.update(1.0 / 30.0);
+            game.state.enemies.clear();
+        }
+        assert_eq!(game.state.objective.outcome, Some(Outcome::Won));
+
+        let mut game = game_with(WinCondition::Escort { health: 10.0, speed: 0.0 });
+        game.state.waves.call_early();
+        for _ in 0..60 {
+            game.update(1.0 / 30.0);
+        }
+        assert_eq!(game.state.objective.outcome, Some(Outcome::Lost(Defeat::ConvoyDestroyed)));
+    }
+
+    #[test]
+    fn test_core_and_assault_conditions() {
+        let position = Position::new(1, 7);
+        let mut game = game_with(WinCondition::DefendCore { position, health: 50.0, hold_above: 20.0, waves: 3 });
+        game.state.grid.set_walkable(&position, false);
+        game.state.recalculate_paths();
+        game.state.waves.call_early();
+        for _ in 0..300 {
+            game.update(1.0 / 30.0);
+        }
+        assert_eq!(game.state.objective.outcome, Some(Outcome::Lost(Defeat::CoreBreached)));
+
+        let mut game = game_with(WinCondition::HoldOut { seconds: 30.0 });
+        game.state.waves.call_early();
+        for _ in 0..(25 * 30) {
+            game.update(1.0 / 30.0);
+            game.state.enemies.clear();
+        }
+        assert!(game.state.waves.wave > 2); // Waves come thick and fast
+        assert_eq!(game.state.objective.outcome, None);
+        for _ in 0..(6 * 30) {
+            game.update(1.0 / 30.0);
+            game.state.enemies.clear();
+        }
+        assert_eq!(game.state.objective.outcome, Some(Outcome::Won));
+    }
+}
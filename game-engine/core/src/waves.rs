@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+
+use crate::checksum::StateHasher;
+use crate::formations::WavePlan;
+use crate::gunners::EnemyKind;
+
+pub const BUILD_PHASE_TIME: f32 = 20.0;
+pub(crate) const SPAWN_INTERVAL: f32 = 0.8; // Between enemies in single file
+const EARLY_CALL_GOLD_PER_SECOND: f32 = 2.0;
+
+/// The game alternates between a build phase (countdown, no spawns)
+/// and a combat phase (the current wave is spawning or still alive)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WavePhase {
+    Build { countdown: f32 },
+    Combat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveManager {
+    pub wave: u32, // Number of the current (or upcoming, during build) wave
+    pub phase: WavePhase,
+    pub remaining_to_spawn: u32,
+    spawn_timer: f32,
+    #[serde(default)]
+    pub plans: Vec<WavePlan>, // The map's wave script; see `formations`
+    #[serde(default = "default_auto_start")]
+    pub auto_start: Option<f32>, // Seconds from a wave's end to the next starting by itself; None waits for a call
+}
+
+fn default_auto_start() -> Option<f32> {
+    Some(BUILD_PHASE_TIME)
+}
+
+/// An enemy the wave wants spawned now
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spawn {
+    pub remaining: u32, // Still to come in the wave after this one
+    pub group: u32,     // Which formation group of the wave it's in, from zero
+    pub lane: f32,      // Cells beside the route; see `Formation::lane`
+    pub kind: EnemyKind,
+}
+
+impl WaveManager {
+    pub fn new() -> Self {
+        WaveManager {
+            wave: 1,
+            phase: WavePhase::Build {
+                countdown: BUILD_PHASE_TIME,
+            },
+            remaining_to_spawn: 0,
+            spawn_timer: 0.0,
+            plans: Vec::new(),
+            auto_start: default_auto_start(),
+        }
+    }
+
+    /// Enemies in a given wave
+    pub fn wave_size(wave: u32) -> u32 {
+        5 + 2 * wave
+    }
+
+    /// The wave script's plan for the current wave
+    pub fn plan(&self) -> WavePlan {
+        WavePlan::for_wave(&self.plans, self.wave)
+    }
+
+    /// Enemies in the current wave, after the wave script
+    pub fn size(&self) -> u32 {
+        self.plan().roster.map_or(Self::wave_size(self.wave), |roster| roster.size())
+    }
+
+    pub fn is_build_phase(&self) -> bool {
+        matches!(self.phase, WavePhase::Build { .. })
+    }
+
+    /// Gold awarded for calling the next wave right now
+    pub fn early_call_bonus(&self) -> i32 {
+        match self.phase {
+            WavePhase::Build { countdown } => (countdown * EARLY_CALL_GOLD_PER_SECOND).floor() as i32,
+            WavePhase::Combat => 0,
+        }
+    }
+
+    /// Keep the build phase from running out (tutorials, menus)
+    pub fn hold(&mut self) {
+        if self.is_build_phase() {
+            self.phase = WavePhase::Build { countdown: BUILD_PHASE_TIME };
+        }
+    }
+
+    /// Cut the build phase down to at most `countdown` seconds
+    pub fn rush(&mut self, countdown: f32) {
+        if let WavePhase::Build { countdown: left } = &mut self.phase {
+            *left = left.min(countdown);
+        }
+    }
+
+    /// Skip the rest of the build phase. Returns the bonus gold earned
+    /// (None if a wave is already in progress).
+    pub fn call_early(&mut self) -> Option<i32> {
+        if !self.is_build_phase() {
+            return None;
+        }
+        let bonus = self.early_call_bonus();
+        self.start_wave();
+        Some(bonus)
+    }
+
+    fn start_wave(&mut self) {
+        self.phase = WavePhase::Combat;
+        self.remaining_to_spawn = self.size();
+        self.spawn_timer = 0.0;
+    }
+
+    /// Advance timers. Returns the enemies to spawn this tick, in order;
+    /// members of a formation can come several at once.
+    pub fn update(&mut self, delta: f32, enemies_alive: usize) -> Vec<Spawn> {
+        match self.phase {
+            WavePhase::Build { .. } if self.auto_start.is_none() => Vec::new(), // Idle until called
+            WavePhase::Build { countdown } => {
+                let countdown = countdown - delta;
+                if countdown <= 0.0 {
+                    self.start_wave();
+                } else {
+                    self.phase = WavePhase::Build { countdown };
+                }
+                Vec::new()
+            }
+            WavePhase::Combat => {
+                if self.remaining_to_spawn == 0 {
+                    if enemies_alive == 0 {
+                        // Wave cleared, back to building
+                        self.wave += 1;
+                        self.phase = WavePhase::Build {
+                            countdown: self.auto_start.unwrap_or(BUILD_PHASE_TIME),
+                        };
+                    }
+                    return Vec::new();
+                }
+
+                self.spawn_timer -= delta;
+                let (plan, size) = (self.plan(), self.size());
+                let formation = plan.formation;
+                let mut spawns = Vec::new();
+                while self.spawn_timer <= 0.0 && self.remaining_to_spawn > 0 {
+                    let slot = size - self.remaining_to_spawn;
+                    self.remaining_to_spawn -= 1;
+                    let (group, lane) = (slot / formation.size(), formation.lane(slot));
+                    let kind = match plan.roster {
+                        Some(roster) => roster.kind(slot),
+                        None => EnemyKind::in_wave(self.wave, self.remaining_to_spawn),
+                    };
+                    spawns.push(Spawn { remaining: self.remaining_to_spawn, group, lane, kind });
+                    self.spawn_timer = formation.gap_after(slot);
+                }
+                spawns
+            }
+        }
+    }
+}
+
+impl Default for WaveManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WaveManager {
+    pub(crate) fn hash_into(&self, hasher: &mut StateHasher) {
+        hasher.u32(self.wave);
+        match self.phase {
+            WavePhase::Build { countdown } => {
+                hasher.u32(0);
+                hasher.f32(countdown);
+            }
+            WavePhase::Combat => hasher.u32(1),
+        }
+        hasher.u32(self.remaining_to_spawn);
+        hasher.f32(self.spawn_timer);
+        hasher.usize(self.plans.len());
+        for plan in &self.plans {
+            plan.hash_into(hasher);
+        }
+        hasher.bool(self.auto_start.is_some());
+        hasher.f32(self.auto_start.unwrap_or(0.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_spawns_during_build_phase() {
+        let mut waves = WaveManager::new();
+        assert!(waves.update(BUILD_PHASE_TIME / 2.0, 0).is_empty());
+        assert!(waves.is_build_phase());
+    }
+
+    #[test]
+    fn test_wave_spawns_then_returns_to_build() {
+        let mut waves = WaveManager::new();
+        waves.update(BUILD_PHASE_TIME + 0.1, 0);
+        assert_eq!(waves.phase, WavePhase::Combat);
+
+        let mut spawned = 0;
+        for _ in 0..1000 {
+            spawned += waves.update(0.1, 1).len() as u32;
+        }
+        assert_eq!(spawned, WaveManager::wave_size(1));
+        assert_eq!(waves.phase, WavePhase::Combat); // Enemies still alive
+
+        waves.update(0.1, 0);
+        assert!(waves.is_build_phase());
+        assert_eq!(waves.wave, 2);
+    }
+
+    #[test]
+    fn test_call_early_bonus() {
+        let mut waves = WaveManager::new();
+        waves.update(BUILD_PHASE_TIME - 10.0, 0);
+
+        assert_eq!(waves.call_early(), Some(20));
+        assert_eq!(waves.phase, WavePhase::Combat);
+        assert_eq!(waves.call_early(), None);
+    }
+
+    #[test]
+    fn test_auto_start_chains_or_waits() {
+        let mut waves = WaveManager::new();
+        waves.auto_start = Some(5.0);
+        waves.call_early();
+        while !waves.is_build_phase() {
+            waves.update(0.1, 0);
+        }
+        assert_eq!(waves.phase, WavePhase::Build { countdown: 5.0 });
+        waves.update(5.1, 0);
+        assert_eq!(waves.phase, WavePhase::Combat);
+
+        let mut waves = WaveManager::new();
+        waves.auto_start = None;
+        waves.update(BUILD_PHASE_TIME * 10.0, 0);
+        assert!(waves.is_build_phase());
+        assert_eq!(waves.call_early(), Some(40)); // The full bonus for a manual start
+    }
+}
@@ -0,0 +1,143 @@
+//! Periodic weather. Each wave's weather is fixed by its number, so the
+//! frontend can announce it during the build phase before it arrives, and
+//! it lasts for that wave's combat phase only. Rain slows every enemy a
+//! little; fog costs towers some range through the stat-modifier system
+//! (source `"weather"`); a storm brings both. Off unless a frontend enables
+//! it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::stat_sheet::{Modifier, ModifierOp, Stat, StatSheet};
+
+pub const WEATHER_SOURCE: &str = "weather";
+const RAIN_PACE: f32 = 0.85; // Enemy speed multiplier in rain and storms
+const FOG_RANGE_MULTIPLIER: f32 = 0.85;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Fog,
+    Storm,
+}
+
+impl Weather {
+    /// The weather a given wave is fought in
+    pub fn forecast(wave: u32) -> Self {
+        if wave.is_multiple_of(7) {
+            Weather::Storm
+        } else if wave.is_multiple_of(4) {
+            Weather::Fog
+        } else if wave.is_multiple_of(3) {
+            Weather::Rain
+        } else {
+            Weather::Clear
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weather::Clear => "Clear",
+            Weather::Rain => "Rain",
+            Weather::Fog => "Fog",
+            Weather::Storm => "Storm",
+        }
+    }
+
+    /// One-line summary for the wave preview
+    pub fn description(self) -> &'static str {
+        match self {
+            Weather::Clear => "no effect",
+            Weather::Rain => "enemies move slower",
+            Weather::Fog => "towers lose range",
+            Weather::Storm => "enemies move slower, towers lose range",
+        }
+    }
+
+    /// Multiplier on every enemy's movement speed
+    pub fn enemy_pace(self) -> f32 {
+        match self {
+            Weather::Rain | Weather::Storm => RAIN_PACE,
+            Weather::Clear | Weather::Fog => 1.0,
+        }
+    }
+
+    fn range_multiplier(self) -> Option<f32> {
+        match self {
+            Weather::Fog | Weather::Storm => Some(FOG_RANGE_MULTIPLIER),
+            Weather::Clear | Weather::Rain => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherState {
+    pub enabled: bool,
+    pub current: Weather,
+}
+
+impl WeatherState {
+    pub fn new(enabled: bool) -> Self {
+        WeatherState { enabled, current: Weather::Clear }
+    }
+
+    /// What wave `wave` will bring, or None with weather off
+    pub fn forecast(&self, wave: u32) -> Option<Weather> {
+        self.enabled.then(|| Weather::forecast(wave))
+    }
+}
+
+impl Default for WeatherState {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+/// Put a tower's stats in line with the weather
+pub fn apply_weather(stats: &mut StatSheet, weather: Weather) {
+    stats.remove_source(WEATHER_SOURCE);
+    if let Some(multiplier) = weather.range_multiplier() {
+        stats.add_modifier(Modifier::new(WEATHER_SOURCE, Stat::Range, ModifierOp::Multiply(multiplier)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildTool, Game, Position, TowerType};
+
+    #[test]
+    fn test_forecast_cycles_through_weather() {
+        assert_eq!(Weather::forecast(1), Weather::Clear);
+        assert_eq!(Weather::forecast(3), Weather::Rain);
+        assert_eq!(Weather::forecast(4), Weather::Fog);
+        assert_eq!(Weather::forecast(12), Weather::Fog);
+        assert_eq!(Weather::forecast(14), Weather::Storm);
+        assert_eq!(WeatherState::default().forecast(3), None);
+    }
+
+    #[test]
+    fn test_weather_lasts_for_its_wave() {
+        let mut game = Game::new();
+        game.state.weather = WeatherState::new(true);
+        game.state.waves.wave = 7;
+        game.build(BuildTool::Tower(TowerType::Basic), Position::new(4, 6));
+        let tower = game.state.towers.keys().next().unwrap();
+        let clear_range = game.state.towers[tower].stats.get(Stat::Range);
+
+        assert!(game.state.call_next_wave().is_ok());
+        game.update(0.1);
+        assert_eq!(game.state.weather.current, Weather::Storm);
+        assert_eq!(game.state.towers[tower].stats.get(Stat::Range), clear_range * FOG_RANGE_MULTIPLIER);
+        let enemy = game.state.enemies.keys().next().unwrap();
+        let start = game.state.enemies[enemy].x;
+        game.update(0.1);
+        assert!(game.state.enemies[enemy].x - start < 50.0 * 0.1 * 0.9);
+
+        game.state.enemies.clear();
+        game.state.waves.remaining_to_spawn = 0;
+        game.update(0.1);
+        assert_eq!(game.state.weather.current, Weather::Clear);
+        assert_eq!(game.state.towers[tower].stats.get(Stat::Range), clear_range);
+    }
+}
@@ -0,0 +1,98 @@
+//! One thread per WebSocket connection. It parses incoming commands and
+//! acks and forwards them to the session, and writes out whatever the
+//! session queued.
+
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Sender, TryRecvError};
+use std::time::Duration;
+
+use rust_rush_core::protocol::{Ack, Command};
+use tracing::{debug, warn};
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::{Error, Message};
+
+use crate::session::{ClientId, Inbound, Role};
+
+// How long a read waits before the thread checks its outbox again
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Connections to this path join read-only
+pub const SPECTATE_PATH: &str = "/spectate";
+
+pub fn serve(client: ClientId, stream: TcpStream, session: Sender<Inbound>) {
+    let mut role = Role::Player;
+    #[allow(clippy::result_large_err)] // tungstenite's callback signature
+    let read_path = |request: &Request, response: Response| {
+        if request.uri().path() == SPECTATE_PATH {
+            role = Role::Spectator;
+        }
+        Ok(response)
+    };
+    let mut socket = match tungstenite::accept_hdr(stream, read_path) {
+        Ok(socket) => socket,
+        Err(err) => {
+            warn!(client, %err, "websocket handshake failed");
+            return;
+        }
+    };
+    if let Err(err) = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL)) {
+        warn!(client, %err, "could not set read timeout");
+        return;
+    }
+
+    let (outbox, inbox) = mpsc::channel();
+    if session.send(Inbound::Joined { client, role, outbox }).is_err() {
+        return;
+    }
+
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let inbound = match serde_json::from_str::<Command>(&text) {
+                    Ok(command) => Some(Inbound::Command { client, command }),
+                    Err(err) => match serde_json::from_str::<Ack>(&text) {
+                        Ok(Ack { ack }) => Some(Inbound::Ack { client, tick: ack }),
+                        Err(_) => {
+                            debug!(client, %err, "ignoring malformed command");
+                            None
+                        }
+                    },
+                };
+                if inbound.is_some_and(|inbound| session.send(inbound).is_err()) {
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(Error::Io(err)) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(err) => {
+                debug!(client, %err, "connection lost");
+                break;
+            }
+        }
+
+        let mut open = true;
+        loop {
+            match inbox.try_recv() {
+                Ok(text) => {
+                    if let Err(err) = socket.send(Message::Text(text)) {
+                        debug!(client, %err, "send failed");
+                        open = false;
+                        break;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    open = false;
+                    break;
+                }
+            }
+        }
+        if !open {
+            break;
+        }
+    }
+
+    let _ = session.send(Inbound::Left { client });
+}
@@ -0,0 +1,124 @@
+//! Headless, authoritative Rust Rush server. Runs the core simulation at a
+//! fixed tick rate and talks to clients over WebSocket: clients send JSON
+//! `Command`s, the server broadcasts `Snapshot`s and `Delta`s
+//! (see `rust_rush_core::protocol`).
+//!
+//! ```text
+//! cargo run -p rust-rush-server -- --bind 0.0.0.0:9001
+//! cargo run -p rust-rush-server -- --export run.json   # run report after every wave
+//! cargo run -p rust-rush-server -- --stdio              # JSON lines for bots, see stdio.rs
+//! ```
+
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rust_rush_core::Game;
+use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
+
+mod client;
+mod session;
+mod stdio;
+use session::{Inbound, Session};
+
+const DEFAULT_BIND: &str = "127.0.0.1:9001";
+const TICK_RATE: u32 = 30;
+
+/// The value of `--name value` or `--name=value`, if given
+fn flag(args: impl IntoIterator<Item = String>, name: &str) -> Option<String> {
+    let mut args = args.into_iter();
+    let inline = format!("{}=", name);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            if let Some(value) = args.next() {
+                return Some(value);
+            }
+        } else if let Some(value) = arg.strip_prefix(&inline) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+fn bind_address(args: impl IntoIterator<Item = String>) -> String {
+    flag(args, "--bind").unwrap_or_else(|| DEFAULT_BIND.to_string())
+}
+
+fn main() {
+    // Logs go to stderr; in --stdio mode stdout carries the replies
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_env("RUST_RUSH_LOG").unwrap_or_else(|_| EnvFilter::new("info")))
+        .with_writer(std::io::stderr)
+        .init();
+
+    if std::env::args().any(|arg| arg == "--stdio") {
+        if let Err(err) = stdio::run(std::io::stdin().lock(), std::io::stdout().lock()) {
+            error!(%err, "stdio session failed");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let address = bind_address(std::env::args().skip(1));
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(%address, %err, "could not bind");
+            std::process::exit(1);
+        }
+    };
+    info!(%address, tick_rate = TICK_RATE, "server listening");
+
+    let (inbound, session_inbox) = mpsc::channel::<Inbound>();
+    thread::spawn(move || {
+        for (client, stream) in (1..).zip(listener.incoming()) {
+            match stream {
+                Ok(stream) => {
+                    let inbound = inbound.clone();
+                    thread::spawn(move || client::serve(client, stream, inbound));
+                }
+                Err(err) => error!(%err, "accept failed"),
+            }
+        }
+    });
+
+    let tick = Duration::from_secs(1) / TICK_RATE;
+    let mut session = Session::new(Game::new());
+    if let Some(path) = flag(std::env::args().skip(1), "--export") {
+        info!(%path, "exporting run report after each wave");
+        session.export_to(PathBuf::from(path));
+    }
+    let mut next_tick = Instant::now() + tick;
+    loop {
+        // Handle messages until the tick is due, then advance the game
+        loop {
+            match session_inbox.recv_timeout(next_tick.saturating_duration_since(Instant::now())) {
+                Ok(message) => session.handle(message),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        session.step(tick.as_secs_f32());
+        next_tick += tick;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_bind_address() {
+        assert_eq!(bind_address(args(&[])), DEFAULT_BIND);
+        assert_eq!(bind_address(args(&["--bind", "0.0.0.0:80"])), "0.0.0.0:80");
+        assert_eq!(bind_address(args(&["--bind=[::1]:9001"])), "[::1]:9001");
+        assert_eq!(flag(args(&["--bind", "x", "--export", "run.json"]), "--export").as_deref(), Some("run.json"));
+    }
+}
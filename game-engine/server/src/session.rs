@@ -0,0 +1,277 @@
+//! The one authoritative game and everyone connected to it. Socket threads
+//! only forward `Inbound` messages here; all simulation happens on the tick
+//! thread that owns the session.
+//!
+//! Each client's deltas are made against its baseline: the newest tick it
+//! acked, or the last full snapshot it was sent. Snapshots are kept back to
+//! the oldest baseline, for at most `KEYFRAME_INTERVAL` ticks; a client
+//! whose baseline is older than that gets a full snapshot instead.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
+use rust_rush_core::actions::ActionError;
+use rust_rush_core::events::GameEvent;
+use rust_rush_core::protocol::{Command, ServerMessage, Snapshot};
+use rust_rush_core::report::RunReport;
+use rust_rush_core::Game;
+use tracing::{debug, info, warn};
+
+pub type ClientId = u64;
+
+/// Every this many ticks the broadcast is a full snapshot instead of a
+/// delta, so a client that fell behind can resync. Also how far back a
+/// baseline can be.
+pub const KEYFRAME_INTERVAL: u64 = 150;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Player,
+    Spectator, // Gets every broadcast but can't send commands
+}
+
+pub enum Inbound {
+    Joined { client: ClientId, role: Role, outbox: Sender<String> },
+    Command { client: ClientId, command: Command },
+    Ack { client: ClientId, tick: u64 },
+    Left { client: ClientId },
+}
+
+struct Client {
+    role: Role,
+    outbox: Sender<String>,
+    baseline: u64, // Tick of the snapshot its deltas are made against
+}
+
+pub struct Session {
+    game: Game,
+    clients: BTreeMap<ClientId, Client>,
+    history: VecDeque<Snapshot>, // Back to the oldest baseline, oldest first
+    export: Option<PathBuf>,     // Where to write the run report
+}
+
+impl Session {
+    pub fn new(game: Game) -> Self {
+        let history = VecDeque::from([Snapshot::capture(&game)]);
+        Session {
+            game,
+            clients: BTreeMap::new(),
+            history,
+            export: None,
+        }
+    }
+
+    /// Rewrite a JSON run report at `path` whenever a wave ends or the base falls
+    pub fn export_to(&mut self, path: PathBuf) {
+        self.export = Some(path);
+    }
+
+    pub fn handle(&mut self, inbound: Inbound) {
+        match inbound {
+            Inbound::Joined { client, role, outbox } => {
+                // Deltas build on exactly this snapshot until the client acks a newer one
+                let latest = self.history.back().expect("history keeps the latest snapshot");
+                if outbox.send(encode(&ServerMessage::Snapshot(latest.clone()))).is_ok() {
+                    self.clients.insert(client, Client { role, outbox, baseline: latest.tick });
+                }
+                info!(client, ?role, clients = self.clients.len(), "client joined");
+            }
+            Inbound::Command { client, command } => {
+                debug!(client, ?command, "command");
+                let is_player = self.clients.get(&client).is_some_and(|client| client.role == Role::Player);
+                let result = if is_player { self.game.apply_command(&command) } else { Err(ActionError::NotAllowed) };
+                if let Err(reason) = result {
+                    self.send_to(client, &ServerMessage::Rejected { command, reason: Some(reason) });
+                }
+            }
+            Inbound::Ack { client, tick } => {
+                // Only ticks it was sent count, and baselines never go back
+                let known = self.history.iter().any(|snapshot| snapshot.tick == tick);
+                if let Some(client) = self.clients.get_mut(&client).filter(|client| known && tick > client.baseline) {
+                    client.baseline = tick;
+                }
+            }
+            Inbound::Left { client } => {
+                self.clients.remove(&client);
+                info!(client, clients = self.clients.len(), "client left");
+            }
+        }
+    }
+
+    /// Advance the game one tick and broadcast the result
+    pub fn step(&mut self, delta: f32) {
+        let was_standing = self.game.state.health > 0;
+        self.game.update(delta);
+        let wave_cleared = self.game.frame_events.iter().any(|event| matches!(event, GameEvent::WaveCleared { .. }));
+        if wave_cleared || (was_standing && self.game.state.health <= 0) {
+            self.write_report();
+        }
+
+        let snapshot = Snapshot::capture(&self.game);
+        let keyframe = snapshot.tick.is_multiple_of(KEYFRAME_INTERVAL);
+        self.history.push_back(snapshot);
+        self.send_latest(keyframe);
+    }
+
+    /// Send each client the latest snapshot, as a delta against its
+    /// baseline where it can be, then drop history no one needs
+    fn send_latest(&mut self, keyframe: bool) {
+        let latest = self.history.back().expect("history keeps the latest snapshot");
+        let oldest_kept = latest.tick.saturating_sub(KEYFRAME_INTERVAL);
+        let mut encoded: HashMap<Option<u64>, String> = HashMap::new(); // By base tick; None for a full snapshot
+        self.clients.retain(|_, client| {
+            let base = self
+                .history
+                .iter()
+                .find(|snapshot| snapshot.tick == client.baseline && snapshot.tick >= oldest_kept)
+                .filter(|_| !keyframe);
+            let text = encoded.entry(base.map(|base| base.tick)).or_insert_with(|| match base {
+                Some(base) => encode(&ServerMessage::Delta(latest.delta_from(base))),
+                None => encode(&ServerMessage::Snapshot(latest.clone())),
+            });
+            if base.is_none() {
+                client.baseline = latest.tick;
+            }
+            // A closed outbox means the socket thread is gone
+            client.outbox.send(text.clone()).is_ok()
+        });
+
+        let oldest_baseline = self.clients.values().map(|client| client.baseline).min().unwrap_or(latest.tick);
+        let keep_from = oldest_baseline.max(oldest_kept);
+        while self.history.len() > 1 && self.history.front().is_some_and(|snapshot| snapshot.tick < keep_from) {
+            self.history.pop_front();
+        }
+    }
+
+    fn write_report(&self) {
+        let Some(path) = &self.export else {
+            return;
+        };
+        let written = serde_json::to_string_pretty(&RunReport::from_game(&self.game))
+            .map_err(|err| err.to_string())
+            .and_then(|json| std::fs::write(path, json).map_err(|err| err.to_string()));
+        match written {
+            Ok(()) => debug!(path = %path.display(), "run report written"),
+            Err(err) => warn!(path = %path.display(), %err, "could not write run report"),
+        }
+    }
+
+    fn send_to(&mut self, client: ClientId, message: &ServerMessage) {
+        if let Some(Client { outbox, .. }) = self.clients.get(&client) {
+            if outbox.send(encode(message)).is_err() {
+                self.clients.remove(&client);
+            }
+        }
+    }
+}
+
+fn encode(message: &ServerMessage) -> String {
+    serde_json::to_string(message).unwrap_or_else(|err| {
+        warn!(%err, "could not encode message");
+        String::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_rush_core::protocol::Baselines;
+    use rust_rush_core::{BuildTool, Position};
+    use std::sync::mpsc;
+
+    fn decode(text: &str) -> ServerMessage {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_joining_client_gets_snapshot_then_deltas() {
+        let mut session = Session::new(Game::new());
+        let (outbox, inbox) = mpsc::channel();
+        session.handle(Inbound::Joined { client: 1, role: Role::Player, outbox });
+
+        let mut view = Baselines::new();
+        let first = decode(&inbox.recv().unwrap());
+        assert!(matches!(first, ServerMessage::Snapshot(_)), "first message should be a snapshot");
+        view.receive(&first).unwrap();
+
+        let command = r#"{"type":"Build","tool":{"Tower":"Basic"},"position":{"x":4,"y":4}}"#;
+        session.handle(Inbound::Command { client: 1, command: serde_json::from_str(command).unwrap() });
+        for _ in 0..3 {
+            session.step(1.0 / 30.0);
+            match decode(&inbox.recv().unwrap()) {
+                ServerMessage::Delta(delta) => view.receive(&ServerMessage::Delta(delta)).unwrap(),
+                other => panic!("expected a delta, got {:?}", other),
+            };
+        }
+        let latest = view.latest().unwrap();
+        assert_eq!(latest, &Snapshot::capture(&session.game));
+        assert_eq!(latest.towers.len(), 1);
+    }
+
+    #[test]
+    fn test_deltas_build_on_each_clients_last_ack() {
+        let mut session = Session::new(Game::new());
+        let (outbox, inbox) = mpsc::channel();
+        session.handle(Inbound::Joined { client: 1, role: Role::Spectator, outbox });
+        let mut view = Baselines::new();
+        view.receive(&decode(&inbox.recv().unwrap())).unwrap();
+
+        // Without acks every delta builds on the join snapshot
+        assert!(session.game.state.spawn_enemy().is_ok());
+        let mut ticks = Vec::new();
+        for _ in 0..3 {
+            session.step(1.0 / 30.0);
+            let ServerMessage::Delta(delta) = decode(&inbox.recv().unwrap()) else { panic!("expected a delta") };
+            assert_eq!(delta.base_tick, 0);
+            ticks.push(view.receive(&ServerMessage::Delta(delta)).unwrap().unwrap().tick);
+        }
+
+        // An ack moves the baseline up; one for a tick never sent doesn't
+        session.handle(Inbound::Ack { client: 1, tick: ticks[1] });
+        session.handle(Inbound::Ack { client: 1, tick: 999 });
+        session.step(1.0 / 30.0);
+        let ServerMessage::Delta(delta) = decode(&inbox.recv().unwrap()) else { panic!("expected a delta") };
+        assert_eq!(delta.base_tick, ticks[1]);
+        view.receive(&ServerMessage::Delta(delta)).unwrap();
+        assert_eq!(view.latest(), Some(&Snapshot::capture(&session.game)));
+        assert_eq!(session.history.front().map(|snapshot| snapshot.tick), Some(ticks[1]));
+    }
+
+    #[test]
+    fn test_rejected_command_goes_back_to_sender_only() {
+        let mut session = Session::new(Game::new());
+        let (first, first_inbox) = mpsc::channel();
+        let (second, second_inbox) = mpsc::channel();
+        session.handle(Inbound::Joined { client: 1, role: Role::Player, outbox: first });
+        session.handle(Inbound::Joined { client: 2, role: Role::Player, outbox: second });
+        first_inbox.recv().unwrap();
+        second_inbox.recv().unwrap();
+
+        let command = Command::SellTower { tower_id: Default::default() };
+        session.handle(Inbound::Command { client: 2, command: command.clone() });
+        let reason = Some(ActionError::NoSuchTower);
+        assert_eq!(decode(&second_inbox.recv().unwrap()), ServerMessage::Rejected { command, reason });
+        assert!(first_inbox.try_recv().is_err());
+
+        session.handle(Inbound::Left { client: 2 });
+        assert_eq!(session.clients.len(), 1);
+    }
+
+    #[test]
+    fn test_spectators_watch_but_cannot_command() {
+        let mut session = Session::new(Game::new());
+        let (outbox, inbox) = mpsc::channel();
+        session.handle(Inbound::Joined { client: 3, role: Role::Spectator, outbox });
+        inbox.recv().unwrap();
+
+        let command = Command::Build { tool: BuildTool::SLOTS[0], position: Position::new(4, 4) };
+        session.handle(Inbound::Command { client: 3, command: command.clone() });
+        let reason = Some(ActionError::NotAllowed);
+        assert_eq!(decode(&inbox.recv().unwrap()), ServerMessage::Rejected { command, reason });
+        assert!(session.game.state.towers.is_empty());
+
+        session.step(1.0 / 30.0);
+        assert!(matches!(decode(&inbox.recv().unwrap()), ServerMessage::Delta(_)));
+    }
+}
@@ -0,0 +1,56 @@
+//! `--stdio` mode for bots: no socket and no clock. Each line on stdin is a
+//! JSON `BotRequest`; each reply is one line of JSON on stdout, either a
+//! `StepResult` or `{"error": "..."}`. The game only advances when a `Step`
+//! asks it to, so a slow agent never falls behind.
+
+use std::io::{self, BufRead, Write};
+
+use rust_rush_core::bot::{BotRequest, BotSession};
+use serde_json::json;
+use tracing::{debug, info};
+
+pub fn run(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut session = BotSession::new();
+    info!("bot session on stdio");
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<BotRequest>(&line) {
+            Ok(request) => serde_json::to_value(session.handle(&request))?,
+            Err(err) => {
+                debug!(%err, "bad bot request");
+                json!({ "error": err.to_string() })
+            }
+        };
+        writeln!(output, "{}", reply)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_json_lines_round_trip() {
+        let input = concat!(
+            r#"{"type":"Step","ticks":30,"commands":[{"type":"Build","tool":{"Tower":"Basic"},"position":{"x":4,"y":6}}]}"#,
+            "\n\nnot json\n",
+            r#"{"type":"Observe"}"#,
+        );
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output).unwrap();
+
+        let replies: Vec<Value> =
+            String::from_utf8(output).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(replies.len(), 3);
+        assert_eq!(replies[0]["accepted"], json!([true]));
+        assert_eq!(replies[0]["observation"]["tick"], 30);
+        assert!(replies[1]["error"].is_string());
+        assert_eq!(replies[2]["observation"]["towers"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,85 @@
+//! Loads the achievement definitions and saved progress into the game, saves
+//! progress when something unlocks or a codex entry is first seen, and
+//! announces unlocks through the notifications.
+
+use rust_rush_core::achievements::{AchievementDef, AchievementProgress, AchievementTracker};
+use rust_rush_core::codex::Subject;
+use rust_rush_core::Game;
+use tracing::warn;
+
+use crate::notifications::{Notifications, Severity};
+use crate::storage;
+
+pub const PROFILE_FILE: &str = "profile.json";
+const DEFINITIONS: &str = include_str!("achievement_defs.json");
+const KILLS_PER_SAVE: u64 = 25; // Lifetime kills are saved in batches, not per kill
+
+pub fn load_into(game: &mut Game) {
+    let defs: Vec<AchievementDef> = serde_json::from_str(DEFINITIONS).unwrap_or_else(|err| {
+        warn!(%err, "bad achievement definitions");
+        Vec::new()
+    });
+    let progress = storage::load(PROFILE_FILE)
+        .and_then(|json| serde_json::from_str::<AchievementProgress>(&json).ok())
+        .unwrap_or_default();
+    game.achievements = AchievementTracker::new(defs, progress);
+}
+
+fn save(progress: &AchievementProgress) {
+    let saved = serde_json::to_string_pretty(progress).map(|json| storage::save(PROFILE_FILE, &json));
+    if !matches!(saved, Ok(Ok(()))) {
+        warn!("could not save achievement progress");
+    }
+}
+
+/// Hands new unlocks and codex entries to the notifications and keeps the
+/// profile saved
+pub struct Unlocks {
+    saved_kills: u64,   // Lifetime kills as of the last save
+    saved_codex: usize, // Codex entries as of the last save
+}
+
+impl Unlocks {
+    /// After `load_into`, so what was already saved isn't announced again
+    pub fn new(game: &Game) -> Self {
+        let progress = &game.achievements.progress;
+        Unlocks { saved_kills: progress.lifetime_kills, saved_codex: progress.codex.seen.len() }
+    }
+
+    pub fn update(&mut self, game: &mut Game, notifications: &mut Notifications) {
+        let unlocked = game.achievements.take_unlocked();
+        let progress = &game.achievements.progress;
+        let kills_due = progress.lifetime_kills >= self.saved_kills + KILLS_PER_SAVE;
+        let new_entries = progress.codex.seen.get(self.saved_codex..).unwrap_or_default();
+        for subject in new_entries {
+            let name = match subject {
+                Subject::Tower(tower_type) => format!("{:?} Tower", tower_type),
+                Subject::Enemy(kind) => kind.name().to_string(),
+            };
+            notifications.push(Severity::Info, "New in the codex", name);
+        }
+        if !unlocked.is_empty() || kills_due || !new_entries.is_empty() {
+            save(progress);
+            self.saved_kills = progress.lifetime_kills;
+            self.saved_codex = progress.codex.seen.len();
+        }
+        for def in unlocked {
+            notifications.push(Severity::Success, format!("Achievement: {}", def.name), def.description);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions_parse() {
+        let defs: Vec<AchievementDef> = serde_json::from_str(DEFINITIONS).unwrap();
+        assert!(defs.iter().any(|def| def.id == "exterminator"));
+        let mut ids: Vec<&str> = defs.iter().map(|def| def.id.as_str()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), defs.len(), "achievement ids must be unique");
+    }
+}
@@ -0,0 +1,79 @@
+//! Art from the `assets` folder, in place of what the client draws itself:
+//! `tiles.png`, a tile sheet for the enemy route, and `shaders/postfx.vert`
+//! and `shaders/postfx.frag` for the post-processing pass. Any of them can
+//! be left out. A file that can't be read or won't decode or compile is
+//! logged and the version already in use stays, so a reload that catches a
+//! file half-written (see `hot_reload`) changes nothing until the next save.
+
+use std::io::ErrorKind;
+use std::path::Path;
+
+use macroquad::prelude::*;
+use tracing::{info, warn};
+
+use crate::autotile::TileKind;
+use crate::board_cache::BoardCache;
+use crate::postfx::PostFx;
+
+pub const ASSETS_DIR: &str = "assets";
+const TILE_SHEET: &str = "tiles.png";
+const SHADER_DIR: &str = "shaders";
+
+/// Columns of the tile sheet: one square tile per kind, unturned
+pub const TILE_SHEET_ORDER: [TileKind; 6] =
+    [TileKind::Single, TileKind::End, TileKind::Straight, TileKind::Corner, TileKind::Tee, TileKind::Cross];
+
+/// Load (or reload) everything in `dir` into the renderers
+pub fn load(dir: &Path, board: &mut BoardCache, postfx: &mut PostFx) {
+    match load_tile_sheet(&dir.join(TILE_SHEET)) {
+        Ok(sheet) => board.set_tile_sheet(sheet),
+        Err(err) => warn!(%err, "tile sheet not loaded; keeping the current one"),
+    }
+    postfx.load_shaders(&dir.join(SHADER_DIR));
+}
+
+/// The file's bytes, or None if there's no such file
+pub fn read_optional(path: &Path) -> Result<Option<Vec<u8>>, String> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if matches!(err.kind(), ErrorKind::NotFound | ErrorKind::Unsupported) => Ok(None),
+        Err(err) => Err(format!("{}: {}", path.display(), err)),
+    }
+}
+
+fn load_tile_sheet(path: &Path) -> Result<Option<Texture2D>, String> {
+    let Some(bytes) = read_optional(path)? else {
+        return Ok(None);
+    };
+    let image = Image::from_file_with_format(&bytes, None).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let (width, height) = (image.width as usize, image.height as usize);
+    if height == 0 || width != height * TILE_SHEET_ORDER.len() {
+        return Err(format!(
+            "{}: expected {} square tiles in a row, got {}x{}",
+            path.display(),
+            TILE_SHEET_ORDER.len(),
+            width,
+            height
+        ));
+    }
+    info!(path = %path.display(), tile = height, "tile sheet loaded");
+    Ok(Some(Texture2D::from_image(&image)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_sheet_is_none_and_a_partial_one_is_an_error() {
+        let dir = std::env::temp_dir().join(format!("rust-rush-assets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let missing = load_tile_sheet(&dir.join(TILE_SHEET));
+        std::fs::write(dir.join(TILE_SHEET), b"\x89PNG\r\n\x1a\n").unwrap(); // Cut off after the signature
+        let partial = load_tile_sheet(&dir.join(TILE_SHEET));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(missing, Ok(None)));
+        assert!(partial.is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Attack panel, shown with `--attack`: counters for the grunts and gunners
+//! in the next wave, its formation, and what it costs against the budget
+//! left. The call-wave key (or the Send button) sends it. The computer
+//! builds the defense, so the board itself only pans and zooms.
+
+use macroquad::prelude::*;
+use rust_rush_core::attack::{roster_cost, send_cost, ATTACK_WAVES, MAX_ROSTER};
+use rust_rush_core::formations::{Formation, Roster};
+use rust_rush_core::gunners::EnemyKind;
+use rust_rush_core::protocol::Command;
+use rust_rush_core::Game;
+
+use crate::display::HudLayout;
+use crate::input::{Action, InputConfig};
+use crate::theme::Theme;
+
+const PANEL_WIDTH: f32 = 260.0;
+const PANEL_HEIGHT: f32 = 190.0;
+const BUTTON_SIZE: f32 = 22.0;
+const BIG_STEP: u32 = 5; // With shift held
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Button {
+    Fewer(EnemyKind),
+    More(EnemyKind),
+    Formation,
+    Send,
+}
+
+const BUTTONS: [Button; 6] = [
+    Button::Fewer(EnemyKind::Grunt),
+    Button::More(EnemyKind::Grunt),
+    Button::Fewer(EnemyKind::Gunner),
+    Button::More(EnemyKind::Gunner),
+    Button::Formation,
+    Button::Send,
+];
+
+pub struct AttackPanel;
+
+impl AttackPanel {
+    pub fn new() -> Self {
+        AttackPanel
+    }
+
+    /// Pause, send and panel clicks. True if the mouse went to the panel,
+    /// so it shouldn't reach the board.
+    pub fn handle_input(&mut self, game: &mut Game, input: &InputConfig, hud: &HudLayout) -> bool {
+        if input.pressed(Action::Pause) {
+            let _ = game.apply_command(&Command::SetPaused { paused: !game.state.paused });
+        }
+        if input.pressed(Action::CallWave) {
+            let _ = game.apply_command(&Command::CallNextWave);
+        }
+
+        let cursor = Vec2::from(mouse_position()) / hud.scale;
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let step = if is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift) { BIG_STEP } else { 1 };
+            if let Some(button) = BUTTONS.into_iter().find(|button| Self::button(*button, hud).contains(cursor)) {
+                press(game, button, step);
+            }
+        }
+        Self::frame(hud).contains(cursor)
+    }
+
+    pub fn render(&self, game: &Game, input: &InputConfig, hud: &HudLayout, theme: &Theme) {
+        set_camera(&hud.camera());
+        let attack = &game.state.attack;
+        let frame = Self::frame(hud);
+        draw_rectangle(frame.x, frame.y, frame.w, frame.h, Color::new(0.0, 0.0, 0.0, 0.75));
+        draw_rectangle_lines(frame.x, frame.y, frame.w, frame.h, 2.0, theme.danger);
+        let wave = game.state.waves.wave.min(ATTACK_WAVES);
+        let title = format!("ATTACK  wave {}/{}  budget ${}", wave, ATTACK_WAVES, attack.budget);
+        draw_text(title, frame.x + 10.0, frame.y + 20.0, 18.0, theme.danger);
+
+        let rows = [(EnemyKind::Grunt, attack.draft.grunts), (EnemyKind::Gunner, attack.draft.gunners)];
+        for (kind, count) in rows {
+            let y = Self::button(Button::More(kind), hud).y;
+            let label = format!("{} x{}  (${} each)", kind.name(), count, send_cost(kind));
+            draw_text(label, frame.x + 10.0, y + 15.0, 16.0, theme.text);
+        }
+        let formation = format!("Formation: {}", formation_name(attack.formation));
+        draw_text(formation, frame.x + 10.0, frame.y + 125.0, 16.0, theme.text);
+
+        for button in BUTTONS {
+            let rect = Self::button(button, hud);
+            let enabled = button != Button::Send || (attack.can_send() && game.state.waves.is_build_phase());
+            let color = if enabled { theme.text } else { GRAY };
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 1.5, color);
+            let label = match button {
+                Button::Fewer(_) => "-".to_string(),
+                Button::More(_) => "+".to_string(),
+                Button::Formation => ">".to_string(),
+                Button::Send => format!("Send ${} [{}]", roster_cost(&attack.draft), input.label(Action::CallWave)),
+            };
+            draw_text(label, rect.x + 6.0, rect.y + rect.h - 6.0, 16.0, color);
+        }
+
+        let leaks = format!("Leaks: {}", game.stats.total_leaks);
+        draw_text(leaks, frame.x + 10.0, frame.y + frame.h - 10.0, 16.0, theme.gold);
+        set_default_camera();
+    }
+
+    /// Bottom-right corner, clear of the minimap and the ability bar
+    fn frame(hud: &HudLayout) -> Rect {
+        Rect::new(hud.width - PANEL_WIDTH - 10.0, hud.height - PANEL_HEIGHT - 10.0, PANEL_WIDTH, PANEL_HEIGHT)
+    }
+
+    fn button(button: Button, hud: &HudLayout) -> Rect {
+        let frame = Self::frame(hud);
+        let right = frame.x + frame.w - 10.0;
+        let row = |kind| match kind {
+            EnemyKind::Grunt => frame.y + 50.0,
+            EnemyKind::Gunner => frame.y + 80.0,
+        };
+        match button {
+            Button::Fewer(kind) => Rect::new(right - 2.0 * BUTTON_SIZE - 6.0, row(kind), BUTTON_SIZE, BUTTON_SIZE),
+            Button::More(kind) => Rect::new(right - BUTTON_SIZE, row(kind), BUTTON_SIZE, BUTTON_SIZE),
+            Button::Formation => Rect::new(right - BUTTON_SIZE, frame.y + 110.0, BUTTON_SIZE, BUTTON_SIZE),
+            Button::Send => Rect::new(right - 150.0, frame.y + frame.h - 32.0, 150.0, BUTTON_SIZE),
+        }
+    }
+}
+
+fn press(game: &mut Game, button: Button, step: u32) {
+    let attack = &game.state.attack;
+    let (mut roster, mut formation) = (attack.draft, attack.formation);
+    match button {
+        Button::Fewer(kind) => {
+            let count = count_of(&mut roster, kind);
+            *count = count.saturating_sub(step);
+        }
+        Button::More(kind) => {
+            let count = count_of(&mut roster, kind);
+            *count = (*count + step).min(MAX_ROSTER);
+        }
+        Button::Formation => formation = next_formation(formation),
+        Button::Send => {
+            let _ = game.apply_command(&Command::CallNextWave);
+            return;
+        }
+    }
+    let _ = game.apply_command(&Command::ComposeWave { roster, formation });
+}
+
+fn count_of(roster: &mut Roster, kind: EnemyKind) -> &mut u32 {
+    match kind {
+        EnemyKind::Grunt => &mut roster.grunts,
+        EnemyKind::Gunner => &mut roster.gunners,
+    }
+}
+
+fn next_formation(formation: Formation) -> Formation {
+    match formation {
+        Formation::Column => Formation::StaggeredPairs,
+        Formation::StaggeredPairs => Formation::Wedge,
+        Formation::Wedge => Formation::Column,
+    }
+}
+
+fn formation_name(formation: Formation) -> &'static str {
+    match formation {
+        Formation::Column => "Column",
+        Formation::StaggeredPairs => "Staggered pairs",
+        Formation::Wedge => "Wedge",
+    }
+}
@@ -0,0 +1,134 @@
+//! Procedurally generated sound cues. Playback needs the `audio` feature
+//! (macroquad's audio backend links against the platform sound library);
+//! without it every cue is silently skipped.
+
+#[cfg(feature = "audio")]
+use macroquad::audio::{load_sound_from_bytes, play_sound, PlaySoundParams, Sound};
+use rust_rush_core::events::GameEvent;
+use rust_rush_core::Game;
+
+const HEARTBEAT_INTERVAL: f32 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundCue {
+    Leak,
+    Heartbeat,
+}
+
+impl SoundCue {
+    /// (frequency in Hz, duration in seconds)
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    fn tone(&self) -> (f32, f32) {
+        match self {
+            SoundCue::Leak => (220.0, 0.25),
+            SoundCue::Heartbeat => (60.0, 0.12),
+        }
+    }
+}
+
+/// Build a mono 16-bit PCM WAV file containing a decaying sine tone
+#[cfg_attr(not(feature = "audio"), allow(dead_code))]
+pub fn synthesize_tone(frequency: f32, duration: f32) -> Vec<u8> {
+    const SAMPLE_RATE: u32 = 22050;
+    let sample_count = (SAMPLE_RATE as f32 * duration) as u32;
+    let data_len = sample_count * 2;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+
+    for i in 0..sample_count {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let envelope = 1.0 - t / duration;
+        let sample = (t * frequency * std::f32::consts::TAU).sin() * envelope * 0.5;
+        wav.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+    }
+
+    wav
+}
+
+/// Decides which cues to play each frame from what the simulation reported
+#[derive(Debug, Default)]
+pub struct CueScheduler {
+    heartbeat_timer: f32,
+}
+
+impl CueScheduler {
+    /// Sound cues for the events of the last update, plus the low-health heartbeat
+    pub fn cues(&mut self, game: &Game, delta: f32) -> Vec<SoundCue> {
+        let mut cues: Vec<SoundCue> = game.frame_events
+            .iter()
+            .filter(|event| matches!(event, GameEvent::EnemyLeaked { .. }))
+            .map(|_| SoundCue::Leak)
+            .take(1)
+            .collect();
+
+        if game.is_low_health() && !game.state.paused {
+            self.heartbeat_timer -= delta;
+            if self.heartbeat_timer <= 0.0 {
+                self.heartbeat_timer = HEARTBEAT_INTERVAL;
+                cues.push(SoundCue::Heartbeat);
+            }
+        }
+
+        cues
+    }
+}
+
+#[derive(Default)]
+pub struct AudioBank {
+    #[cfg(feature = "audio")]
+    sounds: Vec<(SoundCue, Sound)>,
+}
+
+impl AudioBank {
+    pub async fn load() -> Self {
+        #[cfg(feature = "audio")]
+        {
+            let mut sounds = Vec::new();
+            for cue in [SoundCue::Leak, SoundCue::Heartbeat] {
+                let (frequency, duration) = cue.tone();
+                if let Ok(sound) = load_sound_from_bytes(&synthesize_tone(frequency, duration)).await {
+                    sounds.push((cue, sound));
+                }
+            }
+            AudioBank { sounds }
+        }
+        #[cfg(not(feature = "audio"))]
+        AudioBank::default()
+    }
+
+    /// Play a cue at `volume` (0 to 1, see `Settings::effects_level`)
+    pub fn play(&self, cue: SoundCue, volume: f32) {
+        #[cfg(feature = "audio")]
+        if let Some((_, sound)) = self.sounds.iter().find(|(c, _)| *c == cue) {
+            play_sound(sound, PlaySoundParams { looped: false, volume });
+        }
+        #[cfg(not(feature = "audio"))]
+        let _ = (cue, volume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_header_and_length() {
+        let wav = synthesize_tone(440.0, 0.1);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+        let samples = (22050.0 * 0.1) as usize;
+        assert_eq!(wav.len(), 44 + samples * 2);
+    }
+}
@@ -0,0 +1,205 @@
+//! Autotiling: every board cell gets a terrain type, and path and wall cells
+//! pick an edge, corner or junction tile from their neighbors, so walls
+//! read as solid blocks and the enemy route as one connected track.
+//!
+//! `TILE_RULES` maps the four sides a cell connects on to a tile kind and a
+//! rotation; a tile sheet only needs one tile per kind, drawn turned. The
+//! board cache draws the kinds from `assets/tiles.png` if there is one, and
+//! as shapes otherwise.
+
+use std::collections::{HashMap, HashSet};
+
+use rust_rush_core::{Direction, GameState, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terrain {
+    Open,
+    Path,     // On the route enemies will take
+    Wall,     // Unwalkable with nothing built on it
+    Occupied, // Under a tower, obstacle, gate or patrol; they draw themselves
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileKind {
+    Single,   // Connects nowhere
+    End,      // Connects up
+    Straight, // Up and down
+    Corner,   // Up and right
+    Tee,      // Up, right and down
+    Cross,    // All four sides
+}
+
+impl TileKind {
+    /// Sides the unturned tile connects on
+    pub fn sides(self) -> u8 {
+        match self {
+            TileKind::Single => 0,
+            TileKind::End => UP,
+            TileKind::Straight => UP | DOWN,
+            TileKind::Corner => UP | RIGHT,
+            TileKind::Tee => UP | RIGHT | DOWN,
+            TileKind::Cross => UP | RIGHT | DOWN | LEFT,
+        }
+    }
+}
+
+/// Turn a side mask clockwise by quarter turns
+pub fn rotate(mask: u8, turns: u8) -> u8 {
+    let turns = turns % 4;
+    ((mask << turns) | (mask >> ((4 - turns) % 4))) & 0b1111
+}
+
+/// Bits of a side mask, clockwise from up
+pub const UP: u8 = 1;
+pub const RIGHT: u8 = 2;
+pub const DOWN: u8 = 4;
+pub const LEFT: u8 = 8;
+
+/// Kind and clockwise quarter turns, indexed by side mask
+const TILE_RULES: [(TileKind, u8); 16] = [
+    (TileKind::Single, 0),   // none
+    (TileKind::End, 0),      // up
+    (TileKind::End, 1),      // right
+    (TileKind::Corner, 0),   // up, right
+    (TileKind::End, 2),      // down
+    (TileKind::Straight, 0), // up, down
+    (TileKind::Corner, 1),   // right, down
+    (TileKind::Tee, 0),      // up, right, down
+    (TileKind::End, 3),      // left
+    (TileKind::Corner, 3),   // up, left
+    (TileKind::Straight, 1), // right, left
+    (TileKind::Tee, 3),      // up, right, left
+    (TileKind::Corner, 2),   // down, left
+    (TileKind::Tee, 2),      // up, down, left
+    (TileKind::Tee, 1),      // right, down, left
+    (TileKind::Cross, 0),    // all
+];
+
+/// The tile one cell shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub terrain: Terrain,
+    pub sides: u8,         // Sides joined to a neighbor of the same terrain
+    pub inner_corners: u8, // Corners to notch: both sides joined but the diagonal isn't (bit = side clockwise before it)
+    pub kind: TileKind,
+    pub rotation: u8,
+}
+
+impl Tile {
+    fn new(terrain: Terrain, sides: u8, inner_corners: u8) -> Self {
+        let (kind, rotation) = TILE_RULES[sides as usize];
+        Tile { terrain, sides, inner_corners, kind, rotation }
+    }
+}
+
+/// Terrain and tile for every cell of the board
+pub fn build_tiles(state: &GameState) -> HashMap<Position, Tile> {
+    let grid = &state.grid;
+    let mut occupied: HashSet<Position> = state.towers.values().flat_map(|tower| tower.cells()).collect();
+    occupied.extend(state.obstacles.iter().map(|obstacle| obstacle.position));
+    occupied.extend(state.gates.iter().map(|gate| gate.position));
+    occupied.extend(state.patrols.iter().map(|patrol| patrol.cell()));
+    let routes = state.lane_routes();
+    let on_route: HashSet<Position> = routes.iter().flatten().copied().collect();
+
+    let terrain = |pos: &Position| {
+        if occupied.contains(pos) {
+            Terrain::Occupied
+        } else if !grid.is_walkable(pos) {
+            Terrain::Wall
+        } else if on_route.contains(pos) {
+            Terrain::Path
+        } else {
+            Terrain::Open
+        }
+    };
+
+    let mut tiles = HashMap::new();
+    for x in 0..grid.width() {
+        for y in 0..grid.height() {
+            let pos = Position::new(x, y);
+            let tile = match terrain(&pos) {
+                // Walls join anything solid, and the board edge, so they
+                // read as one mass
+                Terrain::Wall => {
+                    let solid = |pos: &Position| !grid.in_bounds(pos) || !grid.is_walkable(pos);
+                    Tile::new(Terrain::Wall, side_mask(pos, solid), inner_corners(pos, solid))
+                }
+                other => Tile::new(other, 0, 0),
+            };
+            tiles.insert(pos, tile);
+        }
+    }
+
+    // The route joins cells it walks between, not every route cell that
+    // happens to be next door, so a tight maze still reads as one track
+    for step in routes.iter().flat_map(|route| route.windows(2)) {
+        let (from, to) = (step[0], step[1]);
+        let Some(side) = side_toward(from, to) else {
+            continue; // A teleporter jump
+        };
+        let back = side_toward(to, from).unwrap_or(0);
+        for (pos, bit) in [(from, side), (to, back)] {
+            if let Some(tile) = tiles.get_mut(&pos).filter(|tile| tile.terrain == Terrain::Path) {
+                *tile = Tile::new(Terrain::Path, tile.sides | bit, 0);
+            }
+        }
+    }
+    tiles
+}
+
+const SIDES: [(u8, Direction); 4] =
+    [(UP, Direction::Up), (RIGHT, Direction::Right), (DOWN, Direction::Down), (LEFT, Direction::Left)];
+
+fn side_mask(pos: Position, same: impl Fn(&Position) -> bool) -> u8 {
+    SIDES.iter().filter(|(_, direction)| same(&pos.step(*direction))).fold(0, |mask, (bit, _)| mask | bit)
+}
+
+/// Corners where both sides are joined but the diagonal between them isn't
+fn inner_corners(pos: Position, same: impl Fn(&Position) -> bool) -> u8 {
+    let sides = side_mask(pos, &same);
+    let mut corners = 0;
+    for (index, (bit, direction)) in SIDES.iter().enumerate() {
+        let (next_bit, next_direction) = SIDES[(index + 1) % 4];
+        if sides & bit != 0 && sides & next_bit != 0 && !same(&pos.step(*direction).step(next_direction)) {
+            corners |= bit;
+        }
+    }
+    corners
+}
+
+fn side_toward(from: Position, to: Position) -> Option<u8> {
+    SIDES.iter().find(|(_, direction)| from.step(*direction) == to).map(|(bit, _)| *bit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_rush_core::pathfinding::find_path;
+
+    #[test]
+    fn test_rules_cover_every_mask() {
+        for (mask, (kind, turns)) in TILE_RULES.iter().enumerate() {
+            assert_eq!(rotate(kind.sides(), *turns), mask as u8, "{:?} turned {}", kind, turns);
+        }
+    }
+
+    #[test]
+    fn test_walls_and_route_pick_tiles_from_neighbors() {
+        let mut state = GameState::new();
+        // A 2x2 block of wall away from the route
+        for (x, y) in [(5, 5), (6, 5), (5, 6), (6, 6)] {
+            state.grid.set_walkable(&Position::new(x, y), false);
+        }
+        let tiles = build_tiles(&state);
+        let top_left = tiles[&Position::new(5, 5)];
+        assert_eq!(top_left.terrain, Terrain::Wall);
+        assert_eq!((top_left.kind, top_left.rotation), (TileKind::Corner, 1)); // Joined right and down
+
+        let spawn = tiles[&state.spawn_point];
+        assert_eq!(spawn.terrain, Terrain::Path);
+        assert_eq!(spawn.kind, TileKind::End);
+        let middle = find_path(&state.grid, state.spawn_point, state.goal_point).unwrap()[1];
+        assert!(matches!(tiles[&middle].kind, TileKind::Straight | TileKind::Corner));
+    }
+}
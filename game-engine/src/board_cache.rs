@@ -0,0 +1,257 @@
+//! The static part of the board: cells, autotiled walls and route (see
+//! `autotile`), spawn and goal tiles, teleporter rings and one-way arrows. They are drawn once into an offscreen texture
+//! and the texture is drawn as a single quad each frame; it is rebuilt only
+//! when the grid's revision, the spawn/goal or the palette changes, or a
+//! tile sheet is loaded (see `assets`), whose tiles then stand in for the
+//! drawn route.
+
+use macroquad::prelude::*;
+use rust_rush_core::{Game, GridShape, Position, CELL_SIZE};
+
+use crate::assets::TILE_SHEET_ORDER;
+use crate::autotile::{self, Terrain, Tile, TileKind, DOWN, LEFT, RIGHT, UP};
+use crate::camera::GameCamera;
+use crate::theme::{Palette, Theme};
+
+const SCALE: f32 = 2.0; // Texels per world unit, so borders stay crisp when zoomed in
+const MAX_TEXTURE: f32 = 4096.0; // Longest side; huge boards get fewer texels per cell
+const PAD: f32 = CELL_SIZE; // Border around the board, where edge hexes stick out
+
+type BoardKey = (u64, i32, i32, Position, Position, Palette); // Grid revision and size, spawn, goal, palette
+
+pub struct BoardCache {
+    target: Option<RenderTarget>,
+    built_for: Option<BoardKey>,
+    tile_sheet: Option<Texture2D>,
+}
+
+impl BoardCache {
+    pub fn new() -> Self {
+        BoardCache { target: None, built_for: None, tile_sheet: None }
+    }
+
+    /// Draw the route from `sheet` from now on, or as shapes with None
+    pub fn set_tile_sheet(&mut self, sheet: Option<Texture2D>) {
+        self.tile_sheet = sheet;
+        self.built_for = None;
+    }
+
+    /// Draw the board through `camera`, rebuilding the texture first if the
+    /// board changed since last time
+    pub fn render(&mut self, game: &Game, camera: &GameCamera, theme: &Theme) {
+        let state = &game.state;
+        let grid = &state.grid;
+        let key = (grid.revision(), grid.width(), grid.height(), state.spawn_point, state.goal_point, theme.palette);
+        let size = board_size(game) + Vec2::splat(PAD * 2.0);
+        if self.built_for != Some(key) || self.target.is_none() {
+            let scale = SCALE.min(MAX_TEXTURE / size.x.max(size.y));
+            let (width, height) = ((size.x * scale) as u32, (size.y * scale) as u32);
+            let reuse = self.target.as_ref().filter(|target| {
+                (target.texture.width() as u32, target.texture.height() as u32) == (width, height)
+            });
+            let target = reuse.cloned().unwrap_or_else(|| {
+                let target = render_target(width.max(1), height.max(1));
+                target.texture.set_filter(FilterMode::Linear);
+                target
+            });
+            let mut board_camera = Camera2D::from_display_rect(Rect::new(-PAD, -PAD, size.x, size.y));
+            board_camera.render_target = Some(target.clone());
+            set_camera(&board_camera);
+            clear_background(BLANK);
+            draw_board(game, theme, self.tile_sheet.as_ref());
+            self.target = Some(target);
+            self.built_for = Some(key);
+        }
+
+        set_camera(&camera.camera2d());
+        if let Some(target) = &self.target {
+            let params = DrawTextureParams { dest_size: Some(size), ..Default::default() };
+            draw_texture_ex(&target.texture, -PAD, -PAD, WHITE, params);
+        }
+    }
+}
+
+const RIM: f32 = 3.0;
+const TRACK_WIDTH: f32 = CELL_SIZE * 0.4;
+
+/// A wall's outline only on the sides facing open ground, plus notches at
+/// inner corners, so joined walls read as one block
+fn draw_wall_rim(x: f32, y: f32, tile: &Tile, color: Color) {
+    let far = CELL_SIZE - RIM;
+    let edges = [
+        (UP, (0.0, 0.0, CELL_SIZE, RIM)),
+        (RIGHT, (far, 0.0, RIM, CELL_SIZE)),
+        (DOWN, (0.0, far, CELL_SIZE, RIM)),
+        (LEFT, (0.0, 0.0, RIM, CELL_SIZE)),
+    ];
+    for (bit, (dx, dy, w, h)) in edges {
+        if tile.sides & bit == 0 {
+            draw_rectangle(x + dx, y + dy, w, h, color);
+        }
+    }
+    // Each bit names the corner clockwise after its side: up-right, down-right, ...
+    let corners = [(UP, (far, 0.0)), (RIGHT, (far, far)), (DOWN, (0.0, far)), (LEFT, (0.0, 0.0))];
+    for (bit, (dx, dy)) in corners {
+        if tile.inner_corners & bit != 0 {
+            draw_rectangle(x + dx, y + dy, RIM, RIM, color);
+        }
+    }
+}
+
+/// The enemy route: the tile's kind, turned, as a band through the cell
+/// center out to each joined side
+fn draw_track(x: f32, y: f32, tile: &Tile, color: Color) {
+    let arms = autotile::rotate(tile.kind.sides(), tile.rotation);
+    let center = vec2(x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+    let half = TRACK_WIDTH / 2.0;
+    if matches!(tile.kind, TileKind::End | TileKind::Single) {
+        draw_circle(center.x, center.y, half, color); // Rounded cap at the route's ends
+    } else {
+        draw_rectangle(center.x - half, center.y - half, TRACK_WIDTH, TRACK_WIDTH, color);
+    }
+    let reach = CELL_SIZE / 2.0;
+    for (bit, (dx, dy, w, h)) in [
+        (UP, (-half, -reach, TRACK_WIDTH, reach)),
+        (RIGHT, (0.0, -half, reach, TRACK_WIDTH)),
+        (DOWN, (-half, 0.0, TRACK_WIDTH, reach)),
+        (LEFT, (-reach, -half, reach, TRACK_WIDTH)),
+    ] {
+        if arms & bit != 0 {
+            draw_rectangle(center.x + dx, center.y + dy, w, h, color);
+        }
+    }
+}
+
+/// The route from the tile sheet: the tile's kind, turned
+fn draw_sheet_tile(x: f32, y: f32, tile: &Tile, sheet: &Texture2D) {
+    let size = sheet.height();
+    let column = TILE_SHEET_ORDER.iter().position(|kind| *kind == tile.kind).unwrap_or(0);
+    let params = DrawTextureParams {
+        dest_size: Some(Vec2::splat(CELL_SIZE)),
+        source: Some(Rect::new(column as f32 * size, 0.0, size, size)),
+        rotation: tile.rotation as f32 * std::f32::consts::FRAC_PI_2,
+        ..Default::default()
+    };
+    draw_texture_ex(sheet, x, y, WHITE, params);
+}
+
+/// Hex boards: a flattened hexagon per cell (see `GridShape`) and the
+/// route as a line through the hex centers. Square autotiles don't apply.
+fn draw_hex_board(game: &Game, theme: &Theme) {
+    let state = &game.state;
+    let grid = &state.grid;
+    for x in 0..grid.width() {
+        for y in 0..grid.height() {
+            let pos = Position::new(x, y);
+            if !grid.in_bounds(&pos) {
+                continue;
+            }
+            let color = if !grid.is_walkable(&pos) {
+                theme.cell_blocked
+            } else if state.is_spawn(&pos) {
+                theme.spawn
+            } else if state.is_goal(&pos) {
+                theme.goal
+            } else {
+                theme.cell
+            };
+            let corners = hex_corners(cell_center(pos));
+            for (i, corner) in corners.iter().enumerate() {
+                draw_triangle(cell_center(pos), *corner, corners[(i + 1) % 6], color);
+            }
+            for (i, corner) in corners.iter().enumerate() {
+                let next = corners[(i + 1) % 6];
+                draw_line(corner.x, corner.y, next.x, next.y, 1.0, theme.cell_border);
+            }
+        }
+    }
+
+    let track = Color::from_vec(theme.cell.to_vec().lerp(theme.cell_border.to_vec(), 0.45));
+    let routes = state.lane_routes();
+    for step in routes.iter().flat_map(|route| route.windows(2)).filter(|step| grid.neighbors(step[0]).contains(&step[1])) {
+        let (from, to) = (cell_center(step[0]), cell_center(step[1]));
+        draw_line(from.x, from.y, to.x, to.y, TRACK_WIDTH, track);
+        draw_circle(to.x, to.y, TRACK_WIDTH / 2.0, track);
+    }
+}
+
+fn cell_center(pos: Position) -> Vec2 {
+    let (x, y) = pos.to_world();
+    vec2(x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0)
+}
+
+/// Pointy-top hexagon two cells wide whose rows sit one cell apart
+fn hex_corners(center: Vec2) -> [Vec2; 6] {
+    let (half_width, tip, shoulder) = (CELL_SIZE, CELL_SIZE * 2.0 / 3.0, CELL_SIZE / 3.0);
+    [
+        center + vec2(0.0, -tip),
+        center + vec2(half_width, -shoulder),
+        center + vec2(half_width, shoulder),
+        center + vec2(0.0, tip),
+        center + vec2(-half_width, shoulder),
+        center + vec2(-half_width, -shoulder),
+    ]
+}
+
+fn board_size(game: &Game) -> Vec2 {
+    let grid = &game.state.grid;
+    vec2(grid.width() as f32 * CELL_SIZE, grid.height() as f32 * CELL_SIZE)
+}
+
+fn draw_board(game: &Game, theme: &Theme, tile_sheet: Option<&Texture2D>) {
+    let grid = &game.state.grid;
+    if grid.shape() == GridShape::Hex {
+        draw_hex_board(game, theme);
+        return;
+    }
+    let tiles = autotile::build_tiles(&game.state);
+    let track = Color::from_vec(theme.cell.to_vec().lerp(theme.cell_border.to_vec(), 0.45));
+    for (&pos, tile) in &tiles {
+        let (wx, wy) = pos.to_world();
+        let color = if !grid.is_walkable(&pos) {
+            theme.cell_blocked
+        } else if game.state.is_spawn(&pos) {
+            theme.spawn
+        } else if game.state.is_goal(&pos) {
+            theme.goal
+        } else {
+            theme.cell
+        };
+        draw_rectangle(wx, wy, CELL_SIZE, CELL_SIZE, color);
+
+        match tile.terrain {
+            Terrain::Wall => draw_wall_rim(wx, wy, tile, theme.cell_border),
+            Terrain::Path => {
+                draw_rectangle_lines(wx, wy, CELL_SIZE, CELL_SIZE, 1.0, theme.cell_border);
+                match tile_sheet {
+                    Some(sheet) => draw_sheet_tile(wx, wy, tile, sheet),
+                    None => draw_track(wx, wy, tile, track),
+                }
+            }
+            Terrain::Open | Terrain::Occupied => {
+                draw_rectangle_lines(wx, wy, CELL_SIZE, CELL_SIZE, 1.0, theme.cell_border)
+            }
+        }
+
+        // One-way tiles as arrows
+        if let Some(direction) = grid.one_way(&pos) {
+            let center = vec2(wx + CELL_SIZE / 2.0, wy + CELL_SIZE / 2.0);
+            let (dx, dy) = direction.offset();
+            let (ahead, side) = (vec2(dx as f32, dy as f32), vec2(-dy as f32, dx as f32));
+            let size = CELL_SIZE * 0.3;
+            let tail = center - ahead * size;
+            draw_triangle(center + ahead * size, tail + side * size, tail - side * size, theme.cell_border);
+        }
+    }
+
+    // Teleporters as paired rings. The fill is mixed with the cell color by
+    // hand: a translucent draw would leave the texture itself see-through.
+    let ring = Color::from_rgba(150, 70, 220, 255);
+    let fill = Color::from_vec(theme.cell.to_vec().lerp(ring.to_vec(), 120.0 / 255.0));
+    for (entrance, _) in grid.teleporters() {
+        let (x, y) = entrance.to_world();
+        let (center_x, center_y) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+        draw_circle(center_x, center_y, CELL_SIZE * 0.35, fill);
+        draw_circle_lines(center_x, center_y, CELL_SIZE * 0.35, 3.0, Color::from_rgba(190, 120, 255, 255));
+    }
+}
@@ -0,0 +1,151 @@
+use macroquad::prelude::*;
+
+pub const MIN_ZOOM: f32 = 0.25;
+pub const MAX_ZOOM: f32 = 3.0;
+
+/// World camera: which world point sits at the middle of the screen, and how
+/// many screen pixels one world unit covers. HUD drawing ignores the camera.
+/// With a `target`, world layers draw offscreen for post-processing.
+#[derive(Debug, Clone)]
+pub struct GameCamera {
+    pub center: Vec2,
+    pub zoom: f32,
+    pub target: Option<RenderTarget>,
+}
+
+impl GameCamera {
+    pub fn new(center: Vec2) -> Self {
+        GameCamera { center, zoom: 1.0, target: None }
+    }
+
+    /// World-space rectangle visible on a screen of the given size
+    pub fn visible_rect(&self, screen_w: f32, screen_h: f32) -> Rect {
+        let w = screen_w / self.zoom;
+        let h = screen_h / self.zoom;
+        Rect::new(self.center.x - w / 2.0, self.center.y - h / 2.0, w, h)
+    }
+
+    pub fn screen_to_world_in(&self, point: Vec2, screen_w: f32, screen_h: f32) -> Vec2 {
+        let rect = self.visible_rect(screen_w, screen_h);
+        vec2(rect.x + point.x / self.zoom, rect.y + point.y / self.zoom)
+    }
+
+    pub fn world_to_screen_in(&self, point: Vec2, screen_w: f32, screen_h: f32) -> Vec2 {
+        let rect = self.visible_rect(screen_w, screen_h);
+        vec2((point.x - rect.x) * self.zoom, (point.y - rect.y) * self.zoom)
+    }
+
+    /// Whether anything within `reach` of the world `point` shows on a screen
+    /// of the given size; world layers skip what it can't
+    pub fn can_see_in(&self, point: Vec2, reach: f32, screen_w: f32, screen_h: f32) -> bool {
+        let rect = self.visible_rect(screen_w, screen_h);
+        point.x + reach >= rect.left()
+            && point.x - reach <= rect.right()
+            && point.y + reach >= rect.top()
+            && point.y - reach <= rect.bottom()
+    }
+
+    pub fn can_see(&self, point: Vec2, reach: f32) -> bool {
+        self.can_see_in(point, reach, screen_width(), screen_height())
+    }
+
+    pub fn screen_to_world(&self, point: Vec2) -> Vec2 {
+        self.screen_to_world_in(point, screen_width(), screen_height())
+    }
+
+    pub fn world_to_screen(&self, point: Vec2) -> Vec2 {
+        self.world_to_screen_in(point, screen_width(), screen_height())
+    }
+
+    /// Mouse cursor in world coordinates
+    pub fn mouse_world(&self) -> Vec2 {
+        self.screen_to_world(mouse_position().into())
+    }
+
+    /// Move the view by a screen-space drag delta
+    pub fn pan_by_screen(&mut self, screen_delta: Vec2) {
+        self.center -= screen_delta / self.zoom;
+    }
+
+    /// Zoom by `factor`, keeping the world point under `screen_anchor` fixed
+    pub fn zoom_at(&mut self, factor: f32, screen_anchor: Vec2, screen_w: f32, screen_h: f32) {
+        let anchor_world = self.screen_to_world_in(screen_anchor, screen_w, screen_h);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let moved = self.screen_to_world_in(screen_anchor, screen_w, screen_h);
+        self.center += anchor_world - moved;
+    }
+
+    /// Zoom and center so `world` fills as much of the screen-space `viewport`
+    /// as possible while keeping its aspect ratio (letterboxed)
+    pub fn fit(&mut self, world: Rect, viewport: Rect, screen_w: f32, screen_h: f32) {
+        self.zoom = (viewport.w / world.w).min(viewport.h / world.h).clamp(MIN_ZOOM, MAX_ZOOM);
+        let viewport_offset = viewport.center() - vec2(screen_w, screen_h) / 2.0;
+        self.center = world.center() - viewport_offset / self.zoom;
+    }
+
+    pub fn camera2d(&self) -> Camera2D {
+        let mut camera = Camera2D::from_display_rect(self.visible_rect(screen_width(), screen_height()));
+        // from_display_rect is y-up when drawing to the screen; keep y-down like the default camera.
+        // Render targets come out y-down already.
+        match &self.target {
+            Some(target) => camera.render_target = Some(target.clone()),
+            None => camera.zoom.y = -camera.zoom.y,
+        }
+        camera
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screen_world_round_trip() {
+        let mut camera = GameCamera::new(vec2(400.0, 300.0));
+        camera.zoom = 2.0;
+        let screen = vec2(123.0, 456.0);
+        let world = camera.screen_to_world_in(screen, 800.0, 600.0);
+        let back = camera.world_to_screen_in(world, 800.0, 600.0);
+        assert!((back - screen).length() < 0.001);
+    }
+
+    #[test]
+    fn test_default_camera_matches_screen() {
+        let camera = GameCamera::new(vec2(400.0, 300.0));
+        let world = camera.screen_to_world_in(vec2(10.0, 20.0), 800.0, 600.0);
+        assert_eq!(world, vec2(10.0, 20.0));
+    }
+
+    #[test]
+    fn test_can_see_counts_reach() {
+        let mut camera = GameCamera::new(vec2(400.0, 300.0));
+        camera.zoom = 2.0; // Sees x 200..600, y 150..450
+        assert!(camera.can_see_in(vec2(210.0, 160.0), 0.0, 800.0, 600.0));
+        assert!(!camera.can_see_in(vec2(150.0, 300.0), 40.0, 800.0, 600.0));
+        assert!(camera.can_see_in(vec2(150.0, 300.0), 60.0, 800.0, 600.0)); // A range circle poking in
+        assert!(!camera.can_see_in(vec2(400.0, 500.0), 10.0, 800.0, 600.0));
+    }
+
+    #[test]
+    fn test_fit_centers_world_in_viewport() {
+        let mut camera = GameCamera::new(Vec2::ZERO);
+        let world = Rect::new(0.0, 0.0, 800.0, 600.0);
+        let viewport = Rect::new(0.0, 100.0, 1600.0, 600.0);
+        camera.fit(world, viewport, 1600.0, 800.0);
+
+        assert_eq!(camera.zoom, 1.0); // Height-limited
+        let top_left = camera.world_to_screen_in(vec2(0.0, 0.0), 1600.0, 800.0);
+        assert!((top_left - vec2(400.0, 100.0)).length() < 0.001);
+    }
+
+    #[test]
+    fn test_zoom_keeps_anchor_fixed() {
+        let mut camera = GameCamera::new(vec2(400.0, 300.0));
+        let anchor = vec2(200.0, 100.0);
+        let before = camera.screen_to_world_in(anchor, 800.0, 600.0);
+        camera.zoom_at(1.5, anchor, 800.0, 600.0);
+        let after = camera.screen_to_world_in(anchor, 800.0, 600.0);
+        assert!((before - after).length() < 0.001);
+        assert_eq!(camera.zoom, 1.5);
+    }
+}
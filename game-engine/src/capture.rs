@@ -0,0 +1,219 @@
+//! Screenshots and GIF clips for sharing. The Screenshot action (F12) saves
+//! the frame as a PNG in `captures/`, named by UTC time. Record Clip (R)
+//! starts keeping the last `CLIP_SECONDS` of frames, scaled down, in a ring
+//! buffer; pressing it again writes them out as a GIF on a background
+//! thread. Frames are read back from the screen after everything is drawn.
+//! GIF encoding needs the `capture` feature; web builds can't write files,
+//! so neither works there.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+use tracing::{info, warn};
+
+use crate::display::HudLayout;
+use crate::input::{Action, InputConfig};
+
+pub const CAPTURE_DIR: &str = "captures";
+const CLIP_SECONDS: f32 = 5.0;
+const CLIP_FPS: f32 = 12.0;
+const CLIP_WIDTH: u16 = 480; // Recorded frames are scaled down to at most this wide
+
+/// One recorded frame, top row first
+#[cfg_attr(not(feature = "capture"), allow(dead_code))]
+struct ClipFrame {
+    width: u16,
+    height: u16,
+    rgba: Vec<u8>,
+}
+
+pub struct Capture {
+    recording: bool,
+    frames: VecDeque<ClipFrame>,
+    since_frame: f32,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Capture { recording: false, frames: VecDeque::new(), since_frame: 0.0 }
+    }
+
+    /// Call once the frame is drawn, before `next_frame`
+    pub fn update(&mut self, input: &InputConfig, delta: f32) {
+        if input.pressed(Action::Screenshot) {
+            screenshot();
+        }
+        if input.pressed(Action::RecordClip) {
+            if self.recording {
+                self.save_clip();
+            } else if cfg!(all(feature = "capture", not(target_arch = "wasm32"))) {
+                self.recording = true;
+                self.since_frame = f32::INFINITY; // Grab this frame straight away
+            } else {
+                warn!("GIF clips need a native build with the `capture` feature");
+            }
+        }
+        if !self.recording {
+            return;
+        }
+        self.since_frame += delta;
+        if self.since_frame >= 1.0 / CLIP_FPS {
+            self.since_frame = 0.0;
+            self.frames.push_back(scale_frame(&get_screen_data(), CLIP_WIDTH));
+            if self.frames.len() > (CLIP_SECONDS * CLIP_FPS) as usize {
+                self.frames.pop_front();
+            }
+        }
+    }
+
+    /// A blinking "REC" while the recorder runs
+    pub fn render(&self, hud: &HudLayout) {
+        if !self.recording {
+            return;
+        }
+        set_camera(&hud.camera());
+        if get_time().fract() < 0.5 {
+            draw_circle(hud.width - 78.0, 28.0, 7.0, RED);
+        }
+        draw_text("REC", hud.width - 64.0, 35.0, 22.0, RED);
+        set_default_camera();
+    }
+
+    fn save_clip(&mut self) {
+        self.recording = false;
+        let frames: Vec<ClipFrame> = self.frames.drain(..).collect();
+        let Some(path) = capture_path("gif") else {
+            return;
+        };
+        #[cfg(feature = "capture")]
+        std::thread::spawn(move || match gif_encoder::write(&path, &frames) {
+            Ok(()) => info!(path = %path.display(), frames = frames.len(), "clip saved"),
+            Err(err) => warn!(path = %path.display(), %err, "clip not saved"),
+        });
+        #[cfg(not(feature = "capture"))]
+        let _ = (path, frames);
+    }
+}
+
+fn screenshot() {
+    let Some(path) = capture_path("png") else {
+        return;
+    };
+    get_screen_data().export_png(&path.to_string_lossy());
+    info!(path = %path.display(), "screenshot saved");
+}
+
+/// A fresh file name in the capture folder, which is created if needed
+fn capture_path(extension: &str) -> Option<PathBuf> {
+    if cfg!(target_arch = "wasm32") {
+        warn!("captures can't be saved in the browser");
+        return None;
+    }
+    if let Err(err) = std::fs::create_dir_all(CAPTURE_DIR) {
+        warn!(%err, "capture folder not created");
+        return None;
+    }
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let stem = format!("rust-rush-{}", timestamp(seconds));
+    // Two captures in one second get a counter
+    (0..100)
+        .map(|n| match n {
+            0 => PathBuf::from(CAPTURE_DIR).join(format!("{}.{}", stem, extension)),
+            n => PathBuf::from(CAPTURE_DIR).join(format!("{}-{}.{}", stem, n, extension)),
+        })
+        .find(|path| !path.exists())
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC for seconds since the Unix epoch
+fn timestamp(seconds: u64) -> String {
+    let [year, month, day, hour, minute, second] = utc_fields(seconds);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, hour, minute, second)
+}
+
+/// Year, month, day, hour, minute and second in UTC for seconds since the
+/// Unix epoch
+pub fn utc_fields(seconds: u64) -> [i64; 6] {
+    let (days, rest) = (seconds / 86_400, (seconds % 86_400) as i64);
+    // Days to a civil date (proleptic Gregorian)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    [year, month, day, rest / 3600, rest % 3600 / 60, rest % 60]
+}
+
+/// Flip a screen readback (bottom row first) upright and shrink it to at
+/// most `max_width`, nearest neighbour
+fn scale_frame(image: &Image, max_width: u16) -> ClipFrame {
+    let (source_width, source_height) = (image.width as usize, image.height as usize);
+    let step = source_width.div_ceil(max_width as usize).max(1);
+    let (width, height) = (source_width / step, source_height / step);
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        let row = source_height - 1 - y * step;
+        for x in 0..width {
+            let at = (row * source_width + x * step) * 4;
+            rgba.extend_from_slice(&image.bytes[at..at + 4]);
+        }
+    }
+    ClipFrame { width: width as u16, height: height as u16, rgba }
+}
+
+#[cfg(feature = "capture")]
+mod gif_encoder {
+    use std::fs::File;
+    use std::io::{self, BufWriter};
+    use std::path::Path;
+
+    use gif::{Encoder, Frame, Repeat};
+
+    use super::{ClipFrame, CLIP_FPS};
+
+    const QUANTIZE_SPEED: i32 = 10; // 1 is best quality, 30 fastest
+
+    pub fn write(path: &Path, frames: &[ClipFrame]) -> io::Result<()> {
+        let Some(first) = frames.first() else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no frames recorded"));
+        };
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = Encoder::new(file, first.width, first.height, &[]).map_err(io::Error::other)?;
+        encoder.set_repeat(Repeat::Infinite).map_err(io::Error::other)?;
+        // The window may have been resized mid-clip; keep to the first size
+        for clip_frame in frames.iter().filter(|frame| (frame.width, frame.height) == (first.width, first.height)) {
+            let mut rgba = clip_frame.rgba.clone();
+            let mut frame = Frame::from_rgba_speed(clip_frame.width, clip_frame.height, &mut rgba, QUANTIZE_SPEED);
+            frame.delay = (100.0 / CLIP_FPS).round() as u16; // Hundredths of a second
+            encoder.write_frame(&frame).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_is_utc_calendar_time() {
+        assert_eq!(timestamp(0), "19700101-000000");
+        assert_eq!(timestamp(1_792_158_330), "20261016-134530");
+        assert_eq!(timestamp(951_782_400), "20000229-000000"); // Leap day
+    }
+
+    #[test]
+    fn test_frames_are_flipped_and_scaled() {
+        // 4x2, each pixel's red channel is its index; the bottom row comes first
+        let image = Image { bytes: (0..8).flat_map(|n| [n, 0, 0, 255]).collect(), width: 4, height: 2 };
+        let frame = scale_frame(&image, 2);
+        assert_eq!((frame.width, frame.height), (2, 1));
+        assert_eq!(frame.rgba.iter().step_by(4).copied().collect::<Vec<_>>(), vec![4, 6]);
+    }
+}
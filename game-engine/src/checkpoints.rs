@@ -0,0 +1,77 @@
+//! The restart offer along the bottom of the screen after a defeat. A
+//! checkpoint is taken as each wave starts (see
+//! `rust_rush_core::checkpoints`); once the run is lost, Left/Right pick
+//! which wave to go back to, newest first, and the restart key plays on
+//! from there. Turning the Wave Checkpoints setting off drops the
+//! checkpoints taken so far and stops the offer; hardcore runs (see
+//! `saves`) never get one.
+
+use macroquad::prelude::*;
+use rust_rush_core::checkpoints::Checkpoints;
+use rust_rush_core::victory::Outcome;
+use rust_rush_core::Game;
+
+use crate::display::HudLayout;
+use crate::input::{Action, InputConfig};
+use crate::theme::Theme;
+
+pub struct CheckpointMenu {
+    checkpoints: Checkpoints,
+    back: usize, // Checkpoints back from the newest that a restart goes to
+}
+
+impl CheckpointMenu {
+    pub fn new() -> Self {
+        CheckpointMenu { checkpoints: Checkpoints::new(), back: 0 }
+    }
+
+    /// After each update: take a checkpoint if a wave just started
+    pub fn record(&mut self, game: &Game, enabled: bool) {
+        if enabled {
+            self.checkpoints.record(game);
+        } else {
+            self.checkpoints.clear();
+        }
+    }
+
+    /// The wave a restart would go back to, once the run is lost
+    fn offered(&self, game: &Game) -> Option<u32> {
+        if !matches!(game.state.objective.outcome, Some(Outcome::Lost(_))) {
+            return None;
+        }
+        let waves = self.checkpoints.waves();
+        waves.len().checked_sub(1 + self.back).map(|index| waves[index])
+    }
+
+    /// Pick a wave or restart. True if the input was for the menu.
+    pub fn handle_input(&mut self, game: &mut Game, input: &InputConfig) -> bool {
+        let Some(wave) = self.offered(game) else {
+            return false;
+        };
+        if is_key_pressed(KeyCode::Left) {
+            self.back = (self.back + 1).min(self.checkpoints.waves().len() - 1);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            self.back = self.back.saturating_sub(1);
+        }
+        if input.pressed(Action::RestartCheckpoint) && self.checkpoints.restart(wave, game).is_ok() {
+            self.back = 0;
+        }
+        true
+    }
+
+    pub fn render(&self, game: &Game, input: &InputConfig, hud: &HudLayout, theme: &Theme) {
+        let Some(wave) = self.offered(game) else {
+            return;
+        };
+        set_camera(&hud.camera());
+        let text = format!(
+            "[{}] Restart from wave {}   Left/Right: earlier or later wave",
+            input.label(Action::RestartCheckpoint),
+            wave
+        );
+        let width = measure_text(&text, None, 24, 1.0).width;
+        draw_text(&text, (hud.width - width) / 2.0, hud.height - 12.0, 24.0, theme.gold);
+        set_default_camera();
+    }
+}
@@ -0,0 +1,140 @@
+//! Launch options. Everything a tester or a balance script might want to
+//! set up without going through menus: the map, scenario switches,
+//! mutators, logging, and the headless modes that print a run report
+//! instead of opening a window. `--help` lists them all.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::logging::LogOptions;
+
+pub const MAX_SPEED: f32 = 10.0;
+
+#[derive(Debug, Clone, Default, PartialEq, Parser)]
+#[command(name = "rust-rush", about = "Rust Rush tower defense")]
+pub struct LaunchOptions {
+    /// Load the board from a map file (JSON, see `rust_rush_core::maps`)
+    #[arg(long, value_name = "FILE")]
+    pub map: Option<PathBuf>,
+    /// Seed for randomness: the simulation's (speed jitter in scripted
+    /// waves) and cosmetic (screen shake, particles)
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+    /// Run without a window and print a JSON run report
+    #[arg(long)]
+    pub headless: bool,
+    /// Play back a bot transcript (JSON lines of `BotRequest`s)
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["ai", "benchmark_waves"])]
+    pub replay: Option<PathBuf>,
+    /// Simulation speed multiplier, overriding the saved game speed
+    #[arg(long, value_name = "X", value_parser = parse_speed)]
+    pub speed: Option<f32>,
+    /// No sound effects this session
+    #[arg(long)]
+    pub mute: bool,
+    /// Let the AI play N waves headless, then report; exits with 1 if the
+    /// base falls first
+    #[arg(long, value_name = "N")]
+    pub benchmark_waves: Option<u32>,
+
+    /// Fog of war
+    #[arg(long)]
+    pub fog: bool,
+    /// Day/night cycle
+    #[arg(long)]
+    pub day_night: bool,
+    /// Changing weather
+    #[arg(long)]
+    pub weather: bool,
+    /// Rocks and trees to clear
+    #[arg(long)]
+    pub obstacles: bool,
+    /// Demo teleporters and one-way tiles
+    #[arg(long)]
+    pub portals: bool,
+    /// Demo gates and a patrol
+    #[arg(long)]
+    pub gates: bool,
+    /// Towers need generators to fire
+    #[arg(long)]
+    pub power: bool,
+    /// Towers run out of rounds and reload
+    #[arg(long)]
+    pub ammo: bool,
+    /// Towers may wall off the goal; enemies break through them
+    #[arg(long)]
+    pub siege: bool,
+    /// Friendly convoys drive the route backwards; enemies go after them
+    #[arg(long)]
+    pub convoys: bool,
+    /// Enemies route around the best-defended ground
+    #[arg(long)]
+    pub smart_enemies: bool,
+    /// The computer builds the defense
+    #[arg(long)]
+    pub ai: bool,
+    /// Guided first game
+    #[arg(long)]
+    pub tutorial: bool,
+    /// Free building and an enemy spawn panel
+    #[arg(long, conflicts_with = "mutators")]
+    pub sandbox: bool,
+    /// Send the waves yourself against a defense the computer builds
+    #[arg(long, conflicts_with_all = ["sandbox", "ai", "tutorial"])]
+    pub attack: bool,
+    /// One life: autosaved to a single slot that's deleted on defeat, no
+    /// checkpoints, and its own leaderboard; resumes the slot if there is one
+    #[arg(long, conflicts_with_all = ["sandbox", "attack", "replay"])]
+    pub hardcore: bool,
+    /// Write saves as sealed JSON rather than binary, to inspect them
+    #[arg(long)]
+    pub text_saves: bool,
+    /// Comma-separated mutators, e.g. `no-selling,half-gold`
+    #[arg(long, value_name = "LIST")]
+    pub mutators: Option<String>,
+    /// Rule script (Rhai)
+    #[arg(long, value_name = "FILE")]
+    pub script: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub log: LogOptions,
+}
+
+impl LaunchOptions {
+    /// Whether to skip the window; benchmarks always run headless
+    pub fn is_headless(&self) -> bool {
+        self.headless || self.benchmark_waves.is_some()
+    }
+}
+
+fn parse_speed(text: &str) -> Result<f32, String> {
+    let speed: f32 = text.parse().map_err(|_| format!("`{}` is not a number", text))?;
+    if speed > 0.0 && speed <= MAX_SPEED {
+        Ok(speed)
+    } else {
+        Err(format!("speed must be above 0 and at most {}", MAX_SPEED))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_launch_options() {
+        let options = LaunchOptions::parse_from([
+            "rust-rush", "--map", "maps/switchback.json", "--seed", "7", "--speed", "2.5", "--mute",
+            "--benchmark-waves", "10", "--fog", "--mutators=half-gold", "--log", "debug",
+        ]);
+        assert_eq!(options.map, Some(PathBuf::from("maps/switchback.json")));
+        assert_eq!((options.seed, options.speed, options.benchmark_waves), (Some(7), Some(2.5), Some(10)));
+        assert!(options.mute && options.fog && options.is_headless());
+        assert_eq!(options.mutators.as_deref(), Some("half-gold"));
+        assert_eq!(options.log.filter.as_deref(), Some("debug"));
+
+        assert!(LaunchOptions::try_parse_from(["rust-rush", "--speed", "0"]).is_err());
+        assert!(LaunchOptions::try_parse_from(["rust-rush", "--sandbox", "--mutators", "decay"]).is_err());
+        assert!(LaunchOptions::try_parse_from(["rust-rush", "--replay", "run.jsonl", "--ai"]).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! The codex screen (see `rust_rush_core::codex`): every tower type, then
+//! every enemy kind, with its stats as this run has them, mods included.
+//! Anything not yet seen in any run shows as "???" until one is on the
+//! board. Up/Down scroll; Esc or the codex key closes it.
+
+use macroquad::prelude::*;
+use rust_rush_core::codex::{enemy_entries, tower_entries, EnemyEntry, Subject, TowerEntry};
+use rust_rush_core::Game;
+
+use crate::display::HudLayout;
+use crate::theme::Theme;
+
+const PANEL_WIDTH: f32 = 620.0;
+const ROW_HEIGHT: f32 = 48.0;
+const TOP: f32 = 60.0;
+
+#[derive(Debug, Clone, Default)]
+pub struct CodexScreen {
+    pub open: bool,
+    first: usize, // Top row shown
+}
+
+impl CodexScreen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn handle_input(&mut self, game: &Game, hud: &HudLayout) {
+        let rows = tower_entries(&game.state).len() + enemy_entries(&game.state).len();
+        if is_key_pressed(KeyCode::Down) {
+            self.first = (self.first + 1).min(rows.saturating_sub(visible_rows(hud)));
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.first = self.first.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.toggle();
+        }
+    }
+
+    pub fn render(&self, game: &Game, hud: &HudLayout, theme: &Theme) {
+        if !self.open {
+            return;
+        }
+        let codex = &game.achievements.progress.codex;
+        let towers = tower_entries(&game.state).into_iter().map(|entry| {
+            let seen = codex.has_seen(Subject::Tower(entry.tower_type));
+            (seen, format!("{:?} Tower", entry.tower_type), tower_details(&entry))
+        });
+        let enemies = enemy_entries(&game.state).into_iter().map(|entry| {
+            let seen = codex.has_seen(Subject::Enemy(entry.kind));
+            (seen, entry.kind.name().to_string(), enemy_details(&entry))
+        });
+        let entries: Vec<_> = towers.chain(enemies).collect();
+
+        set_camera(&hud.camera());
+        let visible = visible_rows(hud);
+        let first = self.first.min(entries.len().saturating_sub(visible));
+        let rows = entries.len().min(visible);
+        let (x, height) = ((hud.width - PANEL_WIDTH) / 2.0, 64.0 + rows as f32 * ROW_HEIGHT);
+        draw_rectangle(x, TOP, PANEL_WIDTH, height, Color::new(0.0, 0.0, 0.0, 0.85));
+        draw_rectangle_lines(x, TOP, PANEL_WIDTH, height, 2.0, theme.cell_border);
+        let seen = entries.iter().filter(|(seen, ..)| *seen).count();
+        draw_text(format!("CODEX   {} / {} seen", seen, entries.len()), x + 12.0, TOP + 26.0, 26.0, theme.text);
+        draw_text("Up/Down: scroll   Esc: close", x + 12.0, TOP + 46.0, 14.0, GRAY);
+
+        for (row, (seen, name, details)) in entries.iter().skip(first).take(rows).enumerate() {
+            let y = TOP + 56.0 + row as f32 * ROW_HEIGHT;
+            if *seen {
+                draw_text(name, x + 16.0, y + 20.0, 22.0, theme.gold);
+                draw_text(details, x + 16.0, y + 40.0, 16.0, theme.text);
+            } else {
+                draw_text("???", x + 16.0, y + 20.0, 22.0, GRAY);
+                draw_text("Not encountered yet", x + 16.0, y + 40.0, 16.0, GRAY);
+            }
+        }
+        set_default_camera();
+    }
+}
+
+fn visible_rows(hud: &HudLayout) -> usize {
+    (((hud.height - TOP - 80.0) / ROW_HEIGHT) as usize).max(1)
+}
+
+/// Price and combat stats, then whatever sets the tower apart
+fn tower_details(entry: &TowerEntry) -> String {
+    let mut details = format!(
+        "${}   Damage {:.0}   Range {:.1}   {:.2} shots/s",
+        entry.cost, entry.damage, entry.range, entry.fire_rate
+    );
+    if entry.splash > 0.0 {
+        details += &format!("   Splash {:.1}", entry.splash);
+    }
+    if let Some((seconds, multiplier)) = entry.slow {
+        details += &format!("   Slows to {:.0}% for {:.1}s", multiplier * 100.0, seconds);
+    }
+    if entry.footprint > 1 {
+        details += &format!("   {0}x{0}", entry.footprint);
+    }
+    if let Some((a, b)) = entry.merged_from {
+        details += &format!("   Merge {:?} + {:?}", a, b);
+    }
+    details
+}
+
+fn enemy_details(entry: &EnemyEntry) -> String {
+    let mut details = format!("Health {}   Speed {:.1} cells/s", entry.health, entry.speed);
+    if entry.range > 0.0 {
+        details += &format!("   Shoots towers within {:.1}", entry.range);
+    }
+    format!("{}   From wave {}", details, entry.first_wave)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_rush_core::TowerType;
+
+    #[test]
+    fn test_details_name_merges_and_gunner_range() {
+        let game = Game::new();
+        let towers = tower_entries(&game.state);
+        let frost = towers.iter().find(|entry| entry.tower_type == TowerType::FrostBomb).unwrap();
+        assert!(tower_details(frost).ends_with("Merge Slow + Splash"));
+        let enemies = enemy_entries(&game.state);
+        assert!(!enemy_details(&enemies[0]).contains("Shoots"));
+        assert!(enemy_details(&enemies[1]).contains("Shoots towers"));
+    }
+}
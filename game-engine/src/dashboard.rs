@@ -0,0 +1,182 @@
+//! End-of-run stats screen: gold earned and spent over time, a DPS graph,
+//! damage by tower type and leaks per wave. Everything comes from the
+//! core's `RunStats` and is drawn with plain macroquad shapes. Opens by
+//! itself when the run is won or lost and can be toggled at any point.
+//! `export` writes the same data out for spreadsheets and scripts.
+
+use macroquad::prelude::*;
+use rust_rush_core::report::RunReport;
+use rust_rush_core::Game;
+use tracing::{info, warn};
+
+use crate::display::HudLayout;
+use crate::storage;
+use crate::theme::Theme;
+
+pub const REPORT_FILE: &str = "run_report.json";
+pub const WAVES_CSV_FILE: &str = "run_waves.csv";
+pub const SAMPLES_CSV_FILE: &str = "run_samples.csv";
+
+const PANEL_MARGIN: f32 = 40.0;
+const CHART_GAP: f32 = 30.0;
+
+/// One line on a chart: label, (x, y) points and colour
+type Series<'a> = (&'a str, &'a [(f32, f32)], Color);
+
+#[derive(Debug, Clone, Default)]
+pub struct Dashboard {
+    pub open: bool,
+    shown_at_end: bool, // Only pop up by itself once per run
+}
+
+impl Dashboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Open the screen when the run ends, and close it again if a
+    /// checkpoint restart takes the run back
+    pub fn update(&mut self, game: &Game) {
+        let over = game.state.health <= 0 || game.state.objective.is_decided();
+        if over && !self.shown_at_end {
+            self.shown_at_end = true;
+            self.open = true;
+        } else if !over && self.shown_at_end {
+            self.shown_at_end = false;
+            self.open = false;
+        }
+    }
+
+    pub fn render(&self, game: &Game, hud: &HudLayout, theme: &Theme) {
+        if !self.open {
+            return;
+        }
+        set_camera(&hud.camera());
+        let stats = &game.stats;
+        draw_rectangle(0.0, 0.0, hud.width, hud.height, Color::from_rgba(0, 0, 0, 220));
+
+        let minutes = (stats.elapsed / 60.0) as u32;
+        let summary = format!(
+            "RUN STATS   Time {}:{:02}   Wave {}   Kills {}   Leaks {}   Damage {}",
+            minutes,
+            stats.elapsed as u32 % 60,
+            game.state.waves.wave,
+            stats.kills,
+            stats.total_leaks,
+            stats.total_damage
+        );
+        draw_text(&summary, PANEL_MARGIN, PANEL_MARGIN, 26.0, theme.text);
+        if let Some(label) = unranked_label(game) {
+            let width = measure_text(&label, None, 20, 1.0).width;
+            draw_text(&label, hud.width - PANEL_MARGIN - width, PANEL_MARGIN, 20.0, theme.danger);
+        }
+
+        let top = PANEL_MARGIN + 30.0;
+        let width = (hud.width - PANEL_MARGIN * 2.0 - CHART_GAP) / 2.0;
+        let height = (hud.height - top - PANEL_MARGIN - CHART_GAP) / 2.0;
+        let left = PANEL_MARGIN;
+        let right = left + width + CHART_GAP;
+        let bottom = top + height + CHART_GAP;
+
+        let earned: Vec<(f32, f32)> = stats.samples.iter().map(|s| (s.time, s.gold_earned as f32)).collect();
+        let spent: Vec<(f32, f32)> = stats.samples.iter().map(|s| (s.time, s.gold_spent as f32)).collect();
+        line_chart(
+            Rect::new(left, top, width, height),
+            "Gold over time",
+            &[("earned", &earned, theme.gold), ("spent", &spent, theme.danger)],
+            theme,
+        );
+        line_chart(
+            Rect::new(right, top, width, height),
+            "Damage per second",
+            &[("dps", &stats.dps_series(), theme.health_high)],
+            theme,
+        );
+
+        let damage: Vec<(String, f32, Color)> = stats
+            .damage_by_tower
+            .iter()
+            .map(|(tower_type, amount)| (format!("{:?}", tower_type), *amount as f32, theme.tower_color(*tower_type)))
+            .collect();
+        bar_chart(Rect::new(left, bottom, width, height), "Damage by tower", &damage, theme);
+
+        let mut waves: Vec<(&u32, &u32)> = stats.leaks_by_wave.iter().collect();
+        waves.sort();
+        let leaks: Vec<(String, f32, Color)> =
+            waves.into_iter().map(|(wave, count)| (wave.to_string(), *count as f32, theme.danger)).collect();
+        bar_chart(Rect::new(right, bottom, width, height), "Leaks per wave", &leaks, theme);
+
+        set_default_camera();
+    }
+}
+
+/// "Unranked" and what marked the run, if anything did
+pub fn unranked_label(game: &Game) -> Option<String> {
+    let integrity = &game.state.run_integrity;
+    let reasons: Vec<&str> = integrity.taints().iter().map(|taint| taint.name()).collect();
+    (!integrity.is_clean()).then(|| format!("Unranked: {}", reasons.join(", ")))
+}
+
+/// Write the run so far as a JSON report plus per-wave and per-second CSVs
+pub fn export(game: &Game) {
+    let report = RunReport::from_game(game);
+    let json = match serde_json::to_string_pretty(&report) {
+        Ok(json) => json,
+        Err(err) => {
+            warn!(%err, "could not encode run report");
+            return;
+        }
+    };
+    for (key, contents) in [(REPORT_FILE, json), (WAVES_CSV_FILE, report.waves_csv()), (SAMPLES_CSV_FILE, report.samples_csv())] {
+        match storage::save(key, &contents) {
+            Ok(()) => info!(file = key, "run data exported"),
+            Err(err) => warn!(file = key, %err, "could not export run data"),
+        }
+    }
+}
+
+fn chart_frame(area: Rect, title: &str, max: f32, theme: &Theme) {
+    draw_rectangle_lines(area.x, area.y, area.w, area.h, 1.0, theme.cell_border);
+    draw_text(title, area.x + 6.0, area.y + 18.0, 20.0, theme.text);
+    draw_text(format!("{:.0}", max), area.x + area.w - 60.0, area.y + 18.0, 16.0, GRAY);
+}
+
+/// Several (x, y) series sharing one set of axes, each scaled to fit
+fn line_chart(area: Rect, title: &str, series: &[Series], theme: &Theme) {
+    let points = series.iter().flat_map(|(_, points, _)| points.iter());
+    let (max_x, max_y) = points.fold((f32::EPSILON, f32::EPSILON), |(mx, my), (x, y)| (mx.max(*x), my.max(*y)));
+    chart_frame(area, title, max_y, theme);
+
+    let plot = Rect::new(area.x + 6.0, area.y + 28.0, area.w - 12.0, area.h - 34.0);
+    let to_screen = |(x, y): (f32, f32)| vec2(plot.x + x / max_x * plot.w, plot.y + plot.h - y / max_y * plot.h);
+    for (i, (label, points, color)) in series.iter().enumerate() {
+        for pair in points.windows(2) {
+            let (a, b) = (to_screen(pair[0]), to_screen(pair[1]));
+            draw_line(a.x, a.y, b.x, b.y, 2.0, *color);
+        }
+        draw_text(label, area.x + 6.0 + i as f32 * 80.0, area.y + area.h - 6.0, 16.0, *color);
+    }
+}
+
+/// Labelled vertical bars, scaled to the tallest
+fn bar_chart(area: Rect, title: &str, bars: &[(String, f32, Color)], theme: &Theme) {
+    let max = bars.iter().map(|(_, value, _)| *value).fold(f32::EPSILON, f32::max);
+    chart_frame(area, title, max, theme);
+    if bars.is_empty() {
+        draw_text("nothing yet", area.x + 6.0, area.y + area.h / 2.0, 18.0, GRAY);
+        return;
+    }
+
+    let plot = Rect::new(area.x + 6.0, area.y + 28.0, area.w - 12.0, area.h - 50.0);
+    let slot = plot.w / bars.len() as f32;
+    for (i, (label, value, color)) in bars.iter().enumerate() {
+        let bar_height = value / max * plot.h;
+        let x = plot.x + i as f32 * slot;
+        draw_rectangle(x + slot * 0.15, plot.y + plot.h - bar_height, slot * 0.7, bar_height, *color);
+        draw_text(label, x + slot * 0.15, area.y + area.h - 6.0, 14.0, theme.text);
+    }
+}
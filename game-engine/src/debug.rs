@@ -0,0 +1,44 @@
+use macroquad::miniquad;
+use rust_rush_core::pathfinding::{self, PathCounters};
+
+const RATE_WINDOW: f64 = 1.0; // Seconds per pathfinding rate sample
+
+/// Wall-clock seconds; works on native and web without a graphics context
+pub fn now() -> f64 {
+    miniquad::date::now()
+}
+
+/// F3 overlay state: toggles plus the sampled pathfinding rates
+#[derive(Debug, Clone, Default)]
+pub struct DebugOverlay {
+    pub open: bool,
+    pub show_paths: bool,
+    pub show_ranges: bool,
+    pub show_targeting: bool, // Target lines and splash radii; works without the panel
+    window_elapsed: f64,
+    window_counters: PathCounters,
+    pub path_rate: PathCounters, // Per second, from the last full window
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold this frame's pathfinding work into the per-second rates
+    pub fn update(&mut self, delta: f32) {
+        let counters = pathfinding::take_counters();
+        self.window_counters.searches += counters.searches;
+        self.window_counters.path_nodes += counters.path_nodes;
+        self.window_elapsed += delta as f64;
+
+        if self.window_elapsed >= RATE_WINDOW {
+            self.path_rate = PathCounters {
+                searches: (self.window_counters.searches as f64 / self.window_elapsed).round() as u64,
+                path_nodes: (self.window_counters.path_nodes as f64 / self.window_elapsed).round() as u64,
+            };
+            self.window_counters = PathCounters::default();
+            self.window_elapsed = 0.0;
+        }
+    }
+}
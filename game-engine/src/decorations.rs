@@ -0,0 +1,41 @@
+//! Map props: scenery drawn from shapes, anchored at the point where each
+//! one meets the ground. `render_world` draws them in `y` order among the
+//! enemies, so a tree lower on the board hides an enemy walking behind it.
+
+use macroquad::prelude::*;
+use rust_rush_core::maps::{Prop, PropKind};
+use rust_rush_core::CELL_SIZE;
+
+/// How far a prop reaches from its anchor, for culling
+pub const PROP_REACH: f32 = CELL_SIZE * 1.5;
+
+pub fn draw_prop(prop: &Prop) {
+    let (x, y) = (prop.x, prop.y);
+    match prop.kind {
+        PropKind::Tree => {
+            draw_rectangle(x - 3.0, y - CELL_SIZE * 0.35, 6.0, CELL_SIZE * 0.35, Color::from_rgba(100, 70, 40, 255));
+            draw_circle(x, y - CELL_SIZE * 0.55, CELL_SIZE * 0.3, Color::from_rgba(35, 105, 45, 255));
+            draw_circle(x - 6.0, y - CELL_SIZE * 0.45, CELL_SIZE * 0.2, Color::from_rgba(45, 125, 55, 255));
+        }
+        PropKind::Rock => {
+            let radius = CELL_SIZE * 0.2;
+            draw_circle(x, y - radius, radius, Color::from_rgba(130, 130, 135, 255));
+            draw_circle_lines(x, y - radius, radius, 1.5, Color::from_rgba(80, 80, 85, 255));
+        }
+        PropKind::Sign => {
+            let board = Rect::new(x - CELL_SIZE * 0.35, y - CELL_SIZE * 0.7, CELL_SIZE * 0.7, CELL_SIZE * 0.35);
+            draw_rectangle(x - 2.0, board.bottom(), 4.0, y - board.bottom(), Color::from_rgba(100, 70, 40, 255));
+            draw_rectangle(board.x, board.y, board.w, board.h, Color::from_rgba(170, 130, 80, 255));
+            if !prop.label.is_empty() {
+                let size = measure_text(&prop.label, None, 16, 1.0);
+                draw_text(&prop.label, x - size.width / 2.0, board.y - 4.0, 16.0, WHITE);
+            }
+        }
+        PropKind::Flowers => {
+            for (dx, color) in [(-6.0, PINK), (0.0, YELLOW), (6.0, VIOLET)] {
+                draw_line(x + dx, y, x + dx, y - 8.0, 1.5, DARKGREEN);
+                draw_circle(x + dx, y - 9.0, 3.0, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::juice::JuiceSettings;
+use crate::postfx::PostFxSettings;
+use crate::storage;
+use crate::theme::Palette;
+
+pub const DISPLAY_SETTINGS_FILE: &str = "display.json";
+
+const MIN_UI_SCALE: f32 = 0.75;
+const MAX_UI_SCALE: f32 = 2.0;
+const UI_SCALE_STEP: f32 = 0.25;
+
+// HUD bands, in UI units, kept clear of the play area
+const HUD_TOP: f32 = 80.0;
+const HUD_BOTTOM: f32 = 68.0;
+const PLAY_AREA_MARGIN: f32 = 8.0;
+
+/// Player-facing window options, part of the saved `Settings`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DisplaySettings {
+    pub ui_scale: f32, // Multiplier on top of the platform's DPI scale
+    pub fullscreen: bool,
+    #[serde(default)]
+    pub palette: Palette,
+    #[serde(default)]
+    pub juice: JuiceSettings,
+    #[serde(default)]
+    pub post: PostFxSettings,
+}
+
+impl DisplaySettings {
+    pub fn new() -> Self {
+        DisplaySettings {
+            ui_scale: 1.0,
+            fullscreen: false,
+            palette: Palette::Standard,
+            juice: JuiceSettings::default(),
+            post: PostFxSettings::default(),
+        }
+    }
+
+    /// Final HUD scale: the user's setting times the screen's DPI scale
+    pub fn effective_ui_scale(&self) -> f32 {
+        self.ui_scale * screen_dpi_scale().max(1.0)
+    }
+
+    pub fn step_ui_scale(&mut self, steps: i32) {
+        self.ui_scale = (self.ui_scale + steps as f32 * UI_SCALE_STEP).clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    }
+
+    pub fn toggle_fullscreen(&mut self) {
+        self.fullscreen = !self.fullscreen;
+        set_fullscreen(self.fullscreen);
+    }
+
+    /// The display file from before `settings.json`
+    pub fn load() -> Self {
+        storage::load(DISPLAY_SETTINGS_FILE)
+            .and_then(|json| serde_json::from_str::<DisplaySettings>(&json).ok())
+            .map(|mut settings| {
+                settings.ui_scale = settings.ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+                settings
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Screen layout for one frame. HUD code draws in UI units (screen pixels
+/// divided by `scale`) and anchors to `width`/`height` instead of assuming
+/// a fixed window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HudLayout {
+    pub scale: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl HudLayout {
+    pub fn new(screen_w: f32, screen_h: f32, scale: f32) -> Self {
+        HudLayout {
+            scale,
+            width: screen_w / scale,
+            height: screen_h / scale,
+        }
+    }
+
+    pub fn current(settings: &DisplaySettings) -> Self {
+        Self::new(screen_width(), screen_height(), settings.effective_ui_scale())
+    }
+
+    /// Screen-space rectangle left for the board between the HUD bands
+    pub fn play_area(&self) -> Rect {
+        let top = (HUD_TOP + PLAY_AREA_MARGIN) * self.scale;
+        let bottom = (HUD_BOTTOM + PLAY_AREA_MARGIN) * self.scale;
+        let side = PLAY_AREA_MARGIN * self.scale;
+        let screen_w = self.width * self.scale;
+        let screen_h = self.height * self.scale;
+        Rect::new(side, top, (screen_w - 2.0 * side).max(1.0), (screen_h - top - bottom).max(1.0))
+    }
+
+    /// Camera for HUD drawing: one unit is `scale` screen pixels
+    pub fn camera(&self) -> Camera2D {
+        let mut camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, self.width, self.height));
+        camera.zoom.y = -camera.zoom.y;
+        camera
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_area_clears_hud_bands() {
+        let layout = HudLayout::new(800.0, 600.0, 1.0);
+        let area = layout.play_area();
+        assert_eq!(area.y, HUD_TOP + PLAY_AREA_MARGIN);
+        assert_eq!(area.bottom(), 600.0 - HUD_BOTTOM - PLAY_AREA_MARGIN);
+
+        // Bands grow with the UI scale
+        let scaled = HudLayout::new(800.0, 600.0, 2.0);
+        assert!(scaled.play_area().h < area.h);
+        assert_eq!(scaled.width, 400.0);
+    }
+
+    #[test]
+    fn test_ui_scale_is_clamped() {
+        let mut settings = DisplaySettings::new();
+        settings.step_ui_scale(100);
+        assert_eq!(settings.ui_scale, MAX_UI_SCALE);
+        settings.step_ui_scale(-100);
+        assert_eq!(settings.ui_scale, MIN_UI_SCALE);
+    }
+}
@@ -0,0 +1,51 @@
+//! Live DPS meter: one bar per tower type for its damage per second over
+//! the last ten seconds (`RunStats::rolling_dps`), so players can see
+//! which towers are pulling their weight mid-wave. Sits on the left under
+//! the HUD text and hides itself when nothing has hit lately.
+
+use macroquad::prelude::*;
+use rust_rush_core::stats::DPS_WINDOW;
+use rust_rush_core::Game;
+
+use crate::display::HudLayout;
+use crate::theme::Theme;
+
+const PANEL_WIDTH: f32 = 210.0;
+const ROW_HEIGHT: f32 = 18.0;
+const TOP: f32 = 110.0;
+
+pub struct DpsMeter {
+    pub visible: bool,
+}
+
+impl DpsMeter {
+    pub fn new() -> Self {
+        DpsMeter { visible: true }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn render(&self, game: &Game, hud: &HudLayout, theme: &Theme) {
+        let dps = game.stats.rolling_dps();
+        if !self.visible || dps.is_empty() {
+            return;
+        }
+        set_camera(&hud.camera());
+        let height = 28.0 + dps.len() as f32 * ROW_HEIGHT;
+        draw_rectangle(10.0, TOP, PANEL_WIDTH, height, Color::new(0.0, 0.0, 0.0, 0.6));
+        let total: f32 = dps.iter().map(|(_, value)| value).sum();
+        draw_text(format!("DPS ({:.0}s)  {:.0}", DPS_WINDOW, total), 18.0, TOP + 18.0, 18.0, theme.text);
+
+        let best = dps[0].1.max(f32::EPSILON);
+        for (row, (tower_type, value)) in dps.iter().enumerate() {
+            let y = TOP + 26.0 + row as f32 * ROW_HEIGHT;
+            let bar = (PANEL_WIDTH - 110.0) * value / best;
+            draw_rectangle(90.0, y + 3.0, bar, ROW_HEIGHT - 6.0, theme.tower_color(*tower_type));
+            draw_text(format!("{:?}", tower_type), 18.0, y + 13.0, 16.0, theme.text);
+            draw_text(format!("{:.0}", value), 94.0 + bar, y + 13.0, 16.0, theme.text);
+        }
+        set_default_camera();
+    }
+}
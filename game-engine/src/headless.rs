@@ -0,0 +1,64 @@
+//! Windowless runs for scripts and CI. The game steps at the bot tick rate
+//! as fast as the machine allows, driven by a `--replay` transcript or the
+//! AI (`--ai`, and always for `--benchmark-waves`), until the run is won
+//! or lost, the benchmark's waves are cleared or `MAX_TICKS` pass. The run report
+//! goes to stdout as JSON; logs stay on stderr.
+
+use std::time::Instant;
+
+use rust_rush_core::ai::AiPlayer;
+use rust_rush_core::bot::TICK;
+use rust_rush_core::report::{RunReport, RunSummary};
+use rust_rush_core::Game;
+use serde::Serialize;
+use tracing::info;
+
+use crate::cli::LaunchOptions;
+use crate::transcript::Transcript;
+
+pub const MAX_TICKS: u64 = 30 * 60 * 60; // An hour of game time
+
+#[derive(Debug, Serialize)]
+struct HeadlessReport {
+    summary: RunSummary,
+    waves_cleared: u32,
+    wall_seconds: f64,
+    ticks_per_second: f64,
+}
+
+/// Play the run out and print the report. Returns the process exit code:
+/// 1 when a benchmark's base falls before its waves are cleared.
+pub fn run(mut game: Game, transcript: Option<Transcript>, options: &LaunchOptions, ai_budget: i32) -> i32 {
+    let cleared = |game: &Game| game.state.waves.wave - 1;
+    let started = Instant::now();
+    if let Some(mut transcript) = transcript {
+        transcript.run_to_end(&mut game);
+    } else {
+        let mut ai = (options.ai || options.benchmark_waves.is_some()).then(|| {
+            let mut ai = AiPlayer::new(ai_budget);
+            ai.call_waves = true; // Nobody is watching the countdown
+            ai
+        });
+        let waves = options.benchmark_waves.unwrap_or(u32::MAX);
+        let playing = |game: &Game| game.state.health > 0 && !game.state.objective.is_decided();
+        while playing(&game) && cleared(&game) < waves && game.tick < MAX_TICKS {
+            if let Some(ai) = &mut ai {
+                ai.act(&mut game);
+            }
+            game.update(TICK);
+        }
+    }
+
+    let wall_seconds = started.elapsed().as_secs_f64();
+    let report = HeadlessReport {
+        summary: RunReport::from_game(&game).summary,
+        waves_cleared: cleared(&game),
+        wall_seconds,
+        ticks_per_second: game.tick as f64 / wall_seconds.max(f64::EPSILON),
+    };
+    info!(ticks = game.tick, wall_seconds, "headless run finished");
+    println!("{}", serde_json::to_string_pretty(&report).expect("report serializes"));
+
+    let fell_short = options.benchmark_waves.is_some_and(|waves| report.waves_cleared < waves);
+    i32::from(fell_short)
+}
@@ -0,0 +1,80 @@
+//! Maze value overlay: tints every open cell from red (no tower damage
+//! reaches it) to green (the best-covered cell), and prints the DPS on the
+//! cells of the current spawn-to-goal route. Coverage and route are kept
+//! between frames and recomputed only when the coverage fingerprint
+//! changes.
+
+use macroquad::prelude::*;
+use rust_rush_core::coverage::Coverage;
+use rust_rush_core::{Game, Position, CELL_SIZE};
+
+use crate::camera::GameCamera;
+
+pub struct Heatmap {
+    pub visible: bool,
+    coverage: Coverage,
+    route: Vec<Position>,
+    built_for: Option<u64>, // Fingerprint of `coverage` and `route`
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Heatmap { visible: false, coverage: Coverage::default(), route: Vec::new(), built_for: None }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn render(&mut self, game: &Game, camera: &GameCamera) {
+        if !self.visible {
+            return;
+        }
+        let fingerprint = Coverage::fingerprint(&game.state);
+        if self.built_for != Some(fingerprint) {
+            self.coverage = Coverage::compute(&game.state);
+            self.route = game.state.lane_routes().concat();
+            self.built_for = Some(fingerprint);
+        }
+
+        set_camera(&camera.camera2d());
+        let grid = &game.state.grid;
+        let max = self.coverage.max().max(f32::EPSILON);
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let cell = Position::new(x, y);
+                if !grid.is_walkable(&cell) {
+                    continue;
+                }
+                let dps = self.coverage.dps_at(cell);
+                let (wx, wy) = cell.to_world();
+                draw_rectangle(wx, wy, CELL_SIZE, CELL_SIZE, heat_color(dps / max));
+            }
+        }
+        // Numbers only along the route, where they matter
+        for cell in &self.route {
+            let (wx, wy) = cell.to_world();
+            draw_text(format!("{:.0}", self.coverage.dps_at(*cell)), wx + 4.0, wy + CELL_SIZE - 6.0, 14.0, WHITE);
+        }
+        set_default_camera();
+    }
+}
+
+/// Red at 0 through yellow to green at 1, translucent over the board
+fn heat_color(value: f32) -> Color {
+    let value = value.clamp(0.0, 1.0);
+    Color::new((2.0 - 2.0 * value).min(1.0), (2.0 * value).min(1.0), 0.1, 0.35)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heat_runs_red_to_green() {
+        assert_eq!(heat_color(0.0).r, 1.0);
+        assert_eq!(heat_color(0.0).g, 0.0);
+        assert_eq!(heat_color(0.5).r, heat_color(0.5).g);
+        assert_eq!(heat_color(2.0), heat_color(1.0));
+    }
+}
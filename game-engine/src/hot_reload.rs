@@ -0,0 +1,134 @@
+//! Hot reload for debug builds: watches the mods folder and the `--map`
+//! file, and when either changes reloads the mod content (tower stats,
+//! enemy overrides, effect stacking, rule script) or the map's wave script
+//! into the running game. Towers already built pick up the new stats, so
+//! tuning doesn't mean replaying to the wave under test. Nothing else on
+//! the map is reloaded; the board stays as it is. Either reload leaves the
+//! run unranked.
+//!
+//! The assets folder is watched too. Art is reloaded once its files have
+//! been quiet for `ASSET_SETTLE`, since image editors and exporters often
+//! write a file in several goes; `assets` keeps the old version of anything
+//! that still fails to load.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use rust_rush_core::integrity::Taint;
+use rust_rush_core::maps::MapFile;
+use rust_rush_core::Game;
+use tracing::{info, warn};
+
+use crate::assets;
+use crate::mods::{self, ModError};
+
+const ASSET_SETTLE: Duration = Duration::from_millis(300);
+
+pub struct HotReload {
+    _watcher: RecommendedWatcher, // Stops watching when dropped
+    changes: Receiver<notify::Result<Event>>,
+    mods_dir: PathBuf,
+    map: Option<PathBuf>,
+    assets_dir: PathBuf,
+    assets_changed_at: Option<Instant>, // Latest change not yet reloaded
+}
+
+impl HotReload {
+    /// Start watching, in debug builds only. None if nothing could be
+    /// watched.
+    pub fn start(map: Option<&Path>) -> Option<Self> {
+        if !cfg!(debug_assertions) {
+            return None;
+        }
+        let (sender, changes) = channel();
+        let mut watcher = notify::recommended_watcher(sender)
+            .map_err(|err| warn!(%err, "hot reload unavailable"))
+            .ok()?;
+        let [mods_dir, assets_dir] = [mods::MODS_DIR, assets::ASSETS_DIR].map(|dir| {
+            let dir = PathBuf::from(dir);
+            dir.canonicalize().unwrap_or(dir)
+        });
+        let mut watching = false;
+        for dir in [&mods_dir, &assets_dir].into_iter().filter(|dir| dir.is_dir()) {
+            watching |= watch(&mut watcher, dir, RecursiveMode::Recursive);
+        }
+        // Editors often save by replacing the file, so watch its folder
+        let map = map.map(|path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        if let Some(folder) = map.as_deref().and_then(Path::parent) {
+            watching |= watch(&mut watcher, folder, RecursiveMode::NonRecursive);
+        }
+        info!(watching, "hot reload started");
+        watching.then_some(HotReload { _watcher: watcher, changes, mods_dir, map, assets_dir, assets_changed_at: None })
+    }
+
+    /// Reload whatever changed since the last call. Returns the mod errors
+    /// from a mod reload, if there was one.
+    pub fn poll(&mut self, game: &mut Game) -> Option<Vec<ModError>> {
+        let (mut mods_changed, mut map_changed) = (false, false);
+        for event in self.changes.try_iter() {
+            let event = match event {
+                Ok(event) if !event.kind.is_access() => event,
+                Ok(_) => continue,
+                Err(err) => {
+                    warn!(%err, "file watch error");
+                    continue;
+                }
+            };
+            for path in &event.paths {
+                mods_changed |= path.starts_with(&self.mods_dir);
+                map_changed |= self.map.as_deref() == Some(path.as_path());
+                if path.starts_with(&self.assets_dir) {
+                    self.assets_changed_at = Some(Instant::now());
+                }
+            }
+        }
+
+        if map_changed {
+            self.reload_waves(game);
+        }
+        mods_changed.then(|| {
+            info!("mods changed; reloading");
+            game.state.taint(Taint::ContentReload);
+            mods::load_into(game, &self.mods_dir)
+        })
+    }
+
+    /// True, once, when asset files changed and have since been quiet long
+    /// enough to reload
+    pub fn assets_settled(&mut self) -> bool {
+        let settled = self.assets_changed_at.is_some_and(|at| at.elapsed() >= ASSET_SETTLE);
+        if settled {
+            self.assets_changed_at = None;
+        }
+        settled
+    }
+
+    fn reload_waves(&self, game: &mut Game) {
+        let Some(path) = &self.map else {
+            return;
+        };
+        if game.state.rules.attack {
+            return; // The attacker writes the wave script there
+        }
+        let map = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| serde_json::from_str::<MapFile>(&text).map_err(|err| err.to_string()));
+        match map {
+            Ok(map) => {
+                info!(path = %path.display(), plans = map.waves.len(), "wave script reloaded");
+                game.state.waves.plans = map.waves;
+                game.state.taint(Taint::ContentReload);
+            }
+            Err(err) => warn!(path = %path.display(), %err, "map not reloaded"),
+        }
+    }
+}
+
+fn watch(watcher: &mut RecommendedWatcher, path: &Path, mode: RecursiveMode) -> bool {
+    watcher
+        .watch(path, mode)
+        .map_err(|err| warn!(path = %path.display(), %err, "cannot watch for changes"))
+        .is_ok()
+}
@@ -0,0 +1,821 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use rust_rush_core::abilities::AbilityType;
+use rust_rush_core::painting::StrokeShape;
+use rust_rush_core::protocol::Command;
+use rust_rush_core::{BuildTool, EnemyId, Game, CELL_SIZE, ENEMY_RADIUS};
+use serde::{Deserialize, Serialize};
+
+use crate::camera::GameCamera;
+use crate::debug::DebugOverlay;
+use crate::notifications::Notifications;
+use crate::save_settings;
+use crate::settings::{Setting, Settings};
+use crate::storage;
+use crate::touch::{Gesture, RadialMenu};
+
+pub const INPUT_CONFIG_FILE: &str = "keybindings.json";
+
+/// Everything the player can do, independent of which key triggers it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Pause,
+    PlaceTower,
+    MoveHero,
+    SellTower,
+    SpawnEnemy,
+    CallWave,
+    CastMeteor,
+    CastFreeze,
+    CastRepair,
+    Cancel,
+    BuildSlot1,
+    BuildSlot2,
+    BuildSlot3,
+    BuildSlot4,
+    BuildSlot5,
+    BuildSlot6,
+    BuildSlot7,
+    BuildSlot8,
+    BuildSlot9,
+    BuildSlot10,
+    OpenSettings,
+    ToggleFullscreen,
+    UiScaleUp,
+    UiScaleDown,
+    ResetView,
+    CyclePalette,
+    ToggleDebug,
+    DebugPaths,
+    DebugRanges,
+    DebugTargeting,
+    Spectate,
+    InstantReplay,
+    RunStats,
+    ExportRun,
+    ToggleJuice,
+    ToggleMinimap,
+    UndoClear,
+    ToggleHeatmap,
+    ClearWave,
+    PaintIgnore,
+    BuildGenerator,
+    ToggleTower,
+    RepairTower,
+    ToggleDpsMeter,
+    Screenshot,
+    RecordClip,
+    DismissReport,
+    RestartCheckpoint,
+    OpenSaves,
+    SavePreset,
+    SelectTowers,
+    CopyTowers,
+    PasteTowers,
+    MirrorPaste,
+    UpgradeGroup,
+    PaintRectangle,
+    OpenCodex,
+}
+
+impl Action {
+    pub const ALL: [Action; 57] = [
+        Action::Pause,
+        Action::PlaceTower,
+        Action::MoveHero,
+        Action::SellTower,
+        Action::SpawnEnemy,
+        Action::CallWave,
+        Action::CastMeteor,
+        Action::CastFreeze,
+        Action::CastRepair,
+        Action::Cancel,
+        Action::BuildSlot1,
+        Action::BuildSlot2,
+        Action::BuildSlot3,
+        Action::BuildSlot4,
+        Action::BuildSlot5,
+        Action::BuildSlot6,
+        Action::BuildSlot7,
+        Action::BuildSlot8,
+        Action::BuildSlot9,
+        Action::BuildSlot10,
+        Action::OpenSettings,
+        Action::ToggleFullscreen,
+        Action::UiScaleUp,
+        Action::UiScaleDown,
+        Action::ResetView,
+        Action::CyclePalette,
+        Action::ToggleDebug,
+        Action::DebugPaths,
+        Action::DebugRanges,
+        Action::DebugTargeting,
+        Action::Spectate,
+        Action::InstantReplay,
+        Action::RunStats,
+        Action::ExportRun,
+        Action::ToggleJuice,
+        Action::ToggleMinimap,
+        Action::UndoClear,
+        Action::ToggleHeatmap,
+        Action::ClearWave,
+        Action::PaintIgnore,
+        Action::BuildGenerator,
+        Action::ToggleTower,
+        Action::RepairTower,
+        Action::ToggleDpsMeter,
+        Action::Screenshot,
+        Action::RecordClip,
+        Action::DismissReport,
+        Action::RestartCheckpoint,
+        Action::OpenSaves,
+        Action::SavePreset,
+        Action::SelectTowers,
+        Action::CopyTowers,
+        Action::PasteTowers,
+        Action::MirrorPaste,
+        Action::UpgradeGroup,
+        Action::PaintRectangle,
+        Action::OpenCodex,
+    ];
+
+    pub const BUILD_SLOTS: [Action; 10] = [
+        Action::BuildSlot1,
+        Action::BuildSlot2,
+        Action::BuildSlot3,
+        Action::BuildSlot4,
+        Action::BuildSlot5,
+        Action::BuildSlot6,
+        Action::BuildSlot7,
+        Action::BuildSlot8,
+        Action::BuildSlot9,
+        Action::BuildSlot10,
+    ];
+
+    /// The action that casts an ability
+    pub fn cast(ability_type: AbilityType) -> Action {
+        match ability_type {
+            AbilityType::MeteorStrike => Action::CastMeteor,
+            AbilityType::GlobalFreeze => Action::CastFreeze,
+            AbilityType::EmergencyRepair => Action::CastRepair,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Pause => "Pause",
+            Action::PlaceTower => "Place / Select",
+            Action::MoveHero => "Move Hero",
+            Action::SellTower => "Sell Tower",
+            Action::SpawnEnemy => "Spawn Enemy",
+            Action::CallWave => "Call Wave Early",
+            Action::CastMeteor => "Cast Meteor",
+            Action::CastFreeze => "Cast Freeze",
+            Action::CastRepair => "Cast Repair",
+            Action::Cancel => "Cancel",
+            Action::BuildSlot1 => "Build Slot 1",
+            Action::BuildSlot2 => "Build Slot 2",
+            Action::BuildSlot3 => "Build Slot 3",
+            Action::BuildSlot4 => "Build Slot 4",
+            Action::BuildSlot5 => "Build Slot 5",
+            Action::BuildSlot6 => "Build Slot 6",
+            Action::BuildSlot7 => "Build Slot 7",
+            Action::BuildSlot8 => "Build Slot 8",
+            Action::BuildSlot9 => "Build Slot 9",
+            Action::BuildSlot10 => "Build Slot 10",
+            Action::OpenSettings => "Settings",
+            Action::ToggleFullscreen => "Toggle Fullscreen",
+            Action::UiScaleUp => "UI Scale Up",
+            Action::UiScaleDown => "UI Scale Down",
+            Action::ResetView => "Reset View",
+            Action::CyclePalette => "Color Palette",
+            Action::ToggleDebug => "Debug Overlay",
+            Action::DebugPaths => "Debug: Paths",
+            Action::DebugRanges => "Debug: Ranges",
+            Action::DebugTargeting => "Debug: Targeting",
+            Action::Spectate => "Spectator Mode",
+            Action::InstantReplay => "Instant Replay",
+            Action::RunStats => "Run Stats",
+            Action::ExportRun => "Export Run Data",
+            Action::ToggleJuice => "Calm Presentation",
+            Action::ToggleMinimap => "Minimap",
+            Action::UndoClear => "Undo Clear",
+            Action::ToggleHeatmap => "Coverage Heatmap",
+            Action::ClearWave => "Clear Wave (Sandbox)",
+            Action::PaintIgnore => "Paint Ignore Zone (hold)",
+            Action::BuildGenerator => "Build Generator",
+            Action::ToggleTower => "Switch Tower On/Off",
+            Action::RepairTower => "Repair Tower",
+            Action::ToggleDpsMeter => "DPS Meter",
+            Action::Screenshot => "Screenshot",
+            Action::RecordClip => "Record Clip",
+            Action::DismissReport => "Dismiss Wave Report",
+            Action::RestartCheckpoint => "Restart From Checkpoint",
+            Action::OpenSaves => "Save Slots",
+            Action::SavePreset => "Save Preset",
+            Action::SelectTowers => "Box Select (hold)",
+            Action::CopyTowers => "Copy Towers",
+            Action::PasteTowers => "Paste Towers",
+            Action::MirrorPaste => "Mirror Paste",
+            Action::UpgradeGroup => "Merge Selected",
+            Action::PaintRectangle => "Build Rectangle (hold)",
+            Action::OpenCodex => "Codex",
+        }
+    }
+}
+
+/// A physical input an action can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// Keys offered for rebinding (and understood in the settings file)
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    KeyCode::Space, KeyCode::Escape, KeyCode::Enter, KeyCode::Tab, KeyCode::Backspace,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::LeftShift, KeyCode::LeftControl, KeyCode::LeftAlt,
+    KeyCode::Equal, KeyCode::Minus, KeyCode::Home, KeyCode::End,
+];
+
+const BINDABLE_BUTTONS: &[MouseButton] = &[MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+impl Binding {
+    pub fn is_pressed(&self) -> bool {
+        match self {
+            Binding::Key(key) => is_key_pressed(*key),
+            Binding::Mouse(button) => is_mouse_button_pressed(*button),
+        }
+    }
+
+    pub fn is_down(&self) -> bool {
+        match self {
+            Binding::Key(key) => is_key_down(*key),
+            Binding::Mouse(button) => is_mouse_button_down(*button),
+        }
+    }
+
+    pub fn is_released(&self) -> bool {
+        match self {
+            Binding::Key(key) => is_key_released(*key),
+            Binding::Mouse(button) => is_mouse_button_released(*button),
+        }
+    }
+
+    /// Settings-file representation, e.g. "Key:Space" or "Mouse:Left"
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("Key:{:?}", key),
+            Binding::Mouse(button) => format!("Mouse:{:?}", button),
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Binding> {
+        let (kind, value) = name.split_once(':')?;
+        match kind {
+            "Key" => BINDABLE_KEYS
+                .iter()
+                .find(|key| format!("{:?}", key) == value)
+                .map(|key| Binding::Key(*key)),
+            "Mouse" => BINDABLE_BUTTONS
+                .iter()
+                .find(|button| format!("{:?}", button) == value)
+                .map(|button| Binding::Mouse(*button)),
+            _ => None,
+        }
+    }
+
+    /// Whatever bindable key or button went down this frame
+    pub fn any_pressed() -> Option<Binding> {
+        BINDABLE_KEYS
+            .iter()
+            .find(|key| is_key_pressed(**key))
+            .map(|key| Binding::Key(*key))
+            .or_else(|| {
+                BINDABLE_BUTTONS
+                    .iter()
+                    .find(|button| is_mouse_button_pressed(**button))
+                    .map(|button| Binding::Mouse(*button))
+            })
+    }
+}
+
+/// Action → binding table, persisted as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "HashMap<String, String>", into = "HashMap<String, String>")]
+pub struct InputConfig {
+    bindings: HashMap<Action, Binding>,
+}
+
+impl InputConfig {
+    pub fn new() -> Self {
+        let defaults = [
+            (Action::Pause, Binding::Key(KeyCode::Space)),
+            (Action::PlaceTower, Binding::Mouse(MouseButton::Left)),
+            (Action::MoveHero, Binding::Mouse(MouseButton::Right)),
+            (Action::SellTower, Binding::Key(KeyCode::X)),
+            (Action::SpawnEnemy, Binding::Key(KeyCode::S)),
+            (Action::CallWave, Binding::Key(KeyCode::N)),
+            (Action::CastMeteor, Binding::Key(KeyCode::Q)),
+            (Action::CastFreeze, Binding::Key(KeyCode::W)),
+            (Action::CastRepair, Binding::Key(KeyCode::E)),
+            (Action::Cancel, Binding::Key(KeyCode::Escape)),
+            (Action::BuildSlot1, Binding::Key(KeyCode::Key1)),
+            (Action::BuildSlot2, Binding::Key(KeyCode::Key2)),
+            (Action::BuildSlot3, Binding::Key(KeyCode::Key3)),
+            (Action::BuildSlot4, Binding::Key(KeyCode::Key4)),
+            (Action::BuildSlot5, Binding::Key(KeyCode::Key5)),
+            (Action::BuildSlot6, Binding::Key(KeyCode::Key6)),
+            (Action::BuildSlot7, Binding::Key(KeyCode::Key7)),
+            (Action::BuildSlot8, Binding::Key(KeyCode::Key8)),
+            (Action::BuildSlot9, Binding::Key(KeyCode::Key9)),
+            (Action::BuildSlot10, Binding::Key(KeyCode::Key0)),
+            (Action::OpenSettings, Binding::Key(KeyCode::F1)),
+            (Action::ToggleFullscreen, Binding::Key(KeyCode::F11)),
+            (Action::UiScaleUp, Binding::Key(KeyCode::Equal)),
+            (Action::UiScaleDown, Binding::Key(KeyCode::Minus)),
+            (Action::ResetView, Binding::Key(KeyCode::Home)),
+            (Action::CyclePalette, Binding::Key(KeyCode::F2)),
+            (Action::ToggleDebug, Binding::Key(KeyCode::F3)),
+            (Action::DebugPaths, Binding::Key(KeyCode::F4)),
+            (Action::DebugRanges, Binding::Key(KeyCode::F5)),
+            (Action::DebugTargeting, Binding::Key(KeyCode::F6)),
+            (Action::Spectate, Binding::Key(KeyCode::F7)),
+            (Action::InstantReplay, Binding::Key(KeyCode::F8)),
+            (Action::RunStats, Binding::Key(KeyCode::F9)),
+            (Action::ExportRun, Binding::Key(KeyCode::F10)),
+            (Action::ToggleJuice, Binding::Key(KeyCode::J)),
+            (Action::ToggleMinimap, Binding::Key(KeyCode::M)),
+            (Action::UndoClear, Binding::Key(KeyCode::Z)),
+            (Action::ToggleHeatmap, Binding::Key(KeyCode::H)),
+            (Action::ClearWave, Binding::Key(KeyCode::K)),
+            (Action::PaintIgnore, Binding::Key(KeyCode::I)),
+            (Action::BuildGenerator, Binding::Key(KeyCode::G)),
+            (Action::ToggleTower, Binding::Key(KeyCode::T)),
+            (Action::RepairTower, Binding::Key(KeyCode::F)),
+            (Action::ToggleDpsMeter, Binding::Key(KeyCode::D)),
+            (Action::Screenshot, Binding::Key(KeyCode::F12)),
+            (Action::RecordClip, Binding::Key(KeyCode::R)),
+            (Action::DismissReport, Binding::Key(KeyCode::Enter)),
+            (Action::RestartCheckpoint, Binding::Key(KeyCode::B)),
+            (Action::OpenSaves, Binding::Key(KeyCode::L)),
+            (Action::SavePreset, Binding::Key(KeyCode::P)),
+            (Action::SelectTowers, Binding::Key(KeyCode::LeftShift)),
+            (Action::CopyTowers, Binding::Key(KeyCode::C)),
+            (Action::PasteTowers, Binding::Key(KeyCode::V)),
+            (Action::MirrorPaste, Binding::Key(KeyCode::Y)),
+            (Action::UpgradeGroup, Binding::Key(KeyCode::U)),
+            (Action::PaintRectangle, Binding::Key(KeyCode::LeftControl)),
+            (Action::OpenCodex, Binding::Key(KeyCode::O)),
+        ];
+        InputConfig {
+            bindings: defaults.into_iter().collect(),
+        }
+    }
+
+    pub fn binding(&self, action: Action) -> Option<Binding> {
+        self.bindings.get(&action).copied()
+    }
+
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        self.bindings.insert(action, binding);
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.binding(action).is_some_and(|b| b.is_pressed())
+    }
+
+    pub fn held(&self, action: Action) -> bool {
+        self.binding(action).is_some_and(|b| b.is_down())
+    }
+
+    pub fn released(&self, action: Action) -> bool {
+        self.binding(action).is_some_and(|b| b.is_released())
+    }
+
+    /// Short label for HUD hints, e.g. "Q" or "Mouse Left"
+    pub fn label(&self, action: Action) -> String {
+        match self.binding(action) {
+            Some(Binding::Key(key)) => format!("{:?}", key).trim_start_matches("Key").to_string(),
+            Some(Binding::Mouse(button)) => format!("Mouse {:?}", button),
+            None => "-".to_string(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Parse a binding table. Unknown or missing entries keep their defaults.
+    pub fn from_json(json: &str) -> Self {
+        serde_json::from_str(json).unwrap_or_default()
+    }
+
+    /// The key binding file from before `settings.json`
+    pub fn load() -> Self {
+        match storage::load(INPUT_CONFIG_FILE) {
+            Some(json) => InputConfig::from_json(&json),
+            None => InputConfig::new(),
+        }
+    }
+}
+
+impl From<HashMap<String, String>> for InputConfig {
+    fn from(table: HashMap<String, String>) -> Self {
+        let mut config = InputConfig::new();
+        for (name, binding) in table {
+            let action = Action::ALL.iter().find(|action| format!("{:?}", action) == name);
+            if let (Some(action), Some(binding)) = (action, Binding::parse(&binding)) {
+                config.bind(*action, binding);
+            }
+        }
+        config
+    }
+}
+
+impl From<InputConfig> for HashMap<String, String> {
+    fn from(config: InputConfig) -> Self {
+        config.bindings.iter().map(|(action, binding)| (format!("{:?}", action), binding.name())).collect()
+    }
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Settings screen state: the `Setting` rows, then one row per action
+#[derive(Debug, Clone, Default)]
+pub struct RebindScreen {
+    pub open: bool,
+    pub selected: usize,
+    pub waiting_for_input: bool,
+}
+
+impl RebindScreen {
+    const ROWS: usize = Setting::ALL.len() + Action::ALL.len();
+
+    /// Handle navigation, edits and capture. Returns true if a setting or
+    /// binding changed.
+    pub fn update(&mut self, settings: &mut Settings) -> bool {
+        let action = self.selected.checked_sub(Setting::ALL.len()).map(|i| Action::ALL[i]);
+        if self.waiting_for_input {
+            if let (Some(binding), Some(action)) = (Binding::any_pressed(), action) {
+                settings.input.bind(action, binding);
+                self.waiting_for_input = false;
+                return true;
+            }
+            return false;
+        }
+
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % Self::ROWS;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + Self::ROWS - 1) % Self::ROWS;
+        }
+        if let Some(setting) = Setting::ALL.get(self.selected) {
+            let step = is_key_pressed(KeyCode::Right) as i32 - is_key_pressed(KeyCode::Left) as i32;
+            if step != 0 {
+                settings.adjust(*setting, step);
+                if *setting == Setting::Resolution {
+                    settings.apply_window();
+                }
+                return true;
+            }
+        } else if is_key_pressed(KeyCode::Enter) {
+            self.waiting_for_input = true;
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            settings.input = InputConfig::new();
+            return true;
+        }
+        false
+    }
+
+    pub fn render(&self, settings: &Settings) {
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::from_rgba(0, 0, 0, 200));
+        draw_text("SETTINGS", 60.0, 60.0, 40.0, WHITE);
+        draw_text(
+            "Up/Down: select   Left/Right: change   Enter: rebind   Backspace: reset key defaults",
+            60.0,
+            90.0,
+            20.0,
+            LIGHTGRAY,
+        );
+
+        // Squeeze rows to fit short windows
+        let row_height = ((screen_height() - 160.0) / Self::ROWS as f32).clamp(14.0, 24.0);
+        let rows = Setting::ALL
+            .iter()
+            .map(|setting| (setting.label(), settings.value(*setting)))
+            .chain(Action::ALL.iter().map(|action| (action.label(), settings.input.label(*action))));
+        for (i, (label, value)) in rows.enumerate() {
+            // A gap between the settings and the bindings
+            let gap = if i >= Setting::ALL.len() { 10.0 } else { 0.0 };
+            let y = 130.0 + i as f32 * row_height + gap;
+            let selected = i == self.selected;
+            let color = if selected { YELLOW } else { WHITE };
+            let value = if selected && self.waiting_for_input { "press a key...".to_string() } else { value };
+            draw_text(label, 80.0, y, row_height - 2.0, color);
+            draw_text(value, 320.0, y, row_height - 2.0, color);
+        }
+    }
+}
+
+/// Translate this frame's bound actions into game commands
+pub fn handle_input(game: &mut Game, input: &InputConfig, camera: &mut GameCamera, notifications: &mut Notifications) {
+    // Mouse wheel zooms the camera around the cursor
+    let (_, wheel) = mouse_wheel();
+    if wheel != 0.0 {
+        let factor = if wheel > 0.0 { 1.1 } else { 1.0 / 1.1 };
+        camera.zoom_at(factor, mouse_position().into(), screen_width(), screen_height());
+    }
+
+    let (mx, my) = camera.mouse_world().into();
+    let hovered = game.state.grid.cell_at(mx, my);
+
+    if input.pressed(Action::Pause) {
+        game.state.paused = !game.state.paused;
+    }
+
+    // The sandbox panel spawns its own
+    if input.pressed(Action::SpawnEnemy) && !game.state.rules.sandbox {
+        if let Err(err) = game.state.debug_spawn() {
+            notifications.refused("Can't spawn", err);
+        }
+    }
+
+    if input.pressed(Action::CallWave) {
+        if let Err(err) = game.state.call_next_wave() {
+            notifications.refused("Can't call the wave", err);
+        }
+    }
+
+    for (action, tool) in Action::BUILD_SLOTS.iter().zip(BuildTool::SLOTS) {
+        if input.pressed(*action) && tool.fits_on(game.state.grid.shape()) {
+            game.build_tool = tool;
+        }
+    }
+
+    if input.pressed(Action::BuildGenerator) && game.state.power.enabled {
+        game.build_tool = BuildTool::Generator;
+    }
+
+    if input.pressed(Action::ToggleTower) {
+        if let Some(tower) = game.state.tower_at(&hovered) {
+            let command = Command::SetTowerEnabled { tower_id: tower.id, enabled: tower.disabled };
+            let _ = game.apply_command(&command); // Only refused for a tower that's gone
+        }
+    }
+
+    if input.pressed(Action::RepairTower) {
+        if let Some(tower_id) = game.state.tower_at(&hovered).map(|tower| tower.id) {
+            if let Err(err) = game.apply_command(&Command::RepairTower { tower_id }) {
+                notifications.refused("Can't repair", err);
+            }
+        }
+    }
+
+    if input.pressed(Action::UndoClear) {
+        if let Err(err) = game.state.undo_clear() {
+            notifications.refused("Can't undo", err);
+        }
+    }
+
+    if input.pressed(Action::SellTower) {
+        if let Some(tower_id) = game.state.tower_at(&hovered).map(|tower| tower.id) {
+            if let Err(err) = game.sell_tower(tower_id) {
+                notifications.refused("Can't sell", err);
+            }
+        }
+    }
+
+    for ability_type in AbilityType::ALL {
+        if input.pressed(Action::cast(ability_type)) {
+            if ability_type.needs_target() {
+                game.abilities.armed = Some(ability_type);
+            } else if let Err(err) = game.cast_ability(ability_type, None) {
+                notifications.refused("Can't cast", err);
+            }
+        }
+    }
+
+    if input.pressed(Action::Cancel) {
+        game.abilities.armed = None;
+    }
+
+    if input.pressed(Action::MoveHero) && game.abilities.armed.take().is_none() {
+        if let Some(tower) = game.state.tower_at(&hovered) {
+            // Start aiming: drag onto an enemy to lock it, or paint ignore zones
+            game.aiming_tower = Some(tower.id);
+        } else if let Err(err) = game.state.hero.move_to(&game.state.grid, hovered) {
+            notifications.refused("Can't go there", err);
+        }
+    }
+
+    if let Some(tower_id) = game.aiming_tower {
+        if input.held(Action::PaintIgnore) && game.state.tower_at(&hovered).is_none() {
+            let _ = game.apply_command(&Command::IgnoreCell { tower_id, position: hovered, ignored: true });
+        }
+    }
+
+    if input.released(Action::MoveHero) {
+        if let Some(tower_id) = game.aiming_tower.take() {
+            if game.state.tower_at(&hovered).is_some_and(|tower| tower.id == tower_id) {
+                // Released where it started: back to default targeting
+                let _ = game.apply_command(&Command::ResetTargeting { tower_id });
+            } else if let Some(enemy_id) = enemy_under(game, mx, my).filter(|_| !input.held(Action::PaintIgnore)) {
+                let _ = game.apply_command(&Command::LockTarget { tower_id, enemy_id: Some(enemy_id) });
+            }
+        }
+    }
+
+    if input.pressed(Action::PlaceTower) {
+        if let Some(armed) = game.abilities.armed.take() {
+            if let Err(err) = game.cast_ability(armed, Some((mx, my))) {
+                notifications.refused("Can't cast", err);
+            }
+        } else if let Some(tower) = game.state.tower_at(&hovered) {
+            // Start dragging an existing tower toward a merge partner
+            game.dragging_tower = Some(tower.id);
+        } else if game.state.obstacle_at(&hovered).is_some() {
+            if let Err(err) = game.state.clear_obstacle(hovered) {
+                notifications.refused("Can't clear", err);
+            }
+        } else if let BuildTool::Tower(tower_type) = game.build_tool {
+            game.start_stroke(tower_type, hovered); // Built on release, so a drag can make it a line
+        } else if let Err(err) = game.build_at(hovered) {
+            notifications.refused("Can't build", err);
+        }
+    }
+
+    let shape = if input.held(Action::PaintRectangle) { StrokeShape::Rectangle } else { StrokeShape::Line };
+    game.drag_stroke(hovered, shape);
+
+    if input.released(Action::PlaceTower) {
+        if let Some(source_id) = game.dragging_tower.take() {
+            // Dropped back on itself is just a click, not a merge
+            let target_id = game.state.tower_at(&hovered).map(|tower| tower.id).filter(|&id| id != source_id);
+            if let Some(Err(err)) = target_id.map(|target_id| game.state.merge_towers(source_id, target_id)) {
+                notifications.refused("Can't merge", err);
+            }
+        }
+        if let Some(Err((cell, err))) = game.finish_stroke() {
+            tracing::debug!(?cell, %err, "stroke not built");
+            notifications.refused("Can't build", err);
+        }
+    }
+}
+
+/// Touch controls: tap a cell to open a radial build menu, drag to pan,
+/// pinch to zoom
+pub fn handle_touch(
+    game: &mut Game,
+    camera: &mut GameCamera,
+    gestures: &[Gesture],
+    menu: &mut Option<RadialMenu>,
+    ui_scale: f32,
+    notifications: &mut Notifications,
+) {
+    for gesture in gestures {
+        match *gesture {
+            Gesture::Pan(delta) => {
+                *menu = None;
+                camera.pan_by_screen(delta);
+            }
+            Gesture::Pinch { factor, center } => {
+                *menu = None;
+                camera.zoom_at(factor, center, screen_width(), screen_height());
+            }
+            Gesture::Tap(point) => {
+                // A tap while the menu is open either picks an option or dismisses it
+                if let Some(open) = menu.take() {
+                    let tools = BuildTool::slots_for(game.state.grid.shape());
+                    if let Some(index) = open.option_at(point, tools.len()) {
+                        game.build_tool = tools[index];
+                        if let Err(err) = game.build_at(open.cell) {
+                            notifications.refused("Can't build", err);
+                        }
+                    }
+                    continue;
+                }
+
+                let world = camera.screen_to_world(point);
+                if let Some(armed) = game.abilities.armed.take() {
+                    if let Err(err) = game.cast_ability(armed, Some((world.x, world.y))) {
+                        notifications.refused("Can't cast", err);
+                    }
+                    continue;
+                }
+
+                let cell = game.state.grid.cell_at(world.x, world.y);
+                if game.state.grid.is_walkable(&cell) && game.state.trap_at(&cell).is_none() {
+                    let (x, y) = cell.to_world();
+                    let center = camera.world_to_screen(vec2(x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0));
+                    *menu = Some(RadialMenu::new(cell, center, ui_scale));
+                }
+            }
+        }
+    }
+}
+
+pub fn handle_debug_input(input: &InputConfig, overlay: &mut DebugOverlay, game: &mut Game) {
+    if input.pressed(Action::ToggleDebug) {
+        overlay.open = !overlay.open;
+    }
+    if overlay.open && input.pressed(Action::DebugPaths) {
+        overlay.show_paths = !overlay.show_paths;
+    }
+    if overlay.open && input.pressed(Action::DebugRanges) {
+        overlay.show_ranges = !overlay.show_ranges;
+    }
+    if input.pressed(Action::DebugTargeting) {
+        overlay.show_targeting = !overlay.show_targeting;
+        game.targeting.enabled = overlay.show_targeting;
+    }
+}
+
+/// Window-level actions: fullscreen, UI scale and view reset.
+/// Returns true when the camera should be re-fit to the board.
+pub fn handle_display_input(settings: &mut Settings) -> bool {
+    let (input, display) = (&settings.input, &mut settings.display);
+    let mut changed = false;
+    if input.pressed(Action::ToggleFullscreen) {
+        display.toggle_fullscreen();
+        changed = true;
+    }
+    if input.pressed(Action::UiScaleUp) {
+        display.step_ui_scale(1);
+        changed = true;
+    }
+    if input.pressed(Action::UiScaleDown) {
+        display.step_ui_scale(-1);
+        changed = true;
+    }
+    let palette_changed = input.pressed(Action::CyclePalette);
+    if palette_changed {
+        display.palette = display.palette.next();
+    }
+    let juice_changed = input.pressed(Action::ToggleJuice);
+    if juice_changed {
+        display.juice.toggle();
+    }
+    let refit = changed || input.pressed(Action::ResetView);
+    if changed || palette_changed || juice_changed {
+        save_settings(settings);
+    }
+    refit
+}
+
+/// The enemy nearest a world point, if one is close enough to click on
+pub fn enemy_under(game: &Game, x: f32, y: f32) -> Option<EnemyId> {
+    let reach = ENEMY_RADIUS * 2.0;
+    game.state
+        .enemies
+        .values()
+        .map(|enemy| (enemy.id, (enemy.x - x).hypot(enemy.y - y)))
+        .filter(|(_, distance)| *distance <= reach)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(enemy_id, _)| enemy_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binding_names_round_trip() {
+        for binding in [Binding::Key(KeyCode::Space), Binding::Key(KeyCode::Key7), Binding::Mouse(MouseButton::Right)] {
+            assert_eq!(Binding::parse(&binding.name()), Some(binding));
+        }
+        assert_eq!(Binding::parse("Key:NotAKey"), None);
+    }
+
+    #[test]
+    fn test_config_json_round_trip() {
+        let mut config = InputConfig::new();
+        config.bind(Action::Pause, Binding::Key(KeyCode::P));
+
+        let loaded = InputConfig::from_json(&config.to_json());
+        assert_eq!(loaded, config);
+    }
+
+    #[test]
+    fn test_partial_config_keeps_defaults() {
+        let loaded = InputConfig::from_json(r#"{ "SellTower": "Key:Delete_", "Pause": "Key:P" }"#);
+        assert_eq!(loaded.binding(Action::Pause), Some(Binding::Key(KeyCode::P)));
+        assert_eq!(loaded.binding(Action::SellTower), Some(Binding::Key(KeyCode::X)));
+    }
+}
@@ -0,0 +1,185 @@
+//! Optional presentation effects ("juice"): projectile trails, a short hit
+//! pause when a boss dies, screen shake from explosions and a white flash on
+//! enemies that take damage. Everything here is cosmetic apart from the hit
+//! pause, which only holds back the delta the frontend feeds the simulation.
+//! Each effect can be turned off in the display settings.
+
+use std::collections::{HashMap, VecDeque};
+
+use macroquad::prelude::*;
+use rust_rush_core::events::GameEvent;
+use rust_rush_core::{EnemyId, Game, ProjectileId};
+use serde::{Deserialize, Serialize};
+
+use crate::camera::GameCamera;
+
+const BOSS_HEALTH: i32 = 300; // Enemies with at least this much max health count as bosses
+const HIT_PAUSE_SECONDS: f32 = 0.15;
+const DAMAGE_FLASH_SECONDS: f32 = 0.1;
+
+// Shake spring: explosions kick its velocity in a random direction, the
+// spring pulls the camera back and damping settles it
+const SHAKE_STIFFNESS: f32 = 300.0;
+const SHAKE_DAMPING: f32 = 12.0;
+const SHAKE_KICK: f32 = 6.0; // Speed added per world unit of explosion radius
+const SHAKE_MAX_OFFSET: f32 = 12.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JuiceSettings {
+    pub trail_length: usize, // Positions kept per projectile; 0 draws no trail
+    pub hit_pause: bool,
+    pub screen_shake: bool,
+    pub damage_flash: bool,
+}
+
+impl JuiceSettings {
+    /// Every effect off
+    pub fn calm() -> Self {
+        JuiceSettings {
+            trail_length: 0,
+            hit_pause: false,
+            screen_shake: false,
+            damage_flash: false,
+        }
+    }
+
+    pub fn is_calm(&self) -> bool {
+        *self == Self::calm()
+    }
+
+    /// Flip between all effects on and all off
+    pub fn toggle(&mut self) {
+        *self = if self.is_calm() { Self::default() } else { Self::calm() };
+    }
+}
+
+impl Default for JuiceSettings {
+    fn default() -> Self {
+        JuiceSettings {
+            trail_length: 6,
+            hit_pause: true,
+            screen_shake: true,
+            damage_flash: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Juice {
+    trails: HashMap<ProjectileId, VecDeque<Vec2>>,
+    enemy_health: HashMap<EnemyId, i32>, // As of last frame, to spot hits
+    flashes: HashMap<EnemyId, f32>,
+    shake_offset: Vec2,
+    shake_velocity: Vec2,
+    pause_remaining: f32,
+}
+
+impl Juice {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The delta to feed the simulation: zero while a hit pause holds
+    pub fn sim_delta(&mut self, delta: f32) -> f32 {
+        if self.pause_remaining > 0.0 {
+            self.pause_remaining -= delta;
+            0.0
+        } else {
+            delta
+        }
+    }
+
+    /// Follow the game after its update; `sim_delta` is what it advanced by
+    pub fn update(&mut self, game: &Game, settings: &JuiceSettings, delta: f32, sim_delta: f32) {
+        self.trails.retain(|id, _| game.projectiles.contains_key(*id));
+        if settings.trail_length > 0 {
+            for (id, projectile) in &game.projectiles {
+                let trail = self.trails.entry(id).or_default();
+                trail.push_front(vec2(projectile.x, projectile.y));
+                trail.truncate(settings.trail_length);
+            }
+        } else {
+            self.trails.clear();
+        }
+
+        for flash in self.flashes.values_mut() {
+            *flash -= delta;
+        }
+        self.flashes.retain(|_, flash| *flash > 0.0);
+        for (id, enemy) in &game.state.enemies {
+            let hit = self.enemy_health.get(&id).is_some_and(|before| enemy.health < *before);
+            if hit && settings.damage_flash {
+                self.flashes.insert(id, DAMAGE_FLASH_SECONDS);
+            }
+        }
+        self.enemy_health = game.state.enemies.iter().map(|(id, enemy)| (id, enemy.health)).collect();
+
+        if settings.hit_pause {
+            let boss_down = game.frame_events.iter().any(|event| {
+                matches!(event, GameEvent::EnemyKilled { max_health, .. } if *max_health >= BOSS_HEALTH)
+            });
+            if boss_down && sim_delta > 0.0 {
+                self.pause_remaining = HIT_PAUSE_SECONDS;
+            }
+        }
+
+        if settings.screen_shake && sim_delta > 0.0 {
+            // Explosions are at most one simulation step old on the frame they go off
+            for explosion in &game.explosions {
+                if explosion.max_lifetime - explosion.lifetime <= sim_delta + f32::EPSILON {
+                    let angle = rand::gen_range(0.0, std::f32::consts::TAU);
+                    self.shake_velocity += Vec2::from_angle(angle) * explosion.max_radius * SHAKE_KICK;
+                }
+            }
+        }
+        let pull = -self.shake_offset * SHAKE_STIFFNESS - self.shake_velocity * SHAKE_DAMPING;
+        self.shake_velocity += pull * delta;
+        self.shake_offset = (self.shake_offset + self.shake_velocity * delta).clamp_length_max(SHAKE_MAX_OFFSET);
+        if !settings.screen_shake {
+            self.shake_offset = Vec2::ZERO;
+            self.shake_velocity = Vec2::ZERO;
+        }
+    }
+
+    /// The camera nudged by the current shake
+    pub fn shaken(&self, camera: &GameCamera) -> GameCamera {
+        GameCamera { center: camera.center + self.shake_offset, ..camera.clone() }
+    }
+
+    /// Recent positions of a projectile, newest first
+    pub fn trail(&self, id: ProjectileId) -> Option<&VecDeque<Vec2>> {
+        self.trails.get(&id)
+    }
+
+    /// How white an enemy should be drawn, from 0 to 1
+    pub fn flash(&self, id: EnemyId) -> f32 {
+        self.flashes.get(&id).map_or(0.0, |remaining| remaining / DAMAGE_FLASH_SECONDS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calm_toggle_and_partial_settings_file() {
+        let mut settings = JuiceSettings::default();
+        settings.toggle();
+        assert!(settings.is_calm());
+        settings.toggle();
+        assert_eq!(settings, JuiceSettings::default());
+
+        let parsed: JuiceSettings = serde_json::from_str(r#"{"screen_shake":false}"#).unwrap();
+        assert!(!parsed.screen_shake && parsed.hit_pause);
+    }
+
+    #[test]
+    fn test_hit_pause_holds_the_simulation() {
+        let mut juice = Juice::new();
+        juice.pause_remaining = HIT_PAUSE_SECONDS;
+        assert_eq!(juice.sim_delta(0.1), 0.0);
+        assert_eq!(juice.sim_delta(0.1), 0.0);
+        assert_eq!(juice.sim_delta(0.1), 0.1);
+    }
+}
@@ -0,0 +1,47 @@
+//! Warning icons over enemies the leak forecast expects to reach the goal,
+//! so players know where to drop an emergency spell or tower. The forecast
+//! runs once per simulated frame; see `rust_rush_core::leaks`.
+
+use macroquad::prelude::*;
+use rust_rush_core::leaks::LeakForecast;
+use rust_rush_core::{Game, CELL_SIZE};
+
+use crate::camera::GameCamera;
+use crate::theme::Theme;
+
+const ICON_SIZE: f32 = 9.0;
+
+pub struct LeakWarnings {
+    forecast: LeakForecast,
+}
+
+impl LeakWarnings {
+    pub fn new() -> Self {
+        LeakWarnings { forecast: LeakForecast::new() }
+    }
+
+    pub fn update(&mut self, game: &Game) {
+        self.forecast.update(&game.state);
+    }
+
+    pub fn render(&self, game: &Game, camera: &GameCamera, theme: &Theme) {
+        if self.forecast.at_risk_count() == 0 {
+            return;
+        }
+        set_camera(&camera.camera2d());
+        let bob = (get_time() as f32 * 6.0).sin() * 2.0;
+        let visible = game.state.enemies.values().filter(|enemy| game.fog.can_see(enemy.x, enemy.y));
+        for enemy in visible.filter(|enemy| self.forecast.is_at_risk(enemy.id)) {
+            // Above the health bar
+            let (x, y) = (enemy.x, enemy.y - CELL_SIZE * 0.5 - 12.0 + bob);
+            draw_triangle(
+                vec2(x, y - ICON_SIZE),
+                vec2(x - ICON_SIZE, y + ICON_SIZE * 0.7),
+                vec2(x + ICON_SIZE, y + ICON_SIZE * 0.7),
+                theme.danger,
+            );
+            draw_text("!", x - 2.5, y + ICON_SIZE * 0.55, 16.0, WHITE);
+        }
+        set_default_camera();
+    }
+}
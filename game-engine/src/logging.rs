@@ -0,0 +1,97 @@
+//! Tracing setup. Verbosity comes from `--log <filter>` or the `RUST_RUSH_LOG`
+//! environment variable (standard `tracing` filter syntax, e.g.
+//! `info,rust_rush_core=trace`). `--log-file <path>` adds a JSON-lines
+//! sink suitable for attaching to bug reports.
+
+use std::path::PathBuf;
+
+pub const LOG_ENV_VAR: &str = "RUST_RUSH_LOG";
+const DEFAULT_FILTER: &str = "warn";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, clap::Args)]
+pub struct LogOptions {
+    /// Tracing filter, e.g. `info,rust_rush_core=trace`
+    #[arg(long = "log", value_name = "FILTER")]
+    pub filter: Option<String>,
+    /// Also write JSON lines to this file
+    #[arg(long = "log-file", value_name = "PATH")]
+    pub file: Option<PathBuf>,
+}
+
+impl LogOptions {
+    /// Flag beats environment beats the default
+    pub fn resolved_filter(&self) -> String {
+        self.filter
+            .clone()
+            .or_else(|| std::env::var(LOG_ENV_VAR).ok())
+            .unwrap_or_else(|| DEFAULT_FILTER.to_string())
+    }
+}
+
+/// Keeps the file writer flushing; hold it until shutdown
+#[cfg(not(target_arch = "wasm32"))]
+pub type LogGuard = Option<tracing_appender::non_blocking::WorkerGuard>;
+#[cfg(target_arch = "wasm32")]
+pub type LogGuard = ();
+
+/// Install the global subscriber: human-readable to stderr, plus JSON lines
+/// to the log file if one was requested
+#[cfg(not(target_arch = "wasm32"))]
+pub fn init(options: &LogOptions) -> LogGuard {
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::{fmt, EnvFilter};
+
+    let filter = EnvFilter::try_new(options.resolved_filter()).unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let stderr_layer = fmt::layer().with_writer(std::io::stderr);
+
+    let (file_layer, guard) = match &options.file {
+        Some(path) => match std::fs::File::create(path) {
+            Ok(file) => {
+                let (writer, guard) = tracing_appender::non_blocking(file);
+                (Some(fmt::layer().json().with_writer(writer)), Some(guard))
+            }
+            Err(err) => {
+                eprintln!("could not open log file {}: {}", path.display(), err);
+                (None, None)
+            }
+        },
+        None => (None, None),
+    };
+
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(stderr_layer)
+        .with(file_layer)
+        .try_init();
+    guard
+}
+
+/// The browser build has no stderr or files; tracing events are dropped
+#[cfg(target_arch = "wasm32")]
+pub fn init(_options: &LogOptions) -> LogGuard {}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        log: LogOptions,
+    }
+
+    #[test]
+    fn test_parses_log_flags() {
+        let options = Cli::parse_from(["game", "--log", "debug", "--log-file=trace.jsonl"]).log;
+        assert_eq!(options.filter.as_deref(), Some("debug"));
+        assert_eq!(options.file, Some(PathBuf::from("trace.jsonl")));
+        assert_eq!(options.resolved_filter(), "debug");
+    }
+
+    #[test]
+    fn test_no_log_flags() {
+        assert_eq!(Cli::parse_from(["game"]).log, LogOptions::default());
+    }
+}
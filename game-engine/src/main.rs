@@ -1,995 +1,529 @@
 use macroquad::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-
-mod pathfinding;
-use pathfinding::find_waypoints;
-
-const CELL_SIZE: f32 = 40.0;
-const GRID_WIDTH: i32 = 20;
-const GRID_HEIGHT: i32 = 15;
-
-// ============================================================================
-// CORE DATA STRUCTURES
-// ============================================================================
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct Position {
-    pub x: i32,
-    pub y: i32,
+use rust_rush_core::ai::AiPlayer;
+use rust_rush_core::gates::{Gate, Patrol};
+use rust_rush_core::maps::MapFile;
+use rust_rush_core::mutators::RuleSet;
+use rust_rush_core::obstacles;
+use rust_rush_core::power::PowerGrid;
+use rust_rush_core::daynight::WorldClock;
+use rust_rush_core::fog::FogOfWar;
+use rust_rush_core::weather::WeatherState;
+use rust_rush_core::rng::SimRng;
+use rust_rush_core::{Direction, Game, Position, CELL_SIZE, GRID_HEIGHT, GRID_WIDTH};
+
+mod achievements;
+mod assets;
+mod attack;
+mod audio;
+mod autotile;
+mod board_cache;
+mod camera;
+mod capture;
+mod checkpoints;
+mod cli;
+mod codex;
+mod dashboard;
+mod debug;
+mod decorations;
+mod display;
+mod dps_meter;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+mod heatmap;
+#[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+mod hot_reload;
+mod input;
+mod juice;
+mod leak_warnings;
+mod minimap;
+mod postfx;
+mod presets;
+mod logging;
+mod mods;
+mod notifications;
+mod replay;
+mod report_card;
+mod route_preview;
+mod sandbox;
+mod save_menu;
+mod selection;
+mod recovery;
+mod render;
+mod saves;
+mod settings;
+mod spectator;
+mod storage;
+mod theme;
+mod tooltips;
+mod touch;
+mod transcript;
+mod tutorial;
+use achievements::Unlocks;
+use attack::AttackPanel;
+use audio::{AudioBank, CueScheduler};
+use board_cache::BoardCache;
+use camera::GameCamera;
+use capture::Capture;
+use checkpoints::CheckpointMenu;
+use clap::Parser;
+use cli::LaunchOptions;
+use debug::DebugOverlay;
+use display::HudLayout;
+use dps_meter::DpsMeter;
+use heatmap::Heatmap;
+use input::{handle_debug_input, handle_display_input, handle_input, handle_touch, Action, RebindScreen};
+use render::{render_debug_overlay, render_hud, render_radial_menu, render_spectator_panel, render_world};
+use dashboard::Dashboard;
+use juice::Juice;
+use minimap::Minimap;
+use notifications::Notifications;
+use postfx::PostFx;
+use replay::InstantReplay;
+use report_card::ReportCardPanel;
+use leak_warnings::LeakWarnings;
+use route_preview::RoutePreview;
+use sandbox::SandboxPanel;
+use codex::CodexScreen;
+use presets::PresetPanel;
+use save_menu::SaveMenu;
+use selection::TowerSelection;
+use tooltips::Tooltips;
+use recovery::{Autosaver, RecoveryPrompt};
+use saves::{Autosave, SaveSlots};
+use settings::Settings;
+use spectator::Spectator;
+use theme::Theme;
+use touch::{GestureRecognizer, RadialMenu};
+use transcript::Transcript;
+use tutorial::TutorialGuide;
+
+const AI_DEMO_BUDGET: i32 = 500; // Gold per wave the computer may spend with `--ai` or `--attack`
+
+/// Frame the whole board inside the space the HUD leaves free
+fn fit_camera(camera: &mut GameCamera, hud: &HudLayout) {
+    let board = Rect::new(0.0, 0.0, GRID_WIDTH as f32 * CELL_SIZE, GRID_HEIGHT as f32 * CELL_SIZE);
+    camera.fit(board, hud.play_area(), screen_width(), screen_height());
 }
 
-impl Position {
-    pub fn new(x: i32, y: i32) -> Self {
-        Position { x, y }
-    }
-
-    pub fn to_world(&self) -> (f32, f32) {
-        (self.x as f32 * CELL_SIZE, self.y as f32 * CELL_SIZE)
-    }
-
-    pub fn from_world(x: f32, y: f32) -> Self {
-        Position {
-            x: (x / CELL_SIZE).floor() as i32,
-            y: (y / CELL_SIZE).floor() as i32,
-        }
-    }
-
-    pub fn manhattan_distance(&self, other: &Position) -> i32 {
-        (self.x - other.x).abs() + (self.y - other.y).abs()
-    }
-
-    pub fn distance(&self, other: &Position) -> f32 {
-        let dx = (self.x - other.x) as f32;
-        let dy = (self.y - other.y) as f32;
-        (dx * dx + dy * dy).sqrt()
-    }
-
-    pub fn neighbors(&self) -> Vec<Position> {
-        vec![
-            Position::new(self.x + 1, self.y),
-            Position::new(self.x - 1, self.y),
-            Position::new(self.x, self.y + 1),
-            Position::new(self.x, self.y - 1),
-        ]
+fn save_settings(settings: &Settings) {
+    if let Err(err) = settings.save() {
+        tracing::warn!(%err, "settings not saved");
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Grid {
-    width: i32,
-    height: i32,
-    walkable: HashMap<Position, bool>,
+/// Mutators named by `--mutators <a,b,...>`, or the sandbox with
+/// `--sandbox`; unknown mutator names are logged and the run starts
+/// without any
+fn run_rules(options: &LaunchOptions) -> RuleSet {
+    let mut rules = if options.sandbox {
+        RuleSet::sandbox()
+    } else {
+        match &options.mutators {
+            Some(list) => RuleSet::parse(list).unwrap_or_else(|err| {
+                tracing::error!(%err, "mutators not applied");
+                RuleSet::default()
+            }),
+            None => RuleSet::default(),
+        }
+    };
+    rules.ammo = options.ammo;
+    rules.siege = options.siege;
+    rules.convoys = options.convoys;
+    rules.attack = options.attack;
+    rules.smart_enemies = options.smart_enemies;
+    rules.hardcore = options.hardcore;
+    rules
 }
 
-impl Grid {
-    pub fn new(width: i32, height: i32) -> Self {
-        Grid {
-            width,
-            height,
-            walkable: HashMap::new(),
+/// Load the rule script named by `--script <path>`, if any
+#[cfg(feature = "scripting")]
+fn load_scripts(game: &mut Game, options: &LaunchOptions) {
+    let Some(path) = &options.script else {
+        return;
+    };
+    match rust_rush_core::scripting::ScriptHost::load(path) {
+        Ok(host) => {
+            tracing::info!(path = %path.display(), "rule script loaded");
+            game.scripts = Some(host);
         }
+        Err(err) => tracing::error!(path = %path.display(), %err, "rule script not loaded"),
     }
-
-    pub fn is_walkable(&self, pos: &Position) -> bool {
-        if pos.x < 0 || pos.x >= self.width || pos.y < 0 || pos.y >= self.height {
-            return false;
-        }
-        *self.walkable.get(pos).unwrap_or(&true)
-    }
-
-    pub fn set_walkable(&mut self, pos: &Position, walkable: bool) {
-        self.walkable.insert(*pos, walkable);
-    }
-}
-
-// ============================================================================
-// TOWER SYSTEM
-// ============================================================================
-
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-pub enum TowerType {
-    Basic,
-    Sniper,
-    Splash,
-    Slow,
 }
 
-impl TowerType {
-    pub fn cost(&self) -> i32 {
-        match self {
-            TowerType::Basic => 50,
-            TowerType::Sniper => 100,
-            TowerType::Splash => 75,
-            TowerType::Slow => 60,
-        }
-    }
-
-    pub fn range(&self) -> f32 {
-        match self {
-            TowerType::Basic => 3.0,
-            TowerType::Sniper => 6.0,
-            TowerType::Splash => 2.5,
-            TowerType::Slow => 3.5,
-        }
-    }
-
-    pub fn damage(&self) -> i32 {
-        match self {
-            TowerType::Basic => 10,
-            TowerType::Sniper => 50,
-            TowerType::Splash => 15,
-            TowerType::Slow => 5,
-        }
-    }
-
-    pub fn fire_rate(&self) -> f32 {
-        match self {
-            TowerType::Basic => 1.0,
-            TowerType::Sniper => 0.3,
-            TowerType::Splash => 0.8,
-            TowerType::Slow => 2.0,
-        }
-    }
-
-    pub fn color(&self) -> Color {
-        match self {
-            TowerType::Basic => BLUE,
-            TowerType::Sniper => RED,
-            TowerType::Splash => ORANGE,
-            TowerType::Slow => SKYBLUE,
-        }
-    }
-
-    pub fn projectile_speed(&self) -> f32 {
-        match self {
-            TowerType::Basic => 300.0,
-            TowerType::Sniper => 600.0,
-            TowerType::Splash => 200.0,
-            TowerType::Slow => 250.0,
-        }
-    }
-
-    pub fn projectile_color(&self) -> Color {
-        match self {
-            TowerType::Basic => YELLOW,
-            TowerType::Sniper => RED,
-            TowerType::Splash => ORANGE,
-            TowerType::Slow => Color::from_rgba(100, 200, 255, 255),
-        }
-    }
-
-    pub fn splash_radius(&self) -> f32 {
-        match self {
-            TowerType::Splash => 1.5,
-            _ => 0.0,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Tower {
-    pub id: u32,
-    pub tower_type: TowerType,
-    pub position: Position,
-    #[serde(skip)]
-    pub cooldown_remaining: f32,
-    #[serde(skip)]
-    pub target_id: Option<u32>,
-    #[serde(skip)]
-    pub rotation: f32, // Rotation angle in radians
-}
-
-impl Tower {
-    pub fn new(id: u32, tower_type: TowerType, position: Position) -> Self {
-        Tower {
-            id,
-            tower_type,
-            position,
-            cooldown_remaining: 0.0,
-            target_id: None,
-            rotation: 0.0,
-        }
-    }
-
-    pub fn can_shoot(&self) -> bool {
-        self.cooldown_remaining <= 0.0
-    }
-
-    pub fn update(&mut self, delta: f32) {
-        if self.cooldown_remaining > 0.0 {
-            self.cooldown_remaining -= delta;
-        }
-    }
-
-    pub fn shoot(&mut self) {
-        self.cooldown_remaining = 1.0 / self.tower_type.fire_rate();
-    }
-
-    pub fn world_position(&self) -> (f32, f32) {
-        let (x, y) = self.position.to_world();
-        (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0)
-    }
+/// Lay the `--map` file onto the board
+fn load_map(game: &mut Game, path: &std::path::Path) -> Result<(), String> {
+    let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let map: MapFile = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+    game.state.load_map(&map).map_err(|err| err.to_string())
 }
 
-// ============================================================================
-// PROJECTILE SYSTEM
-// ============================================================================
-
-#[derive(Debug, Clone)]
-pub struct Projectile {
-    pub id: u32,
-    pub tower_type: TowerType,
-    pub x: f32,
-    pub y: f32,
-    pub target_id: u32,
-    pub target_x: f32,
-    pub target_y: f32,
-    pub damage: i32,
-    pub speed: f32,
-    pub lifetime: f32, // For safety, remove after X seconds
+/// The run the launch options describe, windowed or not
+fn setup_game(options: &LaunchOptions) -> Result<Game, String> {
+    let mut game = Game::with_rules(run_rules(options));
+    if let Some(path) = &options.map {
+        load_map(&mut game, path).map_err(|err| format!("map {}: {}", path.display(), err))?;
+    }
+    game.fog = FogOfWar::new(options.fog);
+    game.state.clock = WorldClock::new(options.day_night);
+    game.state.weather = WeatherState::new(options.weather);
+    if options.portals {
+        let grid = &mut game.state.grid;
+        grid.link_teleporters(Position::new(3, 2), Position::new(16, 12));
+        for x in 8..=11 {
+            grid.set_one_way(&Position::new(x, 7), Some(Direction::Right));
+        }
+    }
+    if options.gates {
+        let route = (3..=11).map(|y| Position::new(10, y)).collect();
+        let added = [
+            game.state.add_gate(Gate::new(Position::new(6, 7), 8.0, 4.0)),
+            game.state.add_gate(Gate::new(Position::new(13, 6), 5.0, 5.0)),
+            game.state.add_patrol(Patrol::new(route, 1.5)),
+        ];
+        for err in added.into_iter().filter_map(Result::err) {
+            tracing::warn!(%err, "gate scenario piece left out");
+        }
+    }
+    game.state.power = PowerGrid::new(options.power);
+    if let Some(seed) = options.seed {
+        game.state.rng = SimRng::new(seed);
+    }
+    if options.obstacles {
+        for obstacle in obstacles::default_layout() {
+            game.state.add_obstacle(obstacle.kind, obstacle.position);
+        }
+    }
+    #[cfg(feature = "scripting")]
+    load_scripts(&mut game, options);
+    Ok(game)
 }
 
-impl Projectile {
-    pub fn new(
-        id: u32,
-        tower_type: TowerType,
-        start_x: f32,
-        start_y: f32,
-        target_id: u32,
-        target_x: f32,
-        target_y: f32,
-    ) -> Self {
-        Projectile {
-            id,
-            tower_type,
-            x: start_x,
-            y: start_y,
-            target_id,
-            target_x,
-            target_y,
-            damage: tower_type.damage(),
-            speed: tower_type.projectile_speed(),
-            lifetime: 5.0, // 5 seconds max
-        }
-    }
-
-    pub fn update(&mut self, delta: f32, enemy_pos: Option<(f32, f32)>) -> bool {
-        self.lifetime -= delta;
-        
-        // Update target position if enemy moved
-        if let Some((new_x, new_y)) = enemy_pos {
-            self.target_x = new_x;
-            self.target_y = new_y;
-        }
-
-        // Calculate direction to target
-        let dx = self.target_x - self.x;
-        let dy = self.target_y - self.y;
-        let distance = (dx * dx + dy * dy).sqrt();
-
-        // Check if reached target
-        if distance < 5.0 || self.lifetime <= 0.0 {
-            return false; // Projectile should be removed
-        }
-
-        // Move toward target
-        let move_distance = self.speed * delta;
-        if move_distance >= distance {
-            self.x = self.target_x;
-            self.y = self.target_y;
-            return false; // Reached target
-        }
-
-        let direction_x = dx / distance;
-        let direction_y = dy / distance;
-        
-        self.x += direction_x * move_distance;
-        self.y += direction_y * move_distance;
-
-        true // Continue moving
-    }
+fn main() {
+    let options = LaunchOptions::parse();
+    let _log_guard = logging::init(&options.log);
+    let mut game = setup_game(&options).unwrap_or_else(|err| exit_with(&err));
+    let mod_errors = mods::load_into(&mut game, std::path::Path::new(mods::MODS_DIR));
+    let transcript = options.replay.as_ref().map(|path| {
+        Transcript::load(path, &game).unwrap_or_else(|err| exit_with(&format!("replay {}: {}", path.display(), err)))
+    });
 
-    pub fn has_hit(&self) -> bool {
-        let dx = self.target_x - self.x;
-        let dy = self.target_y - self.y;
-        let distance = (dx * dx + dy * dy).sqrt();
-        distance < 10.0
+    #[cfg(not(target_arch = "wasm32"))]
+    if options.is_headless() {
+        std::process::exit(headless::run(game, transcript, &options, AI_DEMO_BUDGET));
     }
+    macroquad::Window::new("Rust Rush", run(options, game, transcript, mod_errors));
 }
 
-// ============================================================================
-// ENEMY SYSTEM
-// ============================================================================
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Enemy {
-    pub id: u32,
-    pub x: f32,
-    pub y: f32,
-    pub path: Vec<Position>,
-    pub current_waypoint: usize,
-    pub speed: f32,
-    pub health: i32,
-    pub max_health: i32,
-    #[serde(skip)]
-    pub slow_duration: f32, // Time remaining slowed
-    #[serde(skip)]
-    pub slow_multiplier: f32, // Speed multiplier when slowed
+fn exit_with(message: &str) -> ! {
+    eprintln!("rust-rush: {}", message);
+    std::process::exit(2)
 }
 
-impl Enemy {
-    pub fn new(id: u32, start: Position, goal: Position, grid: &Grid) -> Option<Self> {
-        let path = find_waypoints(grid, start, goal)?;
-        let (x, y) = start.to_world();
-        
-        Some(Enemy {
-            id,
-            x: x + CELL_SIZE / 2.0,
-            y: y + CELL_SIZE / 2.0,
-            path,
-            current_waypoint: 0,
-            speed: 50.0,
-            health: 100,
-            max_health: 100,
-            slow_duration: 0.0,
-            slow_multiplier: 1.0,
-        })
-    }
-
-    pub fn update(&mut self, delta: f32) -> bool {
-        // Update slow effect
-        if self.slow_duration > 0.0 {
-            self.slow_duration -= delta;
-            if self.slow_duration <= 0.0 {
-                self.slow_multiplier = 1.0;
+async fn run(options: LaunchOptions, mut game: Game, mut transcript: Option<Transcript>, mod_errors: Vec<mods::ModError>) {
+    if let Some(seed) = options.seed {
+        rand::srand(seed);
+    }
+    game.profiler.set_clock(debug::now);
+    achievements::load_into(&mut game);
+    // Demo and attack modes: the computer builds the defense
+    let mut ai = (options.ai || game.state.rules.attack).then(|| AiPlayer::new(AI_DEMO_BUDGET));
+    let mut tutorial = options.tutorial.then(TutorialGuide::new);
+    let mut sandbox = game.state.rules.sandbox.then(SandboxPanel::new);
+    let mut attack = game.state.rules.attack.then(AttackPanel::new);
+    let mut unlocks = Unlocks::new(&game);
+    let mut notifications = Notifications::new();
+    let mut cue_scheduler = CueScheduler::default();
+    let audio = AudioBank::load().await;
+    let mut settings = Settings::load();
+    settings.apply_window();
+    if transcript.is_none() {
+        game.state.waves.auto_start = settings.auto_start(); // A transcript keeps its recorded pace
+    }
+    let mut rebind_screen = RebindScreen::default();
+    let mut camera = GameCamera::new(vec2(screen_width() / 2.0, screen_height() / 2.0));
+    let mut last_screen_size = vec2(0.0, 0.0); // Forces a fit on the first frame
+    let mut theme = Theme::new(settings.display.palette);
+    let mut debug_overlay = DebugOverlay::new();
+    let mut gestures = GestureRecognizer::new();
+    let mut radial_menu: Option<RadialMenu> = None;
+    let mut spectator = Spectator::new();
+    let mut replay = InstantReplay::new();
+    let mut dashboard = Dashboard::new();
+    let mut juice = Juice::new();
+    let mut minimap = Minimap::new();
+    let mut route_preview = RoutePreview::new();
+    let mut selection = TowerSelection::new();
+    let mut tooltips = Tooltips::new();
+    let mut leak_warnings = LeakWarnings::new();
+    let mut dps_meter = DpsMeter::new();
+    let mut report_card = ReportCardPanel::new();
+    // A transcript has to play out as recorded, so it gets no restarts; nor
+    // does a hardcore run, which keeps its single save slot instead
+    let hardcore = game.state.rules.hardcore && transcript.is_none();
+    let mut checkpoints = (transcript.is_none() && !hardcore).then(CheckpointMenu::new);
+    let mut save_slots = SaveSlots::open();
+    save_slots.text_saves = options.text_saves;
+    let mut autosave = hardcore.then(|| Autosave::start(&mut game, &mut save_slots));
+    let mut save_menu = transcript.is_none().then(SaveMenu::new);
+    let mut codex = CodexScreen::new();
+    // In attack mode the board is the AI's to build
+    let mut presets = (transcript.is_none() && attack.is_none()).then(PresetPanel::open);
+    // The hardcore slot already plays on after a crash
+    let (mut autosaver, unclean) = if transcript.is_none() && !hardcore {
+        let (saver, unclean) = Autosaver::start(&save_slots);
+        saver.install_panic_hook();
+        (Some(saver), unclean)
+    } else {
+        (None, None)
+    };
+    let mut recovery_prompt = unclean.map(RecoveryPrompt::new);
+    // A replay has to play out on the content it was recorded with
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    let mut hot_reload = transcript.is_none().then(|| hot_reload::HotReload::start(options.map.as_deref())).flatten();
+    #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+    let mut mod_errors = mod_errors;
+    let mut heatmap = Heatmap::new();
+    let mut capture = Capture::new();
+    let mut postfx = PostFx::new();
+    let mut board_cache = BoardCache::new();
+    assets::load(std::path::Path::new(assets::ASSETS_DIR), &mut board_cache, &mut postfx);
+
+    // Touches are handled as gestures; don't let them double as mouse clicks
+    simulate_mouse_with_touch(false);
+    // Closing the window ends the loop, so the session can be marked clean
+    prevent_quit();
+    
+    loop {
+        if is_quit_requested() {
+            if let Some(saver) = &autosaver {
+                saver.finish();
             }
+            break;
         }
+        let delta = get_frame_time();
 
-        if self.current_waypoint >= self.path.len() {
-            return false; // Reached goal
-        }
-
-        let waypoint = &self.path[self.current_waypoint];
-        let (target_x, target_y) = waypoint.to_world();
-        let target_x = target_x + CELL_SIZE / 2.0;
-        let target_y = target_y + CELL_SIZE / 2.0;
-
-        let dx = target_x - self.x;
-        let dy = target_y - self.y;
-        let distance = (dx * dx + dy * dy).sqrt();
+        // Keys typed into a prompt aren't hotkeys
+        let typing = rebind_screen.waiting_for_input || save_menu.as_ref().is_some_and(SaveMenu::is_renaming);
 
-        if distance < 2.0 {
-            self.current_waypoint += 1;
-            return self.current_waypoint < self.path.len();
+        // Re-frame the board whenever the window size or UI scale changes
+        let refit = !typing && handle_display_input(&mut settings);
+        if !typing {
+            handle_debug_input(&settings.input, &mut debug_overlay, &mut game);
         }
-
-        let effective_speed = self.speed * self.slow_multiplier;
-        let move_distance = effective_speed * delta;
-        let direction_x = dx / distance;
-        let direction_y = dy / distance;
-
-        self.x += direction_x * move_distance;
-        self.y += direction_y * move_distance;
-
-        true
-    }
-
-    pub fn recalculate_path(&mut self, grid: &Grid, goal: Position) {
-        let current_pos = Position::from_world(self.x, self.y);
-        if let Some(new_path) = find_waypoints(grid, current_pos, goal) {
-            self.path = new_path;
-            self.current_waypoint = 0;
+        let hud = HudLayout::current(&settings.display);
+        if theme.palette != settings.display.palette {
+            theme = Theme::new(settings.display.palette);
         }
-    }
-
-    pub fn take_damage(&mut self, damage: i32) {
-        self.health = (self.health - damage).max(0);
-    }
-
-    pub fn is_alive(&self) -> bool {
-        self.health > 0
-    }
-
-    pub fn apply_slow(&mut self, duration: f32, multiplier: f32) {
-        self.slow_duration = duration;
-        self.slow_multiplier = multiplier;
-    }
-}
-
-// ============================================================================
-// EFFECTS SYSTEM
-// ============================================================================
-
-#[derive(Debug, Clone)]
-pub struct MuzzleFlash {
-    pub x: f32,
-    pub y: f32,
-    pub color: Color,
-    pub lifetime: f32,
-    pub max_lifetime: f32,
-}
-
-impl MuzzleFlash {
-    pub fn new(x: f32, y: f32, color: Color) -> Self {
-        MuzzleFlash {
-            x,
-            y,
-            color,
-            lifetime: 0.1,
-            max_lifetime: 0.1,
+        let screen_size = vec2(screen_width(), screen_height());
+        if refit || screen_size != last_screen_size {
+            fit_camera(&mut camera, &hud);
+            last_screen_size = screen_size;
         }
-    }
 
-    pub fn update(&mut self, delta: f32) -> bool {
-        self.lifetime -= delta;
-        self.lifetime > 0.0
-    }
-
-    pub fn alpha(&self) -> f32 {
-        self.lifetime / self.max_lifetime
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct ExplosionEffect {
-    pub x: f32,
-    pub y: f32,
-    pub radius: f32,
-    pub max_radius: f32,
-    pub color: Color,
-    pub lifetime: f32,
-    pub max_lifetime: f32,
-}
-
-impl ExplosionEffect {
-    pub fn new(x: f32, y: f32, radius: f32, color: Color) -> Self {
-        ExplosionEffect {
-            x,
-            y,
-            radius: 0.0,
-            max_radius: radius * CELL_SIZE,
-            color,
-            lifetime: 0.3,
-            max_lifetime: 0.3,
-        }
-    }
-
-    pub fn update(&mut self, delta: f32) -> bool {
-        self.lifetime -= delta;
-        let progress = 1.0 - (self.lifetime / self.max_lifetime);
-        self.radius = self.max_radius * progress;
-        self.lifetime > 0.0
-    }
-
-    pub fn alpha(&self) -> f32 {
-        self.lifetime / self.max_lifetime
-    }
-}
-
-// ============================================================================
-// GAME STATE
-// ============================================================================
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct GameState {
-    pub grid: Grid,
-    pub towers: HashMap<u32, Tower>,
-    pub enemies: HashMap<u32, Enemy>,
-    pub spawn_point: Position,
-    pub goal_point: Position,
-    pub next_tower_id: u32,
-    pub next_enemy_id: u32,
-    pub gold: i32,
-    pub health: i32,
-    pub paused: bool,
-}
-
-impl GameState {
-    pub fn new() -> Self {
-        GameState {
-            grid: Grid::new(GRID_WIDTH, GRID_HEIGHT),
-            towers: HashMap::new(),
-            enemies: HashMap::new(),
-            spawn_point: Position::new(0, 7),
-            goal_point: Position::new(19, 7),
-            next_tower_id: 0,
-            next_enemy_id: 0,
-            gold: 200,
-            health: 20,
-            paused: false,
+        // Handle input
+        if settings.input.pressed(Action::Spectate) && !typing {
+            spectator.toggle();
+            game.dragging_tower = None;
+            game.aiming_tower = None;
+            game.stroke = None;
+            radial_menu = None;
         }
-    }
-
-    pub fn place_tower(&mut self, tower_type: TowerType, position: Position) -> bool {
-        if self.gold < tower_type.cost() {
-            return false;
+        if settings.input.pressed(Action::InstantReplay) && !typing {
+            replay.toggle();
         }
-
-        if !self.grid.is_walkable(&position) {
-            return false;
+        if settings.input.pressed(Action::RunStats) && !typing {
+            dashboard.toggle();
         }
-
-        let tower = Tower::new(self.next_tower_id, tower_type, position);
-        self.towers.insert(self.next_tower_id, tower);
-        self.next_tower_id += 1;
-        self.gold -= tower_type.cost();
-        self.grid.set_walkable(&position, false);
-
-        // Recalculate paths for all enemies
-        for enemy in self.enemies.values_mut() {
-            enemy.recalculate_path(&self.grid, self.goal_point);
+        if settings.input.pressed(Action::ToggleHeatmap) && !typing {
+            heatmap.toggle();
         }
-
-        true
-    }
-
-    pub fn spawn_enemy(&mut self) -> bool {
-        if let Some(enemy) = Enemy::new(
-            self.next_enemy_id,
-            self.spawn_point,
-            self.goal_point,
-            &self.grid,
-        ) {
-            self.enemies.insert(self.next_enemy_id, enemy);
-            self.next_enemy_id += 1;
-            true
-        } else {
-            false
+        if settings.input.pressed(Action::ToggleMinimap) && !typing {
+            minimap.toggle();
         }
-    }
-
-    pub fn clear_all(&mut self) {
-        for tower in self.towers.values() {
-            self.grid.set_walkable(&tower.position, true);
-        }
-        self.towers.clear();
-        self.enemies.clear();
-    }
-}
-
-// ============================================================================
-// GAME LOGIC WITH SHOOTING
-// ============================================================================
-
-pub struct Game {
-    pub state: GameState,
-    pub projectiles: HashMap<u32, Projectile>,
-    pub next_projectile_id: u32,
-    pub muzzle_flashes: Vec<MuzzleFlash>,
-    pub explosions: Vec<ExplosionEffect>,
-}
-
-impl Game {
-    pub fn new() -> Self {
-        Game {
-            state: GameState::new(),
-            projectiles: HashMap::new(),
-            next_projectile_id: 0,
-            muzzle_flashes: Vec::new(),
-            explosions: Vec::new(),
+        if settings.input.pressed(Action::ToggleDpsMeter) && !typing {
+            dps_meter.toggle();
         }
-    }
-
-    pub fn update(&mut self, delta: f32) {
-        if self.state.paused {
-            return;
+        if settings.input.pressed(Action::ExportRun) && !typing {
+            dashboard::export(&game);
         }
-
-        // Update towers
-        self.update_towers(delta);
-
-        // Update projectiles
-        self.update_projectiles(delta);
-
-        // Update enemies
-        self.update_enemies(delta);
-
-        // Update effects
-        self.update_effects(delta);
-    }
-
-    fn update_towers(&mut self, delta: f32) {
-        let mut new_projectiles = Vec::new();
-        let mut new_flashes = Vec::new();
-
-        // Collect tower IDs and positions first to avoid borrow issues
-        let tower_data: Vec<(u32, TowerType, f32, f32, bool)> = self.state.towers
-            .values()
-            .map(|tower| {
-                let (x, y) = tower.world_position();
-                (tower.id, tower.tower_type, x, y, tower.can_shoot())
-            })
-            .collect();
-
-        // Update tower cooldowns
-        for tower in self.state.towers.values_mut() {
-            tower.update(delta);
-        }
-
-        // Find targets and shoot
-        for (tower_id, tower_type, tower_x, tower_y, can_shoot) in tower_data {
-            if !can_shoot {
-                continue;
-            }
-
-            // Find target in range
-            if let Some(target) = self.find_target_for_tower_at(tower_x, tower_y, tower_type) {
-                // Update tower rotation and shoot
-                if let Some(tower) = self.state.towers.get_mut(&tower_id) {
-                    let dx = target.x - tower_x;
-                    let dy = target.y - tower_y;
-                    tower.rotation = dy.atan2(dx);
-                    tower.target_id = Some(target.id);
-                    tower.shoot();
-                }
-
-                // Create projectile
-                let projectile = Projectile::new(
-                    self.next_projectile_id,
-                    tower_type,
-                    tower_x,
-                    tower_y,
-                    target.id,
-                    target.x,
-                    target.y,
-                );
-                new_projectiles.push((self.next_projectile_id, projectile));
-                self.next_projectile_id += 1;
-
-                // Create muzzle flash
-                new_flashes.push(MuzzleFlash::new(
-                    tower_x,
-                    tower_y,
-                    tower_type.projectile_color(),
-                ));
-            } else {
-                // Clear target if none found
-                if let Some(tower) = self.state.towers.get_mut(&tower_id) {
-                    tower.target_id = None;
-                }
+        if settings.input.pressed(Action::OpenSaves) && !typing && !rebind_screen.open {
+            if let Some(menu) = &mut save_menu {
+                menu.toggle();
             }
         }
-
-        // Add new projectiles
-        for (id, projectile) in new_projectiles {
-            self.projectiles.insert(id, projectile);
+        if settings.input.pressed(Action::OpenCodex) && !typing && !rebind_screen.open {
+            codex.toggle();
         }
-
-        // Add new flashes
-        self.muzzle_flashes.extend(new_flashes);
-    }
-
-    fn find_target_for_tower_at(&self, tower_x: f32, tower_y: f32, tower_type: TowerType) -> Option<Enemy> {
-        let range = tower_type.range() * CELL_SIZE;
-
-        self.state
-            .enemies
-            .values()
-            .filter(|enemy| {
-                let dx = enemy.x - tower_x;
-                let dy = enemy.y - tower_y;
-                let distance = (dx * dx + dy * dy).sqrt();
-                distance <= range && enemy.is_alive()
-            })
-            .max_by(|a, b| {
-                // Target enemy furthest along path (closest to goal)
-                a.current_waypoint.cmp(&b.current_waypoint)
-            })
-            .cloned()
-    }
-
-    fn update_projectiles(&mut self, delta: f32) {
-        let mut projectiles_to_remove = Vec::new();
-        let mut hits = Vec::new();
-
-        for (id, projectile) in self.projectiles.iter_mut() {
-            let enemy_pos = self.state.enemies
-                .get(&projectile.target_id)
-                .map(|e| (e.x, e.y));
-
-            let still_active = projectile.update(delta, enemy_pos);
-
-            if !still_active || projectile.has_hit() {
-                // Record hit before removing projectile
-                if let Some(enemy) = self.state.enemies.get(&projectile.target_id) {
-                    hits.push((
-                        projectile.target_id,
-                        projectile.damage,
-                        projectile.tower_type,
-                        enemy.x,
-                        enemy.y,
-                    ));
+        if settings.input.pressed(Action::OpenSettings) && !typing {
+            rebind_screen.open = !rebind_screen.open;
+        } else if rebind_screen.open {
+            if rebind_screen.update(&mut settings) {
+                save_settings(&settings);
+                if transcript.is_none() {
+                    game.state.waves.auto_start = settings.auto_start();
                 }
-                projectiles_to_remove.push(*id);
             }
+        } else if let Some(prompt) = &mut recovery_prompt {
+            if prompt.handle_input(&mut game, &save_slots) {
+                recovery_prompt = None;
+            }
+        } else if let Some(menu) = save_menu.as_mut().filter(|menu| menu.open) {
+            if menu.handle_input(&mut game, &mut save_slots) {
+                // A different run; the old one's checkpoints don't apply
+                checkpoints = checkpoints.map(|_| CheckpointMenu::new());
+            }
+        } else if codex.open {
+            codex.handle_input(&game, &hud);
+        } else if replay.is_playing() {
+            replay.update();
+        } else if checkpoints.as_mut().is_some_and(|menu| menu.handle_input(&mut game, &settings.input)) {
+            // The run is lost; the keys pick a checkpoint to restart from
+        } else if minimap.handle_click(&game, &mut camera, &hud) {
+            // Click went to the minimap, not the board
+        } else if presets.as_mut().is_some_and(|panel| panel.handle_input(&mut game, &settings.input, &hud)) {
+            // Mouse is on the preset list
+        } else if sandbox.as_mut().is_some_and(|panel| panel.handle_input(&mut game, &settings.input, &hud)) {
+            // Mouse is on the sandbox panel
+        } else if spectator.active || transcript.as_ref().is_some_and(|transcript| !transcript.is_finished()) {
+            spectator.handle_input(&mut camera); // Look around, but leave the play to the transcript
+        } else if let Some(panel) = attack.as_mut() {
+            if !panel.handle_input(&mut game, &settings.input, &hud) {
+                spectator.handle_input(&mut camera); // The board is the AI's; the player only sends waves
+            }
+        } else if let Some(guide) = tutorial.as_mut().filter(|guide| guide.is_active()) {
+            guide.handle_input(&mut game, &settings.input, &camera);
+        } else if selection.handle_input(&mut game, &settings.input, &camera) {
+            // A box select, group action or paste
+        } else {
+            handle_input(&mut game, &settings.input, &mut camera, &mut notifications);
+            let recognized = gestures.update(&touches(), get_time());
+            handle_touch(&mut game, &mut camera, &recognized, &mut radial_menu, hud.scale, &mut notifications);
         }
 
-        // Remove finished projectiles
-        for id in projectiles_to_remove {
-            self.projectiles.remove(&id);
-        }
-
-        // Apply damage
-        for (enemy_id, damage, tower_type, hit_x, hit_y) in hits {
-            self.apply_damage(enemy_id, damage, tower_type, hit_x, hit_y);
+        #[cfg(all(feature = "hot-reload", not(target_arch = "wasm32")))]
+        if let Some(watcher) = hot_reload.as_mut() {
+            if let Some(errors) = watcher.poll(&mut game) {
+                mod_errors = errors;
+            }
+            if watcher.assets_settled() {
+                assets::load(std::path::Path::new(assets::ASSETS_DIR), &mut board_cache, &mut postfx);
+            }
         }
-    }
-
-    fn apply_damage(&mut self, enemy_id: u32, damage: i32, tower_type: TowerType, hit_x: f32, hit_y: f32) {
-        match tower_type {
-            TowerType::Splash => {
-                // Splash damage to nearby enemies
-                let splash_radius = tower_type.splash_radius() * CELL_SIZE;
-                let enemies_to_damage: Vec<u32> = self.state.enemies
-                    .iter()
-                    .filter(|(_, enemy)| {
-                        let dx = enemy.x - hit_x;
-                        let dy = enemy.y - hit_y;
-                        let distance = (dx * dx + dy * dy).sqrt();
-                        distance <= splash_radius
-                    })
-                    .map(|(id, _)| *id)
-                    .collect();
 
-                for id in enemies_to_damage {
-                    if let Some(enemy) = self.state.enemies.get_mut(&id) {
-                        enemy.take_damage(damage);
+        // Update game; a replay freezes the live run until it ends, and
+        // nothing moves until the recovery offer is answered
+        if !replay.is_playing() && recovery_prompt.is_none() {
+            let speed = options.speed.unwrap_or(settings.game_speed as f32);
+            let sim_delta = juice.sim_delta(delta) * speed;
+            match transcript.as_mut().filter(|transcript| !transcript.is_finished()) {
+                Some(transcript) => transcript.advance(&mut game, sim_delta),
+                None => recovery::guarded(&mut game, autosaver.as_mut(), |game| {
+                    if let Some(ai) = &mut ai {
+                        ai.act(game);
                     }
-                }
-
-                // Create explosion effect
-                self.explosions.push(ExplosionEffect::new(
-                    hit_x,
-                    hit_y,
-                    tower_type.splash_radius(),
-                    ORANGE,
-                ));
+                    game.update(sim_delta);
+                }),
             }
-            TowerType::Slow => {
-                // Apply slow effect
-                if let Some(enemy) = self.state.enemies.get_mut(&enemy_id) {
-                    enemy.take_damage(damage);
-                    enemy.apply_slow(2.0, 0.5); // Slow for 2 seconds at 50% speed
-                }
+            leak_warnings.update(&game);
+            if let Some(menu) = &mut checkpoints {
+                menu.record(&game, settings.checkpoints);
             }
-            _ => {
-                // Regular single-target damage
-                if let Some(enemy) = self.state.enemies.get_mut(&enemy_id) {
-                    enemy.take_damage(damage);
-                }
+            if let Some(autosave) = &mut autosave {
+                autosave.update(&game, &mut save_slots, delta);
             }
-        }
-
-        // Remove dead enemies
-        let mut enemies_to_remove = Vec::new();
-        for (id, enemy) in self.state.enemies.iter() {
-            if !enemy.is_alive() {
-                enemies_to_remove.push(*id);
+            if let Some(saver) = &mut autosaver {
+                saver.update(&game, &settings, delta);
+            }
+            if let Some(guide) = &mut tutorial {
+                guide.update(&mut game, sim_delta);
+            }
+            juice.update(&game, &settings.display.juice, delta, sim_delta);
+            replay.record(&game);
+            let volume = if options.mute { 0.0 } else { settings.effects_level() };
+            for cue in cue_scheduler.cues(&game, delta) {
+                audio.play(cue, volume);
             }
         }
-
-        for id in enemies_to_remove {
-            self.state.enemies.remove(&id);
-            self.state.gold += 10; // Reward for killing enemy
+        debug_overlay.update(delta);
+        unlocks.update(&mut game, &mut notifications);
+        notifications.update(&game, delta);
+        dashboard.update(&game);
+        selection.update(&game, delta);
+        tooltips.update(&game, &camera, delta);
+        if let Some(panel) = &mut presets {
+            panel.update(delta);
         }
-    }
-
-    fn update_enemies(&mut self, delta: f32) {
-        let mut enemies_to_remove = Vec::new();
-
-        for (id, enemy) in self.state.enemies.iter_mut() {
-            let still_moving = enemy.update(delta);
-            if !still_moving {
-                // Enemy reached goal
-                enemies_to_remove.push(*id);
-                self.state.health -= 1;
-            }
+        if !rebind_screen.open && !save_menu.as_ref().is_some_and(|menu| menu.open) {
+            report_card.update(&game, &settings.input);
         }
 
-        for id in enemies_to_remove {
-            self.state.enemies.remove(&id);
+        // Render
+        clear_background(BLACK);
+        let shown = replay.view().unwrap_or(&game);
+        let world_camera = postfx.world_camera(&juice.shaken(&camera), &settings.display.post);
+        render_world(shown, &world_camera, &mut board_cache, &theme, &juice);
+        heatmap.render(shown, &world_camera);
+        route_preview.render(shown, &world_camera, &theme);
+        if !replay.is_playing() {
+            leak_warnings.render(&game, &world_camera, &theme);
         }
-    }
-
-    fn update_effects(&mut self, delta: f32) {
-        // Update muzzle flashes
-        self.muzzle_flashes.retain_mut(|flash| flash.update(delta));
-
-        // Update explosions
-        self.explosions.retain_mut(|explosion| explosion.update(delta));
-    }
-}
-
-// ============================================================================
-// RENDERING
-// ============================================================================
-
-pub fn render_game(game: &Game) {
-    // Draw grid
-    for x in 0..GRID_WIDTH {
-        for y in 0..GRID_HEIGHT {
-            let pos = Position::new(x, y);
-            let (wx, wy) = pos.to_world();
-            
-            let color = if !game.state.grid.is_walkable(&pos) {
-                Color::from_rgba(60, 60, 60, 255)  // Darker gray for towers
-            } else if pos == game.state.spawn_point {
-                Color::from_rgba(50, 150, 50, 255)  // Darker green for spawn
-            } else if pos == game.state.goal_point {
-                Color::from_rgba(150, 50, 50, 255)  // Darker red for goal
-            } else {
-                Color::from_rgba(30, 30, 30, 255)  // Dark gray for walkable
-            };
-            
-            draw_rectangle(wx, wy, CELL_SIZE, CELL_SIZE, color);
-            draw_rectangle_lines(wx, wy, CELL_SIZE, CELL_SIZE, 1.0, Color::from_rgba(50, 50, 50, 255));
+        postfx.composite(shown, &world_camera, &settings.display.post, &theme, delta);
+        render_hud(shown, &mod_errors, &settings.input, &hud, &theme);
+        minimap.render(shown, &camera, &hud, &theme);
+        dps_meter.render(shown, &hud, &theme);
+        report_card.render(&game, &settings.input, &hud, &theme);
+        replay.render_banner(&hud);
+        notifications.render(&hud, &theme);
+        if let Some(guide) = &tutorial {
+            guide.render(&game, &settings.input, &camera, &hud, &theme);
         }
-    }
-
-    // Draw towers
-    for tower in game.state.towers.values() {
-        let (x, y) = tower.position.to_world();
-        let center_x = x + CELL_SIZE / 2.0;
-        let center_y = y + CELL_SIZE / 2.0;
-        
-        // Draw range circle (subtle)
-        draw_circle_lines(
-            center_x,
-            center_y,
-            tower.tower_type.range() * CELL_SIZE,
-            1.0,
-            Color::from_rgba(100, 100, 100, 50),
-        );
-        
-        // Draw tower base
-        draw_circle(center_x, center_y, CELL_SIZE * 0.4, tower.tower_type.color());
-        
-        // Draw tower barrel/cannon (rotated)
-        let barrel_length = CELL_SIZE * 0.5;
-        let barrel_end_x = center_x + barrel_length * tower.rotation.cos();
-        let barrel_end_y = center_y + barrel_length * tower.rotation.sin();
-        draw_line(center_x, center_y, barrel_end_x, barrel_end_y, 4.0, DARKGRAY);
-        
-        // Draw cooldown indicator
-        if tower.cooldown_remaining > 0.0 {
-            let cooldown_ratio = tower.cooldown_remaining / (1.0 / tower.tower_type.fire_rate());
-            let _angle = cooldown_ratio * std::f32::consts::PI * 2.0;
-            draw_circle_lines(center_x, center_y, CELL_SIZE * 0.3, 2.0, YELLOW);
+        if let Some(panel) = &sandbox {
+            panel.render(&settings.input, &hud, &theme);
         }
-    }
-
-    // Draw projectiles
-    for projectile in game.projectiles.values() {
-        draw_circle(
-            projectile.x,
-            projectile.y,
-            5.0,
-            projectile.tower_type.projectile_color(),
-        );
-        
-        // Draw trail effect
-        let dx = projectile.target_x - projectile.x;
-        let dy = projectile.target_y - projectile.y;
-        let distance = (dx * dx + dy * dy).sqrt();
-        if distance > 0.0 {
-            let trail_length = 10.0;
-            let trail_x = projectile.x - (dx / distance) * trail_length;
-            let trail_y = projectile.y - (dy / distance) * trail_length;
-            
-            let mut trail_color = projectile.tower_type.projectile_color();
-            trail_color.a = 0.3;
-            draw_line(projectile.x, projectile.y, trail_x, trail_y, 3.0, trail_color);
+        if let Some(panel) = &attack {
+            panel.render(&game, &settings.input, &hud, &theme);
         }
-    }
-
-    // Draw muzzle flashes
-    for flash in &game.muzzle_flashes {
-        let mut color = flash.color;
-        color.a = flash.alpha();
-        draw_circle(flash.x, flash.y, 8.0, color);
-    }
-
-    // Draw explosions
-    for explosion in &game.explosions {
-        let mut color = explosion.color;
-        color.a = explosion.alpha() * 0.5;
-        draw_circle_lines(explosion.x, explosion.y, explosion.radius, 3.0, color);
-    }
-
-    // Draw enemies
-    for enemy in game.state.enemies.values() {
-        // Draw enemy body
-        let base_color = RED;
-        let color = if enemy.slow_duration > 0.0 {
-            SKYBLUE // Show when slowed
-        } else {
-            base_color
-        };
-        
-        draw_circle(enemy.x, enemy.y, CELL_SIZE * 0.3, color);
-        
-        // Draw health bar
-        let health_ratio = enemy.health as f32 / enemy.max_health as f32;
-        let bar_width = CELL_SIZE * 0.6;
-        let bar_height = 4.0;
-        let bar_x = enemy.x - bar_width / 2.0;
-        let bar_y = enemy.y - CELL_SIZE * 0.5;
-        
-        draw_rectangle(bar_x, bar_y, bar_width, bar_height, DARKGRAY);
-        draw_rectangle(
-            bar_x,
-            bar_y,
-            bar_width * health_ratio,
-            bar_height,
-            if health_ratio > 0.5 { GREEN } else { ORANGE },
-        );
-    }
-
-    // Draw UI
-    draw_text(
-        &format!("Gold: ${}", game.state.gold),
-        10.0,
-        25.0,
-        30.0,
-        GOLD,
-    );
-    draw_text(
-        &format!("Health: {}", game.state.health),
-        10.0,
-        55.0,
-        30.0,
-        RED,
-    );
-    draw_text(
-        &format!("Enemies: {}", game.state.enemies.len()),
-        10.0,
-        85.0,
-        30.0,
-        WHITE,
-    );
-    draw_text(
-        &format!("Towers: {}", game.state.towers.len()),
-        10.0,
-        115.0,
-        30.0,
-        WHITE,
-    );
-
-    if game.state.paused {
-        draw_text("PAUSED", 400.0, 300.0, 60.0, YELLOW);
-    }
-}
-
-#[macroquad::main("Rust Rush")]
-async fn main() {
-    let mut game = Game::new();
-    
-    loop {
-        let delta = get_frame_time();
-
-        // Handle input
-        if is_key_pressed(KeyCode::Space) {
-            game.state.paused = !game.state.paused;
+        if let Some(panel) = &presets {
+            panel.render(&game, &settings.input, &hud, &theme);
         }
-
-        if is_key_pressed(KeyCode::E) {
-            game.state.spawn_enemy();
+        if !replay.is_playing() {
+            selection.render(&game, &settings.input, &camera, &hud, &theme);
         }
-
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let (mx, my) = mouse_position();
-            let pos = Position::from_world(mx, my);
-            game.state.place_tower(TowerType::Basic, pos);
+        let menu_open = rebind_screen.open
+            || save_menu.as_ref().is_some_and(|menu| menu.open)
+            || codex.open
+            || recovery_prompt.is_some();
+        if !replay.is_playing() && !menu_open && !spectator.active && game.stroke.is_none() {
+            tooltips.render(&game, &hud, &theme);
         }
-
-        // Update game
-        game.update(delta);
-
-        // Render
-        clear_background(BLACK);
-        render_game(&game);
+        if let Some(menu) = &radial_menu {
+            render_radial_menu(&game, menu, &theme);
+        }
+        if spectator.active {
+            render_spectator_panel(&game, &hud, &theme);
+        }
+        if debug_overlay.open || debug_overlay.show_targeting {
+            render_debug_overlay(&game, &debug_overlay, &camera, &hud);
+        }
+        dashboard.render(&game, &hud, &theme);
+        if let (Some(menu), false) = (&checkpoints, replay.is_playing()) {
+            menu.render(&game, &settings.input, &hud, &theme); // Over the stats screen that opens on defeat
+        }
+        if let Some(menu) = &save_menu {
+            menu.render(&save_slots, &hud, &theme);
+        }
+        codex.render(&game, &hud, &theme);
+        if let Some(prompt) = &recovery_prompt {
+            prompt.render(&hud, &theme);
+        }
+        if rebind_screen.open {
+            rebind_screen.render(&settings);
+        }
+        if !typing {
+            capture.update(&settings.input, delta);
+        }
+        capture.render(&hud);
 
         next_frame().await;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,145 @@
+//! Corner minimap: terrain, tower and enemy dots, and the camera's view as a
+//! rectangle. Clicking it centers the camera on that spot. The terrain is a
+//! one-pixel-per-cell texture, rebuilt only when the grid's revision changes.
+
+use macroquad::prelude::*;
+use rust_rush_core::{Game, Grid, CELL_SIZE};
+
+use crate::camera::GameCamera;
+use crate::display::HudLayout;
+use crate::theme::{Palette, Theme};
+
+const MAX_SIZE: f32 = 160.0; // Longest side, in UI units
+const MARGIN: f32 = 10.0;
+
+pub struct Minimap {
+    pub visible: bool,
+    terrain: Option<Texture2D>,
+    built_for: Option<(u64, i32, i32, Palette)>, // Grid revision, size and palette of the texture
+}
+
+impl Minimap {
+    pub fn new() -> Self {
+        Minimap {
+            visible: true,
+            terrain: None,
+            built_for: None,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Where the minimap sits this frame, in UI units
+    fn frame(grid: &Grid, hud: &HudLayout) -> Rect {
+        let (cols, rows) = (grid.width() as f32, grid.height() as f32);
+        let cell = MAX_SIZE / cols.max(rows);
+        let (w, h) = (cols * cell, rows * cell);
+        let top = hud.play_area().y / hud.scale;
+        Rect::new(hud.width - w - MARGIN, top + MARGIN, w, h)
+    }
+
+    /// Jump the camera to a click on the minimap. True if the click was
+    /// used, so it shouldn't reach the board.
+    pub fn handle_click(&self, game: &Game, camera: &mut GameCamera, hud: &HudLayout) -> bool {
+        if !self.visible || !is_mouse_button_pressed(MouseButton::Left) {
+            return false;
+        }
+        let frame = Self::frame(&game.state.grid, hud);
+        let cursor = Vec2::from(mouse_position()) / hud.scale;
+        if !frame.contains(cursor) {
+            return false;
+        }
+        let world_per_unit = game.state.grid.width() as f32 * CELL_SIZE / frame.w;
+        camera.center = (cursor - frame.point()) * world_per_unit;
+        true
+    }
+
+    pub fn render(&mut self, game: &Game, camera: &GameCamera, hud: &HudLayout, theme: &Theme) {
+        if !self.visible {
+            return;
+        }
+        let grid = &game.state.grid;
+        let key = (grid.revision(), grid.width(), grid.height(), theme.palette);
+        if self.built_for != Some(key) {
+            self.terrain = Some(terrain_texture(game, theme));
+            self.built_for = Some(key);
+        }
+
+        set_camera(&hud.camera());
+        let frame = Self::frame(grid, hud);
+        draw_rectangle(frame.x - 2.0, frame.y - 2.0, frame.w + 4.0, frame.h + 4.0, Color::from_rgba(0, 0, 0, 200));
+        if let Some(terrain) = &self.terrain {
+            let params = DrawTextureParams { dest_size: Some(frame.size()), ..Default::default() };
+            draw_texture_ex(terrain, frame.x, frame.y, WHITE, params);
+        }
+
+        let units_per_world = frame.w / (grid.width() as f32 * CELL_SIZE);
+        let to_map = |x: f32, y: f32| vec2(frame.x + x * units_per_world, frame.y + y * units_per_world);
+        let dot = (CELL_SIZE * units_per_world * 0.35).max(1.5);
+        for tower in game.state.towers.values() {
+            let (x, y) = tower.world_position();
+            let at = to_map(x, y);
+            draw_rectangle(at.x - dot, at.y - dot, dot * 2.0, dot * 2.0, theme.tower_color(tower.tower_type));
+        }
+        for enemy in game.state.enemies.values().filter(|enemy| game.fog.can_see(enemy.x, enemy.y)) {
+            let at = to_map(enemy.x, enemy.y);
+            draw_circle(at.x, at.y, dot * 0.8, theme.enemy);
+        }
+
+        // Camera viewport, clipped to the map
+        let view = camera.visible_rect(screen_width(), screen_height());
+        let (top_left, bottom_right) = (to_map(view.x, view.y), to_map(view.right(), view.bottom()));
+        let left = top_left.x.max(frame.x);
+        let top = top_left.y.max(frame.y);
+        let right = bottom_right.x.min(frame.right());
+        let bottom = bottom_right.y.min(frame.bottom());
+        if right > left && bottom > top {
+            draw_rectangle_lines(left, top, right - left, bottom - top, 1.5, WHITE);
+        }
+        set_default_camera();
+    }
+}
+
+/// One pixel per cell, colored like the board
+fn terrain_texture(game: &Game, theme: &Theme) -> Texture2D {
+    let grid = &game.state.grid;
+    let (width, height) = (grid.width().max(1) as u16, grid.height().max(1) as u16);
+    let mut image = Image::gen_image_color(width, height, theme.cell);
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            // The cell under the pixel's center; on hex boards that's a
+            // neighbor for the squares between hexes
+            let pos = grid.cell_at((x as f32 + 0.5) * CELL_SIZE, (y as f32 + 0.5) * CELL_SIZE);
+            let color = if !grid.is_walkable(&pos) {
+                theme.cell_blocked
+            } else if game.state.is_spawn(&pos) {
+                theme.spawn
+            } else if game.state.is_goal(&pos) {
+                theme.goal
+            } else {
+                continue;
+            };
+            image.set_pixel(x as u32, y as u32, color);
+        }
+    }
+    let texture = Texture2D::from_image(&image);
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_keeps_board_aspect_in_top_right() {
+        let hud = HudLayout::new(1280.0, 720.0, 1.0);
+        let frame = Minimap::frame(&Grid::new(20, 15), &hud);
+        assert_eq!(frame.w, MAX_SIZE);
+        assert_eq!(frame.h, MAX_SIZE * 15.0 / 20.0);
+        assert_eq!(frame.right(), hud.width - MARGIN);
+        assert!(frame.y > hud.play_area().y - 0.01);
+    }
+}
@@ -0,0 +1,128 @@
+//! Finds mods on disk and feeds them to the core registry. Every folder in
+//! `mods/` with a `mod.json` is one mod; folders load in name order, so a
+//! `10-` prefix loads before `20-`. Web builds have no mods folder. Loading
+//! again mid-run swaps the content in place (see `hot_reload`).
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use rust_rush_core::mods::{ModManifest, ModRegistry};
+use rust_rush_core::Game;
+use tracing::{info, warn};
+
+pub const MODS_DIR: &str = "mods";
+const MANIFEST_FILE: &str = "mod.json";
+
+/// A mod folder that couldn't be loaded
+#[derive(Debug)]
+pub struct ModError {
+    pub folder: PathBuf,
+    pub reason: String,
+}
+
+impl fmt::Display for ModError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.folder.display(), self.reason)
+    }
+}
+
+/// Load every mod under `dir` into the game. Broken mods are skipped and
+/// returned so the menu can show them.
+pub fn load_into(game: &mut Game, dir: &Path) -> Vec<ModError> {
+    let Scan { registry, folders, errors } = scan(dir);
+    for error in &errors {
+        warn!(%error, "mod skipped");
+    }
+    for conflict in &registry.conflicts {
+        warn!(
+            field = %conflict.field,
+            loser = %conflict.loser,
+            winner = %conflict.winner,
+            "mods conflict; the later one wins"
+        );
+    }
+    if !registry.is_empty() {
+        info!(mods = ?registry.active, "mods loaded");
+    }
+
+    #[cfg(feature = "scripting")]
+    if let Some((name, script)) = registry.script() {
+        let folder = folders.iter().find(|(mod_name, _)| mod_name == name).map_or(dir, |(_, folder)| folder.as_path());
+        match rust_rush_core::scripting::ScriptHost::load(&folder.join(script)) {
+            Ok(host) => game.scripts = Some(host),
+            Err(err) => warn!(mod_name = name, %err, "mod script not loaded"),
+        }
+    }
+
+    #[cfg(not(feature = "scripting"))]
+    let _ = folders;
+
+    game.state.reload_content(registry);
+    errors
+}
+
+#[derive(Default)]
+struct Scan {
+    registry: ModRegistry,
+    folders: Vec<(String, PathBuf)>, // Where each active mod lives, for its files
+    errors: Vec<ModError>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn scan(dir: &Path) -> Scan {
+    let mut scan = Scan::default();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return scan; // No mods folder is the normal case
+    };
+
+    let mut folders: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()).collect();
+    folders.sort();
+
+    for folder in folders {
+        let manifest = std::fs::read_to_string(folder.join(MANIFEST_FILE))
+            .map_err(|err| err.to_string())
+            .and_then(|text| serde_json::from_str::<ModManifest>(&text).map_err(|err| err.to_string()));
+        match manifest {
+            Ok(manifest) if scan.registry.active.contains(&manifest.name) => scan.errors.push(ModError {
+                folder,
+                reason: format!("another mod is already named \"{}\"", manifest.name),
+            }),
+            Ok(manifest) => {
+                scan.registry.merge(&manifest);
+                scan.folders.push((manifest.name, folder));
+            }
+            Err(reason) => scan.errors.push(ModError { folder, reason }),
+        }
+    }
+    scan
+}
+
+#[cfg(target_arch = "wasm32")]
+fn scan(_dir: &Path) -> Scan {
+    Scan::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_rush_core::status_effects::Stacking;
+
+    #[test]
+    fn test_scan_loads_template_and_skips_broken_folders() {
+        let dir = std::env::temp_dir().join(format!("rust-rush-mods-{}", std::process::id()));
+        let template = Path::new(env!("CARGO_MANIFEST_DIR")).join("mod-template");
+        std::fs::create_dir_all(dir.join("10-template")).unwrap();
+        std::fs::create_dir_all(dir.join("20-broken")).unwrap();
+        std::fs::copy(template.join(MANIFEST_FILE), dir.join("10-template").join(MANIFEST_FILE)).unwrap();
+        std::fs::write(dir.join("20-broken").join(MANIFEST_FILE), "{ not json").unwrap();
+
+        let scan = scan(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(scan.registry.active, vec!["example-balance"]);
+        assert_eq!(scan.registry.effect_rules().slow, Stacking::DiminishingReturns { falloff: 0.5 });
+        assert_eq!(scan.folders[0].1, dir.join("10-template"));
+        assert_eq!(scan.errors.len(), 1);
+        assert_eq!(scan.errors[0].folder, dir.join("20-broken"));
+    }
+}
@@ -0,0 +1,152 @@
+//! Toasts for short-lived messages: achievements, the next wave closing in,
+//! and actions the game refused, with why (see `ActionError`). They stack in the bottom-right
+//! corner, newest at the bottom, and fade out on a timer; the border takes
+//! the message's severity color. Past `MAX_SHOWN` the rest wait their turn,
+//! and a message already on screen is refreshed rather than stacked again.
+
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+use rust_rush_core::actions::ActionError;
+use rust_rush_core::waves::WavePhase;
+use rust_rush_core::Game;
+
+use crate::display::HudLayout;
+use crate::theme::Theme;
+
+const SHOW_SECONDS: f32 = 4.0;
+const FADE_SECONDS: f32 = 0.5;
+const MAX_SHOWN: usize = 4;
+const WAVE_WARNING: f32 = 5.0; // Seconds of build phase left when the next wave is announced
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn color(self, theme: &Theme) -> Color {
+        match self {
+            Severity::Info => theme.text,
+            Severity::Success => theme.health_high,
+            Severity::Warning => theme.gold,
+            Severity::Error => theme.danger,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Toast {
+    severity: Severity,
+    title: String,
+    detail: String, // Second line; may be empty
+    remaining: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct Notifications {
+    shown: Vec<Toast>,
+    queued: VecDeque<Toast>,
+    warned_wave: u32, // Last wave announced as incoming
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, severity: Severity, title: impl Into<String>, detail: impl Into<String>) {
+        let toast = Toast { severity, title: title.into(), detail: detail.into(), remaining: SHOW_SECONDS };
+        let same = |other: &Toast| (other.severity, &other.title, &other.detail) == (toast.severity, &toast.title, &toast.detail);
+        if let Some(shown) = self.shown.iter_mut().find(|shown| same(shown)) {
+            shown.remaining = SHOW_SECONDS;
+        } else if !self.queued.iter().any(same) {
+            self.queued.push_back(toast);
+        }
+        self.promote();
+    }
+
+    /// An action the game refused, e.g. `refused("Can't build", err)`
+    pub fn refused(&mut self, title: &str, err: ActionError) {
+        let severity = match err {
+            ActionError::InsufficientGold { .. } => Severity::Warning,
+            _ => Severity::Error,
+        };
+        self.push(severity, title, err.to_string());
+    }
+
+    fn promote(&mut self) {
+        while self.shown.len() < MAX_SHOWN {
+            let Some(toast) = self.queued.pop_front() else {
+                break;
+            };
+            self.shown.push(toast);
+        }
+    }
+
+    /// Age the toasts, and announce the next wave as its countdown runs out
+    pub fn update(&mut self, game: &Game, delta: f32) {
+        let waves = &game.state.waves;
+        if let WavePhase::Build { countdown } = waves.phase {
+            if waves.auto_start.is_some() && countdown <= WAVE_WARNING && self.warned_wave != waves.wave {
+                self.warned_wave = waves.wave;
+                self.push(Severity::Warning, format!("Wave {} incoming", waves.wave), format!("{} enemies", waves.size()));
+            }
+        }
+
+        for toast in &mut self.shown {
+            toast.remaining -= delta;
+        }
+        self.shown.retain(|toast| toast.remaining > 0.0);
+        self.promote();
+    }
+
+    pub fn render(&self, hud: &HudLayout, theme: &Theme) {
+        if self.shown.is_empty() {
+            return;
+        }
+        set_camera(&hud.camera());
+        let (width, height) = (300.0, 48.0);
+        for (i, toast) in self.shown.iter().enumerate() {
+            let x = hud.width - width - 12.0;
+            let y = hud.height - 90.0 - (self.shown.len() - i) as f32 * (height + 6.0);
+            let alpha = (toast.remaining / FADE_SECONDS).min(1.0);
+            let mut border = toast.severity.color(theme);
+            border.a = alpha;
+            draw_rectangle(x, y, width, height, Color::new(0.1, 0.1, 0.1, 0.85 * alpha));
+            draw_rectangle_lines(x, y, width, height, 2.0, border);
+            let title_y = if toast.detail.is_empty() { y + 30.0 } else { y + 20.0 };
+            draw_text(&toast.title, x + 10.0, title_y, 20.0, Color::new(1.0, 1.0, 1.0, alpha));
+            draw_text(&toast.detail, x + 10.0, y + 38.0, 16.0, Color::new(0.8, 0.8, 0.8, alpha));
+        }
+        set_default_camera();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_queue_past_the_limit_and_repeats_refresh() {
+        let mut notifications = Notifications::new();
+        for i in 0..6 {
+            notifications.push(Severity::Info, format!("Message {}", i), "");
+        }
+        assert_eq!((notifications.shown.len(), notifications.queued.len()), (MAX_SHOWN, 2));
+
+        // The same refusal twice is one toast
+        notifications.refused("Can't build", ActionError::WouldBlockPath);
+        notifications.refused("Can't build", ActionError::WouldBlockPath);
+        assert_eq!(notifications.queued.len(), 3);
+
+        let game = Game::new();
+        notifications.update(&game, SHOW_SECONDS + 0.1);
+        assert_eq!(notifications.shown[0].title, "Message 4");
+        assert_eq!(notifications.shown[2].severity, Severity::Error);
+        assert!(notifications.queued.is_empty());
+    }
+}
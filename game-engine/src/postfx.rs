@@ -0,0 +1,240 @@
+//! Post-processing. The world layers draw into an offscreen target instead
+//! of the screen, projectiles are drawn a second time into a half-size glow
+//! target, and one shader pass puts the scene on screen with:
+//!
+//! - bloom around projectiles and muzzle flashes (the blurred glow target)
+//! - a red vignette that deepens as the base runs out of health
+//! - grayscale, faded in while paused or after the base falls
+//! - an optional CRT look: curved screen and scanlines
+//!
+//! The HUD draws afterwards, untouched. Each effect can be switched off in
+//! `settings.json`; with `enabled` off the world draws straight to the
+//! screen as before. The shaders below can be replaced from the assets
+//! folder (see `assets`).
+
+use std::path::Path;
+
+use macroquad::prelude::*;
+use rust_rush_core::{Game, PROJECTILE_RADIUS, STARTING_HEALTH};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::assets;
+use crate::camera::GameCamera;
+use crate::theme::Theme;
+
+const LOW_HEALTH: f32 = 0.4; // Vignette starts below this share of the starting health
+const GRAYSCALE_FADE: f32 = 3.0; // Per second
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostFxSettings {
+    pub enabled: bool,
+    pub bloom: bool,
+    pub vignette: bool,
+    pub grayscale: bool,
+    pub crt: bool,
+}
+
+impl Default for PostFxSettings {
+    fn default() -> Self {
+        PostFxSettings { enabled: true, bloom: true, vignette: true, grayscale: true, crt: false }
+    }
+}
+
+struct Targets {
+    scene: RenderTarget,
+    glow: RenderTarget,
+    size: (u32, u32),
+}
+
+pub struct PostFx {
+    material: Option<Material>, // None if the shader failed to build
+    targets: Option<Targets>,
+    grayscale: f32, // Eased toward 1 while paused or lost
+}
+
+impl PostFx {
+    pub fn new() -> Self {
+        let material = build_material(VERTEX_SHADER, FRAGMENT_SHADER)
+            .inspect_err(|err| warn!(%err, "post-processing shader failed; drawing without it"))
+            .ok();
+        PostFx { material, targets: None, grayscale: 0.0 }
+    }
+
+    /// Swap in `postfx.vert` and `postfx.frag` from `dir`, each falling back
+    /// to the built-in shader when missing. If either can't be read or the
+    /// pair won't compile, the shader in use stays.
+    pub fn load_shaders(&mut self, dir: &Path) {
+        let read = |name: &str| -> Result<Option<String>, String> {
+            let bytes = assets::read_optional(&dir.join(name))?;
+            bytes.map(|bytes| String::from_utf8(bytes).map_err(|err| format!("{}: {}", name, err))).transpose()
+        };
+        let sources = read("postfx.vert").and_then(|vertex| Ok((vertex, read("postfx.frag")?)));
+        let (vertex, fragment) = match sources {
+            Ok(sources) => sources,
+            Err(err) => {
+                warn!(%err, "post-processing shaders not loaded; keeping the current ones");
+                return;
+            }
+        };
+        let vertex = vertex.as_deref().unwrap_or(VERTEX_SHADER);
+        let fragment = fragment.as_deref().unwrap_or(FRAGMENT_SHADER);
+        match build_material(vertex, fragment) {
+            Ok(material) => {
+                info!(dir = %dir.display(), "post-processing shaders loaded");
+                self.material = Some(material);
+            }
+            Err(err) => warn!(%err, "post-processing shaders failed to compile; keeping the current ones"),
+        }
+    }
+
+    /// The camera world layers should draw through this frame: the offscreen
+    /// scene, cleared, or the screen itself when post-processing is off
+    pub fn world_camera(&mut self, camera: &GameCamera, settings: &PostFxSettings) -> GameCamera {
+        if !settings.enabled || self.material.is_none() {
+            self.targets = None; // Free the textures
+            return camera.clone();
+        }
+        let size = (screen_width().max(1.0) as u32, screen_height().max(1.0) as u32);
+        if self.targets.as_ref().is_none_or(|targets| targets.size != size) {
+            let scene = render_target(size.0, size.1);
+            let glow = render_target((size.0 / 2).max(1), (size.1 / 2).max(1));
+            scene.texture.set_filter(FilterMode::Linear);
+            glow.texture.set_filter(FilterMode::Linear);
+            self.targets = Some(Targets { scene, glow, size });
+        }
+        let scene = self.targets.as_ref().map(|targets| targets.scene.clone());
+        let world = GameCamera { target: scene, ..camera.clone() };
+        set_camera(&world.camera2d());
+        clear_background(BLACK);
+        world
+    }
+
+    /// Draw the finished scene to the screen through the effects shader
+    pub fn composite(&mut self, game: &Game, camera: &GameCamera, settings: &PostFxSettings, theme: &Theme, delta: f32) {
+        let lost_or_paused = game.state.paused || game.state.health <= 0;
+        let goal = if lost_or_paused && settings.grayscale { 1.0 } else { 0.0 };
+        self.grayscale += (goal - self.grayscale).clamp(-GRAYSCALE_FADE * delta, GRAYSCALE_FADE * delta);
+        let (Some(targets), Some(material)) = (&self.targets, &self.material) else {
+            return;
+        };
+
+        if settings.bloom {
+            render_glow(game, &GameCamera { target: Some(targets.glow.clone()), ..camera.clone() }, theme);
+        }
+
+        let health = game.state.health.max(0) as f32 / STARTING_HEALTH as f32;
+        let vignette = if settings.vignette { (1.0 - health / LOW_HEALTH).clamp(0.0, 1.0) } else { 0.0 };
+        let (width, height) = targets.size;
+        material.set_uniform("texel", vec2(1.0 / width as f32, 1.0 / height as f32));
+        material.set_uniform("bloom", if settings.bloom { 1.0f32 } else { 0.0 });
+        material.set_uniform("vignette", vignette);
+        material.set_uniform("grayscale", self.grayscale);
+        material.set_uniform("crt", if settings.crt { 1.0f32 } else { 0.0 });
+        material.set_texture("Glow", targets.glow.texture.clone());
+
+        set_default_camera();
+        gl_use_material(material);
+        let params = DrawTextureParams { dest_size: Some(vec2(screen_width(), screen_height())), ..Default::default() };
+        draw_texture_ex(&targets.scene.texture, 0.0, 0.0, WHITE, params);
+        gl_use_default_material();
+    }
+}
+
+fn build_material(vertex: &str, fragment: &str) -> Result<Material, macroquad::Error> {
+    let params = MaterialParams {
+        uniforms: vec![
+            UniformDesc::new("texel", UniformType::Float2),
+            UniformDesc::new("bloom", UniformType::Float1),
+            UniformDesc::new("vignette", UniformType::Float1),
+            UniformDesc::new("grayscale", UniformType::Float1),
+            UniformDesc::new("crt", UniformType::Float1),
+        ],
+        textures: vec!["Glow".to_string()],
+        ..Default::default()
+    };
+    load_material(ShaderSource::Glsl { vertex, fragment }, params)
+}
+
+/// The bright things that should bloom, on black
+fn render_glow(game: &Game, camera: &GameCamera, theme: &Theme) {
+    set_camera(&camera.camera2d());
+    clear_background(BLACK);
+    for projectile in game.projectiles.values() {
+        let color = theme.projectile_color(projectile.tower_type);
+        draw_circle(projectile.x, projectile.y, PROJECTILE_RADIUS * 2.5, color);
+    }
+    for flash in &game.muzzle_flashes {
+        let mut color = theme.effect_color(flash.style);
+        color.a = flash.alpha();
+        draw_circle(flash.x, flash.y, 12.0, color);
+    }
+}
+
+const VERTEX_SHADER: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    uv = texcoord;
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"#version 100
+precision mediump float;
+
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+uniform sampler2D Glow;
+uniform vec2 texel;
+uniform float bloom;
+uniform float vignette;
+uniform float grayscale;
+uniform float crt;
+
+void main() {
+    vec2 at = uv;
+    if (crt > 0.5) {
+        // Bulge the picture like a curved tube; black past the edges
+        vec2 centered = at * 2.0 - 1.0;
+        centered *= 1.0 + 0.05 * dot(centered.yx, centered.yx);
+        at = centered * 0.5 + 0.5;
+        if (at.x < 0.0 || at.x > 1.0 || at.y < 0.0 || at.y > 1.0) {
+            gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+            return;
+        }
+    }
+
+    vec3 color = texture2D(Texture, at).rgb;
+    if (bloom > 0.5) {
+        // Wide, cheap blur of the glow target
+        vec3 glow = vec3(0.0);
+        for (int x = -2; x <= 2; x++) {
+            for (int y = -2; y <= 2; y++) {
+                glow += texture2D(Glow, at + vec2(float(x), float(y)) * texel * 4.0).rgb;
+            }
+        }
+        color += glow / 25.0 * 1.5;
+    }
+    if (crt > 0.5) {
+        color *= 0.85 + 0.15 * sin(at.y / texel.y * 3.14159);
+    }
+
+    float gray = dot(color, vec3(0.299, 0.587, 0.114));
+    color = mix(color, vec3(gray), grayscale);
+
+    float edge = smoothstep(0.35, 0.85, distance(uv, vec2(0.5)));
+    color = mix(color, vec3(0.45, 0.0, 0.0), edge * vignette * 0.8);
+
+    gl_FragColor = vec4(color, 1.0);
+}
+"#;
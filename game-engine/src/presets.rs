@@ -0,0 +1,136 @@
+//! The starting loadout panel (see `rust_rush_core::presets`). The save
+//! preset key keeps the towers standing now as a new preset, at any point
+//! in a run. Before the first wave the saved presets are listed down the
+//! left of the screen with their price under this run's rules; clicking one
+//! puts it down, and right-clicking deletes it. Presets live in
+//! `presets.json` beside the save slots, so they carry across runs and maps.
+
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+use rust_rush_core::presets::Preset;
+use rust_rush_core::Game;
+use tracing::warn;
+
+use crate::display::HudLayout;
+use crate::input::{Action, InputConfig};
+use crate::saves::backend;
+use crate::theme::Theme;
+
+const PRESETS_FILE: &str = "presets.json";
+const PANEL_WIDTH: f32 = 240.0;
+const TOP: f32 = 150.0; // Below the HUD text
+const ROW_HEIGHT: f32 = 22.0;
+const STATUS_TIME: f32 = 3.0;
+
+pub struct PresetPanel {
+    path: PathBuf,
+    presets: Vec<Preset>,
+    status: String,     // What the last save, apply or delete did
+    status_left: f32,   // Seconds the status stays up once the panel's gone
+}
+
+impl PresetPanel {
+    pub fn open() -> Self {
+        let path = if cfg!(target_arch = "wasm32") {
+            PathBuf::from(PRESETS_FILE) // A LocalStorage key
+        } else {
+            crate::settings::data_dir().unwrap_or_default().join(PRESETS_FILE)
+        };
+        let presets = backend::read(&path)
+            .and_then(|json| {
+                serde_json::from_str(&json).map_err(|err| warn!(%err, "presets file unreadable; starting empty")).ok()
+            })
+            .unwrap_or_default();
+        PresetPanel { path, presets, status: String::new(), status_left: 0.0 }
+    }
+
+    /// The save key, and clicks on the list. True if the mouse went to the
+    /// panel, so it shouldn't reach the board.
+    pub fn handle_input(&mut self, game: &mut Game, input: &InputConfig, hud: &HudLayout) -> bool {
+        if input.pressed(Action::SavePreset) {
+            self.save(game);
+        }
+        if !game.before_first_wave() {
+            return false;
+        }
+        let cursor = Vec2::from(mouse_position()) / hud.scale;
+        let row = (0..self.presets.len()).find(|&index| Self::row(index).contains(cursor));
+        match row {
+            Some(index) if is_mouse_button_pressed(MouseButton::Left) => {
+                let preset = &self.presets[index];
+                let result = game.apply_preset(preset).map(|placed| format!("{}: {} towers placed", preset.name, placed));
+                self.report(result.map_err(|err| format!("{}: {}", preset.name, err)));
+            }
+            Some(index) if is_mouse_button_pressed(MouseButton::Right) => {
+                let preset = self.presets.remove(index);
+                self.report(self.write().map(|()| format!("Deleted {}", preset.name)));
+            }
+            _ => {}
+        }
+        !self.presets.is_empty() && Self::frame(self.presets.len()).contains(cursor)
+    }
+
+    fn save(&mut self, game: &Game) {
+        if game.state.towers.is_empty() {
+            return self.report(Err("No towers to save as a preset".to_string()));
+        }
+        let taken = |name: &str| self.presets.iter().any(|preset| preset.name == name);
+        let name = (1..).map(|n| format!("Preset {}", n)).find(|name| !taken(name)).unwrap_or_default();
+        let preset = Preset::capture(&name, &game.state);
+        let saved = format!("Saved {} ({} towers)", name, preset.towers.len());
+        self.presets.push(preset);
+        self.report(self.write().map(|()| saved));
+    }
+
+    fn write(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.presets).map_err(|err| err.to_string())?;
+        backend::write(&self.path, &json)
+    }
+
+    fn report(&mut self, result: Result<String, String>) {
+        self.status = result.unwrap_or_else(|err| {
+            warn!(%err, "preset");
+            err
+        });
+        self.status_left = STATUS_TIME;
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        self.status_left = (self.status_left - delta).max(0.0);
+    }
+
+    pub fn render(&self, game: &Game, input: &InputConfig, hud: &HudLayout, theme: &Theme) {
+        set_camera(&hud.camera());
+        let listed = game.before_first_wave();
+        if listed {
+            let frame = Self::frame(self.presets.len());
+            draw_rectangle(frame.x, frame.y, frame.w, frame.h, Color::new(0.0, 0.0, 0.0, 0.75));
+            draw_rectangle_lines(frame.x, frame.y, frame.w, frame.h, 2.0, theme.cell_border);
+            draw_text("PRESETS", frame.x + 10.0, frame.y + 20.0, 18.0, theme.text);
+            let help = format!("[{}] save   Right-click: delete", input.label(Action::SavePreset));
+            draw_text(help, frame.x + 10.0, frame.y + frame.h - 8.0, 14.0, GRAY);
+            for (index, preset) in self.presets.iter().enumerate() {
+                let row = Self::row(index);
+                let cost = preset.cost(&game.state);
+                let color = if cost <= game.state.gold { theme.gold } else { GRAY };
+                let label = format!("{}  ${}  ({} towers)", preset.name, cost, preset.towers.len());
+                draw_text(label, row.x, row.y + 16.0, 18.0, color);
+            }
+        }
+        if listed || self.status_left > 0.0 {
+            let y = Self::frame(self.presets.len()).bottom() + 18.0;
+            draw_text(&self.status, 10.0, if listed { y } else { TOP }, 16.0, theme.gold);
+        }
+        set_default_camera();
+    }
+
+    /// Down the left side, under the HUD text and clear of the ability bar
+    fn frame(presets: usize) -> Rect {
+        Rect::new(10.0, TOP, PANEL_WIDTH, 48.0 + presets as f32 * ROW_HEIGHT)
+    }
+
+    fn row(index: usize) -> Rect {
+        Rect::new(20.0, TOP + 26.0 + index as f32 * ROW_HEIGHT, PANEL_WIDTH - 20.0, ROW_HEIGHT)
+    }
+}
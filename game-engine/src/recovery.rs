@@ -0,0 +1,279 @@
+//! Rolling autosaves and crash recovery. A run is autosaved every
+//! `Settings::autosave_interval` seconds and as every `autosave_waves`th
+//! wave starts, taking turns between `ROLLING_SAVES` files beside the save
+//! slots (see `saves`). `session.json` there names the newest. It's written
+//! when the game starts and deleted when the window is closed, so finding
+//! one at launch means the last session didn't shut down cleanly, and the
+//! game offers to play on from it before anything moves.
+//!
+//! A panic writes its message into the session file, and a panic in the
+//! simulation also dumps the run as it stood to `CRASH_DUMP`. Recovery
+//! tries the dump first and falls back to the newest autosave if it won't
+//! load. Closing a browser tab isn't a clean quit, so on the web the offer
+//! comes up after every session that played long enough to autosave.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::*;
+use rust_rush_core::savegame::SavedRun;
+use rust_rush_core::Game;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::display::HudLayout;
+use crate::saves::{backend, decode_run, encode_run, SaveSlots};
+use crate::settings::Settings;
+use crate::theme::Theme;
+
+const SESSION_FILE: &str = "session.json";
+const CRASH_DUMP: &str = "crash-dump.sav";
+const ROLLING_SAVES: u32 = 3;
+const PANEL_WIDTH: f32 = 520.0;
+
+/// What a session left behind for the next one
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub latest: Option<u32>,      // The newest rolling autosave
+    pub wave: u32,                // The wave it was taken on
+    pub next: u32,                // The rolling autosave to write next
+    pub crashed: Option<String>,  // The panic, if the session died of one
+    pub dumped: Option<u32>,      // The wave a crash dump was taken on
+}
+
+impl Session {
+    fn read(root: &Path) -> Option<Session> {
+        let json = backend::read(&root.join(SESSION_FILE))?;
+        Some(serde_json::from_str(&json).unwrap_or_else(|err| {
+            warn!(%err, "session file unreadable");
+            Session::default()
+        }))
+    }
+
+    fn write(&self, root: &Path) {
+        let written = serde_json::to_string(self).map_err(|err| err.to_string());
+        if let Err(err) = written.and_then(|json| backend::write(&root.join(SESSION_FILE), &json)) {
+            warn!(%err, "session file not written");
+        }
+    }
+
+    fn recoverable(&self) -> bool {
+        self.latest.is_some() || self.dumped.is_some()
+    }
+
+    /// The crash dump if there is one and it loads, else the newest autosave
+    fn recover(&self, root: &Path) -> Result<SavedRun, String> {
+        let read = |name: &str| backend::read_bytes(&root.join(name)).ok_or_else(|| format!("{} is missing", name));
+        if self.dumped.is_some() {
+            match read(CRASH_DUMP).and_then(|bytes| decode_run(&bytes)) {
+                Ok(saved) => return Ok(saved),
+                Err(err) => warn!(%err, "crash dump unusable; trying the last autosave"),
+            }
+        }
+        let latest = self.latest.ok_or("no autosave to fall back on")?;
+        read(&rolling_file(latest)).and_then(|bytes| decode_run(&bytes))
+    }
+}
+
+fn rolling_file(turn: u32) -> String {
+    format!("autosave-{}.sav", turn)
+}
+
+/// Keeps this session's autosaves and session file
+pub struct Autosaver {
+    root: PathBuf,
+    text_saves: bool,
+    session: Session,
+    since_save: f32,
+    last_wave: u32,
+}
+
+impl Autosaver {
+    /// Start a session. Also returns the last one if it didn't shut down
+    /// cleanly and left something to recover.
+    pub fn start(slots: &SaveSlots) -> (Self, Option<Session>) {
+        let root = slots.root().to_path_buf();
+        let previous = Session::read(&root);
+        if let Some(session) = &previous {
+            warn!(crashed = ?session.crashed, "last session didn't shut down cleanly");
+        }
+        // Carry on the rotation, so the last session's newest autosave
+        // outlives this one's first
+        let session = Session { next: previous.as_ref().map_or(0, |session| session.next), ..Session::default() };
+        session.write(&root);
+        let saver = Autosaver { root, text_saves: slots.text_saves, session, since_save: 0.0, last_wave: 0 };
+        (saver, previous.filter(Session::recoverable))
+    }
+
+    /// Record panics in the session file, on top of the usual report
+    pub fn install_panic_hook(&self) {
+        let root = self.root.clone();
+        let report = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            error!(%info, "panic");
+            let mut session = Session::read(&root).unwrap_or_default();
+            session.crashed = Some(info.to_string());
+            session.write(&root);
+            report(info);
+        }));
+    }
+
+    /// After each update: autosave if the timer's up or a wave has started
+    pub fn update(&mut self, game: &Game, settings: &Settings, delta: f32) {
+        if game.state.health <= 0 || game.state.objective.is_decided() {
+            return; // Nothing left to play on from
+        }
+        self.since_save += delta;
+        let wave = game.state.waves.wave;
+        let wave_started = wave != self.last_wave && !game.state.waves.is_build_phase();
+        if wave_started {
+            self.last_wave = wave;
+        }
+        let timer = settings.autosave_interval > 0 && self.since_save >= settings.autosave_interval as f32;
+        let on_wave = wave_started && settings.autosave_waves > 0 && wave.is_multiple_of(settings.autosave_waves);
+        if timer || on_wave {
+            self.save(game);
+        }
+    }
+
+    fn save(&mut self, game: &Game) {
+        self.since_save = 0.0;
+        let turn = self.session.next;
+        let path = self.root.join(rolling_file(turn));
+        match encode_run(game, self.text_saves).and_then(|bytes| backend::write_bytes(&path, &bytes)) {
+            Ok(()) => {
+                self.session.latest = Some(turn);
+                self.session.wave = game.state.waves.wave;
+                self.session.next = (turn + 1) % ROLLING_SAVES;
+                self.session.write(&self.root);
+            }
+            Err(err) => warn!(%err, "autosave failed"),
+        }
+    }
+
+    /// Save the run as it stood when the simulation panicked
+    fn dump(&mut self, game: &Game) {
+        let path = self.root.join(CRASH_DUMP);
+        match encode_run(game, self.text_saves).and_then(|bytes| backend::write_bytes(&path, &bytes)) {
+            Ok(()) => {
+                self.session.crashed = Session::read(&self.root).and_then(|session| session.crashed);
+                self.session.dumped = Some(game.state.waves.wave);
+                self.session.write(&self.root);
+                error!("run dumped to {}", CRASH_DUMP);
+            }
+            Err(err) => error!(%err, "crash dump failed"),
+        }
+    }
+
+    /// The window was closed: this session ended cleanly
+    pub fn finish(&self) {
+        if let Err(err) = backend::remove(&self.root.join(SESSION_FILE)) {
+            warn!(%err, "session file not removed; next launch will offer a recovery");
+        }
+    }
+}
+
+/// Run a simulation step, dumping the run if it panics before the panic
+/// carries on
+pub fn guarded(game: &mut Game, saver: Option<&mut Autosaver>, step: impl FnOnce(&mut Game)) {
+    if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| step(game))) {
+        if let Some(saver) = saver {
+            saver.dump(game);
+        }
+        panic::resume_unwind(panic);
+    }
+}
+
+/// The "recover last session?" offer shown at launch
+pub struct RecoveryPrompt {
+    session: Session,
+    status: String, // Why a recovery failed
+}
+
+impl RecoveryPrompt {
+    pub fn new(session: Session) -> Self {
+        RecoveryPrompt { session, status: String::new() }
+    }
+
+    /// Y (or Enter) recovers, N (or Escape) starts fresh. True once it's
+    /// answered; a failed recovery keeps asking.
+    pub fn handle_input(&mut self, game: &mut Game, slots: &SaveSlots) -> bool {
+        let root = slots.root();
+        if is_key_pressed(KeyCode::Y) || is_key_pressed(KeyCode::Enter) {
+            match self.session.recover(root) {
+                Ok(saved) => {
+                    info!(wave = saved.state.waves.wave, "last session recovered");
+                    game.resume(saved);
+                }
+                Err(err) => {
+                    self.status = format!("Couldn't recover: {}", err);
+                    return false;
+                }
+            }
+        } else if !(is_key_pressed(KeyCode::N) || is_key_pressed(KeyCode::Escape)) {
+            return false;
+        }
+        // A dump is only good for the session that crashed
+        if let Err(err) = backend::remove(&root.join(CRASH_DUMP)) {
+            warn!(%err, "crash dump not removed");
+        }
+        true
+    }
+
+    pub fn render(&self, hud: &HudLayout, theme: &Theme) {
+        set_camera(&hud.camera());
+        let (x, y) = ((hud.width - PANEL_WIDTH) / 2.0, hud.height / 2.0 - 70.0);
+        draw_rectangle(x, y, PANEL_WIDTH, 140.0, Color::new(0.0, 0.0, 0.0, 0.85));
+        draw_rectangle_lines(x, y, PANEL_WIDTH, 140.0, 2.0, theme.cell_border);
+        draw_text("RECOVER LAST SESSION?", x + 12.0, y + 28.0, 26.0, theme.text);
+        let cause = if self.session.crashed.is_some() { "crashed" } else { "didn't shut down cleanly" };
+        let wave = self.session.dumped.unwrap_or(self.session.wave);
+        draw_text(format!("The last session {}. It was on wave {}.", cause, wave), x + 12.0, y + 56.0, 18.0, theme.text);
+        if let Some(crash) = &self.session.crashed {
+            let crash: String = crash.chars().take(70).collect();
+            draw_text(crash, x + 12.0, y + 78.0, 14.0, GRAY);
+        }
+        draw_text("Y: recover it   N: start fresh", x + 12.0, y + 106.0, 18.0, theme.gold);
+        draw_text(&self.status, x + 12.0, y + 130.0, 16.0, theme.danger);
+        set_default_camera();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unclean_session_recovers_newest_autosave() {
+        let root = std::env::temp_dir().join(format!("rust-rush-recovery-{}", std::process::id()));
+        let slots = SaveSlots::open_at(root.clone());
+        let settings = Settings { autosave_interval: 30, ..Settings::new() };
+        let (mut saver, previous) = Autosaver::start(&slots);
+        assert!(previous.is_none());
+
+        // Four timed saves take turns between the three files
+        let mut game = Game::new();
+        for _ in 0..4 {
+            game.state.gold += 10;
+            saver.update(&game, &settings, 30.0);
+        }
+        assert_eq!((saver.session.latest, saver.session.next), (Some(0), 1));
+
+        // Never finished, so the next launch offers it
+        let (mut saver, previous) = Autosaver::start(&slots);
+        let previous = previous.unwrap();
+        assert_eq!(previous.recover(&root).unwrap().state.gold, game.state.gold);
+        assert_eq!(saver.session.next, 1);
+
+        // A crash dump is preferred over the autosave
+        game.state.gold += 5;
+        saver.dump(&game);
+        let (saver, previous) = Autosaver::start(&slots);
+        assert_eq!(previous.unwrap().recover(&root).unwrap().state.gold, game.state.gold);
+
+        saver.finish();
+        assert!(Autosaver::start(&slots).1.is_none());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,1071 @@
+//! Drawing the board and the HUD: the world through the game camera, then
+//! the wave, ability, objective and mod panels in HUD space, plus the
+//! debug overlay and spectator panel.
+
+use macroquad::prelude::*;
+use rust_rush_core::convoys;
+use rust_rush_core::daynight::TimeOfDay;
+use rust_rush_core::gunners::EnemyKind;
+use rust_rush_core::hero::Hero;
+use rust_rush_core::merging;
+use rust_rush_core::obstacles::ObstacleKind;
+use rust_rush_core::portals::PortalStage;
+use rust_rush_core::power::POWER_RADIUS;
+use rust_rush_core::stat_sheet::Stat;
+use rust_rush_core::status_effects::EffectKind;
+use rust_rush_core::traps::TrapType;
+use rust_rush_core::victory::{Outcome, WinCondition};
+use rust_rush_core::waves::WavePhase;
+use rust_rush_core::weather::Weather;
+use rust_rush_core::{BuildTool, Enemy, Game, Position, Tower, TowerType, CELL_SIZE, ENEMY_RADIUS, GRID_HEIGHT, GRID_WIDTH, PROJECTILE_RADIUS};
+
+use crate::board_cache::BoardCache;
+use crate::camera::GameCamera;
+use crate::debug::DebugOverlay;
+use crate::decorations::{draw_prop, PROP_REACH};
+use crate::display::HudLayout;
+use crate::input::{enemy_under, Action, InputConfig};
+use crate::juice::Juice;
+use crate::mods;
+use crate::theme::{Theme, TowerShape};
+use crate::touch::RadialMenu;
+
+/// The board and everything on it, drawn through the pannable/zoomable
+/// camera (offscreen when post-processing is on)
+pub fn render_world(game: &Game, camera: &GameCamera, board: &mut BoardCache, theme: &Theme, juice: &Juice) {
+    // Cells, teleporters and one-way arrows come from a cached texture;
+    // everything after is skipped when it's off screen
+    board.render(game, camera, theme);
+
+    // Gates (bars when shut, a frame when open) and patrolling blockers with
+    // their routes
+    for gate in &game.state.gates {
+        let (x, y) = gate.position.to_world();
+        let bars = Color::from_rgba(150, 110, 60, 255);
+        if gate.open {
+            draw_rectangle_lines(x + 3.0, y + 3.0, CELL_SIZE - 6.0, CELL_SIZE - 6.0, 2.0, bars);
+        } else {
+            for i in 1..4 {
+                let bar_x = x + CELL_SIZE * i as f32 / 4.0;
+                draw_line(bar_x, y + 3.0, bar_x, y + CELL_SIZE - 3.0, 4.0, bars);
+            }
+        }
+    }
+    for patrol in &game.state.patrols {
+        for cell in &patrol.route {
+            let (x, y) = cell.to_world();
+            draw_circle(x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0, 3.0, Color::from_rgba(200, 60, 60, 120));
+        }
+        let (x, y) = patrol.cell().to_world();
+        draw_rectangle(x + 6.0, y + 6.0, CELL_SIZE - 12.0, CELL_SIZE - 12.0, Color::from_rgba(170, 40, 40, 255));
+    }
+
+    render_portals(game, theme);
+    render_objective(game, theme);
+
+    // Spawn and goal icons for every lane, so they aren't told apart by
+    // color alone
+    for (spawn, goal) in game.state.lane_ends() {
+        let (sx, sy) = spawn.to_world();
+        draw_triangle(
+            vec2(sx + 12.0, sy + 10.0),
+            vec2(sx + 12.0, sy + CELL_SIZE - 10.0),
+            vec2(sx + CELL_SIZE - 10.0, sy + CELL_SIZE / 2.0),
+            BLACK,
+        );
+        let (gx, gy) = goal.to_world();
+        draw_line(gx + 10.0, gy + 10.0, gx + CELL_SIZE - 10.0, gy + CELL_SIZE - 10.0, 4.0, BLACK);
+        draw_line(gx + CELL_SIZE - 10.0, gy + 10.0, gx + 10.0, gy + CELL_SIZE - 10.0, 4.0, BLACK);
+    }
+
+    // Draw traps
+    let cell_in_view = |pos: &Position| {
+        let (x, y) = pos.to_world();
+        camera.can_see(vec2(x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0), CELL_SIZE)
+    };
+    for trap in game.state.traps.values().filter(|trap| cell_in_view(&trap.position)) {
+        let (x, y) = trap.position.to_world();
+        let center_x = x + CELL_SIZE / 2.0;
+        let center_y = y + CELL_SIZE / 2.0;
+        let color = theme.trap_color(trap.trap_type);
+
+        match trap.trap_type {
+            TrapType::Mine => {
+                draw_circle(center_x, center_y, CELL_SIZE * 0.2, color);
+                draw_circle(center_x, center_y, CELL_SIZE * 0.06, YELLOW);
+            }
+            TrapType::Glue => {
+                let mut fill = color;
+                fill.a = 0.6;
+                draw_rectangle(x + 6.0, y + 6.0, CELL_SIZE - 12.0, CELL_SIZE - 12.0, fill);
+            }
+            TrapType::Spikes => {
+                for i in 0..3 {
+                    let spike_x = x + 8.0 + i as f32 * 12.0;
+                    draw_triangle(
+                        vec2(spike_x, center_y + 6.0),
+                        vec2(spike_x + 8.0, center_y + 6.0),
+                        vec2(spike_x + 4.0, center_y - 6.0),
+                        color,
+                    );
+                }
+            }
+        }
+    }
+
+    // Draw obstacles, with the clearing price on the hovered one
+    let (mx, my) = camera.mouse_world().into();
+    let hovered = game.state.grid.cell_at(mx, my);
+    for obstacle in game.state.obstacles.iter().filter(|obstacle| cell_in_view(&obstacle.position)) {
+        let (x, y) = obstacle.position.to_world();
+        let (center_x, center_y) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+        match obstacle.kind {
+            ObstacleKind::Rock => {
+                draw_circle(center_x, center_y, CELL_SIZE * 0.38, Color::from_rgba(120, 120, 125, 255));
+                draw_circle_lines(center_x, center_y, CELL_SIZE * 0.38, 2.0, Color::from_rgba(70, 70, 75, 255));
+            }
+            ObstacleKind::Tree => {
+                draw_rectangle(center_x - 3.0, center_y, 6.0, CELL_SIZE * 0.35, Color::from_rgba(110, 75, 40, 255));
+                draw_circle(center_x, center_y - 2.0, CELL_SIZE * 0.3, Color::from_rgba(40, 120, 50, 255));
+            }
+        }
+        if obstacle.position == hovered {
+            let affordable = game.state.gold >= obstacle.kind.clear_cost();
+            let color = if affordable { GOLD } else { RED };
+            draw_text(format!("Clear ${}", obstacle.kind.clear_cost()), x, y - 4.0, 20.0, color);
+        }
+    }
+
+    // Generators, with their reach while placing one
+    for generator in game.state.power.generators.iter().filter(|generator| cell_in_view(generator)) {
+        let (x, y) = generator.to_world();
+        draw_rectangle(x + 6.0, y + 6.0, CELL_SIZE - 12.0, CELL_SIZE - 12.0, Color::from_rgba(60, 60, 40, 255));
+        draw_bolt(x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0, CELL_SIZE * 0.3, theme.gold);
+    }
+    if game.build_tool == BuildTool::Generator {
+        let mut reach = theme.gold;
+        reach.a = 0.4;
+        for center in game.state.power.generators.iter().chain([&hovered]) {
+            let (x, y) = center.to_world();
+            draw_circle_lines(x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0, POWER_RADIUS * CELL_SIZE, 1.5, reach);
+        }
+    }
+    let powered = game.state.powered_towers();
+
+    // Draw towers
+    for tower in game.state.towers.values() {
+        let (center_x, center_y) = tower.world_position();
+        let scale = tower.tower_type.footprint() as f32;
+        let reach = (tower.stats.get(Stat::Range) * CELL_SIZE).max(CELL_SIZE * scale);
+        if !camera.can_see(vec2(center_x, center_y), reach) {
+            continue;
+        }
+        
+        // Draw range circle (subtle)
+        draw_circle_lines(
+            center_x,
+            center_y,
+            tower.stats.get(Stat::Range) * CELL_SIZE,
+            1.0,
+            Color::from_rgba(100, 100, 100, 50),
+        );
+        let min_range = tower.tower_type.min_range();
+        if min_range > 0.0 {
+            // Dead zone the tower can't fire into
+            draw_circle_lines(center_x, center_y, min_range * CELL_SIZE, 1.0, Color::from_rgba(200, 60, 60, 60));
+        }
+        
+        // Draw tower base; each type has its own shape as well as color
+        TowerShape::of(tower.tower_type).draw(center_x, center_y, CELL_SIZE * 0.4 * scale, theme.tower_color(tower.tower_type));
+        
+        // Draw tower barrel/cannon (rotated)
+        let barrel_length = CELL_SIZE * 0.5 * scale;
+        let barrel_end_x = center_x + barrel_length * tower.rotation.cos();
+        let barrel_end_y = center_y + barrel_length * tower.rotation.sin();
+        draw_line(center_x, center_y, barrel_end_x, barrel_end_y, 4.0 * scale, DARKGRAY);
+        
+        // Switched off: dimmed; on but without power: a red bolt
+        let radius = CELL_SIZE * 0.4 * scale;
+        if tower.disabled {
+            draw_circle(center_x, center_y, radius, Color::new(0.0, 0.0, 0.0, 0.55));
+            draw_text("OFF", center_x - 12.0, center_y + 5.0, 16.0, LIGHTGRAY);
+        } else if !powered.contains(&tower.id) {
+            draw_bolt(center_x + radius * 0.7, center_y - radius * 0.7, CELL_SIZE * 0.15, theme.danger);
+        }
+
+        // Reloading (ammo rule): a ring filling up, otherwise the rounds left
+        if let Some(progress) = tower.reload_progress() {
+            let radius = CELL_SIZE * 0.45 * scale;
+            draw_circle_lines(center_x, center_y, radius, 3.0, Color::new(0.0, 0.0, 0.0, 0.5));
+            draw_arc(center_x, center_y, 24, radius, -90.0, 3.0, progress * 360.0, theme.gold);
+        } else if game.state.rules.ammo {
+            render_rounds(tower, center_x, center_y + CELL_SIZE * 0.45 * scale, theme);
+        }
+
+        // Damaged: cracks across the base, a health bar and the repair price
+        // when hovered
+        if tower.is_damaged() {
+            let health_ratio = tower.health() as f32 / tower.max_health() as f32;
+            let crack = Color::new(0.0, 0.0, 0.0, 0.7);
+            let cracks = ((1.0 - health_ratio) * 4.0).ceil() as i32;
+            for i in 0..cracks {
+                let angle = 0.9 + i as f32 * 1.6;
+                let (dx, dy) = (angle.cos() * radius, angle.sin() * radius);
+                draw_line(center_x + dx * 0.2, center_y + dy * 0.2, center_x + dx * 0.9, center_y + dy * 0.7, 2.0, crack);
+            }
+            let bar_width = CELL_SIZE * 0.6 * scale;
+            let bar_y = center_y - CELL_SIZE * 0.5 * scale;
+            theme.draw_health_bar(center_x - bar_width / 2.0, bar_y, bar_width, 4.0, health_ratio);
+            if tower.occupies(&hovered) {
+                let affordable = game.state.gold >= tower.repair_cost();
+                let color = if affordable { GOLD } else { RED };
+                draw_text(format!("Repair ${}", tower.repair_cost()), center_x - radius, bar_y - 4.0, 20.0, color);
+            }
+        }
+
+        // Draw cooldown indicator
+        if tower.cooldown_remaining > 0.0 {
+            let cooldown_ratio = tower.cooldown_remaining / tower.reload_time();
+            let _angle = cooldown_ratio * std::f32::consts::PI * 2.0;
+            draw_circle_lines(center_x, center_y, CELL_SIZE * 0.3 * scale, 2.0, YELLOW);
+        }
+    }
+
+    // Draw projectiles
+    for (id, projectile) in &game.projectiles {
+        if !camera.can_see(vec2(projectile.x, projectile.y), CELL_SIZE * 2.0) {
+            continue; // Trails are short, so two cells of slack covers them
+        }
+        draw_circle(
+            projectile.x,
+            projectile.y,
+            PROJECTILE_RADIUS,
+            theme.projectile_color(projectile.tower_type),
+        );
+        
+        // Draw trail effect, fading and thinning toward the tail
+        if let Some(trail) = juice.trail(id) {
+            let mut trail_color = theme.projectile_color(projectile.tower_type);
+            for (i, (a, b)) in trail.iter().zip(trail.iter().skip(1)).enumerate() {
+                let fade = 1.0 - i as f32 / trail.len() as f32;
+                trail_color.a = 0.4 * fade;
+                draw_line(a.x, a.y, b.x, b.y, 3.0 * fade, trail_color);
+            }
+        }
+    }
+
+    // Gunners' shots at towers
+    for shot in game.enemy_shots.iter().filter(|shot| camera.can_see(vec2(shot.x, shot.y), PROJECTILE_RADIUS)) {
+        draw_circle(shot.x, shot.y, PROJECTILE_RADIUS * 0.8, theme.danger);
+    }
+
+    // Draw muzzle flashes
+    for flash in game.muzzle_flashes.iter().filter(|flash| camera.can_see(vec2(flash.x, flash.y), 8.0)) {
+        let mut color = theme.effect_color(flash.style);
+        color.a = flash.alpha();
+        draw_circle(flash.x, flash.y, 8.0, color);
+    }
+
+    // Draw explosions
+    for explosion in game.explosions.iter().filter(|explosion| camera.can_see(vec2(explosion.x, explosion.y), explosion.radius)) {
+        let mut color = theme.effect_color(explosion.style);
+        color.a = explosion.alpha() * 0.5;
+        draw_circle_lines(explosion.x, explosion.y, explosion.radius, 3.0, color);
+    }
+
+    // Draw floating text
+    for text in game.floating_texts.iter().filter(|text| camera.can_see(vec2(text.x, text.y), CELL_SIZE * 3.0)) {
+        let mut color = theme.effect_color(text.style);
+        color.a = text.alpha();
+        draw_text(&text.text, text.x, text.y, 28.0, color);
+    }
+
+    // Draw enemies, apart from those hidden by fog, back to front with the
+    // map's props (already sorted) between them
+    let enemy_shown = |enemy: &&Enemy| camera.can_see(vec2(enemy.x, enemy.y), CELL_SIZE) && game.fog.can_see(enemy.x, enemy.y);
+    let mut enemies: Vec<&Enemy> = game.state.enemies.values().filter(enemy_shown).collect();
+    enemies.sort_by(|a, b| a.y.total_cmp(&b.y));
+    let effect_rules = game.state.content.effect_rules();
+    let mut props = game.state.decorations.iter().filter(|prop| camera.can_see(vec2(prop.x, prop.y), PROP_REACH)).peekable();
+    for enemy in enemies {
+        while let Some(prop) = props.next_if(|prop| prop.y <= enemy.y) {
+            draw_prop(prop);
+        }
+
+        // Draw enemy body
+        let slowed = enemy.is_slowed();
+        let color = if slowed { theme.enemy_slowed } else { theme.enemy };
+        let color = Color::from_vec(color.to_vec().lerp(WHITE.to_vec(), juice.flash(enemy.id)));
+        
+        draw_circle(enemy.x, enemy.y, ENEMY_RADIUS, color);
+        if enemy.kind == EnemyKind::Gunner {
+            // A gun barrel, so they stand out from grunts by shape
+            draw_rectangle(enemy.x - 2.0, enemy.y - ENEMY_RADIUS - 4.0, 4.0, ENEMY_RADIUS, DARKGRAY);
+        }
+        if slowed {
+            // Frost ring doubles the color cue
+            draw_circle_lines(enemy.x, enemy.y, CELL_SIZE * 0.38, 2.0, theme.enemy_slowed);
+        }
+        let armor = enemy.armor_left(&effect_rules);
+        if armor > 0.0 {
+            // Plating, thicker the more of each hit it blocks
+            draw_circle_lines(enemy.x, enemy.y, ENEMY_RADIUS, 1.0 + armor * 6.0, GRAY);
+        }
+        
+        // Draw health bar
+        let health_ratio = enemy.health as f32 / enemy.max_health as f32;
+        let bar_width = CELL_SIZE * 0.6;
+        let bar_height = 4.0;
+        let bar_x = enemy.x - bar_width / 2.0;
+        let bar_y = enemy.y - CELL_SIZE * 0.5;
+        theme.draw_health_bar(bar_x, bar_y, bar_width, bar_height, health_ratio);
+
+        // Debuff icons after the bar, in the shape and color of the tower
+        // that applied them
+        let debuffs = [(EffectKind::Shred, TowerType::Shredder), (EffectKind::Amplify, TowerType::Marker)];
+        let mut icon_x = bar_x + bar_width + 5.0;
+        for (_, tower_type) in debuffs.into_iter().filter(|(kind, _)| enemy.effects.has(*kind)) {
+            TowerShape::of(tower_type).draw(icon_x, bar_y + bar_height / 2.0, 4.0, theme.tower_color(tower_type));
+            icon_x += 10.0;
+        }
+    }
+    props.for_each(draw_prop);
+
+    render_fog(game);
+    render_night_tint(game);
+    render_weather(game);
+
+    render_merge_drag(game, camera);
+    render_stroke(game, camera, theme);
+    render_target_controls(game, camera, theme);
+
+    render_hero(&game.state.hero, theme);
+
+    render_ability_reticle(game, camera, theme);
+}
+
+/// Status text, wave and ability bars and the pause screen, in scaled
+/// screen space anchored to the window edges
+pub fn render_hud(game: &Game, mod_errors: &[mods::ModError], input: &InputConfig, hud: &HudLayout, theme: &Theme) {
+    set_camera(&hud.camera());
+    draw_text(
+        format!("Gold: ${}", game.state.gold),
+        10.0,
+        25.0,
+        28.0,
+        theme.gold,
+    );
+    draw_text(
+        format!("Health: {}", game.state.health),
+        150.0,
+        25.0,
+        28.0,
+        theme.danger,
+    );
+    draw_text(
+        format!("Enemies: {}  Towers: {}", game.state.enemies.len(), game.state.towers.len()),
+        10.0,
+        50.0,
+        22.0,
+        theme.text,
+    );
+
+    draw_text(
+        format!("Build: {} (${})", game.build_tool.name(), game.build_tool.cost()),
+        10.0,
+        72.0,
+        22.0,
+        LIGHTGRAY,
+    );
+    draw_text(
+        format!("Palette: {}", theme.palette.name()),
+        300.0,
+        72.0,
+        18.0,
+        GRAY,
+    );
+    if game.state.clock.enabled {
+        let label = match game.state.clock.time_of_day() {
+            TimeOfDay::Day => "Day",
+            TimeOfDay::Night => "Night: tower range -20%",
+        };
+        draw_text(label, 450.0, 72.0, 18.0, GRAY);
+    }
+    let rules = &game.state.rules;
+    if !rules.mutators().is_empty() {
+        let names: Vec<&str> = rules.mutators().iter().map(|mutator| mutator.name()).collect();
+        let label = format!("Mutators: {}  (score x{:.2})", names.join(", "), rules.score_multiplier());
+        draw_text(label, 10.0, 92.0, 18.0, GRAY);
+    }
+    if let Some(label) = crate::dashboard::unranked_label(game) {
+        draw_text(label, 450.0, 92.0, 18.0, theme.danger);
+    }
+    if rules.hardcore {
+        draw_text("HARDCORE", 680.0, 92.0, 18.0, theme.danger);
+    }
+
+    if let Some(condition) = &game.state.objective.condition {
+        draw_text(objective_progress(game, condition), 10.0, 112.0, 18.0, theme.text);
+    }
+    if rules.convoys {
+        let convoys = &game.state.convoys;
+        let label = format!("Convoys: {} delivered, {}/{} lost", convoys.delivered, convoys.lost, convoys::MAX_LOST);
+        draw_text(label, 10.0, 132.0, 18.0, theme.gold);
+    }
+
+    render_wave_status(game, input, hud);
+
+    render_leak_warnings(game, hud, theme);
+
+    render_abilities(game, input, hud, theme);
+
+    if let Some(outcome) = game.state.objective.outcome {
+        render_outcome(outcome, hud, theme);
+    } else if game.state.paused {
+        let width = measure_text("PAUSED", None, 60, 1.0).width;
+        draw_text("PAUSED", (hud.width - width) / 2.0, hud.height / 2.0, 60.0, YELLOW);
+        render_mod_list(game, mod_errors, hud, theme);
+    }
+
+    set_default_camera();
+}
+
+/// Active mods, clashes and broken mod folders, listed under the pause title
+fn render_mod_list(game: &Game, mod_errors: &[mods::ModError], hud: &HudLayout, theme: &Theme) {
+    let content = &game.state.content;
+    if content.is_empty() && mod_errors.is_empty() {
+        return;
+    }
+
+    let x = hud.width / 2.0 - 160.0;
+    let mut y = hud.height / 2.0 + 40.0;
+    draw_text(format!("Mods ({})", content.active.len()), x, y, 24.0, theme.text);
+    for name in &content.active {
+        y += 20.0;
+        draw_text(format!("  {}", name), x, y, 18.0, LIGHTGRAY);
+    }
+    for conflict in &content.conflicts {
+        y += 20.0;
+        draw_text(
+            format!("  {}: {} overrides {}", conflict.field, conflict.winner, conflict.loser),
+            x,
+            y,
+            18.0,
+            YELLOW,
+        );
+    }
+    for error in mod_errors {
+        y += 20.0;
+        draw_text(format!("  skipped {}", error), x, y, 18.0, theme.danger);
+    }
+}
+
+/// Darken cells outside tower vision; never-seen cells more so
+fn render_fog(game: &Game) {
+    if !game.fog.enabled {
+        return;
+    }
+    for y in 0..GRID_HEIGHT {
+        for x in 0..GRID_WIDTH {
+            let pos = Position::new(x, y);
+            if game.fog.is_visible(pos) {
+                continue;
+            }
+            let alpha = if game.fog.is_explored(pos) { 120 } else { 210 };
+            let (wx, wy) = pos.to_world();
+            draw_rectangle(wx, wy, CELL_SIZE, CELL_SIZE, Color::from_rgba(0, 0, 0, alpha));
+        }
+    }
+}
+
+/// Blue-black wash over the board that deepens toward midnight
+fn render_night_tint(game: &Game) {
+    let darkness = 1.0 - game.state.clock.light_level();
+    if darkness <= 0.0 {
+        return;
+    }
+    let board = (GRID_WIDTH as f32 * CELL_SIZE, GRID_HEIGHT as f32 * CELL_SIZE);
+    draw_rectangle(0.0, 0.0, board.0, board.1, Color::new(0.02, 0.03, 0.15, darkness * 0.55));
+}
+
+/// Falling rain streaks for rain and storms, a grey wash for fog and a
+/// darker sky with the odd lightning flash in a storm. Purely cosmetic, so
+/// it runs off wall-clock time.
+fn render_weather(game: &Game) {
+    let weather = game.state.weather.current;
+    let board = (GRID_WIDTH as f32 * CELL_SIZE, GRID_HEIGHT as f32 * CELL_SIZE);
+    let time = get_time() as f32;
+    match weather {
+        Weather::Clear => return,
+        Weather::Fog => {
+            draw_rectangle(0.0, 0.0, board.0, board.1, Color::new(0.75, 0.78, 0.8, 0.3));
+            return;
+        }
+        Weather::Storm => {
+            let lightning = (time * 0.7).fract() < 0.03;
+            let sky = if lightning { Color::new(1.0, 1.0, 1.0, 0.35) } else { Color::new(0.05, 0.05, 0.1, 0.3) };
+            draw_rectangle(0.0, 0.0, board.0, board.1, sky);
+        }
+        Weather::Rain => {}
+    }
+
+    let (count, fall_speed) = if weather == Weather::Storm { (160, 700.0) } else { (90, 500.0) };
+    let color = Color::new(0.7, 0.8, 1.0, 0.45);
+    for i in 0..count {
+        // Spread drops with a cheap hash of their index; each wraps down the board
+        let seed = (i as u32).wrapping_mul(2_654_435_761);
+        let x = (seed % 1000) as f32 / 1000.0 * board.0;
+        let offset = (seed / 1000 % 1000) as f32 / 1000.0 * board.1;
+        let y = (offset + time * fall_speed) % board.1;
+        draw_line(x, y, x - 3.0, y + 12.0, 1.0, color);
+    }
+}
+
+fn render_merge_drag(game: &Game, camera: &GameCamera) {
+    let Some(source) = game.dragging_tower.and_then(|id| game.state.towers.get(id)) else {
+        return;
+    };
+
+    let (mx, my) = camera.mouse_world().into();
+    let (sx, sy) = source.world_position();
+    let hovered = game.state.tower_at(&game.state.grid.cell_at(mx, my));
+    let result = hovered
+        .filter(|target| target.id != source.id)
+        .and_then(|target| merging::find_recipe(source.tower_type, target.tower_type));
+
+    let color = if result.is_some() { GREEN } else { GRAY };
+    draw_line(sx, sy, mx, my, 2.0, color);
+    draw_circle_lines(mx, my, CELL_SIZE * 0.4, 2.0, color);
+
+    if let Some(result) = result {
+        draw_text(format!("{:?}", result), mx + 12.0, my - 12.0, 20.0, GREEN);
+    }
+}
+
+/// The line or rectangle of towers being dragged out: a ghost on each
+/// tower's cells, red where it would be refused, and the total price
+fn render_stroke(game: &Game, camera: &GameCamera, theme: &Theme) {
+    let Some(stroke) = &game.stroke else {
+        return;
+    };
+    let size = stroke.tower_type.footprint() as f32 * CELL_SIZE;
+    for (cell, legal) in &stroke.plan.towers {
+        let (x, y) = cell.to_world();
+        let mut color = if *legal { theme.tower_color(stroke.tower_type) } else { theme.danger };
+        color.a = 0.45;
+        draw_rectangle(x + 4.0, y + 4.0, size - 8.0, size - 8.0, color);
+    }
+    let (mx, my) = camera.mouse_world().into();
+    let color = if stroke.plan.buildable() { theme.gold } else { theme.danger };
+    let label = format!("{:?} x{}  ${}", stroke.tower_type, stroke.cells.len(), stroke.plan.cost);
+    draw_text(label, mx + 12.0, my - 12.0, 20.0, color);
+}
+
+/// Target locks, the aiming drag, and the ignore zones of the tower being
+/// aimed or hovered
+fn render_target_controls(game: &Game, camera: &GameCamera, theme: &Theme) {
+    for tower in game.state.towers.values() {
+        if let Some(enemy) = tower.locked_target.and_then(|id| game.state.enemies.get(id)) {
+            let (tx, ty) = tower.world_position();
+            draw_line(tx, ty, enemy.x, enemy.y, 1.0, Color::new(1.0, 0.2, 0.2, 0.6));
+            draw_circle_lines(enemy.x, enemy.y, ENEMY_RADIUS + 4.0, 2.0, theme.danger);
+        }
+    }
+
+    let (mx, my) = camera.mouse_world().into();
+    let shown = game.aiming_tower.or_else(|| game.state.tower_at(&game.state.grid.cell_at(mx, my)).map(|tower| tower.id));
+    let Some(tower) = shown.and_then(|id| game.state.towers.get(id)) else {
+        return;
+    };
+    for cell in &tower.ignore_zones {
+        let (wx, wy) = cell.to_world();
+        draw_rectangle(wx, wy, CELL_SIZE, CELL_SIZE, Color::new(0.3, 0.3, 0.3, 0.45));
+        draw_line(wx, wy, wx + CELL_SIZE, wy + CELL_SIZE, 1.0, GRAY);
+        draw_line(wx + CELL_SIZE, wy, wx, wy + CELL_SIZE, 1.0, GRAY);
+    }
+    if game.aiming_tower.is_some() {
+        let (tx, ty) = tower.world_position();
+        let color = if enemy_under(game, mx, my).is_some() { theme.danger } else { GRAY };
+        draw_line(tx, ty, mx, my, 2.0, color);
+        draw_circle_lines(mx, my, CELL_SIZE * 0.3, 2.0, color);
+    }
+}
+
+/// A row of pips under a tower for the rounds left in its magazine; big
+/// magazines show as a bar instead
+fn render_rounds(tower: &Tower, x: f32, y: f32, theme: &Theme) {
+    let (left, magazine) = (tower.rounds_left(), tower.tower_type.magazine());
+    if magazine > 8 {
+        let width = CELL_SIZE * 0.6;
+        draw_rectangle(x - width / 2.0, y, width, 3.0, Color::new(0.0, 0.0, 0.0, 0.5));
+        draw_rectangle(x - width / 2.0, y, width * left as f32 / magazine as f32, 3.0, theme.gold);
+        return;
+    }
+    let start = x - (magazine as f32 - 1.0) * 3.0;
+    for round in 0..magazine {
+        let color = if round < left { theme.gold } else { Color::new(0.0, 0.0, 0.0, 0.5) };
+        draw_circle(start + round as f32 * 6.0, y + 1.5, 2.0, color);
+    }
+}
+
+/// Lightning bolt icon for power
+fn draw_bolt(x: f32, y: f32, size: f32, color: Color) {
+    draw_triangle(vec2(x + size * 0.2, y - size), vec2(x - size * 0.5, y + size * 0.15), vec2(x + size * 0.1, y + size * 0.15), color);
+    draw_triangle(vec2(x - size * 0.2, y + size), vec2(x + size * 0.5, y - size * 0.15), vec2(x - size * 0.1, y - size * 0.15), color);
+}
+
+fn render_hero(hero: &Hero, theme: &Theme) {
+    if !hero.is_alive() {
+        let (x, y) = hero.home.to_world();
+        draw_text(
+            format!("{:.0}", hero.respawn_timer.ceil()),
+            x + 12.0,
+            y + 28.0,
+            24.0,
+            LIGHTGRAY,
+        );
+        return;
+    }
+
+    // Remaining route
+    let mut prev = (hero.x, hero.y);
+    for waypoint in hero.path.iter().skip(hero.current_waypoint) {
+        let (wx, wy) = waypoint.to_world();
+        let next = (wx + CELL_SIZE / 2.0, wy + CELL_SIZE / 2.0);
+        draw_line(prev.0, prev.1, next.0, next.1, 1.0, Color::from_rgba(255, 255, 255, 60));
+        prev = next;
+    }
+
+    draw_circle_lines(hero.x, hero.y, hero.range() * CELL_SIZE, 1.0, Color::from_rgba(255, 215, 0, 50));
+
+    // Diamond body with a facing line
+    let size = CELL_SIZE * 0.35;
+    let top = vec2(hero.x, hero.y - size);
+    let bottom = vec2(hero.x, hero.y + size);
+    let left = vec2(hero.x - size, hero.y);
+    let right = vec2(hero.x + size, hero.y);
+    draw_triangle(top, left, right, GOLD);
+    draw_triangle(bottom, left, right, GOLD);
+    draw_line(
+        hero.x,
+        hero.y,
+        hero.x + size * hero.rotation.cos(),
+        hero.y + size * hero.rotation.sin(),
+        3.0,
+        BROWN,
+    );
+    draw_text(hero.level.to_string(), hero.x - 4.0, hero.y + 5.0, 16.0, BLACK);
+
+    // Health bar
+    let health_ratio = hero.health as f32 / hero.max_health as f32;
+    let bar_width = CELL_SIZE * 0.8;
+    let bar_x = hero.x - bar_width / 2.0;
+    let bar_y = hero.y - CELL_SIZE * 0.6;
+    theme.draw_health_bar(bar_x, bar_y, bar_width, 4.0, health_ratio);
+}
+
+fn render_leak_warnings(game: &Game, hud: &HudLayout, theme: &Theme) {
+    // Red flash around the screen edge when an enemy leaks
+    let mut intensity = (game.leak_flash / 0.4).clamp(0.0, 1.0) * 0.6;
+
+    // Pulsing heartbeat glow while health is critical
+    if game.is_low_health() {
+        let pulse = ((get_time() as f32) * 7.0).sin().abs();
+        intensity = intensity.max(0.15 + 0.25 * pulse);
+    }
+
+    if intensity > 0.0 {
+        let color = Color::new(theme.danger.r, theme.danger.g, theme.danger.b, intensity);
+        let (w, h) = (hud.width, hud.height);
+        let thickness = 16.0;
+        draw_rectangle(0.0, 0.0, w, thickness, color);
+        draw_rectangle(0.0, h - thickness, w, thickness, color);
+        draw_rectangle(0.0, 0.0, thickness, h, color);
+        draw_rectangle(w - thickness, 0.0, thickness, h, color);
+    }
+
+    if game.is_low_health() {
+        draw_text("LOW HEALTH!", hud.width / 2.0 - 90.0, 40.0, 36.0, theme.danger);
+    }
+}
+
+/// Spawn portals: a swirl while a wave comes through, a ring that charges
+/// up over the telegraph before it, and a pulsing warning around a portal
+/// that opens for the first time on the coming wave
+fn render_portals(game: &Game, theme: &Theme) {
+    let waves = &game.state.waves;
+    let spin = get_time() as f32 * 3.0;
+    let announced = if waves.is_build_phase() { game.state.portals_opening_on(waves.wave) } else { Vec::new() };
+    let color = Color::from_rgba(150, 70, 220, 255);
+    for portal in game.state.all_portals() {
+        let (x, y) = portal.position.to_world();
+        let (cx, cy) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+        let radius = CELL_SIZE * 0.42;
+        match portal.stage(waves) {
+            PortalStage::Dormant => draw_circle_lines(cx, cy, radius * 0.5, 1.0, Color { a: 0.4, ..color }),
+            PortalStage::Telegraphing { progress } => {
+                // Grows to full size, flickering faster as the wave nears
+                let flicker = 0.6 + 0.4 * (spin * (1.0 + 3.0 * progress)).sin();
+                draw_circle(cx, cy, radius * progress, Color { a: 0.5 * flicker, ..color });
+                draw_circle_lines(cx, cy, radius, 2.0, Color { a: flicker, ..color });
+            }
+            PortalStage::Open => {
+                draw_circle(cx, cy, radius, Color { a: 0.6, ..color });
+                for arm in 0..3 {
+                    let angle = spin + arm as f32 * std::f32::consts::TAU / 3.0;
+                    let (tip_x, tip_y) = (cx + angle.cos() * radius, cy + angle.sin() * radius);
+                    draw_line(cx, cy, tip_x, tip_y, 2.0, Color::new(1.0, 1.0, 1.0, 0.7));
+                }
+            }
+        }
+        if announced.contains(&portal.position) {
+            let pulse = 0.5 + 0.5 * (spin * 2.0).sin();
+            draw_circle_lines(cx, cy, radius + 4.0 + pulse * 3.0, 2.0, theme.danger);
+        }
+    }
+}
+
+/// The core to defend and the convoys to protect, each with a health bar;
+/// the core's bar marks the line it must stay above
+fn render_objective(game: &Game, theme: &Theme) {
+    let objective = &game.state.objective;
+    match objective.condition {
+        Some(WinCondition::DefendCore { position, health, hold_above, .. }) => {
+            let (x, y) = position.to_world();
+            let (cx, cy) = (x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0);
+            draw_poly(cx, cy, 6, CELL_SIZE * 0.45, 0.0, Color::from_rgba(60, 140, 200, 255));
+            draw_poly_lines(cx, cy, 6, CELL_SIZE * 0.45, 0.0, 2.0, BLACK);
+            draw_circle(cx, cy, CELL_SIZE * 0.15, Color::from_rgba(200, 240, 255, 255));
+            let bar_x = x + 2.0;
+            theme.draw_health_bar(bar_x, y - 6.0, CELL_SIZE - 4.0, 4.0, objective.core_health / health);
+            let line_x = bar_x + (CELL_SIZE - 4.0) * hold_above / health;
+            draw_line(line_x, y - 8.0, line_x, y, 2.0, BLACK);
+        }
+        Some(WinCondition::Escort { health, .. }) => {
+            if let Some(convoy) = objective.convoy {
+                draw_convoy(convoy.x, convoy.y, convoy.health / health, theme);
+            }
+        }
+        _ => {}
+    }
+    for unit in game.state.convoys.units.values() {
+        draw_convoy(unit.x, unit.y, unit.health as f32 / unit.max_health as f32, theme);
+    }
+}
+
+/// A wagon with its health bar
+fn draw_convoy(x: f32, y: f32, health_ratio: f32, theme: &Theme) {
+    let (width, height) = (CELL_SIZE * 0.6, CELL_SIZE * 0.4);
+    let (left, top) = (x - width / 2.0, y - height / 2.0);
+    draw_rectangle(left, top, width, height, Color::from_rgba(170, 130, 70, 255));
+    draw_rectangle_lines(left, top, width, height, 2.0, BLACK);
+    for wheel_x in [left + 4.0, left + width - 4.0] {
+        draw_circle(wheel_x, top + height, 3.0, BLACK);
+    }
+    theme.draw_health_bar(left, top - 7.0, width, 4.0, health_ratio);
+}
+
+/// The win condition and how far along it is
+fn objective_progress(game: &Game, condition: &WinCondition) -> String {
+    let objective = &game.state.objective;
+    let cleared = game.state.waves.wave - 1;
+    let progress = match condition {
+        WinCondition::SurviveWaves { waves } | WinCondition::DefendCore { waves, .. } => {
+            format!("{}/{} waves", cleared.min(*waves), waves)
+        }
+        WinCondition::Escort { health, .. } => {
+            let convoy = objective.convoy.map_or(0.0, |convoy| convoy.health.max(0.0));
+            format!("convoy {:.0}/{:.0}", convoy, health)
+        }
+        WinCondition::HoldOut { seconds } => {
+            let left = (seconds - objective.elapsed).max(0.0) as u32;
+            format!("{}:{:02} left", left / 60, left % 60)
+        }
+    };
+    match condition {
+        WinCondition::DefendCore { health, .. } => {
+            let core = objective.core_health.max(0.0);
+            format!("Goal: {} ({}, core {:.0}/{:.0})", condition.describe(), progress, core, health)
+        }
+        _ => format!("Goal: {} ({})", condition.describe(), progress),
+    }
+}
+
+/// Big centered result once the run is won or lost
+fn render_outcome(outcome: Outcome, hud: &HudLayout, theme: &Theme) {
+    let (title, detail, color) = match outcome {
+        Outcome::Won => ("VICTORY", "The map is won", GREEN),
+        Outcome::Lost(defeat) => ("DEFEAT", defeat.describe(), theme.danger),
+    };
+    let width = measure_text(title, None, 60, 1.0).width;
+    draw_text(title, (hud.width - width) / 2.0, hud.height / 2.0, 60.0, color);
+    let width = measure_text(detail, None, 26, 1.0).width;
+    draw_text(detail, (hud.width - width) / 2.0, hud.height / 2.0 + 36.0, 26.0, theme.text);
+}
+
+fn render_wave_status(game: &Game, input: &InputConfig, hud: &HudLayout) {
+    let waves = &game.state.waves;
+    let x = hud.width - 320.0;
+
+    match waves.phase {
+        WavePhase::Build { countdown } => {
+            let status = match waves.auto_start {
+                Some(_) => format!("Wave {} in {:.0}s", waves.wave, countdown.ceil()),
+                None => format!("Wave {} ready", waves.wave),
+            };
+            draw_text(status, x, 25.0, 30.0, GREEN);
+            let call = if waves.auto_start.is_some() { "Call early" } else { "Start" };
+            draw_text(
+                format!("[{}] {}: +${}", input.label(Action::CallWave), call, waves.early_call_bonus()),
+                x,
+                50.0,
+                22.0,
+                GOLD,
+            );
+            if let Some(weather) = game.state.weather.forecast(waves.wave) {
+                draw_text(
+                    format!("Weather: {} ({})", weather.name(), weather.description()),
+                    x,
+                    72.0,
+                    22.0,
+                    SKYBLUE,
+                );
+            }
+            let opening = game.state.portals_opening_on(waves.wave);
+            if !opening.is_empty() {
+                let cells: Vec<String> = opening.iter().map(|cell| format!("({}, {})", cell.x, cell.y)).collect();
+                draw_text(format!("New portal opens: {}", cells.join(", ")), x, 94.0, 22.0, VIOLET);
+            }
+        }
+        WavePhase::Combat => {
+            draw_text(format!("Wave {}", waves.wave), x, 25.0, 30.0, RED);
+            draw_text(
+                format!("Incoming: {}", waves.remaining_to_spawn),
+                x,
+                50.0,
+                22.0,
+                LIGHTGRAY,
+            );
+            draw_text(
+                format!("Leaks this wave: {}", game.stats.leaks_in_wave(waves.wave)),
+                x,
+                72.0,
+                22.0,
+                LIGHTGRAY,
+            );
+        }
+    }
+}
+
+fn render_ability_reticle(game: &Game, camera: &GameCamera, theme: &Theme) {
+    // Targeting reticle for an armed ability
+    if let Some(armed) = game.abilities.armed {
+        let (mx, my) = camera.mouse_world().into();
+        let radius = armed.radius() * CELL_SIZE;
+        let armed_color = theme.ability_color(armed);
+        let mut fill = armed_color;
+        fill.a = 0.15;
+        draw_circle(mx, my, radius, fill);
+        draw_circle_lines(mx, my, radius, 2.0, armed_color);
+        draw_line(mx - 8.0, my, mx + 8.0, my, 2.0, armed_color);
+        draw_line(mx, my - 8.0, mx, my + 8.0, 2.0, armed_color);
+    }
+}
+
+fn render_abilities(game: &Game, input: &InputConfig, hud: &HudLayout, theme: &Theme) {
+    // Ability bar along the bottom of the screen
+    let slot_size = 48.0;
+    let bar_y = hud.height - slot_size - 10.0;
+    for (i, ability) in game.abilities.slots.iter().enumerate() {
+        let ability_type = ability.ability_type;
+        let slot_x = 10.0 + i as f32 * (slot_size + 8.0);
+        let affordable = game.state.gold >= ability_type.cost();
+
+        let mut color = theme.ability_color(ability_type);
+        if !ability.is_ready() || !affordable {
+            color.a = 0.35;
+        }
+        draw_rectangle(slot_x, bar_y, slot_size, slot_size, Color::from_rgba(20, 20, 20, 220));
+        draw_rectangle(slot_x, bar_y, slot_size, slot_size, Color::new(color.r, color.g, color.b, color.a * 0.5));
+
+        // Cooldown shade drains from the top as the ability recharges
+        let shade = slot_size * ability.cooldown_ratio();
+        draw_rectangle(slot_x, bar_y, slot_size, shade, Color::from_rgba(0, 0, 0, 160));
+
+        let outline = if game.abilities.armed == Some(ability_type) { WHITE } else { color };
+        draw_rectangle_lines(slot_x, bar_y, slot_size, slot_size, 2.0, outline);
+        draw_text(input.label(Action::cast(ability_type)), slot_x + 4.0, bar_y + 16.0, 20.0, WHITE);
+        draw_text(ability_type.name(), slot_x + 4.0, bar_y + 32.0, 14.0, WHITE);
+        draw_text(format!("${}", ability_type.cost()), slot_x + 4.0, bar_y + 44.0, 14.0, GOLD);
+    }
+}
+
+pub fn render_radial_menu(game: &Game, menu: &RadialMenu, theme: &Theme) {
+    let (cx, cy) = (menu.center.x, menu.center.y);
+    draw_circle_lines(cx, cy, CELL_SIZE * 0.5 * menu.scale, 2.0, WHITE);
+
+    let tools = BuildTool::slots_for(game.state.grid.shape());
+    let count = tools.len();
+    for (i, tool) in tools.iter().enumerate() {
+        let pos = menu.option_position(i, count);
+        let radius = menu.button_radius();
+        let affordable = game.state.gold >= tool.cost();
+        let fill = if affordable {
+            Color::from_rgba(40, 40, 40, 230)
+        } else {
+            Color::from_rgba(40, 20, 20, 230)
+        };
+        let color = match tool {
+            BuildTool::Tower(tower_type) => theme.tower_color(*tower_type),
+            BuildTool::Trap(trap_type) => theme.trap_color(*trap_type),
+            BuildTool::Generator => theme.gold,
+        };
+
+        draw_circle(pos.x, pos.y, radius, fill);
+        draw_circle_lines(pos.x, pos.y, radius, 2.0, color);
+        let font_size = 14.0 * menu.scale;
+        let name = tool.name();
+        let width = measure_text(&name, None, font_size as u16, 1.0).width;
+        draw_text(name, pos.x - width / 2.0, pos.y, font_size, WHITE);
+        let cost = format!("${}", tool.cost());
+        let width = measure_text(&cost, None, font_size as u16, 1.0).width;
+        draw_text(cost, pos.x - width / 2.0, pos.y + font_size, font_size, if affordable { GOLD } else { RED });
+    }
+}
+
+pub fn render_debug_overlay(game: &Game, overlay: &DebugOverlay, camera: &GameCamera, hud: &HudLayout) {
+    // World-space visualizations
+    set_camera(&camera.camera2d());
+    if overlay.show_paths || overlay.show_targeting {
+        for enemy in game.state.enemies.values() {
+            let mut prev = (enemy.x, enemy.y);
+            for waypoint in enemy.path.iter().skip(enemy.current_waypoint) {
+                let (wx, wy) = waypoint.to_world();
+                let next = (wx + CELL_SIZE / 2.0, wy + CELL_SIZE / 2.0);
+                draw_line(prev.0, prev.1, next.0, next.1, 1.5, Color::from_rgba(255, 0, 255, 160));
+                prev = next;
+            }
+        }
+    }
+    if overlay.show_ranges {
+        for tower in game.state.towers.values() {
+            let (x, y) = tower.world_position();
+            draw_circle_lines(x, y, tower.stats.get(Stat::Range) * CELL_SIZE, 1.5, Color::from_rgba(0, 255, 0, 160));
+            if tower.tower_type.min_range() > 0.0 {
+                draw_circle_lines(x, y, tower.tower_type.min_range() * CELL_SIZE, 1.5, Color::from_rgba(255, 80, 80, 160));
+            }
+        }
+    }
+    if overlay.show_targeting {
+        // Tower -> chosen target, labelled with the ranking inputs
+        for decision in &game.targeting.decisions {
+            let (fx, fy) = decision.from;
+            let (tx, ty) = decision.to;
+            draw_line(fx, fy, tx, ty, 2.0, YELLOW);
+            draw_circle_lines(tx, ty, CELL_SIZE * 0.4, 1.5, YELLOW);
+            draw_text(
+                format!("{:?} wp{} of {}", decision.enemy_id, decision.enemy_waypoint, decision.candidates),
+                tx + 8.0,
+                ty - 8.0,
+                14.0,
+                YELLOW,
+            );
+        }
+        for splash in &game.targeting.splashes {
+            draw_circle_lines(splash.x, splash.y, splash.radius, 2.0, Color::from_rgba(255, 140, 0, 200));
+            draw_text(
+                format!("hit {}", splash.enemies_hit),
+                splash.x - 14.0,
+                splash.y + 4.0,
+                14.0,
+                ORANGE,
+            );
+        }
+    }
+
+    if !overlay.open {
+        set_default_camera();
+        return;
+    }
+
+    // Occupied spatial hash buckets with their enemy counts
+    let bucket = game.enemy_index.bucket_size();
+    for ((bx, by), count) in game.enemy_index.occupancy() {
+        let (x, y) = (bx as f32 * bucket, by as f32 * bucket);
+        draw_rectangle_lines(x, y, bucket, bucket, 1.0, Color::from_rgba(0, 200, 255, 140));
+        draw_text(format!("{}", count), x + 3.0, y + 12.0, 12.0, Color::from_rgba(0, 200, 255, 200));
+    }
+
+    // Stats panel
+    set_camera(&hud.camera());
+    let effects = game.muzzle_flashes.len() + game.explosions.len() + game.floating_texts.len();
+    let live_path_nodes: usize = game.state.enemies.values().map(|enemy| enemy.path.len()).sum::<usize>()
+        + game.state.hero.path.len();
+    let mut lines = vec![
+        format!("FPS: {}  frame: {:.1} ms", get_fps(), get_frame_time() * 1000.0),
+        format!(
+            "Enemies: {}  Towers: {}  Projectiles: {}",
+            game.state.enemies.len(),
+            game.state.towers.len(),
+            game.projectiles.len()
+        ),
+        format!("Traps: {}  Effects: {}", game.state.traps.len(), effects),
+        format!(
+            "Pathfinding: {}/s  path nodes: {}/s  live: {}",
+            overlay.path_rate.searches, overlay.path_rate.path_nodes, live_path_nodes
+        ),
+        format!("Update: {:.3} ms", game.profiler.total_millis()),
+    ];
+    for (system, millis) in game.profiler.timings() {
+        lines.push(format!("  {:<12} {:.3} ms", system, millis));
+    }
+    let on_off = |flag: bool| if flag { "on" } else { "off" };
+    lines.push(format!(
+        "[F4] paths: {}  [F5] ranges: {}  [F6] targeting: {}",
+        on_off(overlay.show_paths),
+        on_off(overlay.show_ranges),
+        on_off(overlay.show_targeting)
+    ));
+
+    let line_height = 18.0;
+    let x = hud.width - 400.0;
+    let y = 90.0;
+    draw_rectangle(x - 8.0, y - 16.0, 400.0, lines.len() as f32 * line_height + 10.0, Color::from_rgba(0, 0, 0, 190));
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x, y + i as f32 * line_height, 18.0, GREEN);
+    }
+    set_default_camera();
+}
+
+/// Economy and run stats for whoever is being watched
+pub fn render_spectator_panel(game: &Game, hud: &HudLayout, theme: &Theme) {
+    set_camera(&hud.camera());
+
+    let mut tower_counts: Vec<(String, usize)> = Vec::new();
+    for tower in game.state.towers.values() {
+        let name = format!("{:?}", tower.tower_type);
+        match tower_counts.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, count)) => *count += 1,
+            None => tower_counts.push((name, 1)),
+        }
+    }
+    tower_counts.sort();
+
+    let mut lines = vec![
+        format!("Gold: ${}   Health: {}", game.state.gold, game.state.health),
+        format!("Wave: {}   Kills: {}   Leaks: {}", game.state.waves.wave, game.stats.kills, game.stats.total_leaks),
+        format!("Enemies on field: {}", game.state.enemies.len()),
+        format!("Towers: {}", game.state.towers.len()),
+    ];
+    lines.extend(tower_counts.iter().map(|(name, count)| format!("  {} x{}", name, count)));
+
+    let line_height = 18.0;
+    let (x, y) = (10.0, 110.0);
+    let height = (lines.len() + 1) as f32 * line_height + 10.0;
+    draw_rectangle(x - 6.0, y - 18.0, 280.0, height, Color::from_rgba(0, 0, 0, 170));
+    draw_text("SPECTATING  (drag to pan)", x, y, 20.0, YELLOW);
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x, y + (i + 1) as f32 * line_height, 18.0, theme.text);
+    }
+    set_default_camera();
+}
@@ -0,0 +1,103 @@
+//! Instant replay of the last few seconds. Every frame's game state goes into
+//! a ring buffer; starting a replay plays the buffer back into a separate
+//! game that only gets rendered, so the live run is untouched. While it
+//! plays, Space pauses and the arrow keys step a frame at a time, which makes
+//! it easy to see how an enemy got somewhere.
+
+use macroquad::prelude::*;
+use rust_rush_core::rollback::History;
+use rust_rush_core::Game;
+
+use crate::display::HudLayout;
+
+const REPLAY_SECONDS: usize = 10;
+const RECORDED_FPS: usize = 60; // Frames kept per second of replay
+
+pub struct InstantReplay {
+    history: History,
+    playback: Option<Playback>,
+    view: Game, // Restored from the history for each replayed frame
+}
+
+struct Playback {
+    frame: usize,
+    paused: bool,
+}
+
+impl InstantReplay {
+    pub fn new() -> Self {
+        InstantReplay {
+            history: History::new(REPLAY_SECONDS * RECORDED_FPS),
+            playback: None,
+            view: Game::new(),
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    pub fn record(&mut self, game: &Game) {
+        if !self.is_playing() {
+            self.history.record(game);
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        if self.playback.take().is_none() && !self.history.is_empty() {
+            self.playback = Some(Playback { frame: 0, paused: false });
+        }
+    }
+
+    /// Advance (or step) playback; ends the replay after the newest frame
+    pub fn update(&mut self) {
+        let (Some(last), Some(playback)) = (self.history.len().checked_sub(1), self.playback.as_mut()) else {
+            return;
+        };
+
+        if is_key_pressed(KeyCode::Space) {
+            playback.paused = !playback.paused;
+        }
+        if is_key_pressed(KeyCode::Right) {
+            playback.paused = true;
+            playback.frame = (playback.frame + 1).min(last);
+        }
+        if is_key_pressed(KeyCode::Left) {
+            playback.paused = true;
+            playback.frame = playback.frame.saturating_sub(1);
+        }
+
+        let frame = playback.frame;
+        if !playback.paused {
+            if frame == last {
+                self.playback = None;
+                return;
+            }
+            playback.frame += 1;
+        }
+        if let Some(snapshot) = self.history.get(frame) {
+            self.view.restore(snapshot);
+        }
+    }
+
+    /// The replayed frame to draw instead of the live game
+    pub fn view(&self) -> Option<&Game> {
+        self.playback.as_ref().map(|_| &self.view)
+    }
+
+    /// Banner with the playback position
+    pub fn render_banner(&self, hud: &HudLayout) {
+        let Some(playback) = &self.playback else {
+            return;
+        };
+        set_camera(&hud.camera());
+        let behind = (self.history.len() - playback.frame) as f32 / RECORDED_FPS as f32;
+        let text = format!(
+            "REPLAY  -{:.1}s  {}",
+            behind,
+            if playback.paused { "[paused: Left/Right to step]" } else { "" }
+        );
+        draw_text(&text, hud.width / 2.0 - 160.0, 100.0, 28.0, ORANGE);
+        set_default_camera();
+    }
+}
@@ -0,0 +1,102 @@
+//! Post-wave report card: pops up at the top of the screen when a wave is
+//! cleared with the core's `ReportCard` for it (grade, gold, damage by tower
+//! type, leaks and hints) and a small graph of gold earned and spent over
+//! the last few waves. The dismiss key puts it away; the next cleared wave
+//! brings a new one.
+
+use macroquad::prelude::*;
+use rust_rush_core::report_card::ReportCard;
+use rust_rush_core::Game;
+
+use crate::display::HudLayout;
+use crate::input::{Action, InputConfig};
+use crate::theme::Theme;
+
+const PANEL_WIDTH: f32 = 460.0;
+const PANEL_HEIGHT: f32 = 210.0;
+const TOP: f32 = 40.0;
+const GRAPH_WAVES: usize = 8;
+const MAX_TOWER_ROWS: usize = 4;
+
+#[derive(Debug, Clone, Default)]
+pub struct ReportCardPanel {
+    card: Option<ReportCard>,
+    last_wave: u32, // Latest wave a card was made for
+}
+
+impl ReportCardPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make a card for a newly cleared wave, unless the run is over (the
+    /// run stats screen has that covered)
+    pub fn update(&mut self, game: &Game, input: &InputConfig) {
+        if input.pressed(Action::DismissReport) {
+            self.card = None;
+        }
+        let over = game.state.health <= 0 || game.state.objective.is_decided();
+        let Some(record) = game.stats.waves.last().filter(|record| record.cleared_at.is_some()) else {
+            return;
+        };
+        if record.wave > self.last_wave {
+            self.last_wave = record.wave;
+            self.card = (!over).then(|| ReportCard::new(record, &game.state));
+        }
+    }
+
+    pub fn render(&self, game: &Game, input: &InputConfig, hud: &HudLayout, theme: &Theme) {
+        let Some(card) = &self.card else {
+            return;
+        };
+        set_camera(&hud.camera());
+        let (x, y) = ((hud.width - PANEL_WIDTH) / 2.0, TOP);
+        draw_rectangle(x, y, PANEL_WIDTH, PANEL_HEIGHT, Color::new(0.0, 0.0, 0.0, 0.8));
+        draw_rectangle_lines(x, y, PANEL_WIDTH, PANEL_HEIGHT, 2.0, theme.cell_border);
+
+        let title = format!("WAVE {} REPORT", card.wave);
+        draw_text(title, x + 12.0, y + 24.0, 22.0, theme.text);
+        let grade = format!("{}  {:.0}% stopped", card.grade.letter(), card.efficiency * 100.0);
+        let grade_color = if card.leaks == 0 { theme.health_high } else { theme.danger };
+        draw_text(grade, x + PANEL_WIDTH - 170.0, y + 24.0, 22.0, grade_color);
+
+        let economy =
+            format!("Gold +{}  -{}    Kills {}   Leaks {}", card.gold_earned, card.gold_spent, card.kills, card.leaks);
+        draw_text(economy, x + 12.0, y + 48.0, 16.0, theme.text);
+
+        let total: u64 = card.damage_by_tower.iter().map(|(_, damage)| damage).sum::<u64>().max(1);
+        for (row, (tower_type, damage)) in card.damage_by_tower.iter().take(MAX_TOWER_ROWS).enumerate() {
+            let row_y = y + 58.0 + row as f32 * 18.0;
+            let share = *damage as f32 / total as f32;
+            draw_text(format!("{:?}", tower_type), x + 12.0, row_y + 13.0, 16.0, theme.text);
+            draw_rectangle(x + 90.0, row_y + 3.0, 120.0 * share, 12.0, theme.tower_color(*tower_type));
+            draw_text(format!("{:.0}%", share * 100.0), x + 214.0, row_y + 13.0, 16.0, theme.text);
+        }
+
+        economy_graph(game, Rect::new(x + 260.0, y + 58.0, PANEL_WIDTH - 272.0, 70.0), theme);
+
+        for (row, hint) in card.hints.iter().enumerate() {
+            draw_text(hint, x + 12.0, y + 150.0 + row as f32 * 18.0, 16.0, theme.gold);
+        }
+        let footer = format!("[{}] Dismiss", input.label(Action::DismissReport));
+        draw_text(footer, x + PANEL_WIDTH - 100.0, y + PANEL_HEIGHT - 8.0, 14.0, GRAY);
+        set_default_camera();
+    }
+}
+
+/// Gold earned and spent per wave, side by side, for the last few waves
+fn economy_graph(game: &Game, area: Rect, theme: &Theme) {
+    let waves = &game.stats.waves;
+    let recent = &waves[waves.len().saturating_sub(GRAPH_WAVES)..];
+    let most = recent.iter().map(|record| record.gold_earned.max(record.gold_spent)).max().unwrap_or(0).max(1) as f32;
+    draw_text("Gold per wave", area.x, area.y + 10.0, 14.0, theme.text);
+    let plot = Rect::new(area.x, area.y + 16.0, area.w, area.h - 16.0);
+    let slot = plot.w / GRAPH_WAVES as f32;
+    for (i, record) in recent.iter().enumerate() {
+        let left = plot.x + i as f32 * slot;
+        for (offset, amount, color) in [(0.1, record.gold_earned, theme.gold), (0.5, record.gold_spent, theme.danger)] {
+            let height = amount as f32 / most * plot.h;
+            draw_rectangle(left + slot * offset, plot.y + plot.h - height, slot * 0.38, height, color);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Build-phase preview of the route the next wave will take: an animated
+//! dotted line from each lane's spawn to its goal, so players can see how their
+//! maze routes enemies before the wave starts. With smart enemies it's the
+//! route around the threat map (see `threat`). The route is only searched
+//! again when the grid's revision or the threat map changes.
+
+use macroquad::prelude::*;
+use rust_rush_core::pathfinding::{find_path, PathGraph};
+use rust_rush_core::threat::Threatened;
+use rust_rush_core::{Game, Position, CELL_SIZE};
+
+use crate::camera::GameCamera;
+use crate::theme::Theme;
+
+const DOT_SPACING: f32 = 14.0;
+const DOT_RADIUS: f32 = 3.0;
+const MARCH_SPEED: f32 = 30.0; // Pixels per second the dots drift toward the goal
+
+pub struct RoutePreview {
+    legs: Vec<Vec<Vec2>>, // Walked stretches in world space, split at teleporter jumps
+    built_for: Option<(u64, Position, Position, u64)>, // Grid revision, spawn, goal and threat map of `legs`
+}
+
+impl RoutePreview {
+    pub fn new() -> Self {
+        RoutePreview { legs: Vec::new(), built_for: None }
+    }
+
+    pub fn render(&mut self, game: &Game, camera: &GameCamera, theme: &Theme) {
+        if !game.state.waves.is_build_phase() {
+            return;
+        }
+        let state = &game.state;
+        let key = (state.grid.revision(), state.spawn_point, state.goal_point, state.threat.fingerprint());
+        if self.built_for != Some(key) {
+            let threatened = Threatened { grid: &state.grid, threat: &state.threat };
+            self.legs = state
+                .lane_ends()
+                .into_iter()
+                .flat_map(|(spawn, goal)| {
+                    if state.rules.smart_enemies {
+                        route_legs(&threatened, spawn, goal)
+                    } else {
+                        route_legs(&state.grid, spawn, goal)
+                    }
+                })
+                .collect();
+            self.built_for = Some(key);
+        }
+
+        set_camera(&camera.camera2d());
+        let mut color = theme.spawn;
+        color.a = 0.8;
+        let offset = (get_time() as f32 * MARCH_SPEED) % DOT_SPACING;
+        for leg in &self.legs {
+            let mut next_dot = offset;
+            let mut walked = 0.0;
+            for pair in leg.windows(2) {
+                let (from, to) = (pair[0], pair[1]);
+                let length = from.distance(to);
+                while next_dot <= walked + length {
+                    let at = from.lerp(to, (next_dot - walked) / length.max(f32::EPSILON));
+                    draw_circle(at.x, at.y, DOT_RADIUS, color);
+                    next_dot += DOT_SPACING;
+                }
+                walked += length;
+            }
+        }
+        set_default_camera();
+    }
+}
+
+/// Cell centers along the cheapest route, starting a new leg wherever the
+/// route jumps through a teleporter. Empty if the goal is unreachable.
+fn route_legs<G: PathGraph>(graph: &G, spawn: Position, goal: Position) -> Vec<Vec<Vec2>> {
+    let Some(path) = find_path(graph, spawn, goal) else {
+        return Vec::new();
+    };
+    let center = |cell: Position| {
+        let (x, y) = cell.to_world();
+        vec2(x + CELL_SIZE / 2.0, y + CELL_SIZE / 2.0)
+    };
+    let mut legs = vec![vec![center(path[0])]];
+    for pair in path.windows(2) {
+        if pair[0].manhattan_distance(&pair[1]) > 1 {
+            legs.push(Vec::new());
+        }
+        legs.last_mut().unwrap().push(center(pair[1]));
+    }
+    legs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_rush_core::Grid;
+
+    #[test]
+    fn test_route_splits_at_teleporters() {
+        let mut grid = Grid::new(10, 1);
+        assert_eq!(route_legs(&grid, Position::new(0, 0), Position::new(9, 0)).len(), 1);
+
+        grid.link_teleporters(Position::new(2, 0), Position::new(7, 0));
+        let legs = route_legs(&grid, Position::new(0, 0), Position::new(9, 0));
+        assert_eq!(legs.iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 3]);
+
+        grid.set_walkable(&Position::new(8, 0), false);
+        assert!(route_legs(&grid, Position::new(0, 0), Position::new(9, 0)).is_empty());
+    }
+}
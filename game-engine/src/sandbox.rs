@@ -0,0 +1,127 @@
+//! Sandbox panel, shown with `--sandbox`: sliders for the health and speed
+//! of spawned enemies plus hotkeys to spawn one and to clear the wave. The
+//! sliders are logarithmic, so 1x sits in the middle.
+
+use macroquad::prelude::*;
+use rust_rush_core::protocol::Command;
+use rust_rush_core::sandbox::{MAX_SCALE, MIN_SCALE};
+use rust_rush_core::Game;
+
+use crate::display::HudLayout;
+use crate::input::{Action, InputConfig};
+use crate::theme::Theme;
+
+const PANEL_WIDTH: f32 = 240.0;
+const PANEL_HEIGHT: f32 = 150.0;
+const TRACK_WIDTH: f32 = 200.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Slider {
+    Health,
+    Speed,
+}
+
+pub struct SandboxPanel {
+    pub health_scale: f32,
+    pub speed_scale: f32,
+    dragging: Option<Slider>,
+}
+
+impl SandboxPanel {
+    pub fn new() -> Self {
+        SandboxPanel { health_scale: 1.0, speed_scale: 1.0, dragging: None }
+    }
+
+    /// Hotkeys and slider drags. True if the mouse went to the panel, so it
+    /// shouldn't reach the board.
+    pub fn handle_input(&mut self, game: &mut Game, input: &InputConfig, hud: &HudLayout) -> bool {
+        if input.pressed(Action::SpawnEnemy) {
+            let _ = game.apply_command(&Command::SpawnEnemy { health_scale: self.health_scale, speed_scale: self.speed_scale });
+        }
+        if input.pressed(Action::ClearWave) {
+            let _ = game.apply_command(&Command::ClearWave);
+        }
+
+        let cursor = Vec2::from(mouse_position()) / hud.scale;
+        if is_mouse_button_pressed(MouseButton::Left) {
+            self.dragging = [Slider::Health, Slider::Speed]
+                .into_iter()
+                .find(|slider| Self::track(*slider, hud).contains(cursor));
+        }
+        if !is_mouse_button_down(MouseButton::Left) {
+            self.dragging = None;
+        }
+        if let Some(slider) = self.dragging {
+            let track = Self::track(slider, hud);
+            let value = scale_at((cursor.x - track.x) / track.w);
+            match slider {
+                Slider::Health => self.health_scale = value,
+                Slider::Speed => self.speed_scale = value,
+            }
+        }
+        self.dragging.is_some() || Self::frame(hud).contains(cursor)
+    }
+
+    pub fn render(&self, input: &InputConfig, hud: &HudLayout, theme: &Theme) {
+        set_camera(&hud.camera());
+        let frame = Self::frame(hud);
+        draw_rectangle(frame.x, frame.y, frame.w, frame.h, Color::new(0.0, 0.0, 0.0, 0.75));
+        draw_rectangle_lines(frame.x, frame.y, frame.w, frame.h, 2.0, theme.gold);
+        draw_text("SANDBOX  (building is free)", frame.x + 10.0, frame.y + 20.0, 18.0, theme.gold);
+
+        for (slider, label, value) in
+            [(Slider::Health, "Enemy health", self.health_scale), (Slider::Speed, "Enemy speed", self.speed_scale)]
+        {
+            let track = Self::track(slider, hud);
+            draw_text(format!("{} x{:.2}", label, value), track.x, track.y - 4.0, 16.0, theme.text);
+            draw_rectangle(track.x, track.y + track.h / 2.0 - 2.0, track.w, 4.0, GRAY);
+            let knob_x = track.x + track.w * fraction_of(value);
+            draw_circle(knob_x, track.y + track.h / 2.0, 7.0, if self.dragging == Some(slider) { WHITE } else { LIGHTGRAY });
+        }
+
+        let keys = format!(
+            "[{}] Spawn   [{}] Clear wave",
+            input.label(Action::SpawnEnemy),
+            input.label(Action::ClearWave)
+        );
+        draw_text(keys, frame.x + 10.0, frame.y + frame.h - 10.0, 16.0, theme.text);
+        set_default_camera();
+    }
+
+    /// Bottom-right corner, clear of the minimap and the ability bar
+    fn frame(hud: &HudLayout) -> Rect {
+        Rect::new(hud.width - PANEL_WIDTH - 10.0, hud.height - PANEL_HEIGHT - 10.0, PANEL_WIDTH, PANEL_HEIGHT)
+    }
+
+    fn track(slider: Slider, hud: &HudLayout) -> Rect {
+        let frame = Self::frame(hud);
+        let row = match slider {
+            Slider::Health => 0.0,
+            Slider::Speed => 1.0,
+        };
+        Rect::new(frame.x + 20.0, frame.y + 50.0 + row * 42.0, TRACK_WIDTH, 16.0)
+    }
+}
+
+/// Slider position (0 to 1) to a scale, evenly spaced on a log scale
+fn scale_at(fraction: f32) -> f32 {
+    MIN_SCALE * (MAX_SCALE / MIN_SCALE).powf(fraction.clamp(0.0, 1.0))
+}
+
+fn fraction_of(scale: f32) -> f32 {
+    ((scale / MIN_SCALE).ln() / (MAX_SCALE / MIN_SCALE).ln()).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliders_are_logarithmic() {
+        assert_eq!(scale_at(0.0), MIN_SCALE);
+        assert!((scale_at(0.5) - 1.0).abs() < 1e-5);
+        assert!((scale_at(1.0) - MAX_SCALE).abs() < 1e-4);
+        assert!((fraction_of(scale_at(0.3)) - 0.3).abs() < 1e-5);
+        assert_eq!(scale_at(-1.0), MIN_SCALE);
+    }
+}
@@ -0,0 +1,212 @@
+//! The save slots screen (see `saves`): every slot with its thumbnail, name,
+//! map, wave, when it was saved and time played, most recent first. Up/Down
+//! pick a row; Enter loads the slot, or saves a new one from the top row;
+//! S saves over the selected slot; N renames it and Delete deletes it.
+//! A hardcore run saves itself and can't load another, and the hardcore
+//! slot is only played on by launching with `--hardcore`.
+
+use macroquad::prelude::*;
+use rust_rush_core::Game;
+
+use crate::capture::utc_fields;
+use crate::display::HudLayout;
+use crate::saves::{SaveSlots, SlotInfo, Thumbnail, HARDCORE_SLOT, MAX_NAME_LEN};
+use crate::theme::Theme;
+
+const PANEL_WIDTH: f32 = 620.0;
+const ROW_HEIGHT: f32 = 64.0;
+const TOP: f32 = 60.0;
+const THUMB_SIZE: f32 = 56.0;
+
+#[derive(Debug, Clone, Default)]
+pub struct SaveMenu {
+    pub open: bool,
+    selected: usize, // 0 is the new slot row, then `SaveSlots::slots` in order
+    renaming: Option<String>,
+    status: String, // What the last save, load or delete did
+}
+
+impl SaveMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.renaming = None;
+        self.status.clear();
+    }
+
+    /// Whether keys are being typed into a slot name
+    pub fn is_renaming(&self) -> bool {
+        self.open && self.renaming.is_some()
+    }
+
+    /// Handle navigation and slot actions. True if a slot was loaded into
+    /// `game`.
+    pub fn handle_input(&mut self, game: &mut Game, slots: &mut SaveSlots) -> bool {
+        let selected = self.selected.checked_sub(1).and_then(|index| slots.slots().get(index)).map(|slot| slot.id);
+        if let Some(name) = &mut self.renaming {
+            while let Some(ch) = get_char_pressed() {
+                if !ch.is_control() && name.chars().count() < MAX_NAME_LEN {
+                    name.push(ch);
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                name.pop();
+            }
+            if is_key_pressed(KeyCode::Enter) {
+                if let (Some(id), Some(name)) = (selected, self.renaming.take()) {
+                    self.report(slots.rename(id, &name).map(|()| "Renamed".to_string()));
+                }
+            } else if is_key_pressed(KeyCode::Escape) {
+                self.renaming = None;
+            }
+            return false;
+        }
+
+        let rows = slots.slots().len() + 1;
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % rows;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + rows - 1) % rows;
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.toggle();
+        } else if is_key_pressed(KeyCode::Enter) {
+            match selected {
+                None => self.save(game, slots, None),
+                Some(id) => return self.load(game, slots, id),
+            }
+        } else if is_key_pressed(KeyCode::S) {
+            if let Some(id) = selected {
+                self.save(game, slots, Some(id));
+            }
+        } else if is_key_pressed(KeyCode::N) && selected.is_some() {
+            while get_char_pressed().is_some() {} // Don't type the key that started it
+            self.renaming = slots.get(selected.unwrap_or_default()).map(|slot| slot.name.clone());
+        } else if is_key_pressed(KeyCode::Delete) {
+            if let Some(id) = selected {
+                self.report(slots.delete(id).map(|()| "Deleted".to_string()));
+                self.selected = self.selected.min(slots.slots().len());
+            }
+        }
+        false
+    }
+
+    fn save(&mut self, game: &Game, slots: &mut SaveSlots, id: Option<u32>) {
+        if game.state.rules.hardcore {
+            self.status = "Hardcore runs save themselves".to_string();
+        } else if id == Some(HARDCORE_SLOT) {
+            self.status = "The hardcore slot only takes hardcore runs".to_string();
+        } else {
+            let saved = slots.save(id, game).map(|id| format!("Saved to {}", slots.get(id).map_or("", |slot| &slot.name)));
+            self.selected = 1; // The slot just saved is the newest
+            self.report(saved);
+        }
+    }
+
+    fn load(&mut self, game: &mut Game, slots: &SaveSlots, id: u32) -> bool {
+        if game.state.rules.hardcore {
+            self.status = "A hardcore run can't load another save".to_string();
+            return false;
+        }
+        if id == HARDCORE_SLOT {
+            self.status = "Launch with --hardcore to play on".to_string();
+            return false;
+        }
+        match slots.load(id) {
+            Ok(saved) => {
+                game.resume(saved);
+                self.toggle();
+                true
+            }
+            Err(err) => {
+                self.status = format!("Can't load: {}", err);
+                false
+            }
+        }
+    }
+
+    fn report(&mut self, result: Result<String, String>) {
+        self.status = result.unwrap_or_else(|err| format!("Failed: {}", err));
+    }
+
+    pub fn render(&self, slots: &SaveSlots, hud: &HudLayout, theme: &Theme) {
+        if !self.open {
+            return;
+        }
+        set_camera(&hud.camera());
+        // As many rows as fit, scrolled to keep the selection in view
+        let visible = (((hud.height - TOP - 80.0) / ROW_HEIGHT) as usize).max(1);
+        let first = (self.selected + 1).saturating_sub(visible);
+        let rows = (slots.slots().len() + 1).min(visible);
+        let (x, height) = ((hud.width - PANEL_WIDTH) / 2.0, 70.0 + rows as f32 * ROW_HEIGHT);
+        draw_rectangle(x, TOP, PANEL_WIDTH, height, Color::new(0.0, 0.0, 0.0, 0.85));
+        draw_rectangle_lines(x, TOP, PANEL_WIDTH, height, 2.0, theme.cell_border);
+        draw_text("SAVE SLOTS", x + 12.0, TOP + 26.0, 26.0, theme.text);
+        let help = "Up/Down: select   Enter: load   S: save over   N: rename   Delete: delete   Esc: close";
+        draw_text(help, x + 12.0, TOP + 46.0, 14.0, GRAY);
+
+        for (row, index) in (first..first + rows).enumerate() {
+            let y = TOP + 56.0 + row as f32 * ROW_HEIGHT;
+            let color = if index == self.selected { theme.gold } else { theme.text };
+            if index == self.selected {
+                draw_rectangle_lines(x + 6.0, y, PANEL_WIDTH - 12.0, ROW_HEIGHT - 4.0, 1.0, theme.gold);
+            }
+            let Some(slot) = index.checked_sub(1).and_then(|index| slots.slots().get(index)) else {
+                draw_text("+ New slot", x + 16.0, y + 36.0, 22.0, color);
+                continue;
+            };
+            draw_thumbnail(&slot.thumbnail, x + 10.0, y + 2.0, theme);
+            let name = match (&self.renaming, index == self.selected) {
+                (Some(name), true) => format!("{}_", name),
+                _ => slot.name.clone(),
+            };
+            draw_text(name, x + THUMB_SIZE + 22.0, y + 22.0, 22.0, color);
+            draw_text(details(slot), x + THUMB_SIZE + 22.0, y + 44.0, 16.0, theme.text);
+        }
+        draw_text(&self.status, x + 12.0, TOP + height - 4.0, 16.0, theme.gold);
+        set_default_camera();
+    }
+}
+
+/// Map, wave, save date and time played
+fn details(slot: &SlotInfo) -> String {
+    let map = if slot.map.is_empty() { "Default board" } else { &slot.map };
+    let [year, month, day, hour, minute, _] = utc_fields(slot.saved_at);
+    let played = slot.playtime as u64;
+    format!(
+        "{}{}   Wave {}   {}-{:02}-{:02} {:02}:{:02} UTC   {}:{:02}:{:02} played",
+        if slot.hardcore { "HARDCORE   " } else { "" },
+        map,
+        slot.wave,
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        played / 3600,
+        played / 60 % 60,
+        played % 60
+    )
+}
+
+/// The board shrunk into a square, colored like the minimap
+fn draw_thumbnail(thumbnail: &Thumbnail, x: f32, y: f32, theme: &Theme) {
+    let cell = THUMB_SIZE / thumbnail.width.max(thumbnail.height).max(1) as f32;
+    draw_rectangle(x, y, thumbnail.width as f32 * cell, thumbnail.height as f32 * cell, theme.cell);
+    for cy in 0..thumbnail.height {
+        for cx in 0..thumbnail.width {
+            let color = match thumbnail.cell(cx, cy) {
+                '#' => theme.cell_blocked,
+                'S' => theme.spawn,
+                'G' => theme.goal,
+                'T' => theme.gold,
+                _ => continue,
+            };
+            draw_rectangle(x + cx as f32 * cell, y + cy as f32 * cell, cell, cell, color);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! Save slots. Each slot is a sealed `SavedRun` (see
+//! `rust_rush_core::savegame`) in its own file, binary unless launched with
+//! `--text-saves`, and `index.json` next to
+//! them lists what the load screen shows for each: name, map, wave, when
+//! it was saved, time played and a minimap thumbnail. Native builds keep
+//! them in `saves` under the platform data directory; web builds keep the
+//! same files in LocalStorage. A slot whose file fails its seal or won't
+//! parse can't be loaded, only deleted.
+//!
+//! Slot `HARDCORE_SLOT` belongs to the hardcore mode: a hardcore run is
+//! autosaved there every `AUTOSAVE_INTERVAL` seconds and the slot is
+//! deleted the moment the run is won or lost, so quitting before a defeat
+//! never buys a retry. Launching with `--hardcore` plays on from it if
+//! it's there. Nothing else saves into it.
+
+use std::path::{Path, PathBuf};
+
+use rust_rush_core::savegame::{self, SavedRun};
+use rust_rush_core::{Game, Position, CELL_SIZE};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+const SAVES_DIR: &str = "saves";
+const INDEX_FILE: &str = "index.json";
+pub const HARDCORE_SLOT: u32 = 0;
+const AUTOSAVE_INTERVAL: f32 = 2.0; // Seconds between hardcore saves
+pub const MAX_NAME_LEN: usize = 24;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlotInfo {
+    pub id: u32,
+    pub name: String,
+    pub map: String, // Empty for the built-in board
+    pub wave: u32,
+    pub saved_at: u64, // Seconds since the Unix epoch
+    pub playtime: f32, // Seconds of play in the run
+    pub hardcore: bool,
+    pub thumbnail: Thumbnail,
+}
+
+/// The board as the minimap draws it, one character per cell: `.` open,
+/// `#` blocked, `S` spawn, `G` goal and `T` tower
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Thumbnail {
+    pub width: i32,
+    pub height: i32,
+    pub cells: String,
+}
+
+impl Thumbnail {
+    pub fn capture(game: &Game) -> Self {
+        let state = &game.state;
+        let grid = &state.grid;
+        let towers: Vec<Position> = state.towers.values().flat_map(|tower| tower.cells()).collect();
+        let mut cells = String::with_capacity((grid.width() * grid.height()).max(0) as usize);
+        for y in 0..grid.height() {
+            for x in 0..grid.width() {
+                let pos = grid.cell_at((x as f32 + 0.5) * CELL_SIZE, (y as f32 + 0.5) * CELL_SIZE);
+                cells.push(if towers.contains(&pos) {
+                    'T'
+                } else if !grid.is_walkable(&pos) {
+                    '#'
+                } else if state.is_spawn(&pos) {
+                    'S'
+                } else if state.is_goal(&pos) {
+                    'G'
+                } else {
+                    '.'
+                });
+            }
+        }
+        Thumbnail { width: grid.width(), height: grid.height(), cells }
+    }
+
+    /// The character for a cell, row-major
+    pub fn cell(&self, x: i32, y: i32) -> char {
+        self.cells.as_bytes().get((y * self.width + x) as usize).map_or('.', |byte| *byte as char)
+    }
+}
+
+/// The slot index, and the files it points at
+pub struct SaveSlots {
+    root: PathBuf,
+    slots: Vec<SlotInfo>, // Most recently saved first
+    pub text_saves: bool, // Write sealed JSON instead of binary, for inspection
+}
+
+impl SaveSlots {
+    /// The player's slots; none if the index is missing or unreadable
+    pub fn open() -> Self {
+        let root = if cfg!(target_arch = "wasm32") {
+            PathBuf::from(SAVES_DIR) // A key prefix in LocalStorage
+        } else {
+            crate::settings::data_dir().unwrap_or_default().join(SAVES_DIR)
+        };
+        SaveSlots::open_at(root)
+    }
+
+    /// The slots kept under `root`
+    pub fn open_at(root: PathBuf) -> Self {
+        let slots = backend::read(&root.join(INDEX_FILE))
+            .map(|json| {
+                serde_json::from_str(&json).unwrap_or_else(|err| {
+                    warn!(%err, "save index unreadable; slots not listed");
+                    Vec::new()
+                })
+            })
+            .unwrap_or_default();
+        SaveSlots { root, slots, text_saves: false }
+    }
+
+    /// The folder the slots are in; a key prefix on the web
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn slots(&self) -> &[SlotInfo] {
+        &self.slots
+    }
+
+    pub fn get(&self, id: u32) -> Option<&SlotInfo> {
+        self.slots.iter().find(|slot| slot.id == id)
+    }
+
+    /// Save `game` into slot `id`, or a new slot; returns the slot used
+    pub fn save(&mut self, id: Option<u32>, game: &Game) -> Result<u32, String> {
+        let id = id.unwrap_or_else(|| self.slots.iter().map(|slot| slot.id).max().unwrap_or(HARDCORE_SLOT) + 1);
+        backend::write_bytes(&self.slot_path(id), &encode_run(game, self.text_saves)?)?;
+
+        let name = match self.get(id) {
+            Some(slot) => slot.name.clone(),
+            None if id == HARDCORE_SLOT => "Hardcore".to_string(),
+            None => format!("Slot {}", id),
+        };
+        let info = SlotInfo {
+            id,
+            name,
+            map: game.state.map_name.clone(),
+            wave: game.state.waves.wave,
+            saved_at: crate::debug::now() as u64,
+            playtime: game.stats.elapsed,
+            hardcore: game.state.rules.hardcore,
+            thumbnail: Thumbnail::capture(game),
+        };
+        self.slots.retain(|slot| slot.id != id);
+        self.slots.insert(0, info);
+        self.write_index()?;
+        Ok(id)
+    }
+
+    /// The run in slot `id`
+    pub fn load(&self, id: u32) -> Result<SavedRun, String> {
+        let bytes = backend::read_bytes(&self.slot_path(id)).ok_or_else(|| format!("slot {} is empty", id))?;
+        let saved = decode_run(&bytes)?;
+        if (id == HARDCORE_SLOT) != saved.state.rules.hardcore {
+            return Err("hardcore runs only go in the hardcore slot".to_string());
+        }
+        Ok(saved)
+    }
+
+    pub fn delete(&mut self, id: u32) -> Result<(), String> {
+        backend::remove(&self.slot_path(id))?;
+        self.slots.retain(|slot| slot.id != id);
+        self.write_index()
+    }
+
+    pub fn rename(&mut self, id: u32, name: &str) -> Result<(), String> {
+        let name: String = name.trim().chars().take(MAX_NAME_LEN).collect();
+        let slot = self.slots.iter_mut().find(|slot| slot.id == id).ok_or_else(|| format!("no slot {}", id))?;
+        if !name.is_empty() {
+            slot.name = name;
+        }
+        self.write_index()
+    }
+
+    fn slot_path(&self, id: u32) -> PathBuf {
+        self.root.join(format!("slot-{}.sav", id))
+    }
+
+    fn write_index(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.slots).map_err(|err| err.to_string())?;
+        backend::write(&self.root.join(INDEX_FILE), &json)
+    }
+}
+
+/// A run as sealed save text
+pub fn encode_run(game: &Game, text: bool) -> Result<Vec<u8>, String> {
+    let saved = SavedRun::capture(game);
+    if !text {
+        return saved.to_binary().map_err(|err| err.to_string());
+    }
+    let body = serde_json::to_string(&saved).map_err(|err| err.to_string())?;
+    Ok(savegame::seal(&body).into_bytes())
+}
+
+/// The run in a save of either kind, if the seal holds and the version is
+/// current
+pub fn decode_run(bytes: &[u8]) -> Result<SavedRun, String> {
+    if savegame::is_binary(bytes) {
+        return SavedRun::from_binary(bytes).map_err(|err| err.to_string());
+    }
+    let text = std::str::from_utf8(bytes).map_err(|_| "not a save file".to_string())?;
+    let body = savegame::unseal(text).map_err(|err| err.to_string())?;
+    let saved: SavedRun = serde_json::from_str(body).map_err(|err| err.to_string())?;
+    saved.check_version().map_err(|err| err.to_string())?;
+    Ok(saved)
+}
+
+/// Keeps a hardcore run in `HARDCORE_SLOT`
+pub struct Autosave {
+    since_save: f32,
+    finished: bool, // The run is decided and the slot gone
+}
+
+impl Autosave {
+    pub fn new() -> Self {
+        Autosave { since_save: 0.0, finished: false }
+    }
+
+    /// Play on from the hardcore slot if it holds a run, then keep it saved
+    pub fn start(game: &mut Game, slots: &mut SaveSlots) -> Self {
+        if slots.get(HARDCORE_SLOT).is_some() {
+            match slots.load(HARDCORE_SLOT) {
+                Ok(saved) => {
+                    info!(wave = saved.state.waves.wave, "hardcore run resumed");
+                    game.resume(saved);
+                }
+                Err(err) => {
+                    error!(%err, "hardcore save discarded");
+                    if let Err(err) = slots.delete(HARDCORE_SLOT) {
+                        warn!(%err, "hardcore slot not deleted");
+                    }
+                }
+            }
+        }
+        Autosave::new()
+    }
+
+    pub fn update(&mut self, game: &Game, slots: &mut SaveSlots, delta: f32) {
+        if self.finished {
+            return;
+        }
+        if game.state.health <= 0 || game.state.objective.is_decided() {
+            self.finished = true;
+            match slots.delete(HARDCORE_SLOT) {
+                Ok(()) => info!("hardcore run over; save slot deleted"),
+                Err(err) => warn!(%err, "hardcore slot not deleted"),
+            }
+            return;
+        }
+        self.since_save += delta;
+        if self.since_save >= AUTOSAVE_INTERVAL {
+            self.since_save = 0.0;
+            if let Err(err) = slots.save(Some(HARDCORE_SLOT), game) {
+                warn!(%err, "hardcore autosave failed");
+            }
+        }
+    }
+}
+
+/// Files by path: on disk natively, LocalStorage keys on the web
+#[cfg(not(target_arch = "wasm32"))]
+pub mod backend {
+    use std::io::ErrorKind;
+    use std::path::Path;
+
+    pub fn read(path: &Path) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    pub fn read_bytes(path: &Path) -> Option<Vec<u8>> {
+        std::fs::read(path).ok()
+    }
+
+    pub fn write(path: &Path, text: &str) -> Result<(), String> {
+        write_bytes(path, text.as_bytes())
+    }
+
+    pub fn write_bytes(path: &Path, bytes: &[u8]) -> Result<(), String> {
+        let fail = |err: std::io::Error| format!("{}: {}", path.display(), err);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(fail)?;
+        }
+        std::fs::write(path, bytes).map_err(fail)
+    }
+
+    pub fn remove(path: &Path) -> Result<(), String> {
+        match std::fs::remove_file(path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(format!("{}: {}", path.display(), err)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub mod backend {
+    use std::path::Path;
+
+    use crate::storage;
+
+    fn key(path: &Path) -> String {
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    pub fn read(path: &Path) -> Option<String> {
+        storage::load(&key(path))
+    }
+
+    pub fn write(path: &Path, text: &str) -> Result<(), String> {
+        storage::save(&key(path), text).map_err(|err| err.to_string())
+    }
+
+    /// LocalStorage only holds strings, so bytes are kept as hex
+    pub fn read_bytes(path: &Path) -> Option<Vec<u8>> {
+        let hex = storage::load(&key(path))?;
+        (0..hex.len()).step_by(2).map(|at| u8::from_str_radix(hex.get(at..at + 2)?, 16).ok()).collect()
+    }
+
+    pub fn write_bytes(path: &Path, bytes: &[u8]) -> Result<(), String> {
+        let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        write(path, &hex)
+    }
+
+    pub fn remove(path: &Path) -> Result<(), String> {
+        storage::remove(&key(path)).map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_rush_core::mutators::RuleSet;
+    use rust_rush_core::{BuildTool, TowerType};
+
+    #[test]
+    fn test_slots_save_load_rename_and_delete() {
+        let root = std::env::temp_dir().join(format!("rust-rush-saves-{}", std::process::id()));
+        let mut slots = SaveSlots::open_at(root.clone());
+        let mut game = Game::new();
+        assert!(game.state.spawn_enemy().is_ok());
+        game.update(0.5);
+        let first = slots.save(None, &game).unwrap();
+        slots.text_saves = true;
+        let second = slots.save(None, &game).unwrap();
+        assert_eq!((first, second), (1, 2));
+        assert!(slots.rename(first, "  Switchback attempt  ").is_ok());
+
+        let reopened = SaveSlots::open_at(root.clone());
+        assert_eq!(reopened.slots().iter().map(|slot| slot.id).collect::<Vec<_>>(), vec![second, first]);
+        let slot = reopened.get(first).unwrap();
+        assert_eq!((slot.name.as_str(), slot.wave), ("Switchback attempt", game.state.waves.wave));
+        assert_eq!(slot.thumbnail.cell(game.state.spawn_point.x, game.state.spawn_point.y), 'S');
+        assert_eq!(reopened.load(first).unwrap().state.hash(), game.state.hash());
+        assert_eq!(reopened.load(second).unwrap().state.hash(), game.state.hash()); // Text saves still load
+
+        // A hand-edited slot won't load, and a casual run can't pose as hardcore
+        let path = slots.slot_path(second);
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, text.replacen("\"gold\":", "\"gold\":9", 1)).unwrap();
+        assert!(slots.load(second).is_err());
+        std::fs::copy(slots.slot_path(first), slots.slot_path(HARDCORE_SLOT)).unwrap();
+        assert!(slots.load(HARDCORE_SLOT).is_err());
+
+        assert!(slots.delete(second).is_ok());
+        assert!(!path.exists() && slots.get(second).is_none());
+        let mut rules = RuleSet::default();
+        rules.hardcore = true;
+        let mut hardcore = Game::with_rules(rules);
+        assert_eq!(slots.save(Some(HARDCORE_SLOT), &hardcore).unwrap(), HARDCORE_SLOT);
+        hardcore.state.health = 0;
+        Autosave::new().update(&hardcore, &mut slots, 0.0);
+        assert!(slots.get(HARDCORE_SLOT).is_none());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_text_saves_keep_towers_and_teleporters() {
+        let mut game = Game::new();
+        game.state.grid.link_teleporters(Position::new(2, 2), Position::new(12, 3));
+        assert!(game.try_build(BuildTool::Tower(TowerType::Basic), Position::new(5, 5)).is_ok());
+
+        let bytes = encode_run(&game, true).unwrap();
+        assert!(!savegame::is_binary(&bytes));
+        let mut loaded = Game::new();
+        loaded.resume(decode_run(&bytes).unwrap());
+        assert_eq!(loaded.state.hash(), game.state.hash());
+        assert_eq!(loaded.state.grid.teleporter(&Position::new(2, 2)), Some(Position::new(12, 3)));
+        assert!(!loaded.state.grid.is_walkable(&Position::new(5, 5)));
+    }
+}
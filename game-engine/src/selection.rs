@@ -0,0 +1,182 @@
+//! Box selection and copy/paste of tower groups (see
+//! `rust_rush_core::groups`). Holding the select key while dragging the
+//! place button picks every tower the box touches. With a selection, the
+//! sell key sells all of it and the merge key merges every pair in it that
+//! has a recipe; the copy key copies it. The paste key then shows the copy
+//! under the cursor: each click builds it there, paying per tower, the
+//! mirror key flips it (left to right, then top to bottom, then both), and
+//! Cancel or the paste key again puts it away.
+
+use macroquad::prelude::*;
+use rust_rush_core::groups::{towers_in, Clipboard};
+use rust_rush_core::{Game, Position, TowerId, CELL_SIZE};
+
+use crate::camera::GameCamera;
+use crate::display::HudLayout;
+use crate::input::{Action, InputConfig};
+use crate::theme::Theme;
+
+const STATUS_TIME: f32 = 3.0;
+
+#[derive(Default)]
+pub struct TowerSelection {
+    anchor: Option<Position>, // Corner the box is being dragged from
+    selected: Vec<TowerId>,
+    clipboard: Option<Clipboard>,
+    pasting: bool,
+    mirror: u8, // 0 as copied, then left-right, top-bottom, both
+    status: String,
+    status_left: f32,
+}
+
+impl TowerSelection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The copy as it would be pasted now
+    fn oriented(&self) -> Option<Clipboard> {
+        let clipboard = self.clipboard.as_ref()?;
+        Some(clipboard.mirrored(self.mirror & 1 != 0, self.mirror & 2 != 0))
+    }
+
+    /// True if the input went to the selection or a paste, so the board
+    /// shouldn't also act on it
+    pub fn handle_input(&mut self, game: &mut Game, input: &InputConfig, camera: &GameCamera) -> bool {
+        let (mx, my) = camera.mouse_world().into();
+        let hovered = game.state.grid.cell_at(mx, my);
+
+        if let Some(anchor) = self.anchor {
+            if input.released(Action::PlaceTower) {
+                self.anchor = None;
+                self.selected = towers_in(&game.state, anchor, hovered);
+                self.report(format!("{} towers selected", self.selected.len()));
+            }
+            return true;
+        }
+        if input.held(Action::SelectTowers) && input.pressed(Action::PlaceTower) {
+            self.anchor = Some(hovered);
+            self.pasting = false;
+            return true;
+        }
+
+        if input.pressed(Action::PasteTowers) {
+            match &self.clipboard {
+                Some(_) => self.pasting = !self.pasting,
+                None => self.report("Nothing copied".to_string()),
+            }
+        }
+        if self.pasting {
+            if input.pressed(Action::MirrorPaste) {
+                self.mirror = (self.mirror + 1) % 4;
+            }
+            if input.pressed(Action::Cancel) {
+                self.pasting = false;
+                return true;
+            }
+            if input.pressed(Action::PlaceTower) {
+                if let Some(clipboard) = self.oriented() {
+                    let pasted = game.paste(&clipboard, hovered);
+                    self.report(match pasted.refused.first() {
+                        None => format!("Pasted {} towers", pasted.placed),
+                        Some((_, err)) => {
+                            format!("Pasted {} towers; {} left out ({})", pasted.placed, pasted.refused.len(), err)
+                        }
+                    });
+                }
+                return true;
+            }
+        }
+
+        if self.selected.is_empty() {
+            return false;
+        }
+        if input.pressed(Action::CopyTowers) {
+            self.clipboard = Clipboard::copy(&game.state, &self.selected);
+            self.mirror = 0;
+            let copied = self.clipboard.as_ref().map_or(0, |clipboard| clipboard.towers.len());
+            self.report(format!("Copied {} towers", copied));
+        }
+        if input.pressed(Action::UpgradeGroup) {
+            let merges = game.merge_group(&self.selected);
+            self.report(format!("{} merges", merges));
+        }
+        if input.pressed(Action::SellTower) {
+            let sold = game.sell_group(&self.selected);
+            self.report(format!("Sold {} towers", sold));
+            self.selected.clear();
+            return true;
+        }
+        if input.pressed(Action::Cancel) {
+            self.selected.clear();
+            return true;
+        }
+        false
+    }
+
+    fn report(&mut self, status: String) {
+        self.status = status;
+        self.status_left = STATUS_TIME;
+    }
+
+    /// Each frame: forget towers that are gone, and let the status fade
+    pub fn update(&mut self, game: &Game, delta: f32) {
+        self.selected.retain(|id| game.state.towers.contains_key(*id));
+        self.status_left = (self.status_left - delta).max(0.0);
+    }
+
+    pub fn render(&self, game: &Game, input: &InputConfig, camera: &GameCamera, hud: &HudLayout, theme: &Theme) {
+        let (mx, my) = camera.mouse_world().into();
+        let hovered = game.state.grid.cell_at(mx, my);
+
+        set_camera(&camera.camera2d());
+        if let Some(anchor) = self.anchor {
+            let (left, top) = Position::new(anchor.x.min(hovered.x), anchor.y.min(hovered.y)).to_world();
+            let (right, bottom) = Position::new(anchor.x.max(hovered.x), anchor.y.max(hovered.y)).to_world();
+            let (width, height) = (right - left + CELL_SIZE, bottom - top + CELL_SIZE);
+            draw_rectangle(left, top, width, height, Color::new(1.0, 1.0, 1.0, 0.08));
+            draw_rectangle_lines(left, top, width, height, 2.0, theme.gold);
+        }
+        for tower in self.selected.iter().filter_map(|id| game.state.towers.get(*id)) {
+            let (x, y) = tower.position.to_world();
+            let size = tower.tower_type.footprint() as f32 * CELL_SIZE;
+            draw_rectangle_lines(x + 2.0, y + 2.0, size - 4.0, size - 4.0, 3.0, theme.gold);
+        }
+        if let (true, Some(clipboard)) = (self.pasting, self.oriented()) {
+            for (tower, position) in clipboard.placements(hovered) {
+                let (x, y) = position.to_world();
+                let size = tower.tower_type.footprint() as f32 * CELL_SIZE;
+                let mut color = theme.tower_color(tower.tower_type);
+                color.a = 0.45;
+                draw_rectangle(x + 4.0, y + 4.0, size - 8.0, size - 8.0, color);
+            }
+        }
+        set_default_camera();
+
+        set_camera(&hud.camera());
+        let hint = if self.pasting {
+            format!(
+                "Click: paste   [{}] mirror   [{}] done",
+                input.label(Action::MirrorPaste),
+                input.label(Action::Cancel)
+            )
+        } else if !self.selected.is_empty() {
+            format!(
+                "{} selected   [{}] copy   [{}] sell   [{}] merge   [{}] clear",
+                self.selected.len(),
+                input.label(Action::CopyTowers),
+                input.label(Action::SellTower),
+                input.label(Action::UpgradeGroup),
+                input.label(Action::Cancel)
+            )
+        } else {
+            String::new()
+        };
+        let status = if self.status_left > 0.0 { self.status.as_str() } else { "" };
+        for (line, text) in [hint.as_str(), status].into_iter().enumerate() {
+            let width = measure_text(text, None, 18, 1.0).width;
+            draw_text(text, (hud.width - width) / 2.0, hud.height - 76.0 + line as f32 * 20.0, 18.0, theme.gold);
+        }
+        set_default_camera();
+    }
+}
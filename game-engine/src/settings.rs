@@ -0,0 +1,331 @@
+//! Player settings, kept together in one `settings.json`: volumes, key
+//! bindings, window size, display options (UI scale, palette, juice,
+//! post-processing), the default game speed, wave auto-start, wave
+//! checkpoints, autosaves and language. Native builds keep the file in the platform's
+//! config directory; web builds use the storage layer. Files from before the merge (`display.json`,
+//! `keybindings.json`) are imported the first time.
+
+use std::io;
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::display::DisplaySettings;
+use crate::input::InputConfig;
+use crate::theme::Palette;
+use rust_rush_core::waves::BUILD_PHASE_TIME;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+const APP_DIR: &str = "rust-rush";
+
+pub const MAX_GAME_SPEED: u32 = 3;
+pub const MAX_AUTO_WAVE_DELAY: u32 = 60;
+const AUTO_WAVE_STEP: u32 = 5;
+pub const MAX_AUTOSAVE_INTERVAL: u32 = 600;
+const AUTOSAVE_STEP: u32 = 30;
+pub const MAX_AUTOSAVE_WAVES: u32 = 10;
+const VOLUME_STEP: f32 = 0.1;
+/// Window sizes offered on the settings screen
+pub const RESOLUTIONS: [(u32, u32); 4] = [(1024, 768), (1280, 720), (1600, 900), (1920, 1080)];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32, // 0 to 1
+    pub effects_volume: f32,
+    pub input: InputConfig,
+    pub resolution: Option<(u32, u32)>, // Windowed size; None leaves the window alone
+    pub display: DisplaySettings,
+    pub game_speed: u32, // Simulation speed multiplier a run starts at, 1 to MAX_GAME_SPEED
+    pub auto_wave: bool,      // Start the next wave by itself; off waits for a call
+    pub auto_wave_delay: u32, // Seconds after a wave ends, 0 to MAX_AUTO_WAVE_DELAY
+    pub checkpoints: bool,    // Offer a restart from an earlier wave on defeat
+    pub autosave_interval: u32, // Seconds between autosaves, 0 for none, up to MAX_AUTOSAVE_INTERVAL
+    pub autosave_waves: u32,    // Autosave as every nth wave starts, 0 for never
+    pub language: String, // Only "en" has text so far
+}
+
+/// The rows of the settings screen above the key bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    MasterVolume,
+    EffectsVolume,
+    GameSpeed,
+    Resolution,
+    Palette,
+    PostEffects,
+    Crt,
+    AutoWave,
+    AutoWaveDelay,
+    Checkpoints,
+    AutosaveInterval,
+    AutosaveWaves,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 12] = [
+        Setting::MasterVolume,
+        Setting::EffectsVolume,
+        Setting::GameSpeed,
+        Setting::Resolution,
+        Setting::Palette,
+        Setting::PostEffects,
+        Setting::Crt,
+        Setting::AutoWave,
+        Setting::AutoWaveDelay,
+        Setting::Checkpoints,
+        Setting::AutosaveInterval,
+        Setting::AutosaveWaves,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Setting::MasterVolume => "Master Volume",
+            Setting::EffectsVolume => "Effects Volume",
+            Setting::GameSpeed => "Game Speed",
+            Setting::Resolution => "Window Size",
+            Setting::Palette => "Palette",
+            Setting::PostEffects => "Post Effects",
+            Setting::Crt => "CRT Filter",
+            Setting::AutoWave => "Auto-Start Waves",
+            Setting::AutoWaveDelay => "Auto-Start Delay",
+            Setting::Checkpoints => "Wave Checkpoints",
+            Setting::AutosaveInterval => "Autosave Timer",
+            Setting::AutosaveWaves => "Autosave Waves",
+        }
+    }
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Settings {
+            master_volume: 1.0,
+            effects_volume: 1.0,
+            input: InputConfig::new(),
+            resolution: None,
+            display: DisplaySettings::new(),
+            game_speed: 1,
+            auto_wave: true,
+            auto_wave_delay: BUILD_PHASE_TIME as u32,
+            checkpoints: true,
+            autosave_interval: 60,
+            autosave_waves: 1,
+            language: "en".to_string(),
+        }
+    }
+
+    /// The saved settings, the older separate files, or the defaults
+    pub fn load() -> Self {
+        match backend::read() {
+            Some(json) => Settings::from_json(&json),
+            None => {
+                info!("no settings file; importing display and key binding files");
+                Settings { input: InputConfig::load(), display: DisplaySettings::load(), ..Settings::new() }
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("settings serialize");
+        backend::write(&json)
+    }
+
+    /// Parse a settings file, pulling anything out of range back in
+    pub fn from_json(json: &str) -> Self {
+        let mut settings = serde_json::from_str::<Settings>(json).unwrap_or_else(|err| {
+            warn!(%err, "settings file unreadable; using defaults");
+            Settings::new()
+        });
+        settings.master_volume = settings.master_volume.clamp(0.0, 1.0);
+        settings.effects_volume = settings.effects_volume.clamp(0.0, 1.0);
+        settings.game_speed = settings.game_speed.clamp(1, MAX_GAME_SPEED);
+        settings.auto_wave_delay = settings.auto_wave_delay.min(MAX_AUTO_WAVE_DELAY);
+        settings.autosave_interval = settings.autosave_interval.min(MAX_AUTOSAVE_INTERVAL);
+        settings.autosave_waves = settings.autosave_waves.min(MAX_AUTOSAVE_WAVES);
+        settings.display.step_ui_scale(0);
+        settings
+    }
+
+    /// Push the window options to the platform; at startup and after edits
+    pub fn apply_window(&self) {
+        set_fullscreen(self.display.fullscreen);
+        if let (Some((width, height)), false) = (self.resolution, self.display.fullscreen) {
+            request_new_screen_size(width as f32, height as f32);
+        }
+    }
+
+    /// What the wave manager's `auto_start` should be
+    pub fn auto_start(&self) -> Option<f32> {
+        self.auto_wave.then_some(self.auto_wave_delay as f32)
+    }
+
+    /// Volume sound effects play at
+    pub fn effects_level(&self) -> f32 {
+        self.master_volume * self.effects_volume
+    }
+
+    /// Step a setting left (-1) or right (+1). Window size changes take
+    /// effect on `apply_window`.
+    pub fn adjust(&mut self, setting: Setting, step: i32) {
+        match setting {
+            Setting::MasterVolume => self.master_volume = step_volume(self.master_volume, step),
+            Setting::EffectsVolume => self.effects_volume = step_volume(self.effects_volume, step),
+            Setting::GameSpeed => {
+                self.game_speed = (self.game_speed as i32 + step).clamp(1, MAX_GAME_SPEED as i32) as u32;
+            }
+            Setting::Resolution => {
+                // "Default" comes before the sizes
+                let options: Vec<_> = std::iter::once(None).chain(RESOLUTIONS.map(Some)).collect();
+                let index = options.iter().position(|option| *option == self.resolution).unwrap_or(0) as i32;
+                self.resolution = options[(index + step).clamp(0, options.len() as i32 - 1) as usize];
+            }
+            Setting::Palette => {
+                for _ in 0..step.rem_euclid(Palette::ALL.len() as i32) {
+                    self.display.palette = self.display.palette.next();
+                }
+            }
+            // Either direction flips a switch
+            Setting::PostEffects => self.display.post.enabled = !self.display.post.enabled,
+            Setting::Crt => self.display.post.crt = !self.display.post.crt,
+            Setting::AutoWave => self.auto_wave = !self.auto_wave,
+            Setting::Checkpoints => self.checkpoints = !self.checkpoints,
+            Setting::AutoWaveDelay => {
+                let delay = self.auto_wave_delay as i32 + step * AUTO_WAVE_STEP as i32;
+                self.auto_wave_delay = delay.clamp(0, MAX_AUTO_WAVE_DELAY as i32) as u32;
+            }
+            Setting::AutosaveInterval => {
+                let interval = self.autosave_interval as i32 + step * AUTOSAVE_STEP as i32;
+                self.autosave_interval = interval.clamp(0, MAX_AUTOSAVE_INTERVAL as i32) as u32;
+            }
+            Setting::AutosaveWaves => {
+                self.autosave_waves = (self.autosave_waves as i32 + step).clamp(0, MAX_AUTOSAVE_WAVES as i32) as u32;
+            }
+        }
+    }
+
+    pub fn value(&self, setting: Setting) -> String {
+        match setting {
+            Setting::MasterVolume => format!("{:.0}%", self.master_volume * 100.0),
+            Setting::EffectsVolume => format!("{:.0}%", self.effects_volume * 100.0),
+            Setting::GameSpeed => format!("x{}", self.game_speed),
+            Setting::Resolution => match self.resolution {
+                Some((width, height)) => format!("{}x{}", width, height),
+                None => "Default".to_string(),
+            },
+            Setting::Palette => self.display.palette.name().to_string(),
+            Setting::PostEffects => on_off(self.display.post.enabled),
+            Setting::Crt => on_off(self.display.post.crt),
+            Setting::AutoWave => on_off(self.auto_wave),
+            Setting::AutoWaveDelay => format!("{}s", self.auto_wave_delay),
+            Setting::Checkpoints => on_off(self.checkpoints),
+            Setting::AutosaveInterval => match self.autosave_interval {
+                0 => "Off".to_string(),
+                seconds => format!("{}s", seconds),
+            },
+            Setting::AutosaveWaves => match self.autosave_waves {
+                0 => "Off".to_string(),
+                1 => "Every wave".to_string(),
+                waves => format!("Every {} waves", waves),
+            },
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn on_off(on: bool) -> String {
+    if on { "On" } else { "Off" }.to_string()
+}
+
+fn step_volume(volume: f32, step: i32) -> f32 {
+    ((volume + step as f32 * VOLUME_STEP) * 10.0).round().clamp(0.0, 10.0) / 10.0
+}
+
+/// The per-user config directory: `$XDG_CONFIG_HOME` (or `~/.config`) on
+/// Linux, Application Support on macOS and `%APPDATA%\rust-rush\config` on Windows
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn config_dir() -> Option<PathBuf> {
+    Some(project_dirs()?.config_dir().to_path_buf())
+}
+
+/// The per-user data directory, for saves: `$XDG_DATA_HOME` (or
+/// `~/.local/share`) on Linux, the config directory on macOS and
+/// `%APPDATA%\rust-rush\data` on Windows
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub fn data_dir() -> Option<PathBuf> {
+    Some(project_dirs()?.data_dir().to_path_buf())
+}
+
+/// Where the platform keeps this game's files; none without a home directory
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", APP_DIR)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::io;
+
+    use super::{config_dir, SETTINGS_FILE};
+
+    pub fn read() -> Option<String> {
+        std::fs::read_to_string(config_dir()?.join(SETTINGS_FILE)).ok()
+    }
+
+    pub fn write(json: &str) -> io::Result<()> {
+        let dir = config_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(SETTINGS_FILE), json)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use std::io;
+
+    use super::SETTINGS_FILE;
+    use crate::storage;
+
+    pub fn read() -> Option<String> {
+        storage::load(SETTINGS_FILE)
+    }
+
+    pub fn write(json: &str) -> io::Result<()> {
+        storage::save(SETTINGS_FILE, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{Action, Binding};
+
+    #[test]
+    fn test_settings_round_trip_and_clamp() {
+        let mut settings = Settings::new();
+        settings.input.bind(Action::Pause, Binding::Key(KeyCode::P));
+        settings.adjust(Setting::GameSpeed, 5);
+        settings.adjust(Setting::EffectsVolume, -3);
+        assert_eq!(settings.game_speed, MAX_GAME_SPEED);
+        assert_eq!(settings.effects_level(), 0.7);
+
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(Settings::from_json(&json), settings);
+
+        let edited = json.replace("\"game_speed\":3", "\"game_speed\":99");
+        assert_eq!(Settings::from_json(&edited).game_speed, MAX_GAME_SPEED);
+        assert_eq!(Settings::from_json("{}"), Settings::new()); // Missing fields keep defaults
+
+        settings.adjust(Setting::AutoWaveDelay, -10);
+        assert_eq!(settings.auto_start(), Some(0.0));
+        settings.adjust(Setting::AutoWave, 1);
+        assert_eq!(settings.auto_start(), None);
+    }
+}
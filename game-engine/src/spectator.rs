@@ -0,0 +1,44 @@
+//! Read-only observer mode. Gameplay input is ignored; the left mouse button
+//! drags the camera anywhere and the wheel zooms, while a panel shows the
+//! player's economy and run stats. Handy for watching a shared run or
+//! poking at a bug without touching the game.
+
+use macroquad::prelude::*;
+
+use crate::camera::GameCamera;
+
+#[derive(Debug, Clone, Default)]
+pub struct Spectator {
+    pub active: bool,
+    drag_from: Option<Vec2>, // Last cursor position while panning
+}
+
+impl Spectator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+        self.drag_from = None;
+    }
+
+    /// Free camera: drag to pan, wheel to zoom
+    pub fn handle_input(&mut self, camera: &mut GameCamera) {
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0.0 {
+            let factor = if wheel > 0.0 { 1.1 } else { 1.0 / 1.1 };
+            camera.zoom_at(factor, mouse_position().into(), screen_width(), screen_height());
+        }
+
+        let cursor: Vec2 = mouse_position().into();
+        if is_mouse_button_down(MouseButton::Left) {
+            if let Some(from) = self.drag_from {
+                camera.pan_by_screen(cursor - from);
+            }
+            self.drag_from = Some(cursor);
+        } else {
+            self.drag_from = None;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Small key/value persistence layer. Native builds store each key as a file
+//! in the working directory; web builds (`wasm` feature on wasm32) keep them
+//! in the browser's LocalStorage through the `web/storage.js` plugin.
+
+use std::io;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::io;
+
+    pub fn read(key: &str) -> Option<String> {
+        std::fs::read_to_string(key).ok()
+    }
+
+    pub fn write(key: &str, value: &str) -> io::Result<()> {
+        std::fs::write(key, value)
+    }
+
+    pub fn remove(key: &str) -> io::Result<()> {
+        match std::fs::remove_file(key) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod backend {
+    use std::io;
+
+    const KEY_PREFIX: &str = "rust-rush/";
+
+    // Provided by web/storage.js
+    extern "C" {
+        fn rust_rush_storage_len(key_ptr: *const u8, key_len: usize) -> i32;
+        fn rust_rush_storage_read(key_ptr: *const u8, key_len: usize, out_ptr: *mut u8, out_len: usize);
+        fn rust_rush_storage_write(key_ptr: *const u8, key_len: usize, value_ptr: *const u8, value_len: usize) -> i32;
+        fn rust_rush_storage_remove(key_ptr: *const u8, key_len: usize) -> i32;
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        // Negative length means the key is missing
+        let len = unsafe { rust_rush_storage_len(key.as_ptr(), key.len()) };
+        if len < 0 {
+            return None;
+        }
+        let mut buffer = vec![0u8; len as usize];
+        unsafe { rust_rush_storage_read(key.as_ptr(), key.len(), buffer.as_mut_ptr(), buffer.len()) };
+        String::from_utf8(buffer).ok()
+    }
+
+    pub fn write(key: &str, value: &str) -> io::Result<()> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        let ok = unsafe { rust_rush_storage_write(key.as_ptr(), key.len(), value.as_ptr(), value.len()) };
+        if ok == 1 {
+            Ok(())
+        } else {
+            Err(io::Error::other("LocalStorage write failed"))
+        }
+    }
+
+    pub fn remove(key: &str) -> io::Result<()> {
+        let key = format!("{}{}", KEY_PREFIX, key);
+        let ok = unsafe { rust_rush_storage_remove(key.as_ptr(), key.len()) };
+        if ok == 1 {
+            Ok(())
+        } else {
+            Err(io::Error::other("LocalStorage remove failed"))
+        }
+    }
+}
+
+// wasm32 without the JS plugin: nothing persists, but the game still runs
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+mod backend {
+    use std::io;
+
+    pub fn read(_key: &str) -> Option<String> {
+        None
+    }
+
+    pub fn write(_key: &str, _value: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "no storage backend"))
+    }
+
+    pub fn remove(_key: &str) -> io::Result<()> {
+        Ok(()) // Nothing was ever stored
+    }
+}
+
+/// Read a previously saved value
+pub fn load(key: &str) -> Option<String> {
+    backend::read(key)
+}
+
+/// Persist a value under `key`, replacing any previous one
+pub fn save(key: &str, value: &str) -> io::Result<()> {
+    backend::write(key, value)
+}
+
+/// Forget the value under `key`; fine if there is none
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))] // Only the web build's save slots live here
+pub fn remove(key: &str) -> io::Result<()> {
+    backend::remove(key)
+}
@@ -0,0 +1,311 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use rust_rush_core::abilities::AbilityType;
+use rust_rush_core::traps::TrapType;
+use rust_rush_core::{EffectStyle, TowerType};
+
+/// Selectable color schemes. Every palette is paired with shape coding, so
+/// nothing on screen is distinguished by color alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Palette {
+    #[default]
+    Standard,
+    Colorblind, // Okabe-Ito: safe for deuteranopia, protanopia and tritanopia
+    HighContrast,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Palette::Standard, Palette::Colorblind, Palette::HighContrast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Standard => "Standard",
+            Palette::Colorblind => "Colorblind",
+            Palette::HighContrast => "High Contrast",
+        }
+    }
+
+    pub fn next(&self) -> Palette {
+        let index = Palette::ALL.iter().position(|p| p == self).unwrap_or(0);
+        Palette::ALL[(index + 1) % Palette::ALL.len()]
+    }
+}
+
+// Okabe-Ito colors
+const OI_ORANGE: Color = Color::new(0.90, 0.62, 0.0, 1.0);
+const OI_SKY_BLUE: Color = Color::new(0.34, 0.71, 0.91, 1.0);
+const OI_GREEN: Color = Color::new(0.0, 0.62, 0.45, 1.0);
+const OI_YELLOW: Color = Color::new(0.94, 0.89, 0.26, 1.0);
+const OI_BLUE: Color = Color::new(0.0, 0.45, 0.70, 1.0);
+const OI_VERMILLION: Color = Color::new(0.84, 0.37, 0.0, 1.0);
+const OI_PURPLE: Color = Color::new(0.80, 0.47, 0.65, 1.0);
+
+/// Base outline drawn for each tower type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TowerShape {
+    Circle,
+    Triangle,
+    Square,
+    Diamond,
+    Pentagon,
+    Hexagon,
+    Octagon,
+    Star,
+    Wedge,
+    Crosshair,
+}
+
+impl TowerShape {
+    pub fn of(tower_type: TowerType) -> TowerShape {
+        match tower_type {
+            TowerType::Basic => TowerShape::Circle,
+            TowerType::Sniper => TowerShape::Triangle,
+            TowerType::Splash => TowerShape::Hexagon,
+            TowerType::Slow => TowerShape::Diamond,
+            TowerType::Cannon => TowerShape::Square,
+            TowerType::Shredder => TowerShape::Wedge,
+            TowerType::Marker => TowerShape::Crosshair,
+            TowerType::FrostBomb => TowerShape::Star,
+            TowerType::Railgun => TowerShape::Pentagon,
+            TowerType::Gatling => TowerShape::Octagon,
+        }
+    }
+
+    /// Filled shape centered on (x, y) fitting inside `radius`
+    pub fn draw(&self, x: f32, y: f32, radius: f32, color: Color) {
+        match self {
+            TowerShape::Circle => draw_circle(x, y, radius, color),
+            TowerShape::Triangle => draw_poly(x, y, 3, radius, -90.0, color),
+            TowerShape::Square => draw_poly(x, y, 4, radius, 45.0, color),
+            TowerShape::Diamond => draw_poly(x, y, 4, radius, 0.0, color),
+            TowerShape::Pentagon => draw_poly(x, y, 5, radius, -90.0, color),
+            TowerShape::Hexagon => draw_poly(x, y, 6, radius, 0.0, color),
+            TowerShape::Octagon => draw_poly(x, y, 8, radius, 22.5, color),
+            TowerShape::Star => {
+                draw_poly(x, y, 4, radius, 0.0, color);
+                draw_poly(x, y, 4, radius, 45.0, color);
+            }
+            TowerShape::Wedge => draw_poly(x, y, 3, radius, 90.0, color),
+            TowerShape::Crosshair => {
+                draw_circle_lines(x, y, radius * 0.85, radius * 0.3, color);
+                draw_circle(x, y, radius * 0.3, color);
+            }
+        }
+    }
+}
+
+/// Every color `render_world` uses for board state, picked from one palette
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub palette: Palette,
+    pub cell: Color,
+    pub cell_blocked: Color,
+    pub cell_border: Color,
+    pub spawn: Color,
+    pub goal: Color,
+    pub enemy: Color,
+    pub enemy_slowed: Color,
+    pub health_high: Color,
+    pub health_low: Color,
+    pub health_back: Color,
+    pub gold: Color,
+    pub danger: Color,
+    pub text: Color,
+}
+
+impl Theme {
+    pub fn new(palette: Palette) -> Self {
+        match palette {
+            Palette::Standard => Theme {
+                palette,
+                cell: Color::from_rgba(30, 30, 30, 255),
+                cell_blocked: Color::from_rgba(60, 60, 60, 255),
+                cell_border: Color::from_rgba(50, 50, 50, 255),
+                spawn: Color::from_rgba(50, 150, 50, 255),
+                goal: Color::from_rgba(150, 50, 50, 255),
+                enemy: RED,
+                enemy_slowed: SKYBLUE,
+                health_high: GREEN,
+                health_low: ORANGE,
+                health_back: DARKGRAY,
+                gold: GOLD,
+                danger: RED,
+                text: WHITE,
+            },
+            Palette::Colorblind => Theme {
+                palette,
+                cell: Color::from_rgba(30, 30, 30, 255),
+                cell_blocked: Color::from_rgba(70, 70, 70, 255),
+                cell_border: Color::from_rgba(50, 50, 50, 255),
+                spawn: OI_BLUE,
+                goal: OI_VERMILLION,
+                enemy: OI_VERMILLION,
+                enemy_slowed: OI_SKY_BLUE,
+                health_high: OI_BLUE,
+                health_low: OI_ORANGE,
+                health_back: DARKGRAY,
+                gold: OI_YELLOW,
+                danger: OI_VERMILLION,
+                text: WHITE,
+            },
+            Palette::HighContrast => Theme {
+                palette,
+                cell: BLACK,
+                cell_blocked: Color::from_rgba(90, 90, 90, 255),
+                cell_border: Color::from_rgba(110, 110, 110, 255),
+                spawn: WHITE,
+                goal: YELLOW,
+                enemy: WHITE,
+                enemy_slowed: Color::from_rgba(0, 255, 255, 255),
+                health_high: WHITE,
+                health_low: YELLOW,
+                health_back: Color::from_rgba(60, 60, 60, 255),
+                gold: YELLOW,
+                danger: Color::from_rgba(255, 80, 255, 255),
+                text: WHITE,
+            },
+        }
+    }
+
+    pub fn tower_color(&self, tower_type: TowerType) -> Color {
+        match self.palette {
+            Palette::Standard => match tower_type {
+                TowerType::Basic => BLUE,
+                TowerType::Sniper => RED,
+                TowerType::Splash => ORANGE,
+                TowerType::Slow => SKYBLUE,
+                TowerType::Cannon => Color::from_rgba(120, 90, 60, 255),
+                TowerType::Shredder => Color::from_rgba(200, 120, 50, 255),
+                TowerType::Marker => MAGENTA,
+                TowerType::FrostBomb => Color::from_rgba(120, 220, 230, 255),
+                TowerType::Railgun => MAROON,
+                TowerType::Gatling => DARKBLUE,
+            },
+            Palette::Colorblind => match tower_type {
+                TowerType::Basic => OI_BLUE,
+                TowerType::Sniper => OI_VERMILLION,
+                TowerType::Splash => OI_ORANGE,
+                TowerType::Slow => OI_SKY_BLUE,
+                TowerType::Cannon => OI_YELLOW,
+                TowerType::Shredder => OI_ORANGE,
+                TowerType::Marker => OI_PURPLE,
+                TowerType::FrostBomb => OI_GREEN,
+                TowerType::Railgun => OI_PURPLE,
+                TowerType::Gatling => Color::from_rgba(150, 150, 150, 255),
+            },
+            // Shapes carry the distinction; keep towers bright against black
+            Palette::HighContrast => match tower_type {
+                TowerType::Sniper | TowerType::Railgun => YELLOW,
+                TowerType::Slow | TowerType::FrostBomb => Color::from_rgba(0, 255, 255, 255),
+                _ => WHITE,
+            },
+        }
+    }
+
+    pub fn projectile_color(&self, tower_type: TowerType) -> Color {
+        match tower_type {
+            TowerType::Basic => YELLOW,
+            TowerType::Sniper => RED,
+            TowerType::Splash => ORANGE,
+            TowerType::Slow => Color::from_rgba(100, 200, 255, 255),
+            TowerType::Cannon => DARKGRAY,
+            TowerType::Shredder => ORANGE,
+            TowerType::Marker => MAGENTA,
+            TowerType::FrostBomb => WHITE,
+            TowerType::Railgun => PINK,
+            TowerType::Gatling => YELLOW,
+        }
+    }
+
+    pub fn ability_color(&self, ability_type: AbilityType) -> Color {
+        match ability_type {
+            AbilityType::MeteorStrike => ORANGE,
+            AbilityType::GlobalFreeze => SKYBLUE,
+            AbilityType::EmergencyRepair => GREEN,
+        }
+    }
+
+    pub fn trap_color(&self, trap_type: TrapType) -> Color {
+        match trap_type {
+            TrapType::Mine => Color::from_rgba(200, 60, 40, 255),
+            TrapType::Glue => Color::from_rgba(180, 170, 60, 255),
+            TrapType::Spikes => LIGHTGRAY,
+        }
+    }
+
+    /// Color for a flash, explosion or floating text from the simulation
+    pub fn effect_color(&self, style: EffectStyle) -> Color {
+        match style {
+            EffectStyle::Projectile(tower_type) => self.projectile_color(tower_type),
+            EffectStyle::Splash => ORANGE,
+            EffectStyle::Frost => SKYBLUE,
+            EffectStyle::Ability(ability_type) => self.ability_color(ability_type),
+            EffectStyle::Trap(trap_type) => self.trap_color(trap_type),
+            EffectStyle::HeroStrike => WHITE,
+            EffectStyle::Leak => self.danger,
+            EffectStyle::Delivery => self.gold,
+        }
+    }
+
+    /// Health bar with quarter ticks; the low-health portion is also hatched
+    /// so it reads without relying on the fill color
+    pub fn draw_health_bar(&self, x: f32, y: f32, width: f32, height: f32, ratio: f32) {
+        let ratio = ratio.clamp(0.0, 1.0);
+        let low = ratio <= 0.5;
+        draw_rectangle(x, y, width, height, self.health_back);
+        draw_rectangle(
+            x,
+            y,
+            width * ratio,
+            height,
+            if low { self.health_low } else { self.health_high },
+        );
+
+        if low {
+            let stripe = Color::new(0.0, 0.0, 0.0, 0.6);
+            let mut sx = x;
+            while sx < x + width * ratio {
+                let end = (sx + height).min(x + width * ratio);
+                draw_line(sx, y + height, end, y, 1.0, stripe);
+                sx += height * 1.5;
+            }
+        }
+
+        for quarter in 1..4 {
+            let tx = x + width * quarter as f32 / 4.0;
+            draw_line(tx, y, tx, y + height, 1.0, BLACK);
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(Palette::Standard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_cycles_through_all() {
+        let mut palette = Palette::Standard;
+        for _ in 0..Palette::ALL.len() {
+            palette = palette.next();
+        }
+        assert_eq!(palette, Palette::Standard);
+    }
+
+    #[test]
+    fn test_similar_towers_have_distinct_shapes() {
+        // Towers that share a color in high-contrast mode must differ in shape
+        let theme = Theme::new(Palette::HighContrast);
+        assert_eq!(theme.tower_color(TowerType::Slow), theme.tower_color(TowerType::FrostBomb));
+        assert_ne!(TowerShape::of(TowerType::Slow), TowerShape::of(TowerType::FrostBomb));
+        assert_ne!(TowerShape::of(TowerType::Sniper), TowerShape::of(TowerType::Railgun));
+        assert_ne!(TowerShape::of(TowerType::Basic), TowerShape::of(TowerType::Cannon));
+        assert_ne!(TowerShape::of(TowerType::Shredder), TowerShape::of(TowerType::Marker));
+    }
+}
@@ -0,0 +1,198 @@
+//! Hover tooltips. Resting the cursor on something for `HOVER_DELAY`
+//! describes it beside the cursor: an enemy's kind, health, armor and status
+//! effects; a tower's stats and kills; or, on bare terrain, what the cell is
+//! and what the current build tool would cost there and whether it fits.
+//! Enemies win over the tower or cell under them. The box flips to the
+//! other side of the cursor rather than run off the screen, and a click
+//! hides it until the cursor rests again.
+
+use macroquad::prelude::*;
+use rust_rush_core::stat_sheet::Stat;
+use rust_rush_core::status_effects::EffectKind;
+use rust_rush_core::{BuildTool, EnemyId, Game, Position, TowerId};
+
+use crate::camera::GameCamera;
+use crate::display::HudLayout;
+use crate::theme::Theme;
+
+const HOVER_DELAY: f32 = 0.35;
+const FONT_SIZE: f32 = 16.0;
+const LINE_HEIGHT: f32 = 18.0;
+const PADDING: f32 = 6.0;
+const CURSOR_GAP: Vec2 = vec2(16.0, 20.0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Hovered {
+    Enemy(EnemyId),
+    Tower(TowerId),
+    Cell(Position),
+}
+
+pub struct Tooltips {
+    hovered: Option<Hovered>,
+    resting: f32, // Seconds the cursor has stayed on `hovered`
+    buildable: Option<((Position, u64, i32, BuildTool), bool)>, // Last fit check, by cell, grid revision, gold and tool
+}
+
+impl Tooltips {
+    pub fn new() -> Self {
+        Tooltips { hovered: None, resting: 0.0, buildable: None }
+    }
+
+    /// Each frame: what's under the cursor, and for how long
+    pub fn update(&mut self, game: &Game, camera: &GameCamera, delta: f32) {
+        let (mx, my) = camera.mouse_world().into();
+        let cell = game.state.grid.cell_at(mx, my);
+        let hovered = if let Some(enemy_id) = crate::input::enemy_under(game, mx, my) {
+            Some(Hovered::Enemy(enemy_id))
+        } else if let Some(tower) = game.state.tower_at(&cell) {
+            Some(Hovered::Tower(tower.id))
+        } else {
+            game.state.grid.in_bounds(&cell).then_some(Hovered::Cell(cell))
+        };
+        let clicked = is_mouse_button_pressed(MouseButton::Left) || is_mouse_button_pressed(MouseButton::Right);
+        if hovered == self.hovered && !clicked {
+            self.resting += delta;
+        } else {
+            (self.hovered, self.resting) = (hovered, 0.0);
+        }
+        if let (Some(Hovered::Cell(cell)), BuildTool::Tower(tower_type)) = (hovered, game.build_tool) {
+            let key = (cell, game.state.grid.revision(), game.state.gold, game.build_tool);
+            if self.buildable.is_none_or(|(checked, _)| checked != key) && self.resting >= HOVER_DELAY {
+                self.buildable = Some((key, game.state.can_place_tower(tower_type, cell)));
+            }
+        }
+    }
+
+    pub fn render(&self, game: &Game, hud: &HudLayout, theme: &Theme) {
+        if self.resting < HOVER_DELAY {
+            return;
+        }
+        let lines = match self.hovered {
+            Some(Hovered::Enemy(id)) => enemy_lines(game, id),
+            Some(Hovered::Tower(id)) => tower_lines(game, id),
+            Some(Hovered::Cell(cell)) => self.cell_lines(game, cell),
+            None => return,
+        };
+        if lines.is_empty() {
+            return;
+        }
+        let width = lines.iter().map(|line| measure_text(line, None, FONT_SIZE as u16, 1.0).width).fold(0.0, f32::max);
+        let size = vec2(width + PADDING * 2.0, lines.len() as f32 * LINE_HEIGHT + PADDING * 2.0);
+        let cursor = Vec2::from(mouse_position()) / hud.scale;
+        let at = placement(cursor, size, vec2(hud.width, hud.height));
+
+        set_camera(&hud.camera());
+        draw_rectangle(at.x, at.y, size.x, size.y, Color::new(0.0, 0.0, 0.0, 0.85));
+        draw_rectangle_lines(at.x, at.y, size.x, size.y, 1.0, theme.cell_border);
+        for (row, line) in lines.iter().enumerate() {
+            let color = if row == 0 { theme.gold } else { theme.text };
+            draw_text(line, at.x + PADDING, at.y + PADDING + (row as f32 + 0.8) * LINE_HEIGHT, FONT_SIZE, color);
+        }
+        set_default_camera();
+    }
+
+    fn cell_lines(&self, game: &Game, cell: Position) -> Vec<String> {
+        let state = &game.state;
+        let mut lines = Vec::new();
+        if cell == state.spawn_point {
+            lines.push("Spawn".to_string());
+        } else if cell == state.goal_point {
+            lines.push("Goal".to_string());
+        } else if let Some(obstacle) = state.obstacle_at(&cell) {
+            lines.push(format!("{}: ${} to clear", obstacle.kind.name(), state.rules.price(obstacle.kind.clear_cost())));
+        } else if let Some(trap) = state.trap_at(&cell) {
+            lines.push(format!("{} Trap", trap.trap_type.name()));
+        } else if state.is_portal(&cell) {
+            lines.push("Spawn portal".to_string());
+        } else if state.is_gated(&cell) {
+            lines.push("Gate".to_string());
+        } else if state.grid.is_walkable(&cell) {
+            lines.push("Open ground".to_string());
+        } else {
+            lines.push("Wall".to_string());
+        }
+        if let Some(exit) = state.grid.teleporter(&cell) {
+            lines.push(format!("Teleports to ({}, {})", exit.x, exit.y));
+        }
+        if let Some(direction) = state.grid.one_way(&cell) {
+            lines.push(format!("One way: {:?}", direction));
+        }
+        let price = state.rules.price(game.build_tool.cost());
+        let fits = match self.buildable {
+            Some(((checked, ..), fits)) if checked == cell => Some(fits),
+            _ => None,
+        };
+        lines.push(match fits {
+            Some(true) => format!("{}: ${} here", game.build_tool.name(), price),
+            Some(false) => format!("{}: ${}, can't build here", game.build_tool.name(), price),
+            None => format!("{}: ${}", game.build_tool.name(), price),
+        });
+        lines
+    }
+}
+
+fn enemy_lines(game: &Game, id: EnemyId) -> Vec<String> {
+    let Some(enemy) = game.state.enemies.get(id) else {
+        return Vec::new();
+    };
+    let mut lines = vec![
+        enemy.kind.name().to_string(),
+        format!("Health {} / {}", enemy.health.max(0), enemy.max_health),
+        format!("Armor {:.0}%", enemy.armor * 100.0),
+    ];
+    for effect in enemy.effects.entries() {
+        let strength = match effect.kind {
+            EffectKind::Burn => format!("{:.0}/s", effect.magnitude),
+            EffectKind::Slow | EffectKind::Shred | EffectKind::Amplify => format!("{:.0}%", effect.magnitude * 100.0),
+        };
+        lines.push(format!("{:?} {} ({:.1}s)", effect.kind, strength, effect.remaining));
+    }
+    lines
+}
+
+fn tower_lines(game: &Game, id: TowerId) -> Vec<String> {
+    let Some(tower) = game.state.towers.get(id) else {
+        return Vec::new();
+    };
+    let stats = &tower.stats;
+    let mut lines = vec![
+        format!("{:?} Tower{}", tower.tower_type, if tower.disabled { " (off)" } else { "" }),
+        format!("Damage {:.0}   Range {:.1}", stats.get(Stat::Damage), stats.get(Stat::Range)),
+        format!("Fire rate {:.2}/s", stats.get(Stat::FireRate)),
+        format!("Kills {}", tower.kills),
+    ];
+    if game.state.rules.ammo {
+        let magazine = tower.tower_type.magazine();
+        lines.push(format!("Ammo {} / {}", magazine.saturating_sub(tower.rounds_fired), magazine));
+    }
+    if tower.damage_taken > 0 {
+        lines.push(format!("Damage taken {}", tower.damage_taken));
+    }
+    lines
+}
+
+/// Top-left of a box of `size` beside the cursor, flipped to the cursor's
+/// other side on either axis where it would leave the screen
+fn placement(cursor: Vec2, size: Vec2, screen: Vec2) -> Vec2 {
+    let flip = |cursor: f32, gap: f32, size: f32, screen: f32| {
+        let at = if cursor + gap + size <= screen { cursor + gap } else { cursor - gap / 2.0 - size };
+        at.clamp(0.0, (screen - size).max(0.0))
+    };
+    vec2(flip(cursor.x, CURSOR_GAP.x, size.x, screen.x), flip(cursor.y, CURSOR_GAP.y, size.y, screen.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tooltip_stays_on_screen() {
+        let (size, screen) = (vec2(200.0, 80.0), vec2(800.0, 600.0));
+        assert_eq!(placement(vec2(100.0, 100.0), size, screen), vec2(116.0, 120.0));
+        // Near the bottom-right corner it sits up and to the left of the cursor
+        assert_eq!(placement(vec2(700.0, 580.0), size, screen), vec2(492.0, 490.0));
+        // Too big to fit either side: pinned to the edge
+        assert_eq!(placement(vec2(10.0, 10.0), vec2(900.0, 80.0), screen).x, 0.0);
+    }
+}
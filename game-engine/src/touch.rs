@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use rust_rush_core::Position;
+
+const TAP_SLOP: f32 = 12.0; // Pixels a finger may wander and still count as a tap
+const TAP_MAX_DURATION: f64 = 0.35;
+const RADIAL_RADIUS: f32 = 70.0;
+const RADIAL_BUTTON_RADIUS: f32 = 24.0;
+
+/// High-level gestures recognized from raw touches, in screen space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    Tap(Vec2),
+    Pan(Vec2),
+    Pinch { factor: f32, center: Vec2 },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TouchTrack {
+    start: Vec2,
+    last: Vec2,
+    start_time: f64,
+}
+
+/// Turns macroquad's per-frame touch list into taps, one-finger pans and pinches
+#[derive(Debug, Default)]
+pub struct GestureRecognizer {
+    tracks: HashMap<u64, TouchTrack>,
+    pinch_distance: Option<f32>,
+    dragging: bool,
+    multi_touch: bool, // Set once a second finger lands; suppresses taps until all lift
+}
+
+impl GestureRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, touches: &[Touch], now: f64) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+
+        for touch in touches {
+            match touch.phase {
+                TouchPhase::Started => {
+                    self.tracks.insert(
+                        touch.id,
+                        TouchTrack {
+                            start: touch.position,
+                            last: touch.position,
+                            start_time: now,
+                        },
+                    );
+                    if self.tracks.len() > 1 {
+                        self.multi_touch = true;
+                    }
+                }
+                TouchPhase::Moved | TouchPhase::Stationary => {
+                    let single = self.tracks.len() == 1 && !self.multi_touch;
+                    if let Some(track) = self.tracks.get_mut(&touch.id) {
+                        if single {
+                            if (touch.position - track.start).length() > TAP_SLOP {
+                                self.dragging = true;
+                            }
+                            if self.dragging && touch.position != track.last {
+                                gestures.push(Gesture::Pan(touch.position - track.last));
+                            }
+                        }
+                        track.last = touch.position;
+                    }
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    if let Some(track) = self.tracks.remove(&touch.id) {
+                        let is_tap = touch.phase == TouchPhase::Ended
+                            && !self.dragging
+                            && !self.multi_touch
+                            && now - track.start_time <= TAP_MAX_DURATION
+                            && (touch.position - track.start).length() <= TAP_SLOP;
+                        if is_tap {
+                            gestures.push(Gesture::Tap(touch.position));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Two fingers: pinch zoom around their midpoint
+        if self.tracks.len() == 2 {
+            let points: Vec<Vec2> = self.tracks.values().map(|track| track.last).collect();
+            let distance = (points[0] - points[1]).length();
+            if let Some(previous) = self.pinch_distance {
+                if previous > 0.0 && distance > 0.0 && (distance - previous).abs() > f32::EPSILON {
+                    gestures.push(Gesture::Pinch {
+                        factor: distance / previous,
+                        center: (points[0] + points[1]) / 2.0,
+                    });
+                }
+            }
+            self.pinch_distance = Some(distance);
+        } else {
+            self.pinch_distance = None;
+        }
+
+        if self.tracks.is_empty() {
+            self.dragging = false;
+            self.multi_touch = false;
+        }
+
+        gestures
+    }
+}
+
+/// Contextual ring of build options shown around a tapped cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadialMenu {
+    pub cell: Position,
+    pub center: Vec2, // Screen space
+    pub scale: f32,
+}
+
+impl RadialMenu {
+    pub fn new(cell: Position, center: Vec2, scale: f32) -> Self {
+        RadialMenu { cell, center, scale }
+    }
+
+    pub fn button_radius(&self) -> f32 {
+        RADIAL_BUTTON_RADIUS * self.scale
+    }
+
+    /// Screen position of option `index` out of `count`, starting at the top
+    pub fn option_position(&self, index: usize, count: usize) -> Vec2 {
+        let angle = -std::f32::consts::FRAC_PI_2 + index as f32 / count as f32 * std::f32::consts::TAU;
+        self.center + vec2(angle.cos(), angle.sin()) * RADIAL_RADIUS * self.scale
+    }
+
+    pub fn option_at(&self, point: Vec2, count: usize) -> Option<usize> {
+        (0..count).find(|i| (self.option_position(*i, count) - point).length() <= self.button_radius())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: u64, phase: TouchPhase, x: f32, y: f32) -> Touch {
+        Touch {
+            id,
+            phase,
+            position: vec2(x, y),
+        }
+    }
+
+    #[test]
+    fn test_quick_touch_is_tap() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.update(&[touch(1, TouchPhase::Started, 100.0, 100.0)], 0.0);
+        let gestures = recognizer.update(&[touch(1, TouchPhase::Ended, 102.0, 101.0)], 0.1);
+        assert_eq!(gestures, vec![Gesture::Tap(vec2(102.0, 101.0))]);
+    }
+
+    #[test]
+    fn test_drag_pans_and_is_not_tap() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.update(&[touch(1, TouchPhase::Started, 100.0, 100.0)], 0.0);
+        let gestures = recognizer.update(&[touch(1, TouchPhase::Moved, 150.0, 100.0)], 0.05);
+        assert_eq!(gestures, vec![Gesture::Pan(vec2(50.0, 0.0))]);
+        let gestures = recognizer.update(&[touch(1, TouchPhase::Ended, 150.0, 100.0)], 0.1);
+        assert!(gestures.is_empty());
+    }
+
+    #[test]
+    fn test_two_fingers_pinch() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.update(
+            &[touch(1, TouchPhase::Started, 100.0, 100.0), touch(2, TouchPhase::Started, 200.0, 100.0)],
+            0.0,
+        );
+        let gestures = recognizer.update(
+            &[touch(1, TouchPhase::Moved, 50.0, 100.0), touch(2, TouchPhase::Moved, 250.0, 100.0)],
+            0.05,
+        );
+        assert_eq!(
+            gestures,
+            vec![Gesture::Pinch {
+                factor: 2.0,
+                center: vec2(150.0, 100.0)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_radial_option_hit_test() {
+        let menu = RadialMenu::new(Position::new(0, 0), vec2(200.0, 200.0), 1.0);
+        let top = menu.option_position(0, 4);
+        assert!((top - vec2(200.0, 130.0)).length() < 0.01);
+        assert_eq!(menu.option_at(top, 4), Some(0));
+        assert_eq!(menu.option_at(vec2(200.0, 200.0), 4), None);
+    }
+}
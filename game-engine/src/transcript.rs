@@ -0,0 +1,128 @@
+//! Playback of a bot transcript: the JSON-lines `BotRequest`s a bot sends
+//! the server's `--stdio` mode, saved to a file and loaded with `--replay`.
+//! Each `Step` applies its commands and runs its ticks at the bot tick
+//! rate, so the run comes out exactly as the bot played it; `Reset` goes
+//! back to the game as it was when the transcript was loaded.
+
+use std::collections::VecDeque;
+use std::io;
+use std::path::Path;
+
+use rust_rush_core::bot::{BotRequest, TICK};
+use rust_rush_core::rollback::GameSnapshot;
+use rust_rush_core::Game;
+
+pub struct Transcript {
+    start: GameSnapshot,
+    requests: VecDeque<BotRequest>,
+    ticks_left: u32, // Of the current step
+    carry: f32,      // Game time not yet run as a whole tick
+}
+
+impl Transcript {
+    pub fn load(path: &Path, game: &Game) -> io::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?, game)
+    }
+
+    /// One request per line; blank lines are skipped
+    pub fn parse(text: &str, game: &Game) -> io::Result<Self> {
+        let requests = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|err| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, err))
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Transcript { start: game.snapshot(), requests, ticks_left: 0, carry: 0.0 })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.ticks_left == 0 && self.requests.is_empty()
+    }
+
+    /// Run `delta` seconds of the transcript, in whole ticks
+    pub fn advance(&mut self, game: &mut Game, delta: f32) {
+        self.carry += delta;
+        while self.carry >= TICK && self.next_tick(game) {
+            game.update(TICK);
+            self.carry -= TICK;
+        }
+        if self.is_finished() {
+            self.carry = 0.0;
+        }
+    }
+
+    /// Run everything that's left
+    pub fn run_to_end(&mut self, game: &mut Game) {
+        while self.next_tick(game) {
+            game.update(TICK);
+        }
+    }
+
+    /// Work through requests until one has a tick to run. False once the
+    /// transcript is used up.
+    fn next_tick(&mut self, game: &mut Game) -> bool {
+        let over = |game: &Game| game.state.health <= 0 || game.state.objective.is_decided();
+        if over(game) {
+            self.ticks_left = 0; // The session stops stepping a finished game too
+        }
+        while self.ticks_left == 0 {
+            match self.requests.pop_front() {
+                Some(BotRequest::Reset) => game.restore(&self.start),
+                Some(BotRequest::Observe) => {}
+                Some(BotRequest::Step { commands, ticks }) => {
+                    for command in &commands {
+                        if let Err(err) = game.apply_command(command) {
+                            tracing::debug!(?command, %err, "transcript command refused");
+                        }
+                    }
+                    self.ticks_left = if over(game) { 0 } else { ticks };
+                }
+                None => return false,
+            }
+        }
+        self.ticks_left -= 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_rush_core::bot::BotSession;
+    use rust_rush_core::protocol::Command;
+    use rust_rush_core::{BuildTool, Position, TowerType};
+
+    use super::*;
+
+    #[test]
+    fn test_playback_matches_the_live_session() {
+        let build = Command::Build { tool: BuildTool::Tower(TowerType::Basic), position: Position::new(4, 6) };
+        let requests = [
+            BotRequest::Step { commands: vec![build], ticks: 40 },
+            BotRequest::Observe,
+            BotRequest::Step { commands: vec![Command::CallNextWave], ticks: 200 },
+        ];
+        let mut session = BotSession::new();
+        let text: String = requests
+            .iter()
+            .map(|request| {
+                session.handle(request);
+                serde_json::to_string(request).unwrap() + "\n\n"
+            })
+            .collect();
+
+        let mut game = Game::new();
+        let mut transcript = Transcript::parse(&text, &game).unwrap();
+        transcript.advance(&mut game, 30.5 * TICK);
+        assert_eq!(game.tick, 30);
+        transcript.run_to_end(&mut game);
+        assert!(transcript.is_finished());
+        assert_eq!(game.tick, session.game.tick);
+        assert_eq!(game.checksum(), session.game.checksum());
+
+        assert!(Transcript::parse("{\"type\":\"Jump\"}", &game).is_err());
+    }
+}
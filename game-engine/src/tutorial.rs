@@ -0,0 +1,124 @@
+//! Frontend for `rust_rush_core::tutorial`: the instruction panel, a
+//! pulsing frame around the highlighted cell, and input gating. While the
+//! script runs, the board takes only clicks, build-slot keys and pause;
+//! clicks turn into commands the script has to allow.
+
+use macroquad::prelude::*;
+use rust_rush_core::protocol::Command;
+use rust_rush_core::tutorial::{first_game, StepGoal, Tutorial};
+use rust_rush_core::{BuildTool, Game, CELL_SIZE};
+
+use crate::camera::GameCamera;
+use crate::display::HudLayout;
+use crate::input::{Action, InputConfig};
+use crate::theme::Theme;
+
+const PANEL_WIDTH: f32 = 560.0;
+const LINE_HEIGHT: f32 = 24.0;
+const CHARS_PER_LINE: usize = 52;
+
+pub struct TutorialGuide {
+    tutorial: Tutorial,
+}
+
+impl TutorialGuide {
+    pub fn new() -> Self {
+        TutorialGuide { tutorial: Tutorial::new(first_game()) }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.tutorial.is_finished()
+    }
+
+    pub fn handle_input(&mut self, game: &mut Game, input: &InputConfig, camera: &GameCamera) {
+        if input.pressed(Action::Pause) {
+            let _ = game.apply_command(&Command::SetPaused { paused: !game.state.paused });
+        }
+        for (action, tool) in Action::BUILD_SLOTS.iter().zip(BuildTool::SLOTS) {
+            if input.pressed(*action) {
+                game.build_tool = tool;
+            }
+        }
+        if input.pressed(Action::PlaceTower) && !game.state.paused && !self.tutorial.acknowledge() {
+            let (mx, my) = camera.mouse_world().into();
+            let command = Command::Build { tool: game.build_tool, position: game.state.grid.cell_at(mx, my) };
+            if self.tutorial.allows(&command) {
+                let _ = game.apply_command(&command); // A refused build just leaves the step waiting
+            }
+        }
+    }
+
+    pub fn update(&mut self, game: &mut Game, delta: f32) {
+        self.tutorial.update(game, delta);
+    }
+
+    pub fn render(&self, game: &Game, input: &InputConfig, camera: &GameCamera, hud: &HudLayout, theme: &Theme) {
+        let Some(step) = self.tutorial.step() else {
+            return;
+        };
+
+        if let Some(cell) = step.highlight {
+            set_camera(&camera.camera2d());
+            let (wx, wy) = cell.to_world();
+            let pulse = 0.5 + 0.5 * (get_time() as f32 * 4.0).sin();
+            let mut color = theme.gold;
+            color.a = 0.5 + 0.5 * pulse;
+            draw_rectangle_lines(wx - 2.0, wy - 2.0, CELL_SIZE + 4.0, CELL_SIZE + 4.0, 3.0 + 2.0 * pulse, color);
+        }
+
+        let mut lines = wrap(&step.text, CHARS_PER_LINE);
+        let (number, count) = self.tutorial.progress();
+        let hint = match step.goal {
+            StepGoal::Acknowledge => format!("[{}] Continue", input.label(Action::PlaceTower)),
+            StepGoal::PlaceTower { tower_type, .. } => {
+                let wanted = BuildTool::Tower(tower_type);
+                match Action::BUILD_SLOTS.iter().zip(BuildTool::SLOTS).find(|(_, tool)| *tool == wanted) {
+                    Some((slot, _)) if game.build_tool != wanted => {
+                        format!("[{}] {}", input.label(*slot), wanted.name())
+                    }
+                    _ => format!("[{}] Build", input.label(Action::PlaceTower)),
+                }
+            }
+            StepGoal::MiniWave { .. } => String::new(),
+        };
+        lines.push(String::new());
+        lines.push(format!("Tutorial {}/{}   {}", number, count, hint));
+
+        set_camera(&hud.camera());
+        let height = LINE_HEIGHT * lines.len() as f32 + 16.0;
+        let (x, y) = ((hud.width - PANEL_WIDTH) / 2.0, hud.height - height - 90.0);
+        draw_rectangle(x, y, PANEL_WIDTH, height, Color::new(0.0, 0.0, 0.0, 0.8));
+        draw_rectangle_lines(x, y, PANEL_WIDTH, height, 2.0, theme.gold);
+        for (row, line) in lines.iter().enumerate() {
+            draw_text(line, x + 12.0, y + LINE_HEIGHT * (row as f32 + 1.0), 20.0, theme.text);
+        }
+        set_default_camera();
+    }
+}
+
+/// Greedy word wrap to at most `width` characters a line
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.len() + 1 + word.len() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_breaks_between_words() {
+        assert_eq!(wrap("place a basic tower", 11), vec!["place a", "basic tower"]);
+        assert_eq!(wrap("unbreakable", 4), vec!["unbreakable"]);
+        assert!(wrap("", 10).is_empty());
+    }
+}
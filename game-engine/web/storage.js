@@ -0,0 +1,50 @@
+// miniquad plugin backing src/storage.rs with window.localStorage.
+// Load after mq_js_bundle.js and before load("rust-rush-engine.wasm").
+(function () {
+    const encoder = new TextEncoder();
+    const decoder = new TextDecoder();
+
+    function readString(ptr, len) {
+        return decoder.decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+    }
+
+    function getItem(key) {
+        try {
+            return window.localStorage.getItem(key);
+        } catch (e) {
+            return null; // Storage disabled (private mode, sandboxed iframe)
+        }
+    }
+
+    miniquad_add_plugin({
+        name: "rust_rush_storage",
+        version: "0.1.0",
+        register_plugin: function (importObject) {
+            importObject.env.rust_rush_storage_len = function (key_ptr, key_len) {
+                const value = getItem(readString(key_ptr, key_len));
+                return value === null ? -1 : encoder.encode(value).length;
+            };
+            importObject.env.rust_rush_storage_read = function (key_ptr, key_len, out_ptr, out_len) {
+                const value = getItem(readString(key_ptr, key_len)) || "";
+                const bytes = encoder.encode(value).subarray(0, out_len);
+                new Uint8Array(wasm_memory.buffer, out_ptr, out_len).set(bytes);
+            };
+            importObject.env.rust_rush_storage_write = function (key_ptr, key_len, value_ptr, value_len) {
+                try {
+                    window.localStorage.setItem(readString(key_ptr, key_len), readString(value_ptr, value_len));
+                    return 1;
+                } catch (e) {
+                    return 0;
+                }
+            };
+            importObject.env.rust_rush_storage_remove = function (key_ptr, key_len) {
+                try {
+                    window.localStorage.removeItem(readString(key_ptr, key_len));
+                    return 1;
+                } catch (e) {
+                    return 0;
+                }
+            };
+        },
+    });
+})();